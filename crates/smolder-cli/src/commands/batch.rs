@@ -0,0 +1,770 @@
+//! Send a sequence of contract calls from a plan file
+//!
+//! A plan lists calls on tracked deployments, in order. They are all resolved
+//! and encoded before anything is sent, then sent one at a time from one
+//! wallet, each waiting for its receipt so a failure can stop the rest. The
+//! run is recorded as a batch linking the call history of every step.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use alloy::json_abi::{Function, StateMutability};
+use alloy::primitives::{Bytes, U256};
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+use serde::{Deserialize, Serialize};
+use smolder_core::{decrypt_private_key, encode_function_call, parse_value, Abi};
+use smolder_db::{
+    Batch, BatchRepository, CallHistoryRepository, CallHistoryUpdate, CallHistoryView, CallType,
+    Database, DeploymentRepository, DeploymentView, Network, NetworkRepository, NewBatch,
+    NewCallHistory, TransactionStatus, WalletRepository, WalletWithKey,
+};
+
+use crate::config::SmolderConfig;
+use crate::ens;
+use crate::interact::param_to_json;
+use crate::locks::Clearance;
+use crate::output::{self, status};
+use crate::policy::{self, Intent};
+use crate::rpc::{self, Outgoing};
+
+/// How long each step waits for its transaction to be mined
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Send a sequence of contract calls from a JSON or CSV plan file
+///
+/// A JSON plan is an array of steps (or an object with a `steps` array), each
+/// `{"contract": "Token", "function": "mint", "args": [...], "value": "0.1 ether"}`.
+/// A CSV plan has a header row with `contract`, `function` and optionally
+/// `value` columns; every other column is an argument, in order.
+#[derive(Args)]
+pub struct BatchCommand {
+    /// Plan file (.json or .csv)
+    #[arg(required_unless_present = "show")]
+    pub plan: Option<PathBuf>,
+
+    /// Network the plan's contracts are deployed on
+    #[arg(long, required_unless_present = "show")]
+    pub network: Option<String>,
+
+    /// Wallet to send every step from
+    #[arg(long, required_unless_present = "show")]
+    pub wallet: Option<String>,
+
+    /// Simulate each step against the latest block instead of sending it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Keep going after a step fails or reverts, instead of stopping
+    #[arg(long)]
+    pub continue_on_error: bool,
+
+    /// Confirm a batch on a locked network by repeating its name
+    #[arg(long, value_name = "NETWORK")]
+    pub unlock_confirm: Option<String>,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+
+    /// Show an earlier batch run and the calls it sent
+    #[arg(long, value_name = "ID", conflicts_with_all = ["plan", "network", "wallet", "dry_run", "continue_on_error", "unlock_confirm"])]
+    pub show: Option<i64>,
+}
+
+/// One call of a plan
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Step {
+    contract: String,
+    /// Function name, or its full signature to pick an overload
+    function: String,
+    #[serde(default)]
+    args: Vec<serde_json::Value>,
+    /// Wei, or an amount with a unit ("0.1 ether")
+    value: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PlanFile {
+    Steps(Vec<Step>),
+    Plan { steps: Vec<Step> },
+}
+
+/// A step resolved against the registry and encoded
+struct Prepared {
+    deployment: DeploymentView,
+    function: Function,
+    params: Vec<serde_json::Value>,
+    data: Bytes,
+    value: Option<U256>,
+}
+
+/// Outcome of a step, printed with `--json` on dry runs
+#[derive(Serialize)]
+struct StepOutcome {
+    step: usize,
+    contract: String,
+    function: String,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_used: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Result of a batch run, printed with `--json`
+#[derive(Serialize)]
+struct BatchReport {
+    #[serde(flatten)]
+    batch: Batch,
+    calls: Vec<CallHistoryView>,
+}
+
+impl BatchCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        if let Some(id) = self.show {
+            return show(&db, id).await;
+        }
+
+        let plan = self.plan.unwrap_or_default();
+        let network_name = self.network.unwrap_or_default();
+        let wallet_name = self.wallet.unwrap_or_default();
+
+        let steps = load_plan(&plan)?;
+        if steps.is_empty() {
+            return Err(eyre!("{} has no steps", plan.display()));
+        }
+        let network = NetworkRepository::get_by_name(&db, &network_name)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", network_name))?;
+        let wallet = WalletRepository::get_with_key(&db, &wallet_name)
+            .await?
+            .ok_or_else(|| eyre!("Wallet '{}' not found", wallet_name))?;
+
+        // Everything is resolved up front, so a typo in step 5 doesn't leave
+        // steps 1 to 4 sent
+        let ens_network = SmolderConfig::load()?.ens.network;
+        let mut deployments = HashMap::new();
+        let mut prepared = Vec::with_capacity(steps.len());
+        for (i, step) in steps.iter().enumerate() {
+            let step = prepare(
+                &db,
+                &network,
+                ens_network.as_deref(),
+                &mut deployments,
+                step,
+            )
+            .await
+            .map_err(|e| eyre!("Step {}: {}", i + 1, e))?;
+            prepared.push(step);
+        }
+
+        if self.dry_run {
+            return dry_run(&network, &wallet, &prepared).await;
+        }
+
+        if !self.force {
+            status!(
+                "{} About to send {} transaction(s) on {} from {} ({})",
+                style("!").yellow(),
+                prepared.len(),
+                style(&network.name).cyan(),
+                wallet.name,
+                wallet.address
+            );
+            for (i, step) in prepared.iter().enumerate() {
+                status!(
+                    "   {:>3}. {}.{}",
+                    i + 1,
+                    step.deployment.contract_name,
+                    step.function.signature()
+                );
+            }
+            status!();
+            let confirmed = Confirm::new()
+                .with_prompt("Send these transactions?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        Clearance::cli(self.unlock_confirm)
+            .check(
+                &db,
+                &network,
+                &format!("batch {} ({} steps)", plan.display(), prepared.len()),
+            )
+            .await?;
+        for (i, step) in prepared.iter().enumerate() {
+            let signature = step.function.signature();
+            let intent = Intent::call(
+                &network,
+                *step.deployment.address,
+                Some(&step.deployment.contract_name),
+                &signature,
+                step.value,
+            );
+            policy::check(&db, wallet.id, &wallet.name, &intent)
+                .await
+                .map_err(|e| eyre!("Step {}: {}", i + 1, e))?;
+        }
+        let batch = BatchRepository::create(
+            &db,
+            &NewBatch {
+                plan: plan.display().to_string(),
+                network_id: network.id,
+                wallet_id: Some(wallet.id),
+                steps: prepared.len() as i64,
+            },
+        )
+        .await?;
+        let private_key = decrypt_private_key(&wallet.encrypted_key)?;
+
+        let mut failed = 0;
+        for (i, step) in prepared.iter().enumerate() {
+            status!(
+                "{} [{}/{}] {}.{}",
+                style("→").blue(),
+                i + 1,
+                prepared.len(),
+                style(&step.deployment.contract_name).cyan(),
+                step.function.signature()
+            );
+            let status = send(&db, &network, &wallet, &private_key, batch.id, i, step).await?;
+            if status != TransactionStatus::Success {
+                failed += 1;
+                if !self.continue_on_error {
+                    break;
+                }
+            }
+        }
+
+        let status = match failed {
+            0 => TransactionStatus::Success,
+            _ => TransactionStatus::Failed,
+        };
+        BatchRepository::finish(&db, batch.id, status).await?;
+        let calls = BatchRepository::list_calls(&db, batch.id).await?;
+        let sent = calls.len();
+
+        if output::is_json() {
+            let batch = BatchRepository::get(&db, batch.id)
+                .await?
+                .ok_or_else(|| eyre!("Batch {} not found", batch.id))?;
+            output::print_json(&BatchReport { batch, calls })?;
+        } else {
+            print_calls(&calls);
+        }
+
+        if failed > 0 {
+            let skipped = prepared.len() - sent;
+            return Err(eyre!(
+                "Batch {}: {} step(s) failed{}",
+                batch.id,
+                failed,
+                match skipped {
+                    0 => String::new(),
+                    n => format!(", {} not sent", n),
+                }
+            ));
+        }
+        status!();
+        status!(
+            "{} Batch {} sent {} transaction(s)",
+            style("✓").green().bold(),
+            batch.id,
+            sent
+        );
+        Ok(())
+    }
+}
+
+/// Read a plan, as CSV when the file name ends in `.csv` and as JSON otherwise
+fn load_plan(path: &Path) -> Result<Vec<Step>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    match is_csv {
+        true => parse_csv_plan(&content),
+        false => match serde_json::from_str(&content) {
+            Ok(PlanFile::Steps(steps) | PlanFile::Plan { steps }) => Ok(steps),
+            Err(e) => Err(eyre!("{} is not a valid plan: {}", path.display(), e)),
+        },
+    }
+}
+
+fn parse_csv_plan(content: &str) -> Result<Vec<Step>> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header = csv_record(lines.next().unwrap_or_default());
+    let column = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (Some(contract), Some(function)) = (column("contract"), column("function")) else {
+        return Err(eyre!(
+            "The CSV header needs 'contract' and 'function' columns"
+        ));
+    };
+    let value = column("value");
+
+    lines
+        .enumerate()
+        .map(|(i, line)| {
+            let record = csv_record(line);
+            let cell = |index: usize| record.get(index).map(String::as_str).unwrap_or_default();
+            if cell(contract).is_empty() || cell(function).is_empty() {
+                return Err(eyre!("Row {} has no contract or function", i + 2));
+            }
+            let mut args: Vec<serde_json::Value> = record
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| {
+                    ![Some(contract), Some(function), value].contains(&Some(*index))
+                })
+                .map(|(_, arg)| serde_json::Value::String(arg.clone()))
+                .collect();
+            // Rows of shorter calls leave the trailing argument columns empty
+            while args.last().is_some_and(|arg| arg == "") {
+                args.pop();
+            }
+            Ok(Step {
+                contract: cell(contract).to_string(),
+                function: cell(function).to_string(),
+                args,
+                value: value
+                    .map(cell)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Split a CSV line into fields; quoted fields may hold commas and `""`
+fn csv_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// Resolve a step's deployment and function, and encode its call
+async fn prepare(
+    db: &Database,
+    network: &Network,
+    ens_network: Option<&str>,
+    deployments: &mut HashMap<String, DeploymentView>,
+    step: &Step,
+) -> Result<Prepared> {
+    if !deployments.contains_key(&step.contract) {
+        let deployment = DeploymentRepository::get_current(db, &step.contract, &network.name)
+            .await?
+            .ok_or_else(|| {
+                eyre!(
+                    "No deployment found for contract '{}' on network '{}'",
+                    step.contract,
+                    network.name
+                )
+            })?;
+        let view = DeploymentRepository::get_view_by_id(db, deployment.id)
+            .await?
+            .ok_or_else(|| eyre!("Deployment {} not found", deployment.id))?;
+        deployments.insert(step.contract.clone(), view);
+    }
+    let deployment = deployments[&step.contract].clone();
+
+    let abi = Abi::parse(&deployment.abi)?;
+    let function = find_function(&abi, &step.function).ok_or_else(|| {
+        eyre!(
+            "{} has no function '{}' (overloaded functions need their full signature)",
+            step.contract,
+            step.function
+        )
+    })?;
+    if step.args.len() != function.inputs.len() {
+        return Err(eyre!(
+            "{} takes {} arguments, got {}",
+            function.signature(),
+            function.inputs.len(),
+            step.args.len()
+        ));
+    }
+    let mut params: Vec<serde_json::Value> = function
+        .inputs
+        .iter()
+        .zip(&step.args)
+        .map(|(input, arg)| match arg {
+            serde_json::Value::String(s) => param_to_json(&input.selector_type(), s),
+            other => other.clone(),
+        })
+        .collect();
+    ens::resolve_params(db, network, ens_network, &function.inputs, &mut params).await?;
+    let data = encode_function_call(&function, &params)?;
+
+    let value = step
+        .value
+        .as_deref()
+        .map(|v| parse_value(v, network.currency()))
+        .transpose()?
+        .filter(|v| !v.is_zero());
+    if value.is_some() && function.state_mutability != StateMutability::Payable {
+        return Err(eyre!("{} is not payable", function.signature()));
+    }
+
+    Ok(Prepared {
+        deployment,
+        function,
+        params,
+        data,
+        value,
+    })
+}
+
+/// The function named `name`, or with signature `name`; a bare name must not
+/// be overloaded
+fn find_function(abi: &Abi, name: &str) -> Option<Function> {
+    let base = name.split('(').next().unwrap_or(name).trim();
+    let overloads = abi.function_overloads(base)?;
+    match name.contains('(') {
+        true => overloads
+            .iter()
+            .find(|f| f.signature() == name.replace(' ', ""))
+            .cloned(),
+        false => match overloads.as_slice() {
+            [function] => Some(function.clone()),
+            _ => None,
+        },
+    }
+}
+
+/// Simulate every step against the latest block, without recording anything
+///
+/// Steps run independently, so one that depends on an earlier step's effects
+/// may fail here and still succeed when the batch is sent.
+async fn dry_run(network: &Network, wallet: &WalletWithKey, prepared: &[Prepared]) -> Result<()> {
+    let mut outcomes = Vec::with_capacity(prepared.len());
+    for (i, step) in prepared.iter().enumerate() {
+        let simulation = rpc::simulate(
+            network.rpc_url.expose(),
+            *wallet.address,
+            Some(*step.deployment.address),
+            step.data.clone(),
+            step.value,
+            None,
+        )
+        .await;
+        let (success, gas_used, error) = match simulation {
+            Ok(sim) => (sim.success, sim.gas_used, sim.error),
+            Err(e) => (false, None, Some(e.to_string())),
+        };
+        outcomes.push(StepOutcome {
+            step: i + 1,
+            contract: step.deployment.contract_name.clone(),
+            function: step.function.signature(),
+            success,
+            gas_used,
+            error,
+        });
+    }
+
+    if output::is_json() {
+        return output::print_json(&outcomes);
+    }
+
+    println!(
+        "{:>4} {:<20} {:<36} {:<8} Gas / Error",
+        "Step", "Contract", "Function", "Result"
+    );
+    println!("{}", "-".repeat(90));
+    for outcome in &outcomes {
+        let (result, detail) = match outcome.success {
+            true => (
+                style("ok").green(),
+                outcome.gas_used.map(|g| g.to_string()).unwrap_or_default(),
+            ),
+            false => (
+                style("fails").red(),
+                outcome.error.clone().unwrap_or_default(),
+            ),
+        };
+        println!(
+            "{:>4} {:<20} {:<36} {:<8} {}",
+            outcome.step, outcome.contract, outcome.function, result, detail
+        );
+    }
+    status!();
+    status!("Dry run: nothing was sent. Steps were simulated independently of each other.");
+    Ok(())
+}
+
+/// Send one step and wait for its receipt, recording it in the call history
+/// and linking it to the batch
+async fn send(
+    db: &Database,
+    network: &Network,
+    wallet: &WalletWithKey,
+    private_key: &str,
+    batch_id: i64,
+    position: usize,
+    step: &Prepared,
+) -> Result<TransactionStatus> {
+    let history = CallHistoryRepository::create(
+        db,
+        &NewCallHistory {
+            deployment_id: step.deployment.id,
+            wallet_id: Some(wallet.id),
+            function_name: step.function.name.clone(),
+            function_signature: step.function.signature(),
+            input_params: serde_json::to_string(&step.params)?,
+            call_type: CallType::Write,
+        },
+    )
+    .await?;
+    BatchRepository::add_call(db, batch_id, position as i64, history.id).await?;
+
+    let address = *step.deployment.address;
+    let signature = step.function.signature();
+    let intent = Intent::call(
+        network,
+        address,
+        Some(&step.deployment.contract_name),
+        &signature,
+        step.value,
+    );
+    let sent = async {
+        // Checked again per step, as earlier steps count towards the daily limit
+        let reservation = policy::reserve(db, wallet.id, &wallet.name, &intent).await?;
+        let sent = async {
+            rpc::preflight(
+                network,
+                Outgoing {
+                    from: *wallet.address,
+                    to: Some(address),
+                    data: &step.data,
+                    value: step.value,
+                },
+            )
+            .await?;
+            rpc::execute_transaction(
+                network.rpc_url.expose(),
+                network.private_relay.as_deref(),
+                private_key,
+                address,
+                step.data.clone(),
+                step.value,
+            )
+            .await
+        }
+        .await;
+        reservation.settle(db, sent).await
+    }
+    .await;
+    let tx_hash = match sent {
+        Ok(tx_hash) => tx_hash,
+        Err(e) => {
+            let update = CallHistoryUpdate {
+                result: None,
+                tx_hash: None,
+                block_number: None,
+                gas_used: None,
+                gas_price: None,
+                status: TransactionStatus::Failed,
+                error_message: Some(e.to_string()),
+            };
+            CallHistoryRepository::update(db, history.id, &update).await?;
+            status!("  {} {}", style("✗").red(), e);
+            return Ok(TransactionStatus::Failed);
+        }
+    };
+
+    let update =
+        match rpc::wait_for_receipt(network.rpc_url.expose(), &tx_hash, RECEIPT_TIMEOUT).await {
+            Ok(receipt) => CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(tx_hash),
+                block_number: receipt.block_number.map(|b| b as i64),
+                gas_used: Some(receipt.gas_used as i64),
+                gas_price: Some(receipt.effective_gas_price.to_string()),
+                status: match receipt.status() {
+                    true => TransactionStatus::Success,
+                    false => TransactionStatus::Reverted,
+                },
+                error_message: None,
+            },
+            // Still pending: the next step would queue behind it, so count it as
+            // failed without claiming it won't be mined
+            Err(e) => CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(tx_hash),
+                block_number: None,
+                gas_used: None,
+                gas_price: None,
+                status: TransactionStatus::Pending,
+                error_message: Some(e.to_string()),
+            },
+        };
+    CallHistoryRepository::update(db, history.id, &update).await?;
+
+    match update.status {
+        TransactionStatus::Success => status!("  {} {}", style("✓").green(), tx_hash),
+        TransactionStatus::Reverted => status!("  {} {} reverted", style("✗").red(), tx_hash),
+        _ => status!(
+            "  {} {} not mined within {}s",
+            style("!").yellow(),
+            tx_hash,
+            RECEIPT_TIMEOUT.as_secs()
+        ),
+    }
+    Ok(update.status)
+}
+
+async fn show(db: &Database, id: i64) -> Result<()> {
+    let batch = BatchRepository::get(db, id)
+        .await?
+        .ok_or_else(|| eyre!("Batch {} not found", id))?;
+    let calls = BatchRepository::list_calls(db, id).await?;
+
+    if output::is_json() {
+        return output::print_json(&BatchReport { batch, calls });
+    }
+
+    println!(
+        "Batch {} of {} on {} from {}: {} ({} of {} step(s) sent)",
+        style(batch.id).cyan(),
+        batch.plan,
+        style(&batch.network_name).cyan(),
+        batch.wallet_name.as_deref().unwrap_or("-"),
+        batch.status,
+        calls.len(),
+        batch.steps
+    );
+    println!(
+        "Started {}{}",
+        batch.created_at,
+        batch
+            .finished_at
+            .as_deref()
+            .map(|at| format!(", finished {}", at))
+            .unwrap_or_default()
+    );
+    print_calls(&calls);
+    Ok(())
+}
+
+fn print_calls(calls: &[CallHistoryView]) {
+    println!();
+    println!(
+        "{:>4} {:<20} {:<36} {:<9} Transaction / Error",
+        "Step", "Contract", "Function", "Status"
+    );
+    println!("{}", "-".repeat(100));
+    for (i, call) in calls.iter().enumerate() {
+        let status = match call.status {
+            Some(TransactionStatus::Success) => style("success".to_string()).green(),
+            Some(TransactionStatus::Pending) | None => style("pending".to_string()).yellow(),
+            Some(status) => style(status.to_string()).red(),
+        };
+        let detail = match (&call.tx_hash, &call.error_message) {
+            (Some(tx_hash), _) => {
+                output::hyperlink(&tx_hash.to_string(), call.links.tx_url.as_deref())
+            }
+            (None, Some(error)) => error.clone(),
+            (None, None) => String::new(),
+        };
+        println!(
+            "{:>4} {:<20} {:<36} {:<9} {}",
+            i + 1,
+            call.contract_name,
+            call.function_signature,
+            status,
+            detail
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_plan() {
+        let steps = r#"[{"contract": "Token", "function": "mint", "args": ["0x1", 5]}]"#;
+        let PlanFile::Steps(steps) = serde_json::from_str(steps).unwrap() else {
+            panic!("expected a list of steps");
+        };
+        assert_eq!(
+            steps[0].args,
+            vec![serde_json::json!("0x1"), serde_json::json!(5)]
+        );
+
+        let plan = r#"{"steps": [{"contract": "Vault", "function": "pause()"}]}"#;
+        let PlanFile::Plan { steps } = serde_json::from_str(plan).unwrap() else {
+            panic!("expected a plan object");
+        };
+        assert!(steps[0].args.is_empty());
+
+        assert!(serde_json::from_str::<PlanFile>(r#"[{"contract": "T", "fn": "x"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_plan() {
+        let csv = "contract,function,value,arg1,arg2\n\
+                   Token,\"transfer(address,uint256)\",,0xabc,100\n\
+                   \n\
+                   Vault,deposit,1 ether,,\n\
+                   Token,setName,,\"Foo, \"\"the\"\" token\",\n";
+        let steps = parse_csv_plan(csv).unwrap();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].function, "transfer(address,uint256)");
+        assert_eq!(
+            steps[0].args,
+            vec![serde_json::json!("0xabc"), serde_json::json!("100")]
+        );
+        assert_eq!(steps[1].value.as_deref(), Some("1 ether"));
+        assert!(steps[1].args.is_empty());
+        assert_eq!(steps[2].args, vec![serde_json::json!("Foo, \"the\" token")]);
+
+        assert!(parse_csv_plan("contract,args\nToken,1\n").is_err());
+    }
+
+    #[test]
+    fn test_find_function() {
+        let abi = Abi::from_human_readable([
+            "function pause()",
+            "function mint(address,uint256)",
+            "function mint(address)",
+        ])
+        .unwrap();
+        assert!(find_function(&abi, "pause").is_some());
+        assert!(find_function(&abi, "mint").is_none());
+        assert_eq!(
+            find_function(&abi, "mint(address, uint256)")
+                .unwrap()
+                .inputs
+                .len(),
+            2
+        );
+        assert!(find_function(&abi, "burn").is_none());
+    }
+}
@@ -0,0 +1,23 @@
+//! Generate shell completion scripts
+
+use clap::{Args, CommandFactory};
+use clap_complete::Shell;
+use color_eyre::eyre::Result;
+
+use crate::Cli;
+
+/// Generate a shell completion script
+#[derive(Args)]
+pub struct CompletionsCommand {
+    /// Shell to generate completions for
+    pub shell: Shell,
+}
+
+impl CompletionsCommand {
+    pub async fn run(self) -> Result<()> {
+        let mut cmd = Cli::command();
+        let name = cmd.get_name().to_string();
+        clap_complete::generate(self.shell, &mut cmd, name, &mut std::io::stdout());
+        Ok(())
+    }
+}
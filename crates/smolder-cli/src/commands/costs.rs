@@ -0,0 +1,156 @@
+//! Report the fees paid per network, contract and wallet
+
+use std::collections::BTreeMap;
+
+use alloy::primitives::U256;
+use clap::Args;
+use color_eyre::eyre::Result;
+use smolder_core::{format_value, Currency};
+use smolder_db::{AnalyticsRepository, CostFilter, CostStat, Database, GasInterval};
+
+use super::gas_report::parse_interval;
+use crate::config::SmolderConfig;
+use crate::output::{self, status};
+use crate::prices::{self, PriceOracle};
+
+/// Report the fees paid per network, contract and wallet
+#[derive(Args)]
+pub struct CostsCommand {
+    /// Only report on this network
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// Only report on this contract
+    #[arg(long)]
+    pub contract: Option<String>,
+
+    /// Only report on this wallet, or deployer address
+    #[arg(long)]
+    pub wallet: Option<String>,
+
+    /// Only count fees paid at or after this date (YYYY-MM-DD[ HH:MM:SS])
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Only count fees paid at or before this date (YYYY-MM-DD[ HH:MM:SS])
+    #[arg(long)]
+    pub until: Option<String>,
+
+    /// Split costs by day, week or month
+    #[arg(long, value_parser = parse_interval)]
+    pub interval: Option<GasInterval>,
+}
+
+impl CostsCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let filter = CostFilter {
+            network: self.network,
+            contract: self.contract,
+            wallet: self.wallet,
+            since: self.since,
+            until: self.until,
+            interval: self.interval,
+        };
+        if let Some(config) = SmolderConfig::load()?.prices {
+            let oracle = PriceOracle::from_config(&config)?;
+            let unpriced = AnalyticsRepository::unpriced_fees(&db, &filter).await?;
+            prices::fill(&db, &oracle, &unpriced).await?;
+        }
+        let stats = AnalyticsRepository::costs(&db, &filter).await?;
+
+        if output::is_json() {
+            return output::print_json(&stats);
+        }
+
+        if stats.is_empty() {
+            status!("No fees recorded yet.");
+            status!(
+                "   Fees are recorded for deployments and for sends once their receipt arrives."
+            );
+            return Ok(());
+        }
+
+        let period_header = match self.interval {
+            Some(_) => format!("{:<11} ", "Period"),
+            None => String::new(),
+        };
+        println!(
+            "{}{:<15} {:<20} {:<20} {:>7} {:>7} {:>12} {:>24} {:>10}",
+            period_header, "Network", "Contract", "Wallet", "Deploys", "Calls", "Gas", "Fee", "USD"
+        );
+        println!("{}", "-".repeat(122 + period_header.len()));
+
+        for stat in &stats {
+            let period = match self.interval {
+                Some(_) => format!("{:<11} ", stat.period.as_deref().unwrap_or("-")),
+                None => String::new(),
+            };
+            println!(
+                "{}{:<15} {:<20} {:<20} {:>7} {:>7} {:>12} {:>24} {:>10}",
+                period,
+                stat.network,
+                stat.contract,
+                stat.wallet.as_deref().map(short_wallet).unwrap_or("-"),
+                stat.deployments,
+                stat.calls,
+                stat.gas_used,
+                format_value(fee(stat), currency(stat)),
+                stat.fee_usd
+                    .map(format_usd)
+                    .unwrap_or_else(|| "-".to_string())
+            );
+        }
+
+        status!();
+        for ((symbol, decimals), total) in totals(&stats) {
+            status!(
+                "Total: {}",
+                format_value(total, Currency { symbol, decimals })
+            );
+        }
+        match stats.iter().map(|s| s.fee_usd).sum::<Option<f64>>() {
+            Some(usd) => status!("Total: {} (approximate)", format_usd(usd)),
+            None if stats.iter().any(|s| s.fee_usd.is_some()) => {
+                status!("Some fees have no USD price yet; configure [prices] in smolder.toml")
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn fee(stat: &CostStat) -> U256 {
+    stat.fee.parse().unwrap_or_default()
+}
+
+fn currency(stat: &CostStat) -> Currency<'_> {
+    Currency {
+        symbol: &stat.currency_symbol,
+        decimals: stat.currency_decimals,
+    }
+}
+
+/// Total fees per native currency
+fn totals(stats: &[CostStat]) -> BTreeMap<(&str, u8), U256> {
+    let mut totals = BTreeMap::new();
+    for stat in stats {
+        *totals
+            .entry((stat.currency_symbol.as_str(), stat.currency_decimals))
+            .or_insert(U256::ZERO) += fee(stat);
+    }
+    totals
+}
+
+fn format_usd(usd: f64) -> String {
+    format!("${:.2}", usd)
+}
+
+/// Deployer addresses shortened to fit the wallet column
+fn short_wallet(wallet: &str) -> &str {
+    match wallet.len() > 20 {
+        true => &wallet[..20],
+        false => wallet,
+    }
+}
@@ -0,0 +1,457 @@
+//! Back up, restore, prune and encrypt the registry database
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::{Confirm, Password};
+use serde::Serialize;
+use smolder_core::SmolderDir;
+use smolder_db::{
+    CallHistoryRepository, Database, DatabaseOptions, HistoryRetention, DB_FILENAME, DB_KEY_ENV,
+};
+
+use crate::config::parse_age;
+use crate::output::{self, status};
+
+/// Directory under `.smolder/` where backups are written by default
+const BACKUPS_DIR: &str = "backups";
+
+/// Prefix of backups taken automatically before destructive operations
+const AUTO_BACKUP_PREFIX: &str = "auto-";
+
+/// Number of automatic backups kept; older ones are deleted
+const AUTO_BACKUPS_KEPT: usize = 10;
+
+/// Back up, restore, prune and encrypt the registry database
+#[derive(Args)]
+pub struct DbCommand {
+    #[command(subcommand)]
+    pub command: DbSubcommand,
+}
+
+impl DbCommand {
+    pub async fn run(self) -> Result<()> {
+        self.command.run().await
+    }
+}
+
+#[derive(Subcommand)]
+pub enum DbSubcommand {
+    /// Write a consistent snapshot of the database, safe while `serve` is running
+    Backup(BackupCommand),
+
+    /// Replace the database with a snapshot
+    Restore(RestoreCommand),
+
+    /// Delete old call history and compact the database
+    Prune(PruneCommand),
+
+    /// Encrypt a plaintext database with a passphrase (needs the
+    /// encrypted-db feature)
+    Encrypt(EncryptCommand),
+}
+
+impl DbSubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Backup(cmd) => cmd.run().await,
+            Self::Restore(cmd) => cmd.run().await,
+            Self::Prune(cmd) => cmd.run().await,
+            Self::Encrypt(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Result of a backup or restore, printed with `--json`
+#[derive(Serialize)]
+struct BackupReport {
+    path: String,
+    /// Snapshot of the previous contents, taken before a restore
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous: Option<String>,
+}
+
+/// Write a consistent snapshot of the database, safe while `serve` is running
+#[derive(Args)]
+pub struct BackupCommand {
+    /// Output file (defaults to .smolder/backups/smolder-<timestamp>.db)
+    #[arg(long, short)]
+    pub output: Option<String>,
+}
+
+impl BackupCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+
+        let path = match self.output {
+            Some(path) => PathBuf::from(path),
+            None => backups_dir()?.join(format!("smolder-{}.db", timestamp(&db).await?)),
+        };
+        if path.exists() {
+            return Err(eyre!("{} already exists", path.display()));
+        }
+
+        db.backup_to(&path).await?;
+
+        if output::is_json() {
+            return output::print_json(&BackupReport {
+                path: path.display().to_string(),
+                previous: None,
+            });
+        }
+
+        status!(
+            "{} Backed up to {}",
+            style("*").green().bold(),
+            style(path.display()).cyan()
+        );
+
+        Ok(())
+    }
+}
+
+/// Replace the database with a snapshot
+#[derive(Args)]
+pub struct RestoreCommand {
+    /// Snapshot file to restore
+    pub file: String,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RestoreCommand {
+    pub async fn run(self) -> Result<()> {
+        let source = Path::new(&self.file);
+        if !source.is_file() {
+            return Err(eyre!("Backup file '{}' not found", self.file));
+        }
+
+        let db = Database::connect().await?;
+
+        if !self.force {
+            status!(
+                "{} About to replace the registry with {}",
+                style("!").yellow(),
+                style(&self.file).cyan()
+            );
+            status!("   The current contents are backed up first.");
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt("Are you sure you want to restore this backup?")
+                .default(false)
+                .interact()?;
+
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        let previous = auto_backup(&db, "restore").await?;
+        db.restore_from(source).await?;
+
+        if output::is_json() {
+            return output::print_json(&BackupReport {
+                path: self.file,
+                previous: Some(previous.display().to_string()),
+            });
+        }
+
+        status!(
+            "{} Restored {}",
+            style("*").green().bold(),
+            style(&self.file).cyan()
+        );
+
+        Ok(())
+    }
+}
+
+/// Result of a prune, printed with `--json`
+#[derive(Serialize)]
+struct PruneReport {
+    /// Calls deleted, or that would be with `--dry-run`
+    calls: u64,
+    dry_run: bool,
+}
+
+/// Delete old call history and compact the database
+///
+/// Pending calls are always kept. The database is backed up first and
+/// VACUUMed afterwards so the file actually shrinks.
+#[derive(Args)]
+pub struct PruneCommand {
+    /// Delete calls older than this age, such as 90d, 12h or 2w
+    #[arg(long, value_parser = parse_age)]
+    pub older_than: Duration,
+
+    /// Keep failed and reverted calls whatever their age
+    #[arg(long)]
+    pub keep_failed: bool,
+
+    /// Report what would be deleted without deleting anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl PruneCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let retention = HistoryRetention {
+            max_age: self.older_than,
+            keep_failed: self.keep_failed,
+        };
+
+        let mut calls = db.count_prunable(&retention).await?;
+        if !self.dry_run && calls > 0 {
+            auto_backup(&db, "prune").await?;
+            calls = CallHistoryRepository::prune(&db, &retention).await?;
+            db.vacuum().await?;
+        }
+
+        if output::is_json() {
+            return output::print_json(&PruneReport {
+                calls,
+                dry_run: self.dry_run,
+            });
+        }
+
+        match (calls, self.dry_run) {
+            (0, _) => status!("{} No calls to prune", style("*").dim()),
+            (n, true) => status!(
+                "{} Would delete {} call(s) from history",
+                style("*").yellow(),
+                n
+            ),
+            (n, false) => status!(
+                "{} Deleted {} call(s) from history",
+                style("*").green().bold(),
+                n
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// Encrypt a plaintext database with a passphrase
+///
+/// The database is copied into a new SQLCipher file, which then replaces it.
+/// Stop `smolder serve` first: writes made during the copy would be lost.
+#[derive(Args)]
+pub struct EncryptCommand {
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl EncryptCommand {
+    pub async fn run(self) -> Result<()> {
+        if !Database::supports_encryption() {
+            return Err(eyre!(
+                "smolder was built without database encryption; reinstall it with {}",
+                style("--features encrypted-db").cyan()
+            ));
+        }
+        let path = SmolderDir::new().join(DB_FILENAME);
+        if !path.is_file() {
+            return Err(eyre!("No database at {}", path.display()));
+        }
+        if Database::is_encrypted(&path) {
+            return Err(eyre!("{} is already encrypted", path.display()));
+        }
+
+        let passphrase = match std::env::var(DB_KEY_ENV) {
+            Ok(key) if !key.is_empty() => key,
+            _ => Password::new()
+                .with_prompt("Passphrase for the database")
+                .with_confirmation("Repeat the passphrase", "Passphrases don't match")
+                .interact()?,
+        };
+        if passphrase.is_empty() {
+            return Err(eyre!("The passphrase can't be empty"));
+        }
+
+        if !self.force {
+            status!(
+                "{} About to encrypt {}",
+                style("!").yellow(),
+                style(path.display()).cyan()
+            );
+            status!("   Stop `smolder serve` first. Without the passphrase the registry is lost.");
+            status!();
+            let confirmed = Confirm::new()
+                .with_prompt("Encrypt the database?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        let encrypted = path.with_extension("db.encrypting");
+        if encrypted.exists() {
+            std::fs::remove_file(&encrypted)?;
+        }
+        let plain = DatabaseOptions {
+            key: None,
+            ..Default::default()
+        };
+        let db = Database::connect_to_with_options(&path, &plain).await?;
+        db.export_encrypted(&encrypted, &passphrase).await?;
+        db.close().await;
+
+        // Closing the last connection checkpoints the WAL into the file
+        // being replaced, so its -wal and -shm files are stale
+        for suffix in ["-wal", "-shm"] {
+            let mut file = path.clone().into_os_string();
+            file.push(suffix);
+            if Path::new(&file).exists() {
+                std::fs::remove_file(&file)?;
+            }
+        }
+        std::fs::rename(&encrypted, &path)?;
+
+        Database::unlock(passphrase);
+        Database::connect().await?.ping().await?;
+
+        if output::is_json() {
+            return output::print_json(&serde_json::json!({
+                "path": path.display().to_string(),
+                "encrypted": true,
+            }));
+        }
+        status!(
+            "{} Encrypted {}",
+            style("*").green().bold(),
+            style(path.display()).cyan()
+        );
+        status!(
+            "   Commands ask for the passphrase, or read it from {}",
+            style(DB_KEY_ENV).cyan()
+        );
+        status!(
+            "   Backups taken before now, in .smolder/{}/, are still plaintext",
+            BACKUPS_DIR
+        );
+        Ok(())
+    }
+}
+
+/// Ask for the passphrase of an encrypted database, unless it is in
+/// [`DB_KEY_ENV`], so commands can open it
+pub fn unlock() -> Result<()> {
+    let path = SmolderDir::new().join(DB_FILENAME);
+    let has_key = std::env::var(DB_KEY_ENV).is_ok_and(|key| !key.is_empty());
+    if has_key || !Database::supports_encryption() || !Database::is_encrypted(&path) {
+        return Ok(());
+    }
+    let passphrase = Password::new()
+        .with_prompt("Database passphrase")
+        .interact()
+        .map_err(|e| {
+            eyre!(
+                "The database is encrypted; set {} or run interactively ({})",
+                DB_KEY_ENV,
+                e
+            )
+        })?;
+    Database::unlock(passphrase);
+    Ok(())
+}
+
+/// Snapshot the database before a destructive operation
+///
+/// Backups land in `.smolder/backups/auto-<timestamp>-<reason>.db`; only the
+/// most recent [`AUTO_BACKUPS_KEPT`] are kept.
+pub async fn auto_backup(db: &Database, reason: &str) -> Result<PathBuf> {
+    let dir = backups_dir()?;
+    let path = dir.join(format!(
+        "{}{}-{}.db",
+        AUTO_BACKUP_PREFIX,
+        timestamp(db).await?,
+        reason
+    ));
+
+    db.backup_to(&path).await?;
+    status!(
+        "{} Backed up registry to {}",
+        style("*").dim(),
+        style(path.display()).dim()
+    );
+
+    prune_auto_backups(&dir)?;
+    Ok(path)
+}
+
+/// Delete all but the newest automatic backups
+fn prune_auto_backups(dir: &Path) -> Result<()> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(AUTO_BACKUP_PREFIX) && n.ends_with(".db"))
+        })
+        .collect();
+
+    // Timestamps sort lexicographically, newest last
+    backups.sort();
+    let excess = backups.len().saturating_sub(AUTO_BACKUPS_KEPT);
+    for path in &backups[..excess] {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+fn backups_dir() -> Result<PathBuf> {
+    let dir = SmolderDir::new().join(BACKUPS_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Current UTC time as a filename-safe, sortable string
+async fn timestamp(db: &Database) -> Result<String> {
+    let now: String = sqlx::query_scalar("SELECT strftime('%Y%m%dT%H%M%S', 'now')")
+        .fetch_one(db.read_pool())
+        .await?;
+    Ok(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_auto_backups() {
+        let dir = std::env::temp_dir().join(format!("smolder-prune-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for i in 0..AUTO_BACKUPS_KEPT + 2 {
+            std::fs::write(
+                dir.join(format!("auto-20240101T0000{:02}-import.db", i)),
+                "",
+            )
+            .unwrap();
+        }
+        std::fs::write(dir.join("smolder-20230101T000000.db"), "").unwrap();
+
+        prune_auto_backups(&dir).unwrap();
+
+        assert!(!dir.join("auto-20240101T000000-import.db").exists());
+        assert!(!dir.join("auto-20240101T000001-import.db").exists());
+        assert!(dir.join("auto-20240101T000002-import.db").exists());
+        // Manual backups are never pruned
+        assert!(dir.join("smolder-20230101T000000.db").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+//! Identify the function arbitrary calldata calls and decode its parameters
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use smolder_db::Database;
+
+use crate::decode;
+use crate::output::{self, status};
+
+/// Identify and decode calldata against the registry's ABIs
+#[derive(Args)]
+pub struct DecodeCommand {
+    /// Calldata as hex, selector included
+    pub calldata: String,
+
+    /// Ask 4byte.directory when no stored ABI has the selector
+    #[arg(long)]
+    pub online: bool,
+}
+
+impl DecodeCommand {
+    pub async fn run(self) -> Result<()> {
+        let calldata = hex::decode(self.calldata.trim().trim_start_matches("0x"))
+            .map_err(|e| eyre!("Invalid calldata: {}", e))?;
+        let db = Database::connect().await?;
+
+        let decoded = decode::decode(&db, &calldata, self.online).await?;
+
+        if output::is_json() {
+            return output::print_json(&decoded);
+        }
+
+        if decoded.matches.is_empty() {
+            status!(
+                "{} No known function has selector {}{}",
+                style("-").dim(),
+                decoded.selector,
+                if self.online { "" } else { " (try --online)" }
+            );
+            return Ok(());
+        }
+
+        for (i, found) in decoded.matches.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("{}", style(&found.signature).cyan().bold());
+            let origin = if found.contracts.is_empty() {
+                found.source.to_string()
+            } else {
+                found.contracts.join(", ")
+            };
+            status!("  {} {}", style("from:").dim(), origin);
+            if let Some(params) = found.params.as_object() {
+                for (name, value) in params {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    println!("  {} {}", style(format!("{}:", name)).bold(), value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,68 +1,184 @@
 //! Deploy contracts via forge script and track in database
 
+use std::collections::BTreeMap;
 use std::process::Command;
+use std::time::SystemTime;
 
+use alloy::hex;
+use alloy::primitives::{Address, Bytes};
 use clap::Args;
 use color_eyre::eyre::{eyre, Result};
 use console::style;
+use dialoguer::{Confirm, Input, Select};
+use serde::Serialize;
+use smolder_core::{
+    chains, decrypt_private_key, encode_constructor_args, format_gas_price, format_value,
+    parse_value, BytecodeHash, LayoutIssue, TxHash,
+};
 use smolder_db::{
     ChainId, ContractRepository, Database, DeploymentRepository, NetworkRepository, NewContract,
-    NewDeployment, NewNetwork,
+    NewDeployment, NewNetwork, WalletRepository,
 };
 
-use crate::config::FoundryConfig;
-use crate::forge::{BroadcastParser, ForgeBroadcastParser};
-use crate::rpc::get_chain_id;
+use crate::config::{FoundryConfig, Framework, SmolderConfig};
+use crate::deploy_plan::Plan;
+use crate::deployment_files;
+use crate::forge::{self, BroadcastParser, ForgeBroadcastParser};
+use crate::hooks::{self, HookEvent};
+use crate::interact::prompt_params;
+use crate::layout;
+use crate::libraries;
+use crate::locks::Clearance;
+use crate::notify::{self, DeploymentNotice};
+use crate::output::{self, status};
+use crate::policy::{self, Intent};
+use crate::rpc::{self, get_chain_id, Outgoing};
+use crate::tokens;
 
 /// Deploy contracts via forge script and track in database
 #[derive(Args)]
 pub struct DeployCommand {
     /// Path to the deployment script
-    pub script: String,
+    #[arg(required_unless_present = "interactive")]
+    pub script: Option<String>,
 
     /// Network to deploy to
-    #[arg(long)]
-    pub network: String,
+    #[arg(long, required_unless_present = "interactive")]
+    pub network: Option<String>,
 
     /// Actually broadcast the transaction (dry-run if omitted)
     #[arg(long)]
     pub broadcast: bool,
+
+    /// Dry-run the script and show which contracts it would create, which
+    /// registry entries would change and what it would cost
+    #[arg(long, conflicts_with = "broadcast")]
+    pub plan: bool,
+
+    /// Pick an artifact, network and wallet interactively and deploy it directly
+    #[arg(long, short, conflicts_with_all = ["script", "broadcast", "plan"])]
+    pub interactive: bool,
+
+    /// Also write the network's deployments to `deployments/<network>.json`
+    /// for versioning in git
+    #[arg(long)]
+    pub write_files: bool,
+
+    /// Link a library at an address, as NAME=ADDRESS (path:Name=ADDRESS for
+    /// scripts); other libraries are linked to their current deployment
+    #[arg(long = "library", value_name = "NAME=ADDRESS", value_parser = libraries::parse_flag)]
+    pub libraries: Vec<(String, String)>,
+
+    /// Confirm a deploy to a locked network by repeating its name
+    #[arg(long, value_name = "NETWORK")]
+    pub unlock_confirm: Option<String>,
+}
+
+/// Result of a deploy run, printed with `--json`
+#[derive(Serialize)]
+struct DeployReport {
+    network: String,
+    chain_id: u64,
+    broadcast: bool,
+    deployments: Vec<DeployedContract>,
+}
+
+#[derive(Serialize)]
+struct DeployedContract {
+    contract: String,
+    address: String,
+    tx_hash: TxHash,
+    version: i64,
+    initcode_size: usize,
+    runtime_size: usize,
+    gas_used: Option<i64>,
+    /// Conflicts with the storage layout of the previous version
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    layout_issues: Vec<LayoutIssue>,
 }
 
 impl DeployCommand {
     pub async fn run(self) -> Result<()> {
+        if self.interactive {
+            if output::is_json() {
+                return Err(eyre!("--interactive cannot be combined with --json"));
+            }
+            let libraries = self.libraries.into_iter().collect();
+            let clearance = Clearance::cli(self.unlock_confirm);
+            return run_wizard(
+                self.network.as_deref(),
+                self.write_files,
+                &libraries,
+                clearance,
+            )
+            .await;
+        }
+
+        // Both are required by clap unless --interactive is set
+        let (Some(script), Some(network_name)) = (self.script, self.network) else {
+            return Err(eyre!("A script and --network are required"));
+        };
+
+        if SmolderConfig::load()?.project.framework == Framework::Hardhat {
+            return Err(eyre!(
+                "Script deploys run forge. In Hardhat projects, deploy with hardhat-deploy and then run {}, or use {}",
+                style("smolder sync").cyan(),
+                style("smolder deploy --interactive").cyan()
+            ));
+        }
+
         // Load config from foundry.toml
         let config = FoundryConfig::load()?;
-        let network = config.get_network(&self.network)?;
+        let network = config.get_network(&network_name)?;
 
         // Fetch chain ID from RPC
-        println!(
+        status!(
             "{} Connecting to {}...",
             style("→").blue(),
-            style(&self.network).cyan()
+            style(&network_name).cyan()
         );
         let chain_id = get_chain_id(&network.rpc_url).await?;
 
-        println!(
+        status!(
             "{} Deploying to {} (chain ID: {})",
             style("→").blue(),
             style(&network.name).cyan(),
             chain_id
         );
 
+        // A locked network in the registry holds back broadcasts to it too
+        if self.broadcast {
+            let db = Database::connect().await?;
+            if let Some(locked) = NetworkRepository::get_by_name(&db, &network_name).await? {
+                Clearance::cli(self.unlock_confirm)
+                    .check(&db, &locked, &format!("deploy {}", script))
+                    .await?;
+            }
+        }
+
         // Build forge command
         let mut cmd = Command::new("forge");
         cmd.arg("script")
-            .arg(&self.script)
+            .arg(&script)
             .arg("--rpc-url")
             .arg(&network.rpc_url);
 
         if self.broadcast {
             cmd.arg("--broadcast");
         }
+        for (name, address) in &self.libraries {
+            if !name.contains(':') {
+                return Err(eyre!(
+                    "forge links libraries by path; pass {}",
+                    style(format!("--library <path>:{}={}", name, address)).cyan()
+                ));
+            }
+            cmd.arg("--libraries").arg(format!("{}:{}", name, address));
+        }
 
         // Execute forge script
-        println!("{} Running forge script...", style("→").blue());
+        status!("{} Running forge script...", style("→").blue());
+        let started = SystemTime::now();
         let output = cmd.output()?;
 
         if !output.status.success() {
@@ -70,11 +186,25 @@ impl DeployCommand {
             return Err(eyre!("Forge script failed:\n{}", stderr));
         }
 
-        println!("{}", String::from_utf8_lossy(&output.stdout));
+        if self.plan {
+            return show_plan(&script, &network.name, chain_id, &network.rpc_url, started).await;
+        }
+
+        status!("{}", String::from_utf8_lossy(&output.stdout));
+
+        let mut report = DeployReport {
+            network: network.name.clone(),
+            chain_id,
+            broadcast: self.broadcast,
+            deployments: Vec::new(),
+        };
 
         if !self.broadcast {
-            println!();
-            println!(
+            if output::is_json() {
+                return output::print_json(&report);
+            }
+            status!();
+            status!(
                 "{} Dry run complete. Use {} to actually deploy.",
                 style("ℹ").blue(),
                 style("--broadcast").yellow()
@@ -83,16 +213,19 @@ impl DeployCommand {
         }
 
         // Parse broadcast output
-        println!("{} Parsing deployment data...", style("→").blue());
+        status!("{} Parsing deployment data...", style("→").blue());
         let parser = ForgeBroadcastParser::new();
-        let broadcast_output = parser.parse(&self.script, chain_id)?;
+        let broadcast_output = parser.parse(&script, chain_id)?;
         let deployments = parser.extract_deployments(&broadcast_output)?;
 
         if deployments.is_empty() {
-            println!(
+            status!(
                 "{} No contract deployments found in broadcast",
                 style("⚠").yellow()
             );
+            if output::is_json() {
+                return output::print_json(&report);
+            }
             return Ok(());
         }
 
@@ -112,7 +245,24 @@ impl DeployCommand {
         .await?;
 
         // Store each deployment
+        let mut notices = Vec::new();
+        let mut ids = Vec::new();
         for deployment in &deployments {
+            // Compare with the version being replaced before recording this one
+            let layout_issues = match &deployment.storage_layout {
+                Some(storage_layout) => {
+                    layout::check_against_current(
+                        &db,
+                        &deployment.contract_name,
+                        &db_network.name,
+                        Some(deployment.address),
+                        storage_layout,
+                    )
+                    .await?
+                }
+                None => Vec::new(),
+            };
+
             // Upsert contract
             let contract = ContractRepository::upsert(
                 &db,
@@ -120,36 +270,91 @@ impl DeployCommand {
                     name: deployment.contract_name.clone(),
                     source_path: deployment.source_path.clone(),
                     abi: deployment.abi.clone(),
-                    bytecode_hash: deployment.bytecode_hash.clone(),
+                    bytecode_hash: deployment.bytecode_hash,
+                    storage_layout: deployment.storage_layout.clone(),
+                    compiler: deployment.compiler.clone(),
                 },
             )
             .await?;
 
             // Create deployment record
-            DeploymentRepository::create(
+            let created = DeploymentRepository::create(
                 &db,
                 &NewDeployment {
                     contract_id: contract.id,
                     network_id: db_network.id,
-                    address: deployment.address.clone(),
+                    address: deployment.address,
                     deployer: deployment.deployer.clone(),
-                    tx_hash: deployment.tx_hash.clone(),
+                    tx_hash: Some(deployment.tx_hash),
                     block_number: deployment.block_number,
                     constructor_args: deployment.constructor_args.clone(),
+                    initcode_size: Some(deployment.code_size.initcode as i64),
+                    runtime_size: Some(deployment.code_size.runtime as i64),
+                    gas_used: deployment.gas_used,
+                    gas_price: None,
                 },
             )
             .await?;
 
-            println!(
+            status!(
                 "{} {} deployed at {}",
                 style("✓").green(),
                 style(&deployment.contract_name).cyan(),
                 style(&deployment.address).yellow()
             );
+            for warning in deployment.code_size.warnings() {
+                status!("   {} {}", style("!").yellow(), warning);
+            }
+            layout::print_issues(&deployment.contract_name, &layout_issues);
+
+            ids.push(created.id);
+            notices.push(DeploymentNotice {
+                contract: deployment.contract_name.clone(),
+                network: db_network.name.clone(),
+                address: created.address.to_string(),
+                version: Some(created.version),
+                deployer: created.deployer,
+                tx_hash: created.tx_hash,
+                explorer_url: db_network.explorer_url.clone(),
+            });
+            report.deployments.push(DeployedContract {
+                contract: deployment.contract_name.clone(),
+                address: created.address.to_string(),
+                tx_hash: deployment.tx_hash,
+                version: created.version,
+                initcode_size: deployment.code_size.initcode,
+                runtime_size: deployment.code_size.runtime,
+                gas_used: deployment.gas_used,
+                layout_issues,
+            });
         }
 
-        println!();
-        println!(
+        tokens::enrich_ids(&db, &ids).await;
+        let smolder_config = SmolderConfig::load()?;
+        deployment_files::write_if_enabled(
+            &db,
+            &smolder_config.deployment_files,
+            self.write_files,
+            std::slice::from_ref(&db_network.name),
+        )
+        .await?;
+        notify::announce(&smolder_config.notifications, &notices).await;
+        if !notices.is_empty() {
+            hooks::run(
+                &db,
+                &smolder_config.hooks,
+                HookEvent::PostDeploy,
+                std::slice::from_ref(&db_network.name),
+            )
+            .await;
+        }
+
+        if output::is_json() {
+            return output::print_json(&report);
+        }
+
+        status!();
+        status!(
             "{} {} contract(s) deployed and tracked",
             style("✓").green().bold(),
             deployments.len()
@@ -158,3 +363,359 @@ impl DeployCommand {
         Ok(())
     }
 }
+
+/// Compare the contracts of the dry run that just finished with the registry
+async fn show_plan(
+    script: &str,
+    network: &str,
+    chain_id: u64,
+    rpc_url: &str,
+    started: SystemTime,
+) -> Result<()> {
+    let dry_run = ForgeBroadcastParser::new().parse_dry_run(script, chain_id, started)?;
+    let db = Database::connect().await?;
+    let loader = forge::artifact_loader(Framework::Foundry);
+
+    // The plan is still useful without a price
+    let gas_price = match rpc::get_fees(rpc_url).await {
+        Ok(fees) => Some(fees.gas_price),
+        Err(e) => {
+            tracing::debug!(error = %e, "Could not fetch the gas price");
+            None
+        }
+    };
+    let plan = Plan::build(&db, loader.as_ref(), network, chain_id, &dry_run, gas_price).await?;
+
+    if output::is_json() {
+        return output::print_json(&plan);
+    }
+
+    let registered = NetworkRepository::get_by_name(&db, network).await?;
+    let currency = registered
+        .as_ref()
+        .map_or(chains::currency(chain_id), |n| n.currency());
+    status!();
+    plan.print(currency);
+    status!();
+    status!(
+        "{} Nothing was deployed. Use {} to apply this plan.",
+        style("ℹ").blue(),
+        style("--broadcast").yellow()
+    );
+    Ok(())
+}
+
+/// Link the artifact's libraries, asking for the addresses of those neither
+/// given nor deployed on the network
+async fn link_libraries(
+    db: &Database,
+    network: &str,
+    artifact: &forge::ArtifactDetails,
+    bytecode: &str,
+    given: &BTreeMap<String, String>,
+) -> Result<String> {
+    let found = libraries::resolve(db, network, artifact, given).await?;
+    let mut addresses = found.addresses;
+    for library in found.missing {
+        let address: String = Input::new()
+            .with_prompt(format!("Address of {}", libraries::short_name(&library)))
+            .validate_with(|v: &String| v.parse::<Address>().map(|_| ()).map_err(|e| e.to_string()))
+            .interact_text()?;
+        addresses.insert(library, address.parse()?);
+    }
+    for (library, address) in &addresses {
+        status!(
+            "   {} Linking {} at {}",
+            style("*").dim(),
+            style(libraries::short_name(library)).cyan(),
+            address
+        );
+    }
+    Ok(artifact.link(bytecode, &addresses)?)
+}
+
+/// Guided deploy: the terminal version of the dashboard's deploy page
+async fn run_wizard(
+    network: Option<&str>,
+    write_files: bool,
+    libraries: &BTreeMap<String, String>,
+    mut clearance: Clearance,
+) -> Result<()> {
+    let config = SmolderConfig::load()?;
+    let framework = config.project.framework;
+    let db = Database::connect().await?;
+    let loader = forge::artifact_loader(framework);
+
+    // Interfaces and abstract contracts can't be deployed
+    let artifacts: Vec<_> = loader
+        .list()?
+        .into_iter()
+        .filter(|a| a.has_bytecode)
+        .collect();
+    if artifacts.is_empty() {
+        let build = match framework {
+            Framework::Foundry => "forge build",
+            Framework::Hardhat => "npx hardhat compile",
+        };
+        return Err(eyre!(
+            "No deployable artifacts found. Run {} first.",
+            style(build).yellow()
+        ));
+    }
+
+    let labels: Vec<String> = artifacts
+        .iter()
+        .map(|a| format!("{} ({})", a.name, a.source_path))
+        .collect();
+    let choice = Select::new()
+        .with_prompt("Contract")
+        .items(&labels)
+        .default(0)
+        .interact()?;
+    let name = &artifacts[choice].name;
+
+    let artifact = loader.get_details(name)?;
+    let bytecode = loader.get_bytecode(name)?;
+
+    let network = match network {
+        Some(n) => NetworkRepository::get_by_name(&db, n)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", n))?,
+        None => {
+            let mut networks = NetworkRepository::list(&db).await?;
+            if networks.is_empty() {
+                return Err(eyre!(
+                    "No networks found. Add one with: {}",
+                    style("smolder network add <name> --rpc-url <url>").cyan()
+                ));
+            }
+            let labels: Vec<String> = networks
+                .iter()
+                .map(|n| format!("{} (chain ID: {})", n.name, n.chain_id))
+                .collect();
+            let choice = Select::new()
+                .with_prompt("Network")
+                .items(&labels)
+                .default(0)
+                .interact()?;
+            networks.swap_remove(choice)
+        }
+    };
+    clearance.prompt(&network)?;
+
+    let wallets = WalletRepository::list(&db).await?;
+    if wallets.is_empty() {
+        return Err(eyre!(
+            "No wallets found. Add one with: {}",
+            style("smolder wallet add <name>").cyan()
+        ));
+    }
+    let labels: Vec<String> = wallets
+        .iter()
+        .map(|w| format!("{} ({})", w.name, w.address))
+        .collect();
+    let choice = Select::new()
+        .with_prompt("Wallet")
+        .items(&labels)
+        .default(0)
+        .interact()?;
+    let wallet = &wallets[choice];
+
+    let bytecode =
+        hex::decode(link_libraries(&db, &network.name, &artifact, &bytecode, libraries).await?)?;
+
+    let (args, encoded_args) = match &artifact.constructor {
+        Some(constructor) if !constructor.inputs.is_empty() => {
+            status!("{} Constructor arguments", style("->").blue());
+            let args = prompt_params(&constructor.inputs)?;
+            let encoded = encode_constructor_args(&constructor.inputs, &args)?;
+            (args, encoded)
+        }
+        _ => (Vec::new(), Vec::new()),
+    };
+
+    let value = match &artifact.constructor {
+        Some(constructor) if constructor.is_payable() => {
+            let input: String = Input::new()
+                .with_prompt(format!(
+                    "Value (wei, or e.g. 0.1 {})",
+                    network.currency_symbol
+                ))
+                .default("0".to_string())
+                .validate_with(|v: &String| {
+                    parse_value(v, network.currency())
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                })
+                .interact_text()?;
+            Some(parse_value(&input, network.currency())?).filter(|v| !v.is_zero())
+        }
+        _ => None,
+    };
+
+    let mut deploy_data = bytecode.clone();
+    deploy_data.extend_from_slice(&encoded_args);
+    let deploy_data = Bytes::from(deploy_data);
+
+    println!();
+    println!(
+        "{} Deploying {} to {} from {}",
+        style("->").blue(),
+        style(name).cyan(),
+        style(&network.name).cyan(),
+        style(&wallet.address).yellow()
+    );
+
+    println!(
+        "   Code size:      {} bytes runtime, {} bytes init",
+        artifact.runtime_size, artifact.initcode_size
+    );
+    for warning in &artifact.size_warnings {
+        println!("   {} {}", style("!").yellow(), warning);
+    }
+
+    let storage_layout = artifact.storage_layout_json();
+    if let Some(storage_layout) = &storage_layout {
+        let issues =
+            layout::check_against_current(&db, name, &network.name, None, storage_layout).await?;
+        layout::print_issues(name, &issues);
+    }
+
+    let preflight = rpc::preflight(
+        &network,
+        Outgoing {
+            from: *wallet.address,
+            to: None,
+            data: &deploy_data,
+            value,
+        },
+    )
+    .await?;
+    if let (Some(gas), Some(cost)) = (preflight.gas, preflight.cost(None)) {
+        println!("   Estimated gas:  {}", gas);
+        println!(
+            "   Gas price:      {}",
+            format_gas_price(preflight.gas_price)
+        );
+        println!(
+            "   Estimated cost: {}",
+            format_value(cost, network.currency())
+        );
+    }
+    println!(
+        "   Balance:        {}",
+        format_value(preflight.balance, network.currency())
+    );
+    for warning in &preflight.warnings {
+        println!("   {} {}", style("!").yellow(), warning.message);
+    }
+    println!();
+
+    let confirmed = Confirm::new()
+        .with_prompt("Deploy?")
+        .default(false)
+        .interact()?;
+    if !confirmed {
+        println!("{} Cancelled", style("*").dim());
+        return Ok(());
+    }
+    clearance
+        .check(&db, &network, &format!("deploy {}", name))
+        .await?;
+    let intent = Intent::deploy(&network, name, value);
+    policy::check(&db, wallet.id, &wallet.name, &intent).await?;
+
+    let key = WalletRepository::get_with_key(&db, &wallet.name)
+        .await?
+        .ok_or_else(|| eyre!("Wallet '{}' not found", wallet.name))?;
+    let private_key = decrypt_private_key(&key.encrypted_key)?;
+
+    status!("{} Sending deployment...", style("->").blue());
+    let reservation = policy::reserve(&db, wallet.id, &wallet.name, &intent).await?;
+    let receipt =
+        rpc::deploy_contract(network.rpc_url.expose(), &private_key, deploy_data, value).await;
+    let receipt = reservation.settle(&db, receipt).await?;
+    let tx_hash = receipt.tx_hash;
+
+    let Some(address) = receipt.contract_address else {
+        return Err(eyre!(
+            "Transaction {} was mined without creating a contract",
+            tx_hash
+        ));
+    };
+
+    let contract = ContractRepository::upsert(
+        &db,
+        &NewContract {
+            name: name.clone(),
+            source_path: artifact.source_path.clone(),
+            abi: serde_json::to_string(&artifact.abi)?,
+            bytecode_hash: BytecodeHash::of(&bytecode),
+            storage_layout,
+            compiler: artifact.compiler.clone(),
+        },
+    )
+    .await?;
+
+    let deployment = DeploymentRepository::create(
+        &db,
+        &NewDeployment {
+            contract_id: contract.id,
+            network_id: network.id,
+            address,
+            deployer: wallet.address.to_string(),
+            tx_hash: Some(tx_hash),
+            block_number: receipt.block_number.map(|b| b as i64),
+            constructor_args: (!args.is_empty())
+                .then(|| serde_json::to_string(&args))
+                .transpose()?,
+            initcode_size: Some(artifact.initcode_size as i64),
+            runtime_size: Some(artifact.runtime_size as i64),
+            gas_used: Some(receipt.gas_used as i64),
+            gas_price: Some(receipt.gas_price.to_string()),
+        },
+    )
+    .await?;
+
+    println!(
+        "{} {} v{} deployed at {}",
+        style("*").green().bold(),
+        style(name).cyan(),
+        deployment.version,
+        style(&address).yellow()
+    );
+    println!("   Tx: {}", tx_hash);
+    println!("   Gas used: {}", receipt.gas_used);
+
+    tokens::enrich_ids(&db, &[deployment.id]).await;
+    deployment_files::write_if_enabled(
+        &db,
+        &config.deployment_files,
+        write_files,
+        std::slice::from_ref(&network.name),
+    )
+    .await?;
+
+    notify::announce(
+        &config.notifications,
+        &[DeploymentNotice {
+            contract: name.clone(),
+            network: network.name.clone(),
+            address: address.to_string(),
+            version: Some(deployment.version),
+            deployer: wallet.address.to_string(),
+            tx_hash: Some(tx_hash),
+            explorer_url: network.explorer_url.clone(),
+        }],
+    )
+    .await;
+    hooks::run(
+        &db,
+        &config.hooks,
+        HookEvent::PostDeploy,
+        std::slice::from_ref(&network.name),
+    )
+    .await;
+
+    Ok(())
+}
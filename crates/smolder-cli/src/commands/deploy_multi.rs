@@ -0,0 +1,201 @@
+//! Deploy one artifact to several networks as a group
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use serde::Serialize;
+use smolder_db::{
+    Database, DeploymentGroup, DeploymentGroupId, DeploymentGroupMember, DeploymentGroupRepository,
+    DeploymentRepository, NetworkRepository, TransactionStatus,
+};
+
+use crate::config::SmolderConfig;
+use crate::deploy_group::{self, GroupRequest};
+use crate::forge;
+use crate::hooks::{self, HookEvent};
+use crate::locks::Clearance;
+use crate::notify::{self, DeploymentNotice};
+use crate::output::{self, status};
+use crate::tokens;
+
+/// Deploy one artifact to several networks as a group
+#[derive(Args)]
+pub struct DeployMultiCommand {
+    /// Artifact to deploy
+    #[arg(required_unless_present = "resume")]
+    pub contract: Option<String>,
+
+    /// Networks to deploy to, comma-separated
+    #[arg(long, value_delimiter = ',', required_unless_present = "resume")]
+    pub networks: Vec<String>,
+
+    /// Wallet to deploy from on every network
+    #[arg(long, required_unless_present = "resume")]
+    pub wallet: Option<String>,
+
+    /// Constructor arguments as a JSON array
+    #[arg(long, default_value = "[]")]
+    pub args: String,
+
+    /// Value to send to a payable constructor: wei, or with a unit ("0.1 ether")
+    #[arg(long)]
+    pub value: Option<String>,
+
+    /// Deploy to all networks at once instead of one after the other
+    #[arg(long)]
+    pub parallel: bool,
+
+    /// Retry the networks of an earlier group that failed or were not reached
+    #[arg(long, conflicts_with_all = ["contract", "networks", "wallet", "args", "value"])]
+    pub resume: Option<i64>,
+
+    /// Confirm deploys to a locked network by repeating its name; repeat the
+    /// flag for each locked network
+    #[arg(long, value_name = "NETWORK")]
+    pub unlock_confirm: Vec<String>,
+}
+
+/// Result of a group run, printed with `--json`
+#[derive(Serialize)]
+struct GroupReport {
+    #[serde(flatten)]
+    group: DeploymentGroup,
+    members: Vec<DeploymentGroupMember>,
+}
+
+impl DeployMultiCommand {
+    pub async fn run(self) -> Result<()> {
+        let config = SmolderConfig::load()?;
+        let db = Database::connect().await?;
+        let loader = forge::artifact_loader(config.project.framework);
+        let clearance = Clearance::cli(self.unlock_confirm);
+
+        let group = match self.resume {
+            Some(id) => DeploymentGroupRepository::get(&db, DeploymentGroupId(id))
+                .await?
+                .ok_or_else(|| eyre!("Deployment group {} not found", id))?,
+            None => {
+                let constructor_args: Vec<serde_json::Value> = serde_json::from_str(&self.args)
+                    .map_err(|e| eyre!("--args must be a JSON array: {}", e))?;
+                let request = GroupRequest {
+                    contract: self.contract.unwrap_or_default(),
+                    networks: self.networks,
+                    wallet: self.wallet.unwrap_or_default(),
+                    constructor_args,
+                    value: self.value,
+                };
+                deploy_group::create(&db, loader.as_ref(), &request, &clearance).await?
+            }
+        };
+
+        status!(
+            "{} Deploying {} as group {} ({})",
+            style("→").blue(),
+            style(&group.contract_name).cyan(),
+            group.id,
+            if self.parallel {
+                "in parallel"
+            } else {
+                "sequentially"
+            }
+        );
+
+        let before = DeploymentGroupRepository::list_members(&db, group.id).await?;
+        let members =
+            deploy_group::run(&db, loader.as_ref(), group.id, self.parallel, &clearance).await?;
+
+        // Only announce what this run deployed
+        let deployed: Vec<&DeploymentGroupMember> = members
+            .iter()
+            .filter(|m| m.status == TransactionStatus::Success)
+            .filter(|m| {
+                before
+                    .iter()
+                    .any(|b| b.network_id == m.network_id && b.status != TransactionStatus::Success)
+            })
+            .collect();
+        let ids: Vec<_> = deployed.iter().filter_map(|m| m.deployment_id).collect();
+        tokens::enrich_ids(&db, &ids).await;
+        let mut notices = Vec::new();
+        for id in ids {
+            let Some(view) = DeploymentRepository::get_view_by_id(&db, id).await? else {
+                continue;
+            };
+            let network = NetworkRepository::get_by_name(&db, &view.network_name).await?;
+            notices.push(DeploymentNotice {
+                contract: view.contract_name,
+                network: view.network_name,
+                address: view.address.to_string(),
+                version: Some(view.version),
+                deployer: view.deployer,
+                tx_hash: view.tx_hash,
+                explorer_url: network.and_then(|n| n.explorer_url),
+            });
+        }
+        notify::announce(&config.notifications, &notices).await;
+        let mut networks: Vec<String> = notices.iter().map(|n| n.network.clone()).collect();
+        networks.sort();
+        networks.dedup();
+        if !networks.is_empty() {
+            hooks::run(&db, &config.hooks, HookEvent::PostDeploy, &networks).await;
+        }
+
+        let failed = members
+            .iter()
+            .filter(|m| m.status != TransactionStatus::Success)
+            .count();
+
+        if output::is_json() {
+            output::print_json(&GroupReport {
+                group: group.clone(),
+                members,
+            })?;
+        } else {
+            print_members(&members);
+        }
+
+        if failed > 0 {
+            return Err(eyre!(
+                "{} of {} network(s) failed. Retry them with: {}",
+                failed,
+                before.len(),
+                style(format!("smolder deploy-multi --resume {}", group.id)).cyan()
+            ));
+        }
+
+        status!();
+        status!(
+            "{} {} deployed to {} network(s)",
+            style("✓").green().bold(),
+            group.contract_name,
+            before.len()
+        );
+        Ok(())
+    }
+}
+
+fn print_members(members: &[DeploymentGroupMember]) {
+    println!();
+    println!(
+        "{:<20} {:<10} {:<8} Address / Error",
+        "Network", "Chain ID", "Status"
+    );
+    println!("{}", "-".repeat(90));
+    for member in members {
+        let (status, detail) = match member.status {
+            TransactionStatus::Success => (
+                style("ok").green(),
+                member.address.map(|a| a.to_string()).unwrap_or_default(),
+            ),
+            TransactionStatus::Pending => (style("pending").yellow(), "not attempted".to_string()),
+            _ => (
+                style("failed").red(),
+                member.error_message.clone().unwrap_or_default(),
+            ),
+        };
+        println!(
+            "{:<20} {:<10} {:<8} {}",
+            member.network_name, member.chain_id, status, detail
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Check that the project, database, networks and wallets are usable
+
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use alloy::signers::local::PrivateKeySigner;
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use futures::future::join_all;
+use serde::Serialize;
+use smolder_core::{decrypt_private_key, ChainId, SmolderDir};
+use smolder_db::{Database, Network, NetworkRepository, WalletRepository, WalletWithKey};
+
+use crate::config::{FoundryConfig, Framework, SmolderConfig};
+use crate::output::{self, status};
+use crate::rpc::get_chain_id;
+
+/// Time allowed for each network's RPC to answer
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Check that the project, database, networks and wallets are usable
+#[derive(Args)]
+pub struct DoctorCommand;
+
+/// Outcome of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Health {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One line of the report, printed with `--json` as an array
+#[derive(Debug, Serialize)]
+struct Check {
+    name: String,
+    status: Health,
+    detail: String,
+    /// What to do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Health::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Health::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Health::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+impl DoctorCommand {
+    pub async fn run(self) -> Result<()> {
+        let mut checks = Vec::new();
+
+        let framework = match SmolderConfig::load() {
+            Ok(config) => {
+                checks.push(Check::ok("smolder.toml", "parsed"));
+                config.project.framework
+            }
+            Err(e) => {
+                checks.push(Check::fail(
+                    "smolder.toml",
+                    e.to_string(),
+                    "Fix the file; see the Configuration section of the README",
+                ));
+                Framework::default()
+            }
+        };
+        checks.extend(check_project(framework));
+
+        if let Some(db) = check_database(&mut checks).await {
+            checks.extend(check_networks(&db).await?);
+            checks.extend(check_wallets(&db).await?);
+        }
+
+        if output::is_json() {
+            output::print_json(&checks)?;
+        } else {
+            print_checks(&checks);
+        }
+
+        let failed = checks.iter().filter(|c| c.status == Health::Fail).count();
+        match failed {
+            0 => Ok(()),
+            n => Err(eyre!("{} check(s) failed", n)),
+        }
+    }
+}
+
+/// Toolchain, configuration and build output
+fn check_project(framework: Framework) -> Vec<Check> {
+    let mut checks = Vec::new();
+    match framework {
+        Framework::Foundry => {
+            checks.push(check_forge());
+            checks.push(check_foundry_config());
+            checks.push(check_dir(
+                "out/",
+                "Run `forge build` to compile the contracts",
+            ));
+            checks.push(check_dir(
+                "broadcast/",
+                "Deployments appear here after `smolder deploy <script> --broadcast`",
+            ));
+        }
+        Framework::Hardhat => {
+            checks.push(check_dir(
+                "artifacts/",
+                "Run `npx hardhat compile` to compile the contracts",
+            ));
+            checks.push(check_dir(
+                "deployments/",
+                "Deployments appear here after `npx hardhat deploy`",
+            ));
+        }
+    }
+    checks
+}
+
+fn check_forge() -> Check {
+    match Command::new("forge").arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            Check::ok("forge", version.lines().next().unwrap_or_default().trim())
+        }
+        Ok(output) => Check::fail(
+            "forge",
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            "Reinstall Foundry with `foundryup`",
+        ),
+        Err(_) => Check::fail(
+            "forge",
+            "not found on PATH",
+            "Install Foundry: https://book.getfoundry.sh/getting-started/installation",
+        ),
+    }
+}
+
+fn check_foundry_config() -> Check {
+    if !FoundryConfig::exists() {
+        return Check::fail(
+            "foundry.toml",
+            "not found",
+            "Run smolder from the root of a Foundry project",
+        );
+    }
+    match FoundryConfig::load() {
+        Ok(config) => Check::ok(
+            "foundry.toml",
+            format!("{} RPC endpoint(s)", config.rpc_endpoints.len()),
+        ),
+        Err(e) => Check::fail("foundry.toml", e.to_string(), "Fix the TOML syntax"),
+    }
+}
+
+fn check_dir(dir: &str, fix: &str) -> Check {
+    match Path::new(dir).is_dir() {
+        true => Check::ok(dir, "present"),
+        false => Check::warn(dir, "missing", fix),
+    }
+}
+
+/// Open the database and compare its schema with this build's, returning it
+/// when it can be used for the remaining checks
+async fn check_database(checks: &mut Vec<Check>) -> Option<Database> {
+    if !Database::exists() {
+        checks.push(Check::fail(
+            "database",
+            format!("{}/ not initialized", SmolderDir::NAME),
+            "Run `smolder init`",
+        ));
+        return None;
+    }
+
+    let db = match Database::connect().await {
+        Ok(db) => db,
+        Err(e) => {
+            checks.push(Check::fail(
+                "database",
+                e.to_string(),
+                "Restore a backup with `smolder db restore <file>`",
+            ));
+            return None;
+        }
+    };
+
+    let latest = Database::latest_schema_version();
+    checks.push(match db.schema_version().await {
+        Ok(version) if version > latest => Check::fail(
+            "database",
+            format!(
+                "schema version {} is newer than this smolder's ({})",
+                version, latest
+            ),
+            "Upgrade smolder",
+        ),
+        Ok(version) => Check::ok("database", format!("schema version {}", version)),
+        Err(e) => Check::fail(
+            "database",
+            e.to_string(),
+            "Restore a backup with `smolder db restore <file>`",
+        ),
+    });
+    Some(db)
+}
+
+/// RPC reachability and chain ID of every network, probed concurrently
+async fn check_networks(db: &Database) -> Result<Vec<Check>> {
+    let networks = NetworkRepository::list(db).await?;
+    if networks.is_empty() {
+        return Ok(vec![Check::warn(
+            "networks",
+            "none registered",
+            "Add one with `smolder network add <name> --rpc-url <url>`",
+        )]);
+    }
+
+    let probes = networks.iter().map(|network| async move {
+        let reported =
+            tokio::time::timeout(RPC_TIMEOUT, get_chain_id(network.rpc_url.expose())).await;
+        let reported = match reported {
+            Ok(Ok(chain_id)) => Ok(chain_id),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err(format!("no answer within {}s", RPC_TIMEOUT.as_secs())),
+        };
+        check_network(network, reported)
+    });
+    Ok(join_all(probes).await)
+}
+
+fn check_network(network: &Network, reported: Result<u64, String>) -> Check {
+    let name = format!("network {}", network.name);
+    match reported {
+        Ok(chain_id) if ChainId::from(chain_id) == network.chain_id => {
+            Check::ok(name, format!("chain ID {}", chain_id))
+        }
+        Ok(chain_id) => Check::fail(
+            name,
+            format!(
+                "RPC reports chain ID {}, registry has {}",
+                chain_id, network.chain_id
+            ),
+            format!(
+                "Point it at the right RPC with `smolder network add {} --rpc-url <url>`",
+                network.name
+            ),
+        ),
+        Err(e) => Check::fail(
+            name,
+            format!("RPC unreachable: {}", e),
+            format!(
+                "Check {} or replace it with `smolder network add {} --rpc-url <url>`",
+                network.rpc_url, network.name
+            ),
+        ),
+    }
+}
+
+/// Every stored key decrypts to its wallet's address
+async fn check_wallets(db: &Database) -> Result<Vec<Check>> {
+    let mut checks = Vec::new();
+    for wallet in WalletRepository::list(db).await? {
+        let Some(wallet) = WalletRepository::get_with_key(db, &wallet.name).await? else {
+            continue;
+        };
+        checks.push(check_wallet(&wallet));
+    }
+    Ok(checks)
+}
+
+fn check_wallet(wallet: &WalletWithKey) -> Check {
+    let name = format!("wallet {}", wallet.name);
+    let fix = format!(
+        "Re-add the key with `smolder wallet remove {0}` and `smolder wallet add {0}`",
+        wallet.name
+    );
+    let signer = decrypt_private_key(&wallet.encrypted_key)
+        .map_err(|e| e.to_string())
+        .and_then(|key| {
+            key.parse::<PrivateKeySigner>()
+                .map_err(|e| format!("Invalid private key: {}", e))
+        });
+    match signer {
+        Ok(signer) if signer.address() == *wallet.address => {
+            Check::ok(name, format!("decrypts to {}", wallet.address))
+        }
+        Ok(signer) => Check::fail(
+            name,
+            format!(
+                "key belongs to {}, not {}",
+                signer.address(),
+                wallet.address
+            ),
+            fix,
+        ),
+        Err(e) => Check::fail(name, e, fix),
+    }
+}
+
+fn print_checks(checks: &[Check]) {
+    for check in checks {
+        let mark = match check.status {
+            Health::Ok => style("✓").green(),
+            Health::Warn => style("!").yellow(),
+            Health::Fail => style("✗").red(),
+        };
+        status!("{} {:<24} {}", mark, check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            status!("  {} {}", style("→").dim(), fix);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> Network {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "mainnet",
+            "chain_id": 1,
+            "rpc_url": "http://localhost:8545",
+            "explorer_url": null,
+            "created_at": "2025-01-01 00:00:00",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_network() {
+        let network = network();
+        assert_eq!(check_network(&network, Ok(1)).status, Health::Ok);
+
+        let mismatch = check_network(&network, Ok(10));
+        assert_eq!(mismatch.status, Health::Fail);
+        assert_eq!(mismatch.detail, "RPC reports chain ID 10, registry has 1");
+
+        let unreachable = check_network(&network, Err("connection refused".to_string()));
+        assert_eq!(unreachable.status, Health::Fail);
+        assert!(unreachable
+            .fix
+            .unwrap()
+            .contains("smolder network add mainnet"));
+    }
+
+    #[test]
+    fn test_check_wallet() {
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        let address: alloy::primitives::Address = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+            .parse()
+            .unwrap();
+        let mut wallet = WalletWithKey {
+            id: smolder_db::WalletId(1),
+            name: "deployer".to_string(),
+            address: address.into(),
+            encrypted_key: smolder_core::encrypt_private_key(key).unwrap(),
+            created_at: "2025-01-01 00:00:00".to_string(),
+        };
+        assert_eq!(check_wallet(&wallet).status, Health::Ok);
+
+        wallet.address = alloy::primitives::Address::ZERO.into();
+        assert_eq!(check_wallet(&wallet).status, Health::Fail);
+
+        wallet.encrypted_key = vec![0; 4];
+        assert_eq!(
+            check_wallet(&wallet).detail,
+            "Keyring error: Invalid encrypted data: too short"
+        );
+    }
+}
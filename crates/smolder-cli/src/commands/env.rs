@@ -0,0 +1,56 @@
+//! Print a network's contract addresses as shell variables
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use futures::StreamExt;
+use smolder_db::{Database, DeploymentRepository, NetworkRepository};
+
+use super::export::{self, EnvExport};
+
+/// Print a network's contract addresses as `export` statements
+///
+/// Load them into a shell or script with `eval "$(smolder env <network>)"`.
+/// Variables are named `<PREFIX><CONTRACT>_ADDRESS`.
+#[derive(Args)]
+pub struct EnvCommand {
+    /// Network name
+    pub network: String,
+
+    /// Prepend this to every variable name
+    #[arg(long, default_value = "", value_parser = export::parse_prefix)]
+    pub prefix: String,
+
+    /// Also write ABI files to this directory and export a
+    /// `<CONTRACT>_ABI` variable holding each one's path
+    #[arg(long, value_name = "DIR")]
+    pub abi_dir: Option<PathBuf>,
+}
+
+impl EnvCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        if NetworkRepository::get_by_name(&db, &self.network)
+            .await?
+            .is_none()
+        {
+            return Err(eyre!("Network '{}' not found", self.network));
+        }
+
+        let deployments = DeploymentRepository::stream_for_export(
+            &db,
+            Some(&self.network),
+            self.abi_dir.is_some(),
+        )
+        .boxed();
+        let mut writer = EnvExport::shell(&self.prefix, self.abi_dir);
+
+        // Nothing but statements goes to stdout, so the output can be eval'd
+        let mut out = io::stdout().lock();
+        export::write_export(deployments, &mut writer, &mut out).await?;
+        out.flush()?;
+        Ok(())
+    }
+}
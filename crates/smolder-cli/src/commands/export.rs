@@ -1,55 +1,153 @@
 //! Export deployments to various formats
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
+use alloy::json_abi::JsonAbi;
 use clap::Args;
 use color_eyre::eyre::{eyre, Result};
 use console::style;
+use futures::stream::{BoxStream, StreamExt};
+use handlebars::{handlebars_helper, Handlebars};
 use serde::Serialize;
 
-use smolder_db::{Database, DeploymentRepository};
+use smolder_db::{Database, DeploymentRepository, DeploymentView};
+
+use crate::output::status;
 
 /// Export deployments to various formats
 #[derive(Args)]
 pub struct ExportCommand {
-    /// Output format: json, ts, env
+    /// Output format: json, ts, env (or dotenv), subgraph, abis, template
     #[arg(long, default_value = "json")]
     pub format: String,
 
-    /// Output file path
+    /// Output file path (a directory for the subgraph and abis formats, and
+    /// for env with --per-network)
     #[arg(long, short)]
     pub output: Option<String>,
+
+    /// Only export deployments on this network
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// With the abis format, write ABIs into one subfolder per network; with
+    /// env, write one `.env.<network>` file per network
+    #[arg(long)]
+    pub per_network: bool,
+
+    /// Leave ABIs out of json and ts exports, writing addresses only
+    #[arg(long)]
+    pub no_abi: bool,
+
+    /// With the env format, prepend this to every variable name
+    #[arg(long, default_value = "", value_parser = parse_prefix)]
+    pub prefix: String,
+
+    /// With the env format, also write ABI files to this directory and add a
+    /// `<CONTRACT>_ABI` variable holding each one's path
+    #[arg(long, value_name = "DIR")]
+    pub abi_dir: Option<PathBuf>,
+
+    /// With the template format, the Handlebars template to render
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
 }
 
+pub(super) type DeploymentStream<'a> = BoxStream<'a, smolder_core::Result<DeploymentView>>;
+
 impl ExportCommand {
     pub async fn run(self) -> Result<()> {
-        let db = Database::connect().await?;
-        let deployments = DeploymentRepository::list_for_export(&db, None).await?;
-
-        if deployments.is_empty() {
-            println!("No deployments to export.");
-            return Ok(());
+        let is_env = matches!(self.format.as_str(), "env" | "dotenv");
+        if !is_env && (!self.prefix.is_empty() || self.abi_dir.is_some()) {
+            return Err(eyre!("--prefix and --abi-dir only apply to the env format"));
+        }
+        if self.format != "template" && self.template.is_some() {
+            return Err(eyre!("--template only applies to the template format"));
         }
 
-        let content = match self.format.as_str() {
-            "json" => export_json(&deployments)?,
-            "ts" => export_typescript(&deployments)?,
-            "env" => export_env(&deployments)?,
+        // Compile the template first, so mistakes in it show before any query
+        let template = match (self.format.as_str(), &self.template) {
+            ("template", Some(path)) => Some(TemplateExport::load(path, !self.no_abi)?),
+            ("template", None) => return Err(eyre!("The template format needs a --template file")),
+            _ => None,
+        };
+
+        let needs_abi = match self.format.as_str() {
+            "json" | "ts" | "template" => !self.no_abi,
+            "env" | "dotenv" => self.abi_dir.is_some(),
+            "subgraph" | "abis" if self.no_abi => {
+                return Err(eyre!(
+                    "--no-abi can't be used with the {} format, which needs ABIs",
+                    self.format
+                ))
+            }
+            "subgraph" | "abis" => true,
             _ => {
                 return Err(eyre!(
-                    "Unknown format '{}'. Use: json, ts, env",
+                    "Unknown format '{}'. Use: json, ts, env (or dotenv), subgraph, abis, template",
                     self.format
                 ))
             }
         };
 
+        let db = Database::connect().await?;
+        let mut deployments =
+            DeploymentRepository::stream_for_export(&db, self.network.as_deref(), needs_abi)
+                .peekable();
+
+        if std::pin::Pin::new(&mut deployments).peek().await.is_none() {
+            status!("No deployments to export.");
+            return Ok(());
+        }
+        let deployments: DeploymentStream = deployments.boxed();
+
+        let mut writer: Box<dyn ExportWriter> = match self.format.as_str() {
+            "json" => Box::new(JsonExport::new(needs_abi)),
+            "ts" => Box::new(TypeScriptExport::new(needs_abi)),
+            "template" => Box::new(template.expect("template format has a template")),
+            "env" | "dotenv" if self.per_network => {
+                let dir = self.output.as_deref().unwrap_or(".");
+                return export_env_files(
+                    deployments,
+                    Path::new(dir),
+                    &self.prefix,
+                    self.abi_dir.as_deref(),
+                )
+                .await;
+            }
+            "env" | "dotenv" => Box::new(EnvExport::dotenv(&self.prefix, true, self.abi_dir)),
+            "subgraph" => return export_subgraph(deployments, self.output.as_deref()).await,
+            _ => {
+                let dir = self.output.as_deref().unwrap_or("abis");
+                return export_abis(deployments, Path::new(dir), self.per_network).await;
+            }
+        };
+
         match self.output {
             Some(path) => {
-                std::fs::write(&path, &content)?;
-                println!("{} Exported to {}", style("✓").green(), path);
+                // Write next to the target and rename at the end, so a failed
+                // export never leaves a truncated file behind
+                let partial = PathBuf::from(format!("{}.partial", path));
+                let mut out = BufWriter::new(File::create(&partial)?);
+                let mut written = write_export(deployments, writer.as_mut(), &mut out).await;
+                if written.is_ok() {
+                    written = out.flush().map_err(Into::into);
+                }
+                if let Err(e) = written {
+                    let _ = std::fs::remove_file(&partial);
+                    return Err(e);
+                }
+                std::fs::rename(&partial, &path)?;
+                status!("{} Exported to {}", style("✓").green(), path);
             }
             None => {
-                print!("{}", content);
+                let mut out = io::stdout().lock();
+                write_export(deployments, writer.as_mut(), &mut out).await?;
+                out.flush()?;
             }
         }
 
@@ -57,77 +155,881 @@ impl ExportCommand {
     }
 }
 
+/// A single-file export format, written one deployment at a time
+///
+/// Deployments arrive ordered by network, then contract.
+pub(super) trait ExportWriter {
+    fn begin(&mut self, out: &mut dyn Write) -> Result<()>;
+    fn write(&mut self, out: &mut dyn Write, deployment: &DeploymentView) -> Result<()>;
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()>;
+}
+
+pub(super) async fn write_export(
+    mut deployments: DeploymentStream<'_>,
+    writer: &mut dyn ExportWriter,
+    out: &mut dyn Write,
+) -> Result<()> {
+    writer.begin(out)?;
+    while let Some(deployment) = deployments.next().await {
+        writer.write(out, &deployment?)?;
+    }
+    writer.finish(out)
+}
+
+/// Tracks which network is being written, and skips a contract name seen
+/// twice on the same network (possible when two builds are both current)
+#[derive(Default)]
+struct Grouping {
+    network: Option<String>,
+    contracts: BTreeSet<String>,
+}
+
+enum Position {
+    /// First deployment of a network; the previous network, if any, is done
+    NewNetwork {
+        first: bool,
+    },
+    SameNetwork,
+    Duplicate,
+}
+
+impl Grouping {
+    fn position(&mut self, d: &DeploymentView) -> Position {
+        if self.network.as_deref() != Some(d.network_name.as_str()) {
+            let first = self.network.is_none();
+            self.network = Some(d.network_name.clone());
+            self.contracts.clear();
+            self.contracts.insert(d.contract_name.clone());
+            return Position::NewNetwork { first };
+        }
+        if self.contracts.insert(d.contract_name.clone()) {
+            Position::SameNetwork
+        } else {
+            Position::Duplicate
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct ContractExport {
     address: String,
-    abi: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    abi: Option<serde_json::Value>,
     version: i64,
 }
 
-fn export_json(deployments: &[smolder_db::DeploymentView]) -> Result<String> {
-    // Group by network -> contract
-    let mut export: HashMap<String, HashMap<String, ContractExport>> = HashMap::new();
+/// `{ network: { contract: { address, abi, version } } }`, pretty-printed
+struct JsonExport {
+    with_abi: bool,
+    grouping: Grouping,
+}
 
-    for d in deployments {
-        let network_map = export.entry(d.network_name.clone()).or_default();
-        network_map.insert(
-            d.contract_name.clone(),
-            ContractExport {
-                address: d.address.clone(),
-                abi: serde_json::from_str(&d.abi)?,
-                version: d.version,
-            },
-        );
+impl JsonExport {
+    fn new(with_abi: bool) -> Self {
+        Self {
+            with_abi,
+            grouping: Grouping::default(),
+        }
     }
+}
 
-    Ok(serde_json::to_string_pretty(&export)?)
+impl ExportWriter for JsonExport {
+    fn begin(&mut self, out: &mut dyn Write) -> Result<()> {
+        write!(out, "{{")?;
+        Ok(())
+    }
+
+    fn write(&mut self, out: &mut dyn Write, d: &DeploymentView) -> Result<()> {
+        match self.grouping.position(d) {
+            Position::Duplicate => return Ok(()),
+            Position::NewNetwork { first } => {
+                if !first {
+                    write!(out, "\n  }},")?;
+                }
+                write!(out, "\n  {}: {{", serde_json::to_string(&d.network_name)?)?;
+            }
+            Position::SameNetwork => write!(out, ",")?,
+        }
+
+        let entry = ContractExport {
+            address: d.address.to_string(),
+            abi: self
+                .with_abi
+                .then(|| serde_json::from_str(&d.abi))
+                .transpose()?,
+            version: d.version,
+        };
+        let entry = serde_json::to_string_pretty(&entry)?.replace('\n', "\n    ");
+        write!(
+            out,
+            "\n    {}: {}",
+            serde_json::to_string(&d.contract_name)?,
+            entry
+        )?;
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()> {
+        if self.grouping.network.is_some() {
+            write!(out, "\n  }}\n")?;
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
 }
 
-fn export_typescript(deployments: &[smolder_db::DeploymentView]) -> Result<String> {
-    let mut output = String::new();
-    output.push_str("// Auto-generated by smolder export\n\n");
-    output.push_str("export const contracts = {\n");
+/// A `contracts` object literal typed `as const`
+struct TypeScriptExport {
+    with_abi: bool,
+    grouping: Grouping,
+}
 
-    // Group by network
-    let mut networks: HashMap<String, Vec<&smolder_db::DeploymentView>> = HashMap::new();
-    for d in deployments {
-        networks.entry(d.network_name.clone()).or_default().push(d);
+impl TypeScriptExport {
+    fn new(with_abi: bool) -> Self {
+        Self {
+            with_abi,
+            grouping: Grouping::default(),
+        }
+    }
+}
+
+impl ExportWriter for TypeScriptExport {
+    fn begin(&mut self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "// Auto-generated by smolder export\n")?;
+        writeln!(out, "export const contracts = {{")?;
+        Ok(())
     }
 
-    for (network_name, contracts) in &networks {
-        output.push_str(&format!("  {}: {{\n", network_name));
+    fn write(&mut self, out: &mut dyn Write, d: &DeploymentView) -> Result<()> {
+        match self.grouping.position(d) {
+            Position::Duplicate => return Ok(()),
+            Position::NewNetwork { first } => {
+                if !first {
+                    writeln!(out, "  }},")?;
+                }
+                writeln!(out, "  {}: {{", d.network_name)?;
+            }
+            Position::SameNetwork => {}
+        }
 
-        for d in contracts {
+        writeln!(out, "    {}: {{", d.contract_name)?;
+        writeln!(out, "      address: \"{}\" as const,", d.address)?;
+        if self.with_abi {
             let abi: serde_json::Value = serde_json::from_str(&d.abi)?;
-            output.push_str(&format!("    {}: {{\n", d.contract_name));
-            output.push_str(&format!("      address: \"{}\" as const,\n", d.address));
-            output.push_str(&format!(
-                "      abi: {} as const,\n",
-                serde_json::to_string(&abi)?
-            ));
-            output.push_str("    },\n");
+            writeln!(out, "      abi: {} as const,", serde_json::to_string(&abi)?)?;
         }
+        writeln!(out, "    }},")?;
+        Ok(())
+    }
 
-        output.push_str("  },\n");
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()> {
+        if self.grouping.network.is_some() {
+            writeln!(out, "  }},")?;
+        }
+        writeln!(out, "}} as const;")?;
+        Ok(())
     }
+}
 
-    output.push_str("} as const;\n");
+/// `<NETWORK>_<CONTRACT>_ADDRESS=<address>` lines, plus the path of each
+/// contract's ABI file when ABIs are written alongside
+pub(super) struct EnvExport {
+    /// Prepended to every variable name
+    prefix: String,
+    /// Put the network in variable names, for files holding several networks
+    with_network: bool,
+    /// Directory ABI files are written to and referenced from
+    abi_dir: Option<PathBuf>,
+    /// `export KEY=value;` statements to eval instead of a dotenv file
+    shell: bool,
+    grouping: Grouping,
+}
 
-    Ok(output)
+impl EnvExport {
+    fn dotenv(prefix: &str, with_network: bool, abi_dir: Option<PathBuf>) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            with_network,
+            abi_dir,
+            shell: false,
+            grouping: Grouping::default(),
+        }
+    }
+
+    /// Statements for `eval "$(smolder env <network>)"`
+    pub(super) fn shell(prefix: &str, abi_dir: Option<PathBuf>) -> Self {
+        Self {
+            shell: true,
+            ..Self::dotenv(prefix, false, abi_dir)
+        }
+    }
+
+    fn variable(&self, out: &mut dyn Write, name: &str, value: &str) -> Result<()> {
+        match self.shell {
+            true => writeln!(out, "export {}={};", name, shell_quote(value))?,
+            false => writeln!(out, "{}={}", name, shell_quote(value))?,
+        }
+        Ok(())
+    }
 }
 
-fn export_env(deployments: &[smolder_db::DeploymentView]) -> Result<String> {
-    let mut output = String::new();
-    output.push_str("# Auto-generated by smolder export\n\n");
+impl ExportWriter for EnvExport {
+    fn begin(&mut self, out: &mut dyn Write) -> Result<()> {
+        // Comments would swallow the statements after them in an unquoted eval
+        if !self.shell {
+            writeln!(out, "# Auto-generated by smolder export\n")?;
+        }
+        Ok(())
+    }
 
-    for d in deployments {
-        let var_name = format!(
-            "{}_{}_ADDRESS",
-            d.network_name.to_uppercase().replace('-', "_"),
-            d.contract_name.to_uppercase()
+    fn write(&mut self, out: &mut dyn Write, d: &DeploymentView) -> Result<()> {
+        if let Position::Duplicate = self.grouping.position(d) {
+            return Ok(());
+        }
+
+        let mut name = self.prefix.clone();
+        if self.with_network {
+            name.push_str(&env_name(&d.network_name));
+            name.push('_');
+        }
+        name.push_str(&env_name(&d.contract_name));
+
+        self.variable(out, &format!("{}_ADDRESS", name), &d.address.to_string())?;
+        if let Some(dir) = &self.abi_dir {
+            std::fs::create_dir_all(dir)?;
+            write_abi_file(dir, &d.contract_name, &d.abi)?;
+            let path = dir.join(format!("{}.json", d.contract_name));
+            self.variable(out, &format!("{}_ABI", name), &path.display().to_string())?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _out: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A user's Handlebars template, rendered once with every deployment
+///
+/// The template sees `networks` (each with `name`, `chain_id` and its
+/// `contracts`) and the flat `deployments` list. Entries carry the fields of
+/// [`DeploymentView`], with `abi` parsed to JSON unless left out.
+struct TemplateExport {
+    registry: Handlebars<'static>,
+    with_abi: bool,
+    grouping: Grouping,
+    networks: Vec<TemplateNetwork>,
+}
+
+#[derive(Serialize)]
+struct TemplateData<'a> {
+    networks: &'a [TemplateNetwork],
+    deployments: Vec<&'a serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct TemplateNetwork {
+    name: String,
+    chain_id: i64,
+    contracts: Vec<serde_json::Value>,
+}
+
+impl TemplateExport {
+    const NAME: &'static str = "export";
+
+    fn load(path: &Path, with_abi: bool) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("Failed to read template {}: {}", path.display(), e))?;
+        Self::new(&source, with_abi)
+            .map_err(|e| eyre!("Invalid template {}: {}", path.display(), e))
+    }
+
+    fn new(source: &str, with_abi: bool) -> Result<Self> {
+        let mut registry = Handlebars::new();
+        // Output is config and code, not HTML, and a misspelt field should
+        // fail rather than render empty
+        registry.register_escape_fn(handlebars::no_escape);
+        registry.set_strict_mode(true);
+        registry.register_helper("json", Box::new(JsonHelper));
+        registry.register_helper("upper", Box::new(UpperHelper));
+        registry.register_helper("lower", Box::new(LowerHelper));
+        registry.register_helper("env_name", Box::new(EnvNameHelper));
+        registry.register_template_string(Self::NAME, source)?;
+
+        Ok(Self {
+            registry,
+            with_abi,
+            grouping: Grouping::default(),
+            networks: Vec::new(),
+        })
+    }
+}
+
+handlebars_helper!(JsonHelper: |value: Json| serde_json::to_string(value).unwrap_or_default());
+handlebars_helper!(UpperHelper: |value: str| value.to_uppercase());
+handlebars_helper!(LowerHelper: |value: str| value.to_lowercase());
+handlebars_helper!(EnvNameHelper: |value: str| env_name(value));
+
+impl ExportWriter for TemplateExport {
+    fn begin(&mut self, _out: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+
+    fn write(&mut self, _out: &mut dyn Write, d: &DeploymentView) -> Result<()> {
+        match self.grouping.position(d) {
+            Position::Duplicate => return Ok(()),
+            Position::NewNetwork { .. } => self.networks.push(TemplateNetwork {
+                name: d.network_name.clone(),
+                chain_id: d.chain_id.0,
+                contracts: Vec::new(),
+            }),
+            Position::SameNetwork => {}
+        }
+
+        let mut entry = serde_json::to_value(d)?;
+        if let Some(fields) = entry.as_object_mut() {
+            match self.with_abi {
+                true => fields.insert("abi".into(), serde_json::from_str(&d.abi)?),
+                false => fields.remove("abi"),
+            };
+        }
+        if let Some(network) = self.networks.last_mut() {
+            network.contracts.push(entry);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()> {
+        let data = TemplateData {
+            networks: &self.networks,
+            deployments: self
+                .networks
+                .iter()
+                .flat_map(|n| n.contracts.iter())
+                .collect(),
+        };
+        self.registry.render_to_write(Self::NAME, &data, out)?;
+        Ok(())
+    }
+}
+
+/// Write one `.env.<network>` file per network into `dir`, with ABIs in
+/// `<abi_dir>/<network>/`
+async fn export_env_files(
+    mut deployments: DeploymentStream<'_>,
+    dir: &Path,
+    prefix: &str,
+    abi_dir: Option<&Path>,
+) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut current: Option<(String, EnvExport, BufWriter<File>)> = None;
+    let mut files = Vec::new();
+
+    while let Some(d) = deployments.next().await {
+        let d = d?;
+        if current.as_ref().map(|(network, ..)| network) != Some(&d.network_name) {
+            if let Some((_, mut writer, mut out)) = current.take() {
+                writer.finish(&mut out)?;
+                out.flush()?;
+            }
+            let path = dir.join(format!(".env.{}", d.network_name));
+            let mut out = BufWriter::new(File::create(&path)?);
+            let mut writer = EnvExport::dotenv(
+                prefix,
+                false,
+                abi_dir.map(|abi_dir| abi_dir.join(&d.network_name)),
+            );
+            writer.begin(&mut out)?;
+            files.push(path);
+            current = Some((d.network_name.clone(), writer, out));
+        }
+        if let Some((_, writer, out)) = current.as_mut() {
+            writer.write(out, &d)?;
+        }
+    }
+    if let Some((_, mut writer, mut out)) = current {
+        writer.finish(&mut out)?;
+        out.flush()?;
+    }
+
+    for file in &files {
+        status!("{} Exported to {}", style("✓").green(), file.display());
+    }
+
+    Ok(())
+}
+
+/// Upper-case `part` for a variable name, with anything but letters and
+/// digits as `_`
+fn env_name(part: &str) -> String {
+    part.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+/// `value` as is when the shell leaves it alone, single-quoted otherwise
+fn shell_quote(value: &str) -> String {
+    let plain = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-./:".contains(c));
+    match plain && !value.is_empty() {
+        true => value.to_string(),
+        false => format!("'{}'", value.replace('\'', "'\\''")),
+    }
+}
+
+/// Variable name prefixes: letters, digits and `_`, not starting with a digit
+pub(super) fn parse_prefix(value: &str) -> Result<String, String> {
+    let valid = value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !value.starts_with(|c: char| c.is_ascii_digit());
+    match valid {
+        true => Ok(value.to_string()),
+        false => Err(format!(
+            "'{}' can't start a variable name; use letters, digits and _",
+            value
+        )),
+    }
+}
+
+/// Write the `dataSources` section of a subgraph manifest, plus the ABI files
+/// it references under `abis/` when an output directory is given.
+async fn export_subgraph(
+    mut deployments: DeploymentStream<'_>,
+    output: Option<&str>,
+) -> Result<()> {
+    let abis_dir = output.map(|dir| Path::new(dir).join("abis"));
+    let mut out: Box<dyn Write> = match (output, &abis_dir) {
+        (Some(dir), Some(abis_dir)) => {
+            std::fs::create_dir_all(abis_dir)?;
+            let manifest = File::create(Path::new(dir).join("datasources.yaml"))?;
+            Box::new(BufWriter::new(manifest))
+        }
+        _ => Box::new(io::stdout().lock()),
+    };
+
+    out.write_all(SUBGRAPH_HEADER.as_bytes())?;
+
+    let mut network: Option<String> = None;
+    let mut count = 0;
+    while let Some(d) = deployments.next().await {
+        let d = d?;
+        match &network {
+            Some(n) if *n != d.network_name => {
+                return Err(eyre!(
+                    "A subgraph manifest targets a single network, but deployments span {} and {}. Pass --network.",
+                    n,
+                    d.network_name
+                ));
+            }
+            Some(_) => {}
+            None => network = Some(d.network_name.clone()),
+        }
+
+        if let Some(abis_dir) = &abis_dir {
+            write_abi_file(abis_dir, &d.contract_name, &d.abi)?;
+        }
+        let mut data_source = String::new();
+        write_data_source(&mut data_source, &d)?;
+        out.write_all(data_source.as_bytes())?;
+        count += 1;
+    }
+    out.flush()?;
+
+    if let Some(dir) = output {
+        status!(
+            "{} Exported {} data source(s) to {}",
+            style("✓").green(),
+            count,
+            dir
         );
-        output.push_str(&format!("{}={}\n", var_name, d.address));
     }
 
+    Ok(())
+}
+
+const SUBGRAPH_HEADER: &str = "# Auto-generated by smolder export\ndataSources:\n";
+
+#[cfg(test)]
+fn subgraph_data_sources(deployments: &[DeploymentView]) -> Result<String> {
+    let mut output = SUBGRAPH_HEADER.to_string();
+    for d in deployments {
+        write_data_source(&mut output, d)?;
+    }
     Ok(output)
 }
+
+fn write_data_source(output: &mut String, d: &DeploymentView) -> Result<()> {
+    let abi: JsonAbi = serde_json::from_str(&d.abi)?;
+    let name = &d.contract_name;
+
+    // Anonymous events have no topic0 and can't be indexed by signature
+    let events: Vec<_> = abi.events().filter(|e| !e.anonymous).collect();
+
+    writeln!(output, "  - kind: ethereum")?;
+    writeln!(output, "    name: {}", name)?;
+    writeln!(output, "    network: {}", d.network_name)?;
+    writeln!(output, "    source:")?;
+    writeln!(output, "      address: \"{}\"", d.address)?;
+    writeln!(output, "      abi: {}", name)?;
+    if let Some(block) = d.block_number {
+        writeln!(output, "      startBlock: {}", block)?;
+    }
+    writeln!(output, "    mapping:")?;
+    writeln!(output, "      kind: ethereum/events")?;
+    writeln!(output, "      apiVersion: 0.0.9")?;
+    writeln!(output, "      language: wasm/assemblyscript")?;
+
+    let entities: BTreeSet<&str> = events.iter().map(|e| e.name.as_str()).collect();
+    if entities.is_empty() {
+        writeln!(output, "      entities: []")?;
+    } else {
+        writeln!(output, "      entities:")?;
+        for entity in entities {
+            writeln!(output, "        - {}", entity)?;
+        }
+    }
+
+    writeln!(output, "      abis:")?;
+    writeln!(output, "        - name: {}", name)?;
+    writeln!(output, "          file: ./abis/{}.json", name)?;
+
+    if events.is_empty() {
+        writeln!(output, "      eventHandlers: []")?;
+    } else {
+        writeln!(output, "      eventHandlers:")?;
+        let mut handlers: HashMap<&str, usize> = HashMap::new();
+        for event in &events {
+            let params: Vec<String> = event
+                .inputs
+                .iter()
+                .map(|p| {
+                    if p.indexed {
+                        format!("indexed {}", p.selector_type())
+                    } else {
+                        p.selector_type().to_string()
+                    }
+                })
+                .collect();
+
+            // Overloaded events need distinct handler names
+            let seen = handlers.entry(event.name.as_str()).or_default();
+            *seen += 1;
+            let handler = match *seen {
+                1 => format!("handle{}", event.name),
+                n => format!("handle{}{}", event.name, n),
+            };
+
+            writeln!(
+                output,
+                "        - event: {}({})",
+                event.name,
+                params.join(",")
+            )?;
+            writeln!(output, "          handler: {}", handler)?;
+        }
+    }
+
+    writeln!(output, "      file: ./src/{}.ts", name)?;
+    Ok(())
+}
+
+/// Write one `<Contract>.json` ABI file per contract, optionally grouped into
+/// `<dir>/<network>/` subfolders.
+async fn export_abis(
+    mut deployments: DeploymentStream<'_>,
+    dir: &Path,
+    per_network: bool,
+) -> Result<()> {
+    let mut written = BTreeSet::new();
+
+    while let Some(d) = deployments.next().await {
+        let d = d?;
+        let target = if per_network {
+            dir.join(&d.network_name)
+        } else {
+            dir.to_path_buf()
+        };
+
+        // A contract deployed on several networks shares one ABI unless split by network
+        if !written.insert(target.join(&d.contract_name)) {
+            continue;
+        }
+
+        std::fs::create_dir_all(&target)?;
+        write_abi_file(&target, &d.contract_name, &d.abi)?;
+    }
+
+    status!(
+        "{} Exported {} ABI file(s) to {}",
+        style("✓").green(),
+        written.len(),
+        dir.display()
+    );
+
+    Ok(())
+}
+
+/// Write a contract's ABI as pretty-printed `<dir>/<name>.json`
+fn write_abi_file(dir: &Path, name: &str, abi: &str) -> Result<()> {
+    let abi: serde_json::Value = serde_json::from_str(abi)?;
+    std::fs::write(
+        dir.join(format!("{}.json", name)),
+        serde_json::to_string_pretty(&abi)?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use smolder_core::{TxHash, B256};
+    use smolder_db::{ChainId, DeploymentId, ExplorerLinks};
+
+    use super::*;
+
+    fn deployment(network: &str, block_number: Option<i64>) -> DeploymentView {
+        DeploymentView {
+            id: DeploymentId(1),
+            contract_name: "Token".to_string(),
+            network_name: network.to_string(),
+            chain_id: ChainId(1),
+            address: "0x1111111111111111111111111111111111111111"
+                .parse()
+                .unwrap(),
+            deployer: "0x2222222222222222222222222222222222222222".to_string(),
+            wallet_name: None,
+            tx_hash: Some(TxHash(B256::repeat_byte(0xab))),
+            block_number,
+            version: 1,
+            deployed_at: "2024-01-01 00:00:00".to_string(),
+            is_current: true,
+            archived_at: None,
+            initcode_size: None,
+            runtime_size: None,
+            gas_used: None,
+            gas_price: None,
+            fee: None,
+            created_by_call_id: None,
+            reorged_at: None,
+            finality: None,
+            pinned: false,
+            label: None,
+            description: None,
+            abi: r#"[
+                {"type":"event","name":"Transfer","anonymous":false,"inputs":[
+                    {"name":"from","type":"address","indexed":true},
+                    {"name":"to","type":"address","indexed":true},
+                    {"name":"value","type":"uint256","indexed":false}
+                ]}
+            ]"#
+            .to_string(),
+            links: ExplorerLinks::default(),
+            token: None,
+        }
+    }
+
+    #[test]
+    fn test_subgraph_data_sources() {
+        let manifest = subgraph_data_sources(&[deployment("mainnet", Some(123))]).unwrap();
+
+        assert!(manifest.contains("    name: Token\n"));
+        assert!(manifest.contains("    network: mainnet\n"));
+        assert!(
+            manifest.contains("      address: \"0x1111111111111111111111111111111111111111\"\n")
+        );
+        assert!(manifest.contains("      startBlock: 123\n"));
+        assert!(manifest.contains("          file: ./abis/Token.json\n"));
+        assert!(manifest
+            .contains("        - event: Transfer(indexed address,indexed address,uint256)\n"));
+        assert!(manifest.contains("          handler: handleTransfer\n"));
+    }
+
+    #[test]
+    fn test_subgraph_omits_unknown_start_block() {
+        let manifest = subgraph_data_sources(&[deployment("mainnet", None)]).unwrap();
+        assert!(!manifest.contains("startBlock"));
+    }
+
+    fn stream(deployments: Vec<DeploymentView>) -> DeploymentStream<'static> {
+        futures::stream::iter(deployments.into_iter().map(Ok)).boxed()
+    }
+
+    async fn export_to_string(
+        writer: &mut dyn ExportWriter,
+        deployments: Vec<DeploymentView>,
+    ) -> String {
+        let mut out = Vec::new();
+        write_export(stream(deployments), writer, &mut out)
+            .await
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_export_json() {
+        let mut vault = deployment("mainnet", None);
+        vault.contract_name = "Vault".to_string();
+        let deployments = vec![
+            deployment("mainnet", None),
+            vault,
+            deployment("sepolia", None),
+        ];
+
+        let json = export_to_string(&mut JsonExport::new(true), deployments.clone()).await;
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed["mainnet"]["Token"]["address"],
+            "0x1111111111111111111111111111111111111111"
+        );
+        assert_eq!(parsed["mainnet"]["Vault"]["abi"][0]["name"], "Transfer");
+        assert_eq!(parsed["sepolia"]["Token"]["version"], 1);
+
+        let json = export_to_string(&mut JsonExport::new(false), deployments).await;
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(parsed["mainnet"]["Token"].get("abi").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_export_typescript_without_abi() {
+        let ts = export_to_string(
+            &mut TypeScriptExport::new(false),
+            vec![deployment("mainnet", None), deployment("sepolia", None)],
+        )
+        .await;
+
+        assert!(ts.contains("  mainnet: {\n    Token: {\n"));
+        assert!(ts.contains("  },\n  sepolia: {\n"));
+        assert!(!ts.contains("abi:"));
+        assert!(ts.ends_with("  },\n} as const;\n"));
+    }
+
+    #[tokio::test]
+    async fn test_export_env() {
+        let deployments = vec![
+            deployment("mainnet", None),
+            deployment("base-sepolia", None),
+        ];
+
+        let env = export_to_string(
+            &mut EnvExport::dotenv("APP_", true, None),
+            deployments.clone(),
+        )
+        .await;
+        assert!(env
+            .contains("\nAPP_MAINNET_TOKEN_ADDRESS=0x1111111111111111111111111111111111111111\n"));
+        assert!(env.contains("\nAPP_BASE_SEPOLIA_TOKEN_ADDRESS="));
+
+        let shell = export_to_string(
+            &mut EnvExport::shell("", None),
+            vec![deployment("mainnet", None)],
+        )
+        .await;
+        assert_eq!(
+            shell,
+            "export TOKEN_ADDRESS=0x1111111111111111111111111111111111111111;\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_template() {
+        let template = "{{#each networks}}[{{name}}:{{chain_id}}]\n\
+            {{#each contracts}}{{env_name contract_name}}={{address}} {{json abi.0.name}}\n{{/each}}\
+            {{/each}}{{len deployments}}";
+        let deployments = vec![deployment("mainnet", None), deployment("sepolia", None)];
+
+        let rendered = export_to_string(
+            &mut TemplateExport::new(template, true).unwrap(),
+            deployments.clone(),
+        )
+        .await;
+        assert_eq!(
+            rendered,
+            "[mainnet:1]\nTOKEN=0x1111111111111111111111111111111111111111 \"Transfer\"\n\
+             [sepolia:1]\nTOKEN=0x1111111111111111111111111111111111111111 \"Transfer\"\n2"
+        );
+
+        // Strict mode turns a missing field into an error instead of a blank
+        let mut writer =
+            TemplateExport::new("{{#each deployments}}{{abi}}{{/each}}", false).unwrap();
+        let mut out = Vec::new();
+        assert!(write_export(stream(deployments), &mut writer, &mut out)
+            .await
+            .is_err());
+        assert!(TemplateExport::new("{{#each networks}}", true).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_env_files() {
+        let dir = std::env::temp_dir().join(format!("smolder-env-{}", std::process::id()));
+        let deployments = vec![deployment("mainnet", None), deployment("sepolia", None)];
+
+        export_env_files(stream(deployments), &dir, "", Some(&dir.join("abis")))
+            .await
+            .unwrap();
+        let mainnet = std::fs::read_to_string(dir.join(".env.mainnet")).unwrap();
+        assert!(mainnet.contains("\nTOKEN_ADDRESS=0x1111111111111111111111111111111111111111\n"));
+        assert!(mainnet.contains(&format!(
+            "\nTOKEN_ABI={}\n",
+            dir.join("abis")
+                .join("mainnet")
+                .join("Token.json")
+                .display()
+        )));
+        assert!(dir.join(".env.sepolia").is_file());
+        assert!(dir
+            .join("abis")
+            .join("sepolia")
+            .join("Token.json")
+            .is_file());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_env_names() {
+        assert_eq!(shell_quote("0xabc"), "0xabc");
+        assert_eq!(shell_quote("my abis/Token.json"), "'my abis/Token.json'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+
+        assert!(parse_prefix("CONTRACT_").is_ok());
+        assert!(parse_prefix("").is_ok());
+        assert!(parse_prefix("1X").is_err());
+        assert!(parse_prefix("A-B").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_abis() {
+        let dir = std::env::temp_dir().join(format!("smolder-abis-{}", std::process::id()));
+        let deployments = vec![deployment("mainnet", None), deployment("sepolia", None)];
+
+        export_abis(stream(deployments.clone()), &dir, false)
+            .await
+            .unwrap();
+        assert!(dir.join("Token.json").is_file());
+
+        export_abis(stream(deployments), &dir, true).await.unwrap();
+        assert!(dir.join("mainnet").join("Token.json").is_file());
+        assert!(dir.join("sepolia").join("Token.json").is_file());
+
+        let abi: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("Token.json")).unwrap())
+                .unwrap();
+        assert_eq!(abi[0]["name"], "Transfer");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_rejects_multiple_networks() {
+        let dir = std::env::temp_dir().join(format!("smolder-subgraph-{}", std::process::id()));
+        let deployments = vec![deployment("mainnet", None), deployment("sepolia", None)];
+
+        assert!(export_subgraph(stream(deployments), dir.to_str())
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
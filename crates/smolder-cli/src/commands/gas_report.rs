@@ -0,0 +1,132 @@
+//! Report the gas used per function of each contract
+
+use clap::Args;
+use color_eyre::eyre::Result;
+use console::style;
+use smolder_db::{AnalyticsRepository, Database, GasInterval, GasStat, GasStatsFilter};
+
+use crate::output::{self, status};
+
+/// Report the gas used per function of each contract
+#[derive(Args)]
+pub struct GasReportCommand {
+    /// Only report on this network
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// Only report on this contract
+    #[arg(long)]
+    pub contract: Option<String>,
+
+    /// Only count gas spent at or after this date (YYYY-MM-DD[ HH:MM:SS])
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Only count gas spent at or before this date (YYYY-MM-DD[ HH:MM:SS])
+    #[arg(long)]
+    pub until: Option<String>,
+
+    /// Split figures by day, week or month
+    #[arg(long, value_parser = parse_interval)]
+    pub interval: Option<GasInterval>,
+}
+
+pub fn parse_interval(value: &str) -> Result<GasInterval, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| format!("unknown interval '{}' (day, week, month)", value))
+}
+
+impl GasReportCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let filter = GasStatsFilter {
+            network: self.network,
+            contract: self.contract,
+            since: self.since,
+            until: self.until,
+            interval: self.interval,
+        };
+        let stats = AnalyticsRepository::gas_stats(&db, &filter).await?;
+
+        if output::is_json() {
+            return output::print_json(&stats);
+        }
+
+        if stats.is_empty() {
+            status!("No gas recorded yet.");
+            status!("   Gas is recorded for deployments and for sends once their receipt arrives.");
+            return Ok(());
+        }
+
+        let period_header = match self.interval {
+            Some(_) => format!("{:<11} ", "Period"),
+            None => String::new(),
+        };
+        println!(
+            "{:<15} {:<20} {:<8} {:<24} {}{:>7} {:>10} {:>10} {:>10} {:>8}",
+            "Network",
+            "Contract",
+            "Version",
+            "Function",
+            period_header,
+            "Calls",
+            "Avg",
+            "Min",
+            "Max",
+            "Change"
+        );
+        println!("{}", "-".repeat(120 + period_header.len()));
+
+        let mut previous: Option<&GasStat> = None;
+        for stat in &stats {
+            let delta = previous
+                .filter(|p| {
+                    p.network == stat.network
+                        && p.contract == stat.contract
+                        && p.function == stat.function
+                })
+                .map(|p| change(p.avg_gas, stat.avg_gas))
+                .unwrap_or_else(|| format!("{:>8}", "-"));
+            let period = match self.interval {
+                Some(_) => format!("{:<11} ", stat.period.as_deref().unwrap_or("-")),
+                None => String::new(),
+            };
+            println!(
+                "{:<15} {:<20} {:<8} {:<24} {}{:>7} {:>10} {:>10} {:>10} {}",
+                stat.network,
+                stat.contract,
+                format!("v{}", stat.version),
+                stat.function,
+                period,
+                stat.calls,
+                stat.avg_gas,
+                stat.min_gas,
+                stat.max_gas,
+                delta
+            );
+            previous = Some(stat);
+        }
+
+        status!();
+        status!(
+            "Change compares the average with the row above: the previous version{} of the same function.",
+            if self.interval.is_some() { " or period" } else { "" }
+        );
+
+        Ok(())
+    }
+}
+
+/// Percentage change of the average, red when the function got more expensive
+fn change(before: i64, after: i64) -> String {
+    if before == 0 {
+        return format!("{:>8}", "-");
+    }
+    let pct = (after - before) as f64 / before as f64 * 100.0;
+    let text = format!("{:>+7.1}%", pct);
+    match pct {
+        p if p > 0.05 => style(text).red().to_string(),
+        p if p < -0.05 => style(text).green().to_string(),
+        _ => style(text).dim().to_string(),
+    }
+}
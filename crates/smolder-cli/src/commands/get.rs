@@ -2,8 +2,16 @@
 
 use clap::Args;
 use color_eyre::eyre::{eyre, Result};
+use console::style;
+use serde::Serialize;
 
-use smolder_db::{Database, DeploymentRepository};
+use smolder_db::{
+    Database, Deployment, DeploymentFilter, DeploymentRepository, DeploymentView, ExplorerLinks,
+    NetworkRepository,
+};
+
+use crate::output::{self, status};
+use crate::sourcify::{self, Verification};
 
 /// Get the address of a deployed contract
 #[derive(Args)]
@@ -12,28 +20,214 @@ pub struct GetCommand {
     pub contract: String,
 
     /// Network name
-    #[arg(long)]
-    pub network: String,
+    #[arg(long, required_unless_present = "all_networks")]
+    pub network: Option<String>,
+
+    /// Show the current deployment on every network side by side, with its
+    /// Sourcify verification status
+    #[arg(long, conflicts_with_all = ["network", "contract_version", "history"])]
+    pub all_networks: bool,
+
+    /// With --all-networks, don't look the deployments up on Sourcify
+    #[arg(long, requires = "all_networks")]
+    pub offline: bool,
+
+    /// Get this version instead of the current one
+    #[arg(long = "version", value_name = "N")]
+    pub contract_version: Option<i64>,
+
+    /// Print every version, newest first, with its address, transaction,
+    /// deploy date and deployer
+    #[arg(long, conflicts_with = "contract_version")]
+    pub history: bool,
+}
+
+/// The deployment with explorer links, printed with `--json`
+#[derive(Serialize)]
+struct GetOutput {
+    #[serde(flatten)]
+    deployment: Deployment,
+    #[serde(flatten)]
+    links: ExplorerLinks,
+}
+
+/// A network's current deployment, printed with `--all-networks --json`
+#[derive(Serialize)]
+struct NetworkDeployment {
+    #[serde(flatten)]
+    deployment: DeploymentView,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification: Option<Verification>,
 }
 
 impl GetCommand {
     pub async fn run(self) -> Result<()> {
         let db = Database::connect().await?;
+        let Some(network) = self.network.as_deref() else {
+            return all_networks(&db, &self.contract, self.offline).await;
+        };
 
-        let deployment =
-            DeploymentRepository::get_current(&db, &self.contract, &self.network).await?;
+        let deployment = match (self.history, self.contract_version) {
+            (false, None) => {
+                DeploymentRepository::get_current(&db, &self.contract, network).await?
+            }
+            (history, version) => {
+                let versions =
+                    DeploymentRepository::list_versions(&db, &self.contract, network).await?;
+                if versions.is_empty() {
+                    return Err(self.not_found());
+                }
+                if history {
+                    return print_history(&versions);
+                }
+                let version = version.unwrap_or_default();
+                let Some(view) = versions.iter().find(|d| d.version == version) else {
+                    return Err(eyre!(
+                        "No version {} of contract '{}' on network '{}'; see {}",
+                        version,
+                        self.contract,
+                        network,
+                        style("--history").cyan()
+                    ));
+                };
+                DeploymentRepository::get_by_id(&db, view.id).await?
+            }
+        };
+        let Some(deployment) = deployment else {
+            return Err(self.not_found());
+        };
+
+        let explorer_url = NetworkRepository::get_by_name(&db, network)
+            .await?
+            .and_then(|n| n.explorer_url);
+        let links = ExplorerLinks::new(
+            explorer_url.as_deref(),
+            &deployment.address.to_string(),
+            deployment.tx_hash.map(|h| h.to_string()).as_deref(),
+        );
+
+        if output::is_json() {
+            return output::print_json(&GetOutput { deployment, links });
+        }
 
-        match deployment {
-            Some(d) => {
-                // Just print the address for easy scripting: $(smolder get MyToken --network tempo)
-                println!("{}", d.address);
-                Ok(())
+        if let Some(view) = DeploymentRepository::get_view_by_id(&db, deployment.id).await? {
+            if let Some(label) = &view.label {
+                status!("{}", style(label).bold());
+            }
+            if let Some(description) = &view.description {
+                status!("{}", style(description).dim());
             }
-            None => Err(eyre!(
-                "No deployment found for contract '{}' on network '{}'",
-                self.contract,
-                self.network
-            )),
         }
+
+        // Just print the address for easy scripting: $(smolder get MyToken --network tempo)
+        println!(
+            "{}",
+            output::hyperlink(
+                &deployment.address.to_string(),
+                links.address_url.as_deref()
+            )
+        );
+        Ok(())
     }
+
+    fn not_found(&self) -> color_eyre::eyre::Report {
+        eyre!(
+            "No deployment found for contract '{}' on network '{}'",
+            self.contract,
+            self.network.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Print the current deployment of `contract` on every network
+async fn all_networks(db: &Database, contract: &str, offline: bool) -> Result<()> {
+    let filter = DeploymentFilter {
+        contract: Some(contract.to_string()),
+        ..DeploymentFilter::current()
+    };
+    let deployments = DeploymentRepository::list(db, filter).await?;
+    if deployments.is_empty() {
+        return Err(eyre!("No deployments found for contract '{}'", contract));
+    }
+
+    let verifications = match offline {
+        true => vec![None; deployments.len()],
+        false => {
+            status!("{} Checking verification on Sourcify...", style("→").blue());
+            sourcify::lookup_all(&deployments).await
+        }
+    };
+    let rows: Vec<NetworkDeployment> = deployments
+        .into_iter()
+        .zip(verifications)
+        .map(|(deployment, verification)| NetworkDeployment {
+            deployment,
+            verification,
+        })
+        .collect();
+
+    if output::is_json() {
+        return output::print_json(&rows);
+    }
+
+    println!(
+        "{:<20} {:<10} {:<8} {:<42} Verified",
+        "Network", "Chain ID", "Version", "Address"
+    );
+    println!("{}", "-".repeat(94));
+    for row in &rows {
+        let d = &row.deployment;
+        let address = d.address.to_string();
+        let verified = match &row.verification {
+            Some(Verification::Verified(level)) => style(level.clone()).green(),
+            Some(Verification::Unverified(_)) => style("no".to_string()).yellow(),
+            None => style("?".to_string()).dim(),
+        };
+        println!(
+            "{:<20} {:<10} {:<8} {}{} {}",
+            d.network_name,
+            d.chain_id.0,
+            format!("v{}", d.version),
+            output::hyperlink(&address, d.links.address_url.as_deref()),
+            " ".repeat(42usize.saturating_sub(address.len())),
+            verified
+        );
+    }
+    Ok(())
+}
+
+/// Print the version lineage, newest first
+fn print_history(versions: &[DeploymentView]) -> Result<()> {
+    if output::is_json() {
+        return output::print_json(&versions);
+    }
+
+    println!(
+        "{:<10} {:<42} {:<66} {:<19} Deployer",
+        "Version", "Address", "Tx Hash", "Deployed At"
+    );
+    println!("{}", "-".repeat(150));
+    for d in versions {
+        let version = match (d.is_current, d.archived_at.is_some()) {
+            (true, _) => format!("v{} *", d.version),
+            (false, true) => format!("v{} (a)", d.version),
+            (false, false) => format!("v{}", d.version),
+        };
+        let address = d.address.to_string();
+        let tx_hash = d.tx_hash.map(|h| h.to_string()).unwrap_or_default();
+        println!(
+            "{:<10} {}{} {}{} {:<19} {}",
+            version,
+            output::hyperlink(&address, d.links.address_url.as_deref()),
+            " ".repeat(42usize.saturating_sub(address.len())),
+            output::hyperlink(&tx_hash, d.links.tx_url.as_deref()),
+            " ".repeat(66usize.saturating_sub(tx_hash.len())),
+            d.deployed_at.chars().take(19).collect::<String>(),
+            d.deployer
+        );
+    }
+
+    status!();
+    status!("* current, (a) archived");
+    Ok(())
 }
@@ -0,0 +1,124 @@
+//! Inspect the history of contract interactions
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::Result;
+use console::style;
+use smolder_db::{CallHistoryFilter, Database, DeploymentId, TransactionStatus};
+
+use crate::config::SmolderConfig;
+use crate::history::{self, HistoryFormat};
+use crate::output::status;
+use crate::prices::PriceOracle;
+
+/// Inspect the history of contract interactions
+#[derive(Args)]
+pub struct HistoryCommand {
+    #[command(subcommand)]
+    pub command: HistorySubcommand,
+}
+
+impl HistoryCommand {
+    pub async fn run(self) -> Result<()> {
+        self.command.run().await
+    }
+}
+
+#[derive(Subcommand)]
+pub enum HistorySubcommand {
+    /// Export calls with decoded parameters and results
+    Export(ExportHistoryCommand),
+}
+
+impl HistorySubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Export(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Export calls with decoded parameters and results
+#[derive(Args)]
+pub struct ExportHistoryCommand {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = HistoryFormat::Json)]
+    pub format: HistoryFormat,
+
+    /// Output file path (stdout when omitted)
+    #[arg(long, short)]
+    pub output: Option<String>,
+
+    /// Only export calls to this deployment ID
+    #[arg(long)]
+    pub deployment: Option<i64>,
+
+    /// Only export calls to this contract
+    #[arg(long)]
+    pub contract: Option<String>,
+
+    /// Only export calls on this network
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// Only export calls with this status (pending, success, failed, reverted)
+    #[arg(long, value_parser = parse_status)]
+    pub status: Option<TransactionStatus>,
+
+    /// Only export calls made at or after this date (YYYY-MM-DD[ HH:MM:SS])
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Only export calls made at or before this date (YYYY-MM-DD[ HH:MM:SS])
+    #[arg(long)]
+    pub until: Option<String>,
+}
+
+fn parse_status(value: &str) -> Result<TransactionStatus, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| format!("unknown status '{}'", value))
+}
+
+impl ExportHistoryCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let filter = CallHistoryFilter {
+            deployment_id: self.deployment.map(DeploymentId),
+            contract: self.contract,
+            network: self.network,
+            status: self.status,
+            since: self.since,
+            until: self.until,
+            ..Default::default()
+        };
+        let oracle = SmolderConfig::load()?
+            .prices
+            .as_ref()
+            .map(PriceOracle::from_config)
+            .transpose()?;
+        let records = history::load(&db, filter, oracle.as_ref()).await?;
+
+        match self.output {
+            Some(path) => {
+                let mut out = BufWriter::new(File::create(&path)?);
+                history::write(&records, self.format, &mut out)?;
+                out.flush()?;
+                status!(
+                    "{} Exported {} call(s) to {}",
+                    style("✓").green(),
+                    records.len(),
+                    path
+                );
+            }
+            None => {
+                let mut out = io::stdout().lock();
+                history::write(&records, self.format, &mut out)?;
+                out.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! Import deployments from an existing manifest
+
+use std::collections::HashMap;
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use serde::Serialize;
+use smolder_core::BytecodeHash;
+use smolder_db::{
+    ChainId, ContractRepository, Database, DeploymentRepository, Network, NetworkRepository,
+    NewContract, NewDeployment, NewNetwork,
+};
+
+use crate::commands::db::auto_backup;
+use crate::config::FoundryConfig;
+use crate::manifest::{parse_manifest, ManifestDeployment};
+use crate::output::{self, status};
+use crate::rpc::get_chain_id;
+
+/// Import deployments from a hardhat-deploy or smolder JSON manifest
+#[derive(Args)]
+pub struct ImportCommand {
+    /// Path to the manifest file
+    pub file: String,
+}
+
+/// Result of an import, printed with `--json`
+#[derive(Serialize, Default)]
+struct ImportReport {
+    imported: Vec<ImportedDeployment>,
+    skipped: usize,
+}
+
+#[derive(Serialize)]
+struct ImportedDeployment {
+    network: String,
+    contract: String,
+    address: String,
+    version: i64,
+}
+
+impl ImportCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read_to_string(&self.file)
+            .map_err(|e| eyre!("Could not read {}: {}", self.file, e))?;
+        let deployments = parse_manifest(&content)?;
+
+        status!(
+            "{} Found {} deployment(s) in {}",
+            style("->").blue(),
+            deployments.len(),
+            style(&self.file).cyan()
+        );
+
+        let db = Database::connect().await?;
+        if !deployments.is_empty() {
+            auto_backup(&db, "import").await?;
+        }
+
+        let mut networks: HashMap<String, Network> = HashMap::new();
+        let mut report = ImportReport::default();
+
+        for deployment in &deployments {
+            let network = match networks.get(&deployment.network) {
+                Some(n) => n.clone(),
+                None => {
+                    let n = resolve_network(&db, deployment).await?;
+                    networks.insert(deployment.network.clone(), n.clone());
+                    n
+                }
+            };
+
+            if DeploymentRepository::exists_by_address(&db, network.id, &deployment.address).await?
+            {
+                status!(
+                    "   {} {} on {} already tracked",
+                    style("-").dim(),
+                    style(&deployment.contract).dim(),
+                    network.name
+                );
+                report.skipped += 1;
+                continue;
+            }
+
+            // Manifests don't carry bytecode, so the ABI stands in for it when
+            // telling contract versions apart
+            let contract = ContractRepository::upsert(
+                &db,
+                &NewContract {
+                    name: deployment.contract.clone(),
+                    source_path: self.file.clone(),
+                    abi: deployment.abi.clone(),
+                    bytecode_hash: BytecodeHash::of(&deployment.abi),
+                    storage_layout: None,
+                    compiler: None,
+                },
+            )
+            .await?;
+
+            // Deployer and transaction are unknown for imported records
+            let created = DeploymentRepository::create(
+                &db,
+                &NewDeployment {
+                    contract_id: contract.id,
+                    network_id: network.id,
+                    address: deployment.address.parse()?,
+                    deployer: String::new(),
+                    tx_hash: None,
+                    block_number: None,
+                    constructor_args: None,
+                    initcode_size: None,
+                    runtime_size: None,
+                    gas_used: None,
+                    gas_price: None,
+                },
+            )
+            .await?;
+
+            status!(
+                "   {} {} on {} at {}",
+                style("+").green(),
+                style(&deployment.contract).cyan(),
+                network.name,
+                style(&deployment.address).yellow()
+            );
+            report.imported.push(ImportedDeployment {
+                network: network.name.clone(),
+                contract: deployment.contract.clone(),
+                address: created.address.to_string(),
+                version: created.version,
+            });
+        }
+
+        if output::is_json() {
+            return output::print_json(&report);
+        }
+
+        status!();
+        status!(
+            "{} Imported {} deployment(s), skipped {} already tracked",
+            style("*").green().bold(),
+            report.imported.len(),
+            report.skipped
+        );
+
+        Ok(())
+    }
+}
+
+/// Find the manifest's network in the registry, or register it from foundry.toml
+async fn resolve_network(db: &Database, deployment: &ManifestDeployment) -> Result<Network> {
+    if let Some(network) = NetworkRepository::get_by_name(db, &deployment.network).await? {
+        if let Some(chain_id) = deployment.chain_id {
+            if ChainId::from(chain_id) != network.chain_id {
+                return Err(eyre!(
+                    "Chain ID mismatch for '{}': registry has {}, manifest has {}",
+                    network.name,
+                    network.chain_id,
+                    chain_id
+                ));
+            }
+        }
+        return Ok(network);
+    }
+
+    let configured = FoundryConfig::exists()
+        .then(FoundryConfig::load)
+        .transpose()?
+        .and_then(|config| config.get_network(&deployment.network).ok());
+
+    let Some(configured) = configured else {
+        return Err(eyre!(
+            "Network '{}' is not registered. Add it with: {}",
+            deployment.network,
+            style(format!(
+                "smolder network add {} --rpc-url <url>",
+                deployment.network
+            ))
+            .cyan()
+        ));
+    };
+
+    let chain_id = match deployment.chain_id {
+        Some(id) => id,
+        None => get_chain_id(&configured.rpc_url).await?,
+    };
+
+    let network = NetworkRepository::upsert(
+        db,
+        &NewNetwork {
+            name: configured.name.clone(),
+            chain_id: ChainId::from(chain_id),
+            rpc_url: configured.rpc_url.clone(),
+            explorer_url: configured.explorer_url.clone(),
+        },
+    )
+    .await?;
+
+    status!(
+        "   {} Registered network {} from foundry.toml",
+        style("*").dim(),
+        style(&network.name).cyan()
+    );
+
+    Ok(network)
+}
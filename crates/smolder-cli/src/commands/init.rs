@@ -1,18 +1,47 @@
 //! Initialize smolder in a Foundry project
 
 use std::path::Path;
+use std::time::Duration;
 
 use clap::Args;
 use color_eyre::eyre::{eyre, Result};
 use console::style;
-use smolder_core::SmolderDir;
-use smolder_db::Database;
+use smolder_core::{chains, ChainId, SmolderDir};
+use smolder_db::{Database, NetworkRepository, NewNetwork};
 
 use crate::config::FoundryConfig;
+use crate::output::status;
+use crate::rpc::get_chain_id;
+
+/// Where `--script` writes the deploy script
+const DEPLOY_SCRIPT: &str = "script/Deploy.s.sol";
+
+/// Time allowed for each foundry.toml RPC to report its chain ID
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Initialize smolder in a Foundry project
+///
+/// Registers the networks of foundry.toml and a local anvil node, and writes
+/// a starter smolder.toml.
 #[derive(Args)]
-pub struct InitCommand;
+pub struct InitCommand {
+    /// Write a forge deploy script to script/Deploy.s.sol
+    #[arg(long)]
+    pub script: bool,
+
+    /// Contract the deploy script deploys, imported from src/<CONTRACT>.sol
+    #[arg(
+        long,
+        value_name = "CONTRACT",
+        default_value = "Counter",
+        requires = "script"
+    )]
+    pub contract: String,
+
+    /// Don't register a local anvil network
+    #[arg(long)]
+    pub no_anvil: bool,
+}
 
 impl InitCommand {
     pub async fn run(self) -> Result<()> {
@@ -22,6 +51,7 @@ impl InitCommand {
                 "Not a Foundry project. Please run this command in a directory with foundry.toml"
             ));
         }
+        let foundry = FoundryConfig::load()?;
 
         // Check if already initialized
         if Database::exists() {
@@ -34,36 +64,236 @@ impl InitCommand {
         // Create .smolder/ directory
         let dir = SmolderDir::new();
         dir.create()?;
-        println!("{} Created {}/", style("✓").green(), SmolderDir::NAME);
+        status!("{} Created {}/", style("✓").green(), SmolderDir::NAME);
 
         // Create and initialize database
         let db = Database::connect().await?;
         db.init_schema().await?;
-        println!("{} Initialized database", style("✓").green());
+        status!("{} Initialized database", style("✓").green());
 
         // Optionally add to .gitignore
         add_to_gitignore()?;
 
-        println!();
-        println!(
+        let mut networks: Vec<&str> = foundry.network_names();
+        networks.sort_unstable();
+        write_if_missing("smolder.toml", &smolder_toml(&networks))?;
+
+        register_networks(&db, &foundry, &networks).await?;
+        if !self.no_anvil {
+            register_anvil(&db).await?;
+        }
+
+        if self.script {
+            std::fs::create_dir_all("script")?;
+            write_if_missing(DEPLOY_SCRIPT, &deploy_script(&self.contract, &networks))?;
+        }
+
+        status!();
+        status!(
             "{} Smolder initialized successfully!",
             style("✓").green().bold()
         );
-        println!();
-        println!("Next steps:");
-        println!(
-            "  1. Configure networks in foundry.toml under {}",
-            style("[rpc_endpoints]").cyan()
-        );
-        println!(
+        status!();
+        status!("Next steps:");
+        if networks.is_empty() {
+            status!(
+                "  1. Configure networks in foundry.toml under {}",
+                style("[rpc_endpoints]").cyan()
+            );
+        } else {
+            status!(
+                "  1. Build the contracts with {}",
+                style("forge build").cyan()
+            );
+        }
+        let script = match self.script {
+            true => DEPLOY_SCRIPT,
+            false => "<script>",
+        };
+        let network = match (networks.contains(&"anvil"), networks.first()) {
+            (true, _) => "anvil",
+            (false, Some(name)) => name,
+            (false, None) => "<name>",
+        };
+        status!(
             "  2. Run {} to deploy contracts",
-            style("smolder deploy <script> --network <name>").cyan()
+            style(format!(
+                "smolder deploy {} --network {} --broadcast",
+                script, network
+            ))
+            .cyan()
+        );
+        status!(
+            "  3. Check the setup any time with {}",
+            style("smolder doctor").cyan()
         );
 
         Ok(())
     }
 }
 
+/// Register the foundry.toml networks whose RPC answers
+///
+/// Networks behind unset environment variables or unreachable RPCs are
+/// skipped with a warning; `smolder network add` registers them later.
+async fn register_networks(db: &Database, foundry: &FoundryConfig, names: &[&str]) -> Result<()> {
+    for name in names {
+        let config = match foundry.get_network(name) {
+            Ok(config) => config,
+            Err(e) => {
+                status!("{} Skipped network '{}': {}", style("!").yellow(), name, e);
+                continue;
+            }
+        };
+        let chain_id = match tokio::time::timeout(RPC_TIMEOUT, get_chain_id(&config.rpc_url)).await
+        {
+            Ok(Ok(chain_id)) => chain_id,
+            Ok(Err(e)) => {
+                status!("{} Skipped network '{}': {}", style("!").yellow(), name, e);
+                continue;
+            }
+            Err(_) => {
+                status!(
+                    "{} Skipped network '{}': RPC did not answer",
+                    style("!").yellow(),
+                    name
+                );
+                continue;
+            }
+        };
+        let explorer_url = config.explorer_url.or_else(|| {
+            chains::by_chain_id(chain_id)?
+                .explorer_url
+                .map(str::to_string)
+        });
+        NetworkRepository::upsert(
+            db,
+            &NewNetwork {
+                name: config.name,
+                chain_id: ChainId::from(chain_id),
+                rpc_url: config.rpc_url,
+                explorer_url,
+            },
+        )
+        .await?;
+        status!(
+            "{} Registered network '{}' (chain ID: {})",
+            style("✓").green(),
+            name,
+            chain_id
+        );
+    }
+    Ok(())
+}
+
+/// Register the default anvil node, with impersonation, unless foundry.toml
+/// already named a network `anvil`
+async fn register_anvil(db: &Database) -> Result<()> {
+    if NetworkRepository::get_by_name(db, "anvil").await?.is_some() {
+        return Ok(());
+    }
+    let Some(anvil) = chains::by_name("anvil") else {
+        return Ok(());
+    };
+    NetworkRepository::upsert(
+        db,
+        &NewNetwork {
+            name: anvil.name.to_string(),
+            chain_id: ChainId::from(anvil.chain_id),
+            rpc_url: anvil.rpc_urls[0].to_string(),
+            explorer_url: None,
+        },
+    )
+    .await?;
+    NetworkRepository::set_impersonation(db, anvil.name, true).await?;
+    status!(
+        "{} Registered network 'anvil' ({})",
+        style("✓").green(),
+        anvil.rpc_urls[0]
+    );
+    Ok(())
+}
+
+/// Write `content` to `path` unless the file exists
+fn write_if_missing(path: &str, content: &str) -> Result<()> {
+    if Path::new(path).exists() {
+        status!("{} Kept existing {}", style("*").dim(), path);
+        return Ok(());
+    }
+    std::fs::write(path, content)?;
+    status!("{} Created {}", style("✓").green(), path);
+    Ok(())
+}
+
+/// Starter smolder.toml, with the optional sections commented out
+fn smolder_toml(networks: &[&str]) -> String {
+    let detected = match networks.is_empty() {
+        true => "none yet".to_string(),
+        false => networks.join(", "),
+    };
+    format!(
+        r#"# smolder configuration; every section is optional
+# Networks detected in foundry.toml: {detected}
+
+[project]
+framework = "foundry"
+
+# Write deployments/<network>.json on each deploy, for versioning in git
+# [deployment_files]
+# write = true
+
+# Resolve ENS names on this network
+# [ens]
+# network = "mainnet"
+
+# Regenerate downstream artifacts after each deploy
+# [[hooks.post_deploy]]
+# export = "ts"
+# output = "../frontend/src/contracts.ts"
+
+# Value fees in USD
+# [prices]
+# source = "coingecko"
+
+# Prune call history older than this while `smolder serve` runs
+# [retention]
+# call_history = "90d"
+"#
+    )
+}
+
+/// Forge script deploying `contract`, with the deploy command of each network
+fn deploy_script(contract: &str, networks: &[&str]) -> String {
+    let mut usage = String::new();
+    for network in networks {
+        usage.push_str(&format!(
+            "///   smolder deploy script/Deploy.s.sol --network {} --broadcast\n",
+            network
+        ));
+    }
+    if usage.is_empty() {
+        usage.push_str("///   smolder deploy script/Deploy.s.sol --network <name> --broadcast\n");
+    }
+    format!(
+        r#"// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+import {{Script}} from "forge-std/Script.sol";
+import {{{contract}}} from "../src/{contract}.sol";
+
+/// Deploys {contract}. Run through smolder to track the deployment:
+///
+{usage}contract DeployScript is Script {{
+    function run() public {{
+        vm.startBroadcast();
+        new {contract}();
+        vm.stopBroadcast();
+    }}
+}}
+"#
+    )
+}
+
 fn add_to_gitignore() -> Result<()> {
     let gitignore_path = Path::new(".gitignore");
     let entry = SmolderDir::NAME;
@@ -82,9 +312,33 @@ fn add_to_gitignore() -> Result<()> {
             new_content.push_str(entry);
             new_content.push('\n');
             std::fs::write(gitignore_path, new_content)?;
-            println!("{} Added {} to .gitignore", style("✓").green(), entry);
+            status!("{} Added {} to .gitignore", style("✓").green(), entry);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SmolderConfig;
+
+    #[test]
+    fn test_smolder_toml_parses() {
+        let content = smolder_toml(&["anvil", "sepolia"]);
+        assert!(content.contains("# Networks detected in foundry.toml: anvil, sepolia"));
+        assert!(toml::from_str::<SmolderConfig>(&content).is_ok());
+    }
+
+    #[test]
+    fn test_deploy_script() {
+        let script = deploy_script("Token", &["sepolia"]);
+        assert!(script.contains("import {Token} from \"../src/Token.sol\";"));
+        assert!(script
+            .contains("///   smolder deploy script/Deploy.s.sol --network sepolia --broadcast\n"));
+        assert!(script.contains("        new Token();"));
+
+        assert!(deploy_script("Token", &[]).contains("--network <name>"));
+    }
+}
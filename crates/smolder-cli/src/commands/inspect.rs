@@ -0,0 +1,301 @@
+//! Show everything known about a deployment next to its live state
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use alloy::json_abi::Function;
+use alloy::primitives::{Address, Bytes};
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use futures::future::join_all;
+use serde::Serialize;
+use smolder_core::{decode_function_result, Abi};
+use smolder_db::{Database, DeploymentRepository, DeploymentView, NetworkRepository};
+
+use crate::output::{self, status};
+use crate::rpc;
+use crate::sourcify::{self, Verification};
+
+/// Time allowed for each live read
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Show a deployment's recorded details next to its live state
+///
+/// Every view function without parameters is called, so the output is a
+/// snapshot of the contract's configuration.
+#[derive(Args)]
+pub struct InspectCommand {
+    /// Contract name
+    pub contract: String,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Inspect this version instead of the current one
+    #[arg(long = "version", value_name = "N")]
+    pub contract_version: Option<i64>,
+
+    /// Don't look the contract up on Sourcify
+    #[arg(long)]
+    pub offline: bool,
+}
+
+/// Printed with `--json`
+#[derive(Serialize)]
+struct InspectReport<'a> {
+    deployment: &'a DeploymentView,
+    /// Constructor arguments keyed by parameter name, or as recorded
+    constructor_args: Option<serde_json::Value>,
+    /// Sourcify match (`exact_match` or `match`), `false` when unverified,
+    /// absent when unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification: Option<Verification>,
+    /// Result of each parameterless view function, by signature
+    state: BTreeMap<String, Read>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Read {
+    Value(serde_json::Value),
+    Error(String),
+}
+
+impl InspectCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+
+        let versions =
+            DeploymentRepository::list_versions(&db, &self.contract, &self.network).await?;
+        let deployment = match self.contract_version {
+            Some(version) => versions.iter().find(|d| d.version == version),
+            None => versions.iter().find(|d| d.is_current),
+        }
+        .ok_or_else(|| {
+            eyre!(
+                "No deployment found for contract '{}' on network '{}'",
+                self.contract,
+                self.network
+            )
+        })?;
+        let network = NetworkRepository::get_by_name(&db, &self.network)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.network))?;
+
+        let abi = Abi::parse(&deployment.abi).ok();
+        let constructor_args = DeploymentRepository::get_by_id(&db, deployment.id)
+            .await?
+            .and_then(|d| d.constructor_args);
+        let constructor_args = constructor_args
+            .as_deref()
+            .map(|args| name_constructor_args(args, abi.as_ref()));
+
+        status!(
+            "{} Reading {} on {}...",
+            style("→").blue(),
+            style(&deployment.contract_name).cyan(),
+            style(&network.name).cyan()
+        );
+        let address = Address::from(deployment.address);
+        let (state, verification) = tokio::join!(
+            read_state(network.rpc_url.expose(), address, abi.as_ref()),
+            async {
+                match self.offline {
+                    true => None,
+                    false => sourcify::lookup(network.chain_id.0, address).await,
+                }
+            }
+        );
+
+        let report = InspectReport {
+            deployment,
+            constructor_args,
+            verification,
+            state,
+        };
+        if output::is_json() {
+            return output::print_json(&report);
+        }
+        print_report(&report, versions.len());
+        Ok(())
+    }
+}
+
+/// Pair recorded constructor arguments with the constructor's parameter
+/// names, keeping them as recorded when they don't line up
+fn name_constructor_args(args: &str, abi: Option<&Abi>) -> serde_json::Value {
+    let values: serde_json::Value =
+        serde_json::from_str(args).unwrap_or_else(|_| serde_json::Value::String(args.to_string()));
+    let (Some(constructor), serde_json::Value::Array(list)) =
+        (abi.and_then(Abi::constructor), &values)
+    else {
+        return values;
+    };
+    if constructor.inputs.len() != list.len() {
+        return values;
+    }
+    constructor
+        .inputs
+        .iter()
+        .zip(list)
+        .enumerate()
+        .map(|(i, (input, value))| {
+            let name = match input.name.is_empty() {
+                true => format!("arg{}", i),
+                false => input.name.clone(),
+            };
+            (name, value.clone())
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Call every view function without parameters
+async fn read_state(rpc_url: &str, address: Address, abi: Option<&Abi>) -> BTreeMap<String, Read> {
+    let Some(abi) = abi else {
+        return BTreeMap::new();
+    };
+    let getters: Vec<&Function> = abi
+        .inner()
+        .functions()
+        .filter(|f| f.inputs.is_empty())
+        .filter(|f| {
+            matches!(
+                f.state_mutability,
+                alloy::json_abi::StateMutability::View | alloy::json_abi::StateMutability::Pure
+            )
+        })
+        .collect();
+
+    let reads = getters.into_iter().map(|function| async move {
+        (function.signature(), read(rpc_url, address, function).await)
+    });
+    join_all(reads).await.into_iter().collect()
+}
+
+async fn read(rpc_url: &str, address: Address, function: &Function) -> Read {
+    let data = Bytes::from(function.selector().to_vec());
+    match tokio::time::timeout(TIMEOUT, rpc::execute_eth_call(rpc_url, address, data)).await {
+        Ok(Ok(output)) => match decode_function_result(function, &output) {
+            Ok(value) => Read::Value(value),
+            Err(e) => Read::Error(e.to_string()),
+        },
+        Ok(Err(e)) => Read::Error(e.to_string()),
+        Err(_) => Read::Error("timed out".to_string()),
+    }
+}
+
+/// Whether Sourcify has verified sources for the contract, or None when it
+/// couldn't be asked
+fn print_report(report: &InspectReport, version_count: usize) {
+    let d = report.deployment;
+    let row = |label: &str, value: String| println!("  {:<14} {}", label, value);
+
+    println!(
+        "{} on {} (chain ID: {})",
+        style(&d.contract_name).cyan().bold(),
+        style(&d.network_name).cyan(),
+        d.chain_id
+    );
+    row(
+        "Address",
+        output::hyperlink(&d.address.to_string(), d.links.address_url.as_deref()),
+    );
+    row(
+        "Version",
+        format!(
+            "v{} of {}{}",
+            d.version,
+            version_count,
+            match d.is_current {
+                true => " (current)",
+                false => "",
+            }
+        ),
+    );
+    row("Deployer", d.deployer.clone());
+    if let Some(tx_hash) = d.tx_hash {
+        row(
+            "Transaction",
+            output::hyperlink(&tx_hash.to_string(), d.links.tx_url.as_deref()),
+        );
+    }
+    if let Some(block) = d.block_number {
+        row("Block", block.to_string());
+    }
+    row("Deployed at", d.deployed_at.clone());
+    row(
+        "Verification",
+        match &report.verification {
+            Some(Verification::Verified(level)) => style(format!(
+                "verified on Sourcify ({})",
+                level.replace('_', " ")
+            ))
+            .green()
+            .to_string(),
+            Some(Verification::Unverified(_)) => {
+                style("not verified on Sourcify").yellow().to_string()
+            }
+            None => style("unknown").dim().to_string(),
+        },
+    );
+
+    if let Some(args) = &report.constructor_args {
+        println!();
+        println!("{}", style("Constructor").bold());
+        match args {
+            serde_json::Value::Object(named) => {
+                for (name, value) in named {
+                    row(name, display(value));
+                }
+            }
+            other => println!("  {}", display(other)),
+        }
+    }
+
+    println!();
+    println!("{}", style("State").bold());
+    if report.state.is_empty() {
+        println!("  {}", style("No view functions without parameters").dim());
+    }
+    for (signature, read) in &report.state {
+        match read {
+            Read::Value(value) => row(signature, display(value)),
+            Read::Error(e) => row(signature, style(e).red().to_string()),
+        }
+    }
+}
+
+/// Strings without their JSON quotes, everything else as JSON
+fn display(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_constructor_args() {
+        let abi = Abi::from_human_readable(["constructor(string name, uint256)"]).unwrap();
+
+        assert_eq!(
+            name_constructor_args(r#"["Token","1000"]"#, Some(&abi)),
+            serde_json::json!({"name": "Token", "arg1": "1000"})
+        );
+        // Kept as recorded when the ABI doesn't match
+        assert_eq!(
+            name_constructor_args(r#"["Token"]"#, Some(&abi)),
+            serde_json::json!(["Token"])
+        );
+        assert_eq!(
+            name_constructor_args("0xdead", None),
+            serde_json::json!("0xdead")
+        );
+    }
+}
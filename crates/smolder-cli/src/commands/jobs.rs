@@ -0,0 +1,77 @@
+//! Show background jobs run by `smolder serve`
+
+use clap::Args;
+use color_eyre::eyre::Result;
+use console::style;
+use smolder_db::{Database, JobFilter, JobRepository, JobState};
+
+use crate::output::{self, status};
+
+/// Show background jobs run by the server
+#[derive(Args)]
+pub struct JobsCommand {
+    /// Only show jobs in this state (queued, running, succeeded, failed)
+    #[arg(long, value_parser = parse_state)]
+    pub state: Option<JobState>,
+
+    /// Only show jobs of this kind, e.g. `receipt`
+    #[arg(long)]
+    pub kind: Option<String>,
+
+    /// Maximum number of jobs to show
+    #[arg(long, default_value_t = 20)]
+    pub limit: u32,
+}
+
+fn parse_state(value: &str) -> Result<JobState, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| format!("unknown state '{}'", value))
+}
+
+impl JobsCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let jobs = JobRepository::list(
+            &db,
+            &JobFilter {
+                state: self.state,
+                kind: self.kind,
+                limit: Some(self.limit),
+            },
+        )
+        .await?;
+
+        if output::is_json() {
+            return output::print_json(&jobs);
+        }
+
+        if jobs.is_empty() {
+            status!("{} No jobs found", style("!").yellow());
+            return Ok(());
+        }
+
+        println!(
+            "{:<8} {:<10} {:<10} {:<9} {:<20} {:<20} Error",
+            "ID", "Kind", "State", "Attempts", "Created", "Next attempt"
+        );
+        println!("{}", "-".repeat(100));
+
+        for job in &jobs {
+            println!(
+                "{:<8} {:<10} {:<10} {:<9} {:<20} {:<20} {}",
+                job.id,
+                job.kind,
+                job.state.as_str(),
+                format!("{}/{}", job.attempts, job.max_attempts),
+                job.created_at,
+                match job.state {
+                    JobState::Queued => job.run_after.as_str(),
+                    _ => "-",
+                },
+                job.last_error.as_deref().unwrap_or("")
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! Label and describe contracts and deployments
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+
+use smolder_db::{ContractRepository, Database, DeploymentRepository};
+
+use crate::commands::pin::find_deployment;
+use crate::output::{self, status};
+
+/// Set a contract's label and description, or one deployment's with
+/// `--network`. Without a label, description or `--clear`, prints them
+#[derive(Args)]
+pub struct LabelCommand {
+    /// Contract name
+    pub contract: String,
+
+    /// Display label, e.g. "Main fee vault"; an empty one removes it
+    pub label: Option<String>,
+
+    /// Markdown description; an empty one removes it
+    #[arg(long, conflicts_with = "description_file")]
+    pub description: Option<String>,
+
+    /// Read the markdown description from a file
+    #[arg(long, value_name = "PATH")]
+    pub description_file: Option<String>,
+
+    /// Remove the label and description
+    #[arg(long, conflicts_with_all = ["label", "description", "description_file"])]
+    pub clear: bool,
+
+    /// Only the contract's deployment on this network
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// With --network, this version rather than the current one
+    #[arg(long = "version", value_name = "N", requires = "network")]
+    pub contract_version: Option<i64>,
+}
+
+impl LabelCommand {
+    pub async fn run(self) -> Result<()> {
+        let description = match &self.description_file {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .map_err(|e| eyre!("Could not read {}: {}", path, e))?,
+            ),
+            None => self.description.clone(),
+        };
+        let db = Database::connect().await?;
+
+        let (deployment, current) = match &self.network {
+            None => {
+                let contract = ContractRepository::get_by_name(&db, &self.contract)
+                    .await?
+                    .ok_or_else(|| eyre!("Contract '{}' not found", self.contract))?;
+                (None, (contract.label, contract.description))
+            }
+            Some(network) => {
+                let view =
+                    find_deployment(&db, &self.contract, network, self.contract_version).await?;
+                let deployment = DeploymentRepository::get_by_id(&db, view.id)
+                    .await?
+                    .ok_or_else(|| eyre!("Deployment {} not found", view.id))?;
+                (Some(view), (deployment.label, deployment.description))
+            }
+        };
+
+        let changed = self.clear || self.label.is_some() || description.is_some();
+        let (label, description) = match self.clear {
+            true => (None, None),
+            false => (
+                merge(self.label.clone(), current.0),
+                merge(description, current.1),
+            ),
+        };
+        if changed {
+            match &deployment {
+                None => {
+                    ContractRepository::set_label(
+                        &db,
+                        &self.contract,
+                        label.as_deref(),
+                        description.as_deref(),
+                    )
+                    .await?
+                }
+                Some(view) => {
+                    DeploymentRepository::set_label(
+                        &db,
+                        view.id,
+                        label.as_deref(),
+                        description.as_deref(),
+                    )
+                    .await?
+                }
+            }
+        }
+
+        if output::is_json() {
+            return output::print_json(&serde_json::json!({
+                "contract": self.contract,
+                "network": self.network,
+                "version": deployment.as_ref().map(|d| d.version),
+                "label": label,
+                "description": description,
+            }));
+        }
+
+        let target = match &deployment {
+            None => format!("Contract {}", style(&self.contract).cyan()),
+            Some(view) => format!(
+                "{} v{} on {}",
+                style(&self.contract).cyan(),
+                view.version,
+                style(&view.network_name).cyan()
+            ),
+        };
+        if changed {
+            status!("{} {} updated", style("*").green().bold(), target);
+        }
+        match (&label, &description) {
+            (None, None) => status!("{} has no label", target),
+            _ => {
+                status!("   Label:       {}", label.as_deref().unwrap_or("-"));
+                if let Some(description) = &description {
+                    status!("   Description: {}", description);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A new value replaces the current one, and an empty one removes it
+fn merge(new: Option<String>, current: Option<String>) -> Option<String> {
+    match new {
+        Some(value) if value.trim().is_empty() => None,
+        Some(value) => Some(value),
+        None => current,
+    }
+}
@@ -1,10 +1,24 @@
 //! List all deployments
 
-use clap::Args;
-use color_eyre::eyre::Result;
-use console::style;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
-use smolder_db::{Database, DeploymentFilter, DeploymentRepository};
+use alloy::primitives::Address;
+use clap::{Args, ValueEnum};
+use color_eyre::eyre::{eyre, Result};
+use console::{style, Term};
+use serde::Serialize;
+
+use smolder_db::{Database, DeploymentFilter, DeploymentId, DeploymentRepository, DeploymentView};
+
+use crate::config::SmolderConfig;
+use crate::contract_status::{self, ContractStatus};
+use crate::ens::EnsResolver;
+
+use crate::interact::prompt_interaction;
+use crate::output::{self, status};
+use crate::tokens;
+use crate::tui::pick_deployment;
 
 /// List all deployments
 #[derive(Args)]
@@ -12,50 +26,536 @@ pub struct ListCommand {
     /// Filter by network
     #[arg(long)]
     pub network: Option<String>,
+
+    /// Filter by contract
+    #[arg(long)]
+    pub contract: Option<String>,
+
+    /// Only show deployments sent from this wallet
+    #[arg(long)]
+    pub wallet: Option<String>,
+
+    /// Only show this version; previous versions need --all
+    #[arg(long = "version", value_name = "N")]
+    pub contract_version: Option<i64>,
+
+    /// Include previous versions, listed under the current one
+    #[arg(long, short)]
+    pub all: bool,
+
+    /// Print one table per network or contract
+    #[arg(long, value_enum, conflicts_with = "interactive")]
+    pub group_by: Option<GroupBy>,
+
+    /// Columns to show, in order (comma-separated). Defaults to network,
+    /// contract, token, version, address and deployed, plus label when any
+    /// deployment has one and the columns of --verbose, --ens and --status
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        conflicts_with = "interactive"
+    )]
+    pub columns: Vec<Column>,
+
+    /// Redraw the table every SECS seconds (5 by default) until interrupted
+    #[arg(
+        long,
+        value_name = "SECS",
+        num_args = 0..=1,
+        default_missing_value = "5",
+        conflicts_with = "interactive"
+    )]
+    pub watch: Option<u64>,
+
+    /// Browse deployments in a searchable table and call or send to the selected one
+    #[arg(long, short)]
+    pub interactive: bool,
+
+    /// Show deployers by their ENS name
+    #[arg(long, conflicts_with = "interactive")]
+    pub ens: bool,
+
+    /// Show code sizes and the gas each deployment used
+    #[arg(long, short, conflicts_with = "interactive")]
+    pub verbose: bool,
+
+    /// Query each contract's paused(), owner() and implementation()
+    #[arg(long, conflicts_with = "interactive")]
+    pub status: bool,
+}
+
+/// What `--group-by` splits the table on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    Network,
+    Contract,
+}
+
+/// A column of the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Network,
+    Contract,
+    /// Display label set with `smolder label`
+    Label,
+    Token,
+    Version,
+    Address,
+    Deployed,
+    /// Runtime code size
+    Runtime,
+    /// Init code size
+    Init,
+    Gas,
+    Deployer,
+    Status,
+}
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Self::Network => "Network",
+            Self::Contract => "Contract",
+            Self::Label => "Label",
+            Self::Token => "Token",
+            Self::Version => "Version",
+            Self::Address => "Address",
+            Self::Deployed => "Deployed At",
+            Self::Runtime => "Runtime",
+            Self::Init => "Init",
+            Self::Gas => "Gas",
+            Self::Deployer => "Deployer",
+            Self::Status => "Status",
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Self::Network => 15,
+            Self::Contract => 20,
+            Self::Label => 24,
+            Self::Token => 10,
+            Self::Version => 8,
+            Self::Address | Self::Deployer => 44,
+            Self::Deployed => 20,
+            Self::Runtime | Self::Init => 9,
+            Self::Gas => 10,
+            Self::Status => 30,
+        }
+    }
+
+    /// Figures are aligned right
+    fn is_numeric(self) -> bool {
+        matches!(self, Self::Runtime | Self::Init | Self::Gas)
+    }
+}
+
+/// A deployment with its deployer's ENS name and live status, printed with
+/// `--json --ens` or `--json --status`
+#[derive(Serialize)]
+struct AnnotatedDeployment<'a> {
+    #[serde(flatten)]
+    deployment: &'a DeploymentView,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deployer_name: Option<&'a String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<&'a ContractStatus>,
+}
+
+/// What the table is drawn from
+struct Listing {
+    deployments: Vec<DeploymentView>,
+    names: HashMap<String, String>,
+    statuses: HashMap<DeploymentId, ContractStatus>,
 }
 
 impl ListCommand {
     pub async fn run(self) -> Result<()> {
+        if self.interactive && output::is_json() {
+            return Err(eyre!("--interactive cannot be combined with --json"));
+        }
+        if self.watch.is_some() && output::is_json() {
+            return Err(eyre!("--watch cannot be combined with --json"));
+        }
+
         let db = Database::connect().await?;
-        let filter = match &self.network {
-            Some(n) => DeploymentFilter::for_network(n),
-            None => DeploymentFilter::current(),
+        let columns = self.columns();
+
+        let Some(secs) = self.watch else {
+            let listing = self.load(&db, &columns).await?;
+            return self.show(&db, &listing, &columns).await;
+        };
+
+        let term = Term::stdout();
+        loop {
+            let listing = self.load(&db, &columns).await?;
+            term.clear_screen()?;
+            self.print(&listing, &columns);
+            status!(
+                "{}",
+                style(format!("Refreshing every {}s, Ctrl-C to stop", secs)).dim()
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(secs.max(1))) => {}
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+            }
+        }
+    }
+
+    /// Columns from `--columns`, or the defaults with those of the flags
+    fn columns(&self) -> Vec<Column> {
+        if !self.columns.is_empty() {
+            return self.columns.clone();
+        }
+        let mut columns = vec![
+            Column::Network,
+            Column::Contract,
+            Column::Token,
+            Column::Version,
+            Column::Address,
+            Column::Deployed,
+        ];
+        if self.verbose {
+            columns.extend([Column::Runtime, Column::Init, Column::Gas]);
+        }
+        if self.ens {
+            columns.push(Column::Deployer);
+        }
+        if self.status {
+            columns.push(Column::Status);
+        }
+        columns
+    }
+
+    async fn load(&self, db: &Database, columns: &[Column]) -> Result<Listing> {
+        let filter = DeploymentFilter {
+            network: self.network.clone(),
+            contract: self.contract.clone(),
+            version: self.contract_version,
+            wallet: self.wallet.clone(),
+            current_only: !self.all,
+            ..Default::default()
         };
-        let deployments = DeploymentRepository::list(&db, filter).await?;
+        let mut deployments = DeploymentRepository::list(db, filter).await?;
+        tokens::enrich(db, &mut deployments).await;
+
+        let names = if self.ens {
+            deployer_names(db, &deployments).await
+        } else {
+            HashMap::new()
+        };
+        let statuses = if self.status || columns.contains(&Column::Status) {
+            contract_status::read(db, &deployments).await
+        } else {
+            HashMap::new()
+        };
+        Ok(Listing {
+            deployments,
+            names,
+            statuses,
+        })
+    }
+
+    async fn show(&self, db: &Database, listing: &Listing, columns: &[Column]) -> Result<()> {
+        let deployments = &listing.deployments;
+        if output::is_json() {
+            if !self.ens && !self.status {
+                return output::print_json(deployments);
+            }
+            let annotated: Vec<_> = deployments
+                .iter()
+                .map(|d| AnnotatedDeployment {
+                    deployment: d,
+                    deployer_name: listing.names.get(&d.deployer),
+                    status: listing.statuses.get(&d.id),
+                })
+                .collect();
+            return output::print_json(&annotated);
+        }
 
         if deployments.is_empty() {
-            println!("No deployments found.");
-            if self.network.is_some() {
-                println!(
+            status!("No deployments found.");
+            if self.network.is_some() || self.contract.is_some() {
+                status!(
                     "Try running without {} to see all deployments.",
-                    style("--network").yellow()
+                    style("--network/--contract").yellow()
                 );
             }
             return Ok(());
         }
 
-        // Print table header
-        println!(
-            "{:<15} {:<20} {:<8} {:<44} {:<20}",
-            "Network", "Contract", "Version", "Address", "Deployed At"
-        );
-        println!("{}", "-".repeat(110));
-
-        // Print each deployment
-        for d in &deployments {
-            println!(
-                "{:<15} {:<20} {:<8} {:<44} {:<20}",
-                d.network_name,
-                d.contract_name,
-                format!("v{}", d.version),
-                d.address,
-                &d.deployed_at[..19] // Trim to just date and time
-            );
+        if self.interactive {
+            return match pick_deployment(deployments)? {
+                Some(deployment) => prompt_interaction(db, deployment).await,
+                None => Ok(()),
+            };
         }
 
-        println!();
-        println!("Total: {} deployment(s)", deployments.len());
-
+        self.print(listing, columns);
         Ok(())
     }
+
+    /// Print the table, or one per group
+    fn print(&self, listing: &Listing, columns: &[Column]) {
+        let mut columns = columns.to_vec();
+        if self.columns.is_empty() && listing.deployments.iter().any(|d| d.label.is_some()) {
+            columns.insert(2, Column::Label);
+        }
+        let columns = columns.as_slice();
+        let rows = tree(&listing.deployments);
+        match self.group_by {
+            None => print_table(&rows, columns, listing),
+            Some(group_by) => {
+                let grouped = match group_by {
+                    GroupBy::Network => Column::Network,
+                    GroupBy::Contract => Column::Contract,
+                };
+                let columns: Vec<Column> =
+                    columns.iter().copied().filter(|c| *c != grouped).collect();
+                let mut groups: Vec<&str> = rows.iter().map(|r| group_key(r, group_by)).collect();
+                groups.sort_unstable();
+                groups.dedup();
+                for (i, group) in groups.into_iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    let rows: Vec<Row> = rows
+                        .iter()
+                        .filter(|r| group_key(r, group_by) == group)
+                        .copied()
+                        .collect();
+                    println!("{}", style(group).cyan().bold());
+                    print_table(&rows, &columns, listing);
+                }
+            }
+        }
+
+        status!();
+        status!("Total: {} deployment(s)", listing.deployments.len());
+    }
+}
+
+/// A deployment in the table; previous versions are drawn as branches of
+/// the current one
+#[derive(Clone, Copy)]
+struct Row<'a> {
+    deployment: &'a DeploymentView,
+    /// A previous version, shown under the latest listed one
+    branch: Option<Branch>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Branch {
+    Middle,
+    Last,
+}
+
+/// Order deployments newest version first within each contract and
+/// network, marking older versions as branches of the newest. Contracts
+/// with a pinned deployment on a network come first
+fn tree(deployments: &[DeploymentView]) -> Vec<Row<'_>> {
+    let pinned: HashSet<(&str, &str)> = deployments
+        .iter()
+        .filter(|d| d.pinned)
+        .map(|d| (d.network_name.as_str(), d.contract_name.as_str()))
+        .collect();
+    let mut sorted: Vec<&DeploymentView> = deployments.iter().collect();
+    sorted.sort_by_cached_key(|d| {
+        let key = (d.network_name.as_str(), d.contract_name.as_str());
+        (!pinned.contains(&key), key, std::cmp::Reverse(d.version))
+    });
+
+    let mut rows: Vec<Row> = Vec::with_capacity(sorted.len());
+    for (i, deployment) in sorted.iter().enumerate() {
+        let same = |other: Option<&&DeploymentView>| {
+            other.is_some_and(|o| {
+                o.network_name == deployment.network_name
+                    && o.contract_name == deployment.contract_name
+            })
+        };
+        let branch = match (
+            same(i.checked_sub(1).map(|p| &sorted[p])),
+            same(sorted.get(i + 1)),
+        ) {
+            (false, _) => None,
+            (true, true) => Some(Branch::Middle),
+            (true, false) => Some(Branch::Last),
+        };
+        rows.push(Row { deployment, branch });
+    }
+    rows
+}
+
+fn group_key<'a>(row: &Row<'a>, group_by: GroupBy) -> &'a str {
+    match group_by {
+        GroupBy::Network => &row.deployment.network_name,
+        GroupBy::Contract => &row.deployment.contract_name,
+    }
+}
+
+fn print_table(rows: &[Row], columns: &[Column], listing: &Listing) {
+    let header: Vec<(String, usize)> = columns
+        .iter()
+        .map(|c| (c.header().to_string(), c.header().len()))
+        .collect();
+    println!("{}", line(&header, columns));
+    let width: usize = columns.iter().map(|c| c.width() + 1).sum();
+    println!("{}", "-".repeat(width.saturating_sub(1)));
+
+    for row in rows {
+        let cells: Vec<(String, usize)> = columns
+            .iter()
+            .map(|column| cell(row, *column, listing))
+            .collect();
+        println!("{}", line(&cells, columns));
+    }
+}
+
+/// Join cells, padding all but the last to their column's width. Cells
+/// carry their visible width, since links and colors add escape codes
+fn line(cells: &[(String, usize)], columns: &[Column]) -> String {
+    let mut line = String::new();
+    for (i, ((text, width), column)) in cells.iter().zip(columns).enumerate() {
+        let pad = " ".repeat(column.width().saturating_sub(*width));
+        let last = i + 1 == cells.len();
+        match (column.is_numeric(), last) {
+            (true, _) => line.push_str(&format!("{}{}", pad, text)),
+            (false, true) => line.push_str(text),
+            (false, false) => line.push_str(&format!("{}{}", text, pad)),
+        }
+        if !last {
+            line.push(' ');
+        }
+    }
+    line
+}
+
+fn cell(row: &Row, column: Column, listing: &Listing) -> (String, usize) {
+    let d = row.deployment;
+    let plain = |text: String| {
+        let width = text.chars().count();
+        (text, width)
+    };
+    match column {
+        Column::Network | Column::Contract if row.branch.is_some() => plain(String::new()),
+        Column::Network => plain(d.network_name.clone()),
+        Column::Contract if d.pinned => plain(format!("{} ★", d.contract_name)),
+        Column::Contract => plain(d.contract_name.clone()),
+        Column::Label => plain(d.label.clone().unwrap_or_default()),
+        Column::Token => plain(
+            d.token
+                .as_ref()
+                .and_then(|t| t.symbol.clone())
+                .unwrap_or_default(),
+        ),
+        Column::Version => plain(match row.branch {
+            None => format!("v{}", d.version),
+            Some(Branch::Middle) => format!("├ v{}", d.version),
+            Some(Branch::Last) => format!("└ v{}", d.version),
+        }),
+        Column::Address => {
+            let address = d.address.to_string();
+            let width = address.len();
+            (
+                output::hyperlink(&address, d.links.address_url.as_deref()),
+                width,
+            )
+        }
+        // Trim to just date and time
+        Column::Deployed => plain(d.deployed_at.chars().take(19).collect()),
+        Column::Runtime => plain(or_dash(d.runtime_size)),
+        Column::Init => plain(or_dash(d.initcode_size)),
+        Column::Gas => plain(or_dash(d.gas_used)),
+        Column::Deployer => plain(
+            listing
+                .names
+                .get(&d.deployer)
+                .or(d.wallet_name.as_ref())
+                .unwrap_or(&d.deployer)
+                .clone(),
+        ),
+        Column::Status => {
+            let status = describe_status(listing.statuses.get(&d.id));
+            let width = console::measure_text_width(&status);
+            (status, width)
+        }
+    }
+}
+
+/// Summary of a contract's status for the status column: whether it is
+/// paused, who owns it and what it points to, or `?` when its network
+/// couldn't be reached
+fn describe_status(status: Option<&ContractStatus>) -> String {
+    let Some(status) = status else {
+        return style("?").dim().to_string();
+    };
+    if status.is_empty() {
+        return style("-").dim().to_string();
+    }
+    let mut parts = Vec::new();
+    match status.paused {
+        Some(true) => parts.push(style("paused").yellow().to_string()),
+        Some(false) => parts.push(style("active").green().to_string()),
+        None => {}
+    }
+    if let Some(owner) = &status.owner {
+        parts.push(format!("owner {}", short_address(owner)));
+    }
+    if let Some(implementation) = &status.implementation {
+        parts.push(format!("impl {}", short_address(implementation)));
+    }
+    parts.join(", ")
+}
+
+/// `0x1234…abcd`
+fn short_address(address: &str) -> String {
+    match address.len() > 12 {
+        true => format!("{}…{}", &address[..6], &address[address.len() - 4..]),
+        false => address.to_string(),
+    }
+}
+
+/// A size or gas figure, or `-` when it wasn't recorded
+fn or_dash(value: Option<i64>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+/// Primary ENS names of the deployers, keyed by address as stored
+///
+/// Lookup failures are reported once and leave the addresses unnamed.
+async fn deployer_names(db: &Database, deployments: &[DeploymentView]) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    let ens_network = match SmolderConfig::load() {
+        Ok(config) => config.ens.network,
+        Err(e) => {
+            status!("{} ENS names unavailable: {}", style("!").yellow(), e);
+            return names;
+        }
+    };
+    let resolver = match EnsResolver::new(db, None, ens_network.as_deref()).await {
+        Ok(resolver) => resolver,
+        Err(e) => {
+            status!("{} ENS names unavailable: {}", style("!").yellow(), e);
+            return names;
+        }
+    };
+
+    let deployers: HashSet<&String> = deployments.iter().map(|d| &d.deployer).collect();
+    for deployer in deployers {
+        let Ok(address) = deployer.parse::<Address>() else {
+            continue;
+        };
+        match resolver.lookup(address).await {
+            Ok(Some(name)) => {
+                names.insert(deployer.clone(), name);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                status!("{} ENS lookup failed: {}", style("!").yellow(), e);
+                break;
+            }
+        }
+    }
+    names
 }
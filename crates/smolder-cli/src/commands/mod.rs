@@ -3,14 +3,42 @@
 use clap::Subcommand;
 use color_eyre::eyre::Result;
 
+pub mod batch;
+pub mod completions;
+pub mod costs;
+pub mod db;
+pub mod decode;
 pub mod deploy;
+pub mod deploy_multi;
+pub mod doctor;
+pub mod env;
 pub mod export;
+pub mod gas_report;
 pub mod get;
+pub mod history;
+pub mod import;
 pub mod init;
+pub mod inspect;
+pub mod jobs;
+pub mod label;
 pub mod list;
+pub mod network;
+pub mod pin;
+pub mod pull;
+pub mod push;
+pub mod raw;
+pub mod remove;
+pub mod roles;
 pub mod serve;
+pub mod sign_typed;
+pub mod storage;
 pub mod sync;
+pub mod template;
+pub mod token;
+pub mod tx;
+pub mod upgrade;
 pub mod wallet;
+pub mod webhook;
 
 /// All available CLI commands
 #[derive(Subcommand)]
@@ -21,15 +49,84 @@ pub enum Command {
     /// Deploy contracts via forge script and track in database
     Deploy(deploy::DeployCommand),
 
+    /// Deploy one artifact to several networks as a group
+    DeployMulti(deploy_multi::DeployMultiCommand),
+
+    /// Deploy a template, setting its parameters by name
+    DeployTemplate(template::DeployTemplateCommand),
+
+    /// Deploy a new implementation and upgrade a proxy to it
+    Upgrade(upgrade::UpgradeCommand),
+
     /// List all deployments
     List(list::ListCommand),
 
     /// Get the address of a deployed contract
     Get(get::GetCommand),
 
+    /// Show a deployment's recorded details next to its live state
+    Inspect(inspect::InspectCommand),
+
+    /// Remove (or archive) a tracked deployment
+    Remove(remove::RemoveCommand),
+
+    /// Pin a contract, or one of its deployments, to the top of listings
+    Pin(pin::PinCommand),
+
+    /// Unpin a contract or deployment
+    Unpin(pin::PinCommand),
+
+    /// Set a display label and description on a contract or deployment
+    Label(label::LabelCommand),
+
     /// Export deployments to various formats
     Export(export::ExportCommand),
 
+    /// Print a network's contract addresses as `export` statements
+    Env(env::EnvCommand),
+
+    /// Import deployments from a hardhat-deploy or smolder JSON manifest
+    Import(import::ImportCommand),
+
+    /// Publish networks and deployments to another smolder server
+    Push(push::PushCommand),
+
+    /// Fetch networks and deployments from another smolder server
+    Pull(pull::PullCommand),
+
+    /// Call a function on any address from a human-readable signature
+    RawCall(raw::RawCallCommand),
+
+    /// Send a transaction to any address from a human-readable signature
+    RawSend(raw::RawSendCommand),
+
+    /// Send a sequence of contract calls from a plan file
+    Batch(batch::BatchCommand),
+
+    /// Inspect the history of contract interactions
+    History(history::HistoryCommand),
+
+    /// Report the gas used per function of each contract
+    GasReport(gas_report::GasReportCommand),
+
+    /// Report the fees paid per network, contract and wallet
+    Costs(costs::CostsCommand),
+
+    /// Read a contract's storage slots
+    Storage(storage::StorageCommand),
+
+    /// Identify the function calldata calls and decode its parameters
+    Decode(decode::DecodeCommand),
+
+    /// Inspect transactions sent outside smolder
+    Tx(tx::TxCommand),
+
+    /// Show the owner and role holders of a contract
+    Roles(roles::RolesCommand),
+
+    /// Sign EIP-712 typed data with a stored wallet
+    SignTyped(sign_typed::SignTypedCommand),
+
     /// Start the web server for the dashboard UI
     Serve(serve::ServeCommand),
 
@@ -38,6 +135,30 @@ pub enum Command {
 
     /// Manage wallets for signing transactions
     Wallet(wallet::WalletCommand),
+
+    /// Manage deployment templates
+    Template(template::TemplateCommand),
+
+    /// Manage webhooks notified by the server
+    Webhook(webhook::WebhookCommand),
+
+    /// Show background jobs run by the server
+    Jobs(jobs::JobsCommand),
+
+    /// Manage API tokens accepted by the server
+    Token(token::TokenCommand),
+
+    /// Manage networks tracked in the registry
+    Network(network::NetworkCommand),
+
+    /// Back up and restore the registry database
+    Db(db::DbCommand),
+
+    /// Check the toolchain, build output, database, networks and wallets
+    Doctor(doctor::DoctorCommand),
+
+    /// Generate shell completion scripts
+    Completions(completions::CompletionsCommand),
 }
 
 impl Command {
@@ -46,12 +167,43 @@ impl Command {
         match self {
             Command::Init(cmd) => cmd.run().await,
             Command::Deploy(cmd) => cmd.run().await,
+            Command::DeployMulti(cmd) => cmd.run().await,
+            Command::DeployTemplate(cmd) => cmd.run().await,
+            Command::Upgrade(cmd) => cmd.run().await,
             Command::List(cmd) => cmd.run().await,
             Command::Get(cmd) => cmd.run().await,
+            Command::Inspect(cmd) => cmd.run().await,
+            Command::Remove(cmd) => cmd.run().await,
+            Command::Pin(cmd) => cmd.run(true).await,
+            Command::Unpin(cmd) => cmd.run(false).await,
+            Command::Label(cmd) => cmd.run().await,
             Command::Export(cmd) => cmd.run().await,
+            Command::Env(cmd) => cmd.run().await,
+            Command::Import(cmd) => cmd.run().await,
+            Command::Push(cmd) => cmd.run().await,
+            Command::Pull(cmd) => cmd.run().await,
+            Command::RawCall(cmd) => cmd.run().await,
+            Command::RawSend(cmd) => cmd.run().await,
+            Command::Batch(cmd) => cmd.run().await,
+            Command::History(cmd) => cmd.run().await,
+            Command::GasReport(cmd) => cmd.run().await,
+            Command::Costs(cmd) => cmd.run().await,
+            Command::Storage(cmd) => cmd.run().await,
+            Command::Decode(cmd) => cmd.run().await,
+            Command::Tx(cmd) => cmd.run().await,
+            Command::Roles(cmd) => cmd.run().await,
+            Command::SignTyped(cmd) => cmd.run().await,
             Command::Serve(cmd) => cmd.run().await,
             Command::Sync(cmd) => cmd.run().await,
             Command::Wallet(cmd) => cmd.run().await,
+            Command::Template(cmd) => cmd.run().await,
+            Command::Webhook(cmd) => cmd.run().await,
+            Command::Jobs(cmd) => cmd.run().await,
+            Command::Token(cmd) => cmd.run().await,
+            Command::Network(cmd) => cmd.run().await,
+            Command::Db(cmd) => cmd.run().await,
+            Command::Doctor(cmd) => cmd.run().await,
+            Command::Completions(cmd) => cmd.run().await,
         }
     }
 }
@@ -0,0 +1,560 @@
+//! Manage networks tracked in the registry
+
+use std::time::Instant;
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+use serde::Serialize;
+use smolder_core::chains::{self, KnownChain};
+use smolder_core::{redact_url, Currency};
+use smolder_db::{
+    AuditRepository, ChainId, Database, DeploymentFilter, DeploymentRepository, NetworkRepository,
+    NewNetwork,
+};
+
+use crate::locks::Clearance;
+use crate::output::{self, status};
+use crate::relay;
+use crate::rpc::get_chain_id;
+
+/// Manage networks tracked in the registry
+#[derive(Args)]
+pub struct NetworkCommand {
+    #[command(subcommand)]
+    pub command: NetworkSubcommand,
+}
+
+impl NetworkCommand {
+    pub async fn run(self) -> Result<()> {
+        self.command.run().await
+    }
+}
+
+#[derive(Subcommand)]
+pub enum NetworkSubcommand {
+    /// Add a network, or update it if it already exists
+    Add(AddNetworkCommand),
+
+    /// List all networks
+    List(ListNetworksCommand),
+
+    /// Remove a network
+    Remove(RemoveNetworkCommand),
+
+    /// Check that a network's RPC is reachable and reports the expected chain ID
+    Test(TestNetworkCommand),
+
+    /// Lock a network so deploys and sends on it need `--unlock-confirm <name>`
+    Lock(LockNetworkCommand),
+
+    /// Unlock a network
+    Unlock(UnlockNetworkCommand),
+
+    /// Show recent locks, unlocks and operations confirmed on locked networks
+    Audit(AuditCommand),
+}
+
+impl NetworkSubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Add(cmd) => cmd.run().await,
+            Self::List(cmd) => cmd.run().await,
+            Self::Remove(cmd) => cmd.run().await,
+            Self::Test(cmd) => cmd.run().await,
+            Self::Lock(cmd) => set_locked(&cmd.name, true).await,
+            Self::Unlock(cmd) => set_locked(&cmd.name, false).await,
+            Self::Audit(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Add a network, or update it if it already exists
+///
+/// Well-known chains are looked up by `--chain-id` or name, filling in the
+/// explorer and, with `--public-rpc`, the RPC.
+#[derive(Args)]
+pub struct AddNetworkCommand {
+    /// Network name (unique identifier); defaults to the name of the chain
+    /// given with `--chain-id`
+    pub name: Option<String>,
+
+    /// RPC endpoint URL
+    #[arg(long, required_unless_present = "public_rpc")]
+    pub rpc_url: Option<String>,
+
+    /// Use a public RPC of the chain from the built-in chain list
+    #[arg(long, conflicts_with = "rpc_url")]
+    pub public_rpc: bool,
+
+    /// Chain ID (fetched from the RPC if omitted)
+    #[arg(long)]
+    pub chain_id: Option<u64>,
+
+    /// Block explorer URL; the chain's known explorer when omitted
+    #[arg(long)]
+    pub explorer: Option<String>,
+
+    /// Allow sends from any address without a wallet (anvil and other nodes
+    /// with `anvil_impersonateAccount`). Kept as is when omitted
+    #[arg(long)]
+    pub impersonation: Option<bool>,
+
+    /// Hold deploys and sends through the API until a second API token
+    /// approves them. Kept as is when omitted
+    #[arg(long)]
+    pub protected: Option<bool>,
+
+    /// Submit signed transactions through a private relay instead of the
+    /// public mempool: `flashbots`, `mev-blocker`, a relay RPC URL, or `none`.
+    /// Kept as is when omitted
+    #[arg(long, value_name = "RELAY")]
+    pub private_relay: Option<String>,
+
+    /// Symbol of the native currency, accepted as a unit in values and used
+    /// to display balances. Defaults to the known chain's, else ETH; kept as
+    /// is when omitted
+    #[arg(long, value_name = "SYMBOL")]
+    pub currency: Option<String>,
+
+    /// Decimals of the native currency. Kept as is when omitted
+    #[arg(long, value_name = "N", requires = "currency")]
+    pub decimals: Option<u8>,
+
+    /// Blocks deep a deploy or call must be before `serve` stops checking it
+    /// for reorgs (12 by default). Kept as is when omitted
+    #[arg(long, value_name = "BLOCKS")]
+    pub reorg_depth: Option<u32>,
+
+    /// Blocks a transaction must be buried under, counting its own, before
+    /// `serve` marks a call successful and its record confirmed (1 by
+    /// default). Kept as is when omitted
+    #[arg(long, value_name = "BLOCKS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub confirmations: Option<u32>,
+}
+
+impl AddNetworkCommand {
+    pub async fn run(self) -> Result<()> {
+        let private_relay = self
+            .private_relay
+            .as_deref()
+            .map(relay::parse)
+            .transpose()?;
+        let db = Database::connect().await?;
+
+        let lookup = match (self.chain_id, &self.name) {
+            (Some(id), _) => chains::by_chain_id(id),
+            (None, Some(name)) => chains::by_name(name),
+            (None, None) => None,
+        };
+        let name = match (&self.name, lookup) {
+            (Some(name), _) => name.clone(),
+            (None, Some(chain)) => chain.name.to_string(),
+            (None, None) => {
+                return Err(eyre!(
+                    "Name the network, or pass the --chain-id of a well-known chain"
+                ))
+            }
+        };
+
+        let (rpc_url, chain_id) = match (&self.rpc_url, lookup) {
+            (Some(rpc_url), _) => {
+                let chain_id = match self.chain_id {
+                    Some(id) => id,
+                    None => {
+                        status!(
+                            "{} Fetching chain ID from {}...",
+                            style("->").blue(),
+                            style(redact_url(rpc_url)).dim()
+                        );
+                        get_chain_id(rpc_url).await.map_err(|e| {
+                            eyre!("Could not fetch chain ID: {}. Pass --chain-id.", e)
+                        })?
+                    }
+                };
+                (rpc_url.clone(), chain_id)
+            }
+            (None, Some(chain)) => (public_rpc(chain).await?, chain.chain_id),
+            (None, None) => {
+                return Err(eyre!("No public RPC known for '{}'; pass --rpc-url", name))
+            }
+        };
+        let known = chains::by_chain_id(chain_id);
+
+        let existing = NetworkRepository::get_by_name(&db, &name).await?;
+
+        let mut network = NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name,
+                chain_id: ChainId::from(chain_id),
+                rpc_url,
+                explorer_url: self
+                    .explorer
+                    .clone()
+                    .or_else(|| known?.explorer_url.map(str::to_string)),
+            },
+        )
+        .await?;
+        if let Some(enabled) = self.impersonation {
+            NetworkRepository::set_impersonation(&db, &network.name, enabled).await?;
+            network.impersonation = enabled;
+        }
+        if let Some(protected) = self.protected {
+            NetworkRepository::set_protected(&db, &network.name, protected).await?;
+            network.protected = protected;
+        }
+        if let Some(relay_url) = private_relay {
+            NetworkRepository::set_private_relay(&db, &network.name, relay_url.as_deref()).await?;
+            network.private_relay = relay_url;
+        }
+        if let Some(symbol) = &self.currency {
+            let currency = Currency {
+                symbol,
+                decimals: self.decimals.unwrap_or(network.currency_decimals),
+            };
+            NetworkRepository::set_currency(&db, &network.name, currency).await?;
+            network.currency_decimals = currency.decimals;
+            network.currency_symbol = symbol.clone();
+        }
+        if let Some(depth) = self.reorg_depth {
+            NetworkRepository::set_reorg_depth(&db, &network.name, depth).await?;
+            network.reorg_depth = depth;
+        }
+        if let Some(confirmations) = self.confirmations {
+            NetworkRepository::set_confirmations(&db, &network.name, confirmations).await?;
+            network.confirmations = confirmations;
+        }
+
+        if output::is_json() {
+            return output::print_json(&network);
+        }
+
+        let action = if existing.is_some() {
+            "updated"
+        } else {
+            "added"
+        };
+        status!(
+            "{} Network '{}' {} (chain ID: {})",
+            style("*").green().bold(),
+            style(&network.name).cyan(),
+            action,
+            network.chain_id
+        );
+        status!(
+            "   Native currency: {} ({} decimals)",
+            network.currency_symbol,
+            network.currency_decimals
+        );
+        if self.public_rpc {
+            status!(
+                "   {} Public RPCs are rate limited; switch to your own with --rpc-url",
+                style("!").yellow()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// First public RPC of `chain` that answers with its chain ID
+async fn public_rpc(chain: &KnownChain) -> Result<String> {
+    for rpc_url in chain.rpc_urls {
+        status!(
+            "{} Trying public RPC {}...",
+            style("->").blue(),
+            style(rpc_url).dim()
+        );
+        match get_chain_id(rpc_url).await {
+            Ok(id) if id == chain.chain_id => return Ok(rpc_url.to_string()),
+            Ok(id) => status!("   {} Reports chain ID {}", style("!").yellow(), id),
+            Err(e) => status!("   {} {}", style("!").yellow(), e),
+        }
+    }
+    Err(eyre!(
+        "No public RPC of '{}' is reachable; pass --rpc-url",
+        chain.name
+    ))
+}
+
+/// List all networks
+#[derive(Args)]
+pub struct ListNetworksCommand;
+
+impl ListNetworksCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let networks = NetworkRepository::list(&db).await?;
+
+        if output::is_json() {
+            return output::print_json(&networks);
+        }
+
+        if networks.is_empty() {
+            status!("{} No networks found", style("!").yellow());
+            status!();
+            status!(
+                "   Add a network with: {}",
+                style("smolder network add <name> --rpc-url <url>").cyan()
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{:<20} {:<12} {:<50} {:<40}",
+            "Network", "Chain ID", "RPC URL", "Explorer"
+        );
+        println!("{}", "-".repeat(122));
+
+        for n in &networks {
+            let name = match n.locked {
+                true => format!("{} (locked)", n.name),
+                false => n.name.clone(),
+            };
+            println!(
+                "{:<20} {:<12} {:<50} {:<40}",
+                name,
+                n.chain_id,
+                n.rpc_url,
+                n.explorer_url.as_deref().unwrap_or("-")
+            );
+        }
+
+        status!();
+        status!("Total: {} network(s)", networks.len());
+
+        Ok(())
+    }
+}
+
+/// Remove a network
+#[derive(Args)]
+pub struct RemoveNetworkCommand {
+    /// Network name to remove
+    pub name: String,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RemoveNetworkCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+
+        let network = NetworkRepository::get_by_name(&db, &self.name)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.name))?;
+
+        // Deployments reference their network, archived ones too, so refuse
+        // to orphan them
+        let deployments = DeploymentRepository::list(
+            &db,
+            DeploymentFilter {
+                network: Some(self.name.clone()),
+                include_archived: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        if !deployments.is_empty() {
+            return Err(eyre!(
+                "Network '{}' still has {} deployment(s) tracked. Remove them first.",
+                self.name,
+                deployments.len()
+            ));
+        }
+
+        if !self.force {
+            status!(
+                "{} About to remove network '{}'",
+                style("!").yellow(),
+                style(&self.name).cyan()
+            );
+            status!("   Chain ID: {}", network.chain_id);
+            status!("   RPC URL:  {}", network.rpc_url);
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt("Are you sure you want to remove this network?")
+                .default(false)
+                .interact()?;
+
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        NetworkRepository::delete(&db, &self.name).await?;
+
+        if output::is_json() {
+            return output::print_json(&network);
+        }
+
+        status!(
+            "{} Network '{}' removed",
+            style("*").green().bold(),
+            style(&self.name).cyan()
+        );
+
+        Ok(())
+    }
+}
+
+/// Lock a network so deploys and sends on it need `--unlock-confirm <name>`
+#[derive(Args)]
+pub struct LockNetworkCommand {
+    /// Network name to lock
+    pub name: String,
+}
+
+/// Unlock a network
+#[derive(Args)]
+pub struct UnlockNetworkCommand {
+    /// Network name to unlock
+    pub name: String,
+}
+
+async fn set_locked(name: &str, locked: bool) -> Result<()> {
+    let db = Database::connect().await?;
+    let network = NetworkRepository::get_by_name(&db, name)
+        .await?
+        .ok_or_else(|| eyre!("Network '{}' not found", name))?;
+
+    let changed = Clearance::cli([]).set_locked(&db, &network, locked).await?;
+
+    if output::is_json() {
+        let network = NetworkRepository::get_by_name(&db, name)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", name))?;
+        return output::print_json(&network);
+    }
+
+    let state = if locked { "locked" } else { "unlocked" };
+    if changed {
+        status!(
+            "{} Network '{}' {}",
+            style("*").green().bold(),
+            style(name).cyan(),
+            state
+        );
+    } else {
+        status!(
+            "{} Network '{}' is already {}",
+            style("*").dim(),
+            style(name).cyan(),
+            state
+        );
+    }
+    Ok(())
+}
+
+/// Show recent locks, unlocks and operations confirmed on locked networks
+#[derive(Args)]
+pub struct AuditCommand {
+    /// Number of entries to show
+    #[arg(long, default_value = "50")]
+    pub limit: i64,
+}
+
+impl AuditCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let entries = AuditRepository::list(&db, self.limit).await?;
+
+        if output::is_json() {
+            return output::print_json(&entries);
+        }
+
+        if entries.is_empty() {
+            status!("{} No audit entries", style("!").yellow());
+            return Ok(());
+        }
+
+        println!(
+            "{:<20} {:<10} {:<20} {:<6} {:<16} Detail",
+            "Time", "Action", "Network", "Via", "Actor"
+        );
+        println!("{}", "-".repeat(100));
+        for entry in &entries {
+            println!(
+                "{:<20} {:<10} {:<20} {:<6} {:<16} {}",
+                entry.created_at,
+                entry.action,
+                entry.target,
+                entry.source,
+                entry.actor.as_deref().unwrap_or("-"),
+                entry.detail.as_deref().unwrap_or("")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Result of a network check, printed with `--json`
+#[derive(Serialize)]
+struct NetworkTestReport {
+    network: String,
+    chain_id: u64,
+    latency_ms: u128,
+}
+
+/// Check that a network's RPC is reachable and reports the expected chain ID
+#[derive(Args)]
+pub struct TestNetworkCommand {
+    /// Network name to test
+    pub name: String,
+}
+
+impl TestNetworkCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+
+        let network = NetworkRepository::get_by_name(&db, &self.name)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.name))?;
+
+        status!(
+            "{} Pinging {}...",
+            style("->").blue(),
+            style(&network.rpc_url).dim()
+        );
+
+        let started = Instant::now();
+        let chain_id = get_chain_id(network.rpc_url.expose())
+            .await
+            .map_err(|e| eyre!("RPC for '{}' is unreachable: {}", self.name, e))?;
+        let latency = started.elapsed();
+
+        status!(
+            "   {} RPC responded in {}ms",
+            style("*").green(),
+            latency.as_millis()
+        );
+
+        if ChainId::from(chain_id) != network.chain_id {
+            return Err(eyre!(
+                "Chain ID mismatch for '{}': registry has {}, RPC reports {}",
+                self.name,
+                network.chain_id,
+                chain_id
+            ));
+        }
+
+        if output::is_json() {
+            return output::print_json(&NetworkTestReport {
+                network: network.name,
+                chain_id,
+                latency_ms: latency.as_millis(),
+            });
+        }
+
+        status!("   {} Chain ID matches ({})", style("*").green(), chain_id);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! Pin contracts and deployments to the top of listings
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+
+use smolder_db::{ContractRepository, Database, DeploymentRepository, DeploymentView};
+
+use crate::output::{self, status};
+
+/// A contract, or one of its deployments with `--network`
+#[derive(Args)]
+pub struct PinCommand {
+    /// Contract name
+    pub contract: String,
+
+    /// Only the contract's deployment on this network
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// With --network, this version rather than the current one
+    #[arg(long = "version", value_name = "N", requires = "network")]
+    pub contract_version: Option<i64>,
+}
+
+impl PinCommand {
+    /// Pin the contract or deployment, or unpin it when `pinned` is false
+    pub async fn run(self, pinned: bool) -> Result<()> {
+        let db = Database::connect().await?;
+        let verb = if pinned { "pinned" } else { "unpinned" };
+
+        let Some(network) = &self.network else {
+            ContractRepository::set_pinned(&db, &self.contract, pinned).await?;
+            if output::is_json() {
+                return output::print_json(&serde_json::json!({
+                    "contract": self.contract,
+                    "pinned": pinned,
+                }));
+            }
+            status!(
+                "{} Contract {} {}",
+                style("*").green().bold(),
+                style(&self.contract).cyan(),
+                verb
+            );
+            return Ok(());
+        };
+
+        let deployment =
+            find_deployment(&db, &self.contract, network, self.contract_version).await?;
+        DeploymentRepository::set_pinned(&db, deployment.id, pinned).await?;
+
+        if output::is_json() {
+            let view = DeploymentRepository::get_view_by_id(&db, deployment.id).await?;
+            return output::print_json(&view);
+        }
+        status!(
+            "{} {} v{} on {} {}",
+            style("*").green().bold(),
+            style(&self.contract).cyan(),
+            deployment.version,
+            style(network).cyan(),
+            verb
+        );
+        Ok(())
+    }
+}
+
+/// A contract's current deployment on a network, or the given version of it
+pub(crate) async fn find_deployment(
+    db: &Database,
+    contract: &str,
+    network: &str,
+    version: Option<i64>,
+) -> Result<DeploymentView> {
+    DeploymentRepository::list_versions(db, contract, network)
+        .await?
+        .into_iter()
+        .find(|d| match version {
+            Some(version) => d.version == version,
+            None => d.is_current,
+        })
+        .ok_or_else(|| {
+            eyre!(
+                "No deployment found for contract '{}' on network '{}'",
+                contract,
+                network
+            )
+        })
+}
@@ -0,0 +1,48 @@
+//! Fetch deployments from another smolder server into the local registry
+
+use clap::Args;
+use color_eyre::eyre::Result;
+use console::style;
+use smolder_db::Database;
+
+use super::push::{print_report, token};
+use crate::commands::db::auto_backup;
+use crate::output::status;
+use crate::registry_sync::{self, Remote};
+
+/// Fetch networks and deployments from another smolder server
+#[derive(Args)]
+pub struct PullCommand {
+    /// Base URL of the remote server, e.g. `https://smolder.example.com`
+    pub remote: String,
+
+    /// Only pull these networks (repeatable); all by default
+    #[arg(long)]
+    pub network: Vec<String>,
+
+    /// API token of the remote [env: SMOLDER_TOKEN]
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+impl PullCommand {
+    pub async fn run(self) -> Result<()> {
+        let remote = Remote::new(&self.remote, token(self.token))?;
+        let bundle = remote.pull(&self.network).await?;
+
+        status!(
+            "{} Pulled {} deployment(s) on {} network(s) from {}",
+            style("->").blue(),
+            bundle.deployments.len(),
+            bundle.networks.len(),
+            style(&self.remote).cyan()
+        );
+
+        let db = Database::connect().await?;
+        if !bundle.deployments.is_empty() {
+            auto_backup(&db, "pull").await?;
+        }
+        let report = registry_sync::merge(&db, &bundle).await?;
+        print_report(&report)
+    }
+}
@@ -0,0 +1,90 @@
+//! Publish the local registry to another smolder server
+
+use clap::Args;
+use color_eyre::eyre::Result;
+use console::style;
+use smolder_db::Database;
+
+use crate::output::{self, status};
+use crate::registry_sync::{self, Remote, SyncReport};
+
+/// Environment variable read for the remote's API token when `--token` is
+/// not given
+pub const TOKEN_ENV: &str = "SMOLDER_TOKEN";
+
+/// Publish networks and deployments to another smolder server
+#[derive(Args)]
+pub struct PushCommand {
+    /// Base URL of the remote server, e.g. `https://smolder.example.com`
+    pub remote: String,
+
+    /// Only push these networks (repeatable); all by default
+    #[arg(long)]
+    pub network: Vec<String>,
+
+    /// API token of the remote, with the operator role [env: SMOLDER_TOKEN]
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+impl PushCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let bundle = registry_sync::bundle(&db, &self.network).await?;
+
+        status!(
+            "{} Pushing {} deployment(s) on {} network(s) to {}",
+            style("->").blue(),
+            bundle.deployments.len(),
+            bundle.networks.len(),
+            style(&self.remote).cyan()
+        );
+
+        let remote = Remote::new(&self.remote, token(self.token))?;
+        let report = remote.push(&bundle).await?;
+        print_report(&report)
+    }
+}
+
+/// The token given with `--token`, or from [`TOKEN_ENV`]
+pub(super) fn token(flag: Option<String>) -> Option<String> {
+    flag.or_else(|| std::env::var(TOKEN_ENV).ok())
+        .filter(|t| !t.is_empty())
+}
+
+/// Print what a push or pull did to the receiving registry
+pub(super) fn print_report(report: &SyncReport) -> Result<()> {
+    if output::is_json() {
+        return output::print_json(report);
+    }
+
+    for network in &report.networks_added {
+        status!(
+            "   {} Registered network {}",
+            style("*").dim(),
+            style(network).cyan()
+        );
+    }
+    for conflict in &report.conflicts {
+        status!(
+            "   {} {} on {} at {}: {}",
+            style("!").yellow(),
+            style(&conflict.contract).cyan(),
+            conflict.network,
+            style(&conflict.address).yellow(),
+            conflict.reason
+        );
+    }
+
+    status!();
+    status!(
+        "{} Added {}, updated {}, skipped {} already tracked, {} conflict(s)",
+        style("*").green().bold(),
+        report.added,
+        report.updated,
+        report.skipped,
+        report.conflicts.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,324 @@
+//! Call or send to an arbitrary address from a human-readable signature
+//!
+//! For poking contracts that aren't tracked in the registry, in the spirit of
+//! `cast call` / `cast send`. Afterwards the address can be saved into the
+//! registry with an ABI made from the signature.
+
+use alloy::json_abi::Function;
+use alloy::primitives::{Address, Bytes};
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::{Confirm, Input};
+use smolder_core::{
+    decode_function_result, decrypt_private_key, encode_function_call, format_value,
+    parse_function_signature, parse_value, Abi, BytecodeHash,
+};
+use smolder_db::{
+    Database, DeploymentRepository, ImportStatus, Network, NetworkRepository, NewContract,
+    NewContractDeployment, WalletRepository,
+};
+
+use crate::config::SmolderConfig;
+use crate::ens;
+use crate::interact::param_to_json;
+use crate::locks::Clearance;
+use crate::output::{self, status};
+use crate::policy::{self, Intent};
+use crate::rpc::{self, Outgoing};
+
+/// Call a view function on any address with `eth_call`
+#[derive(Args)]
+pub struct RawCallCommand {
+    /// Contract address or ENS name
+    pub address: String,
+
+    /// Function signature, e.g. "balanceOf(address)(uint256)"
+    pub sig: String,
+
+    /// Function arguments (arrays and tuples as JSON)
+    pub args: Vec<String>,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Save the address into the registry under this contract name
+    #[arg(long, value_name = "NAME")]
+    pub save: Option<String>,
+}
+
+/// Sign and send a transaction to any address with a stored wallet
+#[derive(Args)]
+pub struct RawSendCommand {
+    /// Contract address or ENS name
+    pub address: String,
+
+    /// Function signature, e.g. "transfer(address,uint256)"
+    pub sig: String,
+
+    /// Function arguments (arrays and tuples as JSON)
+    pub args: Vec<String>,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Wallet to sign with
+    #[arg(long)]
+    pub wallet: String,
+
+    /// Value to send: wei, or with a unit ("0.1 ether", "0.1 ETH", "30 gwei")
+    #[arg(long)]
+    pub value: Option<String>,
+
+    /// Save the address into the registry under this contract name
+    #[arg(long, value_name = "NAME")]
+    pub save: Option<String>,
+
+    /// Confirm a send on a locked network by repeating its name
+    #[arg(long, value_name = "NETWORK")]
+    pub unlock_confirm: Option<String>,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RawCallCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let network = get_network(&db, &self.network).await?;
+        let ens_network = SmolderConfig::load()?.ens.network;
+        let address =
+            ens::resolve_address(&db, &network, ens_network.as_deref(), &self.address).await?;
+        let function = parse_function_signature(&self.sig)?;
+        let call_data =
+            encode_args(&db, &network, ens_network.as_deref(), &function, &self.args).await?;
+
+        let data = rpc::execute_eth_call(network.rpc_url.expose(), address, call_data).await?;
+        let result = decode_function_result(&function, &data)?;
+
+        if output::is_json() {
+            output::print_json(&serde_json::json!({
+                "address": address.to_string(),
+                "function": function.signature(),
+                "result": result,
+                "raw": data.to_string(),
+            }))?;
+        } else if function.outputs.is_empty() {
+            // Without declared outputs there is nothing to decode into
+            println!("{}", data);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+
+        offer_save(&db, &network, address, &function, self.save).await
+    }
+}
+
+impl RawSendCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let network = get_network(&db, &self.network).await?;
+        let ens_network = SmolderConfig::load()?.ens.network;
+        let address =
+            ens::resolve_address(&db, &network, ens_network.as_deref(), &self.address).await?;
+        let function = parse_function_signature(&self.sig)?;
+        let call_data =
+            encode_args(&db, &network, ens_network.as_deref(), &function, &self.args).await?;
+
+        let wallet = WalletRepository::get_with_key(&db, &self.wallet)
+            .await?
+            .ok_or_else(|| eyre!("Wallet '{}' not found", self.wallet))?;
+
+        let value = self
+            .value
+            .as_deref()
+            .map(|v| parse_value(v, network.currency()))
+            .transpose()?
+            .filter(|v| !v.is_zero());
+        let preflight = rpc::preflight(
+            &network,
+            Outgoing {
+                from: *wallet.address,
+                to: Some(address),
+                data: &call_data,
+                value,
+            },
+        )
+        .await?;
+        for warning in &preflight.warnings {
+            status!("{} {}", style("!").yellow(), warning.message);
+        }
+
+        if !self.force {
+            status!(
+                "{} About to send {} to {} on {}",
+                style("!").yellow(),
+                style(function.signature()).cyan(),
+                style(address).yellow(),
+                style(&network.name).cyan()
+            );
+            status!("   From:  {} ({})", wallet.name, wallet.address);
+            if let Some(value) = value {
+                status!("   Value: {}", format_value(value, network.currency()));
+            }
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt("Send this transaction?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        Clearance::cli(self.unlock_confirm)
+            .check(
+                &db,
+                &network,
+                &format!("send {} to {}", function.signature(), address),
+            )
+            .await?;
+        let signature = function.signature();
+        let intent = Intent::call(&network, address, None, &signature, value);
+        policy::check(&db, wallet.id, &wallet.name, &intent).await?;
+        let private_key = decrypt_private_key(&wallet.encrypted_key)?;
+        let reservation = policy::reserve(&db, wallet.id, &wallet.name, &intent).await?;
+        let sent = rpc::execute_transaction(
+            network.rpc_url.expose(),
+            network.private_relay.as_deref(),
+            &private_key,
+            address,
+            call_data,
+            value,
+        )
+        .await;
+        let tx_hash = reservation.settle(&db, sent).await?;
+
+        if output::is_json() {
+            output::print_json(&serde_json::json!({
+                "address": address.to_string(),
+                "function": function.signature(),
+                "tx_hash": tx_hash,
+            }))?;
+        } else if output::is_verbose() {
+            println!(
+                "{} Transaction sent: {}",
+                style("*").green().bold(),
+                style(&tx_hash).yellow()
+            );
+        } else {
+            println!("{}", tx_hash);
+        }
+
+        offer_save(&db, &network, address, &function, self.save).await
+    }
+}
+
+async fn get_network(db: &Database, name: &str) -> Result<Network> {
+    NetworkRepository::get_by_name(db, name)
+        .await?
+        .ok_or_else(|| eyre!("Network '{}' not found", name))
+}
+
+/// Encode a call to `function` from command-line arguments, resolving ENS
+/// names given for addresses
+pub async fn encode_args(
+    db: &Database,
+    network: &Network,
+    ens_network: Option<&str>,
+    function: &Function,
+    args: &[String],
+) -> Result<Bytes> {
+    if args.len() != function.inputs.len() {
+        return Err(eyre!(
+            "{} takes {} arguments, got {}",
+            function.signature(),
+            function.inputs.len(),
+            args.len()
+        ));
+    }
+
+    let mut params: Vec<serde_json::Value> = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| param_to_json(&param.selector_type(), arg))
+        .collect();
+    ens::resolve_params(db, network, ens_network, &function.inputs, &mut params).await?;
+
+    Ok(encode_function_call(function, &params)?)
+}
+
+/// Record an untracked address in the registry, with `--save` or after asking
+async fn offer_save(
+    db: &Database,
+    network: &Network,
+    address: Address,
+    function: &Function,
+    name: Option<String>,
+) -> Result<()> {
+    if DeploymentRepository::exists_by_address(db, network.id, &address.to_string()).await? {
+        return Ok(());
+    }
+
+    let name = match name {
+        Some(name) => name,
+        // Only prompt in an interactive text session
+        None if output::is_verbose() => {
+            status!();
+            let save = Confirm::new()
+                .with_prompt(format!("{} is not in the registry. Save it?", address))
+                .default(false)
+                .interact()?;
+            if !save {
+                return Ok(());
+            }
+            Input::new().with_prompt("Contract name").interact_text()?
+        }
+        None => return Ok(()),
+    };
+
+    let abi = Abi::from_human_readable([function.full_signature()])?.to_json()?;
+
+    let statuses = DeploymentRepository::import_batch(
+        db,
+        network.id,
+        &[NewContractDeployment {
+            contract: NewContract {
+                name: name.clone(),
+                source_path: String::new(),
+                bytecode_hash: BytecodeHash::of(&abi),
+                storage_layout: None,
+                compiler: None,
+                abi,
+            },
+            address: address.into(),
+            deployer: String::new(),
+            tx_hash: None,
+            block_number: None,
+            constructor_args: None,
+            initcode_size: None,
+            runtime_size: None,
+            gas_used: None,
+            gas_price: None,
+        }],
+    )
+    .await?;
+
+    if statuses.first() == Some(&ImportStatus::Added) {
+        status!(
+            "{} Saved {} on {} at {}",
+            style("+").green(),
+            style(&name).cyan(),
+            style(&network.name).cyan(),
+            style(&address).yellow()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Remove a tracked deployment from the registry
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+
+use smolder_db::{Database, DeploymentRepository};
+
+use crate::commands::db::auto_backup;
+use crate::output::{self, status};
+
+/// Remove the current deployment of a contract on a network
+#[derive(Args)]
+pub struct RemoveCommand {
+    /// Contract name
+    pub contract: String,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Archive the deployment instead of deleting it (keeps it for auditing)
+    #[arg(long)]
+    pub archive: bool,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RemoveCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+
+        let deployment = DeploymentRepository::get_current(&db, &self.contract, &self.network)
+            .await?
+            .ok_or_else(|| {
+                eyre!(
+                    "No deployment found for contract '{}' on network '{}'",
+                    self.contract,
+                    self.network
+                )
+            })?;
+
+        let action = if self.archive { "archive" } else { "delete" };
+
+        if !self.force {
+            status!(
+                "{} About to {} {} v{} on {}",
+                style("!").yellow(),
+                action,
+                style(&self.contract).cyan(),
+                deployment.version,
+                style(&self.network).cyan()
+            );
+            status!("   Address: {}", style(&deployment.address).yellow());
+            if !self.archive {
+                status!("   Its call history will be deleted as well.");
+            }
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt(format!(
+                    "Are you sure you want to {} this deployment?",
+                    action
+                ))
+                .default(false)
+                .interact()?;
+
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        if self.archive {
+            DeploymentRepository::archive(&db, deployment.id).await?;
+        } else {
+            auto_backup(&db, "remove").await?;
+            DeploymentRepository::delete(&db, deployment.id).await?;
+        }
+
+        if output::is_json() {
+            return output::print_json(&deployment);
+        }
+
+        status!(
+            "{} {} v{} on {} {}",
+            style("*").green().bold(),
+            style(&self.contract).cyan(),
+            deployment.version,
+            style(&self.network).cyan(),
+            if self.archive { "archived" } else { "deleted" }
+        );
+
+        match DeploymentRepository::get_current(&db, &self.contract, &self.network).await? {
+            Some(previous) => status!(
+                "   v{} at {} is now current",
+                previous.version,
+                style(&previous.address).yellow()
+            ),
+            None => status!("   No remaining versions on this network"),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Show who owns a contract and who holds its roles
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use smolder_db::{Database, DeploymentRepository, NetworkRepository};
+
+use crate::output::{self, status};
+use crate::roles::{self, RoleMember};
+
+/// Show the owner and AccessControl role holders of a deployment
+#[derive(Args)]
+pub struct RolesCommand {
+    /// Contract name
+    pub contract: String,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+}
+
+impl RolesCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let network = NetworkRepository::get_by_name(&db, &self.network)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.network))?;
+        let deployment = DeploymentRepository::get_current(&db, &self.contract, &self.network)
+            .await?
+            .ok_or_else(|| {
+                eyre!(
+                    "No deployment found for contract '{}' on network '{}'",
+                    self.contract,
+                    self.network
+                )
+            })?;
+        let view = DeploymentRepository::get_view_by_id(&db, deployment.id)
+            .await?
+            .ok_or_else(|| eyre!("Deployment {} not found", deployment.id))?;
+
+        let report = roles::read(&db, &view, network.rpc_url.expose()).await?;
+
+        if output::is_json() {
+            return output::print_json(&report);
+        }
+
+        if let Some(owner) = &report.owner {
+            println!("{} {}", style("Owner:").bold(), describe(owner));
+        }
+        if let Some(error) = &report.events_error {
+            status!(
+                "{} Could not read role events ({}); only registry wallets were checked",
+                style("!").yellow(),
+                error
+            );
+        }
+
+        for role in &report.roles {
+            println!();
+            println!(
+                "{}",
+                style(role.name.as_deref().unwrap_or(&role.role))
+                    .cyan()
+                    .bold()
+            );
+            if role.name.is_some() {
+                status!("  {}", style(&role.role).dim());
+            }
+            if let Some(admin) = &role.admin_role {
+                let admin_name = report
+                    .roles
+                    .iter()
+                    .find(|r| &r.role == admin)
+                    .and_then(|r| r.name.as_deref())
+                    .unwrap_or(admin);
+                status!("  {} {}", style("admin:").dim(), admin_name);
+            }
+            if role.members.is_empty() {
+                println!("  {}", style("no holders").dim());
+            }
+            for member in &role.members {
+                println!("  {}", describe(member));
+            }
+        }
+
+        if report.owner.is_none() && report.roles.is_empty() {
+            status!(
+                "{} No owner or role holders found on {}",
+                style("-").dim(),
+                view.address
+            );
+        }
+        Ok(())
+    }
+}
+
+fn describe(member: &RoleMember) -> String {
+    match &member.wallet {
+        Some(wallet) => format!("{} ({})", member.address, style(wallet).green()),
+        None => member.address.clone(),
+    }
+}
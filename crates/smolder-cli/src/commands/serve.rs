@@ -1,11 +1,24 @@
 //! Start the web server for the dashboard UI
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 use clap::Args;
 use color_eyre::eyre::{eyre, Result};
 use console::style;
-use smolder_db::Database;
+use smolder_core::SmolderDir;
+use smolder_db::{Database, DB_FILENAME};
 
-use crate::server::ServerConfig;
+use crate::config::{RetentionConfig, SmolderConfig};
+use crate::output::status;
+use crate::prices::PriceOracle;
+use crate::server::cors::{AllowedOrigins, CorsPolicy};
+use crate::server::jobs;
+use crate::server::limits::{self, Limits};
+use crate::server::logging::{self, LogFormat};
+use crate::server::monitor::{Monitor, DEFAULT_INTERVAL as DEFAULT_MONITOR_INTERVAL};
+use crate::server::{proxy, ServerConfig, TlsConfig};
+use crate::tenderly::Tenderly;
 
 /// Start the web server for the dashboard UI
 #[derive(Args)]
@@ -17,6 +30,78 @@ pub struct ServeCommand {
     /// Port to listen on
     #[arg(long, short, default_value = "3000")]
     pub port: u16,
+
+    /// Log filter, e.g. `debug` or `info,tower_http=debug`
+    #[arg(long, default_value = "info")]
+    pub log_level: String,
+
+    /// Seconds to wait for in-flight requests to finish on shutdown
+    #[arg(long, default_value = "30")]
+    pub shutdown_timeout: u64,
+
+    /// Seconds between gas price samples of each network; 0 disables sampling
+    #[arg(long, default_value = "60")]
+    pub gas_sample_interval: u64,
+
+    /// Seconds between checks of recently mined deployments and calls for
+    /// reorgs and finality; 0 disables them
+    #[arg(long, default_value = "30")]
+    pub reorg_check_interval: u64,
+
+    /// Hours a deploy or send on a protected network waits for approval
+    /// before it expires
+    #[arg(long, default_value = "24")]
+    pub approval_ttl: u64,
+
+    /// Requests each token, or each IP when the API is open, may make per
+    /// minute; 0 disables rate limiting
+    #[arg(long, default_value_t = limits::DEFAULT_REQUESTS_PER_MINUTE)]
+    pub rate_limit: u32,
+
+    /// Largest request body accepted, in KiB
+    #[arg(long, default_value_t = limits::DEFAULT_MAX_BODY_BYTES / 1024)]
+    pub max_body_kb: usize,
+
+    /// Requests that call RPC nodes (calls, sends, deploys, ENS, contract
+    /// status) served at once; others wait their turn
+    #[arg(long, default_value_t = limits::DEFAULT_RPC_CONCURRENCY)]
+    pub rpc_concurrency: usize,
+
+    /// Background jobs, like following sent transactions until they are
+    /// mined, run at once
+    #[arg(long, default_value_t = jobs::DEFAULT_WORKERS)]
+    pub job_workers: usize,
+
+    /// PEM certificate chain to serve HTTPS with
+    #[arg(long, requires = "tls_key", value_name = "PATH")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert", value_name = "PATH")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Sub-path a reverse proxy mounts the server under, e.g. `/smolder`
+    #[arg(long, default_value = "", value_parser = proxy::parse_base_path)]
+    pub base_path: String,
+
+    /// Trust the last `X-Forwarded-For` entry to name the client, for logs
+    /// and rate limits; only set this behind a proxy that appends to it
+    #[arg(long)]
+    pub behind_proxy: bool,
+
+    /// Origin whose pages may call the API, e.g. `https://app.example.com`,
+    /// or `*` for any; repeatable, replaces `[cors] origins` in smolder.toml.
+    /// Only localhost is allowed by default
+    #[arg(long = "cors-origin", value_name = "ORIGIN")]
+    pub cors_origins: Vec<String>,
+
+    /// Let allowed origins send cookies and HTTP auth with their requests
+    #[arg(long)]
+    pub cors_credentials: bool,
+
+    /// Log line format; use `json` when shipping logs to a collector
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
 }
 
 impl ServeCommand {
@@ -29,38 +114,122 @@ impl ServeCommand {
             ));
         }
 
+        logging::init(&self.log_level, self.log_format)?;
+
+        let smolder_config = SmolderConfig::load()?;
+        let cors = CorsPolicy::new(
+            match self.cors_origins.is_empty() {
+                true => &smolder_config.cors.origins,
+                false => &self.cors_origins,
+            },
+            self.cors_credentials || smolder_config.cors.allow_credentials,
+        )
+        .map_err(|e| eyre!(e))?;
+
         // Connect to database
-        let db = Database::connect().await?;
+        let db = Database::connect_to_with_options(
+            SmolderDir::new().join(DB_FILENAME),
+            &smolder_config.database.options()?,
+        )
+        .await?;
 
         let config = ServerConfig {
             host: self.host.clone(),
             port: self.port,
+            framework: smolder_config.project.framework,
+            shutdown_timeout: Duration::from_secs(self.shutdown_timeout),
+            ens_network: smolder_config.ens.network.clone(),
+            tenderly: smolder_config
+                .tenderly
+                .as_ref()
+                .map(Tenderly::from_config)
+                .transpose()?,
+            prices: smolder_config
+                .prices
+                .as_ref()
+                .map(PriceOracle::from_config)
+                .transpose()?,
+            gas_sample_interval: match self.gas_sample_interval {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            reorg_check_interval: match self.reorg_check_interval {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            history_retention: smolder_config
+                .retention
+                .as_ref()
+                .map(RetentionConfig::policy)
+                .transpose()?,
+            monitor: smolder_config
+                .monitor
+                .as_ref()
+                .map(|monitor| -> Result<Monitor> {
+                    Ok(Monitor {
+                        networks: monitor.networks.clone(),
+                        interval: monitor.interval()?.unwrap_or(DEFAULT_MONITOR_INTERVAL),
+                        notifications: monitor.notify.then(|| smolder_config.notifications.clone()),
+                    })
+                })
+                .transpose()?,
+            approval_ttl: Duration::from_secs(self.approval_ttl * 60 * 60),
+            limits: Limits {
+                requests_per_minute: self.rate_limit,
+                max_body_bytes: self.max_body_kb * 1024,
+                rpc_concurrency: self.rpc_concurrency.max(1),
+            },
+            tls: match (self.tls_cert, self.tls_key) {
+                (Some(cert), Some(key)) => Some(TlsConfig { cert, key }),
+                _ => None,
+            },
+            base_path: self.base_path.clone(),
+            trust_forwarded: self.behind_proxy,
+            cors,
+            job_workers: self.job_workers.max(1),
         };
+        let url = format!(
+            "{}://{}:{}{}",
+            match config.tls {
+                Some(_) => "https",
+                None => "http",
+            },
+            self.host,
+            self.port,
+            self.base_path
+        );
 
-        println!("{} Starting Smolder server...", style("→").blue());
-        println!();
-        println!(
+        status!("{} Starting Smolder server...", style("→").blue());
+        status!();
+        status!(
             "  {} Dashboard: {}",
             style("◆").cyan(),
-            style(format!("http://{}:{}", self.host, self.port))
-                .underlined()
-                .cyan()
+            style(format!("{}/", url)).underlined().cyan()
         );
-        println!(
+        status!(
             "  {} API:       {}",
             style("◆").cyan(),
-            style(format!("http://{}:{}/api", self.host, self.port))
-                .underlined()
-                .cyan()
+            style(format!("{}/api", url)).underlined().cyan()
         );
-        println!();
-        println!("  Press {} to stop the server", style("Ctrl+C").yellow());
-        println!();
+        status!(
+            "  {} CORS:      {}",
+            style("◆").cyan(),
+            match config.cors.origins() {
+                AllowedOrigins::Localhost => "localhost only".to_string(),
+                AllowedOrigins::List(origins) => origins.join(", "),
+                AllowedOrigins::Any => "any origin".to_string(),
+            }
+        );
+        status!();
+        status!("  Press {} to stop the server", style("Ctrl+C").yellow());
+        status!();
 
         crate::server::run_server(db, config)
             .await
             .map_err(|e| eyre!("Server error: {}", e))?;
 
+        status!("{} Server stopped", style("✓").green());
+
         Ok(())
     }
 }
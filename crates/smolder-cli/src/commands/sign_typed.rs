@@ -0,0 +1,88 @@
+//! Sign EIP-712 typed data with a stored wallet
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+use smolder_db::{Database, WalletRepository};
+
+use crate::output::{self, status};
+use crate::typed_data;
+
+/// Sign an EIP-712 payload (a permit, meta-transaction or vote) with a
+/// stored wallet, recording it in the registry
+#[derive(Args)]
+pub struct SignTypedCommand {
+    /// Wallet to sign with
+    #[arg(long)]
+    pub wallet: String,
+
+    /// JSON file with `types`, `primaryType`, `domain` and `message`, as for
+    /// `eth_signTypedData_v4`
+    #[arg(long)]
+    pub file: String,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl SignTypedCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read_to_string(&self.file)
+            .map_err(|e| eyre!("Could not read {}: {}", self.file, e))?;
+        let payload: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| eyre!("{} is not valid JSON: {}", self.file, e))?;
+        let typed = typed_data::parse(&payload)?;
+
+        let db = Database::connect().await?;
+        let wallet = WalletRepository::get_with_key(&db, &self.wallet)
+            .await?
+            .ok_or_else(|| eyre!("Wallet '{}' not found", self.wallet))?;
+
+        if !self.force {
+            let domain = &typed.domain;
+            status!(
+                "{} About to sign {} with {} ({})",
+                style("!").yellow(),
+                style(&typed.primary_type).cyan(),
+                wallet.name,
+                wallet.address
+            );
+            if let Some(name) = &domain.name {
+                status!("   Domain:   {}", name);
+            }
+            if let Some(chain_id) = domain.chain_id {
+                status!("   Chain ID: {}", chain_id);
+            }
+            if let Some(contract) = domain.verifying_contract {
+                status!("   Contract: {}", contract);
+            }
+            status!("   Message:  {}", typed.message);
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt("Sign this message?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        let record = typed_data::sign(&db, &wallet, &typed, None).await?;
+
+        if output::is_json() {
+            return output::print_json(&record);
+        }
+        status!(
+            "{} Signed {} (digest {})",
+            style("✓").green(),
+            style(&record.primary_type).cyan(),
+            record.digest
+        );
+        println!("{}", record.signature);
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! Read raw storage slots of a contract
+
+use alloy::primitives::{Address, U256};
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use smolder_core::ens::is_ens_name;
+use smolder_core::storage::{derive_slot, parse_slot, NAMED_SLOTS};
+use smolder_core::StorageWord;
+use smolder_db::{Database, DeploymentRepository, NetworkRepository};
+
+use crate::config::SmolderConfig;
+use crate::ens;
+use crate::output::{self, status};
+use crate::rpc;
+
+/// Read a storage slot with `eth_getStorageAt`
+#[derive(Args)]
+pub struct StorageCommand {
+    /// Contract name, address or ENS name
+    pub contract: String,
+
+    /// Slot number (decimal or hex) or `implementation`, `admin`, `beacon`
+    /// or `owner`. Without it, all of those are shown
+    pub slot: Option<String>,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Mapping key at the slot; repeat for nested mappings, outermost first
+    #[arg(long = "key", value_name = "KEY", requires = "slot")]
+    pub keys: Vec<String>,
+
+    /// Element of the dynamic array at the slot (after any keys)
+    #[arg(long, requires = "slot")]
+    pub index: Option<U256>,
+}
+
+impl StorageCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let network = NetworkRepository::get_by_name(&db, &self.network)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.network))?;
+
+        let address: Address = if self.contract.starts_with("0x") || is_ens_name(&self.contract) {
+            let ens_network = SmolderConfig::load()?.ens.network;
+            ens::resolve_address(&db, &network, ens_network.as_deref(), &self.contract).await?
+        } else {
+            DeploymentRepository::get_current(&db, &self.contract, &self.network)
+                .await?
+                .ok_or_else(|| {
+                    eyre!(
+                        "No deployment found for contract '{}' on network '{}'",
+                        self.contract,
+                        self.network
+                    )
+                })?
+                .address
+                .0
+        };
+
+        let Some(slot) = self.slot else {
+            return print_named_slots(network.rpc_url.expose(), address).await;
+        };
+
+        let slot = derive_slot(parse_slot(&slot)?, &self.keys, self.index);
+        let value = rpc::get_storage_at(network.rpc_url.expose(), address, slot).await?;
+        let word = StorageWord::new(slot, value);
+
+        if output::is_json() {
+            return output::print_json(&word);
+        }
+
+        status!("{} {}", style("Slot:").dim(), word.slot);
+        println!("{}", word.value);
+        status!("{} {}", style("uint:").dim(), word.uint);
+        if let Some(address) = &word.address {
+            status!("{} {}", style("address:").dim(), address);
+        }
+        Ok(())
+    }
+}
+
+/// Show the proxy and ownership slots, skipping empty ones in text mode
+async fn print_named_slots(rpc_url: &str, address: Address) -> Result<()> {
+    let mut words = Vec::with_capacity(NAMED_SLOTS.len());
+    for named in &NAMED_SLOTS {
+        let value = rpc::get_storage_at(rpc_url, address, named.slot).await?;
+        words.push((named, StorageWord::new(named.slot, value)));
+    }
+
+    if output::is_json() {
+        let map: serde_json::Map<_, _> = words
+            .iter()
+            .map(|(named, word)| Ok((named.name.to_string(), serde_json::to_value(word)?)))
+            .collect::<Result<_>>()?;
+        return output::print_json(&map);
+    }
+
+    let mut found = false;
+    for (named, word) in &words {
+        if word.uint == "0" {
+            continue;
+        }
+        found = true;
+        println!(
+            "{:<16} {}",
+            named.name,
+            word.address.as_deref().unwrap_or(&word.value)
+        );
+        status!("  {}", style(named.description).dim());
+    }
+    if !found {
+        status!(
+            "{} No proxy or ownership slots set on {}",
+            style("-").dim(),
+            address
+        );
+    }
+    Ok(())
+}
@@ -1,116 +1,238 @@
 //! Sync deployments from broadcast directory
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::time::Duration;
 
 use clap::Args;
 use color_eyre::eyre::{eyre, Result};
 use console::style;
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::Serialize;
+use smolder_core::TxHash;
 use smolder_db::{
-    ChainId, ContractRepository, Database, DeploymentRepository, NetworkRepository, NewContract,
-    NewDeployment, NewNetwork,
+    ChainId, Database, DeploymentFilter, DeploymentRepository, ImportStatus, NetworkRepository,
+    NewContract, NewContractDeployment, NewNetwork,
 };
 
-use crate::config::FoundryConfig;
-use crate::forge::{BroadcastOutput, BroadcastParser, ForgeBroadcastParser};
+use super::db::auto_backup;
+use super::push::print_report;
+use crate::config::{FoundryConfig, Framework, SmolderConfig};
+use crate::deployment_files;
+use crate::forge;
+use crate::hooks::{self, HookEvent};
+use crate::layout;
+use crate::notify::{self, DeploymentNotice};
+use crate::output::{self, status};
+use crate::registry_sync;
 use crate::rpc::get_chain_id;
+use crate::tokens;
 
-/// Sync deployments from broadcast directory
+/// How long each RPC endpoint gets to report its chain ID
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sync deployments from broadcast directory (or hardhat-deploy deployments)
 #[derive(Args)]
-pub struct SyncCommand;
+pub struct SyncCommand {
+    /// Also write each synced network's deployments to
+    /// `deployments/<network>.json` for versioning in git
+    #[arg(long)]
+    pub write_files: bool,
+
+    /// Rebuild the registry from the deployment files instead of the
+    /// broadcast directory
+    #[arg(long, conflicts_with = "write_files")]
+    pub from_files: bool,
+}
+
+/// Result of a sync run, printed with `--json`
+#[derive(Serialize, Default)]
+struct SyncReport {
+    added: Vec<SyncedDeployment>,
+    /// Already tracked deployments whose block number or constructor
+    /// arguments were filled in
+    updated: Vec<SyncedDeployment>,
+    skipped: usize,
+}
+
+#[derive(Serialize)]
+struct SyncedDeployment {
+    network: String,
+    contract: String,
+    address: String,
+    tx_hash: TxHash,
+}
 
 impl SyncCommand {
     pub async fn run(self) -> Result<()> {
-        // Load foundry config
-        let config = FoundryConfig::load()?;
+        let smolder_config = SmolderConfig::load()?;
+        if self.from_files {
+            return sync_from_files(&smolder_config).await;
+        }
+        let framework = smolder_config.project.framework;
+        let parser = forge::broadcast_parser(framework);
+        let output_dir = match framework {
+            Framework::Foundry => "broadcast",
+            Framework::Hardhat => "deployments",
+        };
+
+        // foundry.toml is required for forge projects and optional otherwise
+        let config = match framework {
+            Framework::Foundry => Some(FoundryConfig::load()?),
+            Framework::Hardhat => FoundryConfig::exists()
+                .then(FoundryConfig::load)
+                .transpose()?,
+        };
 
         // Scan for broadcast files
-        println!("{} Scanning broadcast directory...", style("->").blue());
-        let broadcast_files = scan_broadcast_directory()?;
+        status!(
+            "{} Scanning {} directory...",
+            style("->").blue(),
+            output_dir
+        );
+        let broadcast_files = parser.discover()?;
 
         if broadcast_files.is_empty() {
-            println!(
-                "{} No broadcast files found in broadcast/",
-                style("!").yellow()
+            status!(
+                "{} No deployment outputs found in {}/",
+                style("!").yellow(),
+                output_dir
             );
+            if output::is_json() {
+                return output::print_json(&SyncReport::default());
+            }
             return Ok(());
         }
 
-        println!(
-            "   Found {} broadcast file(s)",
-            style(broadcast_files.len()).cyan()
-        );
+        status!("   Found {} output(s)", style(broadcast_files.len()).cyan());
 
-        // Build chain_id -> network mapping by querying RPC for each network
-        println!(
-            "{} Resolving networks from foundry.toml...",
-            style("->").blue()
-        );
-        let mut chain_to_network: HashMap<u64, (String, String, Option<String>)> = HashMap::new();
+        // Connect to database
+        let db = Database::connect().await?;
 
-        for network_name in config.network_names() {
-            let network = match config.get_network(network_name) {
-                Ok(n) => n,
-                Err(e) => {
-                    println!(
-                        "   {} Skipping {}: {}",
-                        style("!").yellow(),
-                        network_name,
-                        e
-                    );
-                    continue;
-                }
-            };
+        // Build chain_id -> network mapping, starting from networks already in
+        // the registry so projects without foundry.toml can still sync
+        let registry = NetworkRepository::list(&db).await?;
+        let mut chain_to_network: HashMap<u64, (String, String, Option<String>)> = registry
+            .iter()
+            .map(|n| {
+                (
+                    u64::from(n.chain_id),
+                    (
+                        n.name.clone(),
+                        n.rpc_url.expose().to_string(),
+                        n.explorer_url.clone(),
+                    ),
+                )
+            })
+            .collect();
 
-            match get_chain_id(&network.rpc_url).await {
-                Ok(chain_id) => {
+        if let Some(config) = &config {
+            status!(
+                "{} Resolving networks from foundry.toml...",
+                style("->").blue()
+            );
+
+            let mut probes = FuturesUnordered::new();
+            for network_name in config.network_names() {
+                let network = match config.get_network(network_name) {
+                    Ok(n) => n,
+                    Err(e) => {
+                        status!(
+                            "   {} Skipping {}: {}",
+                            style("!").yellow(),
+                            network_name,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                // A registered network with the same RPC URL already knows its
+                // chain ID; only probe endpoints that are new or changed
+                let cached = registry
+                    .iter()
+                    .find(|n| n.name == network.name && n.rpc_url == network.rpc_url.as_str());
+                if let Some(cached) = cached {
                     chain_to_network.insert(
-                        chain_id,
-                        (
-                            network.name.clone(),
-                            network.rpc_url.clone(),
-                            network.explorer_url.clone(),
-                        ),
+                        u64::from(cached.chain_id),
+                        (network.name.clone(), network.rpc_url, network.explorer_url),
                     );
-                    println!(
-                        "   {} {} (chain ID: {})",
+                    status!(
+                        "   {} {} (chain ID: {}, cached)",
                         style("*").dim(),
                         style(&network.name).cyan(),
-                        chain_id
+                        cached.chain_id
                     );
+                    continue;
                 }
-                Err(e) => {
-                    println!(
-                        "   {} Could not connect to {}: {}",
-                        style("!").yellow(),
-                        network_name,
-                        e
-                    );
+
+                probes.push(async move {
+                    let result =
+                        tokio::time::timeout(PROBE_TIMEOUT, get_chain_id(&network.rpc_url))
+                            .await
+                            .map_err(|_| eyre!("no response within {}s", PROBE_TIMEOUT.as_secs()))
+                            .and_then(|r| r);
+                    (network, result)
+                });
+            }
+
+            // Endpoints are probed concurrently, reported as they answer
+            while let Some((network, result)) = probes.next().await {
+                match result {
+                    Ok(chain_id) => {
+                        // Remember the chain ID so the next sync skips the probe
+                        NetworkRepository::upsert(
+                            &db,
+                            &NewNetwork {
+                                name: network.name.clone(),
+                                chain_id: ChainId::from(chain_id),
+                                rpc_url: network.rpc_url.clone(),
+                                explorer_url: network.explorer_url.clone(),
+                            },
+                        )
+                        .await?;
+
+                        status!(
+                            "   {} {} (chain ID: {})",
+                            style("*").dim(),
+                            style(&network.name).cyan(),
+                            chain_id
+                        );
+                        chain_to_network.insert(
+                            chain_id,
+                            (network.name, network.rpc_url, network.explorer_url),
+                        );
+                    }
+                    Err(e) => {
+                        status!(
+                            "   {} Could not connect to {}: {}",
+                            style("!").yellow(),
+                            network.name,
+                            e
+                        );
+                    }
                 }
             }
         }
 
         if chain_to_network.is_empty() {
             return Err(eyre!(
-                "No networks could be resolved. Check your foundry.toml and RPC endpoints."
+                "No networks could be resolved. Check your foundry.toml and RPC endpoints, or register networks with {}",
+                style("smolder network add").cyan()
             ));
         }
 
-        // Connect to database
-        let db = Database::connect().await?;
-
-        let mut total_imported = 0;
-        let mut total_skipped = 0;
+        let mut report = SyncReport::default();
+        let mut synced_networks = Vec::new();
 
         // Process each broadcast file
         for broadcast_file in &broadcast_files {
             let network_info = match chain_to_network.get(&broadcast_file.chain_id) {
                 Some(info) => info,
                 None => {
-                    println!(
+                    status!(
                         "{} Skipping {} - no network configured for chain ID {}",
                         style("!").yellow(),
-                        broadcast_file.script_name,
+                        broadcast_file.label,
                         broadcast_file.chain_id
                     );
                     continue;
@@ -119,22 +241,21 @@ impl SyncCommand {
 
             let (network_name, rpc_url, explorer_url) = network_info;
 
-            println!(
-                "{} Processing {} (run-{}) on {}...",
+            status!(
+                "{} Processing {} on {}...",
                 style("->").blue(),
-                style(&broadcast_file.script_name).cyan(),
-                style(&broadcast_file.run_id).dim(),
+                style(&broadcast_file.label).cyan(),
                 style(network_name).cyan()
             );
 
             // Load and parse broadcast
-            let broadcast = match load_broadcast(&broadcast_file.path) {
+            let broadcast = match parser.load(broadcast_file) {
                 Ok(b) => b,
                 Err(e) => {
-                    println!(
+                    status!(
                         "   {} Failed to parse {}: {}",
                         style("!").yellow(),
-                        broadcast_file.path,
+                        broadcast_file.path.display(),
                         e
                     );
                     continue;
@@ -142,11 +263,10 @@ impl SyncCommand {
             };
 
             // Extract deployments
-            let parser = ForgeBroadcastParser::new();
             let deployments = match parser.extract_deployments(&broadcast) {
                 Ok(d) => d,
                 Err(e) => {
-                    println!(
+                    status!(
                         "   {} Failed to extract deployments: {}",
                         style("!").yellow(),
                         e
@@ -156,7 +276,7 @@ impl SyncCommand {
             };
 
             if deployments.is_empty() {
-                println!("   No deployments found");
+                status!("   No deployments found");
                 continue;
             }
 
@@ -171,182 +291,184 @@ impl SyncCommand {
                 },
             )
             .await?;
+            if !synced_networks.contains(&network.name) {
+                synced_networks.push(network.name.clone());
+            }
 
-            // Import each deployment
-            for deployment in &deployments {
-                // Check if already exists
-                if DeploymentRepository::exists_by_tx_hash(&db, &deployment.tx_hash).await? {
-                    println!(
-                        "   {} {} already tracked (tx: {}...)",
-                        style("-").dim(),
-                        style(&deployment.contract_name).dim(),
-                        &deployment.tx_hash[..10]
-                    );
-                    total_skipped += 1;
-                    continue;
-                }
+            // Layouts are compared with the current versions before those
+            // are replaced; only conflicts of newly added deployments are shown
+            let mut layout_issues = Vec::with_capacity(deployments.len());
+            for d in &deployments {
+                let issues = match &d.storage_layout {
+                    Some(storage_layout) => {
+                        layout::check_against_current(
+                            &db,
+                            &d.contract_name,
+                            &network.name,
+                            Some(d.address),
+                            storage_layout,
+                        )
+                        .await?
+                    }
+                    None => Vec::new(),
+                };
+                layout_issues.push(issues);
+            }
 
-                // Upsert contract
-                let contract = ContractRepository::upsert(
-                    &db,
-                    &NewContract {
-                        name: deployment.contract_name.clone(),
-                        source_path: deployment.source_path.clone(),
-                        abi: deployment.abi.clone(),
-                        bytecode_hash: deployment.bytecode_hash.clone(),
-                    },
-                )
-                .await?;
-
-                // Create deployment record
-                DeploymentRepository::create(
-                    &db,
-                    &NewDeployment {
-                        contract_id: contract.id,
-                        network_id: network.id,
-                        address: deployment.address.clone(),
-                        deployer: deployment.deployer.clone(),
-                        tx_hash: deployment.tx_hash.clone(),
-                        block_number: deployment.block_number,
-                        constructor_args: deployment.constructor_args.clone(),
+            // Record the whole file in one transaction, so an interrupted
+            // sync never leaves it half imported
+            let batch: Vec<NewContractDeployment> = deployments
+                .iter()
+                .map(|d| NewContractDeployment {
+                    contract: NewContract {
+                        name: d.contract_name.clone(),
+                        source_path: d.source_path.clone(),
+                        abi: d.abi.clone(),
+                        bytecode_hash: d.bytecode_hash,
+                        storage_layout: d.storage_layout.clone(),
+                        compiler: d.compiler.clone(),
                     },
-                )
-                .await?;
-
-                println!(
-                    "   {} {} at {}",
-                    style("+").green(),
-                    style(&deployment.contract_name).cyan(),
-                    style(&deployment.address).yellow()
-                );
-                total_imported += 1;
+                    address: d.address,
+                    deployer: d.deployer.clone(),
+                    tx_hash: Some(d.tx_hash),
+                    block_number: d.block_number,
+                    constructor_args: d.constructor_args.clone(),
+                    initcode_size: Some(d.code_size.initcode as i64),
+                    runtime_size: Some(d.code_size.runtime as i64),
+                    gas_used: d.gas_used,
+                    gas_price: None,
+                })
+                .collect();
+            let statuses = DeploymentRepository::import_batch(&db, network.id, &batch).await?;
+            let mut notices = Vec::new();
+
+            for ((deployment, import_status), layout_issues) in
+                deployments.iter().zip(statuses).zip(layout_issues)
+            {
+                let synced = SyncedDeployment {
+                    network: network_name.clone(),
+                    contract: deployment.contract_name.clone(),
+                    address: deployment.address.to_string(),
+                    tx_hash: deployment.tx_hash,
+                };
+
+                match import_status {
+                    ImportStatus::Added => {
+                        status!(
+                            "   {} {} at {}",
+                            style("+").green(),
+                            style(&deployment.contract_name).cyan(),
+                            style(&deployment.address).yellow()
+                        );
+                        layout::print_issues(&deployment.contract_name, &layout_issues);
+                        notices.push(DeploymentNotice {
+                            contract: deployment.contract_name.clone(),
+                            network: network.name.clone(),
+                            address: deployment.address.to_string(),
+                            version: None,
+                            deployer: deployment.deployer.clone(),
+                            tx_hash: Some(deployment.tx_hash),
+                            explorer_url: network.explorer_url.clone(),
+                        });
+                        report.added.push(synced);
+                    }
+                    ImportStatus::Updated => {
+                        status!(
+                            "   {} {} at {} (filled in missing details)",
+                            style("~").yellow(),
+                            style(&deployment.contract_name).cyan(),
+                            style(&deployment.address).yellow()
+                        );
+                        report.updated.push(synced);
+                    }
+                    ImportStatus::Skipped => {
+                        status!(
+                            "   {} {} already tracked at {}",
+                            style("-").dim(),
+                            style(&deployment.contract_name).dim(),
+                            style(&deployment.address).dim()
+                        );
+                        report.skipped += 1;
+                    }
+                }
             }
+
+            notify::announce(&smolder_config.notifications, &notices).await;
         }
 
-        println!();
-        if total_imported > 0 {
-            println!(
-                "{} Imported {} deployment(s)",
-                style("*").green().bold(),
-                total_imported
-            );
+        if !report.added.is_empty() {
+            let mut current = DeploymentRepository::list(&db, DeploymentFilter::current()).await?;
+            tokens::enrich(&db, &mut current).await;
         }
-        if total_skipped > 0 {
-            println!(
-                "{} Skipped {} already tracked deployment(s)",
-                style("*").dim(),
-                total_skipped
-            );
+        deployment_files::write_if_enabled(
+            &db,
+            &smolder_config.deployment_files,
+            self.write_files,
+            &synced_networks,
+        )
+        .await?;
+
+        let mut changed: Vec<String> = report
+            .added
+            .iter()
+            .chain(&report.updated)
+            .map(|d| d.network.clone())
+            .collect();
+        changed.sort();
+        changed.dedup();
+        if !changed.is_empty() {
+            hooks::run(&db, &smolder_config.hooks, HookEvent::PostSync, &changed).await;
         }
-        if total_imported == 0 && total_skipped == 0 {
-            println!("{} No deployments found to import", style("*").yellow());
+
+        if output::is_json() {
+            return output::print_json(&report);
+        }
+
+        status!();
+        if report.added.is_empty() && report.updated.is_empty() && report.skipped == 0 {
+            status!("{} No deployments found to import", style("*").yellow());
+        } else {
+            status!(
+                "{} Sync complete: {} added, {} updated, {} skipped",
+                style("*").green().bold(),
+                style(report.added.len()).green(),
+                style(report.updated.len()).yellow(),
+                style(report.skipped).dim()
+            );
         }
 
         Ok(())
     }
 }
 
-/// Discovered broadcast file with metadata
-struct BroadcastFile {
-    path: String,
-    chain_id: u64,
-    script_name: String,
-    /// Run identifier for display (e.g., "1", "2", "latest")
-    run_id: String,
-    /// Sort key for ordering (numbered runs come before "latest")
-    sort_key: u64,
-}
-
-/// Scan the broadcast directory for all run-*.json files
-fn scan_broadcast_directory() -> Result<Vec<BroadcastFile>> {
-    let broadcast_dir = Path::new("broadcast");
-    if !broadcast_dir.exists() {
-        return Ok(Vec::new());
+/// Merge the deployment files written by `--write-files` into the registry
+async fn sync_from_files(config: &SmolderConfig) -> Result<()> {
+    let dir = config.deployment_files.dir();
+    status!(
+        "{} Reading deployment files in {}...",
+        style("->").blue(),
+        dir.display()
+    );
+    let files = deployment_files::read_dir(dir)?;
+    if files.is_empty() {
+        return Err(eyre!("No deployment files found in {}", dir.display()));
     }
 
-    let mut files = Vec::new();
-
-    // broadcast/<ScriptName>/<chainId>/run-*.json
-    for script_entry in std::fs::read_dir(broadcast_dir)? {
-        let script_entry = script_entry?;
-        let script_path = script_entry.path();
-
-        if !script_path.is_dir() {
-            continue;
-        }
-
-        let script_name = script_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("")
-            .to_string();
-
-        for chain_entry in std::fs::read_dir(&script_path)? {
-            let chain_entry = chain_entry?;
-            let chain_path = chain_entry.path();
-
-            if !chain_path.is_dir() {
-                continue;
-            }
-
-            let chain_id_str = chain_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("");
-
-            let chain_id: u64 = match chain_id_str.parse() {
-                Ok(id) => id,
-                Err(_) => continue,
-            };
-
-            // Find all run-*.json files
-            for file_entry in std::fs::read_dir(&chain_path)? {
-                let file_entry = file_entry?;
-                let file_path = file_entry.path();
-
-                if !file_path.is_file() {
-                    continue;
-                }
-
-                let file_name = file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-                // Match run-*.json pattern
-                if !file_name.starts_with("run-") || !file_name.ends_with(".json") {
-                    continue;
-                }
-
-                // Extract run identifier (e.g., "1", "2", "latest")
-                let run_id = file_name
-                    .strip_prefix("run-")
-                    .and_then(|s| s.strip_suffix(".json"))
-                    .unwrap_or("")
-                    .to_string();
-
-                // Compute sort key: numbered runs first (1, 2, 3...), then "latest"
-                let sort_key = run_id.parse::<u64>().unwrap_or(u64::MAX);
-
-                files.push(BroadcastFile {
-                    path: file_path.to_string_lossy().to_string(),
-                    chain_id,
-                    script_name: script_name.clone(),
-                    run_id,
-                    sort_key,
-                });
-            }
-        }
+    let db = Database::connect().await?;
+    let bundle = deployment_files::to_bundle(&db, files).await?;
+    status!(
+        "   Found {} deployment(s) on {} network(s)",
+        style(bundle.deployments.len()).cyan(),
+        bundle.networks.len()
+    );
+    if !bundle.deployments.is_empty() {
+        auto_backup(&db, "sync").await?;
     }
 
-    // Sort by script name, chain ID, then run number (chronological order)
-    files.sort_by(|a, b| {
-        (&a.script_name, a.chain_id, a.sort_key).cmp(&(&b.script_name, b.chain_id, b.sort_key))
-    });
-
-    Ok(files)
-}
-
-/// Load and parse a broadcast file
-fn load_broadcast(path: &str) -> Result<BroadcastOutput> {
-    let content = std::fs::read_to_string(path)?;
-    let output: BroadcastOutput = serde_json::from_str(&content)?;
-    Ok(output)
+    let report = registry_sync::merge(&db, &bundle).await?;
+    if report.added > 0 || report.updated > 0 {
+        let networks: Vec<String> = bundle.networks.iter().map(|n| n.name.clone()).collect();
+        hooks::run(&db, &config.hooks, HookEvent::PostSync, &networks).await;
+    }
+    print_report(&report)
 }
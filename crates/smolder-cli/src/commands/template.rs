@@ -0,0 +1,660 @@
+//! Deployment templates: an artifact saved with named constructor parameters
+//!
+//! A template records which artifact to deploy and what each constructor
+//! argument is called, with optional defaults, so a deployment only has to
+//! `--set` the values by name instead of repeating them in order.
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+use smolder_core::{decrypt_private_key, json_to_sol_value, Error, ParamInfo};
+use smolder_db::{
+    Database, DeploymentRepository, NetworkRepository, NewTemplate, Template, TemplateParam,
+    TemplateRepository, WalletRepository,
+};
+
+use crate::config::SmolderConfig;
+use crate::deploy_group::{self, Creation};
+use crate::forge;
+use crate::hooks::{self, HookEvent};
+use crate::interact::param_to_json;
+use crate::locks::Clearance;
+use crate::notify::{self, DeploymentNotice};
+use crate::output::{self, status};
+use crate::tokens;
+
+/// Manage deployment templates
+#[derive(Args)]
+pub struct TemplateCommand {
+    #[command(subcommand)]
+    pub command: TemplateSubcommand,
+}
+
+impl TemplateCommand {
+    pub async fn run(self) -> Result<()> {
+        self.command.run().await
+    }
+}
+
+#[derive(Subcommand)]
+pub enum TemplateSubcommand {
+    /// Save an artifact as a template, or replace one
+    Add(AddTemplateCommand),
+
+    /// List templates
+    List(ListTemplatesCommand),
+
+    /// Show a template's parameters
+    Show(ShowTemplateCommand),
+
+    /// Delete a template
+    Remove(RemoveTemplateCommand),
+}
+
+impl TemplateSubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Add(cmd) => cmd.run().await,
+            Self::List(cmd) => cmd.run().await,
+            Self::Show(cmd) => cmd.run().await,
+            Self::Remove(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Save an artifact as a template, or replace one
+#[derive(Args)]
+pub struct AddTemplateCommand {
+    /// Template name
+    pub name: String,
+
+    /// Artifact to deploy
+    #[arg(long)]
+    pub artifact: String,
+
+    /// Names of the constructor parameters, in order and comma-separated,
+    /// each with an optional default: "name,symbol,supply=1000000". Defaults
+    /// to the names in the ABI
+    #[arg(long, value_name = "SCHEMA")]
+    pub args_schema: Option<String>,
+
+    /// What the template deploys
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Replace an existing template with the same name
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl AddTemplateCommand {
+    pub async fn run(self) -> Result<()> {
+        let config = SmolderConfig::load()?;
+        let db = Database::connect().await?;
+        let loader = forge::artifact_loader(config.project.framework);
+
+        if !self.force && TemplateRepository::get(&db, &self.name).await?.is_some() {
+            return Err(eyre!(
+                "Template '{}' already exists. Pass {} to replace it",
+                self.name,
+                style("--force").yellow()
+            ));
+        }
+
+        let details = loader
+            .get_details(&self.artifact)
+            .map_err(|_| Error::ArtifactNotFound(self.artifact.clone()))?;
+        let inputs = details
+            .constructor
+            .as_ref()
+            .map(|c| c.inputs.as_slice())
+            .unwrap_or_default();
+        let schema = self.args_schema.as_deref().map(parse_schema).transpose()?;
+        let params = template_params(inputs, schema)?;
+
+        let template = TemplateRepository::upsert(
+            &db,
+            &NewTemplate {
+                name: self.name,
+                artifact: self.artifact,
+                params,
+                description: self.description,
+            },
+        )
+        .await?;
+
+        if output::is_json() {
+            return output::print_json(&template);
+        }
+
+        status!(
+            "{} Template '{}' saved for {}",
+            style("*").green().bold(),
+            style(&template.name).cyan(),
+            template.artifact
+        );
+        print_params(&template.params);
+        status!();
+        status!(
+            "   Deploy it with: {}",
+            style(format!(
+                "smolder deploy-template {} --network <network> --wallet <wallet>{}",
+                template.name,
+                usage(&template.params)
+            ))
+            .cyan()
+        );
+
+        Ok(())
+    }
+}
+
+/// List templates
+#[derive(Args)]
+pub struct ListTemplatesCommand;
+
+impl ListTemplatesCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let templates = TemplateRepository::list(&db).await?;
+
+        if output::is_json() {
+            return output::print_json(&templates);
+        }
+
+        if templates.is_empty() {
+            status!("No templates found.");
+            status!();
+            status!(
+                "   Save one with: {}",
+                style("smolder template add <name> --artifact <artifact>").cyan()
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{:<20} {:<24} {:<40} Description",
+            "Name", "Artifact", "Parameters"
+        );
+        println!("{}", "-".repeat(100));
+
+        for t in &templates {
+            let params: Vec<&str> = t.params.iter().map(|p| p.name.as_str()).collect();
+            println!(
+                "{:<20} {:<24} {:<40} {}",
+                t.name,
+                t.artifact,
+                params.join(", "),
+                t.description.as_deref().unwrap_or("")
+            );
+        }
+
+        status!();
+        status!("Total: {} template(s)", templates.len());
+
+        Ok(())
+    }
+}
+
+/// Show a template's parameters
+#[derive(Args)]
+pub struct ShowTemplateCommand {
+    /// Template name
+    pub name: String,
+}
+
+impl ShowTemplateCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let template = get_template(&db, &self.name).await?;
+
+        if output::is_json() {
+            return output::print_json(&template);
+        }
+
+        println!(
+            "{} deploys {}",
+            style(&template.name).cyan().bold(),
+            template.artifact
+        );
+        if let Some(description) = &template.description {
+            println!("{}", description);
+        }
+        print_params(&template.params);
+
+        Ok(())
+    }
+}
+
+/// Delete a template
+#[derive(Args)]
+pub struct RemoveTemplateCommand {
+    /// Template name
+    pub name: String,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RemoveTemplateCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+
+        if !self.force {
+            let confirmed = Confirm::new()
+                .with_prompt(format!("Delete template '{}'?", self.name))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        TemplateRepository::delete(&db, &self.name)
+            .await
+            .map_err(|e| match e.is_not_found() {
+                true => eyre!("Template '{}' not found", self.name),
+                false => e.into(),
+            })?;
+
+        if output::is_json() {
+            return output::print_json(&serde_json::json!({ "name": self.name }));
+        }
+
+        status!(
+            "{} Template '{}' deleted",
+            style("*").green().bold(),
+            self.name
+        );
+
+        Ok(())
+    }
+}
+
+/// Deploy a template, setting its parameters by name
+#[derive(Args)]
+pub struct DeployTemplateCommand {
+    /// Template name
+    pub template: String,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Wallet to deploy from
+    #[arg(long)]
+    pub wallet: String,
+
+    /// Parameter values as name=value; parameters with a default may be
+    /// left out. Arrays and tuples are passed as JSON
+    #[arg(long, num_args = 1.., value_name = "NAME=VALUE", value_parser = parse_assignment)]
+    pub set: Vec<(String, String)>,
+
+    /// Value to send to a payable constructor: wei, or with a unit ("0.1 ether")
+    #[arg(long)]
+    pub value: Option<String>,
+
+    /// Confirm a deploy to a locked network by repeating its name
+    #[arg(long, value_name = "NETWORK")]
+    pub unlock_confirm: Option<String>,
+}
+
+impl DeployTemplateCommand {
+    pub async fn run(self) -> Result<()> {
+        let config = SmolderConfig::load()?;
+        let db = Database::connect().await?;
+        let loader = forge::artifact_loader(config.project.framework);
+
+        let template = get_template(&db, &self.template).await?;
+        let args = resolve_args(&template.params, &self.set)?;
+        let creation = Creation::load(
+            loader.as_ref(),
+            &template.artifact,
+            &args,
+            self.value.as_deref(),
+        )?;
+        let inputs = creation
+            .details
+            .constructor
+            .as_ref()
+            .map(|c| c.inputs.as_slice())
+            .unwrap_or_default();
+        if inputs
+            .iter()
+            .zip(&template.params)
+            .any(|(input, param)| input.param_type != param.ty)
+        {
+            return Err(eyre!(
+                "The constructor of {} changed since template '{}' was saved. Save it again with {}",
+                template.artifact,
+                template.name,
+                style(format!("smolder template add {} --force", template.name)).cyan()
+            ));
+        }
+
+        let network = NetworkRepository::get_by_name(&db, &self.network)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.network))?;
+        Clearance::cli(self.unlock_confirm)
+            .check(
+                &db,
+                &network,
+                &format!("deploy {} (template {})", template.artifact, template.name),
+            )
+            .await?;
+        let wallet = WalletRepository::get_with_key(&db, &self.wallet)
+            .await?
+            .ok_or_else(|| eyre!("Wallet '{}' not found", self.wallet))?;
+        let private_key = decrypt_private_key(&wallet.encrypted_key)?;
+
+        status!(
+            "{} Deploying {} from template '{}' to {}",
+            style("→").blue(),
+            style(&template.artifact).cyan(),
+            template.name,
+            style(&network.name).cyan()
+        );
+        let deployment =
+            deploy_group::deploy_to(&db, &creation, &args, &network, &wallet, &private_key).await?;
+
+        tokens::enrich_ids(&db, &[deployment.id]).await;
+        if let Some(view) = DeploymentRepository::get_view_by_id(&db, deployment.id).await? {
+            let notice = DeploymentNotice {
+                contract: view.contract_name,
+                network: view.network_name,
+                address: view.address.to_string(),
+                version: Some(view.version),
+                deployer: view.deployer,
+                tx_hash: view.tx_hash,
+                explorer_url: network.explorer_url.clone(),
+            };
+            notify::announce(&config.notifications, &[notice]).await;
+        }
+        hooks::run(
+            &db,
+            &config.hooks,
+            HookEvent::PostDeploy,
+            std::slice::from_ref(&network.name),
+        )
+        .await;
+
+        if output::is_json() {
+            return output::print_json(&deployment);
+        }
+
+        status!(
+            "{} {} deployed to {} at {}",
+            style("✓").green().bold(),
+            template.artifact,
+            network.name,
+            style(deployment.address).cyan()
+        );
+
+        Ok(())
+    }
+}
+
+async fn get_template(db: &Database, name: &str) -> Result<Template> {
+    TemplateRepository::get(db, name)
+        .await?
+        .ok_or_else(|| eyre!("Template '{}' not found", name))
+}
+
+fn parse_assignment(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", value))
+}
+
+/// Split a schema like `name,supply=1000000` into names and defaults
+///
+/// Commas inside brackets belong to the default, so arrays and tuples can
+/// be given as JSON.
+fn parse_schema(schema: &str) -> Result<Vec<(String, Option<String>)>, Error> {
+    let mut entries = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in schema.char_indices() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                entries.push(&schema[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    entries.push(&schema[start..]);
+
+    entries
+        .into_iter()
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, default) = match entry.split_once('=') {
+                Some((name, default)) => (name.trim(), Some(default.trim().to_string())),
+                None => (entry, None),
+            };
+            match name.is_empty() {
+                true => Err(Error::invalid_param(
+                    "args_schema",
+                    format!("'{}' has no name", entry),
+                )),
+                false => Ok((name.to_string(), default)),
+            }
+        })
+        .collect()
+}
+
+/// Pair the constructor's parameters with the schema's names and defaults,
+/// or with the ABI's names when there is no schema
+fn template_params(
+    inputs: &[ParamInfo],
+    schema: Option<Vec<(String, Option<String>)>>,
+) -> Result<Vec<TemplateParam>, Error> {
+    let schema = match schema {
+        Some(schema) => schema,
+        None => inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| match input.name.is_empty() {
+                true => (format!("arg{}", i), None),
+                false => (input.name.clone(), None),
+            })
+            .collect(),
+    };
+    if schema.len() != inputs.len() {
+        return Err(Error::invalid_param(
+            "args_schema",
+            format!(
+                "the constructor takes {} arguments, the schema names {}",
+                inputs.len(),
+                schema.len()
+            ),
+        ));
+    }
+
+    let mut params: Vec<TemplateParam> = Vec::with_capacity(inputs.len());
+    for (input, (name, default)) in inputs.iter().zip(schema) {
+        if params.iter().any(|p| p.name == name) {
+            return Err(Error::invalid_param(
+                "args_schema",
+                format!("'{}' is named twice", name),
+            ));
+        }
+        if let Some(default) = &default {
+            json_to_sol_value(
+                &input.param_type,
+                &param_to_json(&input.param_type, default),
+            )
+            .map_err(|e| {
+                Error::invalid_param("args_schema", format!("default of '{}': {}", name, e))
+            })?;
+        }
+        params.push(TemplateParam {
+            name,
+            ty: input.param_type.clone(),
+            default,
+        });
+    }
+    Ok(params)
+}
+
+/// Constructor arguments in order, from the values set by name and the
+/// template's defaults
+fn resolve_args(
+    params: &[TemplateParam],
+    set: &[(String, String)],
+) -> Result<Vec<serde_json::Value>, Error> {
+    if let Some((name, _)) = set
+        .iter()
+        .find(|(name, _)| !params.iter().any(|p| &p.name == name))
+    {
+        let known: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+        return Err(Error::invalid_param(
+            "set",
+            format!(
+                "the template has no parameter '{}' (it takes: {})",
+                name,
+                known.join(", ")
+            ),
+        ));
+    }
+
+    let mut missing = Vec::new();
+    let mut args = Vec::with_capacity(params.len());
+    for param in params {
+        // The last value set wins, like a repeated flag
+        let value = set
+            .iter()
+            .rev()
+            .find(|(name, _)| name == &param.name)
+            .map(|(_, value)| value)
+            .or(param.default.as_ref());
+        match value {
+            Some(value) => args.push(param_to_json(&param.ty, value)),
+            None => missing.push(param.name.as_str()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(Error::invalid_param(
+            "set",
+            format!("missing a value for {}", missing.join(", ")),
+        ));
+    }
+    Ok(args)
+}
+
+fn print_params(params: &[TemplateParam]) {
+    if params.is_empty() {
+        status!("   The constructor takes no arguments");
+        return;
+    }
+    for param in params {
+        let default = match &param.default {
+            Some(default) => format!(" = {}", default),
+            None => String::new(),
+        };
+        status!(
+            "   {} {}{}",
+            style(&param.ty).dim(),
+            param.name,
+            style(default).dim()
+        );
+    }
+}
+
+/// `--set` flags for the parameters without a default
+fn usage(params: &[TemplateParam]) -> String {
+    let required: Vec<String> = params
+        .iter()
+        .filter(|p| p.default.is_none())
+        .map(|p| format!("{}=<{}>", p.name, p.ty))
+        .collect();
+    match required.is_empty() {
+        true => String::new(),
+        false => format!(" --set {}", required.join(" ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(name: &str, ty: &str) -> ParamInfo {
+        ParamInfo {
+            name: name.to_string(),
+            param_type: ty.to_string(),
+            components: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        assert_eq!(
+            parse_schema("name, symbol,supply=1000000,holders=[\"0x1\",\"0x2\"]").unwrap(),
+            vec![
+                ("name".to_string(), None),
+                ("symbol".to_string(), None),
+                ("supply".to_string(), Some("1000000".to_string())),
+                ("holders".to_string(), Some("[\"0x1\",\"0x2\"]".to_string())),
+            ]
+        );
+        assert!(parse_schema("name,=1").is_err());
+    }
+
+    #[test]
+    fn test_template_params() {
+        let inputs = [input("name_", "string"), input("", "uint256")];
+
+        let params = template_params(&inputs, None).unwrap();
+        assert_eq!(params[0].name, "name_");
+        assert_eq!(params[1].name, "arg1");
+
+        let schema = parse_schema("name,supply=1000").unwrap();
+        let params = template_params(&inputs, Some(schema)).unwrap();
+        assert_eq!(params[1].ty, "uint256");
+        assert_eq!(params[1].default.as_deref(), Some("1000"));
+
+        assert!(template_params(&inputs, Some(parse_schema("name").unwrap())).is_err());
+        assert!(template_params(&inputs, Some(parse_schema("a,a").unwrap())).is_err());
+        // Defaults must fit the parameter's type
+        assert!(template_params(&inputs, Some(parse_schema("name,supply=lots").unwrap())).is_err());
+    }
+
+    #[test]
+    fn test_resolve_args() {
+        let inputs = [input("name", "string"), input("supply", "uint256")];
+        let params =
+            template_params(&inputs, Some(parse_schema("name,supply=1000").unwrap())).unwrap();
+        let set = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            resolve_args(&params, &set(&[("name", "Foo")])).unwrap(),
+            vec![serde_json::json!("Foo"), serde_json::json!("1000")]
+        );
+        assert_eq!(
+            resolve_args(&params, &set(&[("supply", "5"), ("name", "Foo")])).unwrap(),
+            vec![serde_json::json!("Foo"), serde_json::json!("5")]
+        );
+        assert!(resolve_args(&params, &[])
+            .unwrap_err()
+            .to_string()
+            .contains("missing a value for name"));
+        assert!(
+            resolve_args(&params, &set(&[("name", "Foo"), ("decimals", "6")]))
+                .unwrap_err()
+                .to_string()
+                .contains("no parameter 'decimals'")
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Manage API tokens accepted by `smolder serve`
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+use smolder_db::{ApiRole, ApiTokenRepository, Database};
+
+use crate::output::{self, status};
+use crate::server::auth::{generate_token, hash_token};
+
+/// Manage API tokens accepted by the server
+#[derive(Args)]
+pub struct TokenCommand {
+    #[command(subcommand)]
+    pub command: TokenSubcommand,
+}
+
+impl TokenCommand {
+    pub async fn run(self) -> Result<()> {
+        self.command.run().await
+    }
+}
+
+#[derive(Subcommand)]
+pub enum TokenSubcommand {
+    /// Create a token; the API requires one once any exists
+    Create(CreateTokenCommand),
+
+    /// List tokens
+    List(ListTokensCommand),
+
+    /// Change the role of a token
+    SetRole(SetRoleCommand),
+
+    /// Revoke a token
+    Revoke(RevokeTokenCommand),
+}
+
+impl TokenSubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Create(cmd) => cmd.run().await,
+            Self::List(cmd) => cmd.run().await,
+            Self::SetRole(cmd) => cmd.run().await,
+            Self::Revoke(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Create a token; the API requires one once any exists
+#[derive(Args)]
+pub struct CreateTokenCommand {
+    /// Token name, e.g. who or what uses it
+    pub name: String,
+
+    /// What the token may do: viewer (read only), operator (call, send and
+    /// deploy on unprotected networks) or admin (everything)
+    #[arg(long, default_value = "viewer", value_parser = parse_role)]
+    pub role: ApiRole,
+}
+
+fn parse_role(value: &str) -> Result<ApiRole, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| format!("unknown role '{}'", value))
+}
+
+impl CreateTokenCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let existing = ApiTokenRepository::list(&db).await?;
+        if existing.iter().any(|t| t.name == self.name) {
+            return Err(eyre!("Token '{}' already exists", self.name));
+        }
+
+        let secret = generate_token();
+        let token =
+            ApiTokenRepository::create(&db, &self.name, &hash_token(&secret), self.role).await?;
+
+        if output::is_json() {
+            let mut value = serde_json::to_value(&token)?;
+            value["token"] = serde_json::json!(secret);
+            return output::print_json(&value);
+        }
+
+        status!(
+            "{} Token '{}' created with the {} role",
+            style("*").green().bold(),
+            style(&token.name).cyan(),
+            token.role
+        );
+        status!("   Token: {}", style(&secret).yellow());
+        status!("   Send it as `Authorization: Bearer <token>`. It is not shown again.");
+        if existing.is_empty() {
+            status!();
+            status!(
+                "{} This is the first token: the API now rejects requests without one",
+                style("!").yellow()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// List tokens
+#[derive(Args)]
+pub struct ListTokensCommand;
+
+impl ListTokensCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let tokens = ApiTokenRepository::list(&db).await?;
+
+        if output::is_json() {
+            return output::print_json(&tokens);
+        }
+
+        if tokens.is_empty() {
+            status!(
+                "{} No tokens found; the API is open to anyone who can reach it",
+                style("!").yellow()
+            );
+            status!();
+            status!(
+                "   Create one with: {}",
+                style("smolder token create <name>").cyan()
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{:<20} {:<10} {:<20} {:<20}",
+            "Name", "Role", "Created", "Last used"
+        );
+        println!("{}", "-".repeat(73));
+
+        for t in &tokens {
+            println!(
+                "{:<20} {:<10} {:<20} {:<20}",
+                t.name,
+                t.role,
+                t.created_at,
+                t.last_used_at.as_deref().unwrap_or("never")
+            );
+        }
+
+        status!();
+        status!("Total: {} token(s)", tokens.len());
+
+        Ok(())
+    }
+}
+
+/// Change the role of a token
+#[derive(Args)]
+pub struct SetRoleCommand {
+    /// Token name
+    pub name: String,
+
+    /// New role: viewer, operator or admin
+    #[arg(value_parser = parse_role)]
+    pub role: ApiRole,
+}
+
+impl SetRoleCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        ApiTokenRepository::set_role(&db, &self.name, self.role)
+            .await
+            .map_err(|e| match e.is_not_found() {
+                true => eyre!("Token '{}' not found", self.name),
+                false => e.into(),
+            })?;
+
+        if output::is_json() {
+            return output::print_json(
+                &serde_json::json!({ "name": self.name, "role": self.role }),
+            );
+        }
+
+        status!(
+            "{} Token '{}' now has the {} role",
+            style("*").green().bold(),
+            style(&self.name).cyan(),
+            self.role
+        );
+
+        Ok(())
+    }
+}
+
+/// Revoke a token
+#[derive(Args)]
+pub struct RevokeTokenCommand {
+    /// Token name
+    pub name: String,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RevokeTokenCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+
+        if !self.force {
+            status!(
+                "{} About to revoke token '{}'",
+                style("!").yellow(),
+                style(&self.name).cyan()
+            );
+            status!("   Requests made with it will be rejected.");
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt("Are you sure you want to revoke this token?")
+                .default(false)
+                .interact()?;
+
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        ApiTokenRepository::delete(&db, &self.name)
+            .await
+            .map_err(|e| match e.is_not_found() {
+                true => eyre!("Token '{}' not found", self.name),
+                false => e.into(),
+            })?;
+
+        if output::is_json() {
+            return output::print_json(&serde_json::json!({ "name": self.name }));
+        }
+
+        status!(
+            "{} Token '{}' revoked",
+            style("*").green().bold(),
+            self.name
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+//! Inspect transactions sent outside smolder
+
+use std::collections::HashMap;
+
+use alloy::consensus::Transaction as _;
+use alloy::network::TransactionResponse;
+use alloy::primitives::Address;
+use alloy::rpc::types::Log;
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use serde::Serialize;
+use smolder_core::{decode_event_log, format_value, Abi, Currency, TxHash};
+use smolder_db::{
+    Database, DeploymentId, NetworkId, NetworkRepository, Repositories, TransactionStatus,
+};
+
+use crate::external;
+use crate::output::{self, status};
+use crate::rpc;
+
+/// Inspect transactions sent outside smolder
+#[derive(Args)]
+pub struct TxCommand {
+    #[command(subcommand)]
+    pub command: TxSubcommand,
+}
+
+impl TxCommand {
+    pub async fn run(self) -> Result<()> {
+        self.command.run().await
+    }
+}
+
+#[derive(Subcommand)]
+pub enum TxSubcommand {
+    /// Decode a transaction's input and logs against the registry
+    Inspect(InspectTxCommand),
+}
+
+impl TxSubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Inspect(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Decode a transaction's input and logs against the registry
+#[derive(Args)]
+pub struct InspectTxCommand {
+    /// Transaction hash
+    pub hash: String,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Record the transaction in call history when it calls a tracked
+    /// deployment
+    #[arg(long)]
+    pub import: bool,
+}
+
+/// A transaction decoded against the registry
+#[derive(Debug, Serialize)]
+struct TxReport {
+    tx_hash: TxHash,
+    from: String,
+    to: Option<String>,
+    /// Value sent, in wei
+    value: String,
+    /// `pending` until the transaction is mined
+    status: String,
+    block_number: Option<u64>,
+    gas_used: Option<u64>,
+    /// Effective gas price, in wei
+    gas_price: Option<String>,
+    /// Tracked deployment the transaction was sent to
+    deployment: Option<TrackedContract>,
+    /// Function called, when the registry knows its selector
+    function: Option<DecodedFunction>,
+    events: Vec<DecodedLog>,
+    /// Call history entry the transaction was imported as
+    imported: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TrackedContract {
+    id: DeploymentId,
+    contract: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DecodedFunction {
+    name: String,
+    signature: String,
+    params: serde_json::Value,
+}
+
+/// A log, decoded when its emitter is tracked and its ABI has the event
+#[derive(Debug, PartialEq, Serialize)]
+struct DecodedLog {
+    address: String,
+    contract: Option<String>,
+    event: Option<String>,
+    params: Option<serde_json::Value>,
+}
+
+/// Deployments on a network by address, with their parsed ABI
+struct Tracked<'a> {
+    db: &'a dyn Repositories,
+    network_id: NetworkId,
+    cache: HashMap<Address, Option<(TrackedContract, Abi)>>,
+}
+
+impl<'a> Tracked<'a> {
+    fn new(db: &'a dyn Repositories, network_id: NetworkId) -> Self {
+        Self {
+            db,
+            network_id,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// The latest deployment at `address`, when it is tracked and its ABI
+    /// parses
+    async fn get(&mut self, address: Address) -> Result<Option<&(TrackedContract, Abi)>> {
+        if !self.cache.contains_key(&address) {
+            let found = self.lookup(address).await?;
+            self.cache.insert(address, found);
+        }
+        Ok(self.cache[&address].as_ref())
+    }
+
+    async fn lookup(&self, address: Address) -> Result<Option<(TrackedContract, Abi)>> {
+        let Some(deployment) = self
+            .db
+            .deployments()
+            .get_by_address(self.network_id, &address.to_string())
+            .await?
+        else {
+            return Ok(None);
+        };
+        let Some(contract) = self
+            .db
+            .contracts()
+            .get_by_id(deployment.contract_id)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let Ok(abi) = Abi::parse(&contract.abi) else {
+            return Ok(None);
+        };
+        let tracked = TrackedContract {
+            id: deployment.id,
+            contract: contract.name,
+        };
+        Ok(Some((tracked, abi)))
+    }
+}
+
+impl InspectTxCommand {
+    pub async fn run(self) -> Result<()> {
+        let tx_hash: TxHash = self
+            .hash
+            .parse()
+            .map_err(|_| eyre!("Invalid transaction hash '{}'", self.hash))?;
+        let db = Database::connect().await?;
+        let network = NetworkRepository::get_by_name(&db, &self.network)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.network))?;
+
+        let (transaction, receipt) = rpc::get_transaction(network.rpc_url.expose(), &tx_hash)
+            .await?
+            .ok_or_else(|| {
+                eyre!(
+                    "Transaction {} not found on network '{}'",
+                    tx_hash,
+                    self.network
+                )
+            })?;
+
+        let mut tracked = Tracked::new(&db, network.id);
+        let input = transaction.input();
+        let target = match transaction.to() {
+            Some(to) => tracked.get(to).await?.cloned(),
+            None => None,
+        };
+
+        // The target's own ABI first, then any function the registry knows
+        let function = external::identify(&db, target.as_ref().map(|(_, abi)| abi), input)
+            .await?
+            .map(|(function, params)| DecodedFunction {
+                name: function.name.clone(),
+                signature: function.signature(),
+                params,
+            });
+
+        let events = match &receipt {
+            Some(receipt) => decode_logs(&mut tracked, receipt.inner.logs()).await?,
+            None => Vec::new(),
+        };
+        let status = receipt.as_ref().map(|r| match r.status() {
+            true => TransactionStatus::Success,
+            false => TransactionStatus::Reverted,
+        });
+
+        let mut imported = None;
+        if self.import {
+            let Some((contract, abi)) = &target else {
+                return Err(eyre!("Only calls to tracked deployments can be imported"));
+            };
+            let receipt = receipt
+                .as_ref()
+                .ok_or_else(|| eyre!("Transaction {} is not mined yet", tx_hash))?;
+            let call = external::import(&db, contract.id, Some(abi), &transaction, receipt)
+                .await?
+                .ok_or_else(|| eyre!("Transaction {} is already in call history", tx_hash))?;
+            imported = Some(call.id);
+        }
+
+        let report = TxReport {
+            tx_hash,
+            from: transaction.from().to_string(),
+            to: transaction.to().map(|to| to.to_string()),
+            value: transaction.value().to_string(),
+            status: status
+                .unwrap_or(TransactionStatus::Pending)
+                .as_str()
+                .to_string(),
+            block_number: receipt.as_ref().and_then(|r| r.block_number),
+            gas_used: receipt.as_ref().map(|r| r.gas_used),
+            gas_price: receipt.as_ref().map(|r| r.effective_gas_price.to_string()),
+            deployment: target.map(|(contract, _)| contract),
+            function,
+            events,
+            imported,
+        };
+
+        if output::is_json() {
+            return output::print_json(&report);
+        }
+        print_report(&report, network.currency());
+        Ok(())
+    }
+}
+
+/// Decode logs emitted by tracked deployments, keeping the others raw
+async fn decode_logs(tracked: &mut Tracked<'_>, logs: &[Log]) -> Result<Vec<DecodedLog>> {
+    let mut decoded = Vec::with_capacity(logs.len());
+    for log in logs {
+        let address = log.address();
+        let mut entry = DecodedLog {
+            address: address.to_string(),
+            contract: None,
+            event: None,
+            params: None,
+        };
+        if let Some((contract, abi)) = tracked.get(address).await? {
+            entry.contract = Some(contract.contract.clone());
+            let topics = log.topics();
+            if let Some(event) = topics.first().and_then(|t| abi.event_by_selector(*t)) {
+                entry.event = Some(event.signature());
+                entry.params = decode_event_log(event, topics, &log.data().data).ok();
+            }
+        }
+        decoded.push(entry);
+    }
+    Ok(decoded)
+}
+
+fn print_report(report: &TxReport, currency: Currency<'_>) {
+    let status = match report.status.as_str() {
+        "success" => style(&report.status).green(),
+        "reverted" => style(&report.status).red(),
+        _ => style(&report.status).yellow(),
+    };
+    println!("{} {}", style("Transaction:").bold(), report.tx_hash);
+    println!("  {} {}", style("status:").dim(), status);
+    println!("  {} {}", style("from:").dim(), report.from);
+    let to = match (&report.to, &report.deployment) {
+        (Some(to), Some(d)) => format!("{} ({})", to, style(&d.contract).cyan()),
+        (Some(to), None) => to.clone(),
+        (None, _) => "contract creation".to_string(),
+    };
+    println!("  {} {}", style("to:").dim(), to);
+    if report.value != "0" {
+        let value = report.value.parse().unwrap_or_default();
+        println!(
+            "  {} {}",
+            style("value:").dim(),
+            format_value(value, currency)
+        );
+    }
+    if let Some(block) = report.block_number {
+        println!("  {} {}", style("block:").dim(), block);
+    }
+    if let Some(gas_used) = report.gas_used {
+        println!("  {} {}", style("gas used:").dim(), gas_used);
+    }
+
+    println!();
+    match &report.function {
+        Some(function) => {
+            println!("{}", style(&function.signature).cyan().bold());
+            print_params(&function.params);
+        }
+        None => status!("{} Function not recognized", style("-").dim()),
+    }
+
+    if !report.events.is_empty() {
+        println!();
+        println!("{}", style("Events").bold());
+    }
+    for log in &report.events {
+        match (&log.event, &log.contract) {
+            (Some(event), Some(contract)) => {
+                println!("  {} {}", style(event).cyan(), style(contract).dim());
+            }
+            (None, Some(contract)) => {
+                println!("  {} {}", style("unknown event").dim(), contract)
+            }
+            _ => println!("  {} {}", style("untracked").dim(), log.address),
+        }
+        if let Some(params) = &log.params {
+            print_params(params);
+        }
+    }
+
+    if let Some(id) = report.imported {
+        println!();
+        status!(
+            "{} Imported into call history as #{}",
+            style("✓").green(),
+            id
+        );
+    }
+}
+
+fn print_params(params: &serde_json::Value) {
+    let Some(params) = params.as_object() else {
+        return;
+    };
+    for (name, value) in params {
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        println!("    {} {}", style(format!("{}:", name)).bold(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Bytes, LogData, B256};
+    use smolder_core::{BytecodeHash, ChainId, EvmAddress};
+    use smolder_db::{MemoryDatabase, NewContract, NewDeployment, NewNetwork};
+
+    const ABI: &str = r#"[
+        {"type":"event","name":"Transfer","anonymous":false,"inputs":[
+            {"name":"from","type":"address","indexed":true},
+            {"name":"to","type":"address","indexed":true},
+            {"name":"value","type":"uint256","indexed":false}
+        ]}
+    ]"#;
+
+    #[tokio::test]
+    async fn test_decode_logs() {
+        let db = MemoryDatabase::new();
+        let network = db
+            .networks()
+            .upsert(&NewNetwork {
+                name: "local".to_string(),
+                chain_id: ChainId(31337),
+                rpc_url: "http://localhost:8545".to_string(),
+                explorer_url: None,
+            })
+            .await
+            .unwrap();
+        let contract = db
+            .contracts()
+            .upsert(&NewContract {
+                name: "Token".to_string(),
+                source_path: "src/Token.sol".to_string(),
+                abi: ABI.to_string(),
+                bytecode_hash: BytecodeHash::of([0x60]),
+                storage_layout: None,
+                compiler: None,
+            })
+            .await
+            .unwrap();
+        let token = Address::repeat_byte(0x0a);
+        db.deployments()
+            .create(&NewDeployment {
+                contract_id: contract.id,
+                network_id: network.id,
+                address: EvmAddress::from(token),
+                deployer: Address::ZERO.to_string(),
+                tx_hash: None,
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            })
+            .await
+            .unwrap();
+
+        let transfer = alloy::primitives::keccak256("Transfer(address,address,uint256)");
+        let log = |address: Address| Log {
+            inner: alloy::primitives::Log {
+                address,
+                data: LogData::new_unchecked(
+                    vec![
+                        transfer,
+                        B256::left_padding_from(&[0x01]),
+                        B256::left_padding_from(&[0x02]),
+                    ],
+                    Bytes::from(B256::left_padding_from(&[7]).to_vec()),
+                ),
+            },
+            ..Default::default()
+        };
+        let untracked = Address::repeat_byte(0x0b);
+
+        let mut tracked = Tracked::new(&db, network.id);
+        let decoded = decode_logs(&mut tracked, &[log(token), log(untracked)])
+            .await
+            .unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].contract.as_deref(), Some("Token"));
+        assert_eq!(
+            decoded[0].event.as_deref(),
+            Some("Transfer(address,address,uint256)")
+        );
+        assert_eq!(decoded[0].params.as_ref().unwrap()["value"], "7");
+        assert_eq!(
+            decoded[1],
+            DecodedLog {
+                address: untracked.to_string(),
+                contract: None,
+                event: None,
+                params: None,
+            }
+        );
+    }
+}
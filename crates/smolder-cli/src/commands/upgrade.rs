@@ -0,0 +1,413 @@
+//! Upgrade a tracked proxy to a new implementation
+
+use std::time::Duration;
+
+use alloy::primitives::{Address, Bytes};
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+use serde::Serialize;
+use smolder_core::storage::IMPLEMENTATION_SLOT;
+use smolder_core::{decrypt_private_key, parse_function_signature};
+use smolder_db::{
+    CallHistory, CallHistoryUpdate, CallType, Database, Deployment, DeploymentRepository,
+    NetworkRepository, NewCallHistory, NewContract, NewContractDeployment, NewUpgrade,
+    TransactionStatus, WalletRepository,
+};
+
+use super::raw::encode_args;
+use crate::config::SmolderConfig;
+use crate::deploy_group::Creation;
+use crate::forge;
+use crate::layout;
+use crate::locks::Clearance;
+use crate::output::{self, status};
+use crate::policy::{self, Intent};
+use crate::rpc::{self, Outgoing};
+use crate::upgrade::{self, LayoutCheck};
+
+/// How long to wait for the upgrade transaction to be mined
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Deploy a new implementation and point a UUPS or transparent proxy at it
+#[derive(Args)]
+pub struct UpgradeCommand {
+    /// Proxy contract name
+    pub proxy: String,
+
+    /// Artifact of the new implementation
+    #[arg(long, value_name = "ARTIFACT")]
+    pub new_impl: String,
+
+    /// Network name
+    #[arg(long)]
+    pub network: String,
+
+    /// Wallet allowed to upgrade the proxy
+    #[arg(long)]
+    pub wallet: String,
+
+    /// Function to call on the new implementation as part of the upgrade,
+    /// e.g. "initializeV2(uint256)"
+    #[arg(long, value_name = "SIG")]
+    pub call: Option<String>,
+
+    /// Arguments for --call (arrays and tuples as JSON)
+    #[arg(requires = "call")]
+    pub args: Vec<String>,
+
+    /// Upgrade even when the storage layout conflicts with the current
+    /// implementation
+    #[arg(long)]
+    pub unsafe_skip_storage_check: bool,
+
+    /// Confirm an upgrade on a locked network by repeating its name
+    #[arg(long, value_name = "NETWORK")]
+    pub unlock_confirm: Option<String>,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+/// Result of an upgrade, printed with `--json`
+#[derive(Serialize)]
+struct UpgradeReport {
+    proxy: String,
+    previous_implementation: String,
+    implementation: Deployment,
+    call: CallHistory,
+}
+
+impl UpgradeCommand {
+    pub async fn run(self) -> Result<()> {
+        let config = SmolderConfig::load()?;
+        let db = Database::connect().await?;
+        let loader = forge::artifact_loader(config.project.framework);
+
+        let network = NetworkRepository::get_by_name(&db, &self.network)
+            .await?
+            .ok_or_else(|| eyre!("Network '{}' not found", self.network))?;
+        let wallet = WalletRepository::get_with_key(&db, &self.wallet)
+            .await?
+            .ok_or_else(|| eyre!("Wallet '{}' not found", self.wallet))?;
+        let proxy_deployment = DeploymentRepository::get_current(&db, &self.proxy, &self.network)
+            .await?
+            .ok_or_else(|| {
+                eyre!(
+                    "No deployment found for contract '{}' on network '{}'",
+                    self.proxy,
+                    self.network
+                )
+            })?;
+        let creation = Creation::load(loader.as_ref(), &self.new_impl, &[], None)?;
+
+        let wallet_address = *wallet.address;
+        let proxy = upgrade::inspect(
+            network.rpc_url.expose(),
+            *proxy_deployment.address,
+            wallet_address,
+        )
+        .await?;
+
+        // Layout conflicts are checked before anything is sent
+        let storage_layout = creation.details.storage_layout_json();
+        match upgrade::check_layout(
+            &db,
+            network.id,
+            proxy.implementation,
+            storage_layout.as_deref(),
+        )
+        .await?
+        {
+            LayoutCheck::Checked(issues) => {
+                layout::print_issues(&self.new_impl, &issues);
+                if issues.iter().any(|i| i.kind.is_breaking()) && !self.unsafe_skip_storage_check {
+                    return Err(eyre!(
+                        "The storage layout of {} is incompatible with the current implementation. Pass {} to upgrade anyway",
+                        self.new_impl,
+                        style("--unsafe-skip-storage-check").yellow()
+                    ));
+                }
+            }
+            LayoutCheck::Skipped(reason) => status!(
+                "{} Storage layout not checked: {}",
+                style("!").yellow(),
+                reason
+            ),
+        }
+
+        let init = match &self.call {
+            Some(sig) => {
+                let function = parse_function_signature(sig)?;
+                let ens_network = config.ens.network.as_deref();
+                encode_args(&db, &network, ens_network, &function, &self.args).await?
+            }
+            None => Bytes::new(),
+        };
+
+        upgrade::simulate(network.rpc_url.expose(), &proxy, wallet_address).await?;
+        let preflight = rpc::preflight(
+            &network,
+            Outgoing {
+                from: wallet_address,
+                to: None,
+                data: &creation.data,
+                value: None,
+            },
+        )
+        .await?;
+        for warning in &preflight.warnings {
+            status!("{} {}", style("!").yellow(), warning.message);
+        }
+
+        if !self.force {
+            status!(
+                "{} About to upgrade {} on {}",
+                style("!").yellow(),
+                style(&self.proxy).cyan(),
+                style(&network.name).cyan()
+            );
+            status!("   Proxy:          {} ({})", proxy.address, proxy.path);
+            status!("   Implementation: {}", proxy.implementation);
+            status!("   New:            {}", style(&self.new_impl).cyan());
+            if let Some(sig) = &self.call {
+                status!("   Call:           {}", sig);
+            }
+            status!("   From:           {} ({})", wallet.name, wallet.address);
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt("Deploy the implementation and upgrade?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        Clearance::cli(self.unlock_confirm)
+            .check(
+                &db,
+                &network,
+                &format!("upgrade {} to {}", self.proxy, self.new_impl),
+            )
+            .await?;
+        // The upgrade call only differs from this one by its implementation
+        let planned = upgrade::upgrade_call(&proxy, proxy.implementation, init.clone());
+        let target = (planned.to == proxy.address).then_some(self.proxy.as_str());
+        for intent in [
+            Intent::deploy(&network, &self.new_impl, None),
+            Intent::call(
+                &network,
+                planned.to,
+                target,
+                planned.function_signature,
+                None,
+            ),
+        ] {
+            policy::check(&db, wallet.id, &wallet.name, &intent).await?;
+        }
+        let private_key = decrypt_private_key(&wallet.encrypted_key)?;
+
+        status!(
+            "{} Deploying {}...",
+            style("→").blue(),
+            style(&self.new_impl).cyan()
+        );
+        let intent = Intent::deploy(&network, &self.new_impl, None);
+        let reservation = policy::reserve(&db, wallet.id, &wallet.name, &intent).await?;
+        let creation_receipt = rpc::deploy_contract(
+            network.rpc_url.expose(),
+            &private_key,
+            creation.data.clone(),
+            None,
+        )
+        .await;
+        let creation_receipt = reservation.settle(&db, creation_receipt).await?;
+        let implementation: Address = creation_receipt
+            .contract_address
+            .ok_or_else(|| {
+                eyre!(
+                    "{} was mined without creating a contract",
+                    creation_receipt.tx_hash
+                )
+            })?
+            .into();
+        status!("   {} at {}", style("✓").green(), implementation);
+
+        let call = upgrade::upgrade_call(&proxy, implementation, init);
+        status!(
+            "{} Calling {}...",
+            style("→").blue(),
+            style(call.function_name).cyan()
+        );
+        let target = (call.to == proxy.address).then_some(self.proxy.as_str());
+        let intent = Intent::call(&network, call.to, target, call.function_signature, None);
+        let reservation = policy::reserve(&db, wallet.id, &wallet.name, &intent).await?;
+        let outcome = send_upgrade(
+            network.rpc_url.expose(),
+            network.private_relay.as_deref(),
+            &private_key,
+            &call,
+        )
+        .await;
+        if outcome.tx_hash.is_none() {
+            reservation.release(&db).await?;
+        }
+
+        // Verify the proxy really points at the new implementation
+        if outcome.status == TransactionStatus::Success {
+            let current =
+                rpc::get_storage_at(network.rpc_url.expose(), proxy.address, IMPLEMENTATION_SLOT)
+                    .await
+                    .map(Address::from_word);
+            if !matches!(current, Ok(current) if current == implementation) {
+                status!(
+                    "{} The upgrade was mined, but the proxy's implementation slot doesn't hold {}",
+                    style("!").yellow(),
+                    implementation
+                );
+            }
+        }
+
+        let details = &creation.details;
+        let (implementation_deployment, history) = DeploymentRepository::record_upgrade(
+            &db,
+            &NewUpgrade {
+                network_id: network.id,
+                implementation: NewContractDeployment {
+                    contract: NewContract {
+                        name: details.name.clone(),
+                        source_path: details.source_path.clone(),
+                        abi: serde_json::to_string(&details.abi)?,
+                        bytecode_hash: creation.bytecode_hash(),
+                        storage_layout,
+                        compiler: details.compiler.clone(),
+                    },
+                    address: implementation.into(),
+                    deployer: wallet.address.to_string(),
+                    tx_hash: Some(creation_receipt.tx_hash),
+                    block_number: creation_receipt.block_number.map(|b| b as i64),
+                    constructor_args: None,
+                    initcode_size: Some(details.initcode_size as i64),
+                    runtime_size: Some(details.runtime_size as i64),
+                    gas_used: Some(creation_receipt.gas_used as i64),
+                    gas_price: Some(creation_receipt.gas_price.to_string()),
+                },
+                call: NewCallHistory {
+                    deployment_id: proxy_deployment.id,
+                    wallet_id: Some(wallet.id),
+                    function_name: call.function_name.to_string(),
+                    function_signature: call.function_signature.to_string(),
+                    input_params: call.params.to_string(),
+                    call_type: CallType::Write,
+                },
+                outcome: outcome.clone(),
+            },
+        )
+        .await?;
+
+        match outcome.status {
+            TransactionStatus::Success => {}
+            TransactionStatus::Pending => {
+                return Err(eyre!(
+                    "{} was sent but not mined after {}s; {} is recorded, check the transaction before retrying",
+                    outcome.tx_hash.unwrap_or_default(),
+                    RECEIPT_TIMEOUT.as_secs(),
+                    self.new_impl
+                ))
+            }
+            _ => {
+                return Err(eyre!(
+                    "Upgrade failed: {}. {} stays deployed at {} and is recorded",
+                    outcome
+                        .error_message
+                        .unwrap_or_else(|| "the transaction reverted".to_string()),
+                    self.new_impl,
+                    implementation
+                ))
+            }
+        }
+
+        if output::is_json() {
+            return output::print_json(&UpgradeReport {
+                proxy: proxy.address.to_string(),
+                previous_implementation: proxy.implementation.to_string(),
+                implementation: implementation_deployment,
+                call: history,
+            });
+        }
+
+        status!();
+        status!(
+            "{} {} now points to {} v{} at {}",
+            style("✓").green().bold(),
+            style(&self.proxy).cyan(),
+            style(&self.new_impl).cyan(),
+            implementation_deployment.version,
+            implementation
+        );
+        status!(
+            "   Upgrade tx: {}",
+            style(history.tx_hash.unwrap_or_default()).yellow()
+        );
+        Ok(())
+    }
+}
+
+/// Send the upgrade and wait for it to be mined, describing the outcome for
+/// the call history
+async fn send_upgrade(
+    rpc_url: &str,
+    relay_url: Option<&str>,
+    private_key: &str,
+    call: &upgrade::UpgradeCall,
+) -> CallHistoryUpdate {
+    let mut outcome = CallHistoryUpdate {
+        result: None,
+        tx_hash: None,
+        block_number: None,
+        gas_used: None,
+        gas_price: None,
+        status: TransactionStatus::Failed,
+        error_message: None,
+    };
+
+    let tx_hash = match rpc::execute_transaction(
+        rpc_url,
+        relay_url,
+        private_key,
+        call.to,
+        call.data.clone(),
+        None,
+    )
+    .await
+    {
+        Ok(tx_hash) => tx_hash,
+        Err(e) => {
+            outcome.error_message = Some(e.to_string());
+            return outcome;
+        }
+    };
+    outcome.tx_hash = Some(tx_hash);
+
+    match rpc::wait_for_receipt(rpc_url, &tx_hash, RECEIPT_TIMEOUT).await {
+        Ok(receipt) => {
+            outcome.block_number = receipt.block_number.map(|b| b as i64);
+            outcome.gas_used = Some(receipt.gas_used as i64);
+            outcome.gas_price = Some(receipt.effective_gas_price.to_string());
+            outcome.status = match receipt.status() {
+                true => TransactionStatus::Success,
+                false => TransactionStatus::Reverted,
+            };
+        }
+        Err(e) => {
+            outcome.status = TransactionStatus::Pending;
+            outcome.error_message = Some(e.to_string());
+        }
+    }
+    outcome
+}
@@ -1,12 +1,28 @@
 //! Manage wallets for signing transactions
 
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use alloy::signers::local::PrivateKeySigner;
 use clap::{Args, Subcommand};
 use color_eyre::eyre::{eyre, Result};
 use console::style;
 use dialoguer::{Confirm, Password};
-use smolder_core::encrypt_private_key;
-use smolder_db::{Database, NewWallet, WalletRepository};
+use futures::future::join_all;
+use smolder_core::{encrypt_private_key, format_value, EvmAddress};
+use smolder_db::{
+    Database, NetworkRepository, NewWallet, Wallet, WalletPolicy, WalletPolicyRepository,
+    WalletRepository,
+};
+
+use crate::output::{self, status};
+use crate::wallet_backup::{self, Restore};
+use crate::{policy, rpc};
+
+/// How long to wait for each network's balance and nonce
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Manage wallets for signing transactions
 #[derive(Args)]
@@ -29,8 +45,23 @@ pub enum WalletSubcommand {
     /// List all wallets
     List(ListWalletsCommand),
 
+    /// Show a wallet and the keys it had
+    Show(ShowWalletCommand),
+
+    /// Replace the private key of a wallet, keeping its history
+    Rotate(RotateWalletCommand),
+
     /// Remove a wallet
     Remove(RemoveWalletCommand),
+
+    /// Write wallets to a passphrase-encrypted bundle
+    Backup(BackupWalletsCommand),
+
+    /// Add the wallets of a bundle written by `wallet backup`
+    Restore(RestoreWalletsCommand),
+
+    /// Limit what a wallet may sign
+    Policy(PolicyCommand),
 }
 
 impl WalletSubcommand {
@@ -38,7 +69,12 @@ impl WalletSubcommand {
         match self {
             Self::Add(cmd) => cmd.run().await,
             Self::List(cmd) => cmd.run().await,
+            Self::Show(cmd) => cmd.run().await,
+            Self::Rotate(cmd) => cmd.run().await,
             Self::Remove(cmd) => cmd.run().await,
+            Self::Backup(cmd) => cmd.run().await,
+            Self::Restore(cmd) => cmd.run().await,
+            Self::Policy(cmd) => cmd.command.run().await,
         }
     }
 }
@@ -63,33 +99,17 @@ impl AddWalletCommand {
         }
 
         // Prompt for private key
-        println!(
+        status!(
             "{} Adding wallet '{}'",
             style("->").blue(),
             style(&self.name).cyan()
         );
-        println!();
-
-        let private_key: String = Password::new()
-            .with_prompt("Enter private key (with or without 0x prefix)")
-            .interact()?;
+        status!();
 
-        // Normalize private key (add 0x prefix if missing)
-        let private_key = if private_key.starts_with("0x") {
-            private_key
-        } else {
-            format!("0x{}", private_key)
-        };
-
-        // Parse and validate private key, get address
-        let signer: PrivateKeySigner = private_key
-            .parse()
-            .map_err(|e| eyre!("Invalid private key: {}", e))?;
-
-        let address = format!("{:?}", signer.address());
+        let (private_key, address) = prompt_private_key("Enter private key")?;
 
         // Check if address already exists
-        if WalletRepository::get_by_address(&db, &address)
+        if WalletRepository::get_by_address(&db, &address.to_string())
             .await?
             .is_some()
         {
@@ -103,23 +123,27 @@ impl AddWalletCommand {
         let encrypted_key = encrypt_private_key(&private_key)
             .map_err(|e| eyre!("Failed to encrypt private key: {}", e))?;
 
-        WalletRepository::create(
+        let wallet = WalletRepository::create(
             &db,
             &NewWallet {
                 name: self.name.clone(),
-                address: address.clone(),
+                address,
                 encrypted_key,
             },
         )
         .await?;
 
-        println!();
-        println!(
+        if output::is_json() {
+            return output::print_json(&wallet);
+        }
+
+        status!();
+        status!(
             "{} Wallet '{}' added successfully",
             style("*").green().bold(),
             style(&self.name).cyan()
         );
-        println!("   Address: {}", style(&address).yellow());
+        status!("   Address: {}", style(&address).yellow());
 
         Ok(())
     }
@@ -134,18 +158,22 @@ impl ListWalletsCommand {
         let db = Database::connect().await?;
         let wallets = WalletRepository::list(&db).await?;
 
+        if output::is_json() {
+            return output::print_json(&wallets);
+        }
+
         if wallets.is_empty() {
-            println!("{} No wallets found", style("!").yellow());
-            println!();
-            println!(
+            status!("{} No wallets found", style("!").yellow());
+            status!();
+            status!(
                 "   Add a wallet with: {}",
                 style("smolder wallet add <name>").cyan()
             );
             return Ok(());
         }
 
-        println!("{} {} wallet(s) found", style("*").green(), wallets.len());
-        println!();
+        status!("{} {} wallet(s) found", style("*").green(), wallets.len());
+        status!();
 
         for wallet in wallets {
             println!(
@@ -156,8 +184,367 @@ impl ListWalletsCommand {
             );
         }
 
+        status!();
+
+        Ok(())
+    }
+}
+
+/// Show a wallet: its balance and nonce on each network, its policy, and
+/// what it deployed and called
+#[derive(Args)]
+pub struct ShowWalletCommand {
+    /// Wallet name
+    pub name: String,
+
+    /// Number of recent deployments and calls to show
+    #[arg(long, default_value_t = 10)]
+    pub limit: u32,
+}
+
+impl ShowWalletCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let wallet = get_wallet(&db, &self.name).await?;
+        let rotations = WalletRepository::rotations(&db, wallet.id).await?;
+        let rules = WalletPolicyRepository::get(&db, wallet.id).await?;
+        let deployments = WalletRepository::deployments(&db, wallet.id, self.limit).await?;
+        let calls = WalletRepository::calls(&db, wallet.id, self.limit).await?;
+
+        let networks = NetworkRepository::list(&db).await?;
+        let probes = networks.iter().map(|network| async move {
+            let account = tokio::time::timeout(
+                RPC_TIMEOUT,
+                rpc::get_account(network.rpc_url.expose(), wallet.address.0),
+            )
+            .await;
+            match account {
+                Ok(Ok(account)) => Ok(account),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("no answer within {}s", RPC_TIMEOUT.as_secs())),
+            }
+        });
+        let accounts = join_all(probes).await;
+
+        if output::is_json() {
+            let accounts: Vec<_> = networks
+                .iter()
+                .zip(&accounts)
+                .map(|(network, account)| match account {
+                    Ok((balance, nonce)) => serde_json::json!({
+                        "network": network.name,
+                        "balance": balance.to_string(),
+                        "nonce": nonce,
+                    }),
+                    Err(e) => serde_json::json!({
+                        "network": network.name,
+                        "error": e,
+                    }),
+                })
+                .collect();
+            return output::print_json(&serde_json::json!({
+                "wallet": wallet,
+                "rotations": rotations,
+                "networks": accounts,
+                "policy": rules,
+                "deployments": deployments,
+                "calls": calls,
+            }));
+        }
+
+        status!(
+            "{} Wallet '{}'",
+            style("*").green(),
+            style(&wallet.name).cyan().bold()
+        );
+        println!("   Address: {}", style(&wallet.address).yellow());
+        println!("   Created: {}", wallet.created_at);
+
+        if !rotations.is_empty() {
+            println!();
+            println!("   Key rotations:");
+            for rotation in &rotations {
+                println!(
+                    "     {} {} -> {}",
+                    style(&rotation.rotated_at).dim(),
+                    rotation.old_address,
+                    rotation.new_address
+                );
+            }
+        }
+
+        if !networks.is_empty() {
+            println!();
+            println!("   Networks:");
+            for (network, account) in networks.iter().zip(&accounts) {
+                match account {
+                    Ok((balance, nonce)) => println!(
+                        "     {:<16} {}  nonce {}",
+                        style(&network.name).cyan(),
+                        format_value(*balance, network.currency()),
+                        nonce
+                    ),
+                    Err(e) => println!(
+                        "     {:<16} {}",
+                        style(&network.name).cyan(),
+                        style(format!("unreachable: {}", e)).red()
+                    ),
+                }
+            }
+        }
+
         println!();
+        match &rules {
+            Some(rules) => {
+                println!("   Policy:");
+                for line in policy::describe(rules) {
+                    println!("     {}", line);
+                }
+            }
+            None => println!("   Policy: none"),
+        }
+
+        if !deployments.is_empty() {
+            println!();
+            println!("   Recent deployments:");
+            for deployment in &deployments {
+                println!(
+                    "     {} {} v{} on {} at {}",
+                    style(&deployment.deployed_at).dim(),
+                    style(&deployment.contract_name).cyan(),
+                    deployment.version,
+                    deployment.network_name,
+                    style(&deployment.address).yellow()
+                );
+            }
+        }
 
+        if !calls.is_empty() {
+            println!();
+            println!("   Recent calls:");
+            for call in &calls {
+                let status = call
+                    .status
+                    .map_or_else(|| call.call_type.to_string(), |s| s.to_string());
+                println!(
+                    "     {} {}.{} on {} ({})",
+                    style(&call.created_at).dim(),
+                    style(&call.contract_name).cyan(),
+                    call.function_name,
+                    call.network_name,
+                    status
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replace the private key of a wallet
+///
+/// The wallet keeps its name, policy, and the calls and deployments it made;
+/// the address it had is kept in its rotation history.
+#[derive(Args)]
+pub struct RotateWalletCommand {
+    /// Wallet name
+    pub name: String,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RotateWalletCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let wallet = get_wallet(&db, &self.name).await?;
+
+        status!(
+            "{} Rotating the key of wallet '{}'",
+            style("->").blue(),
+            style(&wallet.name).cyan()
+        );
+        status!("   Current address: {}", style(&wallet.address).yellow());
+        status!();
+
+        let (private_key, address) = prompt_private_key("Enter the new private key")?;
+        if address == wallet.address {
+            return Err(eyre!("Wallet '{}' already uses this key", wallet.name));
+        }
+        if let Some(other) = WalletRepository::get_by_address(&db, &address.to_string()).await? {
+            return Err(eyre!(
+                "Wallet '{}' already has address {}",
+                other.name,
+                style(&address).yellow()
+            ));
+        }
+
+        if !self.force {
+            status!(
+                "{} {} will replace {}",
+                style("!").yellow(),
+                style(&address).yellow(),
+                wallet.address
+            );
+            let confirmed = Confirm::new()
+                .with_prompt("Replace the key of this wallet?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        let encrypted_key = encrypt_private_key(&private_key)
+            .map_err(|e| eyre!("Failed to encrypt private key: {}", e))?;
+        let rotated = WalletRepository::rotate(&db, &wallet.name, address, &encrypted_key).await?;
+
+        if output::is_json() {
+            return output::print_json(&serde_json::json!({
+                "wallet": rotated,
+                "old_address": wallet.address,
+            }));
+        }
+
+        status!();
+        status!(
+            "{} Key of wallet '{}' rotated",
+            style("*").green().bold(),
+            style(&rotated.name).cyan()
+        );
+        status!("   Address: {}", style(&rotated.address).yellow());
+        status!(
+            "   Fund the new address; {} keeps the funds left at the old one",
+            wallet.address
+        );
+        Ok(())
+    }
+}
+
+/// Write wallets, with their keys and policies, to a passphrase-encrypted
+/// bundle for another machine
+#[derive(Args)]
+pub struct BackupWalletsCommand {
+    /// Wallets to include; all of them by default
+    pub names: Vec<String>,
+
+    /// File to write the bundle to
+    #[arg(long, short)]
+    pub output: PathBuf,
+
+    /// Overwrite the file if it exists
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl BackupWalletsCommand {
+    pub async fn run(self) -> Result<()> {
+        if self.output.exists() && !self.force {
+            return Err(eyre!(
+                "{} already exists; use --force to overwrite it",
+                self.output.display()
+            ));
+        }
+        let db = Database::connect().await?;
+
+        let passphrase = Password::new()
+            .with_prompt("Passphrase for the bundle")
+            .with_confirmation("Repeat the passphrase", "Passphrases don't match")
+            .interact()?;
+        if passphrase.is_empty() {
+            return Err(eyre!("The passphrase can't be empty"));
+        }
+
+        let (bundle, names) = wallet_backup::backup(&db, &self.names, &passphrase).await?;
+        if names.is_empty() {
+            return Err(eyre!("No wallets to back up"));
+        }
+        write_private(&self.output, &bundle)
+            .map_err(|e| eyre!("Could not write {}: {}", self.output.display(), e))?;
+
+        if output::is_json() {
+            return output::print_json(&serde_json::json!({
+                "output": self.output,
+                "wallets": names,
+            }));
+        }
+        status!(
+            "{} Backed up {} wallet(s) to {}",
+            style("*").green().bold(),
+            names.len(),
+            style(self.output.display()).cyan()
+        );
+        status!("   It holds private keys; keep it, and its passphrase, safe");
+        Ok(())
+    }
+}
+
+/// Write `contents` to `path`, readable and writable by the owner only, as
+/// it holds keys however well encrypted
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, not to one being overwritten
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+/// Add the wallets of a bundle written by `wallet backup`, checking each
+/// key against the address recorded with it
+#[derive(Args)]
+pub struct RestoreWalletsCommand {
+    /// Bundle to restore from
+    pub file: PathBuf,
+}
+
+impl RestoreWalletsCommand {
+    pub async fn run(self) -> Result<()> {
+        let bundle = std::fs::read(&self.file)
+            .map_err(|e| eyre!("Could not read {}: {}", self.file.display(), e))?;
+        let db = Database::connect().await?;
+
+        let passphrase = Password::new()
+            .with_prompt("Passphrase of the bundle")
+            .interact()?;
+        let restored = wallet_backup::restore(&db, &bundle, &passphrase).await?;
+
+        if output::is_json() {
+            return output::print_json(&restored);
+        }
+        for wallet in &restored {
+            let outcome = match &wallet.restore {
+                Restore::Added => style("added".to_string()).green(),
+                Restore::Present => style("already present".to_string()).dim(),
+                Restore::NameTaken { existing } => {
+                    style(format!("skipped, '{}' has {}", wallet.name, existing)).yellow()
+                }
+                Restore::AddressTaken { existing } => {
+                    style(format!("skipped, address is wallet '{}'", existing)).yellow()
+                }
+            };
+            status!(
+                "   {:<20} {} {}",
+                wallet.name,
+                style(&wallet.address).yellow(),
+                outcome
+            );
+        }
+        let added = restored
+            .iter()
+            .filter(|w| w.restore == Restore::Added)
+            .count();
+        status!();
+        status!(
+            "{} Restored {} of {} wallet(s)",
+            style("*").green().bold(),
+            added,
+            restored.len()
+        );
         Ok(())
     }
 }
@@ -184,13 +571,13 @@ impl RemoveWalletCommand {
 
         // Confirm deletion unless forced
         if !self.force {
-            println!(
+            status!(
                 "{} About to remove wallet '{}'",
                 style("!").yellow(),
                 style(&self.name).cyan()
             );
-            println!("   Address: {}", style(&wallet.address).yellow());
-            println!();
+            status!("   Address: {}", style(&wallet.address).yellow());
+            status!();
 
             let confirmed = Confirm::new()
                 .with_prompt("Are you sure you want to remove this wallet?")
@@ -198,7 +585,7 @@ impl RemoveWalletCommand {
                 .interact()?;
 
             if !confirmed {
-                println!("{} Cancelled", style("*").dim());
+                status!("{} Cancelled", style("*").dim());
                 return Ok(());
             }
         }
@@ -206,8 +593,12 @@ impl RemoveWalletCommand {
         // Delete wallet from database
         WalletRepository::delete(&db, &self.name).await?;
 
-        println!();
-        println!(
+        if output::is_json() {
+            return output::print_json(&wallet);
+        }
+
+        status!();
+        status!(
             "{} Wallet '{}' removed",
             style("*").green().bold(),
             style(&self.name).cyan()
@@ -216,3 +607,283 @@ impl RemoveWalletCommand {
         Ok(())
     }
 }
+
+/// Limit what a wallet may sign
+///
+/// Deploys and sends signed with the wallet, from the CLI or the API, fail
+/// with a policy violation when they break one of its rules.
+#[derive(Args)]
+pub struct PolicyCommand {
+    #[command(subcommand)]
+    pub command: PolicySubcommand,
+}
+
+#[derive(Subcommand)]
+pub enum PolicySubcommand {
+    /// Set rules of a wallet's policy, keeping the ones not given
+    Set(SetPolicyCommand),
+
+    /// Show a wallet's policy and what it sent in the last 24 hours
+    Show(ShowPolicyCommand),
+
+    /// Remove a wallet's policy, lifting all its limits
+    Clear(ClearPolicyCommand),
+}
+
+impl PolicySubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Set(cmd) => cmd.run().await,
+            Self::Show(cmd) => cmd.run().await,
+            Self::Clear(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Set rules of a wallet's policy
+///
+/// Each rule given replaces the current one; `none` removes it.
+#[derive(Args)]
+pub struct SetPolicyCommand {
+    /// Wallet name
+    pub wallet: String,
+
+    /// Max value of a transaction, in wei or e.g. `0.5 ether`
+    #[arg(long, value_name = "VALUE")]
+    pub max_value: Option<String>,
+
+    /// Max value sent per network over 24 hours, in wei or e.g. `2 ether`
+    #[arg(long, value_name = "VALUE")]
+    pub max_daily: Option<String>,
+
+    /// Networks the wallet may use, comma-separated
+    #[arg(long, value_name = "NAMES")]
+    pub networks: Option<String>,
+
+    /// Contracts the wallet may deploy or call, by name or address,
+    /// comma-separated
+    #[arg(long, value_name = "CONTRACTS")]
+    pub contracts: Option<String>,
+
+    /// Functions the wallet may call, by name or full signature,
+    /// comma-separated
+    #[arg(long, value_name = "FUNCTIONS")]
+    pub functions: Option<String>,
+}
+
+impl SetPolicyCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let wallet = get_wallet(&db, &self.wallet).await?;
+        let mut rules = WalletPolicyRepository::get(&db, wallet.id)
+            .await?
+            .unwrap_or_default();
+
+        if let Some(value) = self.max_value.as_deref().map(unless_none) {
+            rules.max_value_per_tx = value.map(String::from);
+        }
+        if let Some(value) = self.max_daily.as_deref().map(unless_none) {
+            rules.max_daily_spend = value.map(String::from);
+        }
+        if let Some(list) = self.networks.as_deref().map(unless_none) {
+            rules.allowed_networks = list.map(split_list);
+        }
+        if let Some(list) = self.contracts.as_deref().map(unless_none) {
+            rules.allowed_contracts = list.map(split_list);
+        }
+        if let Some(list) = self.functions.as_deref().map(unless_none) {
+            rules.allowed_functions = list.map(split_list);
+        }
+        let rules = policy::normalize(&db, rules).await?;
+
+        if rules == WalletPolicy::default() {
+            WalletPolicyRepository::clear(&db, wallet.id).await?;
+        } else {
+            WalletPolicyRepository::set(&db, wallet.id, &rules).await?;
+        }
+
+        if output::is_json() {
+            return output::print_json(&rules);
+        }
+        status!(
+            "{} Policy of wallet '{}' updated",
+            style("*").green().bold(),
+            style(&wallet.name).cyan()
+        );
+        print_rules(&rules);
+        Ok(())
+    }
+}
+
+/// Show a wallet's policy
+#[derive(Args)]
+pub struct ShowPolicyCommand {
+    /// Wallet name
+    pub wallet: String,
+}
+
+impl ShowPolicyCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let wallet = get_wallet(&db, &self.wallet).await?;
+        let rules = WalletPolicyRepository::get(&db, wallet.id).await?;
+
+        let mut spent = Vec::new();
+        for network in NetworkRepository::list(&db).await? {
+            let value = policy::spent_today(&db, wallet.id, &network).await?;
+            if !value.is_zero() {
+                spent.push((network, value));
+            }
+        }
+
+        if output::is_json() {
+            let spent: serde_json::Map<_, _> = spent
+                .iter()
+                .map(|(network, value)| (network.name.clone(), value.to_string().into()))
+                .collect();
+            return output::print_json(&serde_json::json!({
+                "wallet": wallet.name,
+                "policy": rules,
+                "spent_24h": spent,
+            }));
+        }
+
+        match &rules {
+            Some(rules) => {
+                status!(
+                    "{} Policy of wallet '{}'",
+                    style("*").green(),
+                    style(&wallet.name).cyan()
+                );
+                print_rules(rules);
+            }
+            None => status!(
+                "{} Wallet '{}' has no policy",
+                style("!").yellow(),
+                style(&wallet.name).cyan()
+            ),
+        }
+        if !spent.is_empty() {
+            status!();
+            status!("   Sent in the last 24 hours:");
+            for (network, value) in &spent {
+                status!(
+                    "     {} {}",
+                    style(&network.name).cyan(),
+                    format_value(*value, network.currency())
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Remove a wallet's policy
+#[derive(Args)]
+pub struct ClearPolicyCommand {
+    /// Wallet name
+    pub wallet: String,
+}
+
+impl ClearPolicyCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let wallet = get_wallet(&db, &self.wallet).await?;
+        let cleared = WalletPolicyRepository::clear(&db, wallet.id).await?;
+
+        if output::is_json() {
+            return output::print_json(&serde_json::json!({
+                "wallet": wallet.name,
+                "cleared": cleared,
+            }));
+        }
+        match cleared {
+            true => status!(
+                "{} Policy of wallet '{}' removed",
+                style("*").green().bold(),
+                style(&wallet.name).cyan()
+            ),
+            false => status!(
+                "{} Wallet '{}' has no policy",
+                style("!").yellow(),
+                style(&wallet.name).cyan()
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Ask for a private key, returning it with a `0x` prefix and its address
+fn prompt_private_key(prompt: &str) -> Result<(String, EvmAddress)> {
+    let private_key: String = Password::new()
+        .with_prompt(format!("{} (with or without 0x prefix)", prompt))
+        .interact()?;
+
+    // Normalize private key (add 0x prefix if missing)
+    let private_key = if private_key.starts_with("0x") {
+        private_key
+    } else {
+        format!("0x{}", private_key)
+    };
+
+    // Parse and validate private key, get address
+    let signer: PrivateKeySigner = private_key
+        .parse()
+        .map_err(|e| eyre!("Invalid private key: {}", e))?;
+
+    Ok((private_key, EvmAddress::from(signer.address())))
+}
+
+async fn get_wallet(db: &Database, name: &str) -> Result<Wallet> {
+    WalletRepository::get_by_name(db, name)
+        .await?
+        .ok_or_else(|| eyre!("Wallet '{}' not found", name))
+}
+
+/// A rule given on the command line, `None` for `none`, which removes it
+fn unless_none(value: &str) -> Option<&str> {
+    match value.trim() {
+        "none" => None,
+        value => Some(value),
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn print_rules(rules: &WalletPolicy) {
+    let lines = policy::describe(rules);
+    if lines.is_empty() {
+        status!("   No limits");
+    }
+    for line in lines {
+        status!("   {}", line);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("smolder-backup-{}", std::process::id()));
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        // Overwritten files are made private too
+        write_private(&path, b"bundle").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read(&path).unwrap(), b"bundle");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+//! Manage webhooks notified by `smolder serve`
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::Confirm;
+use smolder_db::{Database, NewWebhook, TransactionStatus, WebhookId, WebhookRepository};
+
+use crate::output::{self, status};
+use crate::server::webhooks::{self, generate_secret};
+
+/// Manage webhooks notified by the server
+#[derive(Args)]
+pub struct WebhookCommand {
+    #[command(subcommand)]
+    pub command: WebhookSubcommand,
+}
+
+impl WebhookCommand {
+    pub async fn run(self) -> Result<()> {
+        self.command.run().await
+    }
+}
+
+#[derive(Subcommand)]
+pub enum WebhookSubcommand {
+    /// Register a webhook URL
+    Add(AddWebhookCommand),
+
+    /// List webhooks
+    List(ListWebhooksCommand),
+
+    /// Remove a webhook and its delivery log
+    Remove(RemoveWebhookCommand),
+
+    /// Show recent deliveries to a webhook
+    Deliveries(DeliveriesCommand),
+}
+
+impl WebhookSubcommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Self::Add(cmd) => cmd.run().await,
+            Self::List(cmd) => cmd.run().await,
+            Self::Remove(cmd) => cmd.run().await,
+            Self::Deliveries(cmd) => cmd.run().await,
+        }
+    }
+}
+
+/// Register a webhook URL
+#[derive(Args)]
+pub struct AddWebhookCommand {
+    /// URL to POST notifications to
+    pub url: String,
+
+    /// Only notify for this network
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// Only notify for this contract
+    #[arg(long)]
+    pub contract: Option<String>,
+
+    /// Only notify when this event is emitted
+    #[arg(long, conflicts_with = "status")]
+    pub event: Option<String>,
+
+    /// Only notify when a call ends with this status (success, failed, reverted)
+    #[arg(long)]
+    pub status: Option<String>,
+
+    /// Signing secret (generated if omitted)
+    #[arg(long)]
+    pub secret: Option<String>,
+}
+
+impl AddWebhookCommand {
+    pub async fn run(self) -> Result<()> {
+        webhooks::validate_url(&self.url)?;
+        let tx_status = self
+            .status
+            .as_deref()
+            .map(|s| {
+                serde_json::from_value::<TransactionStatus>(serde_json::json!(s))
+                    .map_err(|_| eyre!("Invalid status '{}'", s))
+            })
+            .transpose()?;
+
+        let db = Database::connect().await?;
+        let secret = self.secret.unwrap_or_else(generate_secret);
+        let webhook = WebhookRepository::create(
+            &db,
+            &NewWebhook {
+                url: self.url,
+                secret: secret.clone(),
+                network: self.network,
+                contract: self.contract,
+                event_name: self.event,
+                tx_status,
+            },
+        )
+        .await?;
+
+        if output::is_json() {
+            let mut value = serde_json::to_value(&webhook)?;
+            value["secret"] = serde_json::json!(secret);
+            return output::print_json(&value);
+        }
+
+        status!(
+            "{} Webhook {} added for {}",
+            style("*").green().bold(),
+            webhook.id,
+            style(&webhook.url).cyan()
+        );
+        status!("   Secret: {}", style(&secret).yellow());
+        status!(
+            "   Verify the {} header (HMAC-SHA256 of the body). The secret is not shown again.",
+            webhooks::SIGNATURE_HEADER
+        );
+
+        Ok(())
+    }
+}
+
+/// List webhooks
+#[derive(Args)]
+pub struct ListWebhooksCommand;
+
+impl ListWebhooksCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let webhooks = WebhookRepository::list(&db).await?;
+
+        if output::is_json() {
+            return output::print_json(&webhooks);
+        }
+
+        if webhooks.is_empty() {
+            status!("{} No webhooks found", style("!").yellow());
+            status!();
+            status!(
+                "   Add one with: {}",
+                style("smolder webhook add <url>").cyan()
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{:<6} {:<50} {:<15} {:<20} {:<15} {:<10}",
+            "ID", "URL", "Network", "Contract", "Event", "Status"
+        );
+        println!("{}", "-".repeat(121));
+
+        for w in &webhooks {
+            println!(
+                "{:<6} {:<50} {:<15} {:<20} {:<15} {:<10}",
+                w.id.0,
+                w.url,
+                w.network.as_deref().unwrap_or("*"),
+                w.contract.as_deref().unwrap_or("*"),
+                w.event_name.as_deref().unwrap_or("*"),
+                w.tx_status.map(|s| s.as_str()).unwrap_or("*")
+            );
+        }
+
+        status!();
+        status!("Total: {} webhook(s)", webhooks.len());
+
+        Ok(())
+    }
+}
+
+/// Remove a webhook and its delivery log
+#[derive(Args)]
+pub struct RemoveWebhookCommand {
+    /// Webhook ID
+    pub id: i64,
+
+    /// Skip confirmation prompt
+    #[arg(long, short)]
+    pub force: bool,
+}
+
+impl RemoveWebhookCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let id = WebhookId(self.id);
+        let webhook = WebhookRepository::get_by_id(&db, id)
+            .await?
+            .ok_or_else(|| eyre!("Webhook {} not found", id))?;
+
+        if !self.force {
+            status!(
+                "{} About to remove webhook {} ({})",
+                style("!").yellow(),
+                id,
+                style(&webhook.url).cyan()
+            );
+            status!("   Its delivery log will be deleted as well.");
+            status!();
+
+            let confirmed = Confirm::new()
+                .with_prompt("Are you sure you want to remove this webhook?")
+                .default(false)
+                .interact()?;
+
+            if !confirmed {
+                status!("{} Cancelled", style("*").dim());
+                return Ok(());
+            }
+        }
+
+        WebhookRepository::delete(&db, id).await?;
+
+        if output::is_json() {
+            return output::print_json(&webhook);
+        }
+
+        status!("{} Webhook {} removed", style("*").green().bold(), id);
+
+        Ok(())
+    }
+}
+
+/// Show recent deliveries to a webhook
+#[derive(Args)]
+pub struct DeliveriesCommand {
+    /// Webhook ID
+    pub id: i64,
+
+    /// Maximum number of deliveries to show
+    #[arg(long, default_value_t = 20)]
+    pub limit: u32,
+}
+
+impl DeliveriesCommand {
+    pub async fn run(self) -> Result<()> {
+        let db = Database::connect().await?;
+        let id = WebhookId(self.id);
+        WebhookRepository::get_by_id(&db, id)
+            .await?
+            .ok_or_else(|| eyre!("Webhook {} not found", id))?;
+
+        let deliveries = WebhookRepository::list_deliveries(&db, id, self.limit).await?;
+
+        if output::is_json() {
+            return output::print_json(&deliveries);
+        }
+
+        if deliveries.is_empty() {
+            status!("{} No deliveries yet", style("!").yellow());
+            return Ok(());
+        }
+
+        println!(
+            "{:<8} {:<8} {:<10} {:<9} {:<6} {:<20} Error",
+            "ID", "Trigger", "Status", "Attempts", "HTTP", "Created"
+        );
+        println!("{}", "-".repeat(90));
+
+        for d in &deliveries {
+            println!(
+                "{:<8} {:<8} {:<10} {:<9} {:<6} {:<20} {}",
+                d.id,
+                d.trigger.as_str(),
+                d.status.as_str(),
+                d.attempts,
+                d.response_status
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                d.created_at,
+                d.error_message.as_deref().unwrap_or("")
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -1,10 +1,348 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
 use color_eyre::eyre::{eyre, Result};
 use serde::Deserialize;
+use smolder_db::{DatabaseOptions, HistoryRetention};
+use sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+
+use crate::deployment_files::{self, FileFormat};
 
 const FOUNDRY_CONFIG: &str = "foundry.toml";
+const SMOLDER_CONFIG: &str = "smolder.toml";
+
+/// Smolder project configuration file structure (smolder.toml)
+/// Every section is optional; a missing file means all defaults
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SmolderConfig {
+    #[serde(default)]
+    pub project: ProjectConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub ens: EnsConfig,
+    #[serde(default)]
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub deployment_files: DeploymentFilesConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    pub retention: Option<RetentionConfig>,
+    pub monitor: Option<MonitorConfig>,
+    pub tenderly: Option<TenderlyConfig>,
+    pub prices: Option<PricesConfig>,
+}
+
+/// The `[project]` section of smolder.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProjectConfig {
+    /// Toolchain whose build and deployment outputs smolder reads
+    #[serde(default)]
+    pub framework: Framework,
+}
+
+/// The `[database]` section of smolder.toml, tuning the pool used by `serve`
+///
+/// Unset fields keep the defaults of [`DatabaseOptions`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Size of the read pool; writes always use one connection
+    pub max_connections: Option<u32>,
+    pub busy_timeout_ms: Option<u64>,
+    pub statement_cache_capacity: Option<usize>,
+    /// `wal`, `delete`, `truncate`, `persist`, `memory` or `off`
+    pub journal_mode: Option<String>,
+    /// `off`, `normal`, `full` or `extra`
+    pub synchronous: Option<String>,
+}
+
+impl DatabaseConfig {
+    /// Apply the configured overrides to the default options
+    pub fn options(&self) -> Result<DatabaseOptions> {
+        let mut options = DatabaseOptions::default();
+
+        if let Some(max_connections) = self.max_connections {
+            if max_connections == 0 {
+                return Err(eyre!("database.max_connections must be at least 1"));
+            }
+            options.max_connections = max_connections;
+        }
+        if let Some(ms) = self.busy_timeout_ms {
+            options.busy_timeout = Duration::from_millis(ms);
+        }
+        if let Some(capacity) = self.statement_cache_capacity {
+            options.statement_cache_capacity = capacity;
+        }
+        if let Some(mode) = &self.journal_mode {
+            options.journal_mode = SqliteJournalMode::from_str(mode)
+                .map_err(|_| eyre!("Invalid database.journal_mode '{}'", mode))?;
+        }
+        if let Some(level) = &self.synchronous {
+            options.synchronous = SqliteSynchronous::from_str(level)
+                .map_err(|_| eyre!("Invalid database.synchronous '{}'", level))?;
+        }
+
+        Ok(options)
+    }
+}
+
+/// The `[notifications]` section of smolder.toml: chat channels that are told
+/// about new deployments
+///
+/// Secrets may reference environment variables with `${VAR}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationsConfig {
+    pub slack: Option<SlackConfig>,
+    pub discord: Option<DiscordConfig>,
+    pub telegram: Option<TelegramConfig>,
+    /// Per-network switch; networks not listed are enabled
+    #[serde(default)]
+    pub networks: HashMap<String, bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlackConfig {
+    /// Incoming webhook URL
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    /// Channel webhook URL
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+impl NotificationsConfig {
+    /// Whether any channel is configured
+    pub fn is_configured(&self) -> bool {
+        self.slack.is_some() || self.discord.is_some() || self.telegram.is_some()
+    }
+
+    /// Whether deployments on `network` should be announced
+    pub fn is_enabled_for(&self, network: &str) -> bool {
+        self.is_configured() && self.networks.get(network).copied().unwrap_or(true)
+    }
+}
+
+/// The `[ens]` section of smolder.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnsConfig {
+    /// Registered network to resolve ENS names on, instead of picking one
+    pub network: Option<String>,
+}
+
+/// The `[cors]` section of smolder.toml: browser origins allowed to call the
+/// API of `serve`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+    /// `scheme://host[:port]` origins, or `["*"]` for any; localhost only
+    /// when empty
+    #[serde(default)]
+    pub origins: Vec<String>,
+    /// Let allowed origins send cookies and HTTP auth with their requests
+    #[serde(default)]
+    pub allow_credentials: bool,
+}
+
+/// The `[tenderly]` section of smolder.toml: simulate sends on Tenderly and
+/// keep a shareable link to each simulation
+///
+/// Secrets may reference environment variables with `${VAR}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenderlyConfig {
+    /// Account (or organization) slug
+    pub account: String,
+    /// Project slug
+    pub project: String,
+    pub access_key: String,
+}
+
+/// The `[prices]` section of smolder.toml: where the USD prices that value
+/// fees in cost reports and history exports come from
+///
+/// Prices are fetched once per currency and day and cached in the database.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PricesConfig {
+    /// `coingecko`, or an endpoint URL with `{symbol}` and `{date}`
+    /// (YYYY-MM-DD) placeholders answering `{"usd": <price>}`
+    pub source: String,
+    /// CoinGecko demo API key; may reference an environment variable
+    pub api_key: Option<String>,
+}
+
+/// The `[deployment_files]` section of smolder.toml: per-network files
+/// written by `deploy` and `sync` for versioning in git
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeploymentFilesConfig {
+    /// Write the files without `--write-files`
+    #[serde(default)]
+    pub write: bool,
+    /// Directory of the files; `deployments` by default
+    pub dir: Option<PathBuf>,
+    #[serde(default)]
+    pub format: FileFormat,
+}
+
+impl DeploymentFilesConfig {
+    /// Directory of the files
+    pub fn dir(&self) -> &Path {
+        self.dir
+            .as_deref()
+            .unwrap_or(Path::new(deployment_files::DEFAULT_DIR))
+    }
+}
+
+/// The `[hooks]` section of smolder.toml: exports and commands that
+/// regenerate downstream artifacts after the registry changes
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Run after `deploy` records new deployments
+    #[serde(default)]
+    pub post_deploy: Vec<HookConfig>,
+    /// Run after `sync` adds or updates deployments
+    #[serde(default)]
+    pub post_sync: Vec<HookConfig>,
+}
+
+/// A hook: either a shell command (`run`) or an export (`export`, the
+/// format, written to `output`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    pub run: Option<String>,
+    pub export: Option<String>,
+    pub output: Option<String>,
+    /// Only run when this network changed, and only export its deployments
+    pub network: Option<String>,
+    #[serde(default)]
+    pub no_abi: bool,
+    /// Handlebars template of the template export format
+    pub template: Option<PathBuf>,
+}
+
+/// The `[retention]` section of smolder.toml: call history `serve` prunes
+/// on its own
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Age past which calls are deleted, such as `90d`
+    pub call_history: String,
+    /// Keep failed and reverted calls whatever their age
+    #[serde(default)]
+    pub keep_failed: bool,
+}
+
+impl RetentionConfig {
+    pub fn policy(&self) -> Result<HistoryRetention> {
+        Ok(HistoryRetention {
+            max_age: parse_age(&self.call_history)
+                .map_err(|e| eyre!("Invalid retention.call_history: {}", e))?,
+            keep_failed: self.keep_failed,
+        })
+    }
+}
+
+/// The `[monitor]` section of smolder.toml: networks `serve` watches for
+/// calls to tracked deployments that smolder didn't send
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// Registered networks whose new blocks are scanned
+    pub networks: Vec<String>,
+    /// Time between two scans, such as `15s`
+    pub interval: Option<String>,
+    /// Post each call to the `[notifications]` channels
+    #[serde(default)]
+    pub notify: bool,
+}
+
+impl MonitorConfig {
+    /// Time between two scans, when set
+    pub fn interval(&self) -> Result<Option<Duration>> {
+        self.interval
+            .as_deref()
+            .map(|interval| {
+                parse_age(interval).map_err(|e| eyre!("Invalid monitor.interval: {}", e))
+            })
+            .transpose()
+    }
+}
+
+/// An age such as `90d`: a number with `s`, `m`, `h`, `d` or `w`
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("'{}' is not an age like 90d", value))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "'{}' has no unit; use s, m, h, d or w (e.g. 90d)",
+                value
+            ))
+        }
+    };
+    Ok(Duration::from_secs(amount * unit_secs))
+}
+
+/// Supported development frameworks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Framework {
+    /// forge build artifacts in `out/` and forge script broadcasts in `broadcast/`
+    #[default]
+    Foundry,
+    /// Hardhat artifacts in `artifacts/` and hardhat-deploy files in `deployments/`
+    Hardhat,
+}
+
+impl SmolderConfig {
+    /// Load configuration from smolder.toml in the current directory,
+    /// falling back to defaults when the file does not exist
+    pub fn load() -> Result<Self> {
+        Self::load_from(Path::new(SMOLDER_CONFIG))
+    }
+
+    /// Load configuration from a specific path
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| eyre!("Invalid {}: {}", path.display(), e))
+    }
+}
 
 /// Foundry configuration file structure (foundry.toml)
 /// We only parse the sections we need
@@ -107,7 +445,7 @@ pub struct NetworkConfig {
 
 /// Resolve environment variable references in a string
 /// Supports ${VAR_NAME} syntax
-fn resolve_env_var(value: &str) -> Result<String> {
+pub(crate) fn resolve_env_var(value: &str) -> Result<String> {
     if value.starts_with("${") && value.ends_with('}') {
         let var_name = &value[2..value.len() - 1];
         std::env::var(var_name).map_err(|_| eyre!("Environment variable '{}' not set", var_name))
@@ -230,6 +568,173 @@ mainnet = "https://eth.rpc"
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_smolder_config() {
+        let config: SmolderConfig = toml::from_str(
+            r#"
+[project]
+framework = "hardhat"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.project.framework, Framework::Hardhat);
+
+        let config: SmolderConfig = toml::from_str("").unwrap();
+        assert_eq!(config.project.framework, Framework::Foundry);
+
+        assert!(toml::from_str::<SmolderConfig>("[project]\nframework = \"truffle\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_database_config() {
+        let config: SmolderConfig = toml::from_str(
+            r#"
+[database]
+max_connections = 16
+busy_timeout_ms = 250
+journal_mode = "delete"
+"#,
+        )
+        .unwrap();
+
+        let options = config.database.options().unwrap();
+        assert_eq!(options.max_connections, 16);
+        assert_eq!(options.busy_timeout, Duration::from_millis(250));
+        assert!(matches!(options.journal_mode, SqliteJournalMode::Delete));
+        assert!(matches!(options.synchronous, SqliteSynchronous::Normal));
+
+        let config: SmolderConfig =
+            toml::from_str("[database]\nsynchronous = \"sometimes\"").unwrap();
+        assert!(config.database.options().is_err());
+    }
+
+    #[test]
+    fn test_parse_notifications_config() {
+        let config: SmolderConfig = toml::from_str(
+            r#"
+[notifications]
+networks = { anvil = false }
+
+[notifications.slack]
+webhook_url = "${SLACK_WEBHOOK_URL}"
+
+[notifications.telegram]
+bot_token = "123:abc"
+chat_id = "-10042"
+"#,
+        )
+        .unwrap();
+
+        let notifications = config.notifications;
+        assert!(notifications.slack.is_some());
+        assert!(notifications.discord.is_none());
+        assert_eq!(notifications.telegram.as_ref().unwrap().chat_id, "-10042");
+        assert!(!notifications.is_enabled_for("anvil"));
+        assert!(notifications.is_enabled_for("mainnet"));
+
+        let config: SmolderConfig = toml::from_str("").unwrap();
+        assert!(!config.notifications.is_enabled_for("mainnet"));
+
+        assert!(toml::from_str::<SmolderConfig>("[notifications.matrix]\nroom = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_tenderly_config() {
+        let config: SmolderConfig = toml::from_str(
+            r#"
+[tenderly]
+account = "acme"
+project = "contracts"
+access_key = "${TENDERLY_ACCESS_KEY}"
+"#,
+        )
+        .unwrap();
+        let tenderly = config.tenderly.unwrap();
+        assert_eq!(tenderly.project, "contracts");
+        assert_eq!(tenderly.access_key, "${TENDERLY_ACCESS_KEY}");
+
+        assert!(toml::from_str::<SmolderConfig>("")
+            .unwrap()
+            .tenderly
+            .is_none());
+        assert!(toml::from_str::<SmolderConfig>("[tenderly]\naccount = \"acme\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_prices_config() {
+        let config: SmolderConfig = toml::from_str(
+            r#"
+[prices]
+source = "coingecko"
+api_key = "${COINGECKO_API_KEY}"
+"#,
+        )
+        .unwrap();
+        let prices = config.prices.unwrap();
+        assert_eq!(prices.source, "coingecko");
+        assert_eq!(prices.api_key.as_deref(), Some("${COINGECKO_API_KEY}"));
+
+        assert!(toml::from_str::<SmolderConfig>("[prices]\napi_key = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_hooks_config() {
+        let config: SmolderConfig = toml::from_str(
+            r#"
+[[hooks.post_deploy]]
+export = "ts"
+output = "../frontend/src/contracts.ts"
+no_abi = true
+
+[[hooks.post_sync]]
+run = "pnpm --dir ../frontend codegen"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.hooks.post_deploy[0].export.as_deref(), Some("ts"));
+        assert!(config.hooks.post_deploy[0].no_abi);
+        assert_eq!(
+            config.hooks.post_sync[0].run.as_deref(),
+            Some("pnpm --dir ../frontend codegen")
+        );
+
+        assert!(toml::from_str::<SmolderConfig>(
+            "[[hooks.post_deploy]]
+command = \"x\""
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_retention_config() {
+        let config: SmolderConfig =
+            toml::from_str("[retention]\ncall_history = \"90d\"\nkeep_failed = true").unwrap();
+        let policy = config.retention.unwrap().policy().unwrap();
+        assert_eq!(policy.max_age, Duration::from_secs(90 * 24 * 3600));
+        assert!(policy.keep_failed);
+
+        assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 24 * 3600)));
+        assert!(parse_age("90").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+    }
+
+    #[test]
+    fn test_parse_monitor_config() {
+        let config: SmolderConfig = toml::from_str(
+            "[monitor]\nnetworks = [\"mainnet\"]\ninterval = \"30s\"\nnotify = true",
+        )
+        .unwrap();
+        let monitor = config.monitor.unwrap();
+        assert_eq!(monitor.networks, ["mainnet"]);
+        assert_eq!(monitor.interval().unwrap(), Some(Duration::from_secs(30)));
+        assert!(monitor.notify);
+
+        let config: SmolderConfig = toml::from_str("[monitor]\nnetworks = []").unwrap();
+        assert_eq!(config.monitor.unwrap().interval().unwrap(), None);
+    }
+
     #[test]
     fn test_rpc_endpoint_object_format() {
         let toml_content = r#"
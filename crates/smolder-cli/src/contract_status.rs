@@ -0,0 +1,207 @@
+//! Pause, ownership and proxy status of deployed contracts
+//!
+//! `paused()`, `owner()` and `implementation()` are called on every
+//! deployment, batched per network through Multicall3 so a long listing
+//! costs a handful of requests. Networks without Multicall3 fall back to
+//! plain calls. Like token metadata, this is best effort: getters a contract
+//! doesn't have are left empty, and deployments on networks that can't be
+//! reached get no status at all.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use alloy::primitives::{address, Address};
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use smolder_db::{DeploymentId, DeploymentView, Repositories};
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+use crate::rpc;
+
+/// Multicall3, deployed at the same address on most chains
+const MULTICALL3: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+/// Deployments batched into one `aggregate3` call
+const BATCH_SIZE: usize = 100;
+
+/// How long a network gets to answer before its deployments are skipped
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Plain calls made at once when Multicall3 isn't available
+const CONCURRENCY: usize = 8;
+
+sol! {
+    interface IStatus {
+        function paused() external view returns (bool);
+        function owner() external view returns (address);
+        function implementation() external view returns (address);
+    }
+
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+    }
+}
+
+/// Live status of a deployment
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ContractStatus {
+    /// `paused()`, for Pausable contracts
+    pub paused: Option<bool>,
+    /// `owner()`, for Ownable contracts
+    pub owner: Option<String>,
+    /// `implementation()`, for proxies that expose it
+    pub implementation: Option<String>,
+}
+
+impl ContractStatus {
+    /// Whether none of the getters answered
+    pub fn is_empty(&self) -> bool {
+        self.paused.is_none() && self.owner.is_none() && self.implementation.is_none()
+    }
+}
+
+/// Read the status of `deployments`, keyed by deployment ID
+///
+/// Networks are queried concurrently. Deployments on networks that are
+/// unknown or unreachable are missing from the result.
+pub async fn read(
+    db: &dyn Repositories,
+    deployments: &[DeploymentView],
+) -> HashMap<DeploymentId, ContractStatus> {
+    let networks = match db.networks().list().await {
+        Ok(networks) => networks,
+        Err(e) => {
+            tracing::debug!(error = %e, "could not load networks for contract status");
+            return HashMap::new();
+        }
+    };
+
+    let mut by_network: HashMap<&str, Vec<(DeploymentId, Address)>> = HashMap::new();
+    for deployment in deployments {
+        by_network
+            .entry(deployment.network_name.as_str())
+            .or_default()
+            .push((deployment.id, *deployment.address));
+    }
+
+    let reads = networks.iter().filter_map(|network| {
+        let targets = by_network.remove(network.name.as_str())?;
+        let rpc_url = network.rpc_url.expose();
+        Some(read_network(rpc_url, targets).instrument(rpc::span(network.chain_id)))
+    });
+    join_all(reads).await.into_iter().flatten().collect()
+}
+
+/// Read the status of contracts on one network
+async fn read_network(
+    rpc_url: &str,
+    targets: Vec<(DeploymentId, Address)>,
+) -> Vec<(DeploymentId, ContractStatus)> {
+    // Failed calls are taken as missing getters below, so make sure the
+    // node is up first
+    if !matches!(
+        tokio::time::timeout(RPC_TIMEOUT, rpc::get_chain_id(rpc_url)).await,
+        Ok(Ok(_))
+    ) {
+        return Vec::new();
+    }
+
+    let mut statuses = Vec::with_capacity(targets.len());
+    for batch in targets.chunks(BATCH_SIZE) {
+        let addresses: Vec<Address> = batch.iter().map(|(_, address)| *address).collect();
+        let batch_statuses = match aggregate(rpc_url, &addresses).await {
+            Some(statuses) => statuses,
+            None => {
+                stream::iter(addresses)
+                    .map(|address| read_one(rpc_url, address))
+                    .buffered(CONCURRENCY)
+                    .collect()
+                    .await
+            }
+        };
+        statuses.extend(batch.iter().map(|(id, _)| *id).zip(batch_statuses));
+    }
+    statuses
+}
+
+/// Read the status of `addresses` in a single Multicall3 call, or `None`
+/// when the network doesn't have it
+async fn aggregate(rpc_url: &str, addresses: &[Address]) -> Option<Vec<ContractStatus>> {
+    let calls = addresses
+        .iter()
+        .flat_map(|&target| {
+            [
+                IStatus::pausedCall {}.abi_encode(),
+                IStatus::ownerCall {}.abi_encode(),
+                IStatus::implementationCall {}.abi_encode(),
+            ]
+            .map(|data| IMulticall3::Call3 {
+                target,
+                allowFailure: true,
+                callData: data.into(),
+            })
+        })
+        .collect();
+    let data = IMulticall3::aggregate3Call { calls }.abi_encode();
+    let output = tokio::time::timeout(
+        RPC_TIMEOUT,
+        rpc::execute_eth_call(rpc_url, MULTICALL3, data.into()),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    let results = IMulticall3::aggregate3Call::abi_decode_returns(&output).ok()?;
+    if results.len() != addresses.len() * 3 {
+        return None;
+    }
+
+    let statuses = results
+        .chunks(3)
+        .map(|results| {
+            let returned = |i: usize| {
+                let result: &IMulticall3::Result = &results[i];
+                result.success.then_some(&result.returnData)
+            };
+            ContractStatus {
+                paused: returned(0)
+                    .and_then(|data| IStatus::pausedCall::abi_decode_returns(data).ok()),
+                owner: returned(1)
+                    .and_then(|data| IStatus::ownerCall::abi_decode_returns(data).ok())
+                    .map(|owner| owner.to_checksum(None)),
+                implementation: returned(2)
+                    .and_then(|data| IStatus::implementationCall::abi_decode_returns(data).ok())
+                    .map(|implementation| implementation.to_checksum(None)),
+            }
+        })
+        .collect();
+    Some(statuses)
+}
+
+/// Read the status of one contract with plain calls
+async fn read_one(rpc_url: &str, address: Address) -> ContractStatus {
+    let (paused, owner, implementation) = futures::join!(
+        rpc::call(rpc_url, address, IStatus::pausedCall {}),
+        rpc::call(rpc_url, address, IStatus::ownerCall {}),
+        rpc::call(rpc_url, address, IStatus::implementationCall {}),
+    );
+    ContractStatus {
+        paused,
+        owner: owner.map(|owner| owner.to_checksum(None)),
+        implementation: implementation.map(|implementation| implementation.to_checksum(None)),
+    }
+}
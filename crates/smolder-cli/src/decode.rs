@@ -0,0 +1,220 @@
+//! Identify and decode arbitrary calldata
+//!
+//! Every stored ABI is indexed into the selectors table on each decode, so
+//! the registry's own functions are always known and win over anything
+//! else. When no stored ABI has the selector, 4byte.directory can be asked
+//! for candidate signatures, which are cached in the table for next time.
+//! Candidates whose parameters don't decode are dropped; colliding ones
+//! that do are all returned.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use alloy::primitives::Selector;
+use serde::Serialize;
+use smolder_core::{decode_function_input, parse_function_signature, Abi, Error};
+use smolder_db::{ContractFilter, NewSelector, Repositories, SelectorSource};
+use utoipa::ToSchema;
+
+const FOUR_BYTE_URL: &str = "https://www.4byte.directory/api/v1/signatures/";
+
+/// Time allowed for a 4byte.directory lookup
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Functions calldata may be a call to
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DecodedCalldata {
+    /// First four bytes of the calldata
+    pub selector: String,
+    /// Signatures whose parameters decode, registry ones first
+    pub matches: Vec<DecodedMatch>,
+}
+
+/// A function the calldata decodes as
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DecodedMatch {
+    /// Canonical signature, e.g. `transfer(address,uint256)`
+    pub signature: String,
+    pub source: SelectorSource,
+    /// Registry contracts whose ABI has the function
+    pub contracts: Vec<String>,
+    /// Decoded parameters, by name when the ABI names them
+    #[schema(value_type = Object)]
+    pub params: serde_json::Value,
+}
+
+/// Decode `calldata` against the registry's ABIs, the cached 4byte
+/// signatures, and 4byte.directory itself when `online` and nothing else
+/// knows the selector
+pub async fn decode(
+    repos: &dyn Repositories,
+    calldata: &[u8],
+    online: bool,
+) -> Result<DecodedCalldata, Error> {
+    let Some(selector) = calldata.get(..4) else {
+        return Err(Error::Validation(
+            "Calldata must be at least 4 bytes".to_string(),
+        ));
+    };
+    let selector = Selector::from_slice(selector);
+
+    // Registry functions, with the contracts that have them
+    let mut registry: BTreeMap<String, (Vec<String>, serde_json::Value)> = BTreeMap::new();
+    let mut indexed = Vec::new();
+    for contract in repos.contracts().list(ContractFilter::default()).await? {
+        let Ok(abi) = Abi::parse(&contract.abi) else {
+            continue;
+        };
+        for function in abi.inner().functions() {
+            indexed.push(NewSelector {
+                selector: function.selector().to_string(),
+                signature: function.signature(),
+                source: SelectorSource::Registry,
+            });
+        }
+        if let Some(function) = abi.function_by_selector(selector) {
+            if let Ok(params) = decode_function_input(function, calldata) {
+                let (contracts, _) = registry
+                    .entry(function.signature())
+                    .or_insert_with(|| (Vec::new(), params));
+                contracts.push(contract.name);
+            }
+        }
+    }
+    repos.selectors().put_many(&indexed).await?;
+
+    let selector_hex = selector.to_string();
+    let mut known = repos.selectors().lookup(&selector_hex).await?;
+    if known.is_empty() && online {
+        let signatures = four_byte(&selector_hex).await;
+        let new: Vec<_> = signatures
+            .into_iter()
+            .map(|signature| NewSelector {
+                selector: selector_hex.clone(),
+                signature,
+                source: SelectorSource::FourByte,
+            })
+            .collect();
+        repos.selectors().put_many(&new).await?;
+        known = repos.selectors().lookup(&selector_hex).await?;
+    }
+
+    let mut matches: Vec<_> = registry
+        .into_iter()
+        .map(|(signature, (contracts, params))| DecodedMatch {
+            signature,
+            source: SelectorSource::Registry,
+            contracts,
+            params,
+        })
+        .collect();
+    for entry in known {
+        if matches.iter().any(|m| m.signature == entry.signature) {
+            continue;
+        }
+        // Registry entries left from since-removed contracts decode too
+        let Ok(function) = parse_function_signature(&entry.signature) else {
+            continue;
+        };
+        if let Ok(params) = decode_function_input(&function, calldata) {
+            matches.push(DecodedMatch {
+                signature: entry.signature,
+                source: entry.source,
+                contracts: Vec::new(),
+                params,
+            });
+        }
+    }
+
+    Ok(DecodedCalldata {
+        selector: selector_hex,
+        matches,
+    })
+}
+
+/// Signatures 4byte.directory knows for `selector`, empty when it can't be
+/// reached
+async fn four_byte(selector: &str) -> Vec<String> {
+    let Ok(client) = reqwest::Client::builder().timeout(TIMEOUT).build() else {
+        return Vec::new();
+    };
+    let body: serde_json::Value = match client
+        .get(format!("{}?hex_signature={}", FOUR_BYTE_URL, selector))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+    {
+        Ok(response) => response.json().await.unwrap_or_default(),
+        Err(e) => {
+            tracing::debug!(error = %e, "4byte.directory lookup failed");
+            return Vec::new();
+        }
+    };
+    body["results"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|r| r["text_signature"].as_str().map(str::to_string))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_core::BytecodeHash;
+    use smolder_db::{MemoryDatabase, NewContract};
+
+    const TOKEN_ABI: &str = r#"[{"type":"function","name":"transfer","inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[{"type":"bool"}],"stateMutability":"nonpayable"}]"#;
+
+    #[tokio::test]
+    async fn test_decode() {
+        let db = MemoryDatabase::new();
+        db.contracts()
+            .upsert(&NewContract {
+                name: "Token".to_string(),
+                source_path: "src/Token.sol".to_string(),
+                abi: TOKEN_ABI.to_string(),
+                bytecode_hash: BytecodeHash::of([0x60]),
+                storage_layout: None,
+                compiler: None,
+            })
+            .await
+            .unwrap();
+        // A colliding signature cached from 4byte
+        db.selectors()
+            .put_many(&[NewSelector {
+                selector: "0xa9059cbb".to_string(),
+                signature: "many_msg_babbage(bytes1)".to_string(),
+                source: SelectorSource::FourByte,
+            }])
+            .await
+            .unwrap();
+
+        let mut calldata = hex::decode("a9059cbb").unwrap();
+        calldata.extend_from_slice(&[0; 12]);
+        calldata.extend_from_slice(&[0x11; 20]);
+        calldata.extend_from_slice(&[0; 31]);
+        calldata.push(100);
+
+        let decoded = decode(&db, &calldata, false).await.unwrap();
+        assert_eq!(decoded.selector, "0xa9059cbb");
+        assert_eq!(decoded.matches.len(), 2);
+        let found = &decoded.matches[0];
+        assert_eq!(found.signature, "transfer(address,uint256)");
+        assert_eq!(found.source, SelectorSource::Registry);
+        assert_eq!(found.contracts, ["Token"]);
+        assert_eq!(found.params["amount"], "100");
+        assert_eq!(decoded.matches[1].signature, "many_msg_babbage(bytes1)");
+        assert_eq!(decoded.matches[1].source, SelectorSource::FourByte);
+
+        // The registry's functions were indexed
+        let known = db.selectors().lookup("0xA9059CBB").await.unwrap();
+        assert_eq!(known[0].source, SelectorSource::Registry);
+
+        assert!(matches!(
+            decode(&db, &[0xa9, 0x05], false).await,
+            Err(Error::Validation(_))
+        ));
+    }
+}
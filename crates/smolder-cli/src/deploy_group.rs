@@ -0,0 +1,330 @@
+//! Deploy one artifact across several networks
+//!
+//! A deployment group records the artifact, arguments and wallet once, plus
+//! the outcome on each network. Networks that failed (or were never reached)
+//! can be retried later with the same inputs, and the bytecode hash makes sure
+//! a resumed group still deploys the code the first networks got.
+
+use alloy::primitives::{Bytes, U256};
+use futures::future::join_all;
+use smolder_core::{
+    decrypt_private_key, encode_constructor_args, parse_value, BytecodeHash, Currency, Error,
+    EvmAddress, TransactionStatus,
+};
+use smolder_db::{
+    Deployment, DeploymentGroup, DeploymentGroupId, DeploymentGroupMember, Network, NewContract,
+    NewDeployment, NewDeploymentGroup, Repositories, WalletWithKey,
+};
+use tracing::Instrument;
+
+use crate::forge::{ArtifactDetails, ArtifactLoader};
+use crate::locks::Clearance;
+use crate::policy::{self, Intent};
+use crate::rpc::{self, Outgoing};
+
+/// What to deploy, and where
+pub struct GroupRequest {
+    pub contract: String,
+    pub networks: Vec<String>,
+    pub wallet: String,
+    pub constructor_args: Vec<serde_json::Value>,
+    /// Wei sent to the constructor, or an amount in `gwei` or `ether`
+    pub value: Option<String>,
+}
+
+/// An artifact ready to be deployed
+pub struct Creation {
+    pub details: ArtifactDetails,
+    pub bytecode: Vec<u8>,
+    /// Bytecode followed by the encoded constructor arguments
+    pub data: Bytes,
+    pub value: Option<U256>,
+}
+
+impl Creation {
+    /// Load an artifact from the build output and encode its constructor
+    /// arguments, checking they fit the constructor
+    pub fn load(
+        loader: &dyn ArtifactLoader,
+        name: &str,
+        args: &[serde_json::Value],
+        value: Option<&str>,
+    ) -> Result<Self, Error> {
+        let details = loader.get_details(name).map_err(|e| {
+            if e.to_string().contains("Could not find artifact") {
+                Error::ArtifactNotFound(name.to_string())
+            } else {
+                Error::Io(e.to_string())
+            }
+        })?;
+        if !details.has_bytecode {
+            return Err(Error::Validation(format!(
+                "Artifact '{}' has no bytecode (may be an interface or abstract contract)",
+                name
+            )));
+        }
+        if !details.libraries.is_empty() {
+            return Err(Error::Validation(format!(
+                "{} links against {}, whose addresses differ between networks; deploy it to one network at a time",
+                name,
+                details.libraries.join(", ")
+            )));
+        }
+        let bytecode = loader
+            .get_bytecode(name)
+            .map_err(|e| Error::Io(e.to_string()))?;
+        let bytecode = hex::decode(bytecode)?;
+
+        let inputs = details
+            .constructor
+            .as_ref()
+            .map(|c| c.inputs.as_slice())
+            .unwrap_or_default();
+        if args.len() != inputs.len() {
+            return Err(Error::invalid_param(
+                "constructor_args",
+                format!("expected {} arguments, got {}", inputs.len(), args.len()),
+            ));
+        }
+        let mut data = bytecode.clone();
+        data.extend_from_slice(&encode_constructor_args(inputs, args)?);
+
+        let value = match value.filter(|v| !v.is_empty()) {
+            Some(v) => {
+                if !details.constructor.as_ref().is_some_and(|c| c.is_payable()) {
+                    return Err(Error::invalid_param(
+                        "value",
+                        "the constructor is not payable",
+                    ));
+                }
+                // Networks differ in symbol, so only wei, gwei and ether apply
+                Some(parse_value(v, Currency::ETHER)?)
+            }
+            None => None,
+        }
+        .filter(|v| !v.is_zero());
+
+        Ok(Self {
+            details,
+            bytecode,
+            data: data.into(),
+            value,
+        })
+    }
+
+    pub fn bytecode_hash(&self) -> BytecodeHash {
+        BytecodeHash::of(&self.bytecode)
+    }
+}
+
+/// Check a request against the registry and the build output, and record it
+/// as a group with every network pending
+///
+/// Locked networks must be confirmed up front, so a group isn't left pending
+/// on a network it was never allowed to reach.
+pub async fn create(
+    db: &dyn Repositories,
+    loader: &dyn ArtifactLoader,
+    request: &GroupRequest,
+    clearance: &Clearance,
+) -> Result<DeploymentGroup, Error> {
+    if request.networks.is_empty() {
+        return Err(Error::invalid_param("networks", "at least one is required"));
+    }
+    let creation = Creation::load(
+        loader,
+        &request.contract,
+        &request.constructor_args,
+        request.value.as_deref(),
+    )?;
+    db.wallets()
+        .get_by_name(&request.wallet)
+        .await?
+        .ok_or_else(|| Error::WalletNotFound(request.wallet.clone()))?;
+
+    let mut network_ids = Vec::with_capacity(request.networks.len());
+    for name in &request.networks {
+        let network = db
+            .networks()
+            .get_by_name(name)
+            .await?
+            .ok_or_else(|| Error::NetworkNotFound(name.clone()))?;
+        if !clearance.allows(&network) {
+            return Err(Error::NetworkLocked(network.name));
+        }
+        if network_ids.contains(&network.id) {
+            return Err(Error::invalid_param(
+                "networks",
+                format!("'{}' is listed twice", name),
+            ));
+        }
+        network_ids.push(network.id);
+    }
+
+    db.deployment_groups()
+        .create(&NewDeploymentGroup {
+            contract_name: request.contract.clone(),
+            bytecode_hash: creation.bytecode_hash(),
+            constructor_args: serde_json::to_string(&request.constructor_args)?,
+            value: request.value.clone().filter(|v| !v.is_empty()),
+            wallet_name: request.wallet.clone(),
+            network_ids,
+        })
+        .await
+}
+
+/// Deploy a group to every network it has not succeeded on yet, one after
+/// the other or all at once
+///
+/// A failure on one network doesn't stop the others; it is recorded on the
+/// member so the group can be run again. Returns every member, including
+/// those that had already succeeded. Nothing is sent unless every remaining
+/// network is unlocked or confirmed.
+pub async fn run(
+    db: &dyn Repositories,
+    loader: &dyn ArtifactLoader,
+    id: DeploymentGroupId,
+    parallel: bool,
+    clearance: &Clearance,
+) -> Result<Vec<DeploymentGroupMember>, Error> {
+    let group = db
+        .deployment_groups()
+        .get(id)
+        .await?
+        .ok_or(Error::DeploymentGroupNotFound(id))?;
+    let args: Vec<serde_json::Value> = serde_json::from_str(&group.constructor_args)?;
+    let creation = Creation::load(loader, &group.contract_name, &args, group.value.as_deref())?;
+    if creation.bytecode_hash() != group.bytecode_hash {
+        return Err(Error::Validation(format!(
+            "{} was rebuilt since group {} was created; its bytecode no longer matches the networks already deployed",
+            group.contract_name, group.id
+        )));
+    }
+
+    let wallet = db
+        .wallets()
+        .get_with_key(&group.wallet_name)
+        .await?
+        .ok_or_else(|| Error::WalletNotFound(group.wallet_name.clone()))?;
+    let private_key = decrypt_private_key(&wallet.encrypted_key)?;
+
+    let mut remaining = Vec::new();
+    for member in db.deployment_groups().list_members(id).await? {
+        if member.status == TransactionStatus::Success {
+            continue;
+        }
+        let network = db
+            .networks()
+            .get_by_name(&member.network_name)
+            .await?
+            .ok_or_else(|| Error::NetworkNotFound(member.network_name.clone()))?;
+        remaining.push(network);
+    }
+    if let Some(locked) = remaining.iter().find(|n| !clearance.allows(n)) {
+        return Err(Error::NetworkLocked(locked.name.clone()));
+    }
+    let operation = format!("deploy {} (group {})", group.contract_name, group.id);
+    for network in &remaining {
+        clearance.check(db, network, &operation).await?;
+    }
+
+    let deploy = |network: Network| {
+        let (creation, args, private_key, wallet) = (&creation, &args, &private_key, &wallet);
+        async move {
+            let span = rpc::span(network.chain_id);
+            let result = deploy_to(db, creation, args, &network, wallet, private_key)
+                .instrument(span)
+                .await;
+            let (status, deployment_id, error) = match result {
+                Ok(deployment) => (TransactionStatus::Success, Some(deployment.id), None),
+                Err(e) => (TransactionStatus::Failed, None, Some(e.to_string())),
+            };
+            db.deployment_groups()
+                .update_member(id, network.id, status, deployment_id, error.as_deref())
+                .await
+        }
+    };
+
+    if parallel {
+        for result in join_all(remaining.into_iter().map(deploy)).await {
+            result?;
+        }
+    } else {
+        for network in remaining {
+            deploy(network).await?;
+        }
+    }
+
+    db.deployment_groups().list_members(id).await
+}
+
+/// Send the creation transaction to one network and record the deployment,
+/// within the policy of `wallet`
+pub async fn deploy_to(
+    db: &dyn Repositories,
+    creation: &Creation,
+    args: &[serde_json::Value],
+    network: &Network,
+    wallet: &WalletWithKey,
+    private_key: &str,
+) -> Result<Deployment, Error> {
+    let intent = Intent::deploy(network, &creation.details.name, creation.value);
+    policy::check(db, wallet.id, &wallet.name, &intent).await?;
+    let deployer = *wallet.address;
+    rpc::preflight(
+        network,
+        Outgoing {
+            from: deployer,
+            to: None,
+            data: &creation.data,
+            value: creation.value,
+        },
+    )
+    .await?;
+    let reservation = policy::reserve(db, wallet.id, &wallet.name, &intent).await?;
+    let receipt = rpc::deploy_contract(
+        network.rpc_url.expose(),
+        private_key,
+        creation.data.clone(),
+        creation.value,
+    )
+    .await;
+    let receipt = reservation.settle(db, receipt).await?;
+    let address = receipt.contract_address.ok_or_else(|| {
+        Error::TransactionFailed(format!(
+            "{} was mined without creating a contract",
+            receipt.tx_hash
+        ))
+    })?;
+
+    let details = &creation.details;
+    let contract = db
+        .contracts()
+        .upsert(&NewContract {
+            name: details.name.clone(),
+            source_path: details.source_path.clone(),
+            abi: serde_json::to_string(&details.abi)?,
+            bytecode_hash: creation.bytecode_hash(),
+            storage_layout: details.storage_layout_json(),
+            compiler: details.compiler.clone(),
+        })
+        .await?;
+
+    db.deployments()
+        .create(&NewDeployment {
+            contract_id: contract.id,
+            network_id: network.id,
+            address,
+            deployer: EvmAddress::from(deployer).to_string(),
+            tx_hash: Some(receipt.tx_hash),
+            block_number: receipt.block_number.map(|b| b as i64),
+            constructor_args: (!args.is_empty())
+                .then(|| serde_json::to_string(args))
+                .transpose()?,
+            initcode_size: Some(details.initcode_size as i64),
+            runtime_size: Some(details.runtime_size as i64),
+            gas_used: Some(receipt.gas_used as i64),
+            gas_price: Some(receipt.gas_price.to_string()),
+        })
+        .await
+}
@@ -0,0 +1,210 @@
+//! Preview of what a deploy script would change in the registry
+//!
+//! `smolder deploy --plan` runs the script without broadcasting, reads the
+//! transactions forge recorded for the dry run, and compares every contract
+//! it creates with the current deployment on the network: a contract never
+//! deployed there is new, one deployed before becomes a new version.
+
+use alloy::hex;
+use alloy::primitives::U256;
+use color_eyre::eyre::Result;
+use console::style;
+use serde::Serialize;
+use smolder_core::{format_gas_price, format_value, BytecodeHash, Currency, LayoutIssue};
+use smolder_db::{ContractRepository, Database, DeploymentRepository};
+
+use crate::forge::{ArtifactLoader, BroadcastOutput};
+use crate::layout;
+use crate::output::status;
+
+/// What deploying a contract does to the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    /// Not deployed on the network yet
+    NewContract,
+    /// Replaces the current deployment with different bytecode
+    NewVersion,
+    /// Replaces the current deployment with the same bytecode
+    Redeploy,
+}
+
+impl Change {
+    fn of(current: Option<BytecodeHash>, planned: BytecodeHash) -> Self {
+        match current {
+            None => Self::NewContract,
+            Some(current) if current == planned => Self::Redeploy,
+            Some(_) => Self::NewVersion,
+        }
+    }
+
+    fn symbol(self) -> console::StyledObject<&'static str> {
+        match self {
+            Self::NewContract => style("+").green(),
+            Self::NewVersion => style("~").yellow(),
+            Self::Redeploy => style("=").dim(),
+        }
+    }
+}
+
+/// Result of a deploy run with `--plan`, printed with `--json`
+#[derive(Serialize)]
+pub struct Plan {
+    pub network: String,
+    pub chain_id: u64,
+    pub deployments: Vec<PlannedDeployment>,
+    /// Transactions other than contract creations
+    pub calls: usize,
+    /// Gas limit of every transaction together
+    pub gas: u64,
+    /// Gas price the cost is estimated at, in wei
+    pub gas_price: Option<u128>,
+    /// Cost of the gas at that price, in wei
+    pub cost: Option<U256>,
+}
+
+#[derive(Serialize)]
+pub struct PlannedDeployment {
+    pub contract: String,
+    /// Address forge predicted for the contract
+    pub address: Option<String>,
+    pub change: Change,
+    /// Version the deployment would replace
+    pub current_version: Option<i64>,
+    pub current_address: Option<String>,
+    pub gas: Option<u64>,
+    /// Conflicts with the storage layout of the current version
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub layout_issues: Vec<LayoutIssue>,
+}
+
+impl Plan {
+    /// Compare the contracts created in a dry run with the registry
+    pub async fn build(
+        db: &Database,
+        loader: &dyn ArtifactLoader,
+        network: &str,
+        chain_id: u64,
+        dry_run: &BroadcastOutput,
+        gas_price: Option<u128>,
+    ) -> Result<Self> {
+        let mut deployments = Vec::new();
+        let mut calls = 0;
+        for tx in &dry_run.transactions {
+            let Some(contract) = tx.contract_name.as_ref().filter(|_| tx.is_create()) else {
+                calls += 1;
+                continue;
+            };
+
+            let artifact = loader.load(contract)?;
+            let bytecode = hex::decode(artifact.bytecode.without_prefix()).unwrap_or_default();
+            let current = DeploymentRepository::get_current(db, contract, network).await?;
+            let current_hash = match &current {
+                Some(current) => ContractRepository::get_by_id(db, current.contract_id)
+                    .await?
+                    .map(|c| c.bytecode_hash),
+                None => None,
+            };
+            let layout_issues = match artifact.storage_layout_json() {
+                Some(storage_layout) => {
+                    layout::check_against_current(db, contract, network, None, &storage_layout)
+                        .await?
+                }
+                None => Vec::new(),
+            };
+
+            deployments.push(PlannedDeployment {
+                contract: contract.clone(),
+                address: tx.contract_address.clone(),
+                change: Change::of(current_hash, BytecodeHash::of(&bytecode)),
+                current_version: current.as_ref().map(|d| d.version),
+                current_address: current.as_ref().map(|d| d.address.to_string()),
+                gas: tx.transaction.gas(),
+                layout_issues,
+            });
+        }
+
+        let gas = dry_run
+            .transactions
+            .iter()
+            .filter_map(|tx| tx.transaction.gas())
+            .sum();
+        Ok(Self {
+            network: network.to_string(),
+            chain_id,
+            deployments,
+            calls,
+            gas,
+            gas_price,
+            cost: gas_price.map(|p| U256::from(gas) * U256::from(p)),
+        })
+    }
+
+    fn count(&self, change: Change) -> usize {
+        self.deployments
+            .iter()
+            .filter(|d| d.change == change)
+            .count()
+    }
+
+    pub fn print(&self, currency: Currency) {
+        if self.deployments.is_empty() {
+            status!("{} The script deploys no contracts", style("ℹ").blue());
+        }
+        for d in &self.deployments {
+            let detail = match (d.change, d.current_version) {
+                (Change::NewContract, _) => "new contract".to_string(),
+                (Change::NewVersion, Some(v)) => format!("new version, replaces v{}", v),
+                (Change::Redeploy, Some(v)) => {
+                    format!("same bytecode as v{}, replaces it", v)
+                }
+                (_, None) => String::new(),
+            };
+            println!(
+                "  {} {:<24} {:<44} {}",
+                d.change.symbol(),
+                d.contract,
+                d.address.as_deref().unwrap_or("-"),
+                style(detail).dim()
+            );
+            if let Some(current) = &d.current_address {
+                println!("      {} {}", style("current:").dim(), style(current).dim());
+            }
+            layout::print_issues(&d.contract, &d.layout_issues);
+        }
+
+        status!();
+        status!(
+            "Plan: {} to create, {} new version(s), {} redeploy(s), {} other transaction(s)",
+            self.count(Change::NewContract),
+            self.count(Change::NewVersion),
+            self.count(Change::Redeploy),
+            self.calls
+        );
+        match self.cost.zip(self.gas_price) {
+            Some((cost, gas_price)) if self.gas > 0 => status!(
+                "Estimated cost: {} ({} gas at {})",
+                format_value(cost, currency),
+                self.gas,
+                format_gas_price(gas_price)
+            ),
+            _ if self.gas > 0 => status!("Estimated gas: {}", self.gas),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change() {
+        let a = BytecodeHash::of([0x60, 0x80]);
+        let b = BytecodeHash::of([0x60, 0x40]);
+
+        assert_eq!(Change::of(None, a), Change::NewContract);
+        assert_eq!(Change::of(Some(a), a), Change::Redeploy);
+        assert_eq!(Change::of(Some(b), a), Change::NewVersion);
+    }
+}
@@ -0,0 +1,356 @@
+//! Deployment files tracked in git
+//!
+//! With `write = true` under `[deployment_files]` in smolder.toml, or
+//! `--write-files`, `deploy` and `sync` write the deployments of each network
+//! they touch to `deployments/<network>.json` (or `.toml`). Entries are
+//! ordered by contract, then version, so a new deployment shows up as a
+//! small diff in review. `smolder sync --from-files` merges them back into
+//! the registry.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use smolder_core::{BytecodeHash, ChainId, CompilerSettings, EvmAddress, TxHash};
+use smolder_db::{Database, NetworkRepository};
+
+use crate::config::{DeploymentFilesConfig, FoundryConfig};
+use crate::output::status;
+use crate::registry_sync::{self, SyncBundle, SyncDeployment, SyncNetwork};
+
+/// Directory the files are written to by default, next to smolder.toml
+pub const DEFAULT_DIR: &str = "deployments";
+
+/// Format of deployment files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    #[default]
+    Json,
+    Toml,
+}
+
+impl FileFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+        }
+    }
+}
+
+/// The deployments of one network, as written to `<network>.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeploymentFile {
+    pub network: String,
+    pub chain_id: ChainId,
+    pub deployments: Vec<FileDeployment>,
+}
+
+/// A deployment with its contract
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDeployment {
+    pub contract: String,
+    pub version: i64,
+    pub address: EvmAddress,
+    pub deployer: String,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "smolder_core::hash::optional_tx_hash"
+    )]
+    pub tx_hash: Option<TxHash>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<i64>,
+    /// Constructor arguments as a JSON string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constructor_args: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initcode_size: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_size: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<i64>,
+    pub source_path: String,
+    pub bytecode_hash: BytecodeHash,
+    /// ABI as a JSON string
+    pub abi: String,
+    /// Storage layout as a JSON string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_layout: Option<String>,
+    /// Compiler version and settings, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<CompilerSettings>,
+}
+
+impl From<SyncDeployment> for FileDeployment {
+    fn from(d: SyncDeployment) -> Self {
+        Self {
+            contract: d.contract,
+            version: d.version,
+            address: d.address,
+            deployer: d.deployer,
+            tx_hash: d.tx_hash,
+            block_number: d.block_number,
+            constructor_args: d.constructor_args,
+            initcode_size: d.initcode_size,
+            runtime_size: d.runtime_size,
+            gas_used: d.gas_used,
+            source_path: d.source_path,
+            bytecode_hash: d.bytecode_hash,
+            abi: d.abi,
+            storage_layout: d.storage_layout,
+            compiler: d.compiler,
+        }
+    }
+}
+
+impl FileDeployment {
+    fn into_sync(self, network: &str) -> SyncDeployment {
+        SyncDeployment {
+            network: network.to_string(),
+            contract: self.contract,
+            version: self.version,
+            source_path: self.source_path,
+            abi: self.abi,
+            bytecode_hash: self.bytecode_hash,
+            storage_layout: self.storage_layout,
+            compiler: self.compiler,
+            address: self.address,
+            deployer: self.deployer,
+            tx_hash: self.tx_hash,
+            block_number: self.block_number,
+            constructor_args: self.constructor_args,
+            initcode_size: self.initcode_size,
+            runtime_size: self.runtime_size,
+            gas_used: self.gas_used,
+            gas_price: None,
+        }
+    }
+}
+
+/// Write the files of `networks` when `--write-files` is given or
+/// smolder.toml asks for them
+pub async fn write_if_enabled(
+    db: &Database,
+    config: &DeploymentFilesConfig,
+    flag: bool,
+    networks: &[String],
+) -> Result<()> {
+    if !(flag || config.write) || networks.is_empty() {
+        return Ok(());
+    }
+    for network in networks {
+        let path = write(db, network, config.dir(), config.format).await?;
+        status!(
+            "   {} Wrote {}",
+            style("*").dim(),
+            style(path.display()).cyan()
+        );
+    }
+    Ok(())
+}
+
+/// Write the non-archived deployments of `network` to `<dir>/<network>.<ext>`
+pub async fn write(
+    db: &Database,
+    network: &str,
+    dir: &Path,
+    format: FileFormat,
+) -> Result<PathBuf> {
+    let bundle = registry_sync::bundle(db, &[network.to_string()]).await?;
+    let chain_id = bundle
+        .networks
+        .first()
+        .map(|n| n.chain_id)
+        .ok_or_else(|| eyre!("Network '{}' is not registered", network))?;
+
+    let mut deployments: Vec<FileDeployment> =
+        bundle.deployments.into_iter().map(Into::into).collect();
+    deployments.sort_by(|a, b| {
+        (&a.contract, a.version, &a.address).cmp(&(&b.contract, b.version, &b.address))
+    });
+    let file = DeploymentFile {
+        network: network.to_string(),
+        chain_id,
+        deployments,
+    };
+
+    let content = match format {
+        FileFormat::Json => serde_json::to_string_pretty(&file)? + "\n",
+        FileFormat::Toml => toml::to_string_pretty(&file)?,
+    };
+    std::fs::create_dir_all(dir).map_err(|e| eyre!("Could not create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.{}", network, format.extension()));
+    std::fs::write(&path, content)
+        .map_err(|e| eyre!("Could not write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Read every `.json` and `.toml` deployment file in `dir`, by file name
+pub fn read_dir(dir: &Path) -> Result<Vec<DeploymentFile>> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| eyre!("Could not read {}: {}", dir.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    let mut files = Vec::new();
+    for path in paths {
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => FileFormat::Json,
+            Some("toml") => FileFormat::Toml,
+            _ => continue,
+        };
+        let content = std::fs::read_to_string(&path)?;
+        let file: DeploymentFile = match format {
+            FileFormat::Json => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            FileFormat::Toml => toml::from_str(&content).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| eyre!("Invalid {}: {}", path.display(), e))?;
+        files.push(file);
+    }
+    Ok(files)
+}
+
+/// Turn deployment files into a bundle to merge, taking each network's RPC
+/// from the registry or foundry.toml
+pub async fn to_bundle(db: &Database, files: Vec<DeploymentFile>) -> Result<SyncBundle> {
+    let foundry = FoundryConfig::exists()
+        .then(FoundryConfig::load)
+        .transpose()?;
+
+    let mut bundle = SyncBundle::default();
+    for file in files {
+        let (rpc_url, explorer_url) =
+            match NetworkRepository::get_by_name(db, &file.network).await? {
+                Some(network) => (network.rpc_url.expose().to_string(), network.explorer_url),
+                None => match foundry
+                    .as_ref()
+                    .and_then(|config| config.get_network(&file.network).ok())
+                {
+                    Some(configured) => (configured.rpc_url, configured.explorer_url),
+                    None => {
+                        return Err(eyre!(
+                            "Network '{}' is not registered or in foundry.toml. Add it with: {}",
+                            file.network,
+                            style(format!(
+                                "smolder network add {} --rpc-url <url>",
+                                file.network
+                            ))
+                            .cyan()
+                        ))
+                    }
+                },
+            };
+        bundle.networks.push(SyncNetwork {
+            name: file.network.clone(),
+            chain_id: file.chain_id,
+            rpc_url,
+            explorer_url,
+        });
+        bundle.deployments.extend(
+            file.deployments
+                .into_iter()
+                .map(|d| d.into_sync(&file.network)),
+        );
+    }
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(contract: &str, version: i64) -> FileDeployment {
+        FileDeployment {
+            contract: contract.to_string(),
+            version,
+            address: format!("0x{:040}", version).parse().unwrap(),
+            deployer: "0xdeployer".to_string(),
+            tx_hash: Some(format!("0x{:064}", version).parse().unwrap()),
+            block_number: Some(version * 10),
+            constructor_args: None,
+            initcode_size: None,
+            runtime_size: None,
+            gas_used: None,
+            source_path: format!("src/{}.sol", contract),
+            bytecode_hash: BytecodeHash::of(contract),
+            abi: "[]".to_string(),
+            storage_layout: None,
+            compiler: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let file = DeploymentFile {
+            network: "sepolia".to_string(),
+            chain_id: ChainId(11155111),
+            deployments: vec![deployment("Token", 1), deployment("Token", 2)],
+        };
+
+        let json = serde_json::to_string_pretty(&file).unwrap();
+        assert!(!json.contains("constructor_args"));
+        let parsed: DeploymentFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.deployments.len(), 2);
+        assert_eq!(parsed.deployments[1].block_number, Some(20));
+
+        let toml = toml::to_string_pretty(&file).unwrap();
+        assert!(toml.contains("[[deployments]]"));
+        let parsed: DeploymentFile = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.chain_id, ChainId(11155111));
+        assert_eq!(parsed.deployments[0].contract, "Token");
+    }
+
+    async fn registry() -> Database {
+        let db = Database::connect_to(":memory:").await.unwrap();
+        db.init_schema().await.unwrap();
+        NetworkRepository::upsert(
+            &db,
+            &smolder_db::NewNetwork {
+                name: "sepolia".to_string(),
+                chain_id: ChainId(11155111),
+                rpc_url: "https://rpc.sepolia.xyz".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_write_and_rebuild() {
+        let dir = std::env::temp_dir().join(format!("smolder-files-{}", std::process::id()));
+        let source = registry().await;
+        let bundle = SyncBundle {
+            networks: Vec::new(),
+            deployments: vec![
+                deployment("Vault", 1).into_sync("sepolia"),
+                deployment("Token", 2).into_sync("sepolia"),
+            ],
+        };
+        registry_sync::merge(&source, &bundle).await.unwrap();
+
+        let path = write(&source, "sepolia", &dir, FileFormat::Toml)
+            .await
+            .unwrap();
+        assert_eq!(path, dir.join("sepolia.toml"));
+        let files = read_dir(&dir).unwrap();
+        assert_eq!(files.len(), 1);
+        // Ordered by contract, whatever the order they were recorded in
+        assert_eq!(files[0].deployments[0].contract, "Token");
+
+        let rebuilt = registry().await;
+        let bundle = to_bundle(&rebuilt, files).await.unwrap();
+        let report = registry_sync::merge(&rebuilt, &bundle).await.unwrap();
+        assert_eq!(report.added, 2);
+        assert!(report.conflicts.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
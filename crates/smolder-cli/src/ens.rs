@@ -0,0 +1,277 @@
+//! ENS names in place of addresses
+//!
+//! Names are resolved on an ENS-enabled chain: the `[ens] network` from
+//! smolder.toml, else the network in use when it is mainnet or a testnet with
+//! the ENS registry, else the registered network with chain ID 1. Lookups,
+//! including misses, are cached in the registry for a day.
+
+use std::time::Duration;
+
+use alloy::json_abi::Param;
+use alloy::primitives::{Address, Bytes, B256};
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use smolder_core::ens::{is_ens_name, namehash, normalize_name, reverse_name, ENS_REGISTRY};
+use smolder_core::Error;
+use smolder_db::{ChainId, EnsLookup, Network, NewEnsRecord, Repositories};
+
+use crate::rpc;
+
+/// How long a cached lookup is trusted
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Chains where the ENS registry lives at [`ENS_REGISTRY`]: mainnet, Sepolia
+/// and Holesky
+const ENS_CHAINS: [u64; 3] = [1, 11_155_111, 17_000];
+
+sol! {
+    interface IEnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    interface IEnsResolver {
+        function addr(bytes32 node) external view returns (address);
+        function name(bytes32 node) external view returns (string);
+    }
+}
+
+/// Resolves names and addresses on one ENS-enabled network
+pub struct EnsResolver<'a> {
+    db: &'a dyn Repositories,
+    network: Network,
+}
+
+impl<'a> EnsResolver<'a> {
+    /// Pick the network to resolve on while working with `network`
+    ///
+    /// `ens_network` is the `[ens] network` setting, which takes precedence.
+    pub async fn new(
+        db: &'a dyn Repositories,
+        network: Option<&Network>,
+        ens_network: Option<&str>,
+    ) -> Result<Self, Error> {
+        let network = match (ens_network, network) {
+            (Some(name), _) => db.networks().get_by_name(name)
+                .await?
+                .ok_or_else(|| Error::NetworkNotFound(name.to_string()))?,
+            (None, Some(n)) if ENS_CHAINS.contains(&u64::from(n.chain_id)) => n.clone(),
+            (None, _) => db.networks().list()
+                .await?
+                .into_iter()
+                .find(|n| n.chain_id == ChainId(1))
+                .ok_or_else(|| {
+                    Error::Config(
+                        "No network to resolve ENS names on. Register mainnet or set `network` under [ens] in smolder.toml".to_string(),
+                    )
+                })?,
+        };
+        Ok(Self { db, network })
+    }
+
+    /// The address `name` resolves to
+    pub async fn resolve(&self, name: &str) -> Result<Address, Error> {
+        let name = normalize_name(name);
+        let result = match self.cached(EnsLookup::Forward, &name).await? {
+            Some(result) => result,
+            None => {
+                let address = self.resolve_uncached(&name).await?;
+                let result = address.map(|a| a.to_string());
+                self.store(EnsLookup::Forward, &name, result.clone())
+                    .await?;
+                result
+            }
+        };
+
+        result
+            .and_then(|a| a.parse().ok())
+            .ok_or_else(|| Error::invalid_param(&name, "ENS name does not resolve to an address"))
+    }
+
+    /// The primary name of `address`, if it has one that resolves back to it
+    pub async fn lookup(&self, address: Address) -> Result<Option<String>, Error> {
+        let key = address.to_string().to_lowercase();
+        if let Some(result) = self.cached(EnsLookup::Reverse, &key).await? {
+            return Ok(result);
+        }
+
+        let node = namehash(&reverse_name(address));
+        let name = match self.resolver_of(node).await? {
+            Some(resolver) => {
+                let data = self
+                    .call(resolver, IEnsResolver::nameCall { node }.abi_encode())
+                    .await?;
+                IEnsResolver::nameCall::abi_decode_returns(&data).unwrap_or_default()
+            }
+            None => String::new(),
+        };
+
+        // A reverse record is only trusted if the name points back
+        let name = match name.is_empty() {
+            true => None,
+            false => (self.resolve_uncached(&normalize_name(&name)).await? == Some(address))
+                .then_some(name),
+        };
+
+        self.store(EnsLookup::Reverse, &key, name.clone()).await?;
+        Ok(name)
+    }
+
+    async fn resolve_uncached(&self, name: &str) -> Result<Option<Address>, Error> {
+        let node = namehash(name);
+        let Some(resolver) = self.resolver_of(node).await? else {
+            return Ok(None);
+        };
+        let data = self
+            .call(resolver, IEnsResolver::addrCall { node }.abi_encode())
+            .await?;
+        let address = IEnsResolver::addrCall::abi_decode_returns(&data)
+            .map_err(|e| Error::AbiDecode(e.to_string()))?;
+        Ok((!address.is_zero()).then_some(address))
+    }
+
+    async fn resolver_of(&self, node: B256) -> Result<Option<Address>, Error> {
+        let data = self
+            .call(
+                ENS_REGISTRY,
+                IEnsRegistry::resolverCall { node }.abi_encode(),
+            )
+            .await?;
+        let resolver = IEnsRegistry::resolverCall::abi_decode_returns(&data)
+            .map_err(|e| Error::AbiDecode(e.to_string()))?;
+        Ok((!resolver.is_zero()).then_some(resolver))
+    }
+
+    async fn call(&self, to: Address, data: Vec<u8>) -> Result<Bytes, Error> {
+        rpc::execute_eth_call(self.network.rpc_url.expose(), to, Bytes::from(data)).await
+    }
+
+    /// The cached result of a fresh lookup; `Some(None)` is a cached miss
+    async fn cached(
+        &self,
+        lookup: EnsLookup,
+        input: &str,
+    ) -> Result<Option<Option<String>>, Error> {
+        let record = self
+            .db
+            .ens_cache()
+            .get(self.network.chain_id, lookup, input, CACHE_TTL)
+            .await?;
+        Ok(record.map(|r| r.result))
+    }
+
+    async fn store(
+        &self,
+        lookup: EnsLookup,
+        input: &str,
+        result: Option<String>,
+    ) -> Result<(), Error> {
+        self.db
+            .ens_cache()
+            .put(&NewEnsRecord {
+                chain_id: self.network.chain_id,
+                lookup,
+                input: input.to_string(),
+                result,
+            })
+            .await
+    }
+}
+
+/// Parse `value` as an address, resolving it first if it is an ENS name
+pub async fn resolve_address(
+    db: &dyn Repositories,
+    network: &Network,
+    ens_network: Option<&str>,
+    value: &str,
+) -> Result<Address, Error> {
+    if !is_ens_name(value) {
+        return value
+            .trim()
+            .parse()
+            .map_err(|e| Error::invalid_param("address", format!("{}", e)));
+    }
+    EnsResolver::new(db, Some(network), ens_network)
+        .await?
+        .resolve(value)
+        .await
+}
+
+/// Replace ENS names given for `address` and `address[]` parameters with the
+/// addresses they resolve to
+///
+/// Nothing is looked up when no parameter holds a name.
+pub async fn resolve_params(
+    db: &dyn Repositories,
+    network: &Network,
+    ens_network: Option<&str>,
+    inputs: &[Param],
+    params: &mut [serde_json::Value],
+) -> Result<(), Error> {
+    let mut names = Vec::new();
+    for (input, value) in inputs.iter().zip(params.iter_mut()) {
+        match value {
+            serde_json::Value::String(s) if input.ty == "address" && is_ens_name(s) => {
+                names.push(value)
+            }
+            serde_json::Value::Array(items) if input.ty.starts_with("address[") => names.extend(
+                items
+                    .iter_mut()
+                    .filter(|item| item.as_str().is_some_and(is_ens_name)),
+            ),
+            _ => {}
+        }
+    }
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    let resolver = EnsResolver::new(db, Some(network), ens_network).await?;
+    for value in names {
+        let name = value.as_str().unwrap_or_default().to_string();
+        *value = serde_json::Value::String(resolver.resolve(&name).await?.to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_db::{Database, NetworkRepository};
+
+    #[tokio::test]
+    async fn test_resolve_params_skips_lookup_without_names() {
+        let db = Database::connect_to(":memory:").await.unwrap();
+        db.init_schema().await.unwrap();
+        let network = NetworkRepository::upsert(
+            &db,
+            &smolder_db::NewNetwork {
+                name: "local".to_string(),
+                chain_id: ChainId(31337),
+                rpc_url: "http://127.0.0.1:1".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let function =
+            smolder_core::parse_function_signature("send(address,address[],string)").unwrap();
+        let mut params = vec![
+            serde_json::json!("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"),
+            serde_json::json!([]),
+            serde_json::json!("hello.eth"),
+        ];
+
+        // The string parameter looks like a name but isn't an address
+        resolve_params(&db, &network, None, &function.inputs, &mut params)
+            .await
+            .unwrap();
+
+        // A name with no ENS network to resolve on is a configuration error
+        params[1] = serde_json::json!(["vitalik.eth"]);
+        let err = resolve_params(&db, &network, None, &function.inputs, &mut params)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Config(_)));
+    }
+}
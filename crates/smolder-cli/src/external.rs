@@ -0,0 +1,155 @@
+//! Transactions sent to tracked deployments without smolder
+//!
+//! Calls made from a multisig, a script or another tool never pass through
+//! smolder, so call history misses them. Once mined they can be imported,
+//! by `tx inspect --import` or the activity monitor of `serve`, as entries
+//! carrying their sender. The function is named from the deployment's ABI,
+//! then from the selectors table, and otherwise by its bare selector.
+
+use alloy::consensus::Transaction as _;
+use alloy::json_abi::Function;
+use alloy::network::TransactionResponse;
+use alloy::rpc::types::{Transaction, TransactionReceipt};
+use smolder_core::{decode_function_input, parse_function_signature, Abi, Error, TxHash};
+use smolder_db::{
+    CallHistory, CallHistoryUpdate, CallType, DeploymentId, NewCallHistory, RecordKind,
+    Repositories, TransactionStatus,
+};
+
+use crate::decode;
+
+/// Record `transaction`, a call to deployment `deployment_id`, in call
+/// history
+///
+/// `None` when the transaction is there already, e.g. because smolder sent
+/// it.
+pub async fn import(
+    repos: &dyn Repositories,
+    deployment_id: DeploymentId,
+    abi: Option<&Abi>,
+    transaction: &Transaction,
+    receipt: &TransactionReceipt,
+) -> Result<Option<CallHistory>, Error> {
+    let tx_hash = TxHash(transaction.tx_hash());
+    if repos
+        .call_history()
+        .get_by_tx_hash(&tx_hash)
+        .await?
+        .is_some()
+    {
+        return Ok(None);
+    }
+
+    let input = transaction.input();
+    let (function_name, function_signature, params) = match identify(repos, abi, input).await? {
+        Some((function, params)) => (
+            function.name.clone(),
+            function.signature(),
+            positional_params(&function, &params),
+        ),
+        None if input.is_empty() => (
+            "receive".to_string(),
+            "receive()".to_string(),
+            serde_json::json!([]),
+        ),
+        None => {
+            let selector = format!("0x{}", hex::encode(&input[..input.len().min(4)]));
+            (
+                "fallback".to_string(),
+                selector,
+                serde_json::json!([format!("0x{}", hex::encode(input))]),
+            )
+        }
+    };
+
+    let sender = transaction.from().to_string();
+    let wallet = repos.wallets().get_by_address(&sender).await?;
+    let entry = NewCallHistory {
+        deployment_id,
+        wallet_id: wallet.map(|w| w.id),
+        function_name,
+        function_signature,
+        input_params: params.to_string(),
+        call_type: CallType::Write,
+    };
+    let outcome = CallHistoryUpdate {
+        result: None,
+        tx_hash: Some(tx_hash),
+        block_number: receipt.block_number.map(|b| b as i64),
+        gas_used: Some(receipt.gas_used as i64),
+        gas_price: Some(receipt.effective_gas_price.to_string()),
+        status: match receipt.status() {
+            true => TransactionStatus::Success,
+            false => TransactionStatus::Reverted,
+        },
+        error_message: None,
+    };
+    let mut call = repos
+        .call_history()
+        .import_external(&entry, &sender, &outcome)
+        .await?;
+    if let (Some(number), Some(hash)) = (call.block_number, receipt.block_hash) {
+        let hash = hash.to_string();
+        repos
+            .reorgs()
+            .set_block(RecordKind::Call, call.id, number, &hash)
+            .await?;
+        call.block_hash = Some(hash);
+    }
+    Ok(Some(call))
+}
+
+/// The function `input` calls and its decoded parameters, from `abi` or
+/// else the first selectors table match
+pub async fn identify(
+    repos: &dyn Repositories,
+    abi: Option<&Abi>,
+    input: &[u8],
+) -> Result<Option<(Function, serde_json::Value)>, Error> {
+    let Some(selector) = input.get(..4) else {
+        return Ok(None);
+    };
+    let from_abi = abi
+        .and_then(|abi| abi.function_by_selector(selector.try_into().ok()?))
+        .and_then(|f| Some((f.clone(), decode_function_input(f, input).ok()?)));
+    if from_abi.is_some() {
+        return Ok(from_abi);
+    }
+
+    let decoded = decode::decode(repos, input, false).await?;
+    Ok(decoded.matches.into_iter().find_map(|m| {
+        let function = parse_function_signature(&m.signature).ok()?;
+        Some((function, m.params))
+    }))
+}
+
+/// Decoded parameters as the positional array call history records
+fn positional_params(function: &Function, params: &serde_json::Value) -> serde_json::Value {
+    function
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let key = match input.name.is_empty() {
+                true => i.to_string(),
+                false => input.name.clone(),
+            };
+            params[&key].clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positional_params() {
+        let function = parse_function_signature("function f(address to, uint256)").unwrap();
+        let params = serde_json::json!({ "to": "0x01", "1": "5" });
+        assert_eq!(
+            positional_params(&function, &params),
+            serde_json::json!(["0x01", "5"])
+        );
+    }
+}
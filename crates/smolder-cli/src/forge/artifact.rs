@@ -1,7 +1,7 @@
 //! Artifact loading trait and filesystem implementation
 
 use color_eyre::eyre::{eyre, Result};
-use smolder_core::Abi;
+use smolder_core::{linking, Abi};
 use std::path::{Path, PathBuf};
 
 use super::types::{ArtifactDetails, ArtifactInfo, ContractArtifact, ContractArtifactFull};
@@ -198,6 +198,9 @@ impl ArtifactLoader for FileSystemArtifactLoader {
         let source_path = self
             .find_source_path(name)
             .unwrap_or_else(|| format!("{}.sol", name));
+        let code_size = artifact.code_size();
+        let compiler = artifact.compiler();
+        let link_references = artifact.bytecode.link_references;
 
         Ok(ArtifactDetails {
             name: name.to_string(),
@@ -205,6 +208,13 @@ impl ArtifactLoader for FileSystemArtifactLoader {
             abi: artifact.abi,
             constructor,
             has_bytecode,
+            initcode_size: code_size.initcode,
+            runtime_size: code_size.runtime,
+            size_warnings: code_size.warnings(),
+            storage_layout: artifact.storage_layout,
+            compiler,
+            libraries: linking::libraries(&link_references),
+            link_references,
         })
     }
 
@@ -249,6 +259,7 @@ impl ArtifactLoader for FileSystemArtifactLoader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use smolder_core::StorageLayout;
 
     #[test]
     fn test_parse_contract_artifact() {
@@ -281,22 +292,98 @@ mod tests {
             artifact.bytecode.without_prefix(),
             "6080604052348015600f57600080fd5b50"
         );
+        assert!(artifact.storage_layout_json().is_none());
+        assert!(artifact.compiler().is_none());
+    }
+
+    #[test]
+    fn test_parse_link_references() {
+        let json = r#"{
+            "abi": [],
+            "bytecode": {
+                "object": "0x73__$1f8d1c6bd1aa4e2f2c0e3bdf5d1c4bba5e$__00",
+                "linkReferences": {"src/Math.sol": {"Math": [{"start": 1, "length": 20}]}}
+            },
+            "deployedBytecode": {"object": "0x60"}
+        }"#;
+
+        let artifact: ContractArtifact = serde_json::from_str(json).unwrap();
+        let references = &artifact.bytecode.link_references;
+        assert_eq!(linking::libraries(references), vec!["src/Math.sol:Math"]);
+
+        let address = alloy::primitives::Address::repeat_byte(0xab);
+        let addresses = [("src/Math.sol:Math".to_string(), address)].into();
+        let linked = linking::link(&artifact.bytecode.object, references, &addresses).unwrap();
+        assert_eq!(linked, format!("0x73{}00", "ab".repeat(20)));
+    }
+
+    #[test]
+    fn test_parse_compiler_metadata() {
+        let json = r#"{
+            "abi": [],
+            "bytecode": {"object": "0x60"},
+            "deployedBytecode": {"object": "0x60"},
+            "metadata": {
+                "compiler": {"version": "0.8.24+commit.e11b9ed9"},
+                "language": "Solidity",
+                "settings": {
+                    "compilationTarget": {"src/Counter.sol": "Counter"},
+                    "evmVersion": "cancun",
+                    "libraries": {},
+                    "optimizer": {"enabled": true, "runs": 200}
+                }
+            }
+        }"#;
+
+        let artifact: ContractArtifact = serde_json::from_str(json).unwrap();
+        let compiler = artifact.compiler().unwrap();
+        assert_eq!(compiler.version, "0.8.24+commit.e11b9ed9");
+        assert_eq!(compiler.optimizer_runs, Some(200));
+        assert_eq!(compiler.evm_version.as_deref(), Some("cancun"));
+        assert!(compiler.libraries.is_empty());
+    }
+
+    #[test]
+    fn test_parse_storage_layout() {
+        let json = r#"{
+            "abi": [],
+            "bytecode": {"object": "0x60"},
+            "deployedBytecode": {"object": "0x60"},
+            "storageLayout": {
+                "storage": [{
+                    "astId": 3, "contract": "src/Counter.sol:Counter", "label": "number",
+                    "offset": 0, "slot": "0", "type": "t_uint256"
+                }],
+                "types": {
+                    "t_uint256": {"encoding": "inplace", "label": "uint256", "numberOfBytes": "32"}
+                }
+            }
+        }"#;
+
+        let artifact: ContractArtifact = serde_json::from_str(json).unwrap();
+        let layout = StorageLayout::parse(&artifact.storage_layout_json().unwrap()).unwrap();
+        assert_eq!(layout.storage.len(), 1);
+        assert_eq!(layout.storage[0].label, "number");
+        assert_eq!(layout.types["t_uint256"].label, "uint256");
     }
 
     #[test]
     fn test_bytecode_object_is_valid() {
         let valid = super::super::types::BytecodeObject {
             object: "0x6080604052".to_string(),
+            link_references: Default::default(),
         };
         assert!(valid.is_valid());
 
         let empty = super::super::types::BytecodeObject {
             object: "".to_string(),
+            link_references: Default::default(),
         };
         assert!(!empty.is_valid());
 
         let just_prefix = super::super::types::BytecodeObject {
             object: "0x".to_string(),
+            link_references: Default::default(),
         };
         assert!(!just_prefix.is_valid());
     }
@@ -1,19 +1,26 @@
 //! Broadcast parsing trait and forge implementation
 
 use alloy::hex;
-use alloy::primitives::keccak256;
 use color_eyre::eyre::{eyre, Result};
+use smolder_core::{BytecodeHash, CompilerSettings, EvmAddress};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use super::artifact::{ArtifactLoader, FileSystemArtifactLoader};
-use super::types::{BroadcastOutput, ParsedDeployment};
+use super::types::{BroadcastOutput, BroadcastRun, BroadcastTransaction, ParsedDeployment};
 
 /// Trait for parsing broadcast outputs from deployment scripts
 pub trait BroadcastParser: Send + Sync {
     /// Parse the broadcast output for a given script and chain ID
     fn parse(&self, script_path: &str, chain_id: u64) -> Result<BroadcastOutput>;
 
+    /// Find every output on disk, in chronological order
+    fn discover(&self) -> Result<Vec<BroadcastRun>>;
+
+    /// Load an output found by [`BroadcastParser::discover`]
+    fn load(&self, run: &BroadcastRun) -> Result<BroadcastOutput>;
+
     /// Extract deployment information from a broadcast output
     fn extract_deployments(&self, broadcast: &BroadcastOutput) -> Result<Vec<ParsedDeployment>>;
 }
@@ -53,58 +60,109 @@ impl ForgeBroadcastParser {
     /// Extract deployment info from a single transaction
     fn extract_single_deployment(
         &self,
-        tx: &super::types::BroadcastTransaction,
+        tx: &BroadcastTransaction,
         broadcast: &BroadcastOutput,
     ) -> Result<ParsedDeployment> {
-        let contract_name = tx.contract_name.as_ref().unwrap().clone();
-        let address = tx.contract_address.as_ref().unwrap().clone();
-
-        // Load artifact for this contract
-        let artifact = self.artifact_loader.load(&contract_name)?;
-
-        // Find matching receipt for block number
-        let block_number = broadcast
-            .receipts
-            .iter()
-            .find(|r| r.transaction_hash == tx.hash)
-            .and_then(|r| parse_hex_block_number(&r.block_number));
-
-        // Compute bytecode hash
-        let bytecode = artifact.bytecode.without_prefix();
-        let bytecode_bytes = hex::decode(bytecode).unwrap_or_default();
-        let bytecode_hash = format!("{:x}", keccak256(&bytecode_bytes));
-
-        // Serialize constructor args if present
-        let constructor_args = tx
-            .arguments
-            .as_ref()
-            .map(serde_json::to_string)
-            .transpose()?;
-
+        let contract_name = tx.contract_name.as_ref().unwrap();
         let source_path = format!("src/{}.sol:{}", contract_name, contract_name);
 
-        Ok(ParsedDeployment {
-            contract_name,
-            address,
-            deployer: tx.transaction.from.clone(),
-            tx_hash: tx.hash.clone(),
-            block_number,
-            constructor_args,
-            abi: serde_json::to_string(&artifact.abi)?,
-            bytecode_hash,
-            source_path,
-        })
+        build_deployment(self.artifact_loader.as_ref(), tx, broadcast, source_path)
     }
 }
 
+/// Combine a CREATE transaction with its artifact into a deployment record
+pub(super) fn build_deployment(
+    loader: &dyn ArtifactLoader,
+    tx: &BroadcastTransaction,
+    broadcast: &BroadcastOutput,
+    source_path: String,
+) -> Result<ParsedDeployment> {
+    let contract_name = tx.contract_name.as_ref().unwrap().clone();
+    let address: EvmAddress = tx.contract_address.as_ref().unwrap().parse()?;
+
+    // Load artifact for this contract
+    let artifact = loader.load(&contract_name)?;
+
+    // Find matching receipt for block number and gas used
+    let receipt = broadcast
+        .receipts
+        .iter()
+        .find(|r| tx.hash.as_ref() == Some(&r.transaction_hash));
+    let block_number = receipt.and_then(|r| parse_hex_block_number(&r.block_number));
+    let gas_used = receipt.and_then(|r| r.gas_used());
+
+    // Compute bytecode hash
+    let bytecode = artifact.bytecode.without_prefix();
+    let bytecode_bytes = hex::decode(bytecode).unwrap_or_default();
+    let bytecode_hash = BytecodeHash::of(&bytecode_bytes);
+
+    let tx_hash = tx
+        .hash
+        .as_deref()
+        .ok_or_else(|| eyre!("{} has no transaction hash", contract_name))?
+        .parse()?;
+
+    // Serialize constructor args if present
+    let constructor_args = tx
+        .arguments
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+
+    Ok(ParsedDeployment {
+        contract_name,
+        address,
+        deployer: tx.transaction.from.clone(),
+        tx_hash,
+        block_number,
+        constructor_args,
+        abi: serde_json::to_string(&artifact.abi)?,
+        bytecode_hash,
+        source_path,
+        code_size: artifact.code_size(),
+        storage_layout: artifact.storage_layout_json(),
+        compiler: artifact.compiler().or_else(|| {
+            let metadata = serde_json::from_str(tx.metadata.as_deref()?).ok()?;
+            CompilerSettings::from_metadata(&metadata)
+        }),
+        gas_used,
+    })
+}
+
 impl Default for ForgeBroadcastParser {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl BroadcastParser for ForgeBroadcastParser {
-    fn parse(&self, script_path: &str, chain_id: u64) -> Result<BroadcastOutput> {
+impl ForgeBroadcastParser {
+    /// Parse the output of a script run without `--broadcast`
+    ///
+    /// Forge leaves the file alone when the script broadcasts nothing, so a
+    /// file older than `since` is from an earlier run and reads as empty.
+    pub fn parse_dry_run(
+        &self,
+        script_path: &str,
+        chain_id: u64,
+        since: SystemTime,
+    ) -> Result<BroadcastOutput> {
+        let path = self
+            .run_dir(script_path, chain_id)?
+            .join("dry-run")
+            .join("run-latest.json");
+        let fresh = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified >= since);
+        if !fresh {
+            return Ok(BroadcastOutput::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// `broadcast/<script file>/<chain ID>`
+    fn run_dir(&self, script_path: &str, chain_id: u64) -> Result<PathBuf> {
         // Strip :ContractName suffix if present
         let script_file = script_path.split(':').next().unwrap_or(script_path);
 
@@ -114,11 +172,16 @@ impl BroadcastParser for ForgeBroadcastParser {
             .and_then(|n| n.to_str())
             .ok_or_else(|| eyre!("Invalid script path"))?;
 
-        let broadcast_path = self
+        Ok(self
             .broadcast_dir
             .join(script_name)
-            .join(chain_id.to_string())
-            .join("run-latest.json");
+            .join(chain_id.to_string()))
+    }
+}
+
+impl BroadcastParser for ForgeBroadcastParser {
+    fn parse(&self, script_path: &str, chain_id: u64) -> Result<BroadcastOutput> {
+        let broadcast_path = self.run_dir(script_path, chain_id)?.join("run-latest.json");
 
         let content = std::fs::read_to_string(&broadcast_path).map_err(|_| {
             eyre!(
@@ -131,6 +194,91 @@ impl BroadcastParser for ForgeBroadcastParser {
         Ok(output)
     }
 
+    fn discover(&self) -> Result<Vec<BroadcastRun>> {
+        if !self.broadcast_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        // (script name, chain ID, run sort key, run)
+        let mut runs = Vec::new();
+
+        // broadcast/<ScriptName>/<chainId>/run-*.json
+        for script_entry in std::fs::read_dir(&self.broadcast_dir)? {
+            let script_path = script_entry?.path();
+
+            if !script_path.is_dir() {
+                continue;
+            }
+
+            let script_name = script_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+
+            for chain_entry in std::fs::read_dir(&script_path)? {
+                let chain_path = chain_entry?.path();
+
+                if !chain_path.is_dir() {
+                    continue;
+                }
+
+                let chain_id: u64 = match chain_path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.parse().ok())
+                {
+                    Some(id) => id,
+                    None => continue,
+                };
+
+                for file_entry in std::fs::read_dir(&chain_path)? {
+                    let file_path = file_entry?.path();
+
+                    if !file_path.is_file() {
+                        continue;
+                    }
+
+                    // Extract run identifier from run-*.json (e.g., "1", "2", "latest")
+                    let run_id = match file_path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .and_then(|n| n.strip_prefix("run-"))
+                        .and_then(|n| n.strip_suffix(".json"))
+                    {
+                        Some(id) => id.to_string(),
+                        None => continue,
+                    };
+
+                    // Numbered runs first (1, 2, 3...), then "latest"
+                    let sort_key = run_id.parse::<u64>().unwrap_or(u64::MAX);
+
+                    runs.push((
+                        script_name.clone(),
+                        chain_id,
+                        sort_key,
+                        BroadcastRun {
+                            label: format!("{} (run-{})", script_name, run_id),
+                            path: file_path,
+                            chain_id,
+                        },
+                    ));
+                }
+            }
+        }
+
+        // Sort by script name, chain ID, then run number (chronological order)
+        runs.sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+
+        Ok(runs.into_iter().map(|(_, _, _, run)| run).collect())
+    }
+
+    fn load(&self, run: &BroadcastRun) -> Result<BroadcastOutput> {
+        let content = std::fs::read_to_string(&run.path)?;
+        let output: BroadcastOutput = serde_json::from_str(&content)?;
+        Ok(output)
+    }
+
     fn extract_deployments(&self, broadcast: &BroadcastOutput) -> Result<Vec<ParsedDeployment>> {
         broadcast
             .transactions
@@ -172,7 +320,8 @@ mod tests {
                 {
                     "transactionHash": "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef",
                     "blockNumber": "0x10",
-                    "contractAddress": "0xabcdef1234567890abcdef1234567890abcdef12"
+                    "contractAddress": "0xabcdef1234567890abcdef1234567890abcdef12",
+                    "gasUsed": "0x1e8480"
                 }
             ]
         }"#;
@@ -181,6 +330,7 @@ mod tests {
 
         assert_eq!(output.transactions.len(), 1);
         assert_eq!(output.receipts.len(), 1);
+        assert_eq!(output.receipts[0].gas_used(), Some(2_000_000));
 
         let tx = &output.transactions[0];
         assert!(tx.is_create());
@@ -225,6 +375,53 @@ mod tests {
         assert!(output.transactions[1].is_create());
     }
 
+    #[test]
+    fn test_parse_dry_run() {
+        let root = std::env::temp_dir().join(format!("smolder-dry-run-{}", std::process::id()));
+        let dir = root.join("broadcast/Deploy.s.sol/1/dry-run");
+        std::fs::create_dir_all(&dir).unwrap();
+        let parser = ForgeBroadcastParser::with_paths(&root);
+
+        let before = SystemTime::now() - std::time::Duration::from_secs(60);
+        std::fs::write(
+            dir.join("run-latest.json"),
+            r#"{
+                "transactions": [
+                    {
+                        "hash": null,
+                        "transactionType": "CREATE",
+                        "contractName": "Token",
+                        "contractAddress": "0x2222222222222222222222222222222222222222",
+                        "arguments": null,
+                        "transaction": {
+                            "from": "0x1111111111111111111111111111111111111111",
+                            "gas": "0x1e8480",
+                            "data": "0x6080"
+                        }
+                    }
+                ],
+                "receipts": []
+            }"#,
+        )
+        .unwrap();
+
+        let output = parser
+            .parse_dry_run("script/Deploy.s.sol:Deploy", 1, before)
+            .unwrap();
+        assert_eq!(output.transactions.len(), 1);
+        assert_eq!(output.transactions[0].hash, None);
+        assert_eq!(output.transactions[0].transaction.gas(), Some(2_000_000));
+
+        // Left over from an earlier run
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        let stale = parser
+            .parse_dry_run("script/Deploy.s.sol", 1, later)
+            .unwrap();
+        assert!(stale.transactions.is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn test_parse_hex_block_number() {
         assert_eq!(parse_hex_block_number("0x1a2b3c"), Some(1715004));
@@ -261,9 +458,10 @@ mod tests {
     fn test_bytecode_hash_computation() {
         let bytecode = "6080604052";
         let bytecode_bytes = hex::decode(bytecode).unwrap();
-        let hash = format!("{:x}", keccak256(&bytecode_bytes));
+        let hash = BytecodeHash::of(&bytecode_bytes).to_string();
 
-        assert_eq!(hash.len(), 64);
+        assert_eq!(hash.len(), 66);
+        assert!(hash.starts_with("0x"));
     }
 
     #[test]
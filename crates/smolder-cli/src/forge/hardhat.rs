@@ -0,0 +1,569 @@
+//! Hardhat artifact loading and hardhat-deploy deployment parsing
+//!
+//! Lets Hardhat projects use the registry and dashboard. Compiled artifacts
+//! are read from `artifacts/contracts/**/<Name>.sol/<Name>.json` and
+//! deployments from hardhat-deploy's `deployments/<network>/<Name>.json`.
+
+use color_eyre::eyre::{eyre, Result};
+use serde::Deserialize;
+use smolder_core::{linking, Abi, LinkReferences};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::artifact::ArtifactLoader;
+use super::broadcast::{build_deployment, BroadcastParser};
+use super::types::{
+    ArtifactDetails, ArtifactInfo, BroadcastOutput, BroadcastReceipt, BroadcastRun,
+    BroadcastTransaction, BytecodeObject, ContractArtifact, ParsedDeployment, TransactionData,
+};
+
+// =============================================================================
+// File Formats
+// =============================================================================
+
+/// A Hardhat compilation artifact (`hh-sol-artifact-1`)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HardhatArtifact {
+    contract_name: String,
+    source_name: String,
+    abi: serde_json::Value,
+    bytecode: String,
+    deployed_bytecode: String,
+    #[serde(default)]
+    link_references: LinkReferences,
+}
+
+impl HardhatArtifact {
+    fn into_contract_artifact(self) -> ContractArtifact {
+        ContractArtifact {
+            abi: self.abi,
+            bytecode: BytecodeObject {
+                object: self.bytecode,
+                link_references: self.link_references,
+            },
+            deployed_bytecode: BytecodeObject {
+                object: self.deployed_bytecode,
+                link_references: LinkReferences::new(),
+            },
+            // Hardhat keeps layouts and metadata in build-info, not in artifacts
+            storage_layout: None,
+            metadata: None,
+        }
+    }
+}
+
+/// A hardhat-deploy deployment file
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HardhatDeployment {
+    address: String,
+    transaction_hash: Option<String>,
+    receipt: Option<HardhatReceipt>,
+    args: Option<Vec<serde_json::Value>>,
+    /// Solidity metadata JSON, as a string
+    metadata: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HardhatReceipt {
+    from: String,
+    block_number: u64,
+    /// A decimal string, or a number in older files
+    #[serde(default)]
+    gas_used: Option<serde_json::Value>,
+}
+
+// =============================================================================
+// Artifact Loader
+// =============================================================================
+
+/// Artifact loader that reads Hardhat compilation output
+#[derive(Debug, Clone)]
+pub struct HardhatArtifactLoader {
+    /// Artifacts of project sources (typically "artifacts/contracts")
+    contracts_dir: PathBuf,
+}
+
+impl HardhatArtifactLoader {
+    /// Create a new loader with default paths relative to current directory
+    pub fn new() -> Self {
+        Self::with_paths(Path::new("."))
+    }
+
+    /// Create a new loader with paths relative to the given project root
+    pub fn with_paths(project_root: &Path) -> Self {
+        Self {
+            contracts_dir: project_root.join("artifacts").join("contracts"),
+        }
+    }
+
+    /// All artifact files, skipping hardhat's `.dbg.json` companions
+    fn artifact_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        collect_artifact_files(&self.contracts_dir, &mut files);
+        files
+    }
+
+    fn read(&self, path: &Path) -> Result<HardhatArtifact> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn find(&self, name: &str) -> Result<HardhatArtifact> {
+        let file_name = format!("{}.json", name);
+        self.artifact_files()
+            .into_iter()
+            .find(|p| p.file_name().is_some_and(|n| *n == *file_name))
+            .ok_or_else(|| {
+                eyre!(
+                    "Could not find artifact for contract '{}'. Make sure `npx hardhat compile` was run.",
+                    name
+                )
+            })
+            .and_then(|path| self.read(&path))
+    }
+}
+
+impl Default for HardhatArtifactLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn collect_artifact_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_artifact_files(&path, files);
+            continue;
+        }
+
+        let is_artifact = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.ends_with(".json") && !n.ends_with(".dbg.json"));
+        if is_artifact {
+            files.push(path);
+        }
+    }
+}
+
+impl ArtifactLoader for HardhatArtifactLoader {
+    fn list(&self) -> Result<Vec<ArtifactInfo>> {
+        let mut artifacts = Vec::new();
+
+        for path in self.artifact_files() {
+            let Ok(artifact) = self.read(&path) else {
+                continue;
+            };
+
+            // Skip artifacts without bytecode (interfaces, abstract contracts)
+            let bytecode = BytecodeObject {
+                object: artifact.bytecode,
+                link_references: LinkReferences::new(),
+            };
+            if !bytecode.is_valid() {
+                continue;
+            }
+
+            let has_constructor = Abi::from_value(&artifact.abi)
+                .map(|abi| abi.has_constructor_with_args())
+                .unwrap_or(false);
+
+            artifacts.push(ArtifactInfo {
+                name: artifact.contract_name,
+                source_path: artifact.source_name,
+                has_constructor,
+                has_bytecode: true,
+            });
+        }
+
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(artifacts)
+    }
+
+    fn get_details(&self, name: &str) -> Result<ArtifactDetails> {
+        let artifact = self.find(name)?;
+        let source_path = artifact.source_name.clone();
+        let artifact = artifact.into_contract_artifact();
+        let constructor = Abi::from_value(&artifact.abi)
+            .ok()
+            .and_then(|abi| abi.constructor());
+        let code_size = artifact.code_size();
+        let has_bytecode = artifact.bytecode.is_valid();
+        let link_references = artifact.bytecode.link_references;
+
+        Ok(ArtifactDetails {
+            name: name.to_string(),
+            source_path,
+            has_bytecode,
+            abi: artifact.abi,
+            constructor,
+            initcode_size: code_size.initcode,
+            runtime_size: code_size.runtime,
+            size_warnings: code_size.warnings(),
+            storage_layout: None,
+            compiler: None,
+            libraries: linking::libraries(&link_references),
+            link_references,
+        })
+    }
+
+    fn get_bytecode(&self, name: &str) -> Result<String> {
+        let artifact = self.load(name)?;
+        let bytecode = artifact.bytecode.without_prefix().to_string();
+
+        if bytecode.is_empty() {
+            return Err(eyre!(
+                "Artifact '{}' has no bytecode (may be an interface or abstract contract)",
+                name
+            ));
+        }
+
+        Ok(bytecode)
+    }
+
+    fn load(&self, name: &str) -> Result<ContractArtifact> {
+        Ok(self.find(name)?.into_contract_artifact())
+    }
+}
+
+// =============================================================================
+// Deployments Parser
+// =============================================================================
+
+/// Parser for hardhat-deploy's `deployments/` directory
+///
+/// Each network folder is treated as one output. hardhat-deploy keeps only the
+/// latest deployment per name, so there is no run history to replay.
+#[derive(Clone)]
+pub struct HardhatDeployParser {
+    /// Directory containing per-network deployments (typically "deployments")
+    deployments_dir: PathBuf,
+    /// Artifact loader for fetching contract ABIs and bytecode
+    artifact_loader: Arc<dyn ArtifactLoader>,
+}
+
+impl HardhatDeployParser {
+    /// Create a new parser with default paths relative to current directory
+    pub fn new() -> Self {
+        Self::with_paths(Path::new("."))
+    }
+
+    /// Create a new parser with paths relative to the given project root
+    pub fn with_paths(project_root: &Path) -> Self {
+        Self {
+            deployments_dir: project_root.join("deployments"),
+            artifact_loader: Arc::new(HardhatArtifactLoader::with_paths(project_root)),
+        }
+    }
+
+    /// Create a new parser with explicit deployments directory and artifact loader
+    #[cfg(test)]
+    pub fn with_loader(deployments_dir: PathBuf, artifact_loader: Arc<dyn ArtifactLoader>) -> Self {
+        Self {
+            deployments_dir,
+            artifact_loader,
+        }
+    }
+}
+
+impl Default for HardhatDeployParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BroadcastParser for HardhatDeployParser {
+    /// hardhat-deploy output is organised by network rather than by script,
+    /// so the script path is ignored and the network folder for `chain_id` is read
+    fn parse(&self, _script_path: &str, chain_id: u64) -> Result<BroadcastOutput> {
+        let run = self
+            .discover()?
+            .into_iter()
+            .find(|run| run.chain_id == chain_id)
+            .ok_or_else(|| {
+                eyre!(
+                    "No hardhat-deploy deployments found for chain ID {} in {}",
+                    chain_id,
+                    self.deployments_dir.display()
+                )
+            })?;
+
+        self.load(&run)
+    }
+
+    fn discover(&self) -> Result<Vec<BroadcastRun>> {
+        if !self.deployments_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut runs = Vec::new();
+
+        // deployments/<network>/.chainId
+        for entry in std::fs::read_dir(&self.deployments_dir)? {
+            let path = entry?.path();
+
+            let chain_id = std::fs::read_to_string(path.join(".chainId"))
+                .ok()
+                .and_then(|id| id.trim().parse::<u64>().ok());
+            let Some(chain_id) = chain_id else {
+                continue;
+            };
+
+            let network = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+
+            runs.push(BroadcastRun {
+                label: format!("deployments/{}", network),
+                path,
+                chain_id,
+            });
+        }
+
+        runs.sort_by(|a, b| a.label.cmp(&b.label));
+        Ok(runs)
+    }
+
+    fn load(&self, run: &BroadcastRun) -> Result<BroadcastOutput> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&run.path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "json"))
+            // Skip hardhat-deploy bookkeeping such as .migrations.json
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| !n.starts_with('.'))
+            })
+            .collect();
+        files.sort();
+
+        let mut output = BroadcastOutput {
+            transactions: Vec::new(),
+            receipts: Vec::new(),
+        };
+
+        for path in files {
+            let name = path
+                .file_stem()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+            let content = std::fs::read_to_string(&path)?;
+            let deployment: HardhatDeployment = serde_json::from_str(&content)
+                .map_err(|e| eyre!("Failed to parse {}: {}", path.display(), e))?;
+
+            // Deployments saved without a transaction (e.g. external addresses)
+            // can't be tracked
+            let Some(tx_hash) = deployment.transaction_hash else {
+                continue;
+            };
+
+            // Deployment names are free-form; the compiled contract comes from metadata
+            let contract_name = deployment
+                .metadata
+                .as_deref()
+                .and_then(compilation_target)
+                .unwrap_or(name);
+
+            if let Some(receipt) = &deployment.receipt {
+                output.receipts.push(BroadcastReceipt {
+                    transaction_hash: tx_hash.clone(),
+                    block_number: format!("{:#x}", receipt.block_number),
+                    contract_address: Some(deployment.address.clone()),
+                    gas_used: receipt.gas_used.as_ref().map(|gas| match gas {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    }),
+                });
+            }
+
+            output.transactions.push(BroadcastTransaction {
+                hash: Some(tx_hash),
+                transaction_type: "CREATE".to_string(),
+                contract_name: Some(contract_name),
+                contract_address: Some(deployment.address),
+                arguments: deployment.args,
+                transaction: TransactionData {
+                    from: deployment.receipt.map(|r| r.from).unwrap_or_default(),
+                    data: None,
+                    gas: None,
+                },
+                metadata: deployment.metadata,
+            });
+        }
+
+        Ok(output)
+    }
+
+    fn extract_deployments(&self, broadcast: &BroadcastOutput) -> Result<Vec<ParsedDeployment>> {
+        broadcast
+            .transactions
+            .iter()
+            .filter(|tx| tx.is_create() && tx.has_deployment_info())
+            .map(|tx| {
+                let name = tx.contract_name.as_ref().unwrap();
+                let source = self.artifact_loader.get_details(name)?.source_path;
+                let source_path = format!("{}:{}", source, name);
+                build_deployment(self.artifact_loader.as_ref(), tx, broadcast, source_path)
+            })
+            .collect()
+    }
+}
+
+/// Contract name from the `settings.compilationTarget` of Solidity metadata
+fn compilation_target(metadata: &str) -> Option<String> {
+    let metadata: serde_json::Value = serde_json::from_str(metadata).ok()?;
+    metadata["settings"]["compilationTarget"]
+        .as_object()?
+        .values()
+        .next()?
+        .as_str()
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_core::CodeSize;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn project(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("smolder-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        write(
+            &root.join("artifacts/contracts/tokens/Token.sol/Token.json"),
+            r#"{
+                "_format": "hh-sol-artifact-1",
+                "contractName": "Token",
+                "sourceName": "contracts/tokens/Token.sol",
+                "abi": [{"type": "constructor", "inputs": [{"name": "supply", "type": "uint256"}]}],
+                "bytecode": "0x6080604052",
+                "deployedBytecode": "0x6080"
+            }"#,
+        );
+        write(
+            &root.join("artifacts/contracts/tokens/Token.sol/Token.dbg.json"),
+            r#"{"_format": "hh-sol-dbg-1", "buildInfo": "../../../build-info/x.json"}"#,
+        );
+        write(
+            &root.join("artifacts/contracts/IToken.sol/IToken.json"),
+            r#"{
+                "_format": "hh-sol-artifact-1",
+                "contractName": "IToken",
+                "sourceName": "contracts/IToken.sol",
+                "abi": [],
+                "bytecode": "0x",
+                "deployedBytecode": "0x"
+            }"#,
+        );
+
+        write(&root.join("deployments/sepolia/.chainId"), "11155111\n");
+        write(
+            &root.join("deployments/sepolia/.migrations.json"),
+            r#"{"deploy_token": 1700000000}"#,
+        );
+        write(
+            &root.join("deployments/sepolia/MainToken.json"),
+            r#"{
+                "address": "0x1111111111111111111111111111111111111111",
+                "abi": [],
+                "transactionHash": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "receipt": {
+                    "from": "0x2222222222222222222222222222222222222222",
+                    "blockNumber": 16,
+                    "gasUsed": "1500000"
+                },
+                "args": ["1000"],
+                "metadata": "{\"settings\":{\"compilationTarget\":{\"contracts/tokens/Token.sol\":\"Token\"}}}"
+            }"#,
+        );
+
+        root
+    }
+
+    #[test]
+    fn test_list_hardhat_artifacts() {
+        let root = project("hardhat-artifacts");
+        let loader = HardhatArtifactLoader::with_paths(&root);
+
+        let artifacts = loader.list().unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].name, "Token");
+        assert_eq!(artifacts[0].source_path, "contracts/tokens/Token.sol");
+        assert!(artifacts[0].has_constructor);
+
+        assert_eq!(loader.get_bytecode("Token").unwrap(), "6080604052");
+        assert!(loader.get_bytecode("IToken").is_err());
+        assert!(loader.load("Missing").is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_hardhat_deployments() {
+        let root = project("hardhat-deployments");
+        let parser = HardhatDeployParser::with_paths(&root);
+
+        let runs = parser.discover().unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].chain_id, 11155111);
+        assert_eq!(runs[0].label, "deployments/sepolia");
+
+        let output = parser.parse("ignored", 11155111).unwrap();
+        let deployments = parser.extract_deployments(&output).unwrap();
+        assert_eq!(deployments.len(), 1);
+
+        let deployment = &deployments[0];
+        assert_eq!(deployment.contract_name, "Token");
+        assert_eq!(deployment.source_path, "contracts/tokens/Token.sol:Token");
+        assert_eq!(
+            deployment.deployer,
+            "0x2222222222222222222222222222222222222222"
+        );
+        assert_eq!(deployment.block_number, Some(16));
+        assert_eq!(deployment.constructor_args.as_deref(), Some(r#"["1000"]"#));
+        assert_eq!(deployment.gas_used, Some(1_500_000));
+        assert_eq!(
+            deployment.code_size,
+            CodeSize {
+                initcode: 5,
+                runtime: 2
+            }
+        );
+
+        assert!(parser.parse("ignored", 1).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parser_with_custom_paths() {
+        let loader = Arc::new(HardhatArtifactLoader::with_paths(Path::new("/custom")));
+        let parser = HardhatDeployParser::with_loader(PathBuf::from("/custom/deployments"), loader);
+
+        assert_eq!(parser.deployments_dir, PathBuf::from("/custom/deployments"));
+    }
+
+    #[test]
+    fn test_compilation_target() {
+        let metadata = r#"{"settings":{"compilationTarget":{"contracts/A.sol":"A"}}}"#;
+        assert_eq!(compilation_target(metadata), Some("A".to_string()));
+        assert_eq!(compilation_target("{}"), None);
+        assert_eq!(compilation_target("not json"), None);
+    }
+}
@@ -1,8 +1,8 @@
 //! Forge artifact and broadcast handling
 //!
 //! This module provides utilities for:
-//! - Loading contract artifacts from forge build output
-//! - Parsing broadcast outputs from forge script
+//! - Loading contract artifacts from forge (or Hardhat) build output
+//! - Parsing broadcast outputs from forge script (or hardhat-deploy)
 //! - Extracting deployment information
 //!
 //! # Traits
@@ -16,6 +16,11 @@
 //!
 //! - [`FileSystemArtifactLoader`] - Loads artifacts from forge build output on disk
 //! - [`ForgeBroadcastParser`] - Parses forge script broadcast files
+//! - [`HardhatArtifactLoader`] - Loads artifacts from Hardhat's `artifacts/` directory
+//! - [`HardhatDeployParser`] - Parses hardhat-deploy `deployments/` files
+//!
+//! Use [`artifact_loader`] and [`broadcast_parser`] to get the implementation
+//! matching the project's configured [`Framework`].
 //!
 //! # Example
 //!
@@ -29,8 +34,13 @@
 
 mod artifact;
 mod broadcast;
+mod hardhat;
 mod types;
 
+use std::sync::Arc;
+
+use crate::config::Framework;
+
 // Re-export traits
 pub use artifact::ArtifactLoader;
 pub use broadcast::BroadcastParser;
@@ -38,6 +48,23 @@ pub use broadcast::BroadcastParser;
 // Re-export implementations
 pub use artifact::FileSystemArtifactLoader;
 pub use broadcast::ForgeBroadcastParser;
+pub use hardhat::{HardhatArtifactLoader, HardhatDeployParser};
 
 // Re-export data types
 pub use types::{ArtifactDetails, ArtifactInfo, BroadcastOutput};
+
+/// Artifact loader for the given framework, rooted at the current directory
+pub fn artifact_loader(framework: Framework) -> Arc<dyn ArtifactLoader> {
+    match framework {
+        Framework::Foundry => Arc::new(FileSystemArtifactLoader::new()),
+        Framework::Hardhat => Arc::new(HardhatArtifactLoader::new()),
+    }
+}
+
+/// Broadcast parser for the given framework, rooted at the current directory
+pub fn broadcast_parser(framework: Framework) -> Box<dyn BroadcastParser> {
+    match framework {
+        Framework::Foundry => Box::new(ForgeBroadcastParser::new()),
+        Framework::Hardhat => Box::new(HardhatDeployParser::new()),
+    }
+}
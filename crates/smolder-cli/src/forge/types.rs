@@ -1,16 +1,37 @@
 //! Type definitions for forge artifacts and broadcast outputs
 
+use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
-use smolder_core::ConstructorInfo;
+use smolder_core::{
+    linking, BytecodeHash, CodeSize, CompilerSettings, ConstructorInfo, EvmAddress, LinkReferences,
+    StorageLayout, TxHash,
+};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use utoipa::ToSchema;
 
 // =============================================================================
 // Broadcast Types
 // =============================================================================
 
+/// A deployment output found on disk, ready to be loaded by a [`BroadcastParser`]
+///
+/// [`BroadcastParser`]: super::BroadcastParser
+#[derive(Debug, Clone)]
+pub struct BroadcastRun {
+    /// File or directory holding the output
+    pub path: PathBuf,
+    pub chain_id: u64,
+    /// Human readable description for progress output
+    pub label: String,
+}
+
 /// Represents the broadcast output from forge script
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct BroadcastOutput {
     pub transactions: Vec<BroadcastTransaction>,
+    /// Empty for dry runs
+    #[serde(default)]
     pub receipts: Vec<BroadcastReceipt>,
 }
 
@@ -18,12 +39,16 @@ pub struct BroadcastOutput {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BroadcastTransaction {
-    pub hash: String,
+    /// `None` for dry runs, which don't sign the transaction
+    pub hash: Option<String>,
     pub transaction_type: String,
     pub contract_name: Option<String>,
     pub contract_address: Option<String>,
     pub arguments: Option<Vec<serde_json::Value>>,
     pub transaction: TransactionData,
+    /// Solidity metadata saved with the deployment (hardhat-deploy)
+    #[serde(skip)]
+    pub metadata: Option<String>,
 }
 
 impl BroadcastTransaction {
@@ -44,6 +69,16 @@ pub struct TransactionData {
     pub from: String,
     #[allow(dead_code)]
     pub data: Option<String>,
+    /// Gas limit forge estimated, as hex
+    #[serde(default)]
+    pub gas: Option<String>,
+}
+
+impl TransactionData {
+    /// Gas limit of the transaction, if recorded
+    pub fn gas(&self) -> Option<u64> {
+        parse_quantity(self.gas.as_deref()?)
+    }
 }
 
 /// A receipt from the broadcast output
@@ -54,6 +89,25 @@ pub struct BroadcastReceipt {
     pub block_number: String,
     #[allow(dead_code)]
     pub contract_address: Option<String>,
+    /// Hex (forge) or decimal (hardhat-deploy) gas used
+    #[serde(default)]
+    pub gas_used: Option<String>,
+}
+
+impl BroadcastReceipt {
+    /// Gas used by the transaction, if recorded
+    pub fn gas_used(&self) -> Option<i64> {
+        parse_quantity(self.gas_used.as_deref()?)?.try_into().ok()
+    }
+}
+
+/// Parse a hex (`0x`-prefixed) or decimal quantity
+fn parse_quantity(value: &str) -> Option<u64> {
+    let value = value.trim();
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
 }
 
 // =============================================================================
@@ -66,14 +120,46 @@ pub struct ContractArtifact {
     pub abi: serde_json::Value,
     pub bytecode: BytecodeObject,
     #[serde(rename = "deployedBytecode")]
-    #[allow(dead_code)]
     pub deployed_bytecode: BytecodeObject,
+    /// Present when built with `extra_output = ["storageLayout"]`
+    #[serde(rename = "storageLayout", default)]
+    pub storage_layout: Option<serde_json::Value>,
+    /// Solidity metadata: compiler version and settings
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl ContractArtifact {
+    /// Init code and runtime code sizes
+    pub fn code_size(&self) -> CodeSize {
+        CodeSize::from_hex(&self.bytecode.object, &self.deployed_bytecode.object)
+    }
+
+    /// The storage layout as stored in the registry, if the artifact has one
+    pub fn storage_layout_json(&self) -> Option<String> {
+        layout_json(self.storage_layout.as_ref())
+    }
+
+    /// The compiler version and settings, if the artifact has metadata
+    pub fn compiler(&self) -> Option<CompilerSettings> {
+        CompilerSettings::from_metadata(self.metadata.as_ref()?)
+    }
+}
+
+/// Normalize an artifact's `storageLayout`, dropping what the registry
+/// doesn't need (AST IDs)
+fn layout_json(value: Option<&serde_json::Value>) -> Option<String> {
+    let layout = StorageLayout::from_value(value?).ok()?;
+    serde_json::to_string(&layout).ok()
 }
 
 /// Bytecode object within an artifact
 #[derive(Debug, Deserialize)]
 pub struct BytecodeObject {
     pub object: String,
+    /// Where library addresses go, when the contract uses external libraries
+    #[serde(rename = "linkReferences", default)]
+    pub link_references: LinkReferences,
 }
 
 impl BytecodeObject {
@@ -103,7 +189,7 @@ pub struct ContractArtifactFull {
 // =============================================================================
 
 /// Information about a compiled artifact (for listing)
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct ArtifactInfo {
     pub name: String,
     pub source_path: String,
@@ -112,13 +198,43 @@ pub struct ArtifactInfo {
 }
 
 /// Detailed artifact information for deployment
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct ArtifactDetails {
     pub name: String,
     pub source_path: String,
     pub abi: serde_json::Value,
     pub constructor: Option<ConstructorInfo>,
     pub has_bytecode: bool,
+    /// Init code size in bytes, without constructor arguments
+    pub initcode_size: usize,
+    /// Deployed (runtime) code size in bytes
+    pub runtime_size: usize,
+    /// Sizes over or close to the EIP-170 and EIP-3860 limits
+    pub size_warnings: Vec<String>,
+    /// Storage layout, when built with `extra_output = ["storageLayout"]`
+    pub storage_layout: Option<serde_json::Value>,
+    /// Compiler version and settings, from the artifact's metadata
+    pub compiler: Option<CompilerSettings>,
+    /// Libraries to link before deploying, as `path:Name`
+    pub libraries: Vec<String>,
+    #[serde(skip)]
+    pub link_references: LinkReferences,
+}
+
+impl ArtifactDetails {
+    /// The storage layout as stored in the registry, if the artifact has one
+    pub fn storage_layout_json(&self) -> Option<String> {
+        layout_json(self.storage_layout.as_ref())
+    }
+
+    /// Write library addresses into the artifact's bytecode
+    pub fn link(
+        &self,
+        bytecode: &str,
+        addresses: &BTreeMap<String, Address>,
+    ) -> Result<String, smolder_core::Error> {
+        linking::link(bytecode, &self.link_references, addresses)
+    }
 }
 
 // =============================================================================
@@ -129,12 +245,18 @@ pub struct ArtifactDetails {
 #[derive(Debug)]
 pub struct ParsedDeployment {
     pub contract_name: String,
-    pub address: String,
+    pub address: EvmAddress,
     pub deployer: String,
-    pub tx_hash: String,
+    pub tx_hash: TxHash,
     pub block_number: Option<i64>,
     pub constructor_args: Option<String>,
     pub abi: String,
-    pub bytecode_hash: String,
+    pub bytecode_hash: BytecodeHash,
     pub source_path: String,
+    pub code_size: CodeSize,
+    pub gas_used: Option<i64>,
+    /// Storage layout JSON, when the artifact has one
+    pub storage_layout: Option<String>,
+    /// Compiler version and settings, when the artifact has metadata
+    pub compiler: Option<CompilerSettings>,
 }
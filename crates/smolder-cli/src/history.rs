@@ -0,0 +1,372 @@
+//! Call history export
+//!
+//! Flattens call history into one record per call, with parameters keyed by
+//! the ABI's input names and results parsed back into JSON, for audit
+//! spreadsheets (CSV) and analysis notebooks (JSON).
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use smolder_core::{chains, Abi, CallType, DeploymentId, Error, TransactionStatus};
+use smolder_db::{CallHistoryFilter, CallHistoryView, Network, Repositories};
+
+use crate::prices::{self, PriceOracle};
+
+/// File format of a history export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    /// One row per call, parameters and results as JSON cells
+    Csv,
+    /// An array of records
+    #[default]
+    Json,
+}
+
+impl HistoryFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Json => "application/json",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// A call as exported
+#[derive(Debug, Serialize)]
+pub struct HistoryRecord {
+    pub id: i64,
+    pub created_at: String,
+    pub confirmed_at: Option<String>,
+    pub network: String,
+    pub contract: String,
+    pub address: String,
+    pub function: String,
+    pub signature: String,
+    pub call_type: CallType,
+    pub status: Option<TransactionStatus>,
+    pub wallet: Option<String>,
+    pub tx_hash: Option<String>,
+    pub block_number: Option<i64>,
+    pub gas_used: Option<i64>,
+    pub gas_price: Option<String>,
+    /// Fee paid, in wei
+    pub fee: Option<String>,
+    /// Approximate USD value of the fee at the day's price; zero on test
+    /// chains, empty while the price is unknown
+    pub fee_usd: Option<f64>,
+    /// Parameters keyed by input name, or as recorded when the function is
+    /// no longer in the deployment's ABI
+    pub params: serde_json::Value,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+const CSV_HEADER: [&str; 20] = [
+    "id",
+    "created_at",
+    "confirmed_at",
+    "network",
+    "contract",
+    "address",
+    "function",
+    "signature",
+    "call_type",
+    "status",
+    "wallet",
+    "tx_hash",
+    "block_number",
+    "gas_used",
+    "gas_price",
+    "fee",
+    "fee_usd",
+    "params",
+    "result",
+    "error",
+];
+
+/// Load the calls matching `filter`, oldest first, ignoring its pagination
+///
+/// Fees are valued with cached prices; with an oracle, missing prices are
+/// fetched first.
+pub async fn load(
+    db: &dyn Repositories,
+    filter: CallHistoryFilter,
+    oracle: Option<&PriceOracle>,
+) -> Result<Vec<HistoryRecord>, Error> {
+    let filter = CallHistoryFilter {
+        limit: None,
+        offset: None,
+        ..filter
+    };
+    let mut calls = db.call_history().list_views(filter).await?;
+    calls.reverse();
+
+    let mut abis: HashMap<DeploymentId, Option<Abi>> = HashMap::new();
+    let mut networks: HashMap<String, Option<Network>> = HashMap::new();
+    let mut records = Vec::with_capacity(calls.len());
+    for call in calls {
+        let network = match networks.entry(call.network_name.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(db.networks().get_by_name(&call.network_name).await?)
+            }
+        };
+        let fee_usd = match (&call.fee, network.as_ref()) {
+            (Some(fee), Some(network)) => {
+                fee_usd(db, oracle, network, fee, &call.created_at).await?
+            }
+            _ => None,
+        };
+
+        let abi = match abis.entry(call.deployment_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let abi = db
+                    .deployments()
+                    .get_view_by_id(call.deployment_id)
+                    .await?
+                    .and_then(|d| Abi::parse(&d.abi).ok());
+                entry.insert(abi)
+            }
+        };
+        let mut record = HistoryRecord::new(call, abi.as_ref());
+        record.fee_usd = fee_usd;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// USD value of a fee paid on `network` at `paid_at`
+async fn fee_usd(
+    db: &dyn Repositories,
+    oracle: Option<&PriceOracle>,
+    network: &Network,
+    fee: &str,
+    paid_at: &str,
+) -> Result<Option<f64>, Error> {
+    if chains::is_testnet(network.chain_id.0 as u64) {
+        return Ok(Some(0.0));
+    }
+    let day = paid_at.get(..10).unwrap_or(paid_at);
+    let usd = prices::usd_price(db, oracle, &network.currency_symbol, day).await?;
+    Ok(usd.and_then(|usd| prices::fee_usd(fee, network.currency_decimals, usd)))
+}
+
+impl HistoryRecord {
+    fn new(call: CallHistoryView, abi: Option<&Abi>) -> Self {
+        let params = name_params(&call, abi);
+        let result = call
+            .result
+            .map(|r| serde_json::from_str(&r).unwrap_or(serde_json::Value::String(r)));
+
+        Self {
+            id: call.id,
+            created_at: call.created_at,
+            confirmed_at: call.confirmed_at,
+            network: call.network_name,
+            contract: call.contract_name,
+            address: call.contract_address.to_string(),
+            function: call.function_name,
+            signature: call.function_signature,
+            call_type: call.call_type,
+            status: call.status,
+            wallet: call.wallet_name,
+            tx_hash: call.tx_hash.map(|h| h.to_string()),
+            block_number: call.block_number,
+            gas_used: call.gas_used,
+            gas_price: call.gas_price,
+            fee: call.fee,
+            fee_usd: None,
+            params,
+            result,
+            error: call.error_message,
+        }
+    }
+
+    fn csv_row(&self) -> [String; 20] {
+        let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+        let num = |v: Option<i64>| v.map(|n| n.to_string()).unwrap_or_default();
+        [
+            self.id.to_string(),
+            self.created_at.clone(),
+            opt(&self.confirmed_at),
+            self.network.clone(),
+            self.contract.clone(),
+            self.address.clone(),
+            self.function.clone(),
+            self.signature.clone(),
+            self.call_type.to_string(),
+            self.status.map(|s| s.to_string()).unwrap_or_default(),
+            opt(&self.wallet),
+            opt(&self.tx_hash),
+            num(self.block_number),
+            num(self.gas_used),
+            opt(&self.gas_price),
+            opt(&self.fee),
+            self.fee_usd.map(|usd| usd.to_string()).unwrap_or_default(),
+            self.params.to_string(),
+            self.result
+                .as_ref()
+                .map(|r| r.to_string())
+                .unwrap_or_default(),
+            opt(&self.error),
+        ]
+    }
+}
+
+/// Pair recorded parameter values with the input names of the function that
+/// was called
+fn name_params(call: &CallHistoryView, abi: Option<&Abi>) -> serde_json::Value {
+    let values: serde_json::Value =
+        serde_json::from_str(&call.input_params).unwrap_or(serde_json::Value::Null);
+    let function = abi
+        .and_then(|abi| abi.function_overloads(&call.function_name))
+        .and_then(|overloads| {
+            overloads
+                .iter()
+                .find(|f| f.signature() == call.function_signature)
+        });
+    let (Some(function), serde_json::Value::Array(values)) = (function, &values) else {
+        return values;
+    };
+    if function.inputs.len() != values.len() {
+        return serde_json::Value::Array(values.clone());
+    }
+
+    function
+        .inputs
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(i, (input, value))| {
+            let name = match input.name.is_empty() {
+                true => format!("arg{}", i),
+                false => input.name.clone(),
+            };
+            (name, value.clone())
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Write `records` in `format`
+pub fn write(
+    records: &[HistoryRecord],
+    format: HistoryFormat,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    match format {
+        HistoryFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, records)?;
+            writeln!(out)
+        }
+        HistoryFormat::Csv => {
+            write_csv_line(out, CSV_HEADER.iter().copied())?;
+            for record in records {
+                let row = record.csv_row();
+                write_csv_line(out, row.iter().map(String::as_str))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn write_csv_line<'a>(
+    out: &mut dyn Write,
+    fields: impl Iterator<Item = &'a str>,
+) -> std::io::Result<()> {
+    let line = fields.map(csv_field).collect::<Vec<_>>().join(",");
+    writeln!(out, "{}", line)
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_db::ExplorerLinks;
+
+    fn call(signature: &str, params: &str) -> CallHistoryView {
+        CallHistoryView {
+            id: 1,
+            deployment_id: DeploymentId(1),
+            contract_name: "Token".to_string(),
+            network_name: "mainnet".to_string(),
+            contract_address: "0x0000000000000000000000000000000000000001"
+                .parse()
+                .unwrap(),
+            wallet_name: Some("deployer".to_string()),
+            function_name: "transfer".to_string(),
+            function_signature: signature.to_string(),
+            input_params: params.to_string(),
+            call_type: CallType::Write,
+            result: Some("true".to_string()),
+            tx_hash: None,
+            block_number: None,
+            gas_used: Some(51000),
+            gas_price: None,
+            fee: None,
+            status: Some(TransactionStatus::Success),
+            error_message: Some("line one, \"two\"".to_string()),
+            created_at: "2025-01-01 00:00:00".to_string(),
+            confirmed_at: None,
+            simulation_url: None,
+            sender: None,
+            reorged_at: None,
+            finality: None,
+            links: ExplorerLinks::default(),
+        }
+    }
+
+    #[test]
+    fn test_record_names_params() {
+        let abi = Abi::from_human_readable([
+            "function transfer(address to, uint256 amount) returns (bool)",
+        ])
+        .unwrap();
+
+        let record = HistoryRecord::new(
+            call("transfer(address,uint256)", r#"["0x02","100"]"#),
+            Some(&abi),
+        );
+        assert_eq!(
+            record.params,
+            serde_json::json!({"to": "0x02", "amount": "100"})
+        );
+        assert_eq!(record.result, Some(serde_json::json!(true)));
+
+        // Without a matching function the values are kept as recorded
+        let record = HistoryRecord::new(call("transfer(address)", r#"["0x02"]"#), Some(&abi));
+        assert_eq!(record.params, serde_json::json!(["0x02"]));
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let record = HistoryRecord::new(call("transfer(address,uint256)", "[]"), None);
+        let mut out = Vec::new();
+        write(&[record], HistoryFormat::Csv, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert!(lines[1].starts_with("1,2025-01-01 00:00:00,,mainnet,Token,0x0000000000000000000000000000000000000001,transfer,"));
+        assert!(lines[1].ends_with(",[],true,\"line one, \"\"two\"\"\""));
+    }
+}
@@ -0,0 +1,308 @@
+//! Regenerate downstream artifacts whenever the registry changes
+//!
+//! Hooks are configured under `[hooks]` in smolder.toml and run after
+//! `deploy` (`post_deploy`) or `sync` (`post_sync`) recorded something new.
+//! Like notifications, they are best effort: a failing hook is reported as a
+//! warning and never fails the command. Every run is appended to
+//! `.smolder/hooks.log`, with the output of shell commands.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::Instant;
+
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use smolder_core::SmolderDir;
+use smolder_db::Database;
+
+use crate::commands::export::ExportCommand;
+use crate::config::{HookConfig, HooksConfig};
+use crate::output::status;
+
+const LOG_FILE: &str = "hooks.log";
+
+/// When hooks run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PostDeploy,
+    PostSync,
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PostDeploy => write!(f, "post_deploy"),
+            Self::PostSync => write!(f, "post_sync"),
+        }
+    }
+}
+
+impl HooksConfig {
+    fn for_event(&self, event: HookEvent) -> &[HookConfig] {
+        match event {
+            HookEvent::PostDeploy => &self.post_deploy,
+            HookEvent::PostSync => &self.post_sync,
+        }
+    }
+}
+
+/// What a hook does, once its configuration is checked
+#[derive(Debug, PartialEq, Eq)]
+enum Action<'a> {
+    Run(&'a str),
+    Export(&'a HookConfig, &'a str),
+}
+
+impl HookConfig {
+    fn action(&self) -> Result<Action<'_>> {
+        match (&self.run, &self.export) {
+            (Some(command), None) => Ok(Action::Run(command)),
+            (None, Some(format)) => Ok(Action::Export(self, format)),
+            _ => Err(eyre!("a hook needs exactly one of `run` or `export`")),
+        }
+    }
+
+    /// Whether the hook cares about a change to `networks`
+    fn applies_to(&self, networks: &[String]) -> bool {
+        self.network
+            .as_ref()
+            .is_none_or(|network| networks.contains(network))
+    }
+}
+
+impl fmt::Display for Action<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Run(command) => write!(f, "{}", command),
+            Self::Export(hook, format) => write!(
+                f,
+                "export {} to {}",
+                format,
+                hook.output.as_deref().unwrap_or("?")
+            ),
+        }
+    }
+}
+
+/// Run the hooks configured for `event` after `networks` changed
+pub async fn run(db: &Database, config: &HooksConfig, event: HookEvent, networks: &[String]) {
+    let hooks: Vec<_> = config
+        .for_event(event)
+        .iter()
+        .filter(|hook| hook.applies_to(networks))
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    status!("{} Running {} hook(s)...", style("->").blue(), event);
+    for hook in hooks {
+        let started = Instant::now();
+        let (description, result) = match hook.action() {
+            Ok(action) => (action.to_string(), execute(&action, event, networks).await),
+            Err(e) => ("invalid hook".to_string(), Err(e)),
+        };
+        let elapsed = started.elapsed();
+
+        let (outcome, output) = match &result {
+            Ok(output) => {
+                status!(
+                    "   {} {} ({:.1}s)",
+                    style("✓").green(),
+                    description,
+                    elapsed.as_secs_f64()
+                );
+                ("ok".to_string(), output.as_str())
+            }
+            Err(e) => {
+                status!(
+                    "   {} Hook '{}' failed: {}",
+                    style("!").yellow(),
+                    description,
+                    e
+                );
+                (format!("failed: {}", e), "")
+            }
+        };
+
+        let entry = LogEntry {
+            at: now(db).await,
+            event,
+            description: &description,
+            outcome: &outcome,
+            seconds: elapsed.as_secs_f64(),
+            output,
+        };
+        if let Err(e) = append_log(&entry) {
+            status!("   {} Could not log hook run: {}", style("!").yellow(), e);
+        }
+    }
+}
+
+/// Run one hook, returning what a shell command printed
+async fn execute(action: &Action<'_>, event: HookEvent, networks: &[String]) -> Result<String> {
+    match action {
+        Action::Run(command) => run_shell(command, event, networks).await,
+        Action::Export(hook, format) => {
+            let output = hook
+                .output
+                .clone()
+                .ok_or_else(|| eyre!("an export hook needs an `output` path"))?;
+            ExportCommand {
+                format: format.to_string(),
+                output: Some(output),
+                network: hook.network.clone(),
+                per_network: false,
+                no_abi: hook.no_abi,
+                prefix: String::new(),
+                abi_dir: None,
+                template: hook.template.clone(),
+            }
+            .run()
+            .await?;
+            Ok(String::new())
+        }
+    }
+}
+
+/// Run `command` in the platform shell, with the event and the changed
+/// networks in `SMOLDER_HOOK` and `SMOLDER_NETWORKS`
+async fn run_shell(command: &str, event: HookEvent, networks: &[String]) -> Result<String> {
+    let mut shell = match cfg!(windows) {
+        true => {
+            let mut shell = tokio::process::Command::new("cmd");
+            shell.arg("/C");
+            shell
+        }
+        false => {
+            let mut shell = tokio::process::Command::new("sh");
+            shell.arg("-c");
+            shell
+        }
+    };
+    let output = shell
+        .arg(command)
+        .env("SMOLDER_HOOK", event.to_string())
+        .env("SMOLDER_NETWORKS", networks.join(","))
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await?;
+
+    let mut printed = String::from_utf8_lossy(&output.stdout).into_owned();
+    printed.push_str(&String::from_utf8_lossy(&output.stderr));
+    match output.status.success() {
+        true => Ok(printed),
+        false => Err(eyre!("{}", output.status)),
+    }
+}
+
+struct LogEntry<'a> {
+    at: String,
+    event: HookEvent,
+    description: &'a str,
+    outcome: &'a str,
+    seconds: f64,
+    output: &'a str,
+}
+
+impl fmt::Display for LogEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} {} -> {} ({:.1}s)",
+            self.at, self.event, self.description, self.outcome, self.seconds
+        )?;
+        for line in self.output.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn append_log(entry: &LogEntry) -> Result<()> {
+    let dir = SmolderDir::new();
+    dir.create()?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE))?;
+    write!(file, "{}", entry)?;
+    Ok(())
+}
+
+/// Current UTC time, from the database so no clock crate is needed
+async fn now(db: &Database) -> String {
+    sqlx::query_scalar("SELECT datetime('now')")
+        .fetch_one(db.read_pool())
+        .await
+        .unwrap_or_else(|_| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(toml: &str) -> HookConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_hook_action() {
+        let run = hook(r#"run = "pnpm codegen""#);
+        assert_eq!(run.action().unwrap(), Action::Run("pnpm codegen"));
+
+        let export = hook(
+            r#"
+            export = "ts"
+            output = "../frontend/src/contracts.ts"
+            network = "mainnet"
+            "#,
+        );
+        assert_eq!(
+            export.action().unwrap().to_string(),
+            "export ts to ../frontend/src/contracts.ts"
+        );
+        assert!(export.applies_to(&["mainnet".to_string()]));
+        assert!(!export.applies_to(&["sepolia".to_string()]));
+        assert!(run.applies_to(&["sepolia".to_string()]));
+
+        assert!(hook("").action().is_err());
+        assert!(hook("run = \"true\"\nexport = \"ts\"").action().is_err());
+    }
+
+    #[test]
+    fn test_log_entry() {
+        let entry = LogEntry {
+            at: "2025-01-01 00:00:00".to_string(),
+            event: HookEvent::PostSync,
+            description: "make abis",
+            outcome: "ok",
+            seconds: 1.5,
+            output: "one\ntwo\n",
+        };
+        assert_eq!(
+            entry.to_string(),
+            "2025-01-01 00:00:00 post_sync make abis -> ok (1.5s)\n    one\n    two\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_shell() {
+        if cfg!(windows) {
+            return;
+        }
+        let networks = ["mainnet".to_string(), "base".to_string()];
+        let output = run_shell(
+            "echo $SMOLDER_HOOK $SMOLDER_NETWORKS",
+            HookEvent::PostDeploy,
+            &networks,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output, "post_deploy mainnet,base\n");
+        assert!(run_shell("exit 3", HookEvent::PostDeploy, &networks)
+            .await
+            .is_err());
+    }
+}
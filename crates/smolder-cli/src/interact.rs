@@ -0,0 +1,302 @@
+//! Prompt-driven call/send against a tracked deployment
+//!
+//! The terminal counterpart of the dashboard's interact page: pick a function,
+//! fill in its parameters, and either `eth_call` it or sign and send it with a
+//! stored wallet.
+
+use alloy::primitives::{Address, Bytes};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use dialoguer::{Confirm, Input, Select};
+use smolder_core::ens::is_ens_name;
+use smolder_core::{
+    decode_function_result, decrypt_private_key, encode_function_call, json_to_sol_value,
+    parse_value, Abi, FunctionInfo, ParamInfo,
+};
+use smolder_db::{
+    CallHistoryRepository, CallHistoryUpdate, CallType, Database, DeploymentView, Network,
+    NetworkRepository, NewCallHistory, TransactionStatus, WalletRepository,
+};
+
+use crate::config::SmolderConfig;
+use crate::ens;
+use crate::locks::Clearance;
+use crate::policy::{self, Intent};
+use crate::rpc::{self, Outgoing};
+
+/// Prompt for a function on `deployment` and execute it
+pub async fn prompt_interaction(db: &Database, deployment: &DeploymentView) -> Result<()> {
+    let abi = Abi::parse(&deployment.abi)?;
+    let parsed = abi.functions();
+
+    let functions: Vec<&FunctionInfo> = parsed.read.iter().chain(parsed.write.iter()).collect();
+    if functions.is_empty() {
+        println!(
+            "{} {} has no callable functions",
+            style("!").yellow(),
+            style(&deployment.contract_name).cyan()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {} v{} on {} at {}",
+        style("->").blue(),
+        style(&deployment.contract_name).cyan(),
+        deployment.version,
+        style(&deployment.network_name).cyan(),
+        style(&deployment.address).yellow()
+    );
+
+    let labels: Vec<String> = functions
+        .iter()
+        .map(|f| {
+            let kind = if f.is_read_only() { "call" } else { "send" };
+            format!("[{}] {}", kind, f.signature)
+        })
+        .collect();
+
+    let choice = Select::new()
+        .with_prompt("Function")
+        .items(&labels)
+        .default(0)
+        .interact()?;
+    let info = functions[choice];
+
+    // Overloads share a name, so match on the full signature
+    let function = abi
+        .function_overloads(&info.name)
+        .and_then(|overloads| overloads.iter().find(|f| f.signature() == info.signature))
+        .ok_or_else(|| eyre!("Function '{}' not found in ABI", info.signature))?;
+
+    let mut params = prompt_params(&info.inputs)?;
+
+    let network = NetworkRepository::get_by_name(db, &deployment.network_name)
+        .await?
+        .ok_or_else(|| eyre!("Network '{}' not found", deployment.network_name))?;
+    let ens_network = SmolderConfig::load()?.ens.network;
+    ens::resolve_params(
+        db,
+        &network,
+        ens_network.as_deref(),
+        &function.inputs,
+        &mut params,
+    )
+    .await?;
+    let call_data = encode_function_call(function, &params)?;
+    let address = *deployment.address;
+
+    if info.is_read_only() {
+        let data = rpc::execute_eth_call(network.rpc_url.expose(), address, call_data).await?;
+        let result = decode_function_result(function, &data)?;
+
+        println!();
+        println!("{} {}", style("*").green().bold(), style(&info.name).cyan());
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+
+    send(db, deployment, &network, address, call_data, info, &params).await
+}
+
+async fn send(
+    db: &Database,
+    deployment: &DeploymentView,
+    network: &Network,
+    address: Address,
+    call_data: Bytes,
+    info: &FunctionInfo,
+    params: &[serde_json::Value],
+) -> Result<()> {
+    let wallets = WalletRepository::list(db).await?;
+    if wallets.is_empty() {
+        return Err(eyre!(
+            "No wallets found. Add one with: {}",
+            style("smolder wallet add <name>").cyan()
+        ));
+    }
+
+    let names: Vec<String> = wallets
+        .iter()
+        .map(|w| format!("{} ({})", w.name, w.address))
+        .collect();
+    let choice = Select::new()
+        .with_prompt("Wallet")
+        .items(&names)
+        .default(0)
+        .interact()?;
+    let wallet_name = &wallets[choice].name;
+
+    let value = if info.is_payable() {
+        let input: String = Input::new()
+            .with_prompt(format!(
+                "Value (wei, or e.g. 0.1 {})",
+                network.currency_symbol
+            ))
+            .default("0".to_string())
+            .validate_with(|v: &String| {
+                parse_value(v, network.currency())
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        Some(parse_value(&input, network.currency())?).filter(|v| !v.is_zero())
+    } else {
+        None
+    };
+
+    let preflight = rpc::preflight(
+        network,
+        Outgoing {
+            from: *wallets[choice].address,
+            to: Some(address),
+            data: &call_data,
+            value,
+        },
+    )
+    .await?;
+    for warning in &preflight.warnings {
+        println!("{} {}", style("!").yellow(), warning.message);
+    }
+
+    let confirmed = Confirm::new()
+        .with_prompt(format!("Send {} from '{}'?", info.signature, wallet_name))
+        .default(false)
+        .interact()?;
+    if !confirmed {
+        println!("{} Cancelled", style("*").dim());
+        return Ok(());
+    }
+    let mut clearance = Clearance::cli([]);
+    clearance.prompt(network)?;
+    clearance
+        .check(
+            db,
+            network,
+            &format!("send {}.{}", deployment.contract_name, info.signature),
+        )
+        .await?;
+
+    let wallet = WalletRepository::get_with_key(db, wallet_name)
+        .await?
+        .ok_or_else(|| eyre!("Wallet '{}' not found", wallet_name))?;
+    let intent = Intent::call(
+        network,
+        address,
+        Some(&deployment.contract_name),
+        &info.signature,
+        value,
+    );
+    policy::check(db, wallet.id, &wallet.name, &intent).await?;
+    let private_key = decrypt_private_key(&wallet.encrypted_key)?;
+
+    let history = CallHistoryRepository::create(
+        db,
+        &NewCallHistory {
+            deployment_id: deployment.id,
+            wallet_id: Some(wallet.id),
+            function_name: info.name.clone(),
+            function_signature: info.signature.clone(),
+            input_params: serde_json::to_string(params)?,
+            call_type: CallType::Write,
+        },
+    )
+    .await?;
+
+    let reservation = policy::reserve(db, wallet.id, &wallet.name, &intent).await?;
+    let outcome = rpc::execute_transaction(
+        network.rpc_url.expose(),
+        network.private_relay.as_deref(),
+        &private_key,
+        address,
+        call_data,
+        value,
+    )
+    .await;
+    let outcome = reservation.settle(db, outcome).await;
+
+    let update = CallHistoryUpdate {
+        result: None,
+        tx_hash: outcome.as_ref().ok().cloned(),
+        block_number: None,
+        gas_used: None,
+        gas_price: None,
+        status: if outcome.is_ok() {
+            TransactionStatus::Pending
+        } else {
+            TransactionStatus::Failed
+        },
+        error_message: outcome.as_ref().err().map(|e| e.to_string()),
+    };
+    CallHistoryRepository::update(db, history.id, &update).await?;
+
+    let tx_hash = outcome?;
+    println!();
+    println!(
+        "{} Transaction sent: {}",
+        style("*").green().bold(),
+        style(&tx_hash).yellow()
+    );
+
+    Ok(())
+}
+
+/// Prompt for each parameter, validating input against its Solidity type
+pub fn prompt_params(inputs: &[ParamInfo]) -> Result<Vec<serde_json::Value>> {
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let name = if param.name.is_empty() {
+                format!("arg{}", i)
+            } else {
+                param.name.clone()
+            };
+            let ty = param.param_type.clone();
+
+            let input: String = Input::new()
+                .with_prompt(format!("{} ({})", name, ty))
+                .validate_with(|v: &String| {
+                    json_to_sol_value(&ty, &placeholder_names(&ty, param_to_json(&ty, v)))
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                })
+                .interact_text()?;
+
+            Ok(param_to_json(&param.param_type, &input))
+        })
+        .collect()
+}
+
+/// Swap ENS names in address input for the zero address, so validation
+/// accepts them; they are resolved before encoding
+fn placeholder_names(ty: &str, value: serde_json::Value) -> serde_json::Value {
+    let placeholder = |v: serde_json::Value| match v.as_str() {
+        Some(s) if is_ens_name(s) => serde_json::json!(Address::ZERO.to_string()),
+        _ => v,
+    };
+    match value {
+        serde_json::Value::Array(items) if ty.starts_with("address[") => {
+            items.into_iter().map(placeholder).collect()
+        }
+        value if ty == "address" => placeholder(value),
+        value => value,
+    }
+}
+
+/// Interpret raw terminal input as the JSON shape `json_to_sol_value` expects
+pub(crate) fn param_to_json(ty: &str, input: &str) -> serde_json::Value {
+    let input = input.trim();
+    if ty.ends_with(']') || ty.starts_with('(') {
+        // Arrays and tuples are entered as JSON, e.g. ["0x..", "0x.."]
+        serde_json::from_str(input).unwrap_or_else(|_| serde_json::json!(input))
+    } else if ty == "bool" {
+        match input {
+            "true" => serde_json::json!(true),
+            "false" => serde_json::json!(false),
+            _ => serde_json::json!(input),
+        }
+    } else {
+        serde_json::json!(input)
+    }
+}
@@ -0,0 +1,63 @@
+//! Storage layout checks for proxy upgrades
+//!
+//! Before a new version of a contract is deployed or recorded, its storage
+//! layout is compared with the one of the version currently deployed on the
+//! network. Conflicts are reported as warnings; contracts built without
+//! `extra_output = ["storageLayout"]` are not checked.
+
+use console::style;
+use smolder_core::{check_upgrade, Error, EvmAddress, LayoutIssue, StorageLayout};
+use smolder_db::Repositories;
+
+use crate::output::status;
+
+/// Compare `layout` (as stored in the registry) with the storage layout of
+/// the current deployment of `contract` on `network`
+///
+/// Nothing is compared when there is no current deployment, when it has no
+/// layout, or when it is the deployment at `address` itself.
+pub async fn check_against_current(
+    db: &dyn Repositories,
+    contract: &str,
+    network: &str,
+    address: Option<EvmAddress>,
+    layout: &str,
+) -> Result<Vec<LayoutIssue>, Error> {
+    let Some(current) = db.deployments().get_current(contract, network).await? else {
+        return Ok(Vec::new());
+    };
+    if address == Some(current.address) {
+        return Ok(Vec::new());
+    }
+    let previous = db
+        .contracts()
+        .get_by_id(current.contract_id)
+        .await?
+        .and_then(|c| c.storage_layout);
+    let Some(previous) = previous else {
+        return Ok(Vec::new());
+    };
+
+    Ok(check_upgrade(
+        &StorageLayout::parse(&previous)?,
+        &StorageLayout::parse(layout)?,
+    ))
+}
+
+/// Print layout conflicts under the line announcing `contract`
+pub fn print_issues(contract: &str, issues: &[LayoutIssue]) {
+    if issues.is_empty() {
+        return;
+    }
+    status!(
+        "   {} Storage layout of {} differs from the deployed version:",
+        style("!").yellow(),
+        style(contract).cyan()
+    );
+    for issue in issues {
+        match issue.kind.is_breaking() {
+            true => status!("     {} {}", style("-").red(), issue.message),
+            false => status!("     {} {}", style("-").dim(), issue.message),
+        }
+    }
+}
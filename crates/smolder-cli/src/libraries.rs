@@ -0,0 +1,133 @@
+//! Library addresses for linking
+//!
+//! Artifacts of contracts that call external libraries hold placeholders
+//! instead of the libraries' addresses. Each library is linked to the address
+//! given for it (`--library Math=0x…`, or `libraries` in a deploy request),
+//! by name or as `path:Name`, or else to its current deployment on the
+//! network.
+
+use std::collections::BTreeMap;
+
+use alloy::primitives::Address;
+use smolder_core::Error;
+use smolder_db::Repositories;
+
+use crate::forge::ArtifactDetails;
+
+/// Libraries of an artifact, with the addresses found for them
+#[derive(Debug, Default)]
+pub struct Libraries {
+    /// Addresses by fully qualified name
+    pub addresses: BTreeMap<String, Address>,
+    /// Libraries without a given address or a deployment on the network
+    pub missing: Vec<String>,
+}
+
+impl Libraries {
+    /// The addresses, or an error naming the libraries still missing
+    pub fn require(self, network: &str) -> Result<BTreeMap<String, Address>, Error> {
+        if self.missing.is_empty() {
+            return Ok(self.addresses);
+        }
+        let (them, their) = match self.missing.len() {
+            1 => ("it", "its"),
+            _ => ("them", "their"),
+        };
+        Err(Error::Validation(format!(
+            "{} not deployed on {}; deploy {} first or give {} address",
+            self.missing.join(", "),
+            network,
+            them,
+            their
+        )))
+    }
+}
+
+/// Parse a `NAME=ADDRESS` flag
+pub fn parse_flag(s: &str) -> Result<(String, String), String> {
+    let (name, address) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=ADDRESS, got '{}'", s))?;
+    address
+        .trim()
+        .parse::<Address>()
+        .map_err(|e| format!("invalid address for {}: {}", name, e))?;
+    Ok((name.trim().to_string(), address.trim().to_string()))
+}
+
+/// Find an address for each library `details` links against, from `given`
+/// or the registry
+///
+/// Names in `given` that are not libraries of the artifact are rejected, so
+/// a typo doesn't leave a library to be picked from the registry.
+pub async fn resolve(
+    db: &dyn Repositories,
+    network: &str,
+    details: &ArtifactDetails,
+    given: &BTreeMap<String, String>,
+) -> Result<Libraries, Error> {
+    for name in given.keys() {
+        if !details.libraries.iter().any(|l| matches(l, name)) {
+            return Err(Error::invalid_param(
+                "libraries",
+                format!("{} is not a library of {}", name, details.name),
+            ));
+        }
+    }
+
+    let mut libraries = Libraries::default();
+    for library in &details.libraries {
+        let address = match given.iter().find(|(name, _)| matches(library, name)) {
+            Some((name, address)) => Some(
+                address
+                    .parse::<Address>()
+                    .map_err(|e| Error::invalid_param("libraries", format!("{}: {}", name, e)))?,
+            ),
+            None => db
+                .deployments()
+                .get_current(short_name(library), network)
+                .await?
+                .map(|d| *d.address),
+        };
+        match address {
+            Some(address) => {
+                libraries.addresses.insert(library.clone(), address);
+            }
+            None => libraries.missing.push(library.clone()),
+        }
+    }
+    Ok(libraries)
+}
+
+/// Library name of a fully qualified `path:Name`
+pub fn short_name(library: &str) -> &str {
+    library.rsplit(':').next().unwrap_or(library)
+}
+
+/// Whether `name` refers to `library`, fully qualified or by name
+fn matches(library: &str, name: &str) -> bool {
+    library == name || short_name(library) == name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flag() {
+        let (name, address) =
+            parse_flag("src/Math.sol:Math=0x1111111111111111111111111111111111111111").unwrap();
+        assert_eq!(name, "src/Math.sol:Math");
+        assert_eq!(address, "0x1111111111111111111111111111111111111111");
+        assert!(parse_flag("Math").is_err());
+        assert!(parse_flag("Math=0x12").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("src/Math.sol:Math", "Math"));
+        assert!(matches("src/Math.sol:Math", "src/Math.sol:Math"));
+        assert!(!matches("src/Math.sol:Math", "Mat"));
+        assert_eq!(short_name("src/Math.sol:Math"), "Math");
+    }
+}
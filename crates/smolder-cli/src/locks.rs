@@ -0,0 +1,185 @@
+//! Networks locked against deploys and sends
+//!
+//! Locking a network (typically mainnet) makes every deploy, send and upgrade
+//! on it fail unless the operator names it again, with `--unlock-confirm
+//! <network>` on the CLI or `unlock_confirm` in API requests. Locking,
+//! unlocking and each confirmed operation go to the audit log.
+
+use dialoguer::Input;
+use smolder_core::Error;
+use smolder_db::{AuditAction, AuditSource, Network, NewAuditEntry, Repositories};
+
+/// Who is running an operation, and which locked networks they confirmed
+pub struct Clearance {
+    source: AuditSource,
+    actor: Option<String>,
+    confirmed: Vec<String>,
+}
+
+impl Clearance {
+    /// The local user running the CLI
+    pub fn cli(confirmed: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            source: AuditSource::Cli,
+            actor: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok(),
+            confirmed: confirmed.into_iter().collect(),
+        }
+    }
+
+    /// An API request, by the token it carried (`None` with auth disabled)
+    pub fn api(actor: Option<String>, confirmed: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            source: AuditSource::Api,
+            actor,
+            confirmed: confirmed.into_iter().collect(),
+        }
+    }
+
+    /// Whether `network` can be changed: it is unlocked or was confirmed
+    pub fn allows(&self, network: &Network) -> bool {
+        !network.locked || self.confirmed.contains(&network.name)
+    }
+
+    /// Ask for the name of `network` when it is locked and wasn't confirmed
+    /// with `--unlock-confirm`, for commands that prompt anyway
+    pub fn prompt(&mut self, network: &Network) -> dialoguer::Result<()> {
+        if !self.allows(network) {
+            let name: String = Input::new()
+                .with_prompt(format!(
+                    "Network '{}' is locked; type its name to continue",
+                    network.name
+                ))
+                .interact_text()?;
+            self.confirmed.push(name);
+        }
+        Ok(())
+    }
+
+    /// Fail unless `network` is unlocked or was confirmed by name, recording
+    /// `operation` in the audit log when it runs on a locked network
+    pub async fn check(
+        &self,
+        repos: &dyn Repositories,
+        network: &Network,
+        operation: &str,
+    ) -> Result<(), Error> {
+        if !self.allows(network) {
+            return Err(Error::NetworkLocked(network.name.clone()));
+        }
+        if network.locked {
+            self.record(
+                repos,
+                AuditAction::Override,
+                &network.name,
+                Some(serde_json::json!({ "operation": operation }).to_string()),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Lock or unlock `network`, recording the change
+    ///
+    /// Returns `false` when the network already was in that state.
+    pub async fn set_locked(
+        &self,
+        repos: &dyn Repositories,
+        network: &Network,
+        locked: bool,
+    ) -> Result<bool, Error> {
+        if network.locked == locked {
+            return Ok(false);
+        }
+        repos.networks().set_locked(&network.name, locked).await?;
+        let action = match locked {
+            true => AuditAction::Lock,
+            false => AuditAction::Unlock,
+        };
+        self.record(repos, action, &network.name, None).await?;
+        Ok(true)
+    }
+
+    async fn record(
+        &self,
+        repos: &dyn Repositories,
+        action: AuditAction,
+        target: &str,
+        detail: Option<String>,
+    ) -> Result<(), Error> {
+        repos
+            .audit_log()
+            .record(&NewAuditEntry {
+                action,
+                target: target.to_string(),
+                source: self.source,
+                actor: self.actor.clone(),
+                detail,
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_db::{ChainId, MemoryDatabase, NewNetwork};
+
+    async fn mainnet(repos: &MemoryDatabase) -> Network {
+        repos
+            .networks()
+            .upsert(&NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "http://localhost:8545".to_string(),
+                explorer_url: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_locked_networks_need_confirmation() {
+        let repos = MemoryDatabase::new();
+        let network = mainnet(&repos).await;
+        let admin = Clearance::api(Some("admin".to_string()), []);
+
+        // Unlocked networks need nothing, and leave no trace
+        admin
+            .check(&repos, &network, "deploy Counter")
+            .await
+            .unwrap();
+        assert!(admin.set_locked(&repos, &network, true).await.unwrap());
+        let network = repos
+            .networks()
+            .get_by_name("mainnet")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!admin.set_locked(&repos, &network, true).await.unwrap());
+
+        let err = admin.check(&repos, &network, "deploy Counter").await;
+        assert!(matches!(err, Err(Error::NetworkLocked(name)) if name == "mainnet"));
+        let wrong = Clearance::api(None, ["sepolia".to_string()]);
+        assert!(wrong
+            .check(&repos, &network, "deploy Counter")
+            .await
+            .is_err());
+
+        let confirmed = Clearance::api(Some("deployer".to_string()), ["mainnet".to_string()]);
+        confirmed
+            .check(&repos, &network, "deploy Counter")
+            .await
+            .unwrap();
+
+        let log = repos.audit_log().list(10).await.unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].action, AuditAction::Override);
+        assert_eq!(log[0].actor.as_deref(), Some("deployer"));
+        assert!(log[0].detail.as_deref().unwrap().contains("deploy Counter"));
+        assert_eq!(log[1].action, AuditAction::Lock);
+        assert_eq!(log[1].source, AuditSource::Api);
+    }
+}
@@ -1,13 +1,47 @@
 mod commands;
 mod config;
+mod contract_status;
+mod decode;
+mod deploy_group;
+mod deploy_plan;
+mod deployment_files;
+mod ens;
+mod external;
 mod forge;
+mod history;
+mod hooks;
+mod interact;
+mod layout;
+mod libraries;
+mod locks;
+mod manifest;
+mod notify;
+mod output;
+mod policy;
+mod prices;
+mod project;
+mod registry_sync;
+mod relay;
+mod roles;
 mod rpc;
 mod server;
+mod sourcify;
+mod tenderly;
+mod tokens;
+mod tui;
+mod typed_data;
+mod upgrade;
+mod wallet_backup;
+
+use std::borrow::Cow;
+use std::path::PathBuf;
 
 use clap::Parser;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
+use smolder_core::scrub;
 
 use commands::Command;
+use output::OutputMode;
 
 #[derive(Parser)]
 #[command(name = "smolder")]
@@ -16,6 +50,19 @@ use commands::Command;
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    /// Print results as JSON instead of styled text
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Only print results, without progress messages
+    #[arg(long, short, global = true, conflicts_with = "json")]
+    quiet: bool,
+
+    /// Project root; defaults to the nearest directory above the current
+    /// one holding foundry.toml, smolder.toml or .smolder/
+    #[arg(long, global = true, value_name = "PATH")]
+    root: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -23,5 +70,31 @@ async fn main() -> Result<()> {
     color_eyre::install()?;
 
     let cli = Cli::parse();
-    cli.command.run().await
+    output::init(if cli.json {
+        OutputMode::Json
+    } else if cli.quiet {
+        OutputMode::Quiet
+    } else {
+        OutputMode::Text
+    });
+
+    let result = match project::enter(cli.root.as_deref()).and_then(|()| commands::db::unlock()) {
+        Ok(()) => cli.command.run().await,
+        Err(e) => Err(e),
+    };
+    // Errors from forge and HTTP clients may quote an RPC URL with its API key
+    let result = result.map_err(|e| match scrub(&format!("{:#}", e)) {
+        Cow::Borrowed(_) => e,
+        Cow::Owned(scrubbed) => eyre!(scrubbed),
+    });
+
+    // Keep stdout parseable for scripts when a command fails in JSON mode
+    if let Err(e) = &result {
+        if output::is_json() {
+            output::print_json(&serde_json::json!({ "error": e.to_string() }))?;
+            std::process::exit(1);
+        }
+    }
+
+    result
 }
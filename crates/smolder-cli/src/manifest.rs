@@ -0,0 +1,205 @@
+//! Parsing of deployment manifests produced by other tools
+//!
+//! Supported layouts:
+//! - smolder's own `export --format json`: `{ network: { contract: { address, abi } } }`
+//! - hardhat-deploy `--export`: `{ name, chainId, contracts: { contract: { address, abi } } }`
+//! - hardhat-deploy `--export-all`: `{ chainId: [ { name, chainId, contracts } ] }`
+
+use color_eyre::eyre::{eyre, Result};
+use serde::Deserialize;
+use smolder_core::Abi;
+use std::collections::HashMap;
+
+/// A deployment read from a manifest, before it is matched against the registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestDeployment {
+    pub network: String,
+    /// Chain ID, when the manifest records it
+    pub chain_id: Option<u64>,
+    pub contract: String,
+    pub address: String,
+    /// ABI as a JSON string
+    pub abi: String,
+}
+
+#[derive(Deserialize)]
+struct HardhatExport {
+    name: String,
+    #[serde(rename = "chainId", deserialize_with = "deserialize_chain_id")]
+    chain_id: u64,
+    contracts: HashMap<String, ManifestContract>,
+}
+
+#[derive(Deserialize)]
+struct ManifestContract {
+    address: String,
+    abi: serde_json::Value,
+}
+
+/// hardhat-deploy writes the chain ID as a string, smolder as a number
+fn deserialize_chain_id<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ChainId {
+        Number(u64),
+        String(String),
+    }
+
+    match ChainId::deserialize(deserializer)? {
+        ChainId::Number(n) => Ok(n),
+        ChainId::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parse a manifest, detecting its layout from its shape
+pub fn parse_manifest(content: &str) -> Result<Vec<ManifestDeployment>> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| eyre!("Invalid JSON: {}", e))?;
+
+    let object = value
+        .as_object()
+        .ok_or_else(|| eyre!("Expected a JSON object at the top level"))?;
+
+    let mut deployments = if object.contains_key("contracts") {
+        from_hardhat(serde_json::from_value(value)?)?
+    } else if !object.is_empty() && object.values().all(|v| v.is_array()) {
+        let exports: HashMap<String, Vec<HardhatExport>> = serde_json::from_value(value)?;
+        let mut deployments = Vec::new();
+        for export in exports.into_values().flatten() {
+            deployments.extend(from_hardhat(export)?);
+        }
+        deployments
+    } else {
+        let networks: HashMap<String, HashMap<String, ManifestContract>> =
+            serde_json::from_value(value).map_err(|e| eyre!("Unrecognized manifest: {}", e))?;
+        let mut deployments = Vec::new();
+        for (network, contracts) in networks {
+            for (contract, entry) in contracts {
+                deployments.push(ManifestDeployment {
+                    network: network.clone(),
+                    chain_id: None,
+                    contract,
+                    address: entry.address,
+                    abi: abi_json(&entry.abi)?,
+                });
+            }
+        }
+        deployments
+    };
+
+    // HashMap order is random; keep imports deterministic
+    deployments.sort_by(|a, b| (&a.network, &a.contract).cmp(&(&b.network, &b.contract)));
+    Ok(deployments)
+}
+
+fn from_hardhat(export: HardhatExport) -> Result<Vec<ManifestDeployment>> {
+    export
+        .contracts
+        .into_iter()
+        .map(|(contract, entry)| {
+            Ok(ManifestDeployment {
+                network: export.name.clone(),
+                chain_id: Some(export.chain_id),
+                contract,
+                address: entry.address,
+                abi: abi_json(&entry.abi)?,
+            })
+        })
+        .collect()
+}
+
+/// Store an ABI as JSON, expanding human-readable ABIs (arrays of signatures
+/// as written by ethers) into the JSON form
+fn abi_json(abi: &serde_json::Value) -> Result<String> {
+    if Abi::is_human_readable(abi) {
+        return Ok(Abi::from_value(abi)?.to_json()?);
+    }
+    Ok(serde_json::to_string(abi)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_smolder_export() {
+        let content = r#"{
+            "mainnet": {
+                "Token": { "address": "0x1111111111111111111111111111111111111111", "abi": [], "version": 2 }
+            }
+        }"#;
+
+        let deployments = parse_manifest(content).unwrap();
+        assert_eq!(
+            deployments,
+            vec![ManifestDeployment {
+                network: "mainnet".to_string(),
+                chain_id: None,
+                contract: "Token".to_string(),
+                address: "0x1111111111111111111111111111111111111111".to_string(),
+                abi: "[]".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_hardhat_export() {
+        let content = r#"{
+            "name": "sepolia",
+            "chainId": "11155111",
+            "contracts": {
+                "Token": { "address": "0x1111111111111111111111111111111111111111", "abi": [] },
+                "Vault": { "address": "0x2222222222222222222222222222222222222222", "abi": [] }
+            }
+        }"#;
+
+        let deployments = parse_manifest(content).unwrap();
+        assert_eq!(deployments.len(), 2);
+        assert_eq!(deployments[0].contract, "Token");
+        assert_eq!(deployments[0].network, "sepolia");
+        assert_eq!(deployments[0].chain_id, Some(11155111));
+        assert_eq!(deployments[1].contract, "Vault");
+    }
+
+    #[test]
+    fn test_parse_hardhat_export_all() {
+        let content = r#"{
+            "1": [{ "name": "mainnet", "chainId": "1", "contracts": {
+                "Token": { "address": "0x1111111111111111111111111111111111111111", "abi": [] }
+            }}],
+            "10": [{ "name": "optimism", "chainId": 10, "contracts": {
+                "Token": { "address": "0x2222222222222222222222222222222222222222", "abi": [] }
+            }}]
+        }"#;
+
+        let deployments = parse_manifest(content).unwrap();
+        let networks: Vec<_> = deployments.iter().map(|d| d.network.as_str()).collect();
+        assert_eq!(networks, vec!["mainnet", "optimism"]);
+        assert_eq!(deployments[1].chain_id, Some(10));
+    }
+
+    #[test]
+    fn test_parse_human_readable_abi() {
+        let content = r#"{
+            "mainnet": {
+                "Token": {
+                    "address": "0x1111111111111111111111111111111111111111",
+                    "abi": ["function balanceOf(address owner) view returns (uint256)"]
+                }
+            }
+        }"#;
+
+        let deployments = parse_manifest(content).unwrap();
+        let abi = Abi::parse(&deployments[0].abi).unwrap();
+        assert!(abi.function("balanceOf").is_some());
+        assert!(deployments[0].abi.starts_with("[{"));
+    }
+
+    #[test]
+    fn test_parse_invalid_manifest() {
+        assert!(parse_manifest("[]").is_err());
+        assert!(parse_manifest(r#"{"mainnet": {"Token": {"abi": []}}}"#).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Announce new deployments, and calls the activity monitor of `serve`
+//! noticed, in Slack, Discord or Telegram
+//!
+//! Channels are configured under `[notifications]` in smolder.toml. Posting
+//! is best effort: a failing channel is reported as a warning and never
+//! fails the command that recorded the deployment.
+
+use std::time::Duration;
+
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use futures::future::join_all;
+use serde_json::json;
+use smolder_core::{address_link, tx_link, TransactionStatus, TxHash};
+
+use crate::config::{resolve_env_var, NotificationsConfig};
+use crate::output::status;
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A deployment that was just recorded in the registry
+#[derive(Debug, Clone)]
+pub struct DeploymentNotice {
+    pub contract: String,
+    pub network: String,
+    pub address: String,
+    /// Registry version, when known
+    pub version: Option<i64>,
+    pub deployer: String,
+    /// None for deployments recorded without their transaction
+    pub tx_hash: Option<TxHash>,
+    /// Base URL of the network's block explorer
+    pub explorer_url: Option<String>,
+}
+
+impl DeploymentNotice {
+    /// Link to the contract on the network's explorer
+    pub fn explorer_link(&self) -> Option<String> {
+        self.explorer_url
+            .as_deref()
+            .map(|explorer_url| address_link(explorer_url, &self.address))
+    }
+
+    fn message(&self) -> String {
+        let version = self.version.map(|v| format!(" v{}", v)).unwrap_or_default();
+        let mut lines = vec![
+            format!("Deployed {}{} on {}", self.contract, version, self.network),
+            format!("Address: {}", self.address),
+        ];
+        if let Some(link) = self.explorer_link() {
+            lines.push(format!("Explorer: {}", link));
+        }
+        if !self.deployer.is_empty() {
+            lines.push(format!("Deployer: {}", self.deployer));
+        }
+        if let Some(tx_hash) = self.tx_hash {
+            lines.push(format!("Tx: {}", tx_hash));
+        }
+        lines.join("\n")
+    }
+}
+
+/// One message covering every notice, separated by blank lines
+fn format_message(notices: &[DeploymentNotice]) -> String {
+    notices
+        .iter()
+        .map(DeploymentNotice::message)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// A call to a tracked deployment that smolder didn't send
+#[derive(Debug, Clone)]
+pub struct ActivityNotice {
+    pub contract: String,
+    pub network: String,
+    pub address: String,
+    /// Signature of the function called, or its bare selector
+    pub function: String,
+    pub sender: String,
+    pub status: TransactionStatus,
+    pub tx_hash: TxHash,
+    /// Base URL of the network's block explorer
+    pub explorer_url: Option<String>,
+}
+
+impl ActivityNotice {
+    fn message(&self) -> String {
+        let tx = match self.explorer_url.as_deref() {
+            Some(explorer_url) => tx_link(explorer_url, &self.tx_hash.to_string()),
+            None => self.tx_hash.to_string(),
+        };
+        [
+            format!("External call to {} on {}", self.contract, self.network),
+            format!("Address: {}", self.address),
+            format!("Function: {}", self.function),
+            format!("From: {}", self.sender),
+            format!("Status: {}", self.status),
+            format!("Tx: {}", tx),
+        ]
+        .join("\n")
+    }
+}
+
+/// Post `notices` to every configured channel
+///
+/// Notices for networks disabled under `[notifications.networks]` are dropped.
+pub async fn announce(config: &NotificationsConfig, notices: &[DeploymentNotice]) {
+    let notices: Vec<_> = notices
+        .iter()
+        .filter(|n| config.is_enabled_for(&n.network))
+        .cloned()
+        .collect();
+    if notices.is_empty() {
+        return;
+    }
+    post(config, &format_message(&notices)).await;
+}
+
+/// Post activity `notices` to every configured channel, like [`announce`]
+pub async fn alert(config: &NotificationsConfig, notices: &[ActivityNotice]) {
+    let messages: Vec<_> = notices
+        .iter()
+        .filter(|n| config.is_enabled_for(&n.network))
+        .map(ActivityNotice::message)
+        .collect();
+    if messages.is_empty() {
+        return;
+    }
+    post(config, &messages.join("\n\n")).await;
+}
+
+/// Post `text` to every configured channel, reporting failures as warnings
+async fn post(config: &NotificationsConfig, text: &str) {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            status!(
+                "{} Could not send notifications: {}",
+                style("!").yellow(),
+                e
+            );
+            return;
+        }
+    };
+
+    // Each channel resolves to a URL and body, or the reason it can't
+    let mut targets: Vec<(&str, Result<(String, serde_json::Value)>)> = Vec::new();
+    if let Some(slack) = &config.slack {
+        let target = resolve_env_var(&slack.webhook_url).map(|url| (url, json!({ "text": text })));
+        targets.push(("Slack", target));
+    }
+    if let Some(discord) = &config.discord {
+        let target =
+            resolve_env_var(&discord.webhook_url).map(|url| (url, json!({ "content": text })));
+        targets.push(("Discord", target));
+    }
+    if let Some(telegram) = &config.telegram {
+        let target = resolve_env_var(&telegram.bot_token).and_then(|token| {
+            let chat_id = resolve_env_var(&telegram.chat_id)?;
+            Ok((
+                format!("{}/bot{}/sendMessage", TELEGRAM_API, token),
+                json!({ "chat_id": chat_id, "text": text }),
+            ))
+        });
+        targets.push(("Telegram", target));
+    }
+
+    let client = &client;
+    let posts = targets.into_iter().map(|(channel, target)| async move {
+        let result = match target {
+            Ok((url, body)) => send(client, &url, &body).await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| eyre!("{}: {}", channel, e))
+    });
+
+    for result in join_all(posts).await {
+        if let Err(e) = result {
+            status!("{} Notification failed: {}", style("!").yellow(), e);
+        }
+    }
+}
+
+async fn send(client: &reqwest::Client, url: &str, body: &serde_json::Value) -> Result<()> {
+    let response = client.post(url).json(body).send().await?;
+    if !response.status().is_success() {
+        return Err(eyre!("endpoint answered {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_core::B256;
+
+    fn notice(network: &str) -> DeploymentNotice {
+        DeploymentNotice {
+            contract: "Token".to_string(),
+            network: network.to_string(),
+            address: "0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string(),
+            version: Some(2),
+            deployer: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            tx_hash: Some(TxHash(B256::repeat_byte(0xab))),
+            explorer_url: Some("https://etherscan.io/".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_activity_message() {
+        let notice = ActivityNotice {
+            contract: "Token".to_string(),
+            network: "mainnet".to_string(),
+            address: "0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string(),
+            function: "pause()".to_string(),
+            sender: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            status: TransactionStatus::Success,
+            tx_hash: TxHash(B256::repeat_byte(0xab)),
+            explorer_url: Some("https://etherscan.io/".to_string()),
+        };
+
+        assert_eq!(
+            notice.message(),
+            "External call to Token on mainnet\n\
+             Address: 0x5FbDB2315678afecb367f032d93F642f64180aa3\n\
+             Function: pause()\n\
+             From: 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266\n\
+             Status: success\n\
+             Tx: https://etherscan.io/tx/0xabababababababababababababababababababababababababababababababab"
+        );
+    }
+
+    #[test]
+    fn test_format_message() {
+        let mut second = notice("mainnet");
+        second.contract = "Vault".to_string();
+        second.version = None;
+        second.deployer = String::new();
+        second.explorer_url = None;
+
+        assert_eq!(
+            format_message(&[notice("mainnet"), second]),
+            "Deployed Token v2 on mainnet\n\
+             Address: 0x5FbDB2315678afecb367f032d93F642f64180aa3\n\
+             Explorer: https://etherscan.io/address/0x5FbDB2315678afecb367f032d93F642f64180aa3\n\
+             Deployer: 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266\n\
+             Tx: 0xabababababababababababababababababababababababababababababababab\n\
+             \n\
+             Deployed Vault on mainnet\n\
+             Address: 0x5FbDB2315678afecb367f032d93F642f64180aa3\n\
+             Tx: 0xabababababababababababababababababababababababababababababababab"
+        );
+    }
+}
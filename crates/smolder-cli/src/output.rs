@@ -0,0 +1,74 @@
+//! Output mode shared by all commands
+//!
+//! Commands print human-readable progress with [`status!`] and emit their
+//! result either as styled text or, with `--json`, as a single JSON document
+//! via [`print_json`]. `--quiet` keeps the result but drops the progress lines.
+
+use std::sync::OnceLock;
+
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+/// How commands should write to stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Styled, human-readable output
+    #[default]
+    Text,
+    /// Machine-readable JSON only
+    Json,
+    /// Results only, no progress or decoration
+    Quiet,
+}
+
+static MODE: OnceLock<OutputMode> = OnceLock::new();
+
+/// Set the output mode for this process. Only the first call takes effect.
+pub fn init(mode: OutputMode) {
+    let _ = MODE.set(mode);
+}
+
+/// The current output mode
+pub fn mode() -> OutputMode {
+    MODE.get().copied().unwrap_or_default()
+}
+
+/// Whether results should be printed as JSON
+pub fn is_json() -> bool {
+    mode() == OutputMode::Json
+}
+
+/// Whether progress and status lines should be printed
+pub fn is_verbose() -> bool {
+    mode() == OutputMode::Text
+}
+
+/// Print a value as pretty JSON on stdout
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Make `text` a clickable link in the terminal (OSC 8) when there is a `url`
+///
+/// Falls back to plain text when stdout isn't an interactive terminal, so
+/// piped and captured output stays clean.
+pub fn hyperlink(text: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) if console::user_attended() && console::colors_enabled() => {
+            format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+        }
+        _ => text.to_string(),
+    }
+}
+
+/// `println!` that only prints in text mode
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::is_verbose() {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use status;
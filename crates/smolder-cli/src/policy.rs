@@ -0,0 +1,520 @@
+//! Wallet policies: limits on what a stored wallet may sign
+//!
+//! A policy caps the value of each transaction and the value sent per network
+//! over 24 hours, and can restrict a wallet to some networks, contracts and
+//! functions. Every deploy and send signed with a stored wallet, from the CLI
+//! or the API, is checked against its wallet's policy before it is sent, and
+//! its value reserved towards the daily limit in the same transaction that
+//! checks it, so concurrent sends can't together go over.
+
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use smolder_core::{format_value, parse_value, Currency, Error, EvmAddress, WalletId};
+use smolder_db::{Network, Repositories, WalletPolicy};
+
+/// Window the daily spending limit applies to
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A transaction a wallet is asked to sign
+pub struct Intent<'a> {
+    pub network: &'a Network,
+    /// Address called; `None` for deploys
+    pub to: Option<Address>,
+    /// Registry name of the contract called, or the artifact deployed
+    pub contract: Option<&'a str>,
+    /// Signature of the function called; `None` for deploys
+    pub function: Option<&'a str>,
+    pub value: Option<U256>,
+}
+
+impl<'a> Intent<'a> {
+    /// Deploying `artifact`
+    pub fn deploy(network: &'a Network, artifact: &'a str, value: Option<U256>) -> Self {
+        Self {
+            network,
+            to: None,
+            contract: Some(artifact),
+            function: None,
+            value,
+        }
+    }
+
+    /// Calling `function` on `to`, the contract named `contract` in the
+    /// registry when it is tracked
+    pub fn call(
+        network: &'a Network,
+        to: Address,
+        contract: Option<&'a str>,
+        function: &'a str,
+        value: Option<U256>,
+    ) -> Self {
+        Self {
+            network,
+            to: Some(to),
+            contract,
+            function: Some(function),
+            value,
+        }
+    }
+}
+
+/// Parse a limit given in wei or with a unit (`1 ether`, `30 gwei`) into wei
+///
+/// Policies span networks, so only `wei`, `gwei` and `ether` apply.
+fn parse_limit(name: &str, value: &str) -> Result<String, Error> {
+    parse_value(value, Currency::ETHER)
+        .map(|wei| wei.to_string())
+        .map_err(|e| Error::invalid_param(name, e.to_string()))
+}
+
+/// Validate a policy before storing it, turning its limits into wei
+pub async fn normalize(
+    repos: &dyn Repositories,
+    mut policy: WalletPolicy,
+) -> Result<WalletPolicy, Error> {
+    for (name, limit) in [
+        ("max_value_per_tx", &mut policy.max_value_per_tx),
+        ("max_daily_spend", &mut policy.max_daily_spend),
+    ] {
+        if let Some(value) = limit {
+            *value = parse_limit(name, value)?;
+        }
+    }
+    for name in policy.allowed_networks.iter().flatten() {
+        if repos.networks().get_by_name(name).await?.is_none() {
+            return Err(Error::NetworkNotFound(name.clone()));
+        }
+    }
+    Ok(policy)
+}
+
+/// Fail unless the policy of `wallet`, if it has one, allows `intent`
+///
+/// Nothing is reserved: call [`reserve`] right before sending.
+pub async fn check(
+    repos: &dyn Repositories,
+    wallet_id: WalletId,
+    wallet: &str,
+    intent: &Intent<'_>,
+) -> Result<(), Error> {
+    let Some(policy) = repos.wallet_policies().get(wallet_id).await? else {
+        return Ok(());
+    };
+    check_rules(&policy, wallet, intent)?;
+
+    let value = intent.value.unwrap_or_default();
+    if let Some(max) = limit(&policy.max_daily_spend)? {
+        if !value.is_zero() {
+            let spent = spent_today(repos, wallet_id, intent.network).await?;
+            if spent.saturating_add(value) > max {
+                return Err(over_daily_limit(wallet, intent, spent, max));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Value reserved towards a wallet's daily limit for a transaction about to
+/// be sent
+#[must_use = "release the reservation if the transaction isn't sent"]
+pub struct Reservation {
+    spend: Option<i64>,
+}
+
+impl Reservation {
+    /// Give the value back, for a transaction that wasn't sent
+    pub async fn release(self, repos: &dyn Repositories) -> Result<(), Error> {
+        match self.spend {
+            Some(id) => repos.wallet_policies().release_spend(id).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Keep the reservation if `sent` succeeded, and release it otherwise
+    pub async fn settle<T, E: std::fmt::Display>(
+        self,
+        repos: &dyn Repositories,
+        sent: Result<T, E>,
+    ) -> Result<T, E> {
+        if let Err(e) = &sent {
+            if let Err(release) = self.release(repos).await {
+                tracing::warn!(error = %release, send_error = %e, "could not release a policy reservation");
+            }
+        }
+        sent
+    }
+}
+
+/// Check `intent` against the policy of `wallet` and reserve its value
+/// towards the daily limit, for a transaction sent right after
+///
+/// The value is recorded whether or not the wallet has a policy, so a policy
+/// set later counts what was already sent.
+pub async fn reserve(
+    repos: &dyn Repositories,
+    wallet_id: WalletId,
+    wallet: &str,
+    intent: &Intent<'_>,
+) -> Result<Reservation, Error> {
+    let policy = repos.wallet_policies().get(wallet_id).await?;
+    if let Some(policy) = &policy {
+        check_rules(policy, wallet, intent)?;
+    }
+    let value = intent.value.unwrap_or_default();
+    if value.is_zero() {
+        return Ok(Reservation { spend: None });
+    }
+
+    let max = match &policy {
+        Some(policy) => limit(&policy.max_daily_spend)?,
+        None => None,
+    };
+    let fits = |spends: &[String]| max.is_none_or(|max| total(spends).saturating_add(value) <= max);
+    let network = intent.network;
+    let spend = repos
+        .wallet_policies()
+        .reserve_spend(wallet_id, network.id, &value.to_string(), DAY, &fits)
+        .await?;
+    if spend.is_some() {
+        return Ok(Reservation { spend });
+    }
+    // Only a daily limit refuses a spend
+    let spent = spent_today(repos, wallet_id, network).await?;
+    Err(over_daily_limit(
+        wallet,
+        intent,
+        spent,
+        max.unwrap_or_default(),
+    ))
+}
+
+fn violation(wallet: &str, reason: String) -> Error {
+    Error::PolicyViolation {
+        wallet: wallet.to_string(),
+        reason,
+    }
+}
+
+/// Every rule of `policy` but the daily limit, which depends on what was sent
+fn check_rules(policy: &WalletPolicy, wallet: &str, intent: &Intent<'_>) -> Result<(), Error> {
+    let violation = |reason: String| violation(wallet, reason);
+    let network = intent.network;
+
+    if let Some(networks) = &policy.allowed_networks {
+        if !networks.contains(&network.name) {
+            return Err(violation(format!(
+                "network '{}' is not allowed",
+                network.name
+            )));
+        }
+    }
+    if let Some(contracts) = &policy.allowed_contracts {
+        if !contracts.iter().any(|c| allows_contract(c, intent)) {
+            let target = match (intent.contract, intent.to) {
+                (Some(name), _) => name.to_string(),
+                (None, Some(to)) => EvmAddress::from(to).to_string(),
+                (None, None) => "this contract".to_string(),
+            };
+            return Err(violation(format!("contract {} is not allowed", target)));
+        }
+    }
+    if let (Some(functions), Some(function)) = (&policy.allowed_functions, intent.function) {
+        if !functions.iter().any(|f| allows_function(f, function)) {
+            return Err(violation(format!("function {} is not allowed", function)));
+        }
+    }
+
+    let value = intent.value.unwrap_or_default();
+    if let Some(max) = limit(&policy.max_value_per_tx)? {
+        if value > max {
+            return Err(violation(format!(
+                "{} exceeds the limit of {} per transaction",
+                format_value(value, network.currency()),
+                format_value(max, network.currency())
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn over_daily_limit(wallet: &str, intent: &Intent<'_>, spent: U256, max: U256) -> Error {
+    let network = intent.network;
+    let value = intent.value.unwrap_or_default();
+    violation(
+        wallet,
+        format!(
+            "{} would bring the value sent on {} in 24 hours to {}, over the limit of {}",
+            format_value(value, network.currency()),
+            network.name,
+            format_value(spent.saturating_add(value), network.currency()),
+            format_value(max, network.currency())
+        ),
+    )
+}
+
+/// Wei a wallet sent on `network` over the last 24 hours
+pub async fn spent_today(
+    repos: &dyn Repositories,
+    wallet_id: WalletId,
+    network: &Network,
+) -> Result<U256, Error> {
+    let spends = repos
+        .wallet_policies()
+        .spent_within(wallet_id, network.id, DAY)
+        .await?;
+    Ok(total(&spends))
+}
+
+fn total(spends: &[String]) -> U256 {
+    spends
+        .iter()
+        .filter_map(|v| v.parse::<U256>().ok())
+        .fold(U256::ZERO, U256::saturating_add)
+}
+
+fn limit(value: &Option<String>) -> Result<Option<U256>, Error> {
+    value
+        .as_deref()
+        .map(|v| {
+            v.parse()
+                .map_err(|_| Error::Validation(format!("Invalid limit in wallet policy: {}", v)))
+        })
+        .transpose()
+}
+
+/// Contracts match by registry name, or by address, case-insensitively
+fn allows_contract(allowed: &str, intent: &Intent<'_>) -> bool {
+    match allowed.parse::<Address>() {
+        Ok(address) => intent.to == Some(address),
+        Err(_) => intent.contract == Some(allowed),
+    }
+}
+
+/// Functions match by name, or by full signature
+fn allows_function(allowed: &str, signature: &str) -> bool {
+    match allowed.contains('(') {
+        true => allowed == signature,
+        false => signature.split('(').next() == Some(allowed),
+    }
+}
+
+/// Describe a policy, one rule per line
+pub fn describe(policy: &WalletPolicy) -> Vec<String> {
+    let wei = |v: &str| {
+        v.parse::<U256>()
+            .map(|v| format_value(v, Currency::ETHER))
+            .unwrap_or_else(|_| v.to_string())
+    };
+    let mut rules = Vec::new();
+    if let Some(max) = &policy.max_value_per_tx {
+        rules.push(format!("Max value per transaction: {}", wei(max)));
+    }
+    if let Some(max) = &policy.max_daily_spend {
+        rules.push(format!("Max value per network per 24h: {}", wei(max)));
+    }
+    for (label, list) in [
+        ("Networks", &policy.allowed_networks),
+        ("Contracts", &policy.allowed_contracts),
+        ("Functions", &policy.allowed_functions),
+    ] {
+        if let Some(list) = list {
+            rules.push(format!("{}: {}", label, list.join(", ")));
+        }
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_core::encrypt_private_key;
+    use smolder_db::{ChainId, MemoryDatabase, NewNetwork, NewWallet};
+
+    async fn setup() -> (MemoryDatabase, Network, WalletId) {
+        let repos = MemoryDatabase::new();
+        let network = repos
+            .networks()
+            .upsert(&NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "http://localhost:8545".to_string(),
+                explorer_url: None,
+            })
+            .await
+            .unwrap();
+        let wallet = repos
+            .wallets()
+            .create(&NewWallet {
+                name: "ops".to_string(),
+                address: Address::repeat_byte(0x11).into(),
+                encrypted_key: encrypt_private_key("0x01").unwrap(),
+            })
+            .await
+            .unwrap();
+        (repos, network, wallet.id)
+    }
+
+    #[tokio::test]
+    async fn test_wallets_without_policy_are_unrestricted() {
+        let (repos, network, wallet) = setup().await;
+        let intent = Intent::deploy(&network, "Vault", Some(U256::MAX));
+        check(&repos, wallet, "ops", &intent).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_policy_rules() {
+        let (repos, network, wallet) = setup().await;
+        let vault = Address::repeat_byte(0xaa);
+        repos
+            .wallet_policies()
+            .set(
+                wallet,
+                &WalletPolicy {
+                    max_value_per_tx: Some(parse_limit("max_value_per_tx", "1 ether").unwrap()),
+                    max_daily_spend: Some(parse_limit("max_daily_spend", "1.5 ether").unwrap()),
+                    allowed_networks: Some(vec!["mainnet".to_string()]),
+                    allowed_contracts: Some(vec![
+                        "Vault".to_string(),
+                        "0xBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB".to_string(),
+                    ]),
+                    allowed_functions: Some(vec![
+                        "deposit".to_string(),
+                        "withdraw(uint256)".to_string(),
+                    ]),
+                },
+            )
+            .await
+            .unwrap();
+        let ether = U256::from(10u64).pow(U256::from(18u64));
+        async fn allowed(
+            repos: &MemoryDatabase,
+            wallet: WalletId,
+            intent: Intent<'_>,
+        ) -> Result<(), Error> {
+            check(repos, wallet, "ops", &intent).await
+        }
+
+        allowed(&repos, wallet, Intent::deploy(&network, "Vault", None))
+            .await
+            .unwrap();
+        allowed(
+            &repos,
+            wallet,
+            Intent::call(&network, vault, Some("Vault"), "deposit()", Some(ether)),
+        )
+        .await
+        .unwrap();
+        allowed(
+            &repos,
+            wallet,
+            Intent::call(
+                &network,
+                Address::repeat_byte(0xbb),
+                None,
+                "withdraw(uint256)",
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+
+        for intent in [
+            Intent::deploy(&network, "Token", None),
+            Intent::call(
+                &network,
+                Address::repeat_byte(0xcc),
+                None,
+                "deposit()",
+                None,
+            ),
+            Intent::call(&network, vault, Some("Vault"), "withdraw(address)", None),
+            Intent::call(&network, vault, Some("Vault"), "sweep()", None),
+            Intent::call(
+                &network,
+                vault,
+                Some("Vault"),
+                "deposit()",
+                Some(ether + U256::from(1)),
+            ),
+        ] {
+            let err = allowed(&repos, wallet, intent).await.unwrap_err();
+            assert_eq!(err.code(), "POLICY_VIOLATION", "{}", err);
+        }
+        let sepolia = Network {
+            name: "sepolia".to_string(),
+            ..network.clone()
+        };
+        assert!(
+            allowed(&repos, wallet, Intent::deploy(&sepolia, "Vault", None))
+                .await
+                .is_err()
+        );
+
+        // Spends add up over the day, per network, and are reserved before
+        // sending so concurrent sends can't together go over
+        let deposit = |value| Intent::call(&network, vault, Some("Vault"), "deposit()", value);
+        let first = reserve(&repos, wallet, "ops", &deposit(Some(ether)))
+            .await
+            .unwrap();
+        let err = reserve(&repos, wallet, "ops", &deposit(Some(ether)))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("24 hours"), "{}", err);
+        first.release(&repos).await.unwrap();
+        let failed = reserve(&repos, wallet, "ops", &deposit(Some(ether)))
+            .await
+            .unwrap();
+        let sent: Result<(), &str> = failed.settle(&repos, Err("reverted")).await;
+        assert!(sent.is_err());
+        reserve(&repos, wallet, "ops", &deposit(Some(ether)))
+            .await
+            .unwrap()
+            .settle(&repos, Ok::<_, Error>(()))
+            .await
+            .unwrap();
+
+        let half = ether / U256::from(2);
+        allowed(&repos, wallet, deposit(Some(half))).await.unwrap();
+        let _ = reserve(&repos, wallet, "ops", &deposit(Some(half)))
+            .await
+            .unwrap();
+        let err = allowed(
+            &repos,
+            wallet,
+            Intent::call(
+                &network,
+                vault,
+                Some("Vault"),
+                "deposit()",
+                Some(U256::from(1)),
+            ),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("24 hours"), "{}", err);
+        allowed(
+            &repos,
+            wallet,
+            Intent::call(&network, vault, Some("Vault"), "deposit()", None),
+        )
+        .await
+        .unwrap();
+
+        repos.advance(DAY + Duration::from_secs(1));
+        allowed(
+            &repos,
+            wallet,
+            Intent::call(&network, vault, Some("Vault"), "deposit()", Some(ether)),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("max", "30 gwei").unwrap(), "30000000000");
+        assert!(parse_limit("max", "lots").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! USD prices of native currencies
+//!
+//! Configured under `[prices]` in smolder.toml. Fees are valued at the
+//! closing price of the day they were paid; prices are fetched from CoinGecko
+//! or a custom endpoint once per currency and day and cached in the database.
+
+use std::time::Duration;
+
+use color_eyre::eyre::Result;
+use smolder_core::{Error, Secret};
+use smolder_db::{PriceDay, Repositories};
+
+use crate::config::{resolve_env_var, PricesConfig};
+
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// CoinGecko coin ids of the native currencies of well-known chains
+const COINGECKO_IDS: &[(&str, &str)] = &[
+    ("ETH", "ethereum"),
+    ("POL", "polygon-ecosystem-token"),
+    ("MATIC", "matic-network"),
+    ("BNB", "binancecoin"),
+    ("AVAX", "avalanche-2"),
+    ("XDAI", "xdai"),
+];
+
+#[derive(Debug, Clone)]
+enum Source {
+    CoinGecko {
+        api_key: Option<Secret<String>>,
+    },
+    /// URL template with `{symbol}` and `{date}` placeholders
+    Endpoint(String),
+}
+
+/// Fetches daily USD prices
+#[derive(Debug, Clone)]
+pub struct PriceOracle {
+    source: Source,
+    client: reqwest::Client,
+}
+
+impl PriceOracle {
+    /// Build an oracle from `[prices]`, resolving `${VAR}` references
+    pub fn from_config(config: &PricesConfig) -> Result<Self> {
+        let source = match config.source.as_str() {
+            "coingecko" => Source::CoinGecko {
+                api_key: config
+                    .api_key
+                    .as_deref()
+                    .map(resolve_env_var)
+                    .transpose()?
+                    .map(Secret::new),
+            },
+            url => Source::Endpoint(url.to_string()),
+        };
+        Ok(Self {
+            source,
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+        })
+    }
+
+    /// USD price of `symbol` on `day` (YYYY-MM-DD), or None when the source
+    /// doesn't know the currency
+    pub async fn fetch(&self, symbol: &str, day: &str) -> Result<Option<f64>, Error> {
+        match &self.source {
+            Source::CoinGecko { api_key } => {
+                let Some(id) = coingecko_id(symbol) else {
+                    return Ok(None);
+                };
+                let url = format!(
+                    "{}/coins/{}/history?date={}&localization=false",
+                    COINGECKO_URL,
+                    id,
+                    coingecko_date(day)?
+                );
+                let mut request = self.client.get(url);
+                if let Some(key) = api_key {
+                    request = request.header("x-cg-demo-api-key", key.expose());
+                }
+                let response = self.get(request).await?;
+                Ok(response["market_data"]["current_price"]["usd"].as_f64())
+            }
+            Source::Endpoint(template) => {
+                let response = self
+                    .get(self.client.get(endpoint_url(template, symbol, day)))
+                    .await?;
+                Ok(response["usd"].as_f64())
+            }
+        }
+    }
+
+    async fn get(&self, request: reqwest::RequestBuilder) -> Result<serde_json::Value, Error> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| Error::Rpc(format!("Price request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(Error::Rpc(format!(
+                "Price source returned {}: {}",
+                status, text
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| Error::Rpc(format!("Invalid price response: {}", e)))
+    }
+}
+
+/// Fetch and cache the prices of `days`
+///
+/// Prices that can't be fetched are logged and left out, so the fees they
+/// value stay unpriced rather than failing the report.
+pub async fn fill(
+    db: &dyn Repositories,
+    oracle: &PriceOracle,
+    days: &[PriceDay],
+) -> Result<(), Error> {
+    for PriceDay { symbol, day } in days {
+        match oracle.fetch(symbol, day).await {
+            Ok(Some(usd)) => db.prices().put(symbol, day, usd).await?,
+            Ok(None) => tracing::debug!(%symbol, %day, "no USD price known"),
+            Err(e) => tracing::warn!(error = %e, %symbol, %day, "could not fetch USD price"),
+        }
+    }
+    Ok(())
+}
+
+/// Cached USD price of `symbol` on `day`, fetched first when an oracle is
+/// given and the price is missing
+pub async fn usd_price(
+    db: &dyn Repositories,
+    oracle: Option<&PriceOracle>,
+    symbol: &str,
+    day: &str,
+) -> Result<Option<f64>, Error> {
+    if let Some(usd) = db.prices().get(symbol, day).await? {
+        return Ok(Some(usd));
+    }
+    let Some(oracle) = oracle else {
+        return Ok(None);
+    };
+    let price_day = PriceDay {
+        symbol: symbol.to_string(),
+        day: day.to_string(),
+    };
+    fill(db, oracle, std::slice::from_ref(&price_day)).await?;
+    db.prices().get(symbol, day).await
+}
+
+/// USD value of `fee`, in the currency's smallest unit
+pub fn fee_usd(fee: &str, decimals: u8, usd: f64) -> Option<f64> {
+    let fee: u128 = fee.parse().ok()?;
+    Some(fee as f64 / 10f64.powi(decimals.into()) * usd)
+}
+
+fn coingecko_id(symbol: &str) -> Option<&'static str> {
+    COINGECKO_IDS
+        .iter()
+        .find(|(s, _)| s.eq_ignore_ascii_case(symbol))
+        .map(|(_, id)| *id)
+}
+
+/// CoinGecko wants dates as DD-MM-YYYY
+fn coingecko_date(day: &str) -> Result<String, Error> {
+    match day.splitn(3, '-').collect::<Vec<_>>()[..] {
+        [year, month, day] => Ok(format!("{}-{}-{}", day, month, year)),
+        _ => Err(Error::invalid_param(
+            "day",
+            format!("'{}' is not YYYY-MM-DD", day),
+        )),
+    }
+}
+
+fn endpoint_url(template: &str, symbol: &str, day: &str) -> String {
+    template.replace("{symbol}", symbol).replace("{date}", day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coingecko_request() {
+        assert_eq!(coingecko_id("eth"), Some("ethereum"));
+        assert_eq!(coingecko_id("DOGE"), None);
+        assert_eq!(coingecko_date("2025-03-07").unwrap(), "07-03-2025");
+        assert!(coingecko_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_endpoint_url() {
+        assert_eq!(
+            endpoint_url(
+                "https://prices.example.com/{symbol}?date={date}",
+                "ETH",
+                "2025-03-07"
+            ),
+            "https://prices.example.com/ETH?date=2025-03-07"
+        );
+    }
+
+    #[test]
+    fn test_fee_usd() {
+        assert_eq!(fee_usd("10000000000000000", 18, 2000.0), Some(20.0));
+        assert_eq!(fee_usd("not a fee", 18, 2000.0), None);
+    }
+}
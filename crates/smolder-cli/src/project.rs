@@ -0,0 +1,79 @@
+//! Project root detection
+//!
+//! Every path smolder reads or writes (foundry.toml, smolder.toml, `.smolder/`,
+//! `out/`, `broadcast/`, deployment files) is relative to the project root.
+//! In a workspace holding several Foundry projects, commands run from any
+//! subdirectory act on the nearest enclosing project, or on `--root`.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result};
+use smolder_core::SmolderDir;
+
+/// Entries whose presence marks a project root
+const MARKERS: &[&str] = &[
+    "foundry.toml",
+    "smolder.toml",
+    SmolderDir::NAME,
+    "hardhat.config.ts",
+    "hardhat.config.js",
+];
+
+/// Nearest directory from `start` upwards holding a project marker
+pub fn find_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .map(Path::to_path_buf)
+}
+
+/// Make the project root the working directory, so relative paths resolve
+/// against it
+///
+/// `root` is used as given; otherwise the nearest project enclosing the
+/// current directory is, and the current directory stays when there is none
+/// (`init` reports what's missing).
+pub fn enter(root: Option<&Path>) -> Result<()> {
+    let root = match root {
+        Some(root) => {
+            if !root.is_dir() {
+                return Err(eyre!(
+                    "Project root '{}' is not a directory",
+                    root.display()
+                ));
+            }
+            root.to_path_buf()
+        }
+        None => match find_root(&std::env::current_dir()?) {
+            Some(root) => root,
+            None => return Ok(()),
+        },
+    };
+    std::env::set_current_dir(&root)
+        .map_err(|e| eyre!("Could not enter project root '{}': {}", root.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_root() {
+        let workspace = std::env::temp_dir().join(format!("smolder-root-{}", std::process::id()));
+        let periphery = workspace.join("periphery");
+        let nested = periphery.join("script").join("utils");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(workspace.join("contracts")).unwrap();
+        std::fs::write(workspace.join("contracts").join("foundry.toml"), "").unwrap();
+        std::fs::write(periphery.join("foundry.toml"), "").unwrap();
+
+        assert_eq!(find_root(&nested), Some(periphery.clone()));
+        assert_eq!(find_root(&periphery), Some(periphery));
+        assert_eq!(
+            find_root(&workspace.join("contracts")),
+            Some(workspace.join("contracts"))
+        );
+
+        std::fs::remove_dir_all(&workspace).unwrap();
+    }
+}
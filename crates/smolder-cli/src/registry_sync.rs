@@ -0,0 +1,392 @@
+//! Registry sync between smolder instances
+//!
+//! `smolder push` and `smolder pull` exchange a [`SyncBundle`] with another
+//! server's `/api/sync`: the networks and the deployments on them, each with
+//! its contract. Wallets never leave the registry. A deployment is the same
+//! on both sides when its transaction hash matches; one recorded at the same
+//! address from a different transaction is a conflict and is left alone.
+//!
+//! RPC URLs travel with their credentials masked, so a network whose URL has
+//! any must be added on the receiving side, with its own URL, before its
+//! deployments can be merged.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use smolder_core::secret::REDACTED;
+use smolder_core::{BytecodeHash, ChainId, CompilerSettings, Error, EvmAddress, TxHash};
+use smolder_db::{
+    DeploymentFilter, ImportStatus, NewContract, NewContractDeployment, NewNetwork, Repositories,
+};
+use utoipa::ToSchema;
+
+/// Path of the sync endpoint, below a server's base URL
+const SYNC_PATH: &str = "api/sync";
+
+/// Time allowed for a whole push or pull
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Networks and deployments exchanged by push and pull
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct SyncBundle {
+    pub networks: Vec<SyncNetwork>,
+    /// Oldest first, so versions are numbered in the same order on both sides
+    pub deployments: Vec<SyncDeployment>,
+}
+
+/// A network, registered on the receiving side when missing
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncNetwork {
+    pub name: String,
+    pub chain_id: ChainId,
+    /// With its credentials masked
+    pub rpc_url: String,
+    pub explorer_url: Option<String>,
+}
+
+/// A deployment with its contract
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncDeployment {
+    pub network: String,
+    pub contract: String,
+    /// Version on the sending side; the receiving side numbers deployments
+    /// in the order they arrive
+    #[serde(default)]
+    pub version: i64,
+    pub source_path: String,
+    /// ABI as a JSON string
+    pub abi: String,
+    pub bytecode_hash: BytecodeHash,
+    pub storage_layout: Option<String>,
+    /// Compiler version and settings, when known
+    #[serde(default)]
+    pub compiler: Option<CompilerSettings>,
+    pub address: EvmAddress,
+    pub deployer: String,
+    /// None when the deployment was recorded without its transaction
+    #[serde(default, with = "smolder_core::hash::optional_tx_hash")]
+    #[schema(value_type = Option<String>)]
+    pub tx_hash: Option<TxHash>,
+    pub block_number: Option<i64>,
+    pub constructor_args: Option<String>,
+    pub initcode_size: Option<i64>,
+    pub runtime_size: Option<i64>,
+    pub gas_used: Option<i64>,
+    /// Effective gas price in wei; missing from bundles of older versions
+    #[serde(default)]
+    pub gas_price: Option<String>,
+}
+
+/// What merging a bundle did
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct SyncReport {
+    /// Networks that were registered
+    pub networks_added: Vec<String>,
+    /// Deployments recorded as new
+    pub added: usize,
+    /// Deployments already tracked that got a missing block number,
+    /// constructor arguments or gas filled in
+    pub updated: usize,
+    /// Deployments already tracked with nothing to change
+    pub skipped: usize,
+    /// Deployments left alone because the two registries disagree
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// A deployment the two registries disagree about
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncConflict {
+    pub network: String,
+    pub contract: String,
+    pub address: String,
+    pub reason: String,
+}
+
+/// Collect the registry's networks and non-archived deployments, or only
+/// those on `networks` when any are given
+pub async fn bundle(db: &dyn Repositories, networks: &[String]) -> Result<SyncBundle, Error> {
+    let wanted = |name: &str| networks.is_empty() || networks.iter().any(|n| n == name);
+
+    let mut bundle = SyncBundle::default();
+    for network in db.networks().list().await? {
+        if !wanted(&network.name) {
+            continue;
+        }
+        bundle.networks.push(SyncNetwork {
+            name: network.name,
+            chain_id: network.chain_id,
+            rpc_url: network.rpc_url.redacted(),
+            explorer_url: network.explorer_url,
+        });
+    }
+    for name in networks {
+        if !bundle.networks.iter().any(|n| &n.name == name) {
+            return Err(Error::NetworkNotFound(name.clone()));
+        }
+    }
+
+    let mut contracts = HashMap::new();
+    for network in &bundle.networks {
+        let mut views = db
+            .deployments()
+            .list(DeploymentFilter {
+                network: Some(network.name.clone()),
+                ..Default::default()
+            })
+            .await?;
+        views.sort_by_key(|view| view.id.0);
+
+        for view in views {
+            let Some(deployment) = db.deployments().get_by_id(view.id).await? else {
+                continue;
+            };
+            if let Entry::Vacant(entry) = contracts.entry(deployment.contract_id) {
+                entry.insert(db.contracts().get_by_id(deployment.contract_id).await?);
+            }
+            let Some(contract) = &contracts[&deployment.contract_id] else {
+                continue;
+            };
+            bundle.deployments.push(SyncDeployment {
+                network: network.name.clone(),
+                contract: contract.name.clone(),
+                version: deployment.version,
+                source_path: contract.source_path.clone(),
+                abi: contract.abi.clone(),
+                bytecode_hash: contract.bytecode_hash,
+                storage_layout: contract.storage_layout.clone(),
+                compiler: contract.compiler.clone(),
+                address: deployment.address,
+                deployer: deployment.deployer,
+                tx_hash: deployment.tx_hash,
+                block_number: deployment.block_number,
+                constructor_args: deployment.constructor_args,
+                initcode_size: deployment.initcode_size,
+                runtime_size: deployment.runtime_size,
+                gas_used: deployment.gas_used,
+                gas_price: deployment.gas_price,
+            });
+        }
+    }
+
+    Ok(bundle)
+}
+
+/// Merge a bundle into the registry
+///
+/// Missing networks are registered, unless their RPC URL was redacted; those,
+/// and networks registered with a different chain ID, are conflicts for all
+/// their deployments. Deployments already tracked at
+/// their address from the same transaction are skipped or filled in, and
+/// those tracked from a different transaction are conflicts.
+pub async fn merge(db: &dyn Repositories, bundle: &SyncBundle) -> Result<SyncReport, Error> {
+    let mut report = SyncReport::default();
+
+    let mut networks = HashMap::new();
+    let mut mismatched = HashMap::new();
+    for incoming in &bundle.networks {
+        match db.networks().get_by_name(&incoming.name).await? {
+            Some(local) if local.chain_id != incoming.chain_id => {
+                mismatched.insert(
+                    incoming.name.as_str(),
+                    format!(
+                        "network has chain ID {} here and {} there",
+                        local.chain_id, incoming.chain_id
+                    ),
+                );
+            }
+            Some(local) => {
+                networks.insert(incoming.name.clone(), local);
+            }
+            None if incoming.rpc_url.contains(REDACTED) => {
+                mismatched.insert(
+                    incoming.name.as_str(),
+                    "network is not registered here and its RPC URL was redacted; add it \
+                     with `smolder network add` first"
+                        .to_string(),
+                );
+            }
+            None => {
+                let network = db
+                    .networks()
+                    .upsert(&NewNetwork {
+                        name: incoming.name.clone(),
+                        chain_id: incoming.chain_id,
+                        rpc_url: incoming.rpc_url.clone(),
+                        explorer_url: incoming.explorer_url.clone(),
+                    })
+                    .await?;
+                report.networks_added.push(network.name.clone());
+                networks.insert(incoming.name.clone(), network);
+            }
+        }
+    }
+
+    // Batches per network, in the bundle's order
+    let mut order = Vec::new();
+    let mut batches: HashMap<&str, Vec<NewContractDeployment>> = HashMap::new();
+    let mut seen = HashSet::new();
+    for deployment in &bundle.deployments {
+        let conflict = |reason: String| SyncConflict {
+            network: deployment.network.clone(),
+            contract: deployment.contract.clone(),
+            address: deployment.address.to_string(),
+            reason,
+        };
+
+        if let Some(reason) = mismatched.get(deployment.network.as_str()) {
+            report.conflicts.push(conflict(reason.clone()));
+            continue;
+        }
+        if !networks.contains_key(&deployment.network) {
+            match db.networks().get_by_name(&deployment.network).await? {
+                Some(network) => {
+                    networks.insert(deployment.network.clone(), network);
+                }
+                None => {
+                    report.conflicts.push(conflict(
+                        "network is not in the bundle or registry".to_string(),
+                    ));
+                    continue;
+                }
+            }
+        }
+        let network = &networks[&deployment.network];
+
+        if let Some(local) = db
+            .deployments()
+            .get_by_address(network.id, &deployment.address.to_string())
+            .await?
+        {
+            if let (Some(here), Some(there)) = (local.tx_hash, deployment.tx_hash) {
+                if here != there {
+                    report.conflicts.push(conflict(format!(
+                        "address was deployed by {} here and {} there",
+                        here, there
+                    )));
+                    continue;
+                }
+            }
+        }
+
+        if seen.insert(deployment.network.as_str()) {
+            order.push(deployment.network.as_str());
+        }
+        batches
+            .entry(deployment.network.as_str())
+            .or_default()
+            .push(NewContractDeployment {
+                contract: NewContract {
+                    name: deployment.contract.clone(),
+                    source_path: deployment.source_path.clone(),
+                    abi: deployment.abi.clone(),
+                    bytecode_hash: deployment.bytecode_hash,
+                    storage_layout: deployment.storage_layout.clone(),
+                    compiler: deployment.compiler.clone(),
+                },
+                address: deployment.address,
+                deployer: deployment.deployer.clone(),
+                tx_hash: deployment.tx_hash,
+                block_number: deployment.block_number,
+                constructor_args: deployment.constructor_args.clone(),
+                initcode_size: deployment.initcode_size,
+                runtime_size: deployment.runtime_size,
+                gas_used: deployment.gas_used,
+                gas_price: deployment.gas_price.clone(),
+            });
+    }
+
+    for name in order {
+        let network = &networks[name];
+        for status in db
+            .deployments()
+            .import_batch(network.id, &batches[name])
+            .await?
+        {
+            match status {
+                ImportStatus::Added => report.added += 1,
+                ImportStatus::Updated => report.updated += 1,
+                ImportStatus::Skipped => report.skipped += 1,
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Another smolder server's sync endpoint
+pub struct Remote {
+    url: reqwest::Url,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+/// Error body of the API
+#[derive(Deserialize)]
+struct RemoteError {
+    message: String,
+}
+
+impl Remote {
+    /// Point at the server at `base_url`, the address its dashboard is
+    /// served from
+    pub fn new(base_url: &str, token: Option<String>) -> Result<Self> {
+        let mut base: reqwest::Url = base_url
+            .parse()
+            .map_err(|e| eyre!("Invalid remote URL '{}': {}", base_url, e))?;
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(Self {
+            url: base.join(SYNC_PATH)?,
+            token,
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+        })
+    }
+
+    /// Get the remote's networks and deployments
+    pub async fn pull(&self, networks: &[String]) -> Result<SyncBundle> {
+        let mut url = self.url.clone();
+        if !networks.is_empty() {
+            url.query_pairs_mut()
+                .append_pair("network", &networks.join(","));
+        }
+        self.send(self.client.get(url)).await
+    }
+
+    /// Merge `bundle` into the remote's registry
+    pub async fn push(&self, bundle: &SyncBundle) -> Result<SyncReport> {
+        let request = self.client.post(self.url.clone()).json(bundle);
+        self.send(request).await
+    }
+
+    async fn send<T: for<'de> Deserialize<'de>>(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| eyre!("Could not reach {}: {}", self.url, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = match response.json::<RemoteError>().await {
+                Ok(error) => error.message,
+                Err(_) => status.to_string(),
+            };
+            return Err(eyre!("{} answered {}: {}", self.url, status, message));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| eyre!("Unexpected response from {}: {}", self.url, e))
+    }
+}
@@ -0,0 +1,139 @@
+//! Submit transactions through private relays
+//!
+//! A network with a private relay sends signed transactions to the relay's
+//! RPC instead of its own, keeping them out of the public mempool until they
+//! are mined. Flashbots Protect also reports what became of a transaction, so
+//! one it drops is noticed instead of being waited on until the receipt
+//! timeout.
+
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{eyre, Result};
+use smolder_core::TxHash;
+
+pub const FLASHBOTS_PROTECT: &str = "https://rpc.flashbots.net";
+pub const MEV_BLOCKER: &str = "https://rpc.mevblocker.io";
+
+const FLASHBOTS_STATUS_URL: &str = "https://protect.flashbots.net/tx";
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(6);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Relay RPC for a `--private-relay` value: `flashbots`, `mev-blocker`, an
+/// `http(s)` URL, or `none` to send through the network's RPC again
+pub fn parse(value: &str) -> Result<Option<String>> {
+    match value {
+        "none" => Ok(None),
+        "flashbots" => Ok(Some(FLASHBOTS_PROTECT.to_string())),
+        "mev-blocker" | "mevblocker" => Ok(Some(MEV_BLOCKER.to_string())),
+        url if url.starts_with("http://") || url.starts_with("https://") => {
+            Ok(Some(url.trim_end_matches('/').to_string()))
+        }
+        other => Err(eyre!(
+            "Invalid private relay '{}': use flashbots, mev-blocker, none or an RPC URL",
+            other
+        )),
+    }
+}
+
+/// Whether `relay_url` is Flashbots Protect, which serves transaction status
+fn is_flashbots(relay_url: &str) -> bool {
+    relay_url
+        .parse::<reqwest::Url>()
+        .ok()
+        .and_then(|url| url.host_str().map(|h| h.ends_with("flashbots.net")))
+        .unwrap_or(false)
+}
+
+/// What a relay reports about a transaction it was given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayStatus {
+    /// Not mined yet; the relay keeps trying
+    Pending,
+    Included,
+    /// Dropped by the relay and never mined
+    Failed(String),
+    Unknown,
+}
+
+impl RelayStatus {
+    fn from_response(response: &serde_json::Value) -> Self {
+        match response["status"].as_str() {
+            Some("PENDING") => Self::Pending,
+            Some("INCLUDED") => Self::Included,
+            Some(status @ ("FAILED" | "CANCELLED")) => Self::Failed(format!(
+                "Private relay reported the transaction {}",
+                status.to_lowercase()
+            )),
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Ask the relay what became of `tx_hash`; `None` when it doesn't tell
+pub async fn status(relay_url: &str, tx_hash: &TxHash) -> Result<Option<RelayStatus>> {
+    if !is_flashbots(relay_url) {
+        return Ok(None);
+    }
+    let response: serde_json::Value = reqwest::Client::new()
+        .get(format!("{}/{}", FLASHBOTS_STATUS_URL, tx_hash))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(Some(RelayStatus::from_response(&response)))
+}
+
+/// Poll the relay until it reports `tx_hash` dropped, returning why
+///
+/// Never returns for relays that don't report status, and gives up after
+/// `timeout`; racing it against the receipt ends waits for transactions that
+/// will never be mined.
+pub async fn wait_for_drop(relay_url: &str, tx_hash: &TxHash, timeout: Duration) -> String {
+    let deadline = Instant::now() + timeout;
+    if is_flashbots(relay_url) {
+        while Instant::now() < deadline {
+            match status(relay_url, tx_hash).await {
+                Ok(Some(RelayStatus::Failed(reason))) => return reason,
+                Ok(Some(RelayStatus::Included)) => break,
+                Ok(_) => {}
+                Err(e) => tracing::debug!(%tx_hash, error = %e, "could not get relay status"),
+            }
+            tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+        }
+    }
+    std::future::pending().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_relay() {
+        assert_eq!(
+            parse("flashbots").unwrap().as_deref(),
+            Some(FLASHBOTS_PROTECT)
+        );
+        assert_eq!(parse("mev-blocker").unwrap().as_deref(), Some(MEV_BLOCKER));
+        assert_eq!(
+            parse("https://rpc.flashbots.net/fast/").unwrap().as_deref(),
+            Some("https://rpc.flashbots.net/fast")
+        );
+        assert_eq!(parse("none").unwrap(), None);
+        assert!(parse("mempool").is_err());
+
+        assert!(is_flashbots("https://rpc.flashbots.net/fast"));
+        assert!(!is_flashbots(MEV_BLOCKER));
+    }
+
+    #[test]
+    fn test_relay_status() {
+        let status = |s: &str| RelayStatus::from_response(&serde_json::json!({ "status": s }));
+        assert_eq!(status("PENDING"), RelayStatus::Pending);
+        assert_eq!(status("INCLUDED"), RelayStatus::Included);
+        assert!(matches!(status("CANCELLED"), RelayStatus::Failed(r) if r.ends_with("cancelled")));
+        assert_eq!(status("UNKNOWN"), RelayStatus::Unknown);
+    }
+}
@@ -0,0 +1,251 @@
+//! Who can do what on Ownable and AccessControl contracts
+//!
+//! Role holders are found by replaying the contract's `RoleGranted` and
+//! `RoleRevoked` events, then confirmed with `hasRole`, together with the
+//! registry's wallets, so a wallet holding a role shows up even when the
+//! node can't serve the logs.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use alloy::primitives::{keccak256, Address, Bytes, B256};
+use alloy::sol;
+use alloy::sol_types::SolEvent;
+use futures::future::join_all;
+use serde::Serialize;
+use smolder_core::{Abi, Error};
+use smolder_db::{DeploymentView, Repositories};
+use utoipa::ToSchema;
+
+use crate::rpc;
+
+sol! {
+    interface IAccessControl {
+        event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+        event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+
+        function owner() external view returns (address);
+        function hasRole(bytes32 role, address account) external view returns (bool);
+        function getRoleAdmin(bytes32 role) external view returns (bytes32);
+    }
+}
+
+/// Roles OpenZeppelin contracts commonly define, recognized even when the
+/// ABI has no getter for them
+const COMMON_ROLES: [&str; 6] = [
+    "MINTER_ROLE",
+    "BURNER_ROLE",
+    "PAUSER_ROLE",
+    "UPGRADER_ROLE",
+    "OPERATOR_ROLE",
+    "ADMIN_ROLE",
+];
+
+/// Owner and role holders of a deployment
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RoleReport {
+    /// `owner()`, for Ownable contracts
+    pub owner: Option<RoleMember>,
+    /// Roles with at least one holder, or with a getter in the ABI
+    pub roles: Vec<Role>,
+    /// Why the role events could not be read, when only registry wallets
+    /// were checked
+    pub events_error: Option<String>,
+}
+
+/// An AccessControl role
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Role {
+    /// Role hash
+    pub role: String,
+    /// Constant name, when the hash is recognized
+    pub name: Option<String>,
+    /// Role allowed to grant and revoke this one
+    pub admin_role: Option<String>,
+    pub members: Vec<RoleMember>,
+}
+
+/// An account holding a role
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RoleMember {
+    pub address: String,
+    /// Registry wallet with this address
+    pub wallet: Option<String>,
+}
+
+/// Read the owner and role holders of `deployment`
+///
+/// Fails with a validation error when its ABI is neither Ownable nor
+/// AccessControl.
+pub async fn read(
+    db: &dyn Repositories,
+    deployment: &DeploymentView,
+    rpc_url: &str,
+) -> Result<RoleReport, Error> {
+    let abi = Abi::parse(&deployment.abi)?;
+    let ownable = abi.function("owner").is_some_and(|f| f.inputs.is_empty());
+    let access_control = abi.function("hasRole").is_some();
+    if !ownable && !access_control {
+        return Err(Error::Validation(format!(
+            "{} implements neither Ownable nor AccessControl",
+            deployment.contract_name
+        )));
+    }
+
+    let address = *deployment.address;
+    let wallets: HashMap<Address, String> = db
+        .wallets()
+        .list()
+        .await?
+        .into_iter()
+        .map(|w| (*w.address, w.name))
+        .collect();
+    let member = |account: Address| RoleMember {
+        address: account.to_checksum(None),
+        wallet: wallets.get(&account).cloned(),
+    };
+
+    // eth_call errors are treated as reverts below, so make sure the node
+    // is up first
+    rpc::get_chain_id(rpc_url)
+        .await
+        .map_err(|e| Error::Rpc(e.to_string()))?;
+
+    let owner = match ownable {
+        true => rpc::call(rpc_url, address, IAccessControl::ownerCall {})
+            .await
+            .map(member),
+        false => None,
+    };
+    if !access_control {
+        return Ok(RoleReport {
+            owner,
+            roles: Vec::new(),
+            events_error: None,
+        });
+    }
+
+    // Custom roles are read from their getters, as the constant may not be
+    // the hash of its name
+    let mut names = known_roles();
+    let mut defined = BTreeSet::from([B256::ZERO]);
+    for name in role_getters(&abi) {
+        let selector = keccak256(format!("{}()", name));
+        let data = Bytes::copy_from_slice(&selector[..4]);
+        if let Ok(value) = rpc::execute_eth_call(rpc_url, address, data).await {
+            if let Ok(role) = B256::try_from(value.as_ref()) {
+                names.insert(role, name);
+                defined.insert(role);
+            }
+        }
+    }
+
+    // Candidates: every role and account seen in events, plus the known
+    // roles for registry wallets
+    let mut roles: BTreeSet<B256> = names.keys().copied().collect();
+    let mut accounts: BTreeSet<Address> = wallets.keys().copied().collect();
+    let from_block = deployment.block_number.unwrap_or(0) as u64;
+    let events_error = match rpc::get_logs(
+        rpc_url,
+        address,
+        vec![
+            IAccessControl::RoleGranted::SIGNATURE_HASH,
+            IAccessControl::RoleRevoked::SIGNATURE_HASH,
+        ],
+        from_block,
+    )
+    .await
+    {
+        Ok(logs) => {
+            for log in logs {
+                if let [_, role, account, ..] = log.topics() {
+                    roles.insert(*role);
+                    accounts.insert(Address::from_word(*account));
+                }
+            }
+            None
+        }
+        Err(e) => Some(e.to_string()),
+    };
+
+    let mut report = Vec::new();
+    for role in roles {
+        let checks = accounts.iter().map(|&account| async move {
+            let has_role = rpc::call(
+                rpc_url,
+                address,
+                IAccessControl::hasRoleCall { role, account },
+            )
+            .await;
+            (account, has_role == Some(true))
+        });
+        let members: Vec<RoleMember> = join_all(checks)
+            .await
+            .into_iter()
+            .filter(|(_, has_role)| *has_role)
+            .map(|(account, _)| member(account))
+            .collect();
+        if members.is_empty() && !defined.contains(&role) {
+            continue;
+        }
+        let name = names.get(&role).cloned();
+        let admin_role = rpc::call(rpc_url, address, IAccessControl::getRoleAdminCall { role })
+            .await
+            .map(|admin| admin.to_string());
+        report.push(Role {
+            role: role.to_string(),
+            name,
+            admin_role,
+            members,
+        });
+    }
+
+    Ok(RoleReport {
+        owner,
+        roles: report,
+        events_error,
+    })
+}
+
+/// The default admin role and common OpenZeppelin roles, by hash
+fn known_roles() -> BTreeMap<B256, String> {
+    let mut names = BTreeMap::from([(B256::ZERO, "DEFAULT_ADMIN_ROLE".to_string())]);
+    for name in COMMON_ROLES {
+        names.insert(keccak256(name), name.to_string());
+    }
+    names
+}
+
+/// Names of the `*_ROLE` constant getters in an ABI
+fn role_getters(abi: &Abi) -> Vec<String> {
+    abi.functions()
+        .read
+        .into_iter()
+        .filter(|f| f.name.ends_with("_ROLE") && f.inputs.is_empty())
+        .map(|f| f.name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_roles() {
+        let names = known_roles();
+        assert_eq!(names[&B256::ZERO], "DEFAULT_ADMIN_ROLE");
+        assert_eq!(names[&keccak256("MINTER_ROLE")], "MINTER_ROLE");
+    }
+
+    #[test]
+    fn test_role_getters() {
+        let abi = Abi::parse(
+            r#"[
+                {"type":"function","name":"MINTER_ROLE","inputs":[],"outputs":[{"name":"","type":"bytes32"}],"stateMutability":"view"},
+                {"type":"function","name":"hasRole","inputs":[{"name":"role","type":"bytes32"},{"name":"account","type":"address"}],"outputs":[{"name":"","type":"bool"}],"stateMutability":"view"},
+                {"type":"function","name":"owner","inputs":[],"outputs":[{"name":"","type":"address"}],"stateMutability":"view"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(role_getters(&abi), vec!["MINTER_ROLE".to_string()]);
+    }
+}
@@ -1,11 +1,1103 @@
-use alloy::providers::{Provider, ProviderBuilder};
+use std::fmt::Display;
+use std::future::IntoFuture;
+use std::time::{Duration, Instant};
+
+use alloy::eips::Encodable2718;
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder, SendableTx};
+use alloy::rpc::json_rpc::ErrorPayload;
+use alloy::rpc::types::state::StateOverride;
+use alloy::rpc::types::{
+    BlockNumberOrTag, Filter, Log, Transaction, TransactionReceipt, TransactionRequest,
+};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolCall;
 use alloy::transports::http::reqwest::Url;
-use color_eyre::eyre::Result;
+use alloy::transports::RpcError;
+use async_trait::async_trait;
+use color_eyre::eyre::{eyre, Result};
+use serde::Serialize;
+use smolder_core::{format_value, scrub, Error, EvmAddress, TxHash};
+use smolder_db::Network;
+use tracing::Span;
+use utoipa::ToSchema;
+
+#[cfg(test)]
+pub mod fake;
+
+/// Span for RPC calls made against a network, so the latency events emitted
+/// here are tagged with the chain they went to
+pub fn span(chain_id: impl Display) -> Span {
+    tracing::info_span!("rpc", chain_id = %chain_id)
+}
+
+/// Await an RPC request, logging its method and latency
+async fn timed<T, E: Display>(
+    method: &'static str,
+    request: impl IntoFuture<Output = std::result::Result<T, E>>,
+) -> std::result::Result<T, E> {
+    let start = Instant::now();
+    let result = request.await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    match &result {
+        Ok(_) => tracing::debug!(method, latency_ms, "rpc call"),
+        Err(e) => tracing::warn!(method, latency_ms, error = %e, "rpc call failed"),
+    }
+
+    result
+}
 
 /// Fetch the chain ID from an RPC endpoint
 pub async fn get_chain_id(rpc_url: &str) -> Result<u64> {
     let url: Url = rpc_url.parse()?;
     let provider = ProviderBuilder::new().connect_http(url);
-    let chain_id = provider.get_chain_id().await?;
+    let chain_id = timed("eth_chainId", provider.get_chain_id()).await?;
     Ok(chain_id)
 }
+
+/// Fetch the latest block number from an RPC endpoint
+pub async fn get_block_number(rpc_url: &str) -> Result<u64> {
+    let url: Url = rpc_url.parse()?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    let block = timed("eth_blockNumber", provider.get_block_number()).await?;
+    Ok(block)
+}
+
+/// Fetch the balance and nonce of an address at the latest block
+pub async fn get_account(rpc_url: &str, address: Address) -> Result<(U256, u64)> {
+    let url: Url = rpc_url.parse()?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    let (balance, nonce) = tokio::try_join!(
+        timed("eth_getBalance", provider.get_balance(address)),
+        timed(
+            "eth_getTransactionCount",
+            provider.get_transaction_count(address)
+        ),
+    )?;
+    Ok((balance, nonce))
+}
+
+/// Fetch the logs `address` emitted with any of `events` as topic0, from
+/// `from_block` to the latest block, in chain order
+pub async fn get_logs(
+    rpc_url: &str,
+    address: Address,
+    events: Vec<B256>,
+    from_block: u64,
+) -> Result<Vec<Log>, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    let filter = Filter::new()
+        .address(address)
+        .event_signature(events)
+        .from_block(from_block);
+
+    timed("eth_getLogs", provider.get_logs(&filter))
+        .await
+        .map_err(|e| Error::Rpc(format!("Failed to fetch logs: {}", e)))
+}
+
+/// Fees of a chain at its latest block
+#[derive(Debug, Clone, Copy)]
+pub struct FeeSnapshot {
+    pub block_number: u64,
+    /// `None` on chains without EIP-1559
+    pub base_fee: Option<u64>,
+    pub gas_price: u128,
+}
+
+/// Fetch the base fee of the latest block and the current gas price
+pub async fn get_fees(rpc_url: &str) -> Result<FeeSnapshot> {
+    let url: Url = rpc_url.parse()?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    let block = timed(
+        "eth_getBlockByNumber",
+        provider.get_block_by_number(BlockNumberOrTag::Latest),
+    )
+    .await?
+    .ok_or_else(|| eyre!("node returned no latest block"))?;
+    let gas_price = timed("eth_gasPrice", provider.get_gas_price()).await?;
+    Ok(FeeSnapshot {
+        block_number: block.header.number,
+        base_fee: block.header.base_fee_per_gas,
+        gas_price,
+    })
+}
+
+/// Execute a read-only `eth_call` against a contract
+pub async fn execute_eth_call(rpc_url: &str, to: Address, data: Bytes) -> Result<Bytes, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let tx = TransactionRequest::default().to(to).input(data.into());
+
+    let result: Bytes = timed("eth_call", provider.call(tx))
+        .await
+        .map_err(|e| Error::Rpc(format!("RPC call failed: {}", e)))?;
+
+    Ok(result)
+}
+
+/// Longest wait for a getter called with [`call`]
+const GETTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Call a getter, treating reverts, timeouts and undecodable results as
+/// missing
+pub async fn call<C: SolCall>(rpc_url: &str, address: Address, call: C) -> Option<C::Return> {
+    let data = tokio::time::timeout(
+        GETTER_TIMEOUT,
+        execute_eth_call(rpc_url, address, Bytes::from(call.abi_encode())),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    C::abi_decode_returns(&data).ok()
+}
+
+/// Read a storage slot of a contract with `eth_getStorageAt`
+pub async fn get_storage_at(rpc_url: &str, address: Address, slot: B256) -> Result<B256, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let value = timed(
+        "eth_getStorageAt",
+        provider.get_storage_at(address, slot.into()),
+    )
+    .await
+    .map_err(|e| Error::Rpc(format!("Failed to read storage: {}", e)))?;
+
+    Ok(B256::from(value))
+}
+
+/// Sign and send a transaction, returning its hash without waiting for a receipt
+///
+/// With `relay_url` the transaction is filled and signed against `rpc_url`
+/// but submitted to that private relay only, so it never enters the public
+/// mempool.
+pub async fn execute_transaction(
+    rpc_url: &str,
+    relay_url: Option<&str>,
+    private_key: &str,
+    to: Address,
+    data: Bytes,
+    value: Option<U256>,
+) -> Result<TxHash, Error> {
+    let signer: PrivateKeySigner = private_key
+        .parse()
+        .map_err(|e| Error::invalid_param("private_key", format!("Invalid: {}", e)))?;
+
+    let wallet = EthereumWallet::from(signer);
+
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().wallet(wallet).connect_http(url);
+
+    let mut tx = TransactionRequest::default().to(to).input(data.into());
+
+    if let Some(v) = value {
+        tx = tx.value(v);
+    }
+
+    if let Some(relay_url) = relay_url {
+        let SendableTx::Envelope(envelope) = provider
+            .fill(tx)
+            .await
+            .map_err(|e| Error::TransactionFailed(format!("{}", e)))?
+        else {
+            return Err(Error::TransactionFailed(
+                "Transaction could not be signed".to_string(),
+            ));
+        };
+        let relay_url: reqwest::Url = relay_url.parse().map_err(|e| {
+            Error::invalid_param("private_relay", format!("Invalid relay URL: {}", e))
+        })?;
+        let relay = ProviderBuilder::new().connect_http(relay_url);
+        let pending = timed(
+            "eth_sendRawTransaction",
+            relay.send_raw_transaction(&envelope.encoded_2718()),
+        )
+        .await
+        .map_err(|e| Error::TransactionFailed(format!("Private relay: {}", e)))?;
+        return Ok(TxHash(*pending.tx_hash()));
+    }
+
+    let pending = timed("eth_sendTransaction", provider.send_transaction(tx))
+        .await
+        .map_err(|e| Error::TransactionFailed(format!("{}", e)))?;
+
+    Ok(TxHash(*pending.tx_hash()))
+}
+
+/// Send a transaction from `from` without its key, through anvil's
+/// `anvil_impersonateAccount`, returning its hash
+///
+/// Impersonation is stopped again once the transaction is submitted.
+pub async fn execute_impersonated(
+    rpc_url: &str,
+    from: Address,
+    to: Address,
+    data: Bytes,
+    value: Option<U256>,
+) -> Result<TxHash, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    timed(
+        "anvil_impersonateAccount",
+        provider.raw_request::<_, ()>("anvil_impersonateAccount".into(), (from,)),
+    )
+    .await
+    .map_err(|e| Error::Rpc(format!("Could not impersonate {}: {}", from, e)))?;
+
+    let mut tx = TransactionRequest::default()
+        .from(from)
+        .to(to)
+        .input(data.into());
+    if let Some(v) = value {
+        tx = tx.value(v);
+    }
+    let sent = timed("eth_sendTransaction", provider.send_transaction(tx)).await;
+
+    let _ = timed(
+        "anvil_stopImpersonatingAccount",
+        provider.raw_request::<_, ()>("anvil_stopImpersonatingAccount".into(), (from,)),
+    )
+    .await;
+
+    let pending = sent.map_err(|e| Error::TransactionFailed(format!("{}", e)))?;
+    Ok(TxHash(*pending.tx_hash()))
+}
+
+/// How often [`wait_for_receipt`] asks for the receipt
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Poll for a transaction's receipt until it is mined or `timeout` elapses
+pub async fn wait_for_receipt(
+    rpc_url: &str,
+    tx_hash: &TxHash,
+    timeout: Duration,
+) -> Result<TransactionReceipt, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    let hash = tx_hash.0;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let receipt = timed(
+            "eth_getTransactionReceipt",
+            provider.get_transaction_receipt(hash),
+        )
+        .await
+        .map_err(|e| Error::Rpc(format!("Failed to get transaction receipt: {}", e)))?;
+
+        if let Some(receipt) = receipt {
+            return Ok(receipt);
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Rpc(format!(
+                "Transaction {} not mined after {}s",
+                tx_hash,
+                timeout.as_secs()
+            )));
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
+
+/// Wait until the transaction of `receipt` is `confirmations` blocks deep,
+/// counting its own block, or `timeout` elapses
+///
+/// Returns the receipt read once deep enough, which differs from `receipt`
+/// when a reorg moved the transaction in the meantime.
+pub async fn wait_for_confirmations(
+    rpc_url: &str,
+    mut receipt: TransactionReceipt,
+    confirmations: u32,
+    timeout: Duration,
+) -> Result<TransactionReceipt, Error> {
+    let deadline = Instant::now() + timeout;
+    let tx_hash = TxHash(receipt.transaction_hash);
+    loop {
+        let Some(mined) = receipt.block_number else {
+            return Ok(receipt);
+        };
+        let latest = get_block_number(rpc_url)
+            .await
+            .map_err(|e| Error::Rpc(e.to_string()))?;
+        if latest + 1 >= mined + u64::from(confirmations) {
+            match get_receipt(rpc_url, &tx_hash).await? {
+                Some(current) if current.block_hash == receipt.block_hash => return Ok(current),
+                // Moved to another block, which must get as deep
+                Some(current) => {
+                    receipt = current;
+                    continue;
+                }
+                // Dropped by a reorg until it is mined again
+                None => {}
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Rpc(format!(
+                "Transaction {} not {} blocks deep after {}s",
+                tx_hash,
+                confirmations,
+                timeout.as_secs()
+            )));
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
+
+/// Fetch the transactions of block `number`, empty when the node doesn't
+/// have it yet
+pub async fn get_block_transactions(rpc_url: &str, number: u64) -> Result<Vec<Transaction>, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let block = timed(
+        "eth_getBlockByNumber",
+        provider.get_block_by_number(number.into()).full(),
+    )
+    .await
+    .map_err(|e| Error::Rpc(format!("Failed to get block {}: {}", number, e)))?;
+    Ok(block
+        .map(|block| block.transactions.into_transactions().collect())
+        .unwrap_or_default())
+}
+
+/// Fetch the hash of block `number`, `None` when the node doesn't have it yet
+pub async fn get_block_hash(rpc_url: &str, number: u64) -> Result<Option<B256>, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let block = timed(
+        "eth_getBlockByNumber",
+        provider.get_block_by_number(number.into()),
+    )
+    .await
+    .map_err(|e| Error::Rpc(format!("Failed to get block {}: {}", number, e)))?;
+    Ok(block.map(|block| block.header.hash))
+}
+
+/// Fetch the number of the latest finalized block, `None` when the node
+/// doesn't track finality
+pub async fn get_finalized_block(rpc_url: &str) -> Result<Option<u64>, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let block = timed(
+        "eth_getBlockByNumber",
+        provider.get_block_by_number(BlockNumberOrTag::Finalized),
+    )
+    .await
+    .map_err(|e| Error::Rpc(format!("Failed to get finalized block: {}", e)))?;
+    Ok(block.map(|block| block.header.number))
+}
+
+/// Send a JSON-RPC request as is, for the server's read proxy
+///
+/// Errors come back as JSON-RPC error objects: the node's own, or an
+/// internal error when it couldn't be reached.
+pub async fn forward_request(
+    rpc_url: &str,
+    method: &str,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, ErrorPayload> {
+    let internal =
+        |message: String| ErrorPayload::internal_error_message(scrub(&message).into_owned().into());
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| internal(format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    let params = serde_json::value::to_raw_value(params).map_err(|e| internal(e.to_string()))?;
+
+    let result = timed(
+        "rpc proxy",
+        provider.raw_request_dyn(method.to_string().into(), &params),
+    )
+    .await
+    .map_err(|e| match e {
+        RpcError::ErrorResp(payload) => payload,
+        e => internal(format!("RPC request failed: {}", e)),
+    })?;
+    serde_json::from_str(result.get()).map_err(|e| internal(e.to_string()))
+}
+
+/// Fetch the receipt of a transaction, `None` until it is mined
+pub async fn get_receipt(
+    rpc_url: &str,
+    tx_hash: &TxHash,
+) -> Result<Option<TransactionReceipt>, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    timed(
+        "eth_getTransactionReceipt",
+        provider.get_transaction_receipt(tx_hash.0),
+    )
+    .await
+    .map_err(|e| Error::Rpc(format!("Failed to get transaction receipt: {}", e)))
+}
+
+/// Fetch a transaction and, once it is mined, its receipt
+///
+/// `None` when the node doesn't know the transaction.
+pub async fn get_transaction(
+    rpc_url: &str,
+    tx_hash: &TxHash,
+) -> Result<Option<(Transaction, Option<TransactionReceipt>)>, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let transaction = timed(
+        "eth_getTransactionByHash",
+        provider.get_transaction_by_hash(tx_hash.0),
+    )
+    .await
+    .map_err(|e| Error::Rpc(format!("Failed to get transaction: {}", e)))?;
+    let Some(transaction) = transaction else {
+        return Ok(None);
+    };
+    let receipt = get_receipt(rpc_url, tx_hash).await?;
+    Ok(Some((transaction, receipt)))
+}
+
+/// A mined contract creation transaction
+#[derive(Debug, Clone)]
+pub struct CreationReceipt {
+    pub tx_hash: TxHash,
+    /// Block the transaction was mined in
+    pub block_number: Option<u64>,
+    /// Address of the deployed contract, if the receipt has one
+    pub contract_address: Option<EvmAddress>,
+    pub gas_used: u64,
+    /// Effective gas price, in wei
+    pub gas_price: u128,
+}
+
+/// Send a contract creation transaction and wait for its receipt
+pub async fn deploy_contract(
+    rpc_url: &str,
+    private_key: &str,
+    data: Bytes,
+    value: Option<U256>,
+) -> Result<CreationReceipt, Error> {
+    let signer: PrivateKeySigner = private_key
+        .parse()
+        .map_err(|e| Error::invalid_param("private_key", format!("Invalid: {}", e)))?;
+
+    let wallet = EthereumWallet::from(signer);
+
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().wallet(wallet).connect_http(url);
+
+    // CREATE transaction - use with_deploy_code to properly mark as deployment
+    let mut tx = TransactionRequest::default().with_deploy_code(data);
+
+    if let Some(v) = value {
+        tx = tx.value(v);
+    }
+
+    let pending = timed("eth_sendTransaction", provider.send_transaction(tx))
+        .await
+        .map_err(|e| Error::TransactionFailed(format!("Failed to send deployment: {}", e)))?;
+
+    let tx_hash = TxHash(*pending.tx_hash());
+
+    // Wait for receipt to get contract address
+    let receipt = timed("eth_getTransactionReceipt", pending.get_receipt())
+        .await
+        .map_err(|e| Error::Rpc(format!("Failed to get transaction receipt: {}", e)))?;
+
+    Ok(CreationReceipt {
+        tx_hash,
+        block_number: receipt.block_number,
+        contract_address: receipt.contract_address.map(EvmAddress::from),
+        gas_used: receipt.gas_used,
+        gas_price: receipt.effective_gas_price,
+    })
+}
+
+/// A transaction about to be sent
+pub struct Outgoing<'a> {
+    pub from: Address,
+    /// `None` for a contract creation
+    pub to: Option<Address>,
+    pub data: &'a Bytes,
+    pub value: Option<U256>,
+}
+
+/// What [`preflight`] found out about a transaction before it is sent
+#[derive(Debug, Clone, Default)]
+pub struct Preflight {
+    /// Estimated gas, unless the estimate failed
+    pub gas: Option<u64>,
+    pub gas_price: u128,
+    pub balance: U256,
+    pub warnings: Vec<PreflightWarning>,
+}
+
+impl Preflight {
+    /// Estimated cost of gas and value, in wei
+    pub fn cost(&self, value: Option<U256>) -> Option<U256> {
+        let gas = U256::from(self.gas?) * U256::from(self.gas_price);
+        Some(gas + value.unwrap_or_default())
+    }
+}
+
+/// Something worth knowing about a transaction that doesn't stop it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PreflightWarning {
+    pub kind: PreflightWarningKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightWarningKind {
+    /// Earlier transactions from the sender are not mined yet, so this one
+    /// waits behind them
+    PendingTransactions,
+    /// The gas could not be estimated, so the balance was not checked
+    GasNotEstimated,
+}
+
+/// Check a transaction against the chain before it is sent
+///
+/// Fails when the RPC serves another chain than the network was registered
+/// with, or when the sender can't pay for the estimated gas and the value.
+/// Transactions still pending from the sender and failed gas estimates are
+/// returned as warnings.
+pub async fn preflight(network: &Network, tx: Outgoing<'_>) -> Result<Preflight, Error> {
+    let url: reqwest::Url = network
+        .rpc_url
+        .expose()
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let actual = timed("eth_chainId", provider.get_chain_id())
+        .await
+        .map_err(|e| Error::Rpc(format!("Failed to get chain ID: {}", e)))?;
+    if actual != u64::from(network.chain_id) {
+        return Err(Error::ChainIdMismatch {
+            network: network.name.clone(),
+            expected: network.chain_id.into(),
+            actual,
+        });
+    }
+
+    let mut preflight = Preflight::default();
+    let (latest, pending) = tokio::try_join!(
+        timed(
+            "eth_getTransactionCount",
+            provider.get_transaction_count(tx.from).latest()
+        ),
+        timed(
+            "eth_getTransactionCount",
+            provider.get_transaction_count(tx.from).pending()
+        ),
+    )
+    .map_err(|e| Error::Rpc(format!("Failed to get nonce: {}", e)))?;
+    if pending > latest {
+        preflight.warnings.push(PreflightWarning {
+            kind: PreflightWarningKind::PendingTransactions,
+            message: format!(
+                "{} transaction(s) from {} are still pending; this one waits until they are mined",
+                pending - latest,
+                tx.from
+            ),
+        });
+    }
+
+    let mut request = TransactionRequest::default()
+        .from(tx.from)
+        .input(tx.data.clone().into());
+    request = match tx.to {
+        Some(to) => request.to(to),
+        None => request.into_create(),
+    };
+    if let Some(v) = tx.value {
+        request = request.value(v);
+    }
+    let (balance, gas_price, gas) = tokio::join!(
+        timed("eth_getBalance", provider.get_balance(tx.from)),
+        timed("eth_gasPrice", provider.get_gas_price()),
+        timed("eth_estimateGas", provider.estimate_gas(request)),
+    );
+    preflight.balance = balance.map_err(|e| Error::Rpc(format!("Failed to get balance: {}", e)))?;
+    preflight.gas_price =
+        gas_price.map_err(|e| Error::Rpc(format!("Failed to get gas price: {}", e)))?;
+    match gas {
+        Ok(gas) => preflight.gas = Some(gas),
+        Err(e) => preflight.warnings.push(PreflightWarning {
+            kind: PreflightWarningKind::GasNotEstimated,
+            message: format!("Gas estimation failed: {}", e),
+        }),
+    }
+
+    if let Some(required) = preflight.cost(tx.value) {
+        if preflight.balance < required {
+            return Err(Error::InsufficientFunds {
+                address: tx.from.to_string(),
+                balance: format_value(preflight.balance, network.currency()),
+                required: format_value(required, network.currency()),
+            });
+        }
+    }
+    Ok(preflight)
+}
+
+/// A log a simulated transaction would emit
+#[derive(Debug, Clone)]
+pub struct SimulatedLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+/// What a transaction would do if it were sent now
+#[derive(Debug, Clone, Default)]
+pub struct Simulation {
+    pub success: bool,
+    /// Return data, or revert data when it fails
+    pub output: Bytes,
+    /// Unknown when the transaction reverts on a node without `debug_traceCall`
+    pub gas_used: Option<u64>,
+    /// Revert reason or error message
+    pub error: Option<String>,
+    /// Logs in emission order; only known from `debug_traceCall`
+    pub logs: Vec<SimulatedLog>,
+    /// Call tree from geth's `callTracer`
+    pub trace: Option<serde_json::Value>,
+    /// Address a contract creation would deploy to
+    pub contract_address: Option<Address>,
+    /// Storage and balance changes; only known from Tenderly
+    pub state_diff: Option<serde_json::Value>,
+    /// Shareable link to the simulation; only known from Tenderly
+    pub url: Option<String>,
+}
+
+/// Run a transaction (a creation when `to` is `None`) against the latest
+/// block without broadcasting it
+///
+/// Uses `debug_traceCall` with the call tracer, which also yields the logs and
+/// the call tree, and falls back to `eth_call` and `eth_estimateGas` on nodes
+/// that don't support it. `state_overrides` is a geth state override set,
+/// keyed by address.
+pub async fn simulate(
+    rpc_url: &str,
+    from: Address,
+    to: Option<Address>,
+    data: Bytes,
+    value: Option<U256>,
+    state_overrides: Option<&serde_json::Value>,
+) -> Result<Simulation, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let mut tx = TransactionRequest::default().from(from);
+    tx = match to {
+        Some(to) => tx.to(to).input(data.into()),
+        None => tx.with_deploy_code(data),
+    };
+    if let Some(v) = value {
+        tx = tx.value(v);
+    }
+
+    let mut config = serde_json::json!({
+        "tracer": "callTracer",
+        "tracerConfig": { "withLog": true },
+    });
+    if let Some(overrides) = state_overrides {
+        config["stateOverrides"] = overrides.clone();
+    }
+    let traced = timed(
+        "debug_traceCall",
+        provider.raw_request::<_, serde_json::Value>(
+            "debug_traceCall".into(),
+            (&tx, "latest", &config),
+        ),
+    )
+    .await;
+    match traced {
+        Ok(frame) => return Ok(simulation_from_trace(frame)),
+        // Most public RPCs don't expose the debug namespace
+        Err(RpcError::ErrorResp(_)) => {}
+        Err(e) => return Err(Error::Rpc(format!("Simulation failed: {}", e))),
+    }
+
+    let overrides: Option<StateOverride> = state_overrides
+        .map(|o| serde_json::from_value(o.clone()))
+        .transpose()
+        .map_err(|e| Error::invalid_param("state_overrides", format!("{}", e)))?;
+    let contract_address = match to {
+        Some(_) => None,
+        None => {
+            let nonce = timed(
+                "eth_getTransactionCount",
+                provider.get_transaction_count(from),
+            )
+            .await
+            .map_err(|e| Error::Rpc(format!("Failed to get nonce: {}", e)))?;
+            Some(from.create(nonce))
+        }
+    };
+
+    let mut call = provider.call(tx.clone());
+    let mut estimate = provider.estimate_gas(tx);
+    if let Some(overrides) = overrides {
+        call = call.overrides(overrides.clone());
+        estimate = estimate.overrides(overrides);
+    }
+
+    match timed("eth_call", call).await {
+        Ok(output) => Ok(Simulation {
+            success: true,
+            output,
+            gas_used: timed("eth_estimateGas", estimate).await.ok(),
+            contract_address,
+            ..Default::default()
+        }),
+        Err(RpcError::ErrorResp(e)) => Ok(Simulation {
+            success: false,
+            output: e.as_revert_data().unwrap_or_default(),
+            error: Some(e.message.to_string()),
+            contract_address,
+            ..Default::default()
+        }),
+        Err(e) => Err(Error::Rpc(format!("Simulation failed: {}", e))),
+    }
+}
+
+/// Read a `callTracer` frame with logs
+fn simulation_from_trace(frame: serde_json::Value) -> Simulation {
+    let error = frame["error"]
+        .as_str()
+        .map(|e| match frame["revertReason"].as_str() {
+            Some(reason) => format!("{}: {}", e, reason),
+            None => e.to_string(),
+        });
+    let is_create = matches!(frame["type"].as_str(), Some("CREATE" | "CREATE2"));
+
+    let mut logs = Vec::new();
+    collect_logs(&frame, &mut logs);
+
+    Simulation {
+        success: error.is_none(),
+        output: frame["output"]
+            .as_str()
+            .and_then(|o| o.parse().ok())
+            .unwrap_or_default(),
+        gas_used: frame["gasUsed"].as_str().and_then(parse_quantity),
+        error,
+        logs,
+        contract_address: is_create
+            .then(|| frame["to"].as_str()?.parse().ok())
+            .flatten(),
+        trace: Some(frame),
+        ..Default::default()
+    }
+}
+
+/// Append the logs of `frame` and of the calls it made, in emission order,
+/// leaving out calls that reverted
+fn collect_logs(frame: &serde_json::Value, logs: &mut Vec<SimulatedLog>) {
+    if frame["error"].is_string() {
+        return;
+    }
+    let calls = frame["calls"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let mut next_call = 0;
+
+    for log in frame["logs"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+        // `position` counts the calls made before the log was emitted
+        let position = match &log["position"] {
+            serde_json::Value::String(p) => parse_quantity(p).unwrap_or(0) as usize,
+            p => p.as_u64().unwrap_or(0) as usize,
+        };
+        while next_call < position.min(calls.len()) {
+            collect_logs(&calls[next_call], logs);
+            next_call += 1;
+        }
+
+        let address = log["address"].as_str().and_then(|a| a.parse().ok());
+        let topics = log["topics"]
+            .as_array()
+            .map(|t| t.iter().filter_map(|t| t.as_str()?.parse().ok()).collect());
+        let data = log["data"].as_str().and_then(|d| d.parse().ok());
+        if let (Some(address), Some(topics), Some(data)) = (address, topics, data) {
+            logs.push(SimulatedLog {
+                address,
+                topics,
+                data,
+            });
+        }
+    }
+    for call in &calls[next_call..] {
+        collect_logs(call, logs);
+    }
+}
+
+/// Call tree of a mined transaction, from `debug_traceTransaction` with geth's
+/// `callTracer`
+///
+/// `None` when the node doesn't serve the debug namespace or has pruned the
+/// state the trace needs.
+pub async fn trace_transaction(
+    rpc_url: &str,
+    tx_hash: &TxHash,
+) -> Result<Option<serde_json::Value>, Error> {
+    let url: reqwest::Url = rpc_url
+        .parse()
+        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+
+    let config = serde_json::json!({ "tracer": "callTracer" });
+    let traced = timed(
+        "debug_traceTransaction",
+        provider.raw_request::<_, serde_json::Value>(
+            "debug_traceTransaction".into(),
+            (tx_hash.0, &config),
+        ),
+    )
+    .await;
+    match traced {
+        Ok(frame) => Ok(Some(frame)),
+        Err(RpcError::ErrorResp(_)) => Ok(None),
+        Err(e) => Err(Error::Rpc(format!("Failed to trace {}: {}", tx_hash, e))),
+    }
+}
+
+/// The node reads the server makes, so handlers can be tested against a fake
+/// node instead of a live one
+///
+/// Transactions, simulations and traces still go through the functions of
+/// this module.
+#[async_trait]
+pub trait RpcClient: Send + Sync {
+    /// Fetch the chain ID of the node
+    async fn chain_id(&self, rpc_url: &str) -> Result<u64>;
+
+    /// Fetch the latest block number
+    async fn block_number(&self, rpc_url: &str) -> Result<u64>;
+
+    /// Fetch the fees of the latest block
+    async fn fees(&self, rpc_url: &str) -> Result<FeeSnapshot>;
+
+    /// Execute a read-only `eth_call`
+    async fn call(&self, rpc_url: &str, to: Address, data: Bytes) -> Result<Bytes, Error>;
+
+    /// Read a storage slot of a contract
+    async fn storage_at(&self, rpc_url: &str, address: Address, slot: B256) -> Result<B256, Error>;
+
+    /// Fetch the transactions of a block; empty when the node doesn't have
+    /// it yet
+    async fn block_transactions(
+        &self,
+        rpc_url: &str,
+        number: u64,
+    ) -> Result<Vec<Transaction>, Error>;
+
+    /// Fetch the receipt of a transaction, `None` until it is mined
+    async fn receipt(
+        &self,
+        rpc_url: &str,
+        tx_hash: &TxHash,
+    ) -> Result<Option<TransactionReceipt>, Error>;
+
+    /// Fetch the hash of a block, `None` when the node doesn't have it yet
+    async fn block_hash(&self, rpc_url: &str, number: u64) -> Result<Option<B256>, Error>;
+
+    /// Fetch the number of the latest finalized block, `None` when the node
+    /// doesn't track finality
+    async fn finalized_block(&self, rpc_url: &str) -> Result<Option<u64>, Error>;
+
+    /// Send a JSON-RPC request as is, see [`forward_request`]
+    async fn forward(
+        &self,
+        rpc_url: &str,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value, ErrorPayload>;
+}
+
+/// [`RpcClient`] sending JSON-RPC requests over HTTP
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpRpcClient;
+
+#[async_trait]
+impl RpcClient for HttpRpcClient {
+    async fn chain_id(&self, rpc_url: &str) -> Result<u64> {
+        get_chain_id(rpc_url).await
+    }
+
+    async fn block_number(&self, rpc_url: &str) -> Result<u64> {
+        get_block_number(rpc_url).await
+    }
+
+    async fn fees(&self, rpc_url: &str) -> Result<FeeSnapshot> {
+        get_fees(rpc_url).await
+    }
+
+    async fn call(&self, rpc_url: &str, to: Address, data: Bytes) -> Result<Bytes, Error> {
+        execute_eth_call(rpc_url, to, data).await
+    }
+
+    async fn storage_at(&self, rpc_url: &str, address: Address, slot: B256) -> Result<B256, Error> {
+        get_storage_at(rpc_url, address, slot).await
+    }
+
+    async fn block_transactions(
+        &self,
+        rpc_url: &str,
+        number: u64,
+    ) -> Result<Vec<Transaction>, Error> {
+        get_block_transactions(rpc_url, number).await
+    }
+
+    async fn receipt(
+        &self,
+        rpc_url: &str,
+        tx_hash: &TxHash,
+    ) -> Result<Option<TransactionReceipt>, Error> {
+        get_receipt(rpc_url, tx_hash).await
+    }
+
+    async fn block_hash(&self, rpc_url: &str, number: u64) -> Result<Option<B256>, Error> {
+        get_block_hash(rpc_url, number).await
+    }
+
+    async fn finalized_block(&self, rpc_url: &str) -> Result<Option<u64>, Error> {
+        get_finalized_block(rpc_url).await
+    }
+
+    async fn forward(
+        &self,
+        rpc_url: &str,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value, ErrorPayload> {
+        forward_request(rpc_url, method, params).await
+    }
+}
+
+/// Parse a hex (`0x`-prefixed) or decimal JSON-RPC quantity
+pub fn parse_quantity(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preflight_cost() {
+        let preflight = Preflight {
+            gas: Some(21_000),
+            gas_price: 2_000_000_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            preflight.cost(Some(U256::from(1_000u64))),
+            Some(U256::from(42_000_000_001_000u64))
+        );
+        assert_eq!(
+            preflight.cost(None),
+            Some(U256::from(42_000_000_000_000u64))
+        );
+
+        // Without an estimate, nothing is known about the cost
+        assert_eq!(Preflight::default().cost(None), None);
+    }
+
+    #[test]
+    fn test_simulation_from_trace() {
+        let log = |address: &str, position: &str| {
+            serde_json::json!({
+                "address": address,
+                "topics": ["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"],
+                "data": "0x01",
+                "position": position,
+            })
+        };
+        let a = "0x000000000000000000000000000000000000000a";
+        let b = "0x000000000000000000000000000000000000000b";
+        let c = "0x000000000000000000000000000000000000000c";
+        let frame = serde_json::json!({
+            "type": "CALL",
+            "from": "0x00000000000000000000000000000000000000ff",
+            "to": a,
+            "gasUsed": "0x5208",
+            "output": "0x2a",
+            "logs": [log(a, "0x0"), log(a, "0x2")],
+            "calls": [
+                { "type": "CALL", "to": b, "logs": [log(b, "0x0")] },
+                { "type": "CALL", "to": c, "error": "execution reverted", "logs": [log(c, "0x0")] },
+            ],
+        });
+
+        let simulation = simulation_from_trace(frame);
+        assert!(simulation.success);
+        assert_eq!(simulation.gas_used, Some(21_000));
+        assert_eq!(simulation.output, Bytes::from(vec![0x2a]));
+        assert!(simulation.contract_address.is_none());
+
+        // Logs follow emission order, without those of the reverted call
+        let emitters: Vec<String> = simulation
+            .logs
+            .iter()
+            .map(|l| l.address.to_string().to_lowercase())
+            .collect();
+        assert_eq!(emitters, vec![a, b, a]);
+    }
+
+    #[test]
+    fn test_simulation_from_reverted_creation() {
+        let frame = serde_json::json!({
+            "type": "CREATE",
+            "to": "0x000000000000000000000000000000000000000a",
+            "gasUsed": "0x100",
+            "error": "execution reverted",
+            "revertReason": "not allowed",
+            "logs": [],
+        });
+
+        let simulation = simulation_from_trace(frame);
+        assert!(!simulation.success);
+        assert_eq!(
+            simulation.error.as_deref(),
+            Some("execution reverted: not allowed")
+        );
+        assert!(simulation.contract_address.is_some());
+    }
+}
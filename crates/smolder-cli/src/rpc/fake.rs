@@ -0,0 +1,173 @@
+//! [`RpcClient`] answering from fixed data, so server tests run without a
+//! node
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use alloy::primitives::{Address, Bytes, B256};
+use alloy::rpc::json_rpc::ErrorPayload;
+use alloy::rpc::types::{Transaction, TransactionReceipt};
+use async_trait::async_trait;
+use color_eyre::eyre::{eyre, Result};
+use smolder_core::{Error, TxHash};
+
+use super::{FeeSnapshot, RpcClient};
+
+/// Node of chain `chain_id` at block `latest`
+///
+/// Out of the box, blocks are empty and unknown to [`RpcClient::block_hash`],
+/// transactions have no receipt and the node doesn't track finality. Calls
+/// return nothing and storage reads echo the slot read, so tests can tell
+/// slots apart. [`RpcClient::forward`] answers `eth_chainId`, `null` for
+/// blocks, reverts `eth_call` and echoes the params of anything else.
+pub struct FakeRpc {
+    chain_id: u64,
+    latest: AtomicU64,
+    /// How far the finalized block trails the latest one
+    finality_depth: Option<u64>,
+    blocks: HashMap<u64, Vec<Transaction>>,
+    /// Fields of the receipt of every transaction
+    receipt: Option<serde_json::Value>,
+    block_hash: Option<fn(u64) -> B256>,
+    forwarded: AtomicUsize,
+}
+
+impl FakeRpc {
+    pub fn new(chain_id: u64, latest: u64) -> Self {
+        Self {
+            chain_id,
+            latest: AtomicU64::new(latest),
+            finality_depth: None,
+            blocks: HashMap::new(),
+            receipt: None,
+            block_hash: None,
+            forwarded: AtomicUsize::new(0),
+        }
+    }
+
+    /// Finalize blocks `depth` below the latest one
+    pub fn with_finality_depth(mut self, depth: u64) -> Self {
+        self.finality_depth = Some(depth);
+        self
+    }
+
+    /// Put `transactions` in block `number`
+    pub fn with_block(mut self, number: u64, transactions: Vec<Transaction>) -> Self {
+        self.blocks.insert(number, transactions);
+        self
+    }
+
+    /// Mine every transaction, with a receipt made of `fields` over a
+    /// successful legacy transfer
+    pub fn with_receipt(mut self, fields: serde_json::Value) -> Self {
+        self.receipt = Some(fields);
+        self
+    }
+
+    /// Hash blocks with `hash`
+    pub fn with_block_hashes(mut self, hash: fn(u64) -> B256) -> Self {
+        self.block_hash = Some(hash);
+        self
+    }
+
+    /// Move the chain head to `block`
+    pub fn set_latest(&self, block: u64) {
+        self.latest.store(block, Ordering::SeqCst);
+    }
+
+    /// Requests sent through [`RpcClient::forward`]
+    pub fn forwarded(&self) -> usize {
+        self.forwarded.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl RpcClient for FakeRpc {
+    async fn chain_id(&self, _rpc_url: &str) -> Result<u64> {
+        Ok(self.chain_id)
+    }
+
+    async fn block_number(&self, _rpc_url: &str) -> Result<u64> {
+        Ok(self.latest.load(Ordering::SeqCst))
+    }
+
+    async fn fees(&self, _rpc_url: &str) -> Result<FeeSnapshot> {
+        Err(eyre!("no fees"))
+    }
+
+    async fn call(&self, _rpc_url: &str, _to: Address, _data: Bytes) -> Result<Bytes, Error> {
+        Ok(Bytes::new())
+    }
+
+    async fn storage_at(
+        &self,
+        _rpc_url: &str,
+        _address: Address,
+        slot: B256,
+    ) -> Result<B256, Error> {
+        Ok(slot)
+    }
+
+    async fn block_transactions(
+        &self,
+        _rpc_url: &str,
+        number: u64,
+    ) -> Result<Vec<Transaction>, Error> {
+        Ok(self.blocks.get(&number).cloned().unwrap_or_default())
+    }
+
+    async fn receipt(
+        &self,
+        _rpc_url: &str,
+        tx_hash: &TxHash,
+    ) -> Result<Option<TransactionReceipt>, Error> {
+        let Some(fields) = &self.receipt else {
+            return Ok(None);
+        };
+        let mut receipt = serde_json::json!({
+            "type": "0x0",
+            "status": "0x1",
+            "cumulativeGasUsed": "0x5208",
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "gasUsed": "0x5208",
+            "contractAddress": null,
+        });
+        if let (Some(receipt), Some(fields)) = (receipt.as_object_mut(), fields.as_object()) {
+            receipt.extend(fields.clone());
+        }
+        Ok(Some(serde_json::from_value(receipt).unwrap()))
+    }
+
+    async fn block_hash(&self, _rpc_url: &str, number: u64) -> Result<Option<B256>, Error> {
+        Ok(self.block_hash.map(|hash| hash(number)))
+    }
+
+    async fn finalized_block(&self, _rpc_url: &str) -> Result<Option<u64>, Error> {
+        let latest = self.latest.load(Ordering::SeqCst);
+        Ok(self
+            .finality_depth
+            .map(|depth| latest.saturating_sub(depth)))
+    }
+
+    async fn forward(
+        &self,
+        _rpc_url: &str,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value, ErrorPayload> {
+        self.forwarded.fetch_add(1, Ordering::SeqCst);
+        match method {
+            "eth_chainId" => Ok(serde_json::json!(format!("{:#x}", self.chain_id))),
+            "eth_getBlockByNumber" => Ok(serde_json::Value::Null),
+            "eth_call" => Err(ErrorPayload {
+                code: 3,
+                message: "execution reverted".into(),
+                data: None,
+            }),
+            _ => Ok(params.clone()),
+        }
+    }
+}
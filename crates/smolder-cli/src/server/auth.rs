@@ -0,0 +1,162 @@
+//! API tokens and roles
+//!
+//! The API stays open until the first token is created with `smolder token
+//! create`. From then on every `/api` and `/rpc` request but the health
+//! check must carry one as `Authorization: Bearer <token>`. Only a hash of
+//! each token is stored.
+//!
+//! Each router declares the role its writes need with [`require`]; reads
+//! only need a token. `create_router` requires an operator for every write,
+//! so routes without a declaration of their own are never open to viewers.
+
+use alloy::primitives::keccak256;
+use axum::{
+    extract::{Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::Response,
+};
+use smolder_db::{ApiRole, ApiTokenId};
+
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+/// Prefix of generated tokens, so they are easy to spot in config and logs
+pub const TOKEN_PREFIX: &str = "smd_";
+
+/// Paths under `/api` answered without a token, for load balancers, uptime
+/// checks and the dashboard's version check
+const PUBLIC_PATHS: [&str; 2] = ["/api/health", "/api/version"];
+
+/// The token a request was authenticated with, set as a request extension
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub token_id: ApiTokenId,
+    pub name: String,
+    pub role: ApiRole,
+}
+
+impl Caller {
+    /// Fail unless the token has at least `role`
+    pub fn require(&self, role: ApiRole, action: &str) -> Result<(), ApiError> {
+        match self.role >= role {
+            true => Ok(()),
+            false => Err(ApiError::forbidden(format!(
+                "Token '{}' has the {} role; {} needs {}",
+                self.name, self.role, action, role
+            ))),
+        }
+    }
+}
+
+/// Generate a new random token
+pub fn generate_token() -> String {
+    format!(
+        "{}{}",
+        TOKEN_PREFIX,
+        hex::encode(rand::random::<[u8; 32]>())
+    )
+}
+
+/// Hash a token for storage and lookup
+pub fn hash_token(token: &str) -> String {
+    keccak256(token.as_bytes()).to_string()
+}
+
+/// Check the bearer token of `/api` and `/rpc` requests, adding the [`Caller`] to the
+/// request when there is one
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let path = request.uri().path();
+    let guarded = path.starts_with("/api/") || path.starts_with("/rpc/");
+    if !guarded || PUBLIC_PATHS.contains(&path) {
+        return Ok(next.run(request).await);
+    }
+
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.strip_prefix("Bearer ").unwrap_or(v).trim().to_string());
+
+    match bearer {
+        Some(token) => {
+            let token = state
+                .repos()
+                .api_tokens()
+                .authenticate(&hash_token(&token))
+                .await?
+                .ok_or_else(|| ApiError::unauthorized("Invalid API token"))?;
+            request.extensions_mut().insert(Caller {
+                token_id: token.id,
+                name: token.name,
+                role: token.role,
+            });
+        }
+        None if state.repos().api_tokens().count().await? > 0 => {
+            return Err(ApiError::unauthorized(
+                "Missing API token; send it as `Authorization: Bearer <token>`",
+            ));
+        }
+        None => {}
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Require the role in the state for writes to the routes this is layered
+/// on, and a token of any role for reads
+///
+/// Declared per router with
+/// `.route_layer(middleware::from_fn_with_state(ApiRole::Admin, auth::require))`.
+/// Requests pass when the API is open (no [`Caller`]), since
+/// [`authenticate`] has already turned away anonymous requests otherwise.
+pub async fn require(
+    State(writes): State<ApiRole>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if let Some(caller) = request.extensions().get::<Caller>() {
+        match *request.method() {
+            Method::GET | Method::HEAD | Method::OPTIONS => {}
+            _ => caller.require(writes, "this request")?,
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token() {
+        let token = generate_token();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(token.len(), TOKEN_PREFIX.len() + 64);
+        assert_ne!(token, generate_token());
+        assert_eq!(hash_token(&token), hash_token(&token));
+    }
+
+    #[test]
+    fn test_caller_require() {
+        let caller = Caller {
+            token_id: ApiTokenId(1),
+            name: "ci".to_string(),
+            role: ApiRole::Operator,
+        };
+        assert!(caller.require(ApiRole::Viewer, "reading").is_ok());
+        assert!(caller.require(ApiRole::Operator, "sending").is_ok());
+        let err = caller
+            .require(ApiRole::Admin, "adding wallets")
+            .unwrap_err();
+        assert_eq!(err.code, "FORBIDDEN");
+        assert_eq!(
+            err.message,
+            "Token 'ci' has the operator role; adding wallets needs admin"
+        );
+    }
+}
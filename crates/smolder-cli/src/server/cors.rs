@@ -0,0 +1,286 @@
+//! Cross-origin access to the API
+//!
+//! Browsers only let pages from the origins in the [`CorsPolicy`] read API
+//! responses: by default that is pages served from localhost, such as the
+//! dashboard's dev server. CORS alone doesn't stop a page from sending a
+//! simple cross-site `POST`, so [`csrf`] also turns away writes whose
+//! `Origin` is neither the server itself nor an allowed origin.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Uri},
+    middleware::Next,
+    response::Response,
+};
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
+
+use crate::server::error::ApiError;
+use crate::server::{idempotency, logging, routes, AppState};
+
+/// Origin value that allows every origin
+pub const ANY_ORIGIN: &str = "*";
+
+/// Header browsers set to tell same-site requests from cross-site ones
+const FETCH_SITE_HEADER: HeaderName = HeaderName::from_static("sec-fetch-site");
+
+/// Header a proxy names the host the client asked for in
+const FORWARDED_HOST_HEADER: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// Origins whose pages may call the API
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AllowedOrigins {
+    /// `localhost`, `127.0.0.1` and `[::1]` on any port
+    #[default]
+    Localhost,
+    /// Exactly these `scheme://host[:port]` origins
+    List(Vec<String>),
+    /// Every origin
+    Any,
+}
+
+/// Which browser pages may call the API, and whether with credentials
+#[derive(Debug, Clone, Default)]
+pub struct CorsPolicy {
+    origins: AllowedOrigins,
+    allow_credentials: bool,
+}
+
+impl CorsPolicy {
+    /// Allow `origins`, or localhost only when empty; `*` allows any origin
+    /// but can't be combined with credentials
+    pub fn new(origins: &[String], allow_credentials: bool) -> Result<Self, String> {
+        let origins = match origins {
+            [] => AllowedOrigins::Localhost,
+            [any] if any == ANY_ORIGIN => {
+                if allow_credentials {
+                    return Err(format!(
+                        "CORS origin '{}' can't be combined with credentials; list the origins instead",
+                        ANY_ORIGIN
+                    ));
+                }
+                AllowedOrigins::Any
+            }
+            origins => AllowedOrigins::List(
+                origins
+                    .iter()
+                    .map(|origin| match origin.as_str() {
+                        ANY_ORIGIN => Err(format!(
+                            "CORS origin '{}' must be the only origin",
+                            ANY_ORIGIN
+                        )),
+                        origin => normalize_origin(origin)
+                            .ok_or_else(|| format!("Invalid CORS origin '{}'", origin)),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        Ok(Self {
+            origins,
+            allow_credentials,
+        })
+    }
+
+    /// Get the origins whose pages may call the API
+    pub fn origins(&self) -> &AllowedOrigins {
+        &self.origins
+    }
+
+    /// Whether a page from `origin` may call the API
+    pub fn allows(&self, origin: &str) -> bool {
+        match &self.origins {
+            AllowedOrigins::Any => true,
+            AllowedOrigins::Localhost => {
+                parse_origin(origin).is_some_and(|uri| is_localhost(uri.host().unwrap_or("")))
+            }
+            AllowedOrigins::List(origins) => {
+                normalize_origin(origin).is_some_and(|origin| origins.contains(&origin))
+            }
+        }
+    }
+
+    /// Build the layer answering preflights and adding CORS headers
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = match &self.origins {
+            AllowedOrigins::Any => AllowOrigin::any(),
+            AllowedOrigins::Localhost | AllowedOrigins::List(_) => {
+                let policy = self.clone();
+                AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                    origin.to_str().is_ok_and(|origin| policy.allows(origin))
+                })
+            }
+        };
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ])
+            .allow_headers(AllowHeaders::mirror_request())
+            .allow_credentials(self.allow_credentials)
+            .expose_headers([
+                routes::TOTAL_COUNT_HEADER,
+                routes::START_BLOCK_HEADER,
+                header::ETAG,
+                header::RETRY_AFTER,
+                idempotency::REPLAYED_HEADER,
+                logging::REQUEST_ID_HEADER,
+            ])
+    }
+}
+
+/// Turn away writes sent from pages of other origins
+///
+/// Requests without an `Origin` come from scripts and the CLI rather than
+/// browsers, unless `Sec-Fetch-Site` says the browser left it out.
+pub async fn csrf(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let method = request.method();
+    if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+        return Ok(next.run(request).await);
+    }
+
+    let headers = request.headers();
+    let allowed = match headers.get(header::ORIGIN).map(|v| v.to_str()) {
+        Some(Ok(origin)) => {
+            is_same_origin(headers, origin, state.trust_forwarded()) || state.cors().allows(origin)
+        }
+        Some(Err(_)) => false,
+        None => headers
+            .get(FETCH_SITE_HEADER)
+            .is_none_or(|site| site != "cross-site"),
+    };
+    if !allowed {
+        let origin = headers
+            .get(header::ORIGIN)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("another site");
+        return Err(ApiError::forbidden(format!(
+            "Cross-origin {} from {} is not allowed; add the origin with --cors-origin",
+            method, origin
+        )));
+    }
+    Ok(next.run(request).await)
+}
+
+/// Whether `origin` is the host the request was sent to, i.e. the dashboard
+/// the server embeds
+fn is_same_origin(headers: &HeaderMap, origin: &str, trust_forwarded: bool) -> bool {
+    let Some(authority) = parse_origin(origin).and_then(|uri| uri.authority().cloned()) else {
+        return false;
+    };
+    let host = match trust_forwarded {
+        true => headers
+            .get(FORWARDED_HOST_HEADER)
+            .or_else(|| headers.get(header::HOST)),
+        false => headers.get(header::HOST),
+    };
+    host.and_then(|v| v.to_str().ok())
+        .is_some_and(|host| host.eq_ignore_ascii_case(authority.as_str()))
+}
+
+/// Parse a `scheme://host[:port]` origin, rejecting paths and `null`
+fn parse_origin(origin: &str) -> Option<Uri> {
+    let uri: Uri = origin.trim().parse().ok()?;
+    let scheme = uri.scheme_str()?;
+    if !matches!(scheme, "http" | "https") || uri.host().is_none_or(str::is_empty) {
+        return None;
+    }
+    match uri.path_and_query().map(|p| p.as_str()) {
+        None | Some("") | Some("/") => Some(uri),
+        Some(_) => None,
+    }
+}
+
+/// Lowercase an origin and drop a trailing slash, as browsers send it
+fn normalize_origin(origin: &str) -> Option<String> {
+    let uri = parse_origin(origin)?;
+    Some(format!(
+        "{}://{}",
+        uri.scheme_str()?,
+        uri.authority()?.as_str().to_ascii_lowercase()
+    ))
+}
+
+fn is_localhost(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost") || host == "127.0.0.1" || host == "[::1]"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localhost_default() {
+        let policy = CorsPolicy::default();
+        assert!(policy.allows("http://localhost:5173"));
+        assert!(policy.allows("http://127.0.0.1:3000"));
+        assert!(policy.allows("https://[::1]"));
+        assert!(!policy.allows("https://localhost.example.com"));
+        assert!(!policy.allows("https://evil.example"));
+        assert!(!policy.allows("null"));
+    }
+
+    #[test]
+    fn test_origin_list() {
+        let policy = CorsPolicy::new(&["https://App.example.com/".to_string()], true).unwrap();
+        assert_eq!(
+            policy.origins(),
+            &AllowedOrigins::List(vec!["https://app.example.com".to_string()])
+        );
+        assert!(policy.allows("https://app.example.com"));
+        assert!(!policy.allows("http://app.example.com"));
+        assert!(!policy.allows("http://localhost:5173"));
+
+        assert!(CorsPolicy::new(&["app.example.com".to_string()], false).is_err());
+        assert!(CorsPolicy::new(&["https://app.example.com/path".to_string()], false).is_err());
+    }
+
+    #[test]
+    fn test_any_origin() {
+        let any = [ANY_ORIGIN.to_string()];
+        assert!(CorsPolicy::new(&any, false)
+            .unwrap()
+            .allows("https://evil.example"));
+        assert!(CorsPolicy::new(&any, true).is_err());
+        assert!(CorsPolicy::new(
+            &[
+                ANY_ORIGIN.to_string(),
+                "https://app.example.com".to_string()
+            ],
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_is_same_origin() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::HOST,
+            HeaderValue::from_static("smolder.internal:3000"),
+        );
+        assert!(is_same_origin(
+            &headers,
+            "http://smolder.internal:3000",
+            false
+        ));
+        assert!(!is_same_origin(&headers, "http://smolder.internal", false));
+
+        headers.insert(
+            FORWARDED_HOST_HEADER,
+            HeaderValue::from_static("tools.example.com"),
+        );
+        assert!(is_same_origin(&headers, "https://tools.example.com", true));
+        assert!(!is_same_origin(
+            &headers,
+            "https://tools.example.com",
+            false
+        ));
+    }
+}
@@ -8,22 +8,26 @@ use axum::{
     Json,
 };
 use serde::Serialize;
-use smolder_core::Error as CoreError;
+use smolder_core::{scrub, Error as CoreError};
 
 /// Structured API error response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct ApiError {
     /// Machine-readable error code
+    #[schema(value_type = String)]
     pub code: &'static str,
     /// Human-readable error message
     pub message: String,
 }
 
 impl ApiError {
+    /// An error with `message`, scrubbed of the credentials of any URL it
+    /// quotes
     pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        let message = message.into();
         Self {
             code,
-            message: message.into(),
+            message: scrub(&message).into_owned(),
         }
     }
 
@@ -46,6 +50,26 @@ impl ApiError {
     pub fn conflict(message: impl Into<String>) -> Self {
         Self::new("CONFLICT", message)
     }
+
+    /// Create an error for a missing or invalid API token
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new("UNAUTHORIZED", message)
+    }
+
+    /// Create an error for a request the token may not make
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new("FORBIDDEN", message)
+    }
+
+    /// Create an error for a write whose `If-Match` doesn't hold
+    pub fn precondition_failed(message: impl Into<String>) -> Self {
+        Self::new("PRECONDITION_FAILED", message)
+    }
+
+    /// Create an error for a client over its rate limit
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        Self::new("TOO_MANY_REQUESTS", message)
+    }
 }
 
 impl IntoResponse for ApiError {
@@ -54,21 +78,42 @@ impl IntoResponse for ApiError {
             "NETWORK_NOT_FOUND"
             | "CONTRACT_NOT_FOUND"
             | "DEPLOYMENT_NOT_FOUND"
+            | "DEPLOYMENT_GROUP_NOT_FOUND"
             | "WALLET_NOT_FOUND"
             | "FUNCTION_NOT_FOUND"
+            | "WEBHOOK_NOT_FOUND"
             | "ARTIFACT_NOT_FOUND"
+            | "API_TOKEN_NOT_FOUND"
+            | "APPROVAL_NOT_FOUND"
             | "FILE_NOT_FOUND"
             | "NOT_FOUND" => StatusCode::NOT_FOUND,
 
             "INVALID_PARAMETER" | "VALIDATION_ERROR" | "BAD_REQUEST" | "ABI_PARSE_ERROR"
-            | "ABI_ENCODE_ERROR" | "ABI_DECODE_ERROR" | "HEX_DECODE_ERROR" => {
-                StatusCode::BAD_REQUEST
+            | "ABI_ENCODE_ERROR" | "ABI_DECODE_ERROR" | "HEX_DECODE_ERROR"
+            | "INSUFFICIENT_FUNDS" => StatusCode::BAD_REQUEST,
+
+            "RPC_ERROR" | "TRANSACTION_FAILED" | "TRANSACTION_REVERTED" | "CHAIN_ID_MISMATCH" => {
+                StatusCode::BAD_GATEWAY
             }
 
-            "RPC_ERROR" | "TRANSACTION_FAILED" | "TRANSACTION_REVERTED" => StatusCode::BAD_GATEWAY,
+            "TRACE_UNSUPPORTED" => StatusCode::NOT_IMPLEMENTED,
+
+            "UNAUTHORIZED" => StatusCode::UNAUTHORIZED,
+
+            "FORBIDDEN" | "POLICY_VIOLATION" => StatusCode::FORBIDDEN,
 
             "CONFLICT" => StatusCode::CONFLICT,
 
+            "VERSION_MISMATCH" | "PRECONDITION_FAILED" => StatusCode::PRECONDITION_FAILED,
+
+            "NETWORK_LOCKED" => StatusCode::LOCKED,
+
+            "IDEMPOTENCY_KEY_REUSED" => StatusCode::UNPROCESSABLE_ENTITY,
+
+            "PAYLOAD_TOO_LARGE" => StatusCode::PAYLOAD_TOO_LARGE,
+
+            "TOO_MANY_REQUESTS" => StatusCode::TOO_MANY_REQUESTS,
+
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
@@ -87,7 +132,7 @@ impl From<CoreError> for ApiError {
             err.to_string()
         };
 
-        Self { code, message }
+        Self::new(code, message)
     }
 }
 
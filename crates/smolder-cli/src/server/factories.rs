@@ -0,0 +1,280 @@
+//! Contracts created by calls to factories
+//!
+//! A transaction sent to a tracked contract may deploy others, which its
+//! receipt doesn't tell: `contractAddress` is only set for top-level
+//! creations. Once the transaction is mined its call tree is traced, and
+//! every `CREATE` and `CREATE2` that didn't revert is matched against the
+//! project's artifacts. The artifact whose bytecode the init code starts with
+//! names the new contract, which is registered as a deployment linked to the
+//! call history entry. Creations no artifact matches stay untracked, and so
+//! does everything when the node can't trace.
+
+use alloy::primitives::{Address, Bytes};
+use smolder_core::{BytecodeHash, Error, Result};
+use smolder_db::{Deployment, NetworkId, NewContract, NewDeployment};
+
+use super::receipts::SentCall;
+use super::AppState;
+use crate::forge::ArtifactLoader;
+use crate::rpc;
+
+/// A contract deployed from within a transaction
+#[derive(Debug, PartialEq)]
+struct Creation {
+    address: Address,
+    /// The contract that deployed it
+    creator: Address,
+    initcode: Bytes,
+}
+
+/// Register the contracts `call` created, as deployments linked to its call
+/// history entry
+///
+/// Failures are logged: the call itself went through either way.
+pub async fn register(
+    state: &AppState,
+    call: &SentCall,
+    block_number: Option<i64>,
+) -> Vec<Deployment> {
+    let frame = match rpc::trace_transaction(&call.rpc_url, &call.tx_hash).await {
+        Ok(Some(frame)) => frame,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            tracing::debug!(tx_hash = %call.tx_hash, error = %e, "could not trace for created contracts");
+            return Vec::new();
+        }
+    };
+    let mut creations = Vec::new();
+    collect_creations(&frame, &mut creations);
+    if creations.is_empty() {
+        return Vec::new();
+    }
+
+    let network_id = match state
+        .repos()
+        .deployments()
+        .get_by_id(call.deployment.id)
+        .await
+    {
+        Ok(Some(deployment)) => deployment.network_id,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            tracing::warn!(history_id = call.history_id, error = %e, "could not register created contracts");
+            return Vec::new();
+        }
+    };
+    let artifacts = artifact_bytecodes(state.artifacts());
+
+    let mut registered = Vec::new();
+    for creation in creations {
+        let Some((name, bytecode)) = match_artifact(&artifacts, &creation.initcode) else {
+            tracing::info!(address = %creation.address, "created contract matches no artifact");
+            continue;
+        };
+        match record(
+            state,
+            call,
+            network_id,
+            &creation,
+            name,
+            bytecode,
+            block_number,
+        )
+        .await
+        {
+            Ok(Some(deployment)) => registered.push(deployment),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(address = %creation.address, contract = name, error = %e, "could not register created contract")
+            }
+        }
+    }
+    registered
+}
+
+/// Collect the creations in a `callTracer` frame and its subcalls, skipping
+/// reverted frames, whose creations were undone with them
+fn collect_creations(frame: &serde_json::Value, creations: &mut Vec<Creation>) {
+    if frame.get("error").is_some_and(|e| !e.is_null()) {
+        return;
+    }
+    if matches!(frame["type"].as_str(), Some("CREATE" | "CREATE2")) {
+        let address = frame["to"].as_str().and_then(|a| a.parse().ok());
+        let creator = frame["from"].as_str().and_then(|a| a.parse().ok());
+        let initcode = frame["input"].as_str().and_then(|i| i.parse().ok());
+        if let (Some(address), Some(creator), Some(initcode)) = (address, creator, initcode) {
+            creations.push(Creation {
+                address,
+                creator,
+                initcode,
+            });
+        }
+    }
+    for call in frame["calls"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+        collect_creations(call, creations);
+    }
+}
+
+/// Bytecode of every artifact that has some; artifacts with unlinked
+/// libraries are left out, since their placeholders never match
+fn artifact_bytecodes(loader: &dyn ArtifactLoader) -> Vec<(String, Vec<u8>)> {
+    let artifacts = match loader.list() {
+        Ok(artifacts) => artifacts,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not list artifacts");
+            return Vec::new();
+        }
+    };
+    artifacts
+        .into_iter()
+        .filter(|a| a.has_bytecode)
+        .filter_map(|a| {
+            let bytecode = hex::decode(loader.get_bytecode(&a.name).ok()?).ok()?;
+            Some((a.name, bytecode))
+        })
+        .collect()
+}
+
+/// The artifact whose bytecode `initcode` starts with, followed by
+/// constructor arguments; the longest wins when several do
+fn match_artifact<'a>(
+    artifacts: &'a [(String, Vec<u8>)],
+    initcode: &[u8],
+) -> Option<(&'a str, &'a [u8])> {
+    artifacts
+        .iter()
+        .filter(|(_, bytecode)| !bytecode.is_empty() && initcode.starts_with(bytecode))
+        .max_by_key(|(_, bytecode)| bytecode.len())
+        .map(|(name, bytecode)| (name.as_str(), bytecode.as_slice()))
+}
+
+/// Record a creation as a deployment of `name`, unless its address is
+/// already tracked
+async fn record(
+    state: &AppState,
+    call: &SentCall,
+    network_id: NetworkId,
+    creation: &Creation,
+    name: &str,
+    bytecode: &[u8],
+    block_number: Option<i64>,
+) -> Result<Option<Deployment>> {
+    let repos = state.repos();
+    let address = creation.address.to_string();
+    if repos
+        .deployments()
+        .exists_by_address(network_id, &address)
+        .await?
+    {
+        return Ok(None);
+    }
+
+    let details = state
+        .artifacts()
+        .get_details(name)
+        .map_err(|e| Error::Io(e.to_string()))?;
+    let contract = repos
+        .contracts()
+        .upsert(&NewContract {
+            name: name.to_string(),
+            source_path: details.source_path.clone(),
+            abi: serde_json::to_string(&details.abi)?,
+            bytecode_hash: BytecodeHash::of(bytecode),
+            storage_layout: details.storage_layout_json(),
+            compiler: details.compiler.clone(),
+        })
+        .await?;
+    let deployment = repos
+        .deployments()
+        .create(&NewDeployment {
+            contract_id: contract.id,
+            network_id,
+            address: creation.address.into(),
+            deployer: creation.creator.to_string(),
+            tx_hash: Some(call.tx_hash),
+            block_number,
+            constructor_args: None,
+            initcode_size: Some(details.initcode_size as i64),
+            runtime_size: Some(details.runtime_size as i64),
+            // Gas and fee belong to the call
+            gas_used: None,
+            gas_price: None,
+        })
+        .await?;
+    repos
+        .deployments()
+        .link_to_call(deployment.id, call.history_id)
+        .await?;
+
+    tracing::info!(contract = name, address = %address, history_id = call.history_id, "registered created contract");
+    Ok(Some(Deployment {
+        created_by_call_id: Some(call.history_id),
+        ..deployment
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_creations() {
+        let factory = Address::repeat_byte(0x0f);
+        let pair = Address::repeat_byte(0x0a);
+        let frame = serde_json::json!({
+            "type": "CALL",
+            "from": Address::repeat_byte(0xff).to_string(),
+            "to": factory.to_string(),
+            "input": "0x12345678",
+            "calls": [
+                {
+                    "type": "CREATE2",
+                    "from": factory.to_string(),
+                    "to": pair.to_string(),
+                    "input": "0x6080aa",
+                },
+                {
+                    "type": "CALL",
+                    "from": factory.to_string(),
+                    "to": pair.to_string(),
+                    "input": "0x",
+                    "error": "execution reverted",
+                    "calls": [{
+                        "type": "CREATE",
+                        "from": pair.to_string(),
+                        "to": Address::repeat_byte(0x0b).to_string(),
+                        "input": "0x6080",
+                    }],
+                },
+            ],
+        });
+
+        let mut creations = Vec::new();
+        collect_creations(&frame, &mut creations);
+        // The creation under the reverted call was undone
+        assert_eq!(
+            creations,
+            [Creation {
+                address: pair,
+                creator: factory,
+                initcode: Bytes::from_static(&[0x60, 0x80, 0xaa]),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_match_artifact() {
+        let artifacts = vec![
+            ("Base".to_string(), vec![0x60, 0x80]),
+            ("Pair".to_string(), vec![0x60, 0x80, 0xaa]),
+            ("Other".to_string(), vec![0x60, 0x40]),
+        ];
+
+        // Constructor arguments follow the bytecode
+        let (name, _) = match_artifact(&artifacts, &[0x60, 0x80, 0xaa, 0x01]).unwrap();
+        assert_eq!(name, "Pair");
+        let (name, _) = match_artifact(&artifacts, &[0x60, 0x80, 0xbb]).unwrap();
+        assert_eq!(name, "Base");
+        assert!(match_artifact(&artifacts, &[0x61]).is_none());
+    }
+}
@@ -0,0 +1,77 @@
+//! Sample the fees of every registered network while the server runs
+//!
+//! Samples feed `GET /api/networks/{name}/gas-history`, so the dashboard can
+//! chart fee trends and deploys can be timed for cheap blocks.
+
+use std::time::Duration;
+
+use smolder_db::NewGasSample;
+use tracing::Instrument;
+
+use super::AppState;
+use crate::rpc;
+
+/// Default time between two samples of a network
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long samples are kept
+const RETENTION: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// Time allowed for one network's node to answer before it is skipped
+const SAMPLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sample every network each `interval` in the background until shutdown
+pub fn start(state: &AppState, interval: Duration) {
+    let task_state = state.clone();
+    state.spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => sample_all(&task_state).await,
+                _ = task_state.shutdown_token().cancelled() => break,
+            }
+        }
+    });
+}
+
+async fn sample_all(state: &AppState) {
+    let networks = match state.repos().networks().list().await {
+        Ok(networks) => networks,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not list networks to sample gas");
+            return;
+        }
+    };
+
+    for network in networks {
+        let fees = tokio::time::timeout(SAMPLE_TIMEOUT, state.rpc().fees(network.rpc_url.expose()))
+            .instrument(rpc::span(network.chain_id))
+            .await;
+        let fees = match fees {
+            Ok(Ok(fees)) => fees,
+            Ok(Err(e)) => {
+                tracing::debug!(network = %network.name, error = %e, "gas sample failed");
+                continue;
+            }
+            Err(_) => {
+                tracing::debug!(network = %network.name, "gas sample timed out");
+                continue;
+            }
+        };
+
+        let sample = NewGasSample {
+            network_id: network.id,
+            block_number: fees.block_number as i64,
+            base_fee: fees.base_fee.map(|fee| fee as i64),
+            gas_price: i64::try_from(fees.gas_price).unwrap_or(i64::MAX),
+        };
+        if let Err(e) = state.repos().gas_samples().record(&sample).await {
+            tracing::warn!(network = %network.name, error = %e, "could not record gas sample");
+        }
+    }
+
+    if let Err(e) = state.repos().gas_samples().prune(RETENTION).await {
+        tracing::warn!(error = %e, "could not prune gas samples");
+    }
+}
@@ -0,0 +1,82 @@
+//! Live status changes of call history entries
+//!
+//! Whatever moves a transaction to another status (sending it, its receipt
+//! arriving, its relay dropping it, a reorg, or the monitor seeing it)
+//! calls [`publish`] afterwards. `GET /api/history/stream` passes the entry
+//! on to its subscribers as a server-sent event, so the dashboard can follow
+//! a transaction without polling `/api/history`.
+
+use std::convert::Infallible;
+
+use axum::response::sse::Event;
+use futures::Stream;
+use smolder_db::DeploymentId;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::AppState;
+
+/// Entries kept for subscribers that fall behind; slower ones get a
+/// `lagged` event instead
+pub const CAPACITY: usize = 256;
+
+/// Send the current state of call history entry `id` to subscribers
+///
+/// Failures are logged rather than returned, like webhook notifications.
+pub async fn publish(state: &AppState, id: i64) {
+    if state.history_events().receiver_count() == 0 {
+        return;
+    }
+    match state.repos().call_history().get_by_id(id).await {
+        Ok(Some(entry)) => {
+            // Only fails when the last subscriber just left
+            let _ = state.history_events().send(entry);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(history_id = id, error = %e, "could not publish call status"),
+    }
+}
+
+/// Events for the entries of `deployment`, or of every deployment
+///
+/// Each entry is a `status` event with the history ID as event ID and the
+/// entry as JSON data. A subscriber that fell more than [`CAPACITY`]
+/// entries behind gets a `lagged` event with the number it missed, and
+/// should reload `/api/history`. The stream ends when the server shuts down.
+pub fn subscribe(
+    state: &AppState,
+    deployment: Option<DeploymentId>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let receiver = state.history_events().subscribe();
+    let shutdown = state.shutdown_token().clone();
+
+    futures::stream::unfold(receiver, move |mut receiver| {
+        let shutdown = shutdown.clone();
+        async move {
+            loop {
+                let received = tokio::select! {
+                    received = receiver.recv() => received,
+                    _ = shutdown.cancelled() => return None,
+                };
+                let event = match received {
+                    Ok(entry) if deployment.is_some_and(|id| id != entry.deployment_id) => continue,
+                    Ok(entry) => match Event::default()
+                        .event("status")
+                        .id(entry.id.to_string())
+                        .json_data(&entry)
+                    {
+                        Ok(event) => event,
+                        Err(e) => {
+                            tracing::warn!(history_id = entry.id, error = %e, "could not encode call status");
+                            continue;
+                        }
+                    },
+                    Err(RecvError::Lagged(missed)) => {
+                        Event::default().event("lagged").data(missed.to_string())
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((Ok(event), receiver));
+            }
+        }
+    })
+}
@@ -0,0 +1,213 @@
+//! Replay the response of POSTs retried with the same `Idempotency-Key`
+//!
+//! A client that times out on a deploy or send can't tell whether it went
+//! through. Sent with an `Idempotency-Key` header, a retry gets the status
+//! and body the first attempt answered with, marked `Idempotent-Replayed:
+//! true`, instead of running again. Keys belong to the API token that sent
+//! them and expire after a day. Reusing a key for a different request is
+//! rejected with 422, and retrying while the first attempt still runs with
+//! 409. Server errors are not kept, so those retries run again. Responses
+//! marked [`HoldsSecret`] aren't kept either: retries get a 409 instead, so
+//! the secret never rests in the database.
+
+use std::time::Duration;
+
+use alloy::primitives::keccak256;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use smolder_db::IdempotencyRecord;
+
+use crate::server::auth::Caller;
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+/// Request header carrying the client's key for a POST
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Response header set on responses replayed for a known key
+pub const REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// How long a key and its response are kept
+const KEY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest key accepted
+const MAX_KEY_LEN: usize = 255;
+
+/// Response extension of handlers whose body holds a secret, such as a new
+/// API token, which must not be kept for replay
+#[derive(Clone, Copy)]
+pub struct HoldsSecret;
+
+/// Run each keyed POST once, replaying its response on retries
+///
+/// Layered innermost in `create_router`, so only requests the token may
+/// make claim a key.
+pub async fn dedupe(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if request.method() != Method::POST {
+        return Ok(next.run(request).await);
+    }
+    let key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(key) => parse_key(key)?,
+        None => return Ok(next.run(request).await),
+    };
+    let scope = request
+        .extensions()
+        .get::<Caller>()
+        .map(|caller| caller.name.clone())
+        .unwrap_or_default();
+
+    let (parts, body) = request.into_parts();
+    let limit = state.limits().max_body_bytes;
+    let body = axum::body::to_bytes(body, limit).await.map_err(|_| {
+        ApiError::new(
+            "PAYLOAD_TOO_LARGE",
+            format!("Request body is over {} bytes", limit),
+        )
+    })?;
+    let fingerprint = fingerprint(&parts.method, &parts.uri.to_string(), &body);
+
+    state.repos().idempotency_keys().prune(KEY_TTL).await?;
+    match state
+        .repos()
+        .idempotency_keys()
+        .claim(&scope, &key, &fingerprint)
+        .await?
+    {
+        None => {}
+        Some(record) if record.fingerprint != fingerprint => {
+            return Err(ApiError::new(
+                "IDEMPOTENCY_KEY_REUSED",
+                format!("Idempotency-Key '{}' was used for a different request", key),
+            ));
+        }
+        Some(IdempotencyRecord { status: None, .. }) => {
+            return Err(ApiError::conflict(format!(
+                "The request with Idempotency-Key '{}' is still running",
+                key
+            )));
+        }
+        Some(record) => return Ok(replay(record)),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    Ok(store(&state, &scope, &key, response).await)
+}
+
+fn parse_key(value: &HeaderValue) -> Result<String, ApiError> {
+    value
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            ApiError::bad_request(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_KEY_LEN
+            ))
+        })
+}
+
+/// Hash of what makes two requests the same
+fn fingerprint(method: &Method, uri: &str, body: &[u8]) -> String {
+    let mut data = format!("{} {}\n", method, uri).into_bytes();
+    data.extend_from_slice(body);
+    keccak256(&data).to_string()
+}
+
+/// Keep the response to replay it, unless it is a server error, or a 409 in
+/// place of one holding a secret
+async fn store(state: &AppState, scope: &str, key: &str, response: Response) -> Response {
+    if response.status().is_server_error() {
+        if let Err(e) = state.repos().idempotency_keys().release(scope, key).await {
+            tracing::warn!(error = %e, "could not release idempotency key");
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            let _ = state.repos().idempotency_keys().release(scope, key).await;
+            return ApiError::internal(format!("Could not read the response: {}", e))
+                .into_response();
+        }
+    };
+    let (status, headers, kept) = match parts.extensions.get::<HoldsSecret>() {
+        Some(_) => {
+            let conflict = ApiError::conflict(format!(
+                "The response to Idempotency-Key '{}' held a secret and was not kept; \
+                 check whether the first attempt went through",
+                key
+            ))
+            .into_response();
+            let (parts, body) = conflict.into_parts();
+            let body = axum::body::to_bytes(body, usize::MAX)
+                .await
+                .unwrap_or_default();
+            (parts.status, parts.headers, body)
+        }
+        None => (parts.status, parts.headers.clone(), body.clone()),
+    };
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    let stored = state
+        .repos()
+        .idempotency_keys()
+        .complete(scope, key, status.as_u16(), content_type, &kept)
+        .await;
+    if let Err(e) = stored {
+        tracing::warn!(error = %e, "could not store idempotent response");
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn replay(record: IdempotencyRecord) -> Response {
+    let status = record
+        .status
+        .and_then(|status| u16::try_from(status).ok())
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut response = (status, record.body.unwrap_or_default()).into_response();
+    let headers = response.headers_mut();
+    if let Some(content_type) = record
+        .content_type
+        .and_then(|v| HeaderValue::from_str(&v).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_and_fingerprint() {
+        assert_eq!(
+            parse_key(&HeaderValue::from_static(" 9f1c ")).unwrap(),
+            "9f1c"
+        );
+        assert!(parse_key(&HeaderValue::from_static("")).is_err());
+        assert!(parse_key(&HeaderValue::from_str(&"k".repeat(256)).unwrap()).is_err());
+
+        let a = fingerprint(&Method::POST, "/api/deploy", b"{}");
+        assert_eq!(a, fingerprint(&Method::POST, "/api/deploy", b"{}"));
+        assert_ne!(a, fingerprint(&Method::POST, "/api/deploy/multi", b"{}"));
+        assert_ne!(a, fingerprint(&Method::POST, "/api/deploy", b"{ }"));
+    }
+}
@@ -0,0 +1,281 @@
+//! Background jobs run by a pool of workers
+//!
+//! Work that must outlive the request asking for it is queued with
+//! [`enqueue`] in the `jobs` table, and picked up by the workers [`start`]
+//! spawns, oldest first. A failed attempt is retried with exponential backoff
+//! until the job runs out of attempts; jobs a shutdown interrupted are queued
+//! again on the next start. Finished jobs are kept for a week, for
+//! `GET /api/jobs` and `smolder jobs`.
+//!
+//! Each kind of job is handled by its own module, which [`dispatch`] routes
+//! the job's JSON payload to.
+
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use smolder_core::{Error, JobState, Result};
+use smolder_db::{Job, NewJob};
+use tracing::Instrument;
+
+use super::receipts;
+use super::AppState;
+
+/// Jobs run at once by default
+pub const DEFAULT_WORKERS: usize = 8;
+
+/// Time between checks for queued jobs when no new one was announced, which
+/// picks up retries as they come due
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Wait before the first retry, doubled after each further failure
+const BASE_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Longest wait between two attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// How long succeeded and failed jobs are kept
+const RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Time between two prunes of finished jobs
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Why an attempt failed
+struct Failure {
+    message: String,
+    /// Whether another attempt may succeed
+    retry: bool,
+}
+
+impl From<Error> for Failure {
+    /// Missing records stay missing, anything else may be transient
+    fn from(e: Error) -> Self {
+        Self {
+            retry: !e.is_not_found(),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Queue a job of `kind`, to be attempted up to `max_attempts` times
+pub async fn enqueue(
+    state: &AppState,
+    kind: &str,
+    payload: &impl Serialize,
+    max_attempts: i64,
+) -> Result<Job> {
+    let job = state
+        .repos()
+        .jobs()
+        .enqueue(&NewJob {
+            kind: kind.to_string(),
+            payload: serde_json::to_string(payload)?,
+            max_attempts,
+        })
+        .await?;
+    state.job_queued().notify_one();
+    Ok(job)
+}
+
+/// Queue again the jobs left running by the last shutdown, then run jobs on
+/// `workers` workers in the background until shutdown
+pub async fn start(state: &AppState, workers: usize) {
+    match state.repos().jobs().requeue_running().await {
+        Ok(0) => {}
+        Ok(jobs) => tracing::info!(jobs, "resuming interrupted jobs"),
+        Err(e) => tracing::warn!(error = %e, "could not requeue interrupted jobs"),
+    }
+
+    for _ in 0..workers {
+        let task_state = state.clone();
+        state.spawn(async move { work(&task_state).await });
+    }
+
+    let task_state = state.clone();
+    state.spawn(async move {
+        let mut ticker = tokio::time::interval(PRUNE_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => prune(&task_state).await,
+                _ = task_state.shutdown_token().cancelled() => break,
+            }
+        }
+    });
+}
+
+/// Run due jobs one after the other until shutdown
+///
+/// A job running when the server shuts down is left in the `running` state,
+/// for [`start`] to queue again.
+async fn work(state: &AppState) {
+    let shutdown = state.shutdown_token();
+    loop {
+        match state.repos().jobs().claim().await {
+            Ok(Some(job)) => {
+                tokio::select! {
+                    _ = run(state, job) => continue,
+                    _ = shutdown.cancelled() => break,
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "could not claim a job"),
+        }
+        tokio::select! {
+            _ = state.job_queued().notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = shutdown.cancelled() => break,
+        }
+    }
+}
+
+/// Attempt `job` and record the outcome
+async fn run(state: &AppState, job: Job) {
+    let span = tracing::info_span!("job", id = job.id, kind = %job.kind, attempt = job.attempts);
+    async {
+        let outcome = dispatch(state, &job).await;
+        let recorded = match outcome {
+            Ok(()) => {
+                tracing::debug!("job succeeded");
+                state.repos().jobs().complete(job.id).await
+            }
+            Err(failure) => {
+                let retry_in = failure.retry.then(|| backoff(job.attempts));
+                match state
+                    .repos()
+                    .jobs()
+                    .fail(job.id, &failure.message, retry_in)
+                    .await
+                {
+                    Ok(JobState::Failed) => {
+                        tracing::warn!(error = %failure.message, "job failed");
+                        Ok(())
+                    }
+                    Ok(_) => {
+                        tracing::debug!(error = %failure.message, "job will be retried");
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        if let Err(e) = recorded {
+            tracing::warn!(error = %e, "could not record job outcome");
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// Hand `job` to the module handling its kind
+async fn dispatch(state: &AppState, job: &Job) -> std::result::Result<(), Failure> {
+    match job.kind.as_str() {
+        receipts::JOB_KIND => receipts::run(state, payload(job)?).await?,
+        kind => {
+            return Err(Failure {
+                message: format!("Unknown job kind '{}'", kind),
+                retry: false,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// The payload of `job`, which no retry will fix if it doesn't parse
+fn payload<T: DeserializeOwned>(job: &Job) -> std::result::Result<T, Failure> {
+    serde_json::from_str(&job.payload).map_err(|e| Failure {
+        message: format!("Invalid payload: {}", e),
+        retry: false,
+    })
+}
+
+/// Wait before the attempt after the `attempts`th
+fn backoff(attempts: i64) -> Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BASE_BACKOFF
+        .saturating_mul(2u32.pow(doublings))
+        .min(MAX_BACKOFF)
+}
+
+async fn prune(state: &AppState) {
+    match state.repos().jobs().prune(RETENTION).await {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!(jobs = deleted, "pruned finished jobs"),
+        Err(e) => tracing::warn!(error = %e, "could not prune finished jobs"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use smolder_db::{Database, JobFilter, MemoryDatabase};
+
+    async fn test_state() -> AppState {
+        let db = Database::connect_to(":memory:").await.unwrap();
+        AppState::new(db, Arc::new(crate::forge::FileSystemArtifactLoader::new()))
+            .with_repositories(Arc::new(MemoryDatabase::new()))
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), BASE_BACKOFF);
+        assert_eq!(backoff(3), BASE_BACKOFF * 4);
+        assert_eq!(backoff(100), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_workers_settle_jobs() {
+        let state = test_state().await;
+        let unknown = enqueue(&state, "unknown", &serde_json::json!({}), 3)
+            .await
+            .unwrap();
+        // Deployment 42 doesn't exist, which no retry fixes
+        let receipt = enqueue(
+            &state,
+            receipts::JOB_KIND,
+            &serde_json::json!({
+                "history_id": 1,
+                "deployment_id": 42,
+                "function_name": "transfer",
+                "tx_hash": format!("0x{}", "11".repeat(32)),
+                "relay_url": null,
+            }),
+            receipts::MAX_ATTEMPTS,
+        )
+        .await
+        .unwrap();
+
+        start(&state, 2).await;
+        let failed = loop {
+            let failed = state
+                .repos()
+                .jobs()
+                .list(&JobFilter {
+                    state: Some(JobState::Failed),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            if failed.len() == 2 {
+                break failed;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        state.shutdown_token().cancel();
+
+        assert_eq!(failed[0].id, receipt.id);
+        assert_eq!(failed[0].attempts, 1);
+        assert!(failed[0]
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("not found"));
+        assert_eq!(failed[1].id, unknown.id);
+        assert_eq!(
+            failed[1].last_error.as_deref(),
+            Some("Unknown job kind 'unknown'")
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Rate, body size and concurrency limits
+//!
+//! Every `/api` and `/rpc` request draws from a token bucket keyed by the API
+//! token it carries, or by the client's IP when the API is open (see
+//! [`proxy::client_ip`]), and gets `429 Too Many Requests` once the bucket is
+//! empty. Routes that talk to RPC nodes
+//! also declare [`rpc`], which caps how many of them run at once across all
+//! clients, so one busy dashboard tab can't use up the provider's quota.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::sync::Semaphore;
+
+use crate::server::auth::Caller;
+use crate::server::error::ApiError;
+use crate::server::proxy;
+use crate::server::AppState;
+
+/// Requests a client may make per minute by default
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 600;
+
+/// Largest request body accepted by default
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// RPC-backed requests served at once by default
+pub const DEFAULT_RPC_CONCURRENCY: usize = 16;
+
+/// Buckets kept at most; full ones are dropped first, then the least
+/// recently used
+const MAX_BUCKETS: usize = 10_000;
+
+/// Limits applied to API requests
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Requests per minute for each token or IP; 0 disables rate limiting
+    pub requests_per_minute: u32,
+    pub max_body_bytes: usize,
+    /// RPC-backed requests served at once; others wait for a slot
+    pub rpc_concurrency: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            rpc_concurrency: DEFAULT_RPC_CONCURRENCY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Token buckets by client, holding up to a minute of requests and refilled
+/// continuously
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_minute: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// Take a request from `key`'s bucket, or return how long until one is
+    /// available
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(self.per_minute);
+        let per_second = capacity / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(key) {
+            // A full bucket is the same as no bucket
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.updated_at).as_secs_f64() * per_second < capacity
+            });
+        }
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(key) {
+            // Clients cycling through keys would grow the map forever, so
+            // forget the tenth seen longest ago, at once to keep this rare
+            let mut seen: Vec<Instant> = buckets.values().map(|b| b.updated_at).collect();
+            let (_, cutoff, _) = seen.select_nth_unstable(MAX_BUCKETS / 10);
+            let cutoff = *cutoff;
+            buckets.retain(|_, b| b.updated_at > cutoff);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * per_second).min(capacity);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Rate limit `/api` requests by token, or by IP when there is none
+///
+/// Layered inside [`crate::server::auth::authenticate`] so the token is
+/// known. Also hands the RPC slots to the routes that declare [`rpc`].
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let key = match request.extensions().get::<Caller>() {
+        Some(caller) => format!("token:{}", caller.name),
+        None => match proxy::client_ip(&request, state.trust_forwarded()) {
+            Some(ip) => format!("ip:{}", ip),
+            None => "ip:unknown".to_string(),
+        },
+    };
+
+    if let Err(retry_after) = state.rate_limiter().check(&key) {
+        let secs = (retry_after.as_secs_f64().ceil() as u64).max(1);
+        let mut response = ApiError::too_many_requests(format!(
+            "Rate limit of {} requests per minute exceeded; retry in {}s",
+            state.limits().requests_per_minute,
+            secs
+        ))
+        .into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        return Ok(response);
+    }
+
+    request.extensions_mut().insert(state.rpc_permits());
+    Ok(next.run(request).await)
+}
+
+/// Wait for one of the RPC slots before running the request
+///
+/// Declared per router with `.route_layer(middleware::from_fn(limits::rpc))`.
+pub async fn rpc(request: Request, next: Next) -> Response {
+    let permits = request.extensions().get::<Arc<Semaphore>>().cloned();
+    let _permit = match &permits {
+        Some(permits) => permits.acquire().await.ok(),
+        None => None,
+    };
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(60);
+        let start = Instant::now();
+        for _ in 0..60 {
+            assert!(limiter.check_at("a", start).is_ok());
+        }
+        let retry = limiter.check_at("a", start).unwrap_err();
+        assert_eq!(retry, Duration::from_secs(1));
+
+        // Other clients have their own bucket
+        assert!(limiter.check_at("b", start).is_ok());
+
+        // One request per second comes back
+        let later = start + Duration::from_secs(2);
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter_evicts_least_recent() {
+        let limiter = RateLimiter::new(60);
+        let start = Instant::now();
+        // Each key keeps a drained bucket, which refilling can't drop
+        for i in 0..MAX_BUCKETS * 2 {
+            let at = start + Duration::from_millis(i as u64);
+            for _ in 0..60 {
+                let _ = limiter.check_at(&format!("ip:{}", i), at);
+            }
+        }
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.len() <= MAX_BUCKETS);
+        assert!(buckets.contains_key(&format!("ip:{}", MAX_BUCKETS * 2 - 1)));
+        assert!(!buckets.contains_key("ip:0"));
+    }
+
+    #[test]
+    fn test_rate_limiter_disabled() {
+        let limiter = RateLimiter::new(0);
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert!(limiter.check_at("a", now).is_ok());
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Structured logging and per-request tracing for the server
+
+use axum::http::{HeaderName, Request};
+use clap::ValueEnum;
+use color_eyre::eyre::{eyre, Result};
+use tracing::{Level, Span};
+use tracing_subscriber::EnvFilter;
+
+use crate::server::proxy;
+
+/// Header carrying the request id, generated when the client sends none
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Log line format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// Install the global tracing subscriber
+///
+/// `level` accepts anything `RUST_LOG` does, e.g. `debug` or
+/// `info,smolder=debug`.
+pub fn init(level: &str, format: LogFormat) -> Result<()> {
+    let filter =
+        EnvFilter::try_new(level).map_err(|e| eyre!("Invalid log level '{}': {}", level, e))?;
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false);
+
+    let installed = match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    };
+    installed.map_err(|e| eyre!("Could not initialize logging: {}", e))
+}
+
+/// Span wrapping a single HTTP request, tagged with its request id and
+/// client
+pub fn request_span<B>(request: &Request<B>, trust_forwarded: bool) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-");
+    let client = proxy::client_ip(request, trust_forwarded)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    tracing::span!(
+        Level::INFO,
+        "request",
+        request_id,
+        client,
+        method = %request.method(),
+        uri = %request.uri(),
+    )
+}
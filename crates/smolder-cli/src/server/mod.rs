@@ -1,13 +1,45 @@
+pub mod auth;
+pub mod cors;
 mod error;
+mod factories;
+mod gas_sampler;
+mod history_stream;
+mod idempotency;
+pub mod jobs;
+pub mod limits;
+pub mod logging;
+pub mod monitor;
+pub mod proxy;
+mod receipts;
+mod reorgs;
+mod retention;
 mod routes;
+mod rpc_proxy;
+mod sandbox;
+mod shutdown;
+mod simulate;
 mod state;
 mod static_files;
+mod trace;
+pub mod webhooks;
 
 pub use state::AppState;
 
-use smolder_db::Database;
+use smolder_db::{Database, HistoryRetention};
+use std::future::{Future, IntoFuture};
 use std::net::SocketAddr;
-use tower_http::cors::{Any, CorsLayer};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::config::Framework;
+use crate::forge;
+use crate::prices::PriceOracle;
+use crate::tenderly::Tenderly;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tower_http::LatencyUnit;
+use tracing::Level;
 
 pub use routes::create_router;
 
@@ -15,6 +47,52 @@ pub use routes::create_router;
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Framework whose build output backs the artifact endpoints
+    pub framework: Framework,
+    /// Time allowed for in-flight requests and background tasks to finish
+    /// after a shutdown signal
+    pub shutdown_timeout: Duration,
+    /// Network to resolve ENS names on
+    pub ens_network: Option<String>,
+    /// Simulates sends, from `[tenderly]` in smolder.toml
+    pub tenderly: Option<Tenderly>,
+    /// Values fees in USD, from `[prices]` in smolder.toml
+    pub prices: Option<PriceOracle>,
+    /// Time between gas price samples of each network; `None` disables
+    /// sampling
+    pub gas_sample_interval: Option<Duration>,
+    /// Time between reorg and finality checks of recently mined records;
+    /// `None` disables them
+    pub reorg_check_interval: Option<Duration>,
+    /// Call history pruned while the server runs, from `[retention]` in
+    /// smolder.toml
+    pub history_retention: Option<HistoryRetention>,
+    /// Calls to tracked deployments watched for, from `[monitor]` in
+    /// smolder.toml
+    pub monitor: Option<monitor::Monitor>,
+    /// How long deploys and sends on protected networks wait for approval
+    pub approval_ttl: Duration,
+    /// Rate, body size and RPC concurrency limits of the API
+    pub limits: limits::Limits,
+    /// Certificate and key to serve HTTPS with; plain HTTP when `None`
+    pub tls: Option<TlsConfig>,
+    /// Sub-path a reverse proxy mounts the server under, as normalized by
+    /// [`proxy::parse_base_path`]
+    pub base_path: String,
+    /// Take the client from `X-Forwarded-For`, for servers behind a proxy
+    pub trust_forwarded: bool,
+    /// Browser origins allowed to call the API
+    pub cors: cors::CorsPolicy,
+    /// Background jobs run at once
+    pub job_workers: usize,
+}
+
+/// PEM files for serving HTTPS
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// Certificate chain
+    pub cert: PathBuf,
+    pub key: PathBuf,
 }
 
 impl Default for ServerConfig {
@@ -22,28 +100,134 @@ impl Default for ServerConfig {
         Self {
             host: "127.0.0.1".to_string(),
             port: 3000,
+            framework: Framework::default(),
+            shutdown_timeout: shutdown::DEFAULT_TIMEOUT,
+            ens_network: None,
+            tenderly: None,
+            prices: None,
+            gas_sample_interval: Some(gas_sampler::DEFAULT_INTERVAL),
+            reorg_check_interval: Some(reorgs::DEFAULT_INTERVAL),
+            history_retention: None,
+            monitor: None,
+            approval_ttl: state::DEFAULT_APPROVAL_TTL,
+            limits: limits::Limits::default(),
+            tls: None,
+            base_path: String::new(),
+            trust_forwarded: false,
+            cors: cors::CorsPolicy::default(),
+            job_workers: jobs::DEFAULT_WORKERS,
         }
     }
 }
 
-/// Start the smolder server
+/// Start the smolder server, returning once it has shut down
+///
+/// On SIGINT/SIGTERM the listener stops accepting connections, in-flight
+/// requests and background tasks get `shutdown_timeout` to finish, and the
+/// database pool is closed.
 pub async fn run_server(
     db: Database,
     config: ServerConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let state = AppState::new(db);
+    let state = AppState::new(db, forge::artifact_loader(config.framework))
+        .with_ens_network(config.ens_network.clone())
+        .with_tenderly(config.tenderly.clone())
+        .with_prices(config.prices.clone())
+        .with_approval_ttl(config.approval_ttl)
+        .with_limits(config.limits)
+        .with_base_path(&config.base_path)
+        .with_trust_forwarded(config.trust_forwarded)
+        .with_cors(config.cors.clone());
+    webhooks::resume_pending(&state).await;
+    jobs::start(&state, config.job_workers).await;
+    if let Some(interval) = config.gas_sample_interval {
+        gas_sampler::start(&state, interval);
+    }
+    if let Some(interval) = config.reorg_check_interval {
+        reorgs::start(&state, interval);
+    }
+    if let Some(retention) = config.history_retention {
+        retention::start(&state, retention);
+    }
+    if let Some(monitor) = config.monitor.clone() {
+        monitor::start(&state, monitor);
+    }
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let trace = TraceLayer::new_for_http()
+        .make_span_with(move |request: &axum::http::Request<_>| {
+            logging::request_span(request, config.trust_forwarded)
+        })
+        .on_response(
+            DefaultOnResponse::new()
+                .level(Level::INFO)
+                .latency_unit(LatencyUnit::Millis),
+        );
 
-    let app = create_router(state).layer(cors);
+    // Layers run bottom-up: the id is set before the trace span reads it and
+    // copied onto the response afterwards
+    let app = create_router(state.clone())
+        .layer(PropagateRequestIdLayer::new(logging::REQUEST_ID_HEADER))
+        .layer(trace)
+        .layer(SetRequestIdLayer::new(
+            logging::REQUEST_ID_HEADER,
+            MakeRequestUuid,
+        ));
 
     let addr: SocketAddr = format!("{}:{}", config.host, config.port).parse()?;
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let token = state.shutdown_token().clone();
+    // Connection info keys the rate limit of requests without a token
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server: Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>> = match &config.tls {
+        Some(tls) => {
+            // Both rustls backends end up in the tree through dependencies,
+            // so one has to be picked
+            let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+            let rustls = axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert, &tls.key)
+                .await
+                .map_err(|e| format!("Could not load TLS certificate and key: {}", e))?;
+            let handle = axum_server::Handle::new();
+            let shutdown = handle.clone();
+            let cancelled = token.clone().cancelled_owned();
+            tokio::spawn(async move {
+                cancelled.await;
+                shutdown.graceful_shutdown(None);
+            });
+            tracing::info!(%addr, "listening with TLS");
+            Box::pin(
+                axum_server::bind_rustls(addr, rustls)
+                    .handle(handle)
+                    .serve(app),
+            )
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            tracing::info!(%addr, "listening");
+            Box::pin(
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(token.clone().cancelled_owned())
+                    .into_future(),
+            )
+        }
+    };
+    let mut server = std::pin::pin!(server);
 
-    axum::serve(listener, app).await?;
+    tokio::select! {
+        result = &mut server => result?,
+        _ = shutdown::signal() => {
+            tracing::info!(
+                timeout_secs = config.shutdown_timeout.as_secs(),
+                "shutting down, draining connections"
+            );
+            token.cancel();
+
+            let deadline = tokio::time::Instant::now() + config.shutdown_timeout;
+            match tokio::time::timeout_at(deadline, &mut server).await {
+                Ok(result) => result?,
+                Err(_) => tracing::warn!("shutdown timeout reached, dropping open connections"),
+            }
+            shutdown::finish(&state, deadline).await;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,373 @@
+//! Watch tracked deployments for calls smolder didn't send
+//!
+//! Configured under `[monitor]` in smolder.toml. Each interval the blocks
+//! mined on the watched networks since the last scan are fetched, and every
+//! transaction sent to a tracked address that isn't already in call history
+//! is imported as an external call (see [`external`]). Imported calls are
+//! passed on to [`webhooks::notify`], and to the `[notifications]` channels
+//! when enabled.
+//!
+//! Only top-level transactions are seen: calls reaching a deployment through
+//! another contract are not. Scanning starts at the latest block when the
+//! server starts, so blocks mined while it was down are skipped.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use alloy::consensus::Transaction as _;
+use alloy::network::TransactionResponse;
+use alloy::primitives::Address;
+use alloy::rpc::types::Transaction;
+use smolder_core::{Abi, TxHash};
+use smolder_db::{DeploymentFilter, DeploymentView, Network, TransactionStatus};
+use tracing::Instrument;
+
+use super::history_stream;
+use super::webhooks::{self, Notification};
+use super::AppState;
+use crate::config::NotificationsConfig;
+use crate::external;
+use crate::notify::{self, ActivityNotice};
+use crate::rpc;
+
+/// Default time between two scans of a network
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Most blocks scanned per network in one pass, so a node that fell far
+/// behind is caught up over several
+const MAX_BLOCKS_PER_SCAN: u64 = 50;
+
+/// What the monitor watches and who it tells
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    /// Names of the networks to scan
+    pub networks: Vec<String>,
+    pub interval: Duration,
+    /// Channels to post each call to
+    pub notifications: Option<NotificationsConfig>,
+}
+
+/// Scan the watched networks each interval in the background until shutdown
+pub fn start(state: &AppState, monitor: Monitor) {
+    let task_state = state.clone();
+    state.spawn(async move {
+        // Next block to scan, per network
+        let mut cursors = HashMap::new();
+        let mut ticker = tokio::time::interval(monitor.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => scan_all(&task_state, &monitor, &mut cursors).await,
+                _ = task_state.shutdown_token().cancelled() => break,
+            }
+        }
+    });
+}
+
+async fn scan_all(state: &AppState, monitor: &Monitor, cursors: &mut HashMap<String, u64>) {
+    for name in &monitor.networks {
+        let network = match state.repos().networks().get_by_name(name).await {
+            Ok(Some(network)) => network,
+            Ok(None) => {
+                tracing::debug!(network = %name, "monitored network is not registered");
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(network = %name, error = %e, "could not load monitored network");
+                continue;
+            }
+        };
+        let notices = scan(state, &network, cursors)
+            .instrument(rpc::span(network.chain_id))
+            .await;
+        if let (Some(notifications), false) = (&monitor.notifications, notices.is_empty()) {
+            notify::alert(notifications, &notices).await;
+        }
+    }
+}
+
+/// Import the calls to tracked deployments mined on `network` since the
+/// last scan
+async fn scan(
+    state: &AppState,
+    network: &Network,
+    cursors: &mut HashMap<String, u64>,
+) -> Vec<ActivityNotice> {
+    let latest = match state.rpc().block_number(network.rpc_url.expose()).await {
+        Ok(latest) => latest,
+        Err(e) => {
+            tracing::debug!(network = %network.name, error = %e, "could not read latest block");
+            return Vec::new();
+        }
+    };
+    let Some(next) = cursors.get(&network.name).copied() else {
+        cursors.insert(network.name.clone(), latest + 1);
+        return Vec::new();
+    };
+    if next > latest {
+        return Vec::new();
+    }
+
+    let filter = DeploymentFilter {
+        network: Some(network.name.clone()),
+        ..Default::default()
+    };
+    let deployments = match state.repos().deployments().list(filter).await {
+        Ok(deployments) => deployments,
+        Err(e) => {
+            tracing::warn!(network = %network.name, error = %e, "could not list monitored deployments");
+            return Vec::new();
+        }
+    };
+    // Older versions are still deployed; the latest wins an address
+    let mut tracked: HashMap<Address, DeploymentView> = HashMap::new();
+    for deployment in deployments {
+        let address = *deployment.address;
+        match tracked.get(&address) {
+            Some(existing) if existing.version >= deployment.version => {}
+            _ => {
+                tracked.insert(address, deployment);
+            }
+        }
+    }
+
+    let mut notices = Vec::new();
+    let last = latest.min(next + MAX_BLOCKS_PER_SCAN - 1);
+    for number in next..=last {
+        let transactions = match state
+            .rpc()
+            .block_transactions(network.rpc_url.expose(), number)
+            .await
+        {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                // Retried from this block on the next scan
+                tracing::debug!(network = %network.name, block = number, error = %e, "could not read block");
+                break;
+            }
+        };
+        for transaction in &transactions {
+            let Some(deployment) = transaction.to().and_then(|to| tracked.get(&to)) else {
+                continue;
+            };
+            match record(state, network, deployment, transaction).await {
+                Ok(Some(notice)) => notices.push(notice),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(tx_hash = %transaction.tx_hash(), error = %e, "could not record external call")
+                }
+            }
+        }
+        cursors.insert(network.name.clone(), number + 1);
+    }
+    notices
+}
+
+/// Import a call to `deployment` unless it is in call history already
+async fn record(
+    state: &AppState,
+    network: &Network,
+    deployment: &DeploymentView,
+    transaction: &Transaction,
+) -> smolder_core::Result<Option<ActivityNotice>> {
+    let tx_hash = TxHash(transaction.tx_hash());
+    let Some(receipt) = state
+        .rpc()
+        .receipt(network.rpc_url.expose(), &tx_hash)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let abi = Abi::parse(&deployment.abi).ok();
+    let Some(call) = external::import(
+        state.repos(),
+        deployment.id,
+        abi.as_ref(),
+        transaction,
+        &receipt,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    let status = match receipt.status() {
+        true => TransactionStatus::Success,
+        false => TransactionStatus::Reverted,
+    };
+    let sender = call.sender.clone().unwrap_or_default();
+    tracing::info!(
+        contract = %deployment.contract_name,
+        function = %call.function_signature,
+        %tx_hash,
+        "recorded external call"
+    );
+    history_stream::publish(state, call.id).await;
+
+    webhooks::notify(
+        state,
+        &Notification::call(
+            deployment,
+            status,
+            serde_json::json!({
+                "history_id": call.id,
+                "function": call.function_name,
+                "tx_hash": tx_hash,
+                "block_number": call.block_number,
+                "gas_used": call.gas_used,
+                "sender": sender,
+                "external": true,
+            }),
+        ),
+    )
+    .await;
+
+    Ok(Some(ActivityNotice {
+        contract: deployment.contract_name.clone(),
+        network: network.name.clone(),
+        address: deployment.address.to_string(),
+        function: call.function_signature,
+        sender,
+        status,
+        tx_hash,
+        explorer_url: network.explorer_url.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::fake::FakeRpc;
+    use alloy::primitives::B256;
+    use smolder_core::{BytecodeHash, ChainId};
+    use smolder_db::{
+        CallHistoryFilter, Database, MemoryDatabase, NewContract, NewDeployment, NewNetwork,
+        Repositories,
+    };
+    use std::sync::Arc;
+
+    const TOKEN: &str = "0x00000000000000000000000000000000000000cc";
+    const SENDER: &str = "0x1111111111111111111111111111111111111111";
+    const ABI: &str = r#"[{"type":"function","name":"pause","inputs":[],"outputs":[],"stateMutability":"nonpayable"}]"#;
+
+    /// Node at block 11 whose block 11 holds one call to the token and one
+    /// transfer elsewhere
+    fn fake_node() -> FakeRpc {
+        FakeRpc::new(31337, 11)
+            .with_block(
+                11,
+                vec![
+                    transaction(tx_hash(1), TOKEN),
+                    transaction(tx_hash(2), &Address::repeat_byte(0xdd).to_string()),
+                ],
+            )
+            .with_receipt(serde_json::json!({
+                "blockHash": B256::repeat_byte(0xbb),
+                "blockNumber": "0xb",
+                "effectiveGasPrice": "0x2",
+                "from": SENDER,
+                "to": TOKEN,
+            }))
+            .with_block_hashes(|_| B256::repeat_byte(0xbb))
+    }
+
+    fn tx_hash(byte: u8) -> B256 {
+        B256::repeat_byte(byte)
+    }
+
+    fn transaction(hash: B256, to: &str) -> Transaction {
+        serde_json::from_value(serde_json::json!({
+            "hash": hash,
+            "type": "0x0",
+            "nonce": "0x1",
+            "blockHash": B256::repeat_byte(0xbb),
+            "blockNumber": "0xb",
+            "transactionIndex": "0x0",
+            "from": SENDER,
+            "to": to,
+            "value": "0x0",
+            "gasPrice": "0x1",
+            "gas": "0x5208",
+            // pause()
+            "input": "0x8456cb59",
+            "v": "0x1b",
+            "r": "0x1",
+            "s": "0x1",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scan_imports_external_calls() {
+        let repos = MemoryDatabase::new();
+        let network = repos
+            .networks()
+            .upsert(&NewNetwork {
+                name: "local".to_string(),
+                chain_id: ChainId(31337),
+                rpc_url: "http://localhost:8545".to_string(),
+                explorer_url: None,
+            })
+            .await
+            .unwrap();
+        let contract = repos
+            .contracts()
+            .upsert(&NewContract {
+                name: "Token".to_string(),
+                source_path: "src/Token.sol".to_string(),
+                abi: ABI.to_string(),
+                bytecode_hash: BytecodeHash::of("Token"),
+                storage_layout: None,
+                compiler: None,
+            })
+            .await
+            .unwrap();
+        repos
+            .deployments()
+            .create(&NewDeployment {
+                contract_id: contract.id,
+                network_id: network.id,
+                address: TOKEN.parse().unwrap(),
+                deployer: SENDER.to_string(),
+                tx_hash: None,
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            })
+            .await
+            .unwrap();
+
+        let db = Database::connect_to(":memory:").await.unwrap();
+        let state = AppState::new(db, Arc::new(crate::forge::FileSystemArtifactLoader::new()))
+            .with_repositories(Arc::new(repos))
+            .with_rpc(Arc::new(fake_node()));
+
+        // The first scan only places the cursor past the latest block
+        let mut cursors = HashMap::new();
+        assert!(scan(&state, &network, &mut cursors).await.is_empty());
+        cursors.insert(network.name.clone(), 11);
+
+        let notices = scan(&state, &network, &mut cursors).await;
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].function, "pause()");
+        assert_eq!(notices[0].status, TransactionStatus::Success);
+        assert_eq!(cursors[&network.name], 12);
+
+        let calls = state
+            .repos()
+            .call_history()
+            .list_views(CallHistoryFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].function_name, "pause");
+        assert_eq!(calls[0].sender.as_deref(), Some(SENDER));
+        assert_eq!(calls[0].fee.as_deref(), Some("42000"));
+
+        // Rescanning the block finds the call already recorded
+        cursors.insert(network.name.clone(), 11);
+        assert!(scan(&state, &network, &mut cursors).await.is_empty());
+    }
+}
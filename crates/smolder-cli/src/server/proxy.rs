@@ -0,0 +1,98 @@
+//! Running behind a reverse proxy
+//!
+//! A proxy may mount the server under a sub-path, which the API and the
+//! dashboard are nested under, and reports the real client in
+//! `X-Forwarded-For`. That header is only trusted when `serve` is told it
+//! sits behind a proxy, since clients can set it to anything otherwise, and
+//! then only its last entry, the one the proxy appended.
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::ConnectInfo;
+use axum::http::{HeaderName, Request};
+
+/// Header a proxy lists the client and the proxies it went through in
+pub const FORWARDED_FOR_HEADER: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// Normalize a `--base-path` to `/segment/...` without a trailing slash,
+/// or an empty string for the root
+pub fn parse_base_path(value: &str) -> Result<String, String> {
+    let trimmed = value.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+    if trimmed
+        .split('/')
+        .any(|segment| segment.is_empty() || segment == "." || segment == "..")
+    {
+        return Err(format!("invalid base path '{}'", value));
+    }
+    if !trimmed
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/-_.~".contains(c))
+    {
+        return Err(format!(
+            "base path '{}' may only contain letters, digits and -_.~",
+            value
+        ));
+    }
+    Ok(format!("/{}", trimmed))
+}
+
+/// The client that made `request`: the last address in `X-Forwarded-For`
+/// when `trust_forwarded`, otherwise the peer of the connection
+///
+/// Entries before the last are whatever the client sent, so can't be trusted.
+pub fn client_ip<B>(request: &Request<B>, trust_forwarded: bool) -> Option<IpAddr> {
+    if trust_forwarded {
+        let forwarded = request
+            .headers()
+            .get(FORWARDED_FOR_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_base_path() {
+        assert_eq!(parse_base_path("").unwrap(), "");
+        assert_eq!(parse_base_path("/").unwrap(), "");
+        assert_eq!(parse_base_path("smolder").unwrap(), "/smolder");
+        assert_eq!(
+            parse_base_path("/tools/smolder/").unwrap(),
+            "/tools/smolder"
+        );
+        assert!(parse_base_path("/a//b").is_err());
+        assert!(parse_base_path("/../etc").is_err());
+        assert!(parse_base_path("/a b").is_err());
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let peer: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let mut request = Request::builder()
+            .header(FORWARDED_FOR_HEADER, "198.51.100.1, 203.0.113.7")
+            .body(())
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer));
+
+        // The client forged the first entry; the proxy appended the last
+        assert_eq!(
+            client_ip(&request, true),
+            Some("203.0.113.7".parse().unwrap())
+        );
+        assert_eq!(client_ip(&request, false), Some(peer.ip()));
+    }
+}
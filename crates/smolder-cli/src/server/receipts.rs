@@ -0,0 +1,259 @@
+//! Follow transactions sent through the API until they are mined
+//!
+//! Once the receipt arrives, and the transaction is buried under the
+//! network's `confirmations`, the call history entry gets its final status,
+//! block (with its hash, for [`reorgs`](super::reorgs)) and gas, contracts
+//! the transaction created are registered (see [`factories`]), and the
+//! events the deployment emitted are decoded. All are passed on to
+//! [`webhooks::notify`]. A transaction its private relay drops
+//! fails the entry instead.
+//!
+//! The wait runs as a [job](super::jobs), so it resumes after a restart and
+//! shows in `GET /api/jobs`. Each attempt waits up to [`ATTEMPT_TIMEOUT`];
+//! the entry is left pending once all of them timed out.
+
+use std::time::Duration;
+
+use alloy::rpc::types::TransactionReceipt;
+use serde::{Deserialize, Serialize};
+use smolder_core::{decode_event_log, Abi, Error, Result, TxHash};
+use smolder_db::{CallHistoryUpdate, DeploymentId, DeploymentView, RecordKind, TransactionStatus};
+use tracing::Instrument;
+
+use super::factories;
+use super::history_stream;
+use super::jobs;
+use super::webhooks::{self, Notification};
+use super::AppState;
+use crate::{relay, rpc};
+
+/// Kind of the jobs waiting for receipts
+pub const JOB_KIND: &str = "receipt";
+
+/// Attempts at getting a receipt before the call is left pending
+pub const MAX_ATTEMPTS: i64 = 10;
+
+/// How long one attempt waits for the transaction to be mined and confirmed
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Payload of a receipt job
+///
+/// The RPC URL and confirmations are read from the network when the job
+/// runs, so no credential is stored with the job.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReceiptJob {
+    pub history_id: i64,
+    pub deployment_id: DeploymentId,
+    pub function_name: String,
+    pub tx_hash: TxHash,
+    /// Private relay the transaction was submitted to, which may drop it
+    pub relay_url: Option<String>,
+}
+
+/// A transaction sent against a deployment, recorded in call history
+pub struct SentCall {
+    pub history_id: i64,
+    pub deployment: DeploymentView,
+    pub function_name: String,
+    pub tx_hash: TxHash,
+    pub rpc_url: String,
+    /// Private relay the transaction was submitted to, which may drop it
+    pub relay_url: Option<String>,
+    /// Blocks the transaction must be buried under, counting its own,
+    /// before the call gets its status
+    pub confirmations: u32,
+}
+
+/// Queue a job waiting for the transaction of `job` to be mined
+///
+/// Failures are logged: the transaction was sent either way.
+pub async fn track(state: &AppState, job: ReceiptJob) {
+    if let Err(e) = jobs::enqueue(state, JOB_KIND, &job, MAX_ATTEMPTS).await {
+        tracing::warn!(history_id = job.history_id, error = %e, "could not queue receipt job");
+    }
+}
+
+/// Attempt a receipt job, failing when the transaction wasn't mined in time
+pub async fn run(state: &AppState, job: ReceiptJob) -> Result<()> {
+    let repos = state.repos();
+    let deployment = repos
+        .deployments()
+        .get_view_by_id(job.deployment_id)
+        .await?
+        .ok_or(Error::DeploymentNotFoundById(job.deployment_id))?;
+    let network = repos
+        .networks()
+        .get_by_name(&deployment.network_name)
+        .await?
+        .ok_or_else(|| Error::NetworkNotFound(deployment.network_name.clone()))?;
+
+    let span = rpc::span(deployment.chain_id);
+    let call = SentCall {
+        history_id: job.history_id,
+        deployment,
+        function_name: job.function_name,
+        tx_hash: job.tx_hash,
+        rpc_url: network.rpc_url.expose().to_string(),
+        relay_url: job.relay_url,
+        confirmations: network.confirmations,
+    };
+    finalize(state, &call).instrument(span).await
+}
+
+async fn finalize(state: &AppState, call: &SentCall) -> Result<()> {
+    let mined = rpc::wait_for_receipt(&call.rpc_url, &call.tx_hash, ATTEMPT_TIMEOUT);
+    let outcome = match &call.relay_url {
+        Some(relay_url) => tokio::select! {
+            outcome = mined => outcome,
+            reason = relay::wait_for_drop(relay_url, &call.tx_hash, ATTEMPT_TIMEOUT) => {
+                dropped(state, call, reason).await;
+                return Ok(());
+            }
+        },
+        None => mined.await,
+    };
+    let receipt = match outcome {
+        Ok(receipt) if call.confirmations > 1 => {
+            rpc::wait_for_confirmations(&call.rpc_url, receipt, call.confirmations, ATTEMPT_TIMEOUT)
+                .await?
+        }
+        outcome => outcome?,
+    };
+
+    let status = if receipt.status() {
+        TransactionStatus::Success
+    } else {
+        TransactionStatus::Reverted
+    };
+    let update = CallHistoryUpdate {
+        result: None,
+        tx_hash: Some(call.tx_hash),
+        block_number: receipt.block_number.map(|b| b as i64),
+        gas_used: Some(receipt.gas_used as i64),
+        gas_price: Some(receipt.effective_gas_price.to_string()),
+        status,
+        error_message: None,
+    };
+    if let Err(e) = state
+        .repos()
+        .call_history()
+        .update(call.history_id, &update)
+        .await
+    {
+        tracing::warn!(history_id = call.history_id, error = %e, "could not finalize call history");
+    }
+    history_stream::publish(state, call.history_id).await;
+    // Kept to notice reorgs that drop the transaction from this block
+    if let (Some(number), Some(hash)) = (update.block_number, receipt.block_hash) {
+        if let Err(e) = state
+            .repos()
+            .reorgs()
+            .set_block(RecordKind::Call, call.history_id, number, &hash.to_string())
+            .await
+        {
+            tracing::warn!(history_id = call.history_id, error = %e, "could not record block hash");
+        }
+    }
+
+    let created = match status {
+        TransactionStatus::Success => factories::register(state, call, update.block_number).await,
+        _ => Vec::new(),
+    };
+
+    webhooks::notify(
+        state,
+        &Notification::call(
+            &call.deployment,
+            status,
+            serde_json::json!({
+                "history_id": call.history_id,
+                "function": call.function_name,
+                "tx_hash": call.tx_hash,
+                "block_number": update.block_number,
+                "gas_used": update.gas_used,
+                "created": created
+                    .iter()
+                    .map(|d| serde_json::json!({ "deployment_id": d.id, "address": d.address }))
+                    .collect::<Vec<_>>(),
+            }),
+        ),
+    )
+    .await;
+
+    for (name, data) in decode_events(&call.deployment, &receipt) {
+        webhooks::notify(state, &Notification::event(&call.deployment, &name, data)).await;
+    }
+    Ok(())
+}
+
+/// Fail the call of a transaction its private relay gave up on
+async fn dropped(state: &AppState, call: &SentCall, reason: String) {
+    let update = CallHistoryUpdate {
+        result: None,
+        tx_hash: Some(call.tx_hash),
+        block_number: None,
+        gas_used: None,
+        gas_price: None,
+        status: TransactionStatus::Failed,
+        error_message: Some(reason.clone()),
+    };
+    if let Err(e) = state
+        .repos()
+        .call_history()
+        .update(call.history_id, &update)
+        .await
+    {
+        tracing::warn!(history_id = call.history_id, error = %e, "could not finalize call history");
+    }
+    history_stream::publish(state, call.history_id).await;
+
+    webhooks::notify(
+        state,
+        &Notification::call(
+            &call.deployment,
+            TransactionStatus::Failed,
+            serde_json::json!({
+                "history_id": call.history_id,
+                "function": call.function_name,
+                "tx_hash": call.tx_hash,
+                "error": reason,
+            }),
+        ),
+    )
+    .await;
+}
+
+/// Decode the logs `deployment` emitted in `receipt` against its ABI
+///
+/// Logs from other addresses or with unknown selectors are skipped.
+fn decode_events(
+    deployment: &DeploymentView,
+    receipt: &TransactionReceipt,
+) -> Vec<(String, serde_json::Value)> {
+    let Ok(abi) = Abi::parse(&deployment.abi) else {
+        return Vec::new();
+    };
+    let address = *deployment.address;
+
+    receipt
+        .inner
+        .logs()
+        .iter()
+        .filter(|log| log.address() == address)
+        .filter_map(|log| {
+            let topics = log.topics();
+            let event = abi.event_by_selector(*topics.first()?)?;
+            let params = decode_event_log(event, topics, &log.data().data).ok()?;
+            Some((
+                event.name.clone(),
+                serde_json::json!({
+                    "signature": event.signature(),
+                    "tx_hash": log.transaction_hash.map(|h| h.to_string()),
+                    "block_number": log.block_number,
+                    "log_index": log.log_index,
+                    "params": params,
+                }),
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,445 @@
+//! Re-check recently mined deployments and calls for chain reorgs
+//!
+//! Each interval, the records of every network mined within its
+//! `reorg_depth` of the head, or marked reorged, are checked: the block
+//! hash recorded for each is compared with the node's block at that height.
+//! When the block was replaced, the transaction's receipt tells where it is
+//! now. A transaction that left its block is marked reorged, and once it is
+//! mined again `reorg_depth` blocks deep the record takes its new block and
+//! outcome and the mark is cleared. Records first seen without a block hash
+//! take the one of their receipt.
+//!
+//! Once the records are checked, those mined `confirmations` deep are
+//! confirmed and those at or below the node's `finalized` block finalized.
+//!
+//! Reorged and re-confirmed calls are passed on to [`webhooks::notify`] with
+//! `reorged` in their data.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use alloy::primitives::B256;
+use alloy::rpc::types::TransactionReceipt;
+use smolder_core::Result;
+use smolder_db::{CallHistoryUpdate, MinedRecord, Network, RecordKind, TransactionStatus};
+use tracing::Instrument;
+
+use super::history_stream;
+use super::webhooks::{self, Notification};
+use super::AppState;
+use crate::rpc;
+
+/// Default time between two checks of a network
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Check every network each `interval` in the background until shutdown
+pub fn start(state: &AppState, interval: Duration) {
+    let task_state = state.clone();
+    state.spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => check_all(&task_state).await,
+                _ = task_state.shutdown_token().cancelled() => break,
+            }
+        }
+    });
+}
+
+async fn check_all(state: &AppState) {
+    let networks = match state.repos().networks().list().await {
+        Ok(networks) => networks,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not list networks to check for reorgs");
+            return;
+        }
+    };
+
+    for network in networks {
+        if let Err(e) = check(state, &network)
+            .instrument(rpc::span(network.chain_id))
+            .await
+        {
+            tracing::debug!(network = %network.name, error = %e, "reorg check failed");
+        }
+    }
+}
+
+/// Re-check the unsettled records of `network`, then advance their finality
+///
+/// Stops at the first node error; the records left are checked next time.
+async fn check(state: &AppState, network: &Network) -> Result<()> {
+    let latest = state
+        .rpc()
+        .block_number(network.rpc_url.expose())
+        .await
+        .map_err(|e| smolder_core::Error::Rpc(e.to_string()))? as i64;
+    let depth = i64::from(network.reorg_depth);
+    let records = state
+        .repos()
+        .reorgs()
+        .unsettled(network.id, latest - depth)
+        .await?;
+
+    // Hashes of the node's blocks, by height
+    let mut blocks: HashMap<i64, Option<B256>> = HashMap::new();
+    for record in records {
+        if let (None, Some(recorded)) = (&record.reorged_at, &record.block_hash) {
+            let canonical = match blocks.get(&record.block_number) {
+                Some(hash) => *hash,
+                None => {
+                    let hash = state
+                        .rpc()
+                        .block_hash(network.rpc_url.expose(), record.block_number as u64)
+                        .await?;
+                    blocks.insert(record.block_number, hash);
+                    hash
+                }
+            };
+            // Still in its block, or a node lagging behind the recording
+            if canonical.is_none_or(|hash| hash.to_string() == *recorded) {
+                continue;
+            }
+        }
+
+        let receipt = state
+            .rpc()
+            .receipt(network.rpc_url.expose(), &record.tx_hash)
+            .await?;
+        settle(state, &record, receipt, latest, depth).await?;
+    }
+
+    // Nodes without finality tags only get records confirmed
+    let finalized = match state.rpc().finalized_block(network.rpc_url.expose()).await {
+        Ok(finalized) => finalized.map(|number| number as i64),
+        Err(e) => {
+            tracing::debug!(network = %network.name, error = %e, "no finalized block");
+            None
+        }
+    };
+    let confirmed = latest - i64::from(network.confirmations) + 1;
+    state
+        .repos()
+        .reorgs()
+        .advance_finality(network.id, confirmed, finalized)
+        .await?;
+    Ok(())
+}
+
+/// Follow `record` to where its transaction is mined now, per `receipt`
+async fn settle(
+    state: &AppState,
+    record: &MinedRecord,
+    receipt: Option<TransactionReceipt>,
+    latest: i64,
+    depth: i64,
+) -> Result<()> {
+    let mut reorged = record.reorged_at.is_some();
+    let mined = receipt.and_then(|receipt| {
+        let number = receipt.block_number? as i64;
+        let hash = receipt.block_hash?.to_string();
+        Some((number, hash, receipt))
+    });
+    let Some((number, hash, receipt)) = mined else {
+        if !reorged {
+            mark_reorged(state, record).await?;
+        }
+        return Ok(());
+    };
+
+    let moved =
+        number != record.block_number || record.block_hash.as_ref().is_some_and(|h| *h != hash);
+    if moved && !reorged {
+        mark_reorged(state, record).await?;
+        reorged = true;
+    }
+    if moved || record.block_hash.is_none() {
+        state
+            .repos()
+            .reorgs()
+            .set_block(record.kind, record.id, number, &hash)
+            .await?;
+    }
+    if reorged && latest - number >= depth {
+        reconfirm(state, record, &receipt).await?;
+    }
+    Ok(())
+}
+
+async fn mark_reorged(state: &AppState, record: &MinedRecord) -> Result<()> {
+    state
+        .repos()
+        .reorgs()
+        .set_reorged(record.kind, record.id, true)
+        .await?;
+    tracing::warn!(
+        kind = %record.kind,
+        id = record.id,
+        tx_hash = %record.tx_hash,
+        block = record.block_number,
+        "transaction dropped from its block by a reorg"
+    );
+    notify(state, record, None).await;
+    Ok(())
+}
+
+/// Clear the mark of a record whose transaction is buried deep enough in
+/// its new block
+///
+/// A deployment whose transaction now reverts created no contract, so it
+/// stays marked.
+async fn reconfirm(
+    state: &AppState,
+    record: &MinedRecord,
+    receipt: &TransactionReceipt,
+) -> Result<()> {
+    let status = match receipt.status() {
+        true => TransactionStatus::Success,
+        false => TransactionStatus::Reverted,
+    };
+    match record.kind {
+        RecordKind::Deployment if status != TransactionStatus::Success => {
+            tracing::warn!(
+                id = record.id,
+                tx_hash = %record.tx_hash,
+                "deployment transaction reverted after a reorg"
+            );
+            return Ok(());
+        }
+        RecordKind::Deployment => {}
+        RecordKind::Call => {
+            let update = CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(record.tx_hash),
+                block_number: receipt.block_number.map(|b| b as i64),
+                gas_used: Some(receipt.gas_used as i64),
+                gas_price: Some(receipt.effective_gas_price.to_string()),
+                status,
+                error_message: None,
+            };
+            state
+                .repos()
+                .call_history()
+                .update(record.id, &update)
+                .await?;
+        }
+    }
+    state
+        .repos()
+        .reorgs()
+        .set_reorged(record.kind, record.id, false)
+        .await?;
+    tracing::info!(
+        kind = %record.kind,
+        id = record.id,
+        tx_hash = %record.tx_hash,
+        block = ?receipt.block_number,
+        "transaction re-confirmed after a reorg"
+    );
+    notify(state, record, Some((status, receipt))).await;
+    Ok(())
+}
+
+/// Tell webhooks and history subscribers a call was dropped by a reorg, or
+/// re-confirmed with `outcome`
+async fn notify(
+    state: &AppState,
+    record: &MinedRecord,
+    outcome: Option<(TransactionStatus, &TransactionReceipt)>,
+) {
+    if record.kind != RecordKind::Call {
+        return;
+    }
+    history_stream::publish(state, record.id).await;
+    let call = match state.repos().call_history().get_by_id(record.id).await {
+        Ok(Some(call)) => call,
+        _ => return,
+    };
+    let deployment = match state
+        .repos()
+        .deployments()
+        .get_view_by_id(call.deployment_id)
+        .await
+    {
+        Ok(Some(deployment)) => deployment,
+        _ => return,
+    };
+
+    let (status, data) = match outcome {
+        None => (
+            call.status.unwrap_or(TransactionStatus::Pending),
+            serde_json::json!({
+                "history_id": call.id,
+                "function": call.function_name,
+                "tx_hash": record.tx_hash,
+                "block_number": record.block_number,
+                "reorged": true,
+            }),
+        ),
+        Some((status, receipt)) => (
+            status,
+            serde_json::json!({
+                "history_id": call.id,
+                "function": call.function_name,
+                "tx_hash": record.tx_hash,
+                "block_number": receipt.block_number,
+                "gas_used": receipt.gas_used,
+                "reorged": false,
+            }),
+        ),
+    };
+    webhooks::notify(state, &Notification::call(&deployment, status, data)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::fake::FakeRpc;
+    use alloy::primitives::Address;
+    use smolder_core::{BytecodeHash, CallType, ChainId, Finality, TxHash};
+    use smolder_db::{
+        Database, MemoryDatabase, NewCallHistory, NewContract, NewDeployment, NewNetwork,
+        Repositories,
+    };
+    use std::sync::Arc;
+
+    /// Node on which the transaction recorded in block 12 was reorged into
+    /// block 13, finalizing blocks 10 deep
+    fn fake_node() -> FakeRpc {
+        FakeRpc::new(31337, 20)
+            .with_finality_depth(10)
+            .with_receipt(serde_json::json!({
+                "blockHash": B256::repeat_byte(0x13),
+                "blockNumber": "0xd",
+                "effectiveGasPrice": "0x3",
+                "from": Address::repeat_byte(0x11),
+                "to": Address::repeat_byte(0xcc),
+            }))
+            .with_block_hashes(|number| B256::repeat_byte(number as u8))
+    }
+
+    #[tokio::test]
+    async fn test_reorged_call_is_reconfirmed_at_depth() {
+        let repos = MemoryDatabase::new();
+        let network = repos
+            .networks()
+            .upsert(&NewNetwork {
+                name: "local".to_string(),
+                chain_id: ChainId(31337),
+                rpc_url: "http://localhost:8545".to_string(),
+                explorer_url: None,
+            })
+            .await
+            .unwrap();
+        let contract = repos
+            .contracts()
+            .upsert(&NewContract {
+                name: "Token".to_string(),
+                source_path: "src/Token.sol".to_string(),
+                abi: "[]".to_string(),
+                bytecode_hash: BytecodeHash::of("Token"),
+                storage_layout: None,
+                compiler: None,
+            })
+            .await
+            .unwrap();
+        let deployment = repos
+            .deployments()
+            .create(&NewDeployment {
+                contract_id: contract.id,
+                network_id: network.id,
+                address: Address::repeat_byte(0xcc).into(),
+                deployer: Address::repeat_byte(0x11).to_string(),
+                tx_hash: None,
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            })
+            .await
+            .unwrap();
+        let call = repos
+            .call_history()
+            .import_external(
+                &NewCallHistory {
+                    deployment_id: deployment.id,
+                    wallet_id: None,
+                    function_name: "pause".to_string(),
+                    function_signature: "pause()".to_string(),
+                    input_params: "[]".to_string(),
+                    call_type: CallType::Write,
+                },
+                &Address::repeat_byte(0x11).to_string(),
+                &CallHistoryUpdate {
+                    result: None,
+                    tx_hash: Some(TxHash(B256::repeat_byte(0xab))),
+                    block_number: Some(12),
+                    gas_used: Some(21_000),
+                    gas_price: Some("2".to_string()),
+                    status: TransactionStatus::Success,
+                    error_message: None,
+                },
+            )
+            .await
+            .unwrap();
+        // Recorded in a block 12 the node no longer has
+        repos
+            .reorgs()
+            .set_block(
+                RecordKind::Call,
+                call.id,
+                12,
+                &B256::repeat_byte(0xee).to_string(),
+            )
+            .await
+            .unwrap();
+
+        let db = Database::connect_to(":memory:").await.unwrap();
+        let node = Arc::new(fake_node());
+        let state = AppState::new(db, Arc::new(crate::forge::FileSystemArtifactLoader::new()))
+            .with_repositories(Arc::new(repos))
+            .with_rpc(node.clone());
+
+        check(&state, &network).await.unwrap();
+        let reorged = state
+            .repos()
+            .call_history()
+            .get_by_id(call.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(reorged.reorged_at.is_some());
+        assert_eq!(reorged.finality, Some(Finality::Pending));
+        assert_eq!(reorged.block_number, Some(13));
+        assert_eq!(
+            reorged.block_hash,
+            Some(B256::repeat_byte(0x13).to_string())
+        );
+
+        // Block 13 is 12 deep at block 25
+        node.set_latest(25);
+        check(&state, &network).await.unwrap();
+        let confirmed = state
+            .repos()
+            .call_history()
+            .get_by_id(call.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(confirmed.reorged_at, None);
+        assert_eq!(confirmed.status, Some(TransactionStatus::Success));
+        assert_eq!(confirmed.fee.as_deref(), Some("63000"));
+        // Block 13 is below the finalized block 15
+        assert_eq!(confirmed.finality, Some(Finality::Finalized));
+        assert!(state
+            .repos()
+            .reorgs()
+            .unsettled(network.id, 25 - 12)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
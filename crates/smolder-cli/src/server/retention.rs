@@ -0,0 +1,44 @@
+//! Enforce the `[retention]` policy of smolder.toml while the server runs
+//!
+//! Old call history is deleted once an hour, and the database VACUUMed
+//! whenever something was, so a long-running server does not grow forever.
+
+use std::time::Duration;
+
+use smolder_db::HistoryRetention;
+
+use super::AppState;
+
+/// Time between two prunes
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Prune call history per `retention` in the background until shutdown
+pub fn start(state: &AppState, retention: HistoryRetention) {
+    let task_state = state.clone();
+    state.spawn(async move {
+        let mut ticker = tokio::time::interval(INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => prune(&task_state, &retention).await,
+                _ = task_state.shutdown_token().cancelled() => break,
+            }
+        }
+    });
+}
+
+async fn prune(state: &AppState, retention: &HistoryRetention) {
+    let deleted = match state.repos().call_history().prune(retention).await {
+        Ok(0) => return,
+        Ok(deleted) => deleted,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not prune call history");
+            return;
+        }
+    };
+    tracing::info!(calls = deleted, "pruned call history");
+
+    if let Err(e) = state.db().vacuum().await {
+        tracing::warn!(error = %e, "could not vacuum the database");
+    }
+}
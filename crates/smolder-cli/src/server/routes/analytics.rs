@@ -0,0 +1,142 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use smolder_db::{CostFilter, CostStat, GasInterval, GasStat, GasStatsFilter, RegistryStats};
+use utoipa::IntoParams;
+
+use crate::prices;
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/analytics/gas", get(gas))
+        .route("/analytics/costs", get(costs))
+        .route("/stats", get(stats))
+}
+
+/// Activity entries returned when `recent` isn't given
+const DEFAULT_RECENT: u32 = 20;
+
+/// Most activity entries one request may ask for
+const MAX_RECENT: u32 = 100;
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GasQuery {
+    pub network: Option<String>,
+    pub contract: Option<String>,
+    /// Only include gas spent at or after this date
+    pub since: Option<String>,
+    /// Only include gas spent at or before this date
+    pub until: Option<String>,
+    /// Split figures by day, week or month
+    pub interval: Option<GasInterval>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analytics/gas",
+    tag = "analytics",
+    params(GasQuery),
+    responses((
+        status = 200,
+        description = "Gas used per function of each contract version, from successful calls \
+            and deployments (as `constructor`)",
+        body = Vec<GasStat>
+    ))
+)]
+async fn gas(
+    State(state): State<AppState>,
+    Query(query): Query<GasQuery>,
+) -> Result<Json<Vec<GasStat>>, ApiError> {
+    let filter = GasStatsFilter {
+        network: query.network,
+        contract: query.contract,
+        since: query.since,
+        until: query.until,
+        interval: query.interval,
+    };
+    let stats = state.repos().analytics().gas_stats(&filter).await?;
+    Ok(Json(stats))
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CostQuery {
+    pub network: Option<String>,
+    pub contract: Option<String>,
+    /// Wallet name, or deployer address
+    pub wallet: Option<String>,
+    /// Only include fees paid at or after this date
+    pub since: Option<String>,
+    /// Only include fees paid at or before this date
+    pub until: Option<String>,
+    /// Split costs by day, week or month
+    pub interval: Option<GasInterval>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analytics/costs",
+    tag = "analytics",
+    params(CostQuery),
+    responses((
+        status = 200,
+        description = "Fees paid for deployments and sent calls, per network, contract and wallet, \
+            in wei of each network's native currency and approximately in USD when prices are \
+            known. Missing prices are fetched first when `[prices]` is configured",
+        body = Vec<CostStat>
+    ))
+)]
+async fn costs(
+    State(state): State<AppState>,
+    Query(query): Query<CostQuery>,
+) -> Result<Json<Vec<CostStat>>, ApiError> {
+    let filter = CostFilter {
+        network: query.network,
+        contract: query.contract,
+        wallet: query.wallet,
+        since: query.since,
+        until: query.until,
+        interval: query.interval,
+    };
+    if let Some(oracle) = state.prices() {
+        let unpriced = state.repos().analytics().unpriced_fees(&filter).await?;
+        prices::fill(state.repos(), oracle, &unpriced).await?;
+    }
+    let stats = state.repos().analytics().costs(&filter).await?;
+    Ok(Json(stats))
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    /// Number of recent deployments and calls to include (default 20, at
+    /// most 100)
+    pub recent: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/stats",
+    tag = "analytics",
+    params(StatsQuery),
+    responses((
+        status = 200,
+        description = "Registry counts, deployments and latest deploy per network, and the \
+            latest deployments and calls, for the dashboard home",
+        body = RegistryStats
+    ))
+)]
+async fn stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<RegistryStats>, ApiError> {
+    let recent = query.recent.unwrap_or(DEFAULT_RECENT).min(MAX_RECENT);
+    let stats = state.repos().analytics().stats(recent).await?;
+    Ok(Json(stats))
+}
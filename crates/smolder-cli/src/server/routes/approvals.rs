@@ -0,0 +1,315 @@
+use axum::{
+    body::to_bytes,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use smolder_core::{ApiRole, Error};
+use smolder_db::{
+    Approval, ApprovalId, ApprovalKind, ApprovalStatus, DeploymentId, Network, NewApproval,
+};
+use utoipa::IntoParams;
+
+use super::{deploy, interact};
+use crate::locks::Clearance;
+use crate::server::auth::{self, Caller};
+use crate::server::error::ApiError;
+use crate::server::limits;
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/approvals", get(list))
+        .route("/approvals/{id}", get(get_approval))
+        .route(
+            "/approvals/{id}/approve",
+            post(approve).route_layer(middleware::from_fn(limits::rpc)),
+        )
+        .route("/approvals/{id}/reject", post(reject))
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
+}
+
+/// Who is running a deploy or send
+pub(super) enum Requester {
+    /// A request made to the API, with the token it carried
+    Caller(Option<Caller>),
+    /// A held request replayed once approved, with the approving token
+    Approval { approved_by: String },
+}
+
+impl Requester {
+    pub(super) fn from_extension(caller: Option<Extension<Caller>>) -> Self {
+        Self::Caller(caller.map(|Extension(caller)| caller))
+    }
+
+    /// Who runs the request on locked networks, with the networks it confirmed
+    pub(super) fn clearance(&self, confirmed: impl IntoIterator<Item = String>) -> Clearance {
+        let actor = match self {
+            Self::Caller(caller) => caller.as_ref().map(|c| c.name.clone()),
+            Self::Approval { approved_by } => Some(approved_by.clone()),
+        };
+        Clearance::api(actor, confirmed)
+    }
+}
+
+/// Hold a deploy or send on a protected network until a second token
+/// approves it
+///
+/// Only admins may request on a protected network. Returns the `202
+/// Accepted` response to answer with, or `None` when the request can run now.
+pub(super) async fn hold(
+    state: &AppState,
+    requester: &Requester,
+    network: &Network,
+    kind: ApprovalKind,
+    deployment_id: Option<DeploymentId>,
+    summary: String,
+    request: &impl Serialize,
+) -> Result<Option<Response>, ApiError> {
+    let caller = match requester {
+        _ if !network.protected => return Ok(None),
+        Requester::Approval { .. } => return Ok(None),
+        Requester::Caller(Some(caller)) => caller,
+        Requester::Caller(None) => {
+            return Err(ApiError::forbidden(format!(
+                "Network '{}' is protected; create API tokens with `smolder token create` so a second one can approve requests",
+                network.name
+            )))
+        }
+    };
+    caller.require(
+        ApiRole::Admin,
+        &format!("requesting on protected network '{}'", network.name),
+    )?;
+
+    let approval = state
+        .repos()
+        .approvals()
+        .create(&NewApproval {
+            kind,
+            network_id: network.id,
+            deployment_id,
+            summary,
+            request: serde_json::to_string(request)?,
+            requested_by: caller.token_id,
+            ttl: state.approval_ttl(),
+        })
+        .await?;
+    tracing::info!(
+        approval_id = %approval.id,
+        network = %network.name,
+        requested_by = %caller.name,
+        "request held for approval"
+    );
+
+    Ok(Some((StatusCode::ACCEPTED, Json(approval)).into_response()))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListQuery {
+    /// Only list approvals with this status
+    status: Option<ApprovalStatus>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/approvals",
+    tag = "approvals",
+    params(ListQuery),
+    responses((status = 200, description = "Approvals, newest first", body = Vec<Approval>))
+)]
+async fn list(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<Approval>>, ApiError> {
+    state.repos().approvals().expire().await?;
+    Ok(Json(state.repos().approvals().list(query.status).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/approvals/{id}",
+    tag = "approvals",
+    params(("id" = i64, Path, description = "Approval ID")),
+    responses(
+        (status = 200, description = "The approval, with the result once executed", body = Approval),
+        (status = 404, description = "Approval not found", body = ApiError)
+    )
+)]
+async fn get_approval(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Approval>, ApiError> {
+    state.repos().approvals().expire().await?;
+    Ok(Json(fetch(&state, ApprovalId(id)).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/approvals/{id}/approve",
+    tag = "approvals",
+    params(("id" = i64, Path, description = "Approval ID")),
+    responses(
+        (
+            status = 200,
+            description = "Approved and executed; `approved` with the response as `result`, or `failed` with `error_message`",
+            body = Approval
+        ),
+        (status = 401, description = "No API token", body = ApiError),
+        (status = 403, description = "The token made the request itself or was created after it, or the requester is unknown", body = ApiError),
+        (status = 404, description = "Approval not found", body = ApiError),
+        (status = 409, description = "Already decided or expired", body = ApiError)
+    )
+)]
+async fn approve(
+    State(state): State<AppState>,
+    caller: Option<Extension<Caller>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Approval>, ApiError> {
+    let caller = require_caller(caller)?;
+    let approval = fetch(&state, ApprovalId(id)).await?;
+    match approval.requester_token_id {
+        None => {
+            return Err(ApiError::forbidden(
+                "The token that made this request is unknown, so it can't be told from the \
+                 approver; reject it and make the request again",
+            ))
+        }
+        Some(requester) if requester == caller.token_id => {
+            return Err(ApiError::forbidden(
+                "Requests must be approved by a different token than the one that made them",
+            ))
+        }
+        Some(_) => {}
+    }
+    // A token created after the request could have been minted by the requester
+    if approval
+        .newest_token_id
+        .is_none_or(|newest| caller.token_id.0 > newest.0)
+    {
+        return Err(ApiError::forbidden(
+            "Requests must be approved by a token that existed when they were made",
+        ));
+    }
+    decide(&state, &approval, ApprovalStatus::Approved, &caller).await?;
+
+    let outcome = match approval.kind {
+        ApprovalKind::Deploy => {
+            deploy::execute(
+                &state,
+                serde_json::from_str(&approval.request)?,
+                false,
+                Requester::Approval {
+                    approved_by: caller.name.clone(),
+                },
+            )
+            .await
+        }
+        ApprovalKind::Send => {
+            let deployment_id = approval
+                .deployment_id
+                .ok_or_else(|| ApiError::internal("Send approval without a deployment"))?;
+            interact::send(
+                &state,
+                deployment_id.0,
+                serde_json::from_str(&approval.request)?,
+                false,
+                Requester::Approval {
+                    approved_by: caller.name.clone(),
+                },
+            )
+            .await
+        }
+    };
+    let (result, error) = match outcome {
+        Ok(response) => match to_bytes(response.into_body(), usize::MAX).await {
+            Ok(body) => (Some(String::from_utf8_lossy(&body).into_owned()), None),
+            Err(e) => (None, Some(e.to_string())),
+        },
+        Err(e) => (None, Some(e.message)),
+    };
+    state
+        .repos()
+        .approvals()
+        .record_result(approval.id, result.as_deref(), error.as_deref())
+        .await?;
+    tracing::info!(
+        approval_id = %approval.id,
+        approved_by = %caller.name,
+        failed = error.is_some(),
+        "approved request executed"
+    );
+
+    Ok(Json(fetch(&state, approval.id).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/approvals/{id}/reject",
+    tag = "approvals",
+    params(("id" = i64, Path, description = "Approval ID")),
+    responses(
+        (status = 200, description = "Rejected; the request will not run", body = Approval),
+        (status = 401, description = "No API token", body = ApiError),
+        (status = 404, description = "Approval not found", body = ApiError),
+        (status = 409, description = "Already decided or expired", body = ApiError)
+    )
+)]
+async fn reject(
+    State(state): State<AppState>,
+    caller: Option<Extension<Caller>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Approval>, ApiError> {
+    let caller = require_caller(caller)?;
+    let approval = fetch(&state, ApprovalId(id)).await?;
+    decide(&state, &approval, ApprovalStatus::Rejected, &caller).await?;
+    Ok(Json(fetch(&state, approval.id).await?))
+}
+
+/// Deciding needs a token to tell the approver from the requester
+fn require_caller(caller: Option<Extension<Caller>>) -> Result<Caller, ApiError> {
+    caller
+        .map(|Extension(caller)| caller)
+        .ok_or_else(|| ApiError::unauthorized("Deciding on approvals needs an API token"))
+}
+
+async fn fetch(state: &AppState, id: ApprovalId) -> Result<Approval, ApiError> {
+    state
+        .repos()
+        .approvals()
+        .get(id)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::ApprovalNotFound(id)))
+}
+
+/// Approve or reject a pending approval, failing when it was decided, or
+/// expired, in the meantime
+async fn decide(
+    state: &AppState,
+    approval: &Approval,
+    status: ApprovalStatus,
+    caller: &Caller,
+) -> Result<(), ApiError> {
+    state.repos().approvals().expire().await?;
+    if state
+        .repos()
+        .approvals()
+        .decide(approval.id, status, caller.token_id)
+        .await?
+    {
+        return Ok(());
+    }
+    let current = fetch(state, approval.id).await?;
+    Err(ApiError::conflict(format!(
+        "Approval {} is {}",
+        approval.id, current.status
+    )))
+}
@@ -5,7 +5,8 @@ use axum::{
 };
 use serde::Serialize;
 use smolder_core::Error;
-use smolder_db::ContractRepository;
+use smolder_db::ContractFilter;
+use utoipa::ToSchema;
 
 use crate::forge::{ArtifactDetails, ArtifactInfo};
 use crate::server::error::ApiError;
@@ -17,13 +18,19 @@ pub fn router() -> Router<AppState> {
         .route("/artifacts/{name}", get(get_by_name))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ArtifactListItem {
     #[serde(flatten)]
     info: ArtifactInfo,
     in_registry: bool,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/artifacts",
+    tag = "artifacts",
+    responses((status = 200, description = "Compiled Forge artifacts", body = Vec<ArtifactListItem>))
+)]
 async fn list(State(state): State<AppState>) -> Result<Json<Vec<ArtifactListItem>>, ApiError> {
     // Get all artifacts from out/ directory
     let artifacts = state
@@ -32,7 +39,11 @@ async fn list(State(state): State<AppState>) -> Result<Json<Vec<ArtifactListItem
         .map_err(|e| ApiError::internal(e.to_string()))?;
 
     // Get contracts in registry to mark which artifacts are tracked
-    let contracts = ContractRepository::list(state.db()).await?;
+    let contracts = state
+        .repos()
+        .contracts()
+        .list(ContractFilter::default())
+        .await?;
 
     let registry_names: Vec<String> = contracts.into_iter().map(|c| c.name).collect();
 
@@ -47,13 +58,23 @@ async fn list(State(state): State<AppState>) -> Result<Json<Vec<ArtifactListItem
     Ok(Json(items))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ArtifactDetailsResponse {
     #[serde(flatten)]
     details: ArtifactDetails,
     in_registry: bool,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/artifacts/{name}",
+    tag = "artifacts",
+    params(("name" = String, Path, description = "Artifact (contract) name")),
+    responses(
+        (status = 200, description = "Artifact details", body = ArtifactDetailsResponse),
+        (status = 404, description = "Artifact not found", body = ApiError)
+    )
+)]
 async fn get_by_name(
     State(state): State<AppState>,
     Path(name): Path<String>,
@@ -67,7 +88,7 @@ async fn get_by_name(
     })?;
 
     // Check if in registry
-    let contract = ContractRepository::get_by_name(state.db(), &name).await?;
+    let contract = state.repos().contracts().get_by_name(&name).await?;
     let in_registry = contract.is_some();
 
     Ok(Json(ArtifactDetailsResponse {
@@ -1,32 +1,252 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
     routing::get,
     Json, Router,
 };
-use smolder_core::Error;
-use smolder_db::{Contract, ContractRepository};
+use serde::{Deserialize, Serialize};
+use smolder_core::{ApiRole, Error};
+use smolder_db::{
+    Contract, ContractFilter, ContractSort, DeploymentFilter, DeploymentView, SortOrder,
+};
+use utoipa::{IntoParams, ToSchema};
 
+use super::{abi_response, paginated};
+use crate::server::auth;
 use crate::server::error::ApiError;
+use crate::server::limits;
 use crate::server::AppState;
+use crate::sourcify::{self, Verification};
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/contracts", get(list))
-        .route("/contracts/{name}", get(get_by_name))
+        .route("/contracts/{name}", get(get_by_name).delete(remove))
+        .route("/contracts/{name}/abi", get(get_abi))
+        .route(
+            "/contracts/{name}/deployments",
+            get(list_deployments).route_layer(middleware::from_fn(limits::rpc)),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// Filter by contract name (substring match)
+    pub name: Option<String>,
+    pub sort: Option<ContractSort>,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
 }
 
-async fn list(State(state): State<AppState>) -> Result<Json<Vec<Contract>>, ApiError> {
-    let contracts = ContractRepository::list(state.db()).await?;
-    Ok(Json(contracts))
+#[utoipa::path(
+    get,
+    path = "/api/contracts",
+    tag = "contracts",
+    params(ListQuery),
+    responses((
+        status = 200,
+        description = "Matching contracts",
+        body = Vec<Contract>,
+        headers(("x-total-count" = i64, description = "Total matching items before pagination"))
+    ))
+)]
+async fn list(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let filter = ContractFilter {
+        name: query.name,
+        sort: query.sort,
+        order: query.order,
+        limit: query.limit,
+        offset: query.offset,
+    };
+
+    let total = state.repos().contracts().count(&filter).await?;
+    let contracts = state.repos().contracts().list(filter).await?;
+    Ok(paginated(total, contracts))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/contracts/{name}",
+    tag = "contracts",
+    params(("name" = String, Path, description = "Contract name")),
+    responses(
+        (status = 200, description = "Contract found", body = Contract),
+        (status = 404, description = "Contract not found", body = ApiError)
+    )
+)]
 async fn get_by_name(
     State(state): State<AppState>,
     Path(name): Path<String>,
 ) -> Result<Json<Contract>, ApiError> {
-    let contract = ContractRepository::get_by_name(state.db(), &name).await?;
+    let contract = state.repos().contracts().get_by_name(&name).await?;
 
     contract
         .map(Json)
         .ok_or_else(|| ApiError::from(Error::ContractNotFound(name)))
 }
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AbiQuery {
+    /// Return the ABI of the contract currently deployed on this network
+    /// instead of the latest compiled version
+    pub network: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/contracts/{name}/abi",
+    tag = "contracts",
+    params(("name" = String, Path, description = "Contract name"), AbiQuery),
+    responses(
+        (
+            status = 200,
+            description = "Raw contract ABI",
+            content_type = "application/json",
+            body = Object,
+            headers(
+                ("etag" = String, description = "Hash of the ABI, for If-None-Match"),
+                ("x-start-block" = i64, description = "Block the deployment was mined in, when `network` is given and the block is known")
+            )
+        ),
+        (status = 304, description = "ABI unchanged since the ETag in If-None-Match"),
+        (status = 404, description = "Contract or deployment not found", body = ApiError)
+    )
+)]
+async fn get_abi(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<AbiQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Some(network) = query.network else {
+        let contract = state
+            .repos()
+            .contracts()
+            .get_by_name(&name)
+            .await?
+            .ok_or_else(|| ApiError::from(Error::ContractNotFound(name)))?;
+        return Ok(abi_response(&headers, contract.abi, None));
+    };
+
+    let not_found = || {
+        ApiError::from(Error::DeploymentNotFound(format!(
+            "contract '{}' on network '{}'",
+            name, network
+        )))
+    };
+
+    let deployment = state
+        .repos()
+        .deployments()
+        .get_current(&name, &network)
+        .await?
+        .ok_or_else(not_found)?;
+    let view = state
+        .repos()
+        .deployments()
+        .get_view_by_id(deployment.id)
+        .await?
+        .ok_or_else(not_found)?;
+
+    Ok(abi_response(&headers, view.abi, view.block_number))
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeploymentsQuery {
+    /// Look each deployment up on Sourcify
+    #[serde(default)]
+    pub include_verification: bool,
+}
+
+/// The current deployment of a contract on one network
+#[derive(Serialize, ToSchema)]
+pub struct NetworkDeployment {
+    #[serde(flatten)]
+    pub deployment: DeploymentView,
+    /// Present with `include_verification=true` when Sourcify answered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/contracts/{name}/deployments",
+    tag = "contracts",
+    params(("name" = String, Path, description = "Contract name"), DeploymentsQuery),
+    responses(
+        (status = 200, description = "Current deployment on every network, by network", body = Vec<NetworkDeployment>),
+        (status = 404, description = "Contract not found", body = ApiError)
+    )
+)]
+async fn list_deployments(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<DeploymentsQuery>,
+) -> Result<Json<Vec<NetworkDeployment>>, ApiError> {
+    state
+        .repos()
+        .contracts()
+        .get_by_name(&name)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::ContractNotFound(name.clone())))?;
+
+    let filter = DeploymentFilter {
+        contract: Some(name),
+        ..DeploymentFilter::current()
+    };
+    let deployments = state.repos().deployments().list(filter).await?;
+    let verifications = match query.include_verification {
+        true => sourcify::lookup_all(&deployments).await,
+        false => vec![None; deployments.len()],
+    };
+
+    Ok(Json(
+        deployments
+            .into_iter()
+            .zip(verifications)
+            .map(|(deployment, verification)| NetworkDeployment {
+                deployment,
+                verification,
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/contracts/{name}",
+    tag = "contracts",
+    params(("name" = String, Path, description = "Contract name")),
+    responses(
+        (status = 204, description = "Contract and its deployments deleted"),
+        (status = 404, description = "Contract not found", body = ApiError)
+    )
+)]
+async fn remove(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .repos()
+        .contracts()
+        .get_by_name(&name)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::ContractNotFound(name.clone())))?;
+
+    state.repos().contracts().delete(&name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
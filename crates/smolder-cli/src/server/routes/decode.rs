@@ -0,0 +1,41 @@
+use axum::{extract::State, routing::post, Json, Router};
+use serde::Deserialize;
+use smolder_core::Error;
+use utoipa::ToSchema;
+
+use crate::decode::{self, DecodedCalldata};
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/decode", post(decode))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct DecodeRequest {
+    /// Calldata as hex, selector included
+    calldata: String,
+    /// Ask 4byte.directory when no stored ABI has the selector
+    #[serde(default)]
+    online: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/decode",
+    tag = "decode",
+    request_body = DecodeRequest,
+    responses(
+        (status = 200, description = "Functions the calldata decodes as, registry ones first", body = DecodedCalldata),
+        (status = 400, description = "Calldata is not hex or shorter than a selector", body = ApiError)
+    )
+)]
+async fn decode(
+    State(state): State<AppState>,
+    Json(payload): Json<DecodeRequest>,
+) -> Result<Json<DecodedCalldata>, ApiError> {
+    let calldata = hex::decode(payload.calldata.trim().trim_start_matches("0x"))
+        .map_err(|e| Error::Validation(format!("Invalid calldata: {}", e)))?;
+    let decoded = decode::decode(state.repos(), &calldata, payload.online).await?;
+    Ok(Json(decoded))
+}
@@ -1,47 +1,129 @@
-use alloy::dyn_abi::DynSolValue;
+use std::collections::BTreeMap;
+
 use alloy::hex;
-use alloy::network::{EthereumWallet, TransactionBuilder};
-use alloy::primitives::{keccak256, Bytes, U256};
-use alloy::providers::{Provider, ProviderBuilder};
-use alloy::rpc::types::TransactionRequest;
-use alloy::signers::local::PrivateKeySigner;
-use axum::{extract::State, routing::post, Json, Router};
+use alloy::primitives::Bytes;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
 use serde::{Deserialize, Serialize};
-use smolder_core::{decrypt_private_key, json_to_sol_value, Error, ParamInfo};
+use smolder_core::{
+    decrypt_private_key, encode_constructor_args, parse_value, Abi, BytecodeHash, Error,
+    EvmAddress, LayoutIssue, TxHash,
+};
 use smolder_db::{
-    ContractRepository, DeploymentId, DeploymentRepository, NetworkRepository, NewContract,
-    NewDeployment, WalletRepository,
+    Approval, ApprovalKind, DeploymentGroup, DeploymentGroupId, DeploymentGroupMember,
+    DeploymentId, NewContract, NewDeployment, TransactionStatus,
 };
-
+use tracing::Instrument;
+use utoipa::{IntoParams, ToSchema};
+
+use super::approvals::{self, Requester};
+use crate::deploy_group::{self, GroupRequest};
+use crate::layout;
+use crate::libraries;
+use crate::policy::{self, Intent};
+use crate::rpc::{self, Outgoing, PreflightWarning};
+use crate::server::auth::Caller;
 use crate::server::error::ApiError;
+use crate::server::limits;
+use crate::server::simulate::{SimulateQuery, SimulationResponse};
 use crate::server::AppState;
 
 pub fn router() -> Router<AppState> {
-    Router::new().route("/deploy", post(deploy_contract))
+    Router::new()
+        .route("/deploy", post(deploy_contract))
+        .route("/deploy/multi", post(deploy_multi))
+        .route("/deploy/groups", get(list_groups))
+        .route("/deploy/groups/{id}", get(get_group))
+        .route("/deploy/groups/{id}/resume", post(resume_group))
+        .route_layer(middleware::from_fn(limits::rpc))
 }
 
-#[derive(Deserialize)]
-struct DeployRequest {
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(super) struct DeployRequest {
     artifact_name: String,
     network_name: String,
     wallet_name: String,
     #[serde(default)]
     constructor_args: Vec<serde_json::Value>,
+    /// Wei, or an amount with a unit: `0.1 ether`, `30 gwei` or the
+    /// network's currency symbol
     #[serde(default)]
     value: Option<String>,
+    /// Geth state override set applied when simulating, keyed by address
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    state_overrides: Option<serde_json::Value>,
+    /// Library addresses, by name or `path:Name`; libraries not listed are
+    /// linked to their current deployment on the network
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    libraries: BTreeMap<String, String>,
+    /// The network's name, to deploy to it while it is locked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unlock_confirm: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct DeployResponse {
-    tx_hash: String,
-    contract_address: Option<String>,
+    tx_hash: TxHash,
+    contract_address: Option<EvmAddress>,
     deployment_id: Option<DeploymentId>,
+    gas_used: u64,
+    /// Code sizes over or close to the EIP-170 and EIP-3860 limits
+    size_warnings: Vec<String>,
+    /// Conflicts with the storage layout of the version this one replaces
+    layout_issues: Vec<LayoutIssue>,
+    /// What the checks before sending found, such as pending transactions
+    warnings: Vec<PreflightWarning>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/deploy",
+    tag = "deploy",
+    params(SimulateQuery),
+    request_body = DeployRequest,
+    responses(
+        (
+            status = 200,
+            description = "Deployment transaction submitted, or a SimulationResponse with `simulate=true`",
+            body = DeployResponse
+        ),
+        (status = 202, description = "Held until a second API token approves it, on protected networks", body = Approval),
+        (status = 400, description = "Invalid artifact or constructor arguments", body = ApiError),
+        (status = 403, description = "Protected network and no API token", body = ApiError),
+        (status = 404, description = "Artifact, network or wallet not found", body = ApiError),
+        (status = 423, description = "Locked network and no matching `unlock_confirm`", body = ApiError),
+        (status = 502, description = "RPC or transaction failure", body = ApiError)
+    )
+)]
 async fn deploy_contract(
     State(state): State<AppState>,
+    Query(query): Query<SimulateQuery>,
+    caller: Option<Extension<Caller>>,
     Json(payload): Json<DeployRequest>,
-) -> Result<Json<DeployResponse>, ApiError> {
+) -> Result<Response, ApiError> {
+    execute(
+        &state,
+        payload,
+        query.simulate,
+        Requester::from_extension(caller),
+    )
+    .await
+}
+
+/// Deploy, simulate or hold a deployment for approval
+pub(super) async fn execute(
+    state: &AppState,
+    payload: DeployRequest,
+    simulate: bool,
+    requester: Requester,
+) -> Result<Response, ApiError> {
     // Get artifact details
     let artifact = state
         .artifacts()
@@ -68,12 +150,23 @@ async fn deploy_contract(
         .map_err(|e| ApiError::internal(e.to_string()))?;
 
     // Get network using repository
-    let network = NetworkRepository::get_by_name(state.db(), &payload.network_name)
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(&payload.network_name)
         .await?
         .ok_or_else(|| ApiError::from(Error::NetworkNotFound(payload.network_name.clone())))?;
 
+    let addresses = libraries::resolve(state.repos(), &network.name, &artifact, &payload.libraries)
+        .await?
+        .require(&network.name)?;
+    let bytecode = artifact.link(&bytecode, &addresses)?;
+
     // Get wallet with encrypted key using repository
-    let wallet = WalletRepository::get_with_key(state.db(), &payload.wallet_name)
+    let wallet = state
+        .repos()
+        .wallets()
+        .get_with_key(&payload.wallet_name)
         .await?
         .ok_or_else(|| ApiError::from(Error::WalletNotFound(payload.wallet_name.clone())))?;
 
@@ -112,38 +205,112 @@ async fn deploy_contract(
                     "Cannot send value to contract without payable constructor",
                 ));
             }
-            Some(
-                v.parse::<U256>()
-                    .map_err(|e| ApiError::bad_request(format!("Invalid value: {}", e)))?,
-            )
+            Some(parse_value(v, network.currency()).map_err(ApiError::from)?)
         }
         _ => None,
     };
 
-    // Decrypt private key from wallet
-    let private_key = decrypt_private_key(&wallet.encrypted_key)
-        .map_err(|e| ApiError::internal(e.to_string()))?;
+    // Compare with the current version before this deployment replaces it
+    let storage_layout = artifact.storage_layout_json();
+    let layout_issues = match &storage_layout {
+        Some(storage_layout) => layout::check_against_current(
+            state.repos(),
+            &payload.artifact_name,
+            &network.name,
+            None,
+            storage_layout,
+        )
+        .await
+        .map_err(ApiError::from)?,
+        None => Vec::new(),
+    };
 
     // Combine bytecode and encoded args
     let bytecode_bytes = hex::decode(&bytecode).map_err(|e| ApiError::internal(e.to_string()))?;
     let mut deploy_data = bytecode_bytes.clone();
     deploy_data.extend_from_slice(&encoded_args);
 
-    // Deploy
-    let (tx_hash, contract_address) = execute_deploy(
-        &network.rpc_url,
-        &private_key,
-        Bytes::from(deploy_data),
-        value,
+    if simulate {
+        let from = *wallet.address;
+        let simulation = rpc::simulate(
+            network.rpc_url.expose(),
+            from,
+            None,
+            Bytes::from(deploy_data),
+            value,
+            payload.state_overrides.as_ref(),
+        )
+        .instrument(rpc::span(network.chain_id))
+        .await
+        .map_err(ApiError::from)?;
+
+        // Events the constructor emits come from the new contract itself
+        let known = match (simulation.contract_address, Abi::from_value(&artifact.abi)) {
+            (Some(address), Ok(abi)) => Some((address, payload.artifact_name.clone(), abi)),
+            _ => None,
+        };
+        let response =
+            SimulationResponse::new(state, &network.name, simulation, None, known).await?;
+        return Ok(Json(response).into_response());
+    }
+
+    if let Some(held) = approvals::hold(
+        state,
+        &requester,
+        &network,
+        ApprovalKind::Deploy,
+        None,
+        format!("Deploy {}", payload.artifact_name),
+        &payload,
     )
-    .await
-    .map_err(ApiError::from)?;
+    .await?
+    {
+        return Ok(held);
+    }
+
+    requester
+        .clearance(payload.unlock_confirm.clone())
+        .check(
+            state.repos(),
+            &network,
+            &format!("deploy {}", payload.artifact_name),
+        )
+        .await?;
+    let intent = Intent::deploy(&network, &payload.artifact_name, value);
+    policy::check(state.repos(), wallet.id, &wallet.name, &intent).await?;
+
+    let from = *wallet.address;
+    let deploy_data = Bytes::from(deploy_data);
+    let preflight = rpc::preflight(
+        &network,
+        Outgoing {
+            from,
+            to: None,
+            data: &deploy_data,
+            value,
+        },
+    )
+    .instrument(rpc::span(network.chain_id))
+    .await?;
+
+    // Decrypt private key from wallet
+    let private_key = decrypt_private_key(&wallet.encrypted_key)
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+
+    // Deploy
+    let reservation = policy::reserve(state.repos(), wallet.id, &wallet.name, &intent).await?;
+    let receipt = rpc::deploy_contract(network.rpc_url.expose(), &private_key, deploy_data, value)
+        .instrument(rpc::span(network.chain_id))
+        .await;
+    let receipt = reservation
+        .settle(state.repos(), receipt)
+        .await
+        .map_err(ApiError::from)?;
+    let tx_hash = receipt.tx_hash;
+    let contract_address = receipt.contract_address;
 
     // Record deployment in database
     let deployment_id = if let Some(ref address) = contract_address {
-        // Compute bytecode hash
-        let bytecode_hash = format!("{:x}", keccak256(&bytecode_bytes));
-
         // Get or create contract in registry
         let abi_json = serde_json::to_string(&artifact.abi)?;
 
@@ -151,23 +318,29 @@ async fn deploy_contract(
             name: payload.artifact_name.clone(),
             source_path: artifact.source_path.clone(),
             abi: abi_json,
-            bytecode_hash,
+            bytecode_hash: BytecodeHash::of(&bytecode_bytes),
+            storage_layout,
+            compiler: artifact.compiler.clone(),
         };
 
-        let contract = ContractRepository::upsert(state.db(), &new_contract).await?;
+        let contract = state.repos().contracts().upsert(&new_contract).await?;
 
         // Record deployment
         let new_deployment = NewDeployment {
             contract_id: contract.id,
             network_id: network.id,
-            address: address.clone(),
-            deployer: wallet.address.clone(),
-            tx_hash: tx_hash.clone(),
-            block_number: None,
+            address: *address,
+            deployer: wallet.address.to_string(),
+            tx_hash: Some(tx_hash),
+            block_number: receipt.block_number.map(|b| b as i64),
             constructor_args: None,
+            initcode_size: Some(artifact.initcode_size as i64),
+            runtime_size: Some(artifact.runtime_size as i64),
+            gas_used: Some(receipt.gas_used as i64),
+            gas_price: Some(receipt.gas_price.to_string()),
         };
 
-        let deployment = DeploymentRepository::create(state.db(), &new_deployment).await?;
+        let deployment = state.repos().deployments().create(&new_deployment).await?;
 
         Some(deployment.id)
     } else {
@@ -178,63 +351,221 @@ async fn deploy_contract(
         tx_hash,
         contract_address,
         deployment_id,
-    }))
+        gas_used: receipt.gas_used,
+        size_warnings: artifact.size_warnings,
+        layout_issues,
+        warnings: preflight.warnings,
+    })
+    .into_response())
 }
 
-fn encode_constructor_args(
-    inputs: &[ParamInfo],
-    args: &[serde_json::Value],
-) -> Result<Vec<u8>, Error> {
-    let mut sol_values = Vec::new();
-
-    for (i, (input, value)) in inputs.iter().zip(args.iter()).enumerate() {
-        let sol_value = json_to_sol_value(&input.param_type, value)
-            .map_err(|e| Error::AbiEncode(format!("Argument {}: {}", i, e)))?;
-        sol_values.push(sol_value);
-    }
-
-    let tuple = DynSolValue::Tuple(sol_values);
-    Ok(tuple.abi_encode_params())
+#[derive(Deserialize, ToSchema)]
+struct DeployMultiRequest {
+    artifact_name: String,
+    /// Networks to deploy to, in order
+    network_names: Vec<String>,
+    wallet_name: String,
+    #[serde(default)]
+    constructor_args: Vec<serde_json::Value>,
+    /// Wei, or an amount in `wei`, `gwei` or `ether`
+    #[serde(default)]
+    value: Option<String>,
+    /// Deploy to all networks at once instead of one after the other
+    #[serde(default)]
+    parallel: bool,
+    /// Names of the locked networks among `network_names`, to deploy to them
+    #[serde(default)]
+    unlock_confirm: Vec<String>,
 }
 
-async fn execute_deploy(
-    rpc_url: &str,
-    private_key: &str,
-    data: Bytes,
-    value: Option<U256>,
-) -> Result<(String, Option<String>), Error> {
-    let signer: PrivateKeySigner = private_key
-        .parse()
-        .map_err(|e| Error::invalid_param("private_key", format!("Invalid: {}", e)))?;
+#[derive(Serialize, ToSchema)]
+struct DeploymentGroupResponse {
+    #[serde(flatten)]
+    group: DeploymentGroup,
+    /// Outcome per network, in the order the networks were given
+    members: Vec<DeploymentGroupMember>,
+}
 
-    let wallet = EthereumWallet::from(signer);
+impl DeploymentGroupResponse {
+    /// 200 when every network succeeded, 207 when some failed
+    fn respond(self) -> Response {
+        let status = match self
+            .members
+            .iter()
+            .all(|m| m.status == TransactionStatus::Success)
+        {
+            true => StatusCode::OK,
+            false => StatusCode::MULTI_STATUS,
+        };
+        (status, Json(self)).into_response()
+    }
+}
 
-    let url: reqwest::Url = rpc_url
-        .parse()
-        .map_err(|e| Error::invalid_param("rpc_url", format!("Invalid RPC URL: {}", e)))?;
-    let provider = ProviderBuilder::new().wallet(wallet).connect_http(url);
+#[utoipa::path(
+    post,
+    path = "/api/deploy/multi",
+    tag = "deploy",
+    request_body = DeployMultiRequest,
+    responses(
+        (status = 200, description = "Deployed to every network", body = DeploymentGroupResponse),
+        (status = 207, description = "Some networks failed; resume the group to retry them", body = DeploymentGroupResponse),
+        (status = 400, description = "Invalid artifact, networks or constructor arguments", body = ApiError),
+        (status = 403, description = "One of the networks is protected", body = ApiError),
+        (status = 404, description = "Artifact, network or wallet not found", body = ApiError),
+        (status = 423, description = "One of the networks is locked and not in `unlock_confirm`", body = ApiError)
+    )
+)]
+async fn deploy_multi(
+    State(state): State<AppState>,
+    caller: Option<Extension<Caller>>,
+    Json(payload): Json<DeployMultiRequest>,
+) -> Result<Response, ApiError> {
+    reject_protected(&state, payload.network_names.iter().map(String::as_str)).await?;
+    let request = GroupRequest {
+        contract: payload.artifact_name,
+        networks: payload.network_names,
+        wallet: payload.wallet_name,
+        constructor_args: payload.constructor_args,
+        value: payload.value,
+    };
+    let clearance = Requester::from_extension(caller).clearance(payload.unlock_confirm);
+    let group =
+        deploy_group::create(state.repos(), state.artifacts(), &request, &clearance).await?;
+    let members = deploy_group::run(
+        state.repos(),
+        state.artifacts(),
+        group.id,
+        payload.parallel,
+        &clearance,
+    )
+    .await?;
 
-    // CREATE transaction - use with_deploy_code to properly mark as deployment
-    let mut tx = TransactionRequest::default().with_deploy_code(data);
+    Ok(DeploymentGroupResponse { group, members }.respond())
+}
 
-    if let Some(v) = value {
-        tx = tx.value(v);
-    }
+#[utoipa::path(
+    get,
+    path = "/api/deploy/groups",
+    tag = "deploy",
+    responses((status = 200, description = "Deployment groups, newest first", body = Vec<DeploymentGroup>))
+)]
+async fn list_groups(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<DeploymentGroup>>, ApiError> {
+    Ok(Json(state.repos().deployment_groups().list().await?))
+}
 
-    let pending = provider
-        .send_transaction(tx)
-        .await
-        .map_err(|e| Error::TransactionFailed(format!("Failed to send deployment: {}", e)))?;
+#[utoipa::path(
+    get,
+    path = "/api/deploy/groups/{id}",
+    tag = "deploy",
+    params(("id" = i64, Path, description = "Deployment group ID")),
+    responses(
+        (status = 200, description = "Group and its outcome per network", body = DeploymentGroupResponse),
+        (status = 404, description = "Deployment group not found", body = ApiError)
+    )
+)]
+async fn get_group(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<DeploymentGroupResponse>, ApiError> {
+    let id = DeploymentGroupId(id);
+    let group = state
+        .repos()
+        .deployment_groups()
+        .get(id)
+        .await?
+        .ok_or(Error::DeploymentGroupNotFound(id))?;
+    let members = state.repos().deployment_groups().list_members(id).await?;
+    Ok(Json(DeploymentGroupResponse { group, members }))
+}
 
-    let tx_hash = format!("{:?}", pending.tx_hash());
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ResumeQuery {
+    /// Retry all remaining networks at once
+    #[serde(default)]
+    parallel: bool,
+    /// Comma-separated names of the locked networks remaining, to deploy to
+    /// them
+    #[serde(default)]
+    unlock_confirm: Option<String>,
+}
 
-    // Wait for receipt to get contract address
-    let receipt = pending
-        .get_receipt()
-        .await
-        .map_err(|e| Error::Rpc(format!("Failed to get transaction receipt: {}", e)))?;
+#[utoipa::path(
+    post,
+    path = "/api/deploy/groups/{id}/resume",
+    tag = "deploy",
+    params(("id" = i64, Path, description = "Deployment group ID"), ResumeQuery),
+    responses(
+        (status = 200, description = "Every network has now succeeded", body = DeploymentGroupResponse),
+        (status = 207, description = "Some networks failed again", body = DeploymentGroupResponse),
+        (status = 400, description = "The artifact was rebuilt since the group was created", body = ApiError),
+        (status = 403, description = "A remaining network has since been protected", body = ApiError),
+        (status = 404, description = "Deployment group not found", body = ApiError),
+        (status = 423, description = "A remaining network is locked and not in `unlock_confirm`", body = ApiError)
+    )
+)]
+async fn resume_group(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<ResumeQuery>,
+    caller: Option<Extension<Caller>>,
+) -> Result<Response, ApiError> {
+    let id = DeploymentGroupId(id);
+    let group = state
+        .repos()
+        .deployment_groups()
+        .get(id)
+        .await?
+        .ok_or(Error::DeploymentGroupNotFound(id))?;
+    let remaining = state.repos().deployment_groups().list_members(id).await?;
+    reject_protected(
+        &state,
+        remaining
+            .iter()
+            .filter(|m| m.status != TransactionStatus::Success)
+            .map(|m| m.network_name.as_str()),
+    )
+    .await?;
+    let confirmed = query
+        .unlock_confirm
+        .iter()
+        .flat_map(|names| names.split(','))
+        .map(|name| name.trim().to_string());
+    let clearance = Requester::from_extension(caller).clearance(confirmed);
+    let members = deploy_group::run(
+        state.repos(),
+        state.artifacts(),
+        id,
+        query.parallel,
+        &clearance,
+    )
+    .await?;
 
-    let contract_address = receipt.contract_address.map(|a| format!("{:?}", a));
+    Ok(DeploymentGroupResponse { group, members }.respond())
+}
 
-    Ok((tx_hash, contract_address))
+/// Refuse groups that would deploy to a protected network: a group can't
+/// wait for approval, so those networks go through `/api/deploy` one at a
+/// time
+async fn reject_protected(
+    state: &AppState,
+    networks: impl Iterator<Item = &str>,
+) -> Result<(), ApiError> {
+    for name in networks {
+        let protected = state
+            .repos()
+            .networks()
+            .get_by_name(name)
+            .await?
+            .is_some_and(|n| n.protected);
+        if protected {
+            return Err(ApiError::forbidden(format!(
+                "Network '{}' is protected; deploy to it with POST /api/deploy so the deployment can be approved",
+                name
+            )));
+        }
+    }
+    Ok(())
 }
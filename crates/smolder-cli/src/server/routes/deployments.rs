@@ -1,18 +1,39 @@
 use axum::{
     extract::{Path, Query, State},
-    routing::get,
+    http::{HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, patch},
     Json, Router,
 };
-use serde::Deserialize;
-use smolder_core::Error;
-use smolder_db::{DeploymentFilter, DeploymentRepository, DeploymentView};
+use serde::{Deserialize, Serialize};
+use smolder_core::{ApiRole, Error};
+use smolder_db::{DeploymentFilter, DeploymentId, DeploymentSort, DeploymentView, SortOrder};
+use tracing::Instrument;
+use utoipa::{IntoParams, ToSchema};
 
+use super::{abi_response, paginated};
+use crate::contract_status::{self, ContractStatus};
+use crate::roles::{self, RoleReport};
+use crate::rpc;
+use crate::server::auth;
 use crate::server::error::ApiError;
+use crate::server::limits;
 use crate::server::AppState;
+use crate::tokens;
 
 pub fn router() -> Router<AppState> {
     Router::new()
-        .route("/deployments", get(list))
+        .route(
+            "/deployments",
+            get(list).route_layer(middleware::from_fn(limits::rpc)),
+        )
+        .route("/deployments/{id}", patch(update).delete(remove))
+        .route("/deployments/{id}/abi", get(get_abi))
+        .route(
+            "/deployments/{id}/roles",
+            get(get_roles).route_layer(middleware::from_fn(limits::rpc)),
+        )
         .route(
             "/deployments/{contract}/{network}",
             get(get_by_contract_and_network),
@@ -21,26 +42,135 @@ pub fn router() -> Router<AppState> {
             "/deployments/{contract}/{network}/versions",
             get(list_versions),
         )
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
 }
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListQuery {
     pub network: Option<String>,
+    pub contract: Option<String>,
+    /// Only include this version; combine with `all_versions` for previous
+    /// ones
+    pub version: Option<i64>,
+    pub deployer: Option<String>,
+    /// Only include deployments sent from this registered wallet
+    pub wallet: Option<String>,
+    /// Only include deployments made at or after this date
+    pub since: Option<String>,
+    /// Only include deployments made at or before this date
+    pub until: Option<String>,
+    /// Only include contracts created by this call history entry, any
+    /// version
+    pub created_by_call: Option<i64>,
+    /// Include previous versions, not just current deployments
+    #[serde(default)]
+    pub all_versions: bool,
+    #[serde(default)]
+    pub include_archived: bool,
+    pub sort: Option<DeploymentSort>,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// Query each contract's paused(), owner() and implementation()
+    #[serde(default)]
+    pub include_status: bool,
+}
+
+/// A deployment in a listing, with its live status when requested
+#[derive(Serialize, ToSchema)]
+pub struct ListedDeployment {
+    #[serde(flatten)]
+    pub deployment: DeploymentView,
+    /// Present with `include_status=true` when the network could be reached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ContractStatus>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/deployments",
+    tag = "deployments",
+    params(ListQuery),
+    responses((
+        status = 200,
+        description = "Matching deployments",
+        body = Vec<ListedDeployment>,
+        headers(("x-total-count" = i64, description = "Total matching items before pagination"))
+    ))
+)]
 async fn list(
     State(state): State<AppState>,
     Query(query): Query<ListQuery>,
-) -> Result<Json<Vec<DeploymentView>>, ApiError> {
-    let filter = match query.network {
-        Some(ref network) => DeploymentFilter::for_network(network),
-        None => DeploymentFilter::current(),
+) -> Result<impl IntoResponse, ApiError> {
+    let filter = DeploymentFilter {
+        network: query.network,
+        contract: query.contract,
+        version: query.version,
+        deployer: query.deployer,
+        wallet: query.wallet,
+        deployed_after: query.since,
+        deployed_before: query.until,
+        created_by_call_id: query.created_by_call,
+        // A factory creates instances of the same contract, each a new version
+        current_only: !query.all_versions && query.created_by_call.is_none(),
+        include_archived: query.include_archived,
+        sort: query.sort,
+        order: query.order,
+        limit: query.limit,
+        offset: query.offset,
+    };
+
+    let total = state.repos().deployments().count(&filter).await?;
+    let deployments = state.repos().deployments().list(filter).await?;
+    enrich_tokens(&state, &deployments);
+
+    let mut statuses = match query.include_status {
+        true => contract_status::read(state.repos(), &deployments).await,
+        false => Default::default(),
     };
+    let listed: Vec<ListedDeployment> = deployments
+        .into_iter()
+        .map(|deployment| ListedDeployment {
+            status: statuses.remove(&deployment.id),
+            deployment,
+        })
+        .collect();
+    Ok(paginated(total, listed))
+}
 
-    let deployments = DeploymentRepository::list(state.db(), filter).await?;
-    Ok(Json(deployments))
+/// Read token metadata for deployments that lack it in the background, so
+/// the response isn't held up by RPC calls; it shows from the next request
+fn enrich_tokens(state: &AppState, deployments: &[DeploymentView]) {
+    let mut pending: Vec<DeploymentView> = deployments
+        .iter()
+        .filter(|d| tokens::needs_metadata(d))
+        .cloned()
+        .collect();
+    if pending.is_empty() {
+        return;
+    }
+    let task_state = state.clone();
+    state.spawn(async move { tokens::enrich(task_state.repos(), &mut pending).await });
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/deployments/{contract}/{network}",
+    tag = "deployments",
+    params(
+        ("contract" = String, Path, description = "Contract name"),
+        ("network" = String, Path, description = "Network name")
+    ),
+    responses(
+        (status = 200, description = "Current deployment", body = DeploymentView),
+        (status = 404, description = "No deployment found", body = ApiError)
+    )
+)]
 async fn get_by_contract_and_network(
     State(state): State<AppState>,
     Path((contract, network)): Path<(String, String)>,
@@ -52,21 +182,208 @@ async fn get_by_contract_and_network(
         )))
     };
 
-    let deployment = DeploymentRepository::get_current(state.db(), &contract, &network)
+    let deployment = state
+        .repos()
+        .deployments()
+        .get_current(&contract, &network)
         .await?
         .ok_or_else(not_found)?;
 
-    let view = DeploymentRepository::get_view_by_id(state.db(), deployment.id)
+    let view = state
+        .repos()
+        .deployments()
+        .get_view_by_id(deployment.id)
         .await?
         .ok_or_else(not_found)?;
+    enrich_tokens(&state, std::slice::from_ref(&view));
 
     Ok(Json(view))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/deployments/{contract}/{network}/versions",
+    tag = "deployments",
+    params(
+        ("contract" = String, Path, description = "Contract name"),
+        ("network" = String, Path, description = "Network name")
+    ),
+    responses((status = 200, description = "All versions, newest first", body = Vec<DeploymentView>))
+)]
 async fn list_versions(
     State(state): State<AppState>,
     Path((contract, network)): Path<(String, String)>,
 ) -> Result<Json<Vec<DeploymentView>>, ApiError> {
-    let versions = DeploymentRepository::list_versions(state.db(), &contract, &network).await?;
+    let versions = state
+        .repos()
+        .deployments()
+        .list_versions(&contract, &network)
+        .await?;
     Ok(Json(versions))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/deployments/{id}/abi",
+    tag = "deployments",
+    params(("id" = i64, Path, description = "Deployment ID")),
+    responses(
+        (
+            status = 200,
+            description = "Raw contract ABI",
+            content_type = "application/json",
+            body = Object,
+            headers(
+                ("etag" = String, description = "Hash of the ABI, for If-None-Match"),
+                ("x-start-block" = i64, description = "Block the deployment was mined in, when known")
+            )
+        ),
+        (status = 304, description = "ABI unchanged since the ETag in If-None-Match"),
+        (status = 404, description = "Deployment not found", body = ApiError)
+    )
+)]
+async fn get_abi(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let deployment = state
+        .repos()
+        .deployments()
+        .get_view_by_id(DeploymentId(id))
+        .await?
+        .ok_or_else(|| ApiError::from(Error::DeploymentNotFound(id.to_string())))?;
+
+    Ok(abi_response(
+        &headers,
+        deployment.abi,
+        deployment.block_number,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/deployments/{id}/roles",
+    tag = "deployments",
+    params(("id" = i64, Path, description = "Deployment ID")),
+    responses(
+        (status = 200, description = "Owner and AccessControl role holders", body = RoleReport),
+        (status = 400, description = "Contract is neither Ownable nor AccessControl", body = ApiError),
+        (status = 404, description = "Deployment not found", body = ApiError),
+        (status = 502, description = "Network unreachable", body = ApiError)
+    )
+)]
+async fn get_roles(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<RoleReport>, ApiError> {
+    let deployment = state
+        .repos()
+        .deployments()
+        .get_view_by_id(DeploymentId(id))
+        .await?
+        .ok_or_else(|| ApiError::from(Error::DeploymentNotFound(id.to_string())))?;
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(&deployment.network_name)
+        .await?
+        .ok_or_else(|| Error::NetworkNotFound(deployment.network_name.clone()))?;
+
+    let report = roles::read(state.repos(), &deployment, network.rpc_url.expose())
+        .instrument(rpc::span(network.chain_id))
+        .await?;
+    Ok(Json(report))
+}
+
+/// Changes to a deployment; fields left out are kept
+#[derive(Deserialize, ToSchema)]
+pub struct DeploymentUpdate {
+    /// Pin the deployment to the top of listings, or unpin it
+    pub pinned: Option<bool>,
+    /// Display label; an empty one removes it, showing the contract's
+    pub label: Option<String>,
+    /// Markdown description; an empty one removes it, showing the contract's
+    pub description: Option<String>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/deployments/{id}",
+    tag = "deployments",
+    params(("id" = i64, Path, description = "Deployment ID")),
+    request_body = DeploymentUpdate,
+    responses(
+        (status = 200, description = "Updated deployment", body = DeploymentView),
+        (status = 404, description = "Deployment not found", body = ApiError)
+    )
+)]
+async fn update(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(update): Json<DeploymentUpdate>,
+) -> Result<Json<DeploymentView>, ApiError> {
+    let id = DeploymentId(id);
+    let deployments = state.repos().deployments();
+
+    if let Some(pinned) = update.pinned {
+        deployments.set_pinned(id, pinned).await?;
+    }
+    if update.label.is_some() || update.description.is_some() {
+        let deployment = deployments
+            .get_by_id(id)
+            .await?
+            .ok_or(Error::DeploymentNotFoundById(id))?;
+        // An empty value removes the field, a missing one keeps it
+        let merge = |new: Option<String>, current: Option<String>| match new {
+            Some(value) if value.trim().is_empty() => None,
+            Some(value) => Some(value),
+            None => current,
+        };
+        let label = merge(update.label, deployment.label);
+        let description = merge(update.description, deployment.description);
+        deployments
+            .set_label(id, label.as_deref(), description.as_deref())
+            .await?;
+    }
+
+    let view = deployments
+        .get_view_by_id(id)
+        .await?
+        .ok_or(Error::DeploymentNotFoundById(id))?;
+    Ok(Json(view))
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RemoveQuery {
+    /// Archive instead of permanently deleting
+    #[serde(default)]
+    pub archive: bool,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/deployments/{id}",
+    tag = "deployments",
+    params(("id" = i64, Path, description = "Deployment ID"), RemoveQuery),
+    responses(
+        (status = 204, description = "Deployment deleted or archived"),
+        (status = 404, description = "Deployment not found", body = ApiError)
+    )
+)]
+async fn remove(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<RemoveQuery>,
+) -> Result<StatusCode, ApiError> {
+    let id = DeploymentId(id);
+
+    if query.archive {
+        state.repos().deployments().archive(id).await?;
+    } else {
+        state.repos().deployments().delete(id).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
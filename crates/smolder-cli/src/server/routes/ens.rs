@@ -0,0 +1,102 @@
+use alloy::primitives::Address;
+use axum::{
+    extract::{Path, Query, State},
+    middleware,
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use smolder_core::Error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::ens::EnsResolver;
+use crate::server::error::ApiError;
+use crate::server::limits;
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/ens/resolve/{name}", get(resolve))
+        .route("/ens/lookup/{address}", get(lookup))
+        .route_layer(middleware::from_fn(limits::rpc))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct EnsQuery {
+    /// Network in use; names are resolved on it when it has ENS, otherwise on
+    /// the configured ENS network or mainnet
+    network: Option<String>,
+}
+
+/// An ENS name and the address it belongs to
+#[derive(Serialize, ToSchema)]
+struct EnsName {
+    address: String,
+    /// Primary name; `null` when the address has none
+    name: Option<String>,
+}
+
+async fn resolver<'a>(state: &'a AppState, query: &EnsQuery) -> Result<EnsResolver<'a>, ApiError> {
+    let network = match &query.network {
+        Some(name) => Some(
+            state
+                .repos()
+                .networks()
+                .get_by_name(name)
+                .await?
+                .ok_or_else(|| Error::NetworkNotFound(name.clone()))?,
+        ),
+        None => None,
+    };
+    Ok(EnsResolver::new(state.repos(), network.as_ref(), state.ens_network()).await?)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ens/resolve/{name}",
+    tag = "ens",
+    params(("name" = String, Path, description = "ENS name"), EnsQuery),
+    responses(
+        (status = 200, description = "Address the name resolves to", body = EnsName),
+        (status = 400, description = "Name does not resolve", body = ApiError),
+        (status = 502, description = "RPC failure", body = ApiError)
+    )
+)]
+async fn resolve(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<EnsQuery>,
+) -> Result<Json<EnsName>, ApiError> {
+    let address = resolver(&state, &query).await?.resolve(&name).await?;
+    Ok(Json(EnsName {
+        address: address.to_string(),
+        name: Some(name),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ens/lookup/{address}",
+    tag = "ens",
+    params(("address" = String, Path, description = "Address to reverse resolve"), EnsQuery),
+    responses(
+        (status = 200, description = "Primary name of the address, verified to resolve back to it", body = EnsName),
+        (status = 400, description = "Invalid address", body = ApiError),
+        (status = 502, description = "RPC failure", body = ApiError)
+    )
+)]
+async fn lookup(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(query): Query<EnsQuery>,
+) -> Result<Json<EnsName>, ApiError> {
+    let address: Address = address
+        .parse()
+        .map_err(|e| Error::invalid_param("address", format!("{}", e)))?;
+    let name = resolver(&state, &query).await?.lookup(address).await?;
+    Ok(Json(EnsName {
+        address: address.to_string(),
+        name,
+    }))
+}
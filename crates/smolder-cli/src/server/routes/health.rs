@@ -1,21 +1,207 @@
-use axum::{routing::get, Json, Router};
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
 use serde::Serialize;
+use tokio::task::JoinSet;
+use tracing::Instrument;
+use utoipa::ToSchema;
 
+use crate::rpc::{self, RpcClient};
+use crate::server::state::RpcProbe;
 use crate::server::AppState;
 
+/// How long an RPC endpoint gets to answer `eth_chainId`
+const RPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a probe result is reused before the endpoint is asked again
+const RPC_CACHE_TTL: Duration = Duration::from_secs(30);
+
 pub fn router() -> Router<AppState> {
     Router::new().route("/health", get(check))
 }
 
-#[derive(Serialize)]
+/// Overall server health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Every dependency is up
+    Ok,
+    /// The database is up but some RPC endpoints are not
+    Degraded,
+    /// The database is unreachable
+    Unavailable,
+}
+
+/// Status of a single dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DatabaseHealth {
+    pub status: DependencyStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NetworkHealth {
+    pub name: String,
+    pub chain_id: i64,
+    pub status: DependencyStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether this result was reused from a recent probe
+    pub cached: bool,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct HealthResponse {
-    pub status: String,
+    pub status: HealthStatus,
     pub version: String,
+    pub database: DatabaseHealth,
+    pub networks: Vec<NetworkHealth>,
 }
 
-async fn check() -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "ok".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-    })
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Database is up; RPC endpoints may be degraded", body = HealthResponse),
+        (status = 503, description = "Database is unreachable", body = HealthResponse)
+    )
+)]
+async fn check(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let database = check_database(&state).await;
+
+    // Without a database there is no network list to probe
+    let networks = if database.status == DependencyStatus::Up {
+        check_networks(&state).await
+    } else {
+        Vec::new()
+    };
+
+    let status = if database.status == DependencyStatus::Down {
+        HealthStatus::Unavailable
+    } else if networks.iter().any(|n| n.status == DependencyStatus::Down) {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+
+    let code = match status {
+        HealthStatus::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Ok | HealthStatus::Degraded => StatusCode::OK,
+    };
+
+    (
+        code,
+        Json(HealthResponse {
+            status,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            database,
+            networks,
+        }),
+    )
+}
+
+async fn check_database(state: &AppState) -> DatabaseHealth {
+    let start = Instant::now();
+    let result = state.db().ping().await;
+
+    DatabaseHealth {
+        status: if result.is_ok() {
+            DependencyStatus::Up
+        } else {
+            DependencyStatus::Down
+        },
+        latency_ms: start.elapsed().as_millis() as u64,
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Probe every network's RPC concurrently, reusing recent results
+async fn check_networks(state: &AppState) -> Vec<NetworkHealth> {
+    let networks = match state.repos().networks().list().await {
+        Ok(networks) => networks,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not list networks for health check");
+            return Vec::new();
+        }
+    };
+
+    let mut probes = JoinSet::new();
+    let mut results: Vec<(usize, RpcProbe, bool)> = Vec::new();
+
+    for (index, network) in networks.iter().enumerate() {
+        if let Some(probe) = state.cached_probe(network.rpc_url.expose(), RPC_CACHE_TTL) {
+            results.push((index, probe, true));
+            continue;
+        }
+
+        let rpc_url = network.rpc_url.expose().to_string();
+        let span = rpc::span(network.chain_id);
+        let task_state = state.clone();
+        probes.spawn(async move {
+            let probe = probe_rpc(task_state.rpc(), &rpc_url).instrument(span).await;
+            (index, probe)
+        });
+    }
+
+    while let Some(joined) = probes.join_next().await {
+        if let Ok((index, probe)) = joined {
+            state.store_probe(networks[index].rpc_url.expose(), probe.clone());
+            results.push((index, probe, false));
+        }
+    }
+
+    results.sort_by_key(|(index, _, _)| *index);
+    results
+        .into_iter()
+        .map(|(index, probe, cached)| {
+            let network = &networks[index];
+            let error = match probe.result {
+                Ok(chain_id) if chain_id as i64 == network.chain_id.0 => None,
+                Ok(chain_id) => Some(format!(
+                    "RPC reports chain ID {}, expected {}",
+                    chain_id, network.chain_id
+                )),
+                Err(e) => Some(e),
+            };
+
+            NetworkHealth {
+                name: network.name.clone(),
+                chain_id: network.chain_id.0,
+                status: if error.is_none() {
+                    DependencyStatus::Up
+                } else {
+                    DependencyStatus::Down
+                },
+                latency_ms: probe.latency_ms,
+                error,
+                cached,
+            }
+        })
+        .collect()
+}
+
+async fn probe_rpc(rpc: &dyn RpcClient, rpc_url: &str) -> RpcProbe {
+    let start = Instant::now();
+    let result = match tokio::time::timeout(RPC_TIMEOUT, rpc.chain_id(rpc_url)).await {
+        Ok(Ok(chain_id)) => Ok(chain_id),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("No response within {}s", RPC_TIMEOUT.as_secs())),
+    };
+
+    RpcProbe {
+        result,
+        latency_ms: start.elapsed().as_millis() as u64,
+        checked_at: Instant::now(),
+    }
 }
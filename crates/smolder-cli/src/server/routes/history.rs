@@ -0,0 +1,225 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    routing::get,
+    Json, Router,
+};
+use futures::Stream;
+use serde::Deserialize;
+use smolder_core::Error;
+use smolder_db::{CallHistoryFilter, CallHistoryView, DeploymentId, TransactionStatus};
+use tracing::Instrument;
+use utoipa::IntoParams;
+
+use super::paginated;
+use crate::history::{self, HistoryFormat};
+use crate::rpc;
+use crate::server::error::ApiError;
+use crate::server::history_stream;
+use crate::server::limits;
+use crate::server::trace::{self, TraceCall};
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/history", get(list))
+        .route("/history/export", get(export))
+        .route("/history/stream", get(stream))
+        .route(
+            "/history/{id}/trace",
+            get(get_trace).route_layer(middleware::from_fn(limits::rpc)),
+        )
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    pub deployment_id: Option<i64>,
+    pub contract: Option<String>,
+    pub network: Option<String>,
+    pub wallet: Option<String>,
+    pub status: Option<TransactionStatus>,
+    /// Only include calls made at or after this date
+    pub since: Option<String>,
+    /// Only include calls made at or before this date
+    pub until: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/history",
+    tag = "history",
+    params(ListQuery),
+    responses((
+        status = 200,
+        description = "Matching calls, newest first",
+        body = Vec<CallHistoryView>,
+        headers(("x-total-count" = i64, description = "Total matching items before pagination"))
+    ))
+)]
+async fn list(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let filter = query.into_filter();
+    let total = state.repos().call_history().count(&filter).await?;
+    let history = state.repos().call_history().list_views(filter).await?;
+    Ok(paginated(total, history))
+}
+
+impl ListQuery {
+    fn into_filter(self) -> CallHistoryFilter {
+        CallHistoryFilter {
+            deployment_id: self.deployment_id.map(DeploymentId),
+            contract: self.contract,
+            network: self.network,
+            wallet: self.wallet,
+            status: self.status,
+            since: self.since,
+            until: self.until,
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: HistoryFormat,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/history/export",
+    tag = "history",
+    params(ExportQuery, ListQuery),
+    responses((
+        status = 200,
+        description = "Matching calls, oldest first, with parameters keyed by input name. \
+            `limit` and `offset` are ignored",
+        content(
+            (Vec<Object> = "application/json"),
+            (String = "text/csv")
+        )
+    ))
+)]
+async fn export(
+    State(state): State<AppState>,
+    Query(format): Query<ExportQuery>,
+    Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let records = history::load(state.repos(), query.into_filter(), state.prices()).await?;
+
+    let mut body = Vec::new();
+    history::write(&records, format.format, &mut body)
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+
+    let disposition = format!(
+        "attachment; filename=\"history.{}\"",
+        format.format.extension()
+    );
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                format.format.content_type().to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ))
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StreamQuery {
+    /// Only follow the calls of this deployment
+    pub deployment_id: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/history/stream",
+    tag = "history",
+    params(StreamQuery),
+    responses((
+        status = 200,
+        description = "Server-sent events: a `status` event with the call history entry each \
+            time a transaction's status changes, and `lagged` with the number of entries \
+            missed by a client that fell behind",
+        content_type = "text/event-stream",
+        body = String
+    ))
+)]
+async fn stream(
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = history_stream::subscribe(&state, query.deployment_id.map(DeploymentId));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/history/{id}/trace",
+    tag = "history",
+    params(("id" = i64, Path, description = "Call history entry ID")),
+    responses(
+        (status = 200, description = "Call tree of the transaction, decoded with registry ABIs", body = TraceCall),
+        (status = 400, description = "The call sent no transaction", body = ApiError),
+        (status = 404, description = "Call not found", body = ApiError),
+        (status = 501, description = "The network's RPC does not serve debug_traceTransaction", body = ApiError)
+    )
+)]
+async fn get_trace(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<TraceCall>, ApiError> {
+    let call = state
+        .repos()
+        .call_history()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Call {} not found", id)))?;
+    let tx_hash = call
+        .tx_hash
+        .ok_or_else(|| ApiError::bad_request(format!("Call {} sent no transaction", id)))?;
+    let deployment = state
+        .repos()
+        .deployments()
+        .get_view_by_id(call.deployment_id)
+        .await?
+        .ok_or(Error::DeploymentNotFoundById(call.deployment_id))?;
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(&deployment.network_name)
+        .await?
+        .ok_or_else(|| Error::NetworkNotFound(deployment.network_name.clone()))?;
+
+    let frame = rpc::trace_transaction(network.rpc_url.expose(), &tx_hash)
+        .instrument(rpc::span(network.chain_id))
+        .await?
+        .ok_or_else(|| {
+            ApiError::new(
+                "TRACE_UNSUPPORTED",
+                format!(
+                    "The RPC of '{}' can't trace {}; it needs debug_traceTransaction and the state at that block",
+                    network.name, tx_hash
+                ),
+            )
+        })?;
+
+    Ok(Json(trace::decode(&state, &network.name, &frame).await?))
+}
@@ -0,0 +1,766 @@
+use alloy::json_abi::StateMutability;
+use alloy::primitives::{Address, U256};
+use axum::{
+    extract::{Path, Query, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use smolder_core::storage::{derive_slot, parse_slot};
+use smolder_core::{
+    decode_function_result, decrypt_private_key, encode_function_call, parse_value, Abi, Error,
+    FunctionInfo, StorageWord, TxHash,
+};
+use smolder_db::{
+    Approval, ApprovalKind, CallHistoryFilter, CallHistoryUpdate, CallHistoryView, CallType,
+    DeploymentId, DeploymentView, Network, NewCallHistory, TransactionStatus, WalletId,
+    WalletWithKey,
+};
+use tracing::Instrument;
+use utoipa::{IntoParams, ToSchema};
+
+use super::approvals::{self, Requester};
+use super::paginated;
+use crate::ens;
+use crate::policy::{self, Intent};
+use crate::relay;
+use crate::rpc::{self, Outgoing, PreflightWarning};
+use crate::server::auth::Caller;
+use crate::server::error::ApiError;
+use crate::server::history_stream;
+use crate::server::limits;
+use crate::server::receipts::{self, ReceiptJob};
+use crate::server::simulate::{SimulateQuery, SimulationResponse};
+use crate::server::webhooks::{self, Notification};
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/deployments/{id}/functions", get(get_functions))
+        .route("/deployments/{id}/call", post(execute_call))
+        .route("/deployments/{id}/send", post(execute_send))
+        .route("/deployments/{id}/history", get(get_history))
+        .route("/deployments/{id}/storage", get(get_storage))
+        .route_layer(middleware::from_fn(limits::rpc))
+}
+
+// ================================
+// GET /deployments/:id/functions
+// ================================
+
+#[derive(Serialize, ToSchema)]
+struct FunctionsResponse {
+    read: Vec<FunctionInfo>,
+    write: Vec<FunctionInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/deployments/{id}/functions",
+    tag = "interact",
+    params(("id" = i64, Path, description = "Deployment ID")),
+    responses(
+        (status = 200, description = "Read and write functions from the ABI", body = FunctionsResponse),
+        (status = 404, description = "Deployment not found", body = ApiError)
+    )
+)]
+async fn get_functions(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<FunctionsResponse>, ApiError> {
+    // Get deployment with ABI
+    let deployment = get_deployment_by_id(&state, id).await?;
+
+    // Parse and categorize functions
+    let abi = Abi::parse(&deployment.abi).map_err(|e| ApiError::internal(e.to_string()))?;
+    let parsed = abi.functions();
+
+    Ok(Json(FunctionsResponse {
+        read: parsed.read,
+        write: parsed.write,
+    }))
+}
+
+// ================================
+// POST /deployments/:id/call
+// ================================
+
+#[derive(Deserialize, ToSchema)]
+struct CallRequest {
+    function_name: String,
+    params: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct CallResponse {
+    result: serde_json::Value,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/deployments/{id}/call",
+    tag = "interact",
+    params(("id" = i64, Path, description = "Deployment ID")),
+    request_body = CallRequest,
+    responses(
+        (status = 200, description = "Decoded return value", body = CallResponse),
+        (status = 400, description = "Not a read function or invalid parameters", body = ApiError),
+        (status = 404, description = "Deployment or function not found", body = ApiError),
+        (status = 502, description = "RPC failure", body = ApiError)
+    )
+)]
+async fn execute_call(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<CallRequest>,
+) -> Result<Json<CallResponse>, ApiError> {
+    let deployment = get_deployment_by_id(&state, id).await?;
+    let network = get_network_by_name(&state, &deployment.network_name).await?;
+
+    // Get function from ABI
+    let abi = Abi::parse(&deployment.abi).map_err(|e| ApiError::internal(e.to_string()))?;
+    let function = abi
+        .function(&payload.function_name)
+        .cloned()
+        .ok_or_else(|| {
+            ApiError::not_found(format!("Function '{}' not found", payload.function_name))
+        })?;
+
+    // Verify it's a read function
+    if !matches!(
+        function.state_mutability,
+        StateMutability::View | StateMutability::Pure
+    ) {
+        return Err(ApiError::bad_request(format!(
+            "Function '{}' is not a read function. Use /send for write operations.",
+            payload.function_name
+        )));
+    }
+
+    let mut params = payload.params;
+    ens::resolve_params(
+        state.repos(),
+        &network,
+        state.ens_network(),
+        &function.inputs,
+        &mut params,
+    )
+    .await
+    .map_err(ApiError::from)?;
+    let call_data = encode_function_call(&function, &params).map_err(ApiError::from)?;
+
+    // Execute eth_call
+    let contract_address = *deployment.address;
+
+    let result = state
+        .rpc()
+        .call(network.rpc_url.expose(), contract_address, call_data)
+        .instrument(rpc::span(network.chain_id))
+        .await
+        .map_err(ApiError::from)?;
+
+    let decoded = decode_function_result(&function, &result).map_err(ApiError::from)?;
+
+    Ok(Json(CallResponse { result: decoded }))
+}
+
+// ================================
+// POST /deployments/:id/send
+// ================================
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub(super) struct SendRequest {
+    function_name: String,
+    params: Vec<serde_json::Value>,
+    /// Stored wallet to sign with
+    #[serde(default)]
+    wallet_name: Option<String>,
+    /// Address to send from without a wallet, on networks that allow
+    /// impersonation (or when simulating)
+    #[serde(default)]
+    from: Option<String>,
+    /// Wei, or an amount with a unit: `0.1 ether`, `30 gwei` or the
+    /// network's currency symbol
+    #[serde(default)]
+    value: Option<String>,
+    /// Geth state override set applied when simulating, keyed by address
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    state_overrides: Option<serde_json::Value>,
+    /// Submit through the network's private relay (Flashbots Protect when it
+    /// has none) with `true`, or the public mempool with `false`; the
+    /// network's setting applies when omitted
+    #[serde(default)]
+    private: Option<bool>,
+    /// The network's name, to send on it while it is locked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unlock_confirm: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SendResponse {
+    tx_hash: TxHash,
+    history_id: i64,
+    /// What the checks before sending found, such as pending transactions
+    warnings: Vec<PreflightWarning>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/deployments/{id}/send",
+    tag = "interact",
+    params(("id" = i64, Path, description = "Deployment ID"), SimulateQuery),
+    request_body = SendRequest,
+    responses(
+        (
+            status = 200,
+            description = "Transaction submitted, or a SimulationResponse with `simulate=true`",
+            body = SendResponse
+        ),
+        (status = 202, description = "Held until a second API token approves it, on protected networks", body = Approval),
+        (
+            status = 400,
+            description = "Not a write function, invalid parameters, or impersonation on a network without it",
+            body = ApiError
+        ),
+        (status = 403, description = "Protected network and no API token", body = ApiError),
+        (status = 404, description = "Deployment, function or wallet not found", body = ApiError),
+        (status = 423, description = "Locked network and no matching `unlock_confirm`", body = ApiError),
+        (status = 502, description = "RPC or transaction failure", body = ApiError)
+    )
+)]
+async fn execute_send(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<SimulateQuery>,
+    caller: Option<Extension<Caller>>,
+    Json(payload): Json<SendRequest>,
+) -> Result<Response, ApiError> {
+    send(
+        &state,
+        id,
+        payload,
+        query.simulate,
+        Requester::from_extension(caller),
+    )
+    .await
+}
+
+/// Send, simulate or hold a transaction for approval
+pub(super) async fn send(
+    state: &AppState,
+    id: i64,
+    payload: SendRequest,
+    simulate: bool,
+    requester: Requester,
+) -> Result<Response, ApiError> {
+    let deployment = get_deployment_by_id(state, id).await?;
+    let network = get_network_by_name(state, &deployment.network_name).await?;
+    let sender = match (&payload.wallet_name, &payload.from) {
+        (Some(name), None) => Sender::Wallet(get_wallet_by_name(state, name).await?),
+        (None, Some(from)) => Sender::Impersonated(
+            from.parse()
+                .map_err(|e| ApiError::bad_request(format!("Invalid from address: {}", e)))?,
+        ),
+        _ => {
+            return Err(ApiError::bad_request(
+                "Set exactly one of wallet_name and from",
+            ))
+        }
+    };
+    if matches!(sender, Sender::Impersonated(_)) && !network.impersonation && !simulate {
+        return Err(ApiError::bad_request(format!(
+            "Network '{}' does not allow impersonation; send with a wallet_name",
+            network.name
+        )));
+    }
+    let relay_url = match payload.private {
+        Some(true) => Some(
+            network
+                .private_relay
+                .clone()
+                .unwrap_or_else(|| relay::FLASHBOTS_PROTECT.to_string()),
+        ),
+        Some(false) => None,
+        None => network.private_relay.clone(),
+    };
+    if payload.private == Some(true) && matches!(sender, Sender::Impersonated(_)) {
+        return Err(ApiError::bad_request(
+            "Impersonated sends can't go through a private relay",
+        ));
+    }
+
+    // Get function from ABI
+    let abi = Abi::parse(&deployment.abi).map_err(|e| ApiError::internal(e.to_string()))?;
+    let function = abi
+        .function(&payload.function_name)
+        .cloned()
+        .ok_or_else(|| {
+            ApiError::not_found(format!("Function '{}' not found", payload.function_name))
+        })?;
+
+    // Verify it's a write function
+    if matches!(
+        function.state_mutability,
+        StateMutability::View | StateMutability::Pure
+    ) {
+        return Err(ApiError::bad_request(format!(
+            "Function '{}' is a read function. Use /call for read operations.",
+            payload.function_name
+        )));
+    }
+
+    let mut params = payload.params.clone();
+    ens::resolve_params(
+        state.repos(),
+        &network,
+        state.ens_network(),
+        &function.inputs,
+        &mut params,
+    )
+    .await
+    .map_err(ApiError::from)?;
+    let call_data = encode_function_call(&function, &params).map_err(ApiError::from)?;
+
+    // Parse value if provided
+    let value = payload
+        .value
+        .as_deref()
+        .map(|v| parse_value(v, network.currency()))
+        .transpose()
+        .map_err(ApiError::from)?;
+
+    let contract_address = *deployment.address;
+
+    if simulate {
+        let simulation = match state.tenderly() {
+            Some(tenderly) => {
+                tenderly
+                    .simulate(
+                        network.chain_id,
+                        sender.address()?,
+                        contract_address,
+                        &call_data,
+                        value,
+                        payload.state_overrides.as_ref(),
+                    )
+                    .await
+            }
+            None => {
+                rpc::simulate(
+                    network.rpc_url.expose(),
+                    sender.address()?,
+                    Some(contract_address),
+                    call_data,
+                    value,
+                    payload.state_overrides.as_ref(),
+                )
+                .instrument(rpc::span(network.chain_id))
+                .await
+            }
+        }
+        .map_err(ApiError::from)?;
+
+        let result = match simulation.success {
+            true => decode_function_result(&function, &simulation.output).ok(),
+            false => None,
+        };
+        let response =
+            SimulationResponse::new(state, &network.name, simulation, result, None).await?;
+        return Ok(Json(response).into_response());
+    }
+
+    // What gets approved is what runs, so ENS names stay resolved
+    let request = SendRequest {
+        params: params.clone(),
+        ..payload.clone()
+    };
+    if let Some(held) = approvals::hold(
+        state,
+        &requester,
+        &network,
+        ApprovalKind::Send,
+        Some(deployment.id),
+        format!("{}.{}", deployment.contract_name, function.signature()),
+        &request,
+    )
+    .await?
+    {
+        return Ok(held);
+    }
+
+    requester
+        .clearance(payload.unlock_confirm.clone())
+        .check(
+            state.repos(),
+            &network,
+            &format!("send {}.{}", deployment.contract_name, function.signature()),
+        )
+        .await?;
+    let signature = function.signature();
+    if let Sender::Wallet(wallet) = &sender {
+        let intent = Intent::call(
+            &network,
+            contract_address,
+            Some(&deployment.contract_name),
+            &signature,
+            value,
+        );
+        policy::check(state.repos(), wallet.id, &wallet.name, &intent).await?;
+    }
+
+    let preflight = rpc::preflight(
+        &network,
+        Outgoing {
+            from: sender.address()?,
+            to: Some(contract_address),
+            data: &call_data,
+            value,
+        },
+    )
+    .instrument(rpc::span(network.chain_id))
+    .await?;
+
+    let history_id = record_call_history(
+        state,
+        deployment.id,
+        sender.wallet_id(),
+        &payload.function_name,
+        &function.signature(),
+        &params,
+        CallType::Write,
+    )
+    .await?;
+
+    // Best effort: a send is never held up by Tenderly being unreachable
+    if let Some(tenderly) = state.tenderly() {
+        let simulated = tenderly
+            .simulate(
+                network.chain_id,
+                sender.address()?,
+                contract_address,
+                &call_data,
+                value,
+                None,
+            )
+            .await;
+        match simulated.map(|s| s.url) {
+            Ok(Some(url)) => {
+                state
+                    .repos()
+                    .call_history()
+                    .set_simulation_url(history_id, &url)
+                    .await?
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, history_id, "could not simulate on Tenderly"),
+        }
+    }
+
+    // Execute transaction
+    let sent = match &sender {
+        Sender::Wallet(wallet) => {
+            let private_key = decrypt_private_key(&wallet.encrypted_key)
+                .map_err(|e| ApiError::internal(e.to_string()))?;
+            let intent = Intent::call(
+                &network,
+                contract_address,
+                Some(&deployment.contract_name),
+                &signature,
+                value,
+            );
+            let reservation =
+                policy::reserve(state.repos(), wallet.id, &wallet.name, &intent).await?;
+            let sent = rpc::execute_transaction(
+                network.rpc_url.expose(),
+                relay_url.as_deref(),
+                &private_key,
+                contract_address,
+                call_data,
+                value,
+            )
+            .instrument(rpc::span(network.chain_id))
+            .await;
+            reservation.settle(state.repos(), sent).await
+        }
+        Sender::Impersonated(from) => {
+            rpc::execute_impersonated(
+                network.rpc_url.expose(),
+                *from,
+                contract_address,
+                call_data,
+                value,
+            )
+            .instrument(rpc::span(network.chain_id))
+            .await
+        }
+    };
+    let tx_hash = sent.map_err(|e| {
+        // Update history with error
+        let state_clone = state.clone();
+        let error_msg = e.to_string();
+        let deployment = deployment.clone();
+        let function_name = payload.function_name.clone();
+        state.spawn(async move {
+            let _ = update_call_history_error(&state_clone, history_id, &error_msg).await;
+            history_stream::publish(&state_clone, history_id).await;
+            let data = serde_json::json!({
+                "history_id": history_id,
+                "function": function_name,
+                "error": error_msg,
+            });
+            webhooks::notify(
+                &state_clone,
+                &Notification::call(&deployment, TransactionStatus::Failed, data),
+            )
+            .await;
+        });
+        ApiError::from(e)
+    })?;
+
+    // Update history with pending tx, then finalize it once mined
+    update_call_history_tx(state, history_id, tx_hash, TransactionStatus::Pending).await?;
+    history_stream::publish(state, history_id).await;
+    receipts::track(
+        state,
+        ReceiptJob {
+            history_id,
+            deployment_id: deployment.id,
+            function_name: payload.function_name.clone(),
+            tx_hash,
+            relay_url: match sender {
+                Sender::Wallet(_) => relay_url,
+                Sender::Impersonated(_) => None,
+            },
+        },
+    )
+    .await;
+
+    Ok(Json(SendResponse {
+        tx_hash,
+        history_id,
+        warnings: preflight.warnings,
+    })
+    .into_response())
+}
+
+// ================================
+// GET /deployments/:id/history
+// ================================
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    #[serde(default = "default_history_limit")]
+    limit: u32,
+    offset: Option<u32>,
+}
+
+fn default_history_limit() -> u32 {
+    100
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/deployments/{id}/history",
+    tag = "interact",
+    params(("id" = i64, Path, description = "Deployment ID"), HistoryQuery),
+    responses((
+        status = 200,
+        description = "Calls made against the deployment, newest first",
+        body = Vec<CallHistoryView>,
+        headers(("x-total-count" = i64, description = "Total matching items before pagination"))
+    ))
+)]
+async fn get_history(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let filter = CallHistoryFilter {
+        deployment_id: Some(DeploymentId(id)),
+        limit: Some(query.limit),
+        offset: query.offset,
+        ..Default::default()
+    };
+
+    let total = state.repos().call_history().count(&filter).await?;
+    let history = state.repos().call_history().list_views(filter).await?;
+
+    Ok(paginated(total, history))
+}
+
+// ================================
+// GET /deployments/:id/storage
+// ================================
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StorageQuery {
+    /// Slot number (decimal or hex) or `implementation`, `admin`, `beacon` or `owner`
+    slot: String,
+    /// Comma-separated mapping keys, outermost first
+    keys: Option<String>,
+    /// Element of the dynamic array at the slot, after any keys
+    index: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/deployments/{id}/storage",
+    tag = "interact",
+    params(("id" = i64, Path, description = "Deployment ID"), StorageQuery),
+    responses(
+        (status = 200, description = "Value stored at the slot", body = StorageWord),
+        (status = 400, description = "Invalid slot, key or index", body = ApiError),
+        (status = 404, description = "Deployment not found", body = ApiError),
+        (status = 502, description = "RPC failure", body = ApiError)
+    )
+)]
+async fn get_storage(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<StorageQuery>,
+) -> Result<Json<StorageWord>, ApiError> {
+    let base = parse_slot(&query.slot).map_err(ApiError::from)?;
+    let keys: Vec<&str> = query
+        .keys
+        .as_deref()
+        .map(|k| k.split(',').collect())
+        .unwrap_or_default();
+    let index = query
+        .index
+        .as_deref()
+        .map(|i| {
+            i.parse::<U256>()
+                .map_err(|e| ApiError::bad_request(format!("Invalid index '{}': {}", i, e)))
+        })
+        .transpose()?;
+    let slot = derive_slot(base, &keys, index);
+
+    let deployment = get_deployment_by_id(&state, id).await?;
+    let network = get_network_by_name(&state, &deployment.network_name).await?;
+    let address = *deployment.address;
+
+    let value = state
+        .rpc()
+        .storage_at(network.rpc_url.expose(), address, slot)
+        .instrument(rpc::span(network.chain_id))
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(StorageWord::new(slot, value)))
+}
+
+// ================================
+// Helper functions
+// ================================
+
+async fn get_deployment_by_id(state: &AppState, id: i64) -> Result<DeploymentView, ApiError> {
+    let deployment = state
+        .repos()
+        .deployments()
+        .get_view_by_id(DeploymentId(id))
+        .await?;
+
+    deployment.ok_or_else(|| ApiError::from(Error::DeploymentNotFound(format!("id {}", id))))
+}
+
+async fn get_network_by_name(state: &AppState, name: &str) -> Result<Network, ApiError> {
+    let network = state.repos().networks().get_by_name(name).await?;
+
+    network.ok_or_else(|| ApiError::from(Error::NetworkNotFound(name.to_string())))
+}
+
+/// Who a transaction is sent as
+enum Sender {
+    Wallet(WalletWithKey),
+    /// Any address, on nodes that let it be impersonated
+    Impersonated(Address),
+}
+
+impl Sender {
+    fn address(&self) -> Result<Address, ApiError> {
+        match self {
+            Self::Wallet(wallet) => Ok(*wallet.address),
+            Self::Impersonated(address) => Ok(*address),
+        }
+    }
+
+    fn wallet_id(&self) -> Option<WalletId> {
+        match self {
+            Self::Wallet(wallet) => Some(wallet.id),
+            Self::Impersonated(_) => None,
+        }
+    }
+}
+
+async fn get_wallet_by_name(state: &AppState, name: &str) -> Result<WalletWithKey, ApiError> {
+    let wallet = state.repos().wallets().get_with_key(name).await?;
+
+    wallet.ok_or_else(|| ApiError::from(Error::WalletNotFound(name.to_string())))
+}
+
+async fn record_call_history(
+    state: &AppState,
+    deployment_id: DeploymentId,
+    wallet_id: Option<WalletId>,
+    function_name: &str,
+    function_signature: &str,
+    params: &[serde_json::Value],
+    call_type: CallType,
+) -> Result<i64, ApiError> {
+    let params_json = serde_json::to_string(params)?;
+
+    let entry = NewCallHistory {
+        deployment_id,
+        wallet_id,
+        function_name: function_name.to_string(),
+        function_signature: function_signature.to_string(),
+        input_params: params_json,
+        call_type,
+    };
+
+    let history = state.repos().call_history().create(&entry).await?;
+
+    Ok(history.id)
+}
+
+async fn update_call_history_tx(
+    state: &AppState,
+    id: i64,
+    tx_hash: TxHash,
+    status: TransactionStatus,
+) -> Result<(), ApiError> {
+    let update = CallHistoryUpdate {
+        result: None,
+        tx_hash: Some(tx_hash),
+        block_number: None,
+        gas_used: None,
+        gas_price: None,
+        status,
+        error_message: None,
+    };
+
+    state.repos().call_history().update(id, &update).await?;
+
+    Ok(())
+}
+
+async fn update_call_history_error(state: &AppState, id: i64, error: &str) -> Result<(), ApiError> {
+    let update = CallHistoryUpdate {
+        result: None,
+        tx_hash: None,
+        block_number: None,
+        gas_used: None,
+        gas_price: None,
+        status: TransactionStatus::Failed,
+        error_message: Some(error.to_string()),
+    };
+
+    state.repos().call_history().update(id, &update).await?;
+
+    Ok(())
+}
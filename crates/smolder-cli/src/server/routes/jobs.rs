@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use smolder_db::{Job, JobFilter, JobState};
+use utoipa::IntoParams;
+
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/jobs", get(list))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListQuery {
+    /// Only list jobs in this state
+    state: Option<JobState>,
+    /// Only list jobs of this kind, e.g. `receipt`
+    kind: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: u32,
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    tag = "jobs",
+    params(ListQuery),
+    responses((status = 200, description = "Background jobs, newest first", body = Vec<Job>))
+)]
+async fn list(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<Job>>, ApiError> {
+    let filter = JobFilter {
+        state: query.state,
+        kind: query.kind,
+        limit: Some(query.limit),
+    };
+    Ok(Json(state.repos().jobs().list(&filter).await?))
+}
@@ -0,0 +1,146 @@
+use alloy::rpc::json_rpc::ErrorPayload;
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    middleware,
+    routing::post,
+    Json, Router,
+};
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use smolder_core::Error;
+
+use crate::server::error::ApiError;
+use crate::server::rpc_proxy::{cache_pin, Pin, READ_METHODS};
+use crate::server::{limits, AppState};
+
+/// Requests allowed in one batch
+const MAX_BATCH: usize = 100;
+
+/// Mounted outside `/api`, at the path JSON-RPC clients are given as their
+/// endpoint
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/rpc/{network}", post(forward))
+        .route_layer(middleware::from_fn(limits::rpc))
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Forward a JSON-RPC request, or a batch of them, to the network's node
+///
+/// Answers `200 OK` with JSON-RPC errors for anything wrong inside the
+/// request, as nodes do, and only fails at the HTTP level for an unknown
+/// network.
+async fn forward(
+    State(state): State<AppState>,
+    Path(network): Path<String>,
+    body: Bytes,
+) -> Result<Json<Value>, ApiError> {
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(&network)
+        .await?
+        .ok_or(Error::NetworkNotFound(network))?;
+    let rpc_url = network.rpc_url.expose();
+
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(_) => {
+            return Ok(Json(response(
+                Value::Null,
+                Err(ErrorPayload::parse_error()),
+            )))
+        }
+    };
+    let answer = match request {
+        Value::Array(batch) if batch.is_empty() || batch.len() > MAX_BATCH => {
+            let mut error = ErrorPayload::invalid_request();
+            if !batch.is_empty() {
+                error.message = format!("Batches are limited to {} requests", MAX_BATCH).into();
+            }
+            response(Value::Null, Err(error))
+        }
+        Value::Array(batch) => {
+            Value::Array(join_all(batch.into_iter().map(|r| answer(&state, rpc_url, r))).await)
+        }
+        request => answer(&state, rpc_url, request).await,
+    };
+    Ok(Json(answer))
+}
+
+/// Answer one request from the cache or the node
+async fn answer(state: &AppState, rpc_url: &str, request: Value) -> Value {
+    let Ok(RpcRequest { id, method, params }) = serde_json::from_value(request) else {
+        return response(Value::Null, Err(ErrorPayload::invalid_request()));
+    };
+    if !READ_METHODS.contains(&method.as_str()) {
+        let mut error = ErrorPayload::method_not_found();
+        error.message = format!(
+            "{} is not forwarded; the proxy only serves reads, send transactions through the API",
+            method
+        )
+        .into();
+        return response(id, Err(error));
+    }
+    let params = match params {
+        Value::Null => json!([]),
+        params => params,
+    };
+
+    let pin = cache_pin(&method, &params);
+    if pin.is_some() {
+        if let Some(result) = state.rpc_cache().get(rpc_url, &method, &params) {
+            return response(id, Ok(result));
+        }
+    }
+    let result = state.rpc().forward(rpc_url, &method, &params).await;
+    if let (Some(pin), Ok(result)) = (pin, &result) {
+        // `null` is what nodes answer for blocks they don't have yet
+        if !result.is_null() && is_settled(state, rpc_url, pin).await {
+            state
+                .rpc_cache()
+                .insert(rpc_url, &method, &params, result.clone());
+        }
+    }
+    response(id, result)
+}
+
+/// Whether answers pinned to `pin` can no longer change, asking the node for
+/// its finalized block when the one last seen is too old to tell
+async fn is_settled(state: &AppState, rpc_url: &str, pin: Pin) -> bool {
+    let Pin::Block(number) = pin else {
+        return true;
+    };
+    if state
+        .rpc_cache()
+        .finalized(rpc_url)
+        .is_some_and(|finalized| number <= finalized)
+    {
+        return true;
+    }
+    match state.rpc().finalized_block(rpc_url).await {
+        Ok(Some(finalized)) => {
+            state.rpc_cache().set_finalized(rpc_url, finalized);
+            number <= finalized
+        }
+        // Without finality, a reorg could always replace the block
+        _ => false,
+    }
+}
+
+fn response(id: Value, result: Result<Value, ErrorPayload>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
@@ -1,18 +1,125 @@
+mod analytics;
+mod approvals;
 mod artifacts;
 mod contracts;
+mod decode;
 mod deploy;
 mod deployments;
+mod ens;
 mod health;
+mod history;
 mod interact;
+mod jobs;
+mod json_rpc;
 mod networks;
+mod openapi;
+mod sandbox;
+mod sync;
+mod tokens;
+mod version;
 mod wallets;
+mod webhooks;
 
-use axum::{routing::get, Router};
+use alloy::primitives::keccak256;
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{
+        header::{self, HeaderName},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
+};
 
-use crate::server::{static_files::serve_static, AppState};
+use smolder_core::ApiRole;
+
+use crate::server::error::ApiError;
+use crate::server::{auth, cors, idempotency, limits, static_files::serve_static, AppState};
+
+/// Response header carrying the total number of items matching a list query
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+/// Build a list response with the unpaginated total in [`TOTAL_COUNT_HEADER`]
+fn paginated<T>(total: i64, items: T) -> ([(HeaderName, HeaderValue); 1], Json<T>) {
+    (
+        [(TOTAL_COUNT_HEADER, HeaderValue::from(total))],
+        Json(items),
+    )
+}
+
+/// Response header carrying the block a deployment was mined in, where
+/// indexers should start scanning
+pub const START_BLOCK_HEADER: HeaderName = HeaderName::from_static("x-start-block");
+
+/// Serve a stored ABI verbatim with an ETag, answering `304 Not Modified`
+/// when the client's `If-None-Match` already covers it
+fn abi_response(request: &HeaderMap, abi: String, start_block: Option<i64>) -> Response {
+    let etag = format!("\"{:x}\"", keccak256(abi.as_bytes()));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+    if let Some(block) = start_block {
+        headers.insert(START_BLOCK_HEADER, HeaderValue::from(block));
+    }
+
+    let cached = request
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        });
+    if cached {
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    (headers, abi).into_response()
+}
+
+/// Serve an entity with its row version as ETag, for `If-Match` on writes
+fn versioned<T: serde::Serialize>(row_version: i64, entity: T) -> Response {
+    let etag = HeaderValue::from_str(&format!("\"{}\"", row_version)).unwrap();
+    ([(header::ETAG, etag)], Json(entity)).into_response()
+}
+
+/// The row version a write is conditional on, from its `If-Match` header
+///
+/// `None` without the header or with `*`, so clients that don't send one
+/// keep last-write-wins.
+fn if_match(headers: &HeaderMap) -> Result<Option<i64>, ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let tag = value.to_str().unwrap_or_default().trim();
+    if tag == "*" {
+        return Ok(None);
+    }
+    tag.trim_start_matches("W/")
+        .trim_matches('"')
+        .parse()
+        .map(Some)
+        .map_err(|_| {
+            ApiError::precondition_failed(format!(
+                "If-Match must be a single ETag from this API, not {}",
+                tag
+            ))
+        })
+}
+
+/// Unknown API routes get a JSON 404 rather than the dashboard
+async fn api_not_found() -> ApiError {
+    ApiError::not_found("No such API route")
+}
 
 pub fn create_router(state: AppState) -> Router {
-    Router::new()
+    let app = Router::new()
         .nest(
             "/api",
             health::router()
@@ -20,24 +127,79 @@ pub fn create_router(state: AppState) -> Router {
                 .merge(contracts::router())
                 .merge(deployments::router())
                 .merge(wallets::router())
+                .merge(webhooks::router())
+                .merge(ens::router())
+                .merge(decode::router())
                 .merge(interact::router())
+                .merge(history::router())
+                .merge(jobs::router())
+                .merge(analytics::router())
                 .merge(artifacts::router())
-                .merge(deploy::router()),
+                .merge(deploy::router())
+                .merge(sandbox::router())
+                .merge(approvals::router())
+                .merge(tokens::router())
+                .merge(sync::router())
+                .merge(version::router())
+                .merge(openapi::router())
+                .fallback(api_not_found),
         )
-        .with_state(state)
-        .fallback(get(serve_static))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            idempotency::dedupe,
+        ))
+        .layer(middleware::from_fn_with_state(
+            ApiRole::Operator,
+            auth::require,
+        ))
+        // Only forwards reads, so viewers may POST to it
+        .merge(json_rpc::router())
+        .layer(DefaultBodyLimit::max(state.limits().max_body_bytes))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            limits::rate_limit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::authenticate,
+        ))
+        .layer(middleware::from_fn_with_state(state.clone(), cors::csrf))
+        .layer(state.cors().layer())
+        .fallback(serve_static)
+        .with_state(state.clone());
+
+    // Behind a proxy mounting the server under a sub-path, everything moves
+    // under it; the bare path redirects so relative links resolve
+    match state.base_path() {
+        "" => app,
+        base_path => {
+            let index = format!("{}/", base_path);
+            Router::new()
+                .route(
+                    base_path,
+                    get(move || async move { Redirect::permanent(&index) }),
+                )
+                .nest(base_path, app)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use axum::{body::Body, http::Request, Router};
+    use smolder_core::{BytecodeHash, TxHash, B256};
     use smolder_db::{
         ChainId, Contract, ContractRepository, Database, DeploymentRepository, DeploymentView,
-        Network, NetworkRepository, NewContract, NewDeployment, NewNetwork,
+        GasSampleRepository, Network, NetworkRepository, NewContract, NewDeployment, NewGasSample,
+        NewNetwork,
     };
     use tower::ServiceExt;
 
     async fn setup_test_app() -> Router {
+        super::create_router(setup_test_state().await)
+    }
+
+    async fn setup_test_state() -> crate::server::AppState {
         let db = Database::connect_to(":memory:").await.unwrap();
         db.init_schema().await.unwrap();
 
@@ -60,7 +222,9 @@ mod tests {
                 name: "TestToken".to_string(),
                 source_path: "src/TestToken.sol".to_string(),
                 abi: r#"[{"type":"function","name":"transfer"}]"#.to_string(),
-                bytecode_hash: "0xabc123".to_string(),
+                bytecode_hash: BytecodeHash::of("TestToken"),
+                storage_layout: None,
+                compiler: None,
             },
         )
         .await
@@ -71,26 +235,91 @@ mod tests {
             &NewDeployment {
                 contract_id: contract.id,
                 network_id: network.id,
-                address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+                address: "0x1234567890abcdef1234567890abcdef12345678"
+                    .parse()
+                    .unwrap(),
                 deployer: "0xdeployer".to_string(),
-                tx_hash: "0xtxhash".to_string(),
+                tx_hash: Some(TxHash(B256::repeat_byte(0x11))),
                 block_number: Some(100),
                 constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
             },
         )
         .await
         .unwrap();
 
-        let state = crate::server::AppState::new(db);
+        for (block_number, gas_price) in [(100, 30), (101, 10), (102, 20)] {
+            GasSampleRepository::record(
+                &db,
+                &NewGasSample {
+                    network_id: network.id,
+                    block_number,
+                    base_fee: None,
+                    gas_price,
+                },
+            )
+            .await
+            .unwrap();
+        }
 
-        super::create_router(state)
+        crate::server::AppState::new(
+            db,
+            std::sync::Arc::new(crate::forge::FileSystemArtifactLoader::new()),
+        )
     }
 
     #[tokio::test]
     async fn test_health_check() {
         let app = setup_test_app().await;
 
+        let health = || {
+            Request::builder()
+                .uri("/api/health")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(health()).await.unwrap();
+
+        // The test network's RPC is unreachable, which degrades but does not
+        // fail the check
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["database"]["status"], "up");
+        assert_eq!(json["networks"][0]["name"], "testnet");
+        assert_eq!(json["networks"][0]["status"], "down");
+        assert_eq!(json["networks"][0]["cached"], false);
+
+        // The probe is reused on the next poll
+        let response = app.oneshot(health()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["networks"][0]["cached"], true);
+    }
+
+    /// Node of chain 12345 at block 100, finalized
+    fn fake_rpc() -> crate::rpc::fake::FakeRpc {
+        crate::rpc::fake::FakeRpc::new(12345, 100).with_finality_depth(0)
+    }
+
+    #[tokio::test]
+    async fn test_node_reads_go_through_rpc_client() {
+        let state = setup_test_state()
+            .await
+            .with_rpc(std::sync::Arc::new(fake_rpc()));
+        let app = super::create_router(state);
+
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .uri("/api/health")
@@ -99,8 +328,194 @@ mod tests {
             )
             .await
             .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["networks"][0]["status"], "up");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/deployments/1/storage?slot=5")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["uint"], "5");
+    }
+
+    #[tokio::test]
+    async fn test_rpc_proxy() {
+        use crate::server::auth::hash_token;
+        use smolder_db::{ApiRole, ApiTokenRepository};
+        use std::sync::Arc;
+
+        let rpc = Arc::new(fake_rpc());
+        let state = setup_test_state().await.with_rpc(rpc.clone());
+        let app = super::create_router(state.clone());
+        let post = |uri: &str, token: Option<&str>, body: &'static str| {
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json");
+            if let Some(token) = token {
+                builder = builder.header("authorization", format!("Bearer {}", token));
+            }
+            let request = builder.body(Body::from(body)).unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap() }
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let chain_id = r#"{"jsonrpc":"2.0","id":7,"method":"eth_chainId"}"#;
+        for _ in 0..2 {
+            let answer = json(post("/rpc/testnet", None, chain_id).await).await;
+            assert_eq!(answer["id"], 7);
+            assert_eq!(answer["result"], "0x3039");
+        }
+        // The chain ID never changes, so only the first went to the node
+        assert_eq!(rpc.forwarded(), 1);
+
+        let batch = r#"[
+            {"jsonrpc":"2.0","id":1,"method":"eth_getBalance","params":["0x01","latest"]},
+            {"jsonrpc":"2.0","id":2,"method":"eth_getBalance","params":["0x01","0x64"]},
+            {"jsonrpc":"2.0","id":3,"method":"eth_sendRawTransaction","params":["0x00"]},
+            {"jsonrpc":"2.0","id":4,"method":"eth_call","params":[{},"0x64"]},
+            {"jsonrpc":"2.0","id":5,"method":"eth_getBalance","params":["0x01","0x65"]},
+            {"jsonrpc":"2.0","id":6,"method":"eth_getBlockByNumber","params":["0x10",false]}
+        ]"#;
+        for round in 0..2 {
+            let answer = json(post("/rpc/testnet", None, batch).await).await;
+            assert_eq!(answer[0]["id"], 1);
+            assert_eq!(answer[1]["result"][1], "0x64");
+            assert_eq!(answer[2]["error"]["code"], -32601);
+            assert_eq!(answer[3]["error"]["message"], "execution reverted");
+            assert!(answer[5]["result"].is_null());
+            // Reads at `latest`, past the finalized block 100, answered with
+            // null or failed go to the node every time; reads at a
+            // finalized block once
+            let forwarded = [6, 10][round];
+            assert_eq!(rpc.forwarded(), forwarded);
+        }
+
+        let answer = json(post("/rpc/testnet", None, "{").await).await;
+        assert_eq!(answer["error"]["code"], -32700);
+        let answer = json(post("/rpc/testnet", None, "[]").await).await;
+        assert_eq!(answer["error"]["code"], -32600);
+        let response = post("/rpc/mainnet", None, chain_id).await;
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        // Once tokens exist it needs one, but any role will do
+        ApiTokenRepository::create(state.db(), "viewer", &hash_token("viewer"), ApiRole::Viewer)
+            .await
+            .unwrap();
+        let response = post("/rpc/testnet", None, chain_id).await;
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+        let response = post("/rpc/testnet", Some("viewer"), chain_id).await;
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_handlers_go_through_repositories() {
+        // Seed an in-memory registry that differs from the SQLite one
+        let repos = smolder_db::MemoryDatabase::new();
+        let network = NetworkRepository::upsert(
+            &repos,
+            &NewNetwork {
+                name: "memnet".to_string(),
+                chain_id: ChainId(31337),
+                rpc_url: "http://localhost:8545".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        let contract = ContractRepository::upsert(
+            &repos,
+            &NewContract {
+                name: "Counter".to_string(),
+                source_path: "src/Counter.sol".to_string(),
+                abi: "[]".to_string(),
+                bytecode_hash: BytecodeHash::of("Counter"),
+                storage_layout: None,
+                compiler: None,
+            },
+        )
+        .await
+        .unwrap();
+        DeploymentRepository::create(
+            &repos,
+            &NewDeployment {
+                contract_id: contract.id,
+                network_id: network.id,
+                address: "0x00000000000000000000000000000000000000cc"
+                    .parse()
+                    .unwrap(),
+                deployer: "0xdeployer".to_string(),
+                tx_hash: None,
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let state = setup_test_state()
+            .await
+            .with_repositories(std::sync::Arc::new(repos));
+        let app = super::create_router(state);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/networks")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let networks: Vec<Network> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(networks.len(), 1);
+        assert_eq!(networks[0].name, "memnet");
 
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/deployments")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
         assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deployments: Vec<DeploymentView> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].contract_name, "Counter");
+        assert_eq!(deployments[0].network_name, "memnet");
     }
 
     #[tokio::test]
@@ -129,6 +544,60 @@ mod tests {
         assert_eq!(networks[0].chain_id, ChainId(12345));
     }
 
+    #[tokio::test]
+    async fn test_networks_hide_rpc_credentials() {
+        let state = setup_test_state().await;
+        NetworkRepository::upsert(
+            state.db(),
+            &NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://mainnet.infura.io/v3/9aa3d95b3bc440fa88ea12eaa4456161"
+                    .to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        let app = super::create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/networks")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("9aa3d95b"));
+        let networks: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let url = |name: &str| {
+            let network = networks.iter().find(|n| n["name"] == name).unwrap();
+            (
+                network["rpc_url"].clone(),
+                network["has_credentials"].clone(),
+            )
+        };
+        assert_eq!(
+            url("mainnet"),
+            (
+                serde_json::json!("https://mainnet.infura.io/v3/***"),
+                serde_json::json!(true)
+            )
+        );
+        assert_eq!(
+            url("testnet"),
+            (
+                serde_json::json!("https://rpc.test.xyz"),
+                serde_json::json!(false)
+            )
+        );
+    }
+
     #[tokio::test]
     async fn test_get_network() {
         let app = setup_test_app().await;
@@ -267,9 +736,10 @@ mod tests {
         let deployment: DeploymentView = serde_json::from_slice(&body).unwrap();
 
         assert_eq!(deployment.contract_name, "TestToken");
+        // Stored and rendered checksummed, whatever the casing it was given in
         assert_eq!(
-            deployment.address,
-            "0x1234567890abcdef1234567890abcdef12345678"
+            deployment.address.to_string(),
+            "0x1234567890AbcdEF1234567890aBcdef12345678"
         );
     }
 
@@ -289,4 +759,1979 @@ mod tests {
 
         assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_archive_deployment() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/api/deployments/1?archive=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/deployments")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deployments: Vec<DeploymentView> = serde_json::from_slice(&body).unwrap();
+
+        assert!(deployments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_deployment_not_found() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/api/deployments/999")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_update_deployment() {
+        let app = setup_test_app().await;
+        let patch = |uri: &str, body: &'static str| {
+            Request::builder()
+                .method("PATCH")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(patch("/api/deployments/1", r#"{"pinned": true}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deployment: DeploymentView = serde_json::from_slice(&body).unwrap();
+        assert!(deployment.pinned);
+
+        // Fields left out are kept
+        let response = app
+            .clone()
+            .oneshot(patch("/api/deployments/1", "{}"))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deployment: DeploymentView = serde_json::from_slice(&body).unwrap();
+        assert!(deployment.pinned);
+
+        let response = app
+            .clone()
+            .oneshot(patch(
+                "/api/deployments/1",
+                r#"{"label": "Main token", "description": "Minted by the **DAO**"}"#,
+            ))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deployment: DeploymentView = serde_json::from_slice(&body).unwrap();
+        assert_eq!(deployment.label.as_deref(), Some("Main token"));
+        assert_eq!(
+            deployment.description.as_deref(),
+            Some("Minted by the **DAO**")
+        );
+
+        // An empty label removes it and keeps the description
+        let response = app
+            .clone()
+            .oneshot(patch("/api/deployments/1", r#"{"label": ""}"#))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deployment: DeploymentView = serde_json::from_slice(&body).unwrap();
+        assert!(deployment.label.is_none());
+        assert!(deployment.description.is_some());
+
+        let response = app
+            .oneshot(patch("/api/deployments/999", r#"{"pinned": true}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_delete_contract() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/api/contracts/TestToken")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/contracts/TestToken")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_list_deployments_returns_total_count() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/deployments?limit=1&offset=1&sort=deployed_at&order=desc")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(
+            response.headers().get(super::TOTAL_COUNT_HEADER).unwrap(),
+            "1"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deployments: Vec<DeploymentView> = serde_json::from_slice(&body).unwrap();
+
+        assert!(deployments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_deployments_with_status() {
+        let app = setup_test_app().await;
+
+        for (uri, has_status_key) in [
+            ("/api/deployments", false),
+            // The fixture network can't be reached, so there is no status
+            ("/api/deployments?include_status=true", false),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK, "{}", uri);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let deployments: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+            assert_eq!(deployments.len(), 1, "{}", uri);
+            assert_eq!(
+                deployments[0].get("status").is_some(),
+                has_status_key,
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_deployment_abi_with_etag() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/deployments/1/abi")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+        assert_eq!(
+            response.headers().get(super::START_BLOCK_HEADER).unwrap(),
+            "100"
+        );
+        let etag = response.headers().get("etag").unwrap().clone();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], br#"[{"type":"function","name":"transfer"}]"#);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/deployments/1/abi")
+                    .header("if-none-match", etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn test_get_contract_abi() {
+        let app = setup_test_app().await;
+
+        for (uri, status) in [
+            ("/api/contracts/TestToken/abi", axum::http::StatusCode::OK),
+            (
+                "/api/contracts/TestToken/abi?network=testnet",
+                axum::http::StatusCode::OK,
+            ),
+            (
+                "/api/contracts/TestToken/abi?network=mainnet",
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+            (
+                "/api/contracts/Missing/abi",
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_contract_deployments() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/contracts/TestToken/deployments")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["network_name"], "testnet");
+        assert_eq!(json[0]["version"], 1);
+        assert!(json[0].get("verification").is_none());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/contracts/Missing/deployments")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_list_history() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/history?status=pending&network=testnet")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(
+            response.headers().get(super::TOTAL_COUNT_HEADER).unwrap(),
+            "0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history_stream() {
+        use futures::StreamExt;
+        use smolder_db::{
+            CallHistoryUpdate, CallType, DeploymentId, NewCallHistory, TransactionStatus,
+        };
+
+        let state = setup_test_state().await;
+        let app = super::create_router(state.clone());
+        let subscribe = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.headers()["content-type"], "text/event-stream");
+                response.into_body().into_data_stream()
+            }
+        };
+        let mut all = subscribe("/api/history/stream").await;
+        let mut other = subscribe("/api/history/stream?deployment_id=2").await;
+
+        let entry = state
+            .repos()
+            .call_history()
+            .create(&NewCallHistory {
+                deployment_id: DeploymentId(1),
+                wallet_id: None,
+                function_name: "transfer".to_string(),
+                function_signature: "transfer(address,uint256)".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Write,
+            })
+            .await
+            .unwrap();
+        for status in [TransactionStatus::Pending, TransactionStatus::Success] {
+            let update = CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(TxHash(B256::repeat_byte(0x22))),
+                block_number: None,
+                gas_used: None,
+                gas_price: None,
+                status,
+                error_message: None,
+            };
+            state
+                .repos()
+                .call_history()
+                .update(entry.id, &update)
+                .await
+                .unwrap();
+            crate::server::history_stream::publish(&state, entry.id).await;
+
+            let chunk = all.next().await.unwrap().unwrap();
+            let event = String::from_utf8(chunk.to_vec()).unwrap();
+            assert!(event.starts_with("event: status\n"), "{}", event);
+            assert!(event.contains(&format!("id: {}\n", entry.id)));
+            let data = event
+                .lines()
+                .find_map(|line| line.strip_prefix("data: "))
+                .unwrap();
+            let data: serde_json::Value = serde_json::from_str(data).unwrap();
+            assert_eq!(data["status"], serde_json::to_value(status).unwrap());
+        }
+
+        // Streams end on shutdown, having seen nothing of other deployments
+        state.shutdown_token().cancel();
+        assert!(all.next().await.is_none());
+        assert!(other.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_jobs() {
+        use smolder_db::NewJob;
+
+        let state = setup_test_state().await;
+        for kind in ["receipt", "receipt", "webhook"] {
+            state
+                .repos()
+                .jobs()
+                .enqueue(&NewJob {
+                    kind: kind.to_string(),
+                    payload: "{}".to_string(),
+                    max_attempts: 3,
+                })
+                .await
+                .unwrap();
+        }
+        let app = super::create_router(state.clone());
+        let list = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), axum::http::StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<Vec<serde_json::Value>>(&body).unwrap()
+            }
+        };
+
+        let jobs = list("/api/jobs").await;
+        assert_eq!(jobs.len(), 3);
+        assert_eq!(jobs[0]["kind"], "webhook");
+        assert_eq!(jobs[0]["state"], "queued");
+
+        let receipts = list("/api/jobs?kind=receipt&limit=1").await;
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0]["kind"], "receipt");
+        assert!(list("/api/jobs?state=failed").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_openapi_spec() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let paths = spec["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/api/deploy"));
+        assert!(paths.contains_key("/api/deployments/{id}/call"));
+        assert!(spec["components"]["schemas"]["DeploymentView"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_lifecycle() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/webhooks")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"url":"https://hooks.example.com/in","network":"testnet","tx_status":"success"}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(created["secret"].as_str().unwrap().starts_with("whsec_"));
+        assert_eq!(created["tx_status"], "success");
+
+        // Secrets are only shown on creation
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/webhooks")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+        assert!(listed[0].get("secret").is_none());
+
+        let uri = format!("/api/webhooks/{}/deliveries", created["id"]);
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let uri = format!("/api/webhooks/{}", created["id"]);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/webhooks")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"url":"ftp://example.com"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_replays_response() {
+        let app = setup_test_app().await;
+        let create = |key: &str, url: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/webhooks")
+                .header("content-type", "application/json")
+                .header("idempotency-key", key)
+                .body(Body::from(format!(r#"{{"url":"{}"}}"#, url)))
+                .unwrap()
+        };
+
+        let first = app
+            .clone()
+            .oneshot(create("k1", "https://hooks.example.com/in"))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), axum::http::StatusCode::CREATED);
+        assert!(first.headers().get("idempotent-replayed").is_none());
+        let first = axum::body::to_bytes(first.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        // The retry gets the same webhook, secret included, without a second one
+        let retry = app
+            .clone()
+            .oneshot(create("k1", "https://hooks.example.com/in"))
+            .await
+            .unwrap();
+        assert_eq!(retry.status(), axum::http::StatusCode::CREATED);
+        assert_eq!(retry.headers()["idempotent-replayed"], "true");
+        assert_eq!(retry.headers()["content-type"], "application/json");
+        let retry = axum::body::to_bytes(retry.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(first, retry);
+
+        let reused = app
+            .clone()
+            .oneshot(create("k1", "https://hooks.example.com/other"))
+            .await
+            .unwrap();
+        assert_eq!(
+            reused.status(),
+            axum::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/webhooks")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_keeps_no_secret() {
+        use crate::server::auth::hash_token;
+        use smolder_db::{ApiRole, ApiTokenRepository};
+
+        let state = setup_test_state().await;
+        ApiTokenRepository::create(state.db(), "admin", &hash_token("admin"), ApiRole::Admin)
+            .await
+            .unwrap();
+        let app = super::create_router(state.clone());
+        let create = || {
+            Request::builder()
+                .method("POST")
+                .uri("/api/tokens")
+                .header("content-type", "application/json")
+                .header("authorization", "Bearer admin")
+                .header("idempotency-key", "k1")
+                .body(Body::from(r#"{"name":"ci"}"#))
+                .unwrap()
+        };
+
+        let first = app.clone().oneshot(create()).await.unwrap();
+        assert_eq!(first.status(), axum::http::StatusCode::CREATED);
+        let body = axum::body::to_bytes(first.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let secret = created["secret"].as_str().unwrap();
+
+        // The retry can't be given the secret, which was never stored
+        let retry = app.oneshot(create()).await.unwrap();
+        assert_eq!(retry.status(), axum::http::StatusCode::CONFLICT);
+        assert_eq!(retry.headers()["idempotent-replayed"], "true");
+        let bodies: Vec<Vec<u8>> = sqlx::query_scalar("SELECT body FROM idempotency_keys")
+            .fetch_all(state.db().read_pool())
+            .await
+            .unwrap();
+        assert_eq!(bodies.len(), 1);
+        assert!(!String::from_utf8_lossy(&bodies[0]).contains(secret));
+    }
+
+    #[tokio::test]
+    async fn test_network_if_match() {
+        let app = setup_test_app().await;
+        let patch = |if_match: &str| {
+            Request::builder()
+                .method("PATCH")
+                .uri("/api/networks/testnet")
+                .header("content-type", "application/json")
+                .header("if-match", if_match)
+                .body(Body::from(r#"{"rpc_url":"https://rpc2.test.xyz"}"#))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/networks/testnet")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let etag = response.headers()["etag"].to_str().unwrap().to_string();
+        assert_eq!(etag, "\"1\"");
+
+        let response = app.clone().oneshot(patch(&etag)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(response.headers()["etag"], "\"2\"");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let network: Network = serde_json::from_slice(&body).unwrap();
+        assert_eq!(network.rpc_url, "https://rpc2.test.xyz");
+
+        // A second edit made from the same read would clobber the first
+        let response = app.clone().oneshot(patch(&etag)).await.unwrap();
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::PRECONDITION_FAILED
+        );
+        let response = app.clone().oneshot(patch("*")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let response = app.oneshot(patch("not-an-etag")).await.unwrap();
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::PRECONDITION_FAILED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ens_validates_input() {
+        let app = setup_test_app().await;
+
+        for (uri, status) in [
+            (
+                "/api/ens/lookup/not-an-address",
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/api/ens/resolve/vitalik.eth?network=unknown",
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_storage_validates_input() {
+        let app = setup_test_app().await;
+
+        for (uri, status) in [
+            (
+                "/api/deployments/1/storage?slot=nope",
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/api/deployments/1/storage?slot=0&index=-1",
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/api/deployments/999/storage?slot=implementation",
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_roles_validates_input() {
+        let app = setup_test_app().await;
+
+        for (uri, status) in [
+            // The fixture ABI can't be parsed, so it can't be Ownable either
+            (
+                "/api/deployments/1/roles",
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/api/deployments/999/roles",
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_validates_input() {
+        let app = setup_test_app().await;
+
+        let post = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/sandbox")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        for (body, status) in [
+            (
+                r#"{"network":"missing"}"#,
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+            (
+                r#"{"network":"testnet","name":"testnet"}"#,
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                r#"{"network":"testnet","rpc_url":"http://127.0.0.1:8545","port":8545}"#,
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+        ] {
+            let response = app.clone().oneshot(post(body)).await.unwrap();
+            assert_eq!(response.status(), status, "{}", body);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/sandbox")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"[]");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/api/sandbox/testnet-sandbox")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_send_impersonation_requires_capability() {
+        let app = setup_test_app().await;
+
+        for body in [
+            // Both or neither of wallet_name and from
+            r#"{"function_name":"transfer","params":[],"wallet_name":"w","from":"0x0000000000000000000000000000000000000001"}"#,
+            r#"{"function_name":"transfer","params":[]}"#,
+            r#"{"function_name":"transfer","params":[],"from":"not-an-address"}"#,
+            // testnet is not marked for impersonation
+            r#"{"function_name":"transfer","params":[],"from":"0x0000000000000000000000000000000000000001"}"#,
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/deployments/1/send")
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                response.status(),
+                axum::http::StatusCode::BAD_REQUEST,
+                "{}",
+                body
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_export() {
+        let app = setup_test_app().await;
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get("/api/history/export?format=csv&deployment_id=1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/csv");
+        assert_eq!(
+            response.headers()["content-disposition"],
+            "attachment; filename=\"history.csv\""
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.starts_with(b"id,created_at,"));
+
+        let response = app
+            .clone()
+            .oneshot(get("/api/history/export?since=2020-01-01"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "application/json");
+
+        let response = app
+            .oneshot(get("/api/history/export?format=xml"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_trace_requires_a_transaction() {
+        use smolder_db::{CallType, DeploymentId, NewCallHistory};
+
+        let state = setup_test_state().await;
+        let read = state
+            .repos()
+            .call_history()
+            .create(&NewCallHistory {
+                deployment_id: DeploymentId(1),
+                wallet_id: None,
+                function_name: "totalSupply".to_string(),
+                function_signature: "totalSupply()".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Read,
+            })
+            .await
+            .unwrap();
+        let app = super::create_router(state);
+
+        for (uri, status) in [
+            (
+                format!("/api/history/{}/trace", read.id),
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/api/history/999/trace".to_string(),
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gas_analytics() {
+        let app = setup_test_app().await;
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get("/api/analytics/gas?network=testnet&interval=week"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"[]");
+
+        let response = app
+            .clone()
+            .oneshot(get("/api/analytics/gas?interval=year"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(get("/api/analytics/costs?since=2025-01-01&interval=month"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"[]");
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/stats?recent=5")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["contracts"], 1);
+        assert_eq!(json["deployments"], 1);
+        assert_eq!(json["pending_txs"], 0);
+        assert_eq!(json["networks"][0]["network"], "testnet");
+        assert_eq!(json["networks"][0]["deployments"], 1);
+        assert_eq!(json["recent_activity"][0]["kind"], "deployment");
+        assert_eq!(json["recent_activity"][0]["contract_name"], "TestToken");
+    }
+
+    #[tokio::test]
+    async fn test_gas_history() {
+        let app = setup_test_app().await;
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get("/api/networks/testnet/gas-history"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let history: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(history["samples"].as_array().unwrap().len(), 3);
+        assert_eq!(history["summary"]["latest"], 20);
+        assert_eq!(history["summary"]["median"], 20);
+        assert_eq!(history["summary"]["percentile"], 33);
+
+        let response = app
+            .clone()
+            .oneshot(get("/api/networks/testnet/gas-history?limit=1"))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let history: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(history["samples"][0]["block_number"], 102);
+
+        let response = app
+            .oneshot(get("/api/networks/unknown/gas-history"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_groups() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/deploy/multi")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"artifact_name":"Missing","network_names":["testnet"],"wallet_name":"deployer"}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app
+            .clone()
+            .oneshot(get("/api/deploy/groups"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"[]");
+
+        let response = app.oneshot(get("/api/deploy/groups/1")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    /// Deploy a pausable Vault on the test network, for sends
+    async fn insert_vault(db: &Database) -> smolder_db::Deployment {
+        let contract = ContractRepository::upsert(
+            db,
+            &NewContract {
+                name: "Vault".to_string(),
+                source_path: "src/Vault.sol".to_string(),
+                abi: r#"[{"type":"function","name":"pause","inputs":[],"outputs":[],"stateMutability":"nonpayable"}]"#.to_string(),
+                bytecode_hash: BytecodeHash::of("Vault"),
+                storage_layout: None,
+                compiler: None,
+            },
+        )
+        .await
+        .unwrap();
+        let network = NetworkRepository::get_by_name(db, "testnet")
+            .await
+            .unwrap()
+            .unwrap();
+        DeploymentRepository::create(
+            db,
+            &NewDeployment {
+                contract_id: contract.id,
+                network_id: network.id,
+                address: "0x00000000000000000000000000000000000000aa"
+                    .parse()
+                    .unwrap(),
+                deployer: "0xdeployer".to_string(),
+                tx_hash: Some(TxHash(B256::repeat_byte(0xaa))),
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_approvals() {
+        use crate::server::auth::hash_token;
+        use smolder_db::{ApiRole, ApiTokenRepository};
+
+        let state = setup_test_state().await;
+        let db = state.db();
+        NetworkRepository::set_protected(db, "testnet", true)
+            .await
+            .unwrap();
+        NetworkRepository::set_impersonation(db, "testnet", true)
+            .await
+            .unwrap();
+        let vault = insert_vault(db).await;
+        let app = super::create_router(state.clone());
+
+        let request = |method: &str, uri: &str, token: Option<&str>, body: &'static str| {
+            let mut builder = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json");
+            if let Some(token) = token {
+                builder = builder.header("authorization", format!("Bearer {}", token));
+            }
+            builder.body(Body::from(body)).unwrap()
+        };
+        let send_uri = format!("/api/deployments/{}/send", vault.id);
+        let pause = r#"{"function_name":"pause","params":[],"from":"0x0000000000000000000000000000000000000001"}"#;
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        // Without tokens nobody could approve, so protected networks refuse
+        let response = app
+            .clone()
+            .oneshot(request("POST", &send_uri, None, pause))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        ApiTokenRepository::create(db, "alice", &hash_token("alice-secret"), ApiRole::Admin)
+            .await
+            .unwrap();
+        ApiTokenRepository::create(db, "bob", &hash_token("bob-secret"), ApiRole::Admin)
+            .await
+            .unwrap();
+
+        // Once a token exists, every request but the health check needs one
+        for token in [None, Some("wrong")] {
+            let response = app
+                .clone()
+                .oneshot(request("GET", "/api/networks", token, ""))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+        }
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/api/health", None, ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        // Groups can't wait for approval
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/deploy/multi",
+                Some("alice-secret"),
+                r#"{"artifact_name":"Vault","network_names":["testnet"],"wallet_name":"deployer"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request("POST", &send_uri, Some("alice-secret"), pause))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::ACCEPTED);
+        let held = json(response).await;
+        assert_eq!(held["status"], "pending");
+        assert_eq!(held["summary"], "Vault.pause()");
+        assert_eq!(held["requested_by"], "alice");
+        let approve_uri = format!("/api/approvals/{}/approve", held["id"]);
+
+        // The requester can't approve their own request
+        let response = app
+            .clone()
+            .oneshot(request("POST", &approve_uri, Some("alice-secret"), ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/api/approvals?status=pending",
+                Some("bob-secret"),
+                "",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json(response).await.as_array().unwrap().len(), 1);
+
+        // Nor can a token minted after the request
+        ApiTokenRepository::create(db, "carol", &hash_token("carol-secret"), ApiRole::Admin)
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(request("POST", &approve_uri, Some("carol-secret"), ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        // Approval runs the request; the test RPC is unreachable, so it fails
+        let response = app
+            .clone()
+            .oneshot(request("POST", &approve_uri, Some("bob-secret"), ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let approved = json(response).await;
+        assert_eq!(approved["status"], "failed");
+        assert_eq!(approved["decided_by"], "bob");
+        assert!(approved["error_message"].is_string());
+
+        let response = app
+            .clone()
+            .oneshot(request("POST", &approve_uri, Some("bob-secret"), ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::CONFLICT);
+
+        // Rejected requests never run
+        let response = app
+            .clone()
+            .oneshot(request("POST", &send_uri, Some("alice-secret"), pause))
+            .await
+            .unwrap();
+        let held = json(response).await;
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                &format!("/api/approvals/{}/reject", held["id"]),
+                Some("bob-secret"),
+                "",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json(response).await["status"], "rejected");
+
+        // Once its requester is forgotten, nobody can tell who may approve
+        let response = app
+            .clone()
+            .oneshot(request("POST", &send_uri, Some("alice-secret"), pause))
+            .await
+            .unwrap();
+        let held = json(response).await;
+        sqlx::query("UPDATE approvals SET requester_token_id = NULL WHERE id = ?")
+            .bind(held["id"].as_i64())
+            .execute(db.write_pool())
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                &format!("/api/approvals/{}/approve", held["id"]),
+                Some("bob-secret"),
+                "",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        let response = app
+            .oneshot(request("GET", "/api/approvals/999", Some("bob-secret"), ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_network_locks() {
+        let state = setup_test_state().await;
+        let db = state.db();
+        NetworkRepository::set_impersonation(db, "testnet", true)
+            .await
+            .unwrap();
+        let vault = insert_vault(db).await;
+        let app = super::create_router(state.clone());
+
+        let post = |uri: &str, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post("/api/networks/testnet/lock", String::new()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(json(response).await["locked"], true);
+
+        // Without the network's name, or with another one, nothing is sent
+        let send_uri = format!("/api/deployments/{}/send", vault.id);
+        for confirm in ["", r#","unlock_confirm":"mainnet""#] {
+            let body = format!(
+                r#"{{"function_name":"pause","params":[],"from":"0x0000000000000000000000000000000000000001"{}}}"#,
+                confirm
+            );
+            let response = app.clone().oneshot(post(&send_uri, body)).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::LOCKED);
+            assert_eq!(json(response).await["code"], "NETWORK_LOCKED");
+        }
+        let response = app
+            .clone()
+            .oneshot(post("/api/networks/testnet/unlock", String::new()))
+            .await
+            .unwrap();
+        assert_eq!(json(response).await["locked"], false);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/audit")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let log = json(response).await;
+        let actions: Vec<_> = log
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["action"].as_str().unwrap(), e["source"].as_str().unwrap()))
+            .collect();
+        assert_eq!(actions, [("unlock", "api"), ("lock", "api")]);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_policy() {
+        let state = setup_test_state().await;
+        let vault = insert_vault(state.db()).await;
+        let app = super::create_router(state);
+
+        let request = |method: &str, uri: &str, body: String| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+        let wallet = serde_json::json!({
+            "name": "ops",
+            "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        });
+        let response = app
+            .clone()
+            .oneshot(request("POST", "/api/wallets", wallet.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let policy = serde_json::json!({
+            "max_value_per_tx": "0.5 ether",
+            "allowed_networks": ["testnet"],
+            "allowed_functions": ["deposit"],
+        });
+        let response = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/api/wallets/ops/policy",
+                policy.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let stored = json(response).await;
+        assert_eq!(stored["max_value_per_tx"], "500000000000000000");
+        assert_eq!(stored["max_daily_spend"], serde_json::Value::Null);
+
+        let unknown = serde_json::json!({ "allowed_networks": ["nowhere"] });
+        let response = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/api/wallets/ops/policy",
+                unknown.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        // Functions outside the policy are refused before anything is sent
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                &format!("/api/deployments/{}/send", vault.id),
+                r#"{"function_name":"pause","params":[],"wallet_name":"ops"}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+        let error = json(response).await;
+        assert_eq!(error["code"], "POLICY_VIOLATION");
+        assert!(error["message"].as_str().unwrap().contains("pause()"));
+
+        let response = app
+            .clone()
+            .oneshot(request("DELETE", "/api/wallets/ops/policy", String::new()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+        let response = app
+            .oneshot(request("GET", "/api/wallets/ops/policy", String::new()))
+            .await
+            .unwrap();
+        assert_eq!(
+            json(response).await["allowed_functions"],
+            serde_json::Value::Null
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_roles() {
+        use crate::server::auth::hash_token;
+        use smolder_db::{ApiRole, ApiTokenRepository};
+
+        let state = setup_test_state().await;
+        let db = state.db();
+        for (name, role) in [
+            ("viewer", ApiRole::Viewer),
+            ("operator", ApiRole::Operator),
+            ("admin", ApiRole::Admin),
+        ] {
+            ApiTokenRepository::create(db, name, &hash_token(name), role)
+                .await
+                .unwrap();
+        }
+        NetworkRepository::set_protected(db, "testnet", true)
+            .await
+            .unwrap();
+        NetworkRepository::set_impersonation(db, "testnet", true)
+            .await
+            .unwrap();
+        let vault = insert_vault(db).await;
+        let app = super::create_router(state.clone());
+
+        let request = |method: &str, uri: &str, token: &str, body: &'static str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let status = |method: &'static str, uri: &str, token: &'static str, body| {
+            let (app, uri) = (app.clone(), uri.to_string());
+            async move {
+                app.oneshot(request(method, &uri, token, body))
+                    .await
+                    .unwrap()
+                    .status()
+                    .as_u16()
+            }
+        };
+        let send_uri = format!("/api/deployments/{}/send", vault.id);
+        let send = r#"{"function_name":"pause","params":[],"from":"0x0000000000000000000000000000000000000001"}"#;
+        let wallet = r#"{"name":"ops","private_key":"0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"}"#;
+
+        // Every role reads
+        for token in ["viewer", "operator", "admin"] {
+            assert_eq!(status("GET", "/api/networks", token, "").await, 200);
+            assert_eq!(status("GET", "/api/wallets", token, "").await, 200);
+        }
+
+        // Writes without a declaration of their own need an operator
+        assert_eq!(status("POST", &send_uri, "viewer", send).await, 403);
+
+        // Operators can't touch protected networks or manage the registry
+        assert_eq!(status("POST", &send_uri, "operator", send).await, 403);
+        assert_eq!(
+            status("POST", "/api/wallets", "operator", wallet).await,
+            403
+        );
+        assert_eq!(
+            status("DELETE", "/api/contracts/TestToken", "operator", "").await,
+            403
+        );
+        assert_eq!(status("GET", "/api/tokens", "operator", "").await, 200);
+        assert_eq!(
+            status("POST", "/api/tokens", "operator", r#"{"name":"ci"}"#).await,
+            403
+        );
+        assert_eq!(status("GET", "/api/sync", "viewer", "").await, 200);
+        let bundle = r#"{"networks":[],"deployments":[]}"#;
+        assert_eq!(status("POST", "/api/sync", "operator", bundle).await, 403);
+
+        // Admins request on protected networks and manage tokens
+        assert_eq!(status("POST", &send_uri, "admin", send).await, 202);
+        assert_eq!(status("POST", "/api/wallets", "admin", wallet).await, 200);
+        assert_eq!(status("POST", "/api/sync", "admin", bundle).await, 200);
+        let response = app
+            .clone()
+            .oneshot(request("POST", "/api/tokens", "admin", r#"{"name":"ci"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created["role"], "viewer");
+        assert!(created.get("token_hash").is_none());
+        let secret = created["secret"].as_str().unwrap().to_string();
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/api/tokens/ci/role",
+                "admin",
+                r#"{"role":"operator"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/api/networks", &secret, ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let tokens = ApiTokenRepository::list(db).await.unwrap();
+        let ci = tokens.iter().find(|t| t.name == "ci").unwrap();
+        assert_eq!(ci.role, ApiRole::Operator);
+
+        assert_eq!(status("DELETE", "/api/tokens/ci", "admin", "").await, 204);
+        assert_eq!(status("DELETE", "/api/tokens/ci", "admin", "").await, 404);
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        use crate::server::limits::Limits;
+
+        let state = setup_test_state().await.with_limits(Limits {
+            requests_per_minute: 2,
+            max_body_bytes: 64,
+            rpc_concurrency: 1,
+        });
+        let app = super::create_router(state);
+        let get = || {
+            Request::builder()
+                .uri("/api/networks")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(get()).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK);
+        }
+        let response = app.clone().oneshot(get()).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "30");
+
+        let state = setup_test_state().await.with_limits(Limits {
+            max_body_bytes: 64,
+            ..Limits::default()
+        });
+        let response = super::create_router(state)
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/wallets")
+                    .header("content-type", "application/json")
+                    .body(Body::from(format!(r#"{{"name":"{}"}}"#, "a".repeat(100))))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_sign_typed_data() {
+        let app = setup_test_app().await;
+        let request = |method: &str, uri: &str, body: String| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let wallet = serde_json::json!({
+            "name": "voter",
+            "private_key": "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        });
+        let response = app
+            .clone()
+            .oneshot(request("POST", "/api/wallets", wallet.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let vote = serde_json::json!({
+            "types": {
+                "EIP712Domain": [{ "name": "name", "type": "string" }],
+                "Ballot": [
+                    { "name": "proposalId", "type": "uint256" },
+                    { "name": "support", "type": "uint8" }
+                ]
+            },
+            "primaryType": "Ballot",
+            "domain": { "name": "Governor" },
+            "message": { "proposalId": "42", "support": 1 }
+        });
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/wallets/voter/sign-typed-data",
+                vote.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let signed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(signed["primary_type"], "Ballot");
+        assert_eq!(
+            signed["signer"],
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(signed["signature"].as_str().unwrap().len(), 132);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/wallets/voter/sign-typed-data",
+                r#"{"message":{}}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/wallets/nobody/sign-typed-data",
+                vote.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(request(
+                "GET",
+                "/api/wallets/voter/signatures",
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let signatures: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0]["id"], signed["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_base_path() {
+        let app = super::create_router(setup_test_state().await.with_base_path("/smolder"));
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get("/smolder/api/networks"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let response = app.clone().oneshot(get("/api/networks")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        let response = app.oneshot(get("/smolder")).await.unwrap();
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::PERMANENT_REDIRECT
+        );
+        assert_eq!(response.headers()["location"], "/smolder/");
+    }
+
+    #[tokio::test]
+    async fn test_version_and_unknown_api_routes() {
+        let app = setup_test_app().await;
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app.clone().oneshot(get("/api/version")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let version: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(version["version"], env!("CARGO_PKG_VERSION"));
+
+        // Unknown API routes don't fall through to the dashboard
+        let response = app.oneshot(get("/api/nope")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_cors_and_csrf() {
+        let app = setup_test_app().await;
+        let post = |origin: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/webhooks")
+                .header("origin", origin)
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"url":"https://hooks.example.com/in"}"#))
+                .unwrap()
+        };
+
+        // Pages from other sites can't write, even without CORS
+        let response = app
+            .clone()
+            .oneshot(post("https://evil.example"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(post("http://localhost:5173"))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://localhost:5173"
+        );
+
+        // Preflights from other sites get no CORS headers
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri("/api/networks")
+                .header("origin", origin)
+                .header("access-control-request-method", "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(preflight("https://evil.example"))
+            .await
+            .unwrap();
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
+
+        let policy =
+            crate::server::cors::CorsPolicy::new(&["https://app.example.com".to_string()], true)
+                .unwrap();
+        let app = super::create_router(setup_test_state().await.with_cors(policy));
+        let response = app
+            .clone()
+            .oneshot(preflight("https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+        assert_eq!(
+            response.headers()["access-control-allow-credentials"],
+            "true"
+        );
+        let response = app.oneshot(post("http://localhost:5173")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_sync() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/sync")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut bundle: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(bundle["networks"][0]["name"], "testnet");
+        assert_eq!(bundle["deployments"][0]["contract"], "TestToken");
+        assert_eq!(
+            bundle["deployments"][0]["tx_hash"],
+            TxHash(B256::repeat_byte(0x11)).to_string()
+        );
+
+        // The same deployment is skipped, one at its address from another
+        // transaction conflicts, and new networks are registered unless
+        // their RPC URL was redacted
+        let tracked = bundle["deployments"][0].clone();
+        let mut conflicting = tracked.clone();
+        conflicting["tx_hash"] = TxHash(B256::repeat_byte(0x22)).to_string().into();
+        let mut new = tracked.clone();
+        new["network"] = "othernet".into();
+        let mut redacted = tracked.clone();
+        redacted["network"] = "keyednet".into();
+        let networks = bundle["networks"].as_array_mut().unwrap();
+        networks.push(serde_json::json!({
+            "name": "othernet",
+            "chain_id": 777,
+            "rpc_url": "https://rpc.other.xyz",
+            "explorer_url": null
+        }));
+        networks.push(serde_json::json!({
+            "name": "keyednet",
+            "chain_id": 1,
+            "rpc_url": "https://mainnet.infura.io/v3/***",
+            "explorer_url": null
+        }));
+        bundle["deployments"] = serde_json::json!([tracked, conflicting, new, redacted]);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/sync")
+                    .header("content-type", "application/json")
+                    .body(Body::from(bundle.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["networks_added"], serde_json::json!(["othernet"]));
+        assert_eq!(report["added"], 1);
+        assert_eq!(report["skipped"], 1);
+        assert_eq!(report["conflicts"][0]["network"], "testnet");
+        assert_eq!(report["conflicts"][1]["network"], "keyednet");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/sync?network=othernet")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let bundle: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(bundle["networks"].as_array().unwrap().len(), 1);
+        assert_eq!(bundle["deployments"][0]["network"], "othernet");
+    }
 }
@@ -1,32 +1,302 @@
 use axum::{
-    extract::{Path, State},
-    routing::get,
-    Json, Router,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    middleware,
+    response::Response,
+    routing::{get, post},
+    Extension, Json, Router,
 };
-use smolder_core::Error;
-use smolder_db::{Network, NetworkRepository};
+use serde::{Deserialize, Serialize};
+use smolder_core::{ApiRole, Error};
+use smolder_db::{AuditEntry, GasSample, Network, NetworkUpdate};
+use utoipa::{IntoParams, ToSchema};
 
+use super::{if_match, versioned};
+use crate::locks::Clearance;
+use crate::server::auth::{self, Caller};
 use crate::server::error::ApiError;
 use crate::server::AppState;
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/networks", get(list))
-        .route("/networks/{name}", get(get_by_name))
+        .route("/networks/{name}", get(get_by_name).patch(update))
+        .route("/networks/{name}/gas-history", get(gas_history))
+        .route("/networks/{name}/lock", post(lock))
+        .route("/networks/{name}/unlock", post(unlock))
+        .route("/audit", get(audit_log))
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
 }
 
-async fn list(State(state): State<AppState>) -> Result<Json<Vec<Network>>, ApiError> {
-    let networks = NetworkRepository::list(state.db()).await?;
-    Ok(Json(networks))
+/// A network, with the credentials of its RPC URL masked
+#[derive(Serialize, ToSchema)]
+struct NetworkResponse {
+    #[serde(flatten)]
+    network: Network,
+    /// Whether the RPC URL carries credentials, shown as `***` in `rpc_url`
+    has_credentials: bool,
 }
 
+impl From<Network> for NetworkResponse {
+    fn from(network: Network) -> Self {
+        Self {
+            has_credentials: network.rpc_url.has_credentials(),
+            network,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/networks",
+    tag = "networks",
+    responses((status = 200, description = "All networks", body = Vec<NetworkResponse>))
+)]
+async fn list(State(state): State<AppState>) -> Result<Json<Vec<NetworkResponse>>, ApiError> {
+    let networks = state.repos().networks().list().await?;
+    Ok(Json(
+        networks.into_iter().map(NetworkResponse::from).collect(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/networks/{name}",
+    tag = "networks",
+    params(("name" = String, Path, description = "Network name")),
+    responses(
+        (status = 200, description = "Network found, with its row version as ETag", body = NetworkResponse),
+        (status = 404, description = "Network not found", body = ApiError)
+    )
+)]
 async fn get_by_name(
     State(state): State<AppState>,
     Path(name): Path<String>,
-) -> Result<Json<Network>, ApiError> {
-    let network = NetworkRepository::get_by_name(state.db(), &name).await?;
+) -> Result<Response, ApiError> {
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(&name)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::NetworkNotFound(name)))?;
+    Ok(versioned(
+        network.row_version,
+        NetworkResponse::from(network),
+    ))
+}
+
+/// Change the RPC or explorer of a network
+///
+/// With `If-Match`, the change only applies if nobody else changed the
+/// network since it was read.
+#[utoipa::path(
+    patch,
+    path = "/api/networks/{name}",
+    tag = "networks",
+    params(
+        ("name" = String, Path, description = "Network name"),
+        ("If-Match" = Option<String>, Header, description = "ETag the network was read with")
+    ),
+    request_body = NetworkUpdate,
+    responses(
+        (status = 200, description = "Network updated, with its new ETag", body = NetworkResponse),
+        (status = 404, description = "Network not found", body = ApiError),
+        (status = 412, description = "Network changed since it was read", body = ApiError)
+    )
+)]
+async fn update(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(update): Json<NetworkUpdate>,
+) -> Result<Response, ApiError> {
+    let network = state
+        .repos()
+        .networks()
+        .update(&name, &update, if_match(&headers)?)
+        .await?;
+    Ok(versioned(
+        network.row_version,
+        NetworkResponse::from(network),
+    ))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct GasHistoryQuery {
+    /// Only include samples taken at or after this date (YYYY-MM-DD[ HH:MM:SS])
+    since: Option<String>,
+    /// Number of most recent samples to return
+    #[serde(default = "default_gas_history_limit")]
+    limit: u32,
+}
 
-    network
-        .map(Json)
-        .ok_or_else(|| ApiError::from(Error::NetworkNotFound(name)))
+fn default_gas_history_limit() -> u32 {
+    // A day of samples at the default interval
+    1440
+}
+
+/// Sampled fees of a network, with where the latest sample sits among them
+#[derive(Serialize, ToSchema)]
+struct GasHistory {
+    network: String,
+    /// Samples, oldest first
+    samples: Vec<GasSample>,
+    summary: Option<GasSummary>,
+}
+
+/// Gas price statistics over the returned samples, in wei
+#[derive(Serialize, ToSchema)]
+struct GasSummary {
+    latest: i64,
+    min: i64,
+    median: i64,
+    max: i64,
+    /// Share of samples cheaper than the latest one, from 0 to 100; low
+    /// values mean now is a good time to send
+    percentile: u8,
+}
+
+impl GasSummary {
+    fn of(samples: &[GasSample]) -> Option<Self> {
+        let latest = samples.last()?.gas_price;
+        let mut prices: Vec<i64> = samples.iter().map(|s| s.gas_price).collect();
+        prices.sort_unstable();
+        let cheaper = prices.partition_point(|&p| p < latest);
+        Some(Self {
+            latest,
+            min: prices[0],
+            median: prices[prices.len() / 2],
+            max: prices[prices.len() - 1],
+            percentile: (cheaper * 100 / prices.len()) as u8,
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/networks/{name}/gas-history",
+    tag = "networks",
+    params(("name" = String, Path, description = "Network name"), GasHistoryQuery),
+    responses(
+        (status = 200, description = "Gas prices sampled by the server", body = GasHistory),
+        (status = 404, description = "Network not found", body = ApiError)
+    )
+)]
+async fn gas_history(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<GasHistoryQuery>,
+) -> Result<Json<GasHistory>, ApiError> {
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(&name)
+        .await?
+        .ok_or_else(|| Error::NetworkNotFound(name))?;
+
+    let samples = state
+        .repos()
+        .gas_samples()
+        .list(network.id, query.since.as_deref(), query.limit)
+        .await?;
+
+    Ok(Json(GasHistory {
+        network: network.name,
+        summary: GasSummary::of(&samples),
+        samples,
+    }))
+}
+
+/// Lock a network, so deploys and sends on it need `unlock_confirm`
+#[utoipa::path(
+    post,
+    path = "/api/networks/{name}/lock",
+    tag = "networks",
+    params(("name" = String, Path, description = "Network name")),
+    responses(
+        (status = 200, description = "Network locked, or already was", body = NetworkResponse),
+        (status = 404, description = "Network not found", body = ApiError)
+    )
+)]
+async fn lock(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    caller: Option<Extension<Caller>>,
+) -> Result<Json<NetworkResponse>, ApiError> {
+    set_locked(&state, &name, caller, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/networks/{name}/unlock",
+    tag = "networks",
+    params(("name" = String, Path, description = "Network name")),
+    responses(
+        (status = 200, description = "Network unlocked, or already was", body = NetworkResponse),
+        (status = 404, description = "Network not found", body = ApiError)
+    )
+)]
+async fn unlock(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    caller: Option<Extension<Caller>>,
+) -> Result<Json<NetworkResponse>, ApiError> {
+    set_locked(&state, &name, caller, false).await
+}
+
+async fn set_locked(
+    state: &AppState,
+    name: &str,
+    caller: Option<Extension<Caller>>,
+    locked: bool,
+) -> Result<Json<NetworkResponse>, ApiError> {
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(name)
+        .await?
+        .ok_or_else(|| Error::NetworkNotFound(name.to_string()))?;
+    let actor = caller.map(|Extension(caller)| caller.name);
+    Clearance::api(actor, [])
+        .set_locked(state.repos(), &network, locked)
+        .await?;
+
+    let network = state
+        .repos()
+        .networks()
+        .get_by_name(name)
+        .await?
+        .ok_or_else(|| Error::NetworkNotFound(name.to_string()))?;
+    Ok(Json(network.into()))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AuditQuery {
+    /// Number of most recent entries to return
+    #[serde(default = "default_audit_limit")]
+    limit: i64,
+}
+
+fn default_audit_limit() -> i64 {
+    100
+}
+
+/// Network locks and unlocks, and the operations confirmed on locked networks
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    tag = "networks",
+    params(AuditQuery),
+    responses((status = 200, description = "Audit entries, newest first", body = Vec<AuditEntry>))
+)]
+async fn audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, ApiError> {
+    Ok(Json(state.repos().audit_log().list(query.limit).await?))
 }
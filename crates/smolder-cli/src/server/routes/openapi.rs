@@ -0,0 +1,125 @@
+//! OpenAPI document and Swagger UI for the HTTP API
+
+use axum::{response::Html, routing::get, Json, Router};
+use utoipa::OpenApi;
+
+use super::{
+    analytics, approvals, artifacts, contracts, decode, deploy, deployments, ens, health, history,
+    interact, jobs, networks, sandbox, sync, tokens, version, wallets, webhooks,
+};
+use crate::server::simulate;
+use crate::server::AppState;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Smolder API",
+        description = "Contract registry and interaction API"
+    ),
+    paths(
+        health::check,
+        version::version,
+        networks::list,
+        networks::get_by_name,
+        networks::update,
+        networks::gas_history,
+        networks::lock,
+        networks::unlock,
+        networks::audit_log,
+        contracts::list,
+        contracts::get_by_name,
+        contracts::get_abi,
+        contracts::list_deployments,
+        contracts::remove,
+        deployments::list,
+        deployments::get_by_contract_and_network,
+        deployments::list_versions,
+        deployments::get_abi,
+        deployments::get_roles,
+        deployments::update,
+        deployments::remove,
+        history::list,
+        history::export,
+        history::stream,
+        history::get_trace,
+        jobs::list,
+        analytics::gas,
+        analytics::costs,
+        analytics::stats,
+        wallets::list,
+        wallets::create,
+        wallets::get_by_name,
+        wallets::remove,
+        wallets::sign_typed_data,
+        wallets::list_signatures,
+        wallets::get_policy,
+        wallets::set_policy,
+        wallets::clear_policy,
+        webhooks::list,
+        webhooks::create,
+        webhooks::remove,
+        webhooks::list_deliveries,
+        ens::resolve,
+        ens::lookup,
+        interact::get_functions,
+        interact::execute_call,
+        interact::execute_send,
+        interact::get_history,
+        interact::get_storage,
+        artifacts::list,
+        artifacts::get_by_name,
+        deploy::deploy_contract,
+        deploy::deploy_multi,
+        deploy::list_groups,
+        deploy::get_group,
+        deploy::resume_group,
+        sandbox::list,
+        sandbox::create,
+        sandbox::remove,
+        approvals::list,
+        approvals::get_approval,
+        approvals::approve,
+        approvals::reject,
+        tokens::list,
+        tokens::create,
+        tokens::set_role,
+        tokens::remove,
+        sync::export,
+        sync::import,
+        decode::decode,
+    ),
+    components(schemas(simulate::SimulationResponse, simulate::SimulatedEvent))
+)]
+pub struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/openapi.json", get(spec))
+        .route("/docs", get(docs))
+}
+
+async fn spec() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI page, loaded from a CDN so the binary doesn't have to embed it
+async fn docs() -> Html<&'static str> {
+    Html(
+        r##"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <title>Smolder API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+    <script>
+      window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+    </script>
+  </body>
+</html>
+"##,
+    )
+}
@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    routing::{delete, get},
+    Json, Router,
+};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use smolder_core::{ApiRole, Error};
+use smolder_db::NewNetwork;
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+use crate::rpc;
+use crate::server::auth;
+use crate::server::error::ApiError;
+use crate::server::sandbox::{clone_deployments, Anvil, Sandbox};
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sandbox", get(list).post(create))
+        .route("/sandbox/{name}", delete(remove))
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sandbox",
+    tag = "sandbox",
+    responses((status = 200, description = "Sandboxes created since the server started", body = Vec<Sandbox>))
+)]
+async fn list(State(state): State<AppState>) -> Json<Vec<Sandbox>> {
+    Json(state.sandboxes())
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct SandboxRequest {
+    /// Network to fork
+    network: String,
+    /// Block to fork at; latest when omitted
+    block: Option<u64>,
+    /// Network name for the fork; `<network>-sandbox` when omitted
+    name: Option<String>,
+    /// Attach to an anvil fork that is already running instead of starting one
+    rpc_url: Option<String>,
+    /// Port to start anvil on; a free one when omitted
+    port: Option<u16>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SandboxResponse {
+    #[serde(flatten)]
+    sandbox: Sandbox,
+    /// Deployments copied from the source network
+    cloned: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sandbox",
+    tag = "sandbox",
+    request_body = SandboxRequest,
+    responses(
+        (status = 201, description = "Fork running and registered as a network", body = SandboxResponse),
+        (status = 400, description = "Invalid name or options", body = ApiError),
+        (status = 404, description = "Network not found", body = ApiError),
+        (status = 409, description = "Name taken by a network that is not a local fork", body = ApiError),
+        (status = 502, description = "Fork could not be started or reached", body = ApiError)
+    )
+)]
+async fn create(
+    State(state): State<AppState>,
+    Json(payload): Json<SandboxRequest>,
+) -> Result<(StatusCode, Json<SandboxResponse>), ApiError> {
+    let source = state
+        .repos()
+        .networks()
+        .get_by_name(&payload.network)
+        .await?
+        .ok_or_else(|| Error::NetworkNotFound(payload.network.clone()))?;
+
+    let name = payload
+        .name
+        .unwrap_or_else(|| format!("{}-sandbox", source.name));
+    if name == source.name {
+        return Err(ApiError::bad_request(
+            "a sandbox needs a different name from the network it forks",
+        ));
+    }
+    if payload.rpc_url.is_some() && payload.port.is_some() {
+        return Err(ApiError::bad_request(
+            "port only applies when the server starts anvil; omit it with rpc_url",
+        ));
+    }
+    if let Some(existing) = state.repos().networks().get_by_name(&name).await? {
+        if !is_local(existing.rpc_url.expose()) {
+            return Err(ApiError::conflict(format!(
+                "network '{}' exists and is not a local fork",
+                name
+            )));
+        }
+    }
+
+    // Free the name (and the port) of a sandbox being recreated
+    state.remove_sandbox(&name);
+
+    let (rpc_url, block, anvil) = async {
+        match payload.rpc_url {
+            Some(rpc_url) => {
+                let chain_id = state
+                    .rpc()
+                    .chain_id(&rpc_url)
+                    .await
+                    .map_err(|e| Error::Rpc(e.to_string()))?;
+                if chain_id as i64 != source.chain_id.0 {
+                    return Err(Error::invalid_param(
+                        "rpc_url",
+                        format!(
+                            "chain ID {} does not match {} ({})",
+                            chain_id, source.name, source.chain_id
+                        ),
+                    ));
+                }
+                let block = match payload.block {
+                    Some(block) => block,
+                    None => state
+                        .rpc()
+                        .block_number(&rpc_url)
+                        .await
+                        .map_err(|e| Error::Rpc(e.to_string()))?,
+                };
+                Ok((rpc_url, block, None))
+            }
+            None => {
+                let block = match payload.block {
+                    Some(block) => block,
+                    None => state
+                        .rpc()
+                        .block_number(source.rpc_url.expose())
+                        .await
+                        .map_err(|e| Error::Rpc(e.to_string()))?,
+                };
+                let anvil = Anvil::spawn(source.rpc_url.expose(), block, payload.port).await?;
+                Ok((anvil.rpc_url.clone(), block, Some(anvil)))
+            }
+        }
+    }
+    .instrument(rpc::span(source.chain_id))
+    .await?;
+
+    let network = state
+        .repos()
+        .networks()
+        .upsert(&NewNetwork {
+            name,
+            chain_id: source.chain_id,
+            rpc_url: rpc_url.clone(),
+            explorer_url: None,
+        })
+        .await?;
+    state
+        .repos()
+        .networks()
+        .set_impersonation(&network.name, true)
+        .await?;
+    let cloned = clone_deployments(state.repos(), &source, &network, block).await?;
+
+    let sandbox = Sandbox {
+        name: network.name,
+        source: source.name,
+        rpc_url,
+        block,
+        managed: anvil.is_some(),
+    };
+    state.add_sandbox(sandbox.clone(), anvil);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SandboxResponse { sandbox, cloned }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/sandbox/{name}",
+    tag = "sandbox",
+    params(("name" = String, Path, description = "Sandbox network name")),
+    responses(
+        (status = 204, description = "Sandbox forgotten and its anvil process stopped"),
+        (status = 404, description = "No sandbox with this name", body = ApiError)
+    )
+)]
+async fn remove(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .remove_sandbox(&name)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or_else(|| ApiError::not_found(format!("No sandbox named '{}'", name)))
+}
+
+/// Whether an RPC URL points at this machine, i.e. could be a fork we own
+fn is_local(rpc_url: &str) -> bool {
+    Url::parse(rpc_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .is_some_and(|host| {
+            matches!(
+                host.as_str(),
+                "127.0.0.1" | "localhost" | "[::1]" | "0.0.0.0"
+            )
+        })
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{DefaultBodyLimit, Query, State},
+    middleware,
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use smolder_core::ApiRole;
+use utoipa::IntoParams;
+
+use crate::registry_sync::{self, SyncBundle, SyncReport};
+use crate::server::auth;
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+/// Largest bundle accepted by a push, which carries every ABI
+const MAX_BUNDLE_BYTES: usize = 64 * 1024 * 1024;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sync", get(export).post(import))
+        .route_layer(DefaultBodyLimit::max(MAX_BUNDLE_BYTES))
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportQuery {
+    /// Comma-separated networks to include; all when omitted
+    network: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sync",
+    tag = "sync",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Networks and non-archived deployments, for `smolder pull`", body = SyncBundle),
+        (status = 404, description = "Network not found", body = ApiError)
+    )
+)]
+async fn export(
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Json<SyncBundle>, ApiError> {
+    let networks: Vec<String> = query
+        .network
+        .iter()
+        .flat_map(|n| n.split(','))
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(String::from)
+        .collect();
+    let bundle = registry_sync::bundle(state.repos(), &networks).await?;
+    Ok(Json(bundle))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sync",
+    tag = "sync",
+    request_body = SyncBundle,
+    responses(
+        (status = 200, description = "Bundle merged; deployments recorded from another transaction are reported as conflicts", body = SyncReport)
+    )
+)]
+async fn import(
+    State(state): State<AppState>,
+    Json(bundle): Json<SyncBundle>,
+) -> Result<Json<SyncReport>, ApiError> {
+    let report = registry_sync::merge(state.repos(), &bundle).await?;
+    Ok(Json(report))
+}
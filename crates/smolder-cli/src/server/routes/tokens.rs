@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    routing::{delete, get, put},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use smolder_core::ApiRole;
+use smolder_db::ApiToken;
+use utoipa::ToSchema;
+
+use crate::server::auth::{self, generate_token, hash_token};
+use crate::server::error::ApiError;
+use crate::server::idempotency::HoldsSecret;
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/tokens", get(list).post(create))
+        .route("/tokens/{name}", delete(remove))
+        .route("/tokens/{name}/role", put(set_role))
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tokens",
+    tag = "tokens",
+    responses((status = 200, description = "All tokens (hashes omitted)", body = Vec<ApiToken>))
+)]
+async fn list(State(state): State<AppState>) -> Result<Json<Vec<ApiToken>>, ApiError> {
+    let tokens = state.repos().api_tokens().list().await?;
+    Ok(Json(tokens))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct CreateTokenRequest {
+    name: String,
+    #[serde(default)]
+    role: Option<ApiRole>,
+}
+
+/// A newly created token, with the secret only shown this once
+#[derive(Serialize, ToSchema)]
+struct CreateTokenResponse {
+    #[serde(flatten)]
+    token: ApiToken,
+    secret: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tokens",
+    tag = "tokens",
+    request_body = CreateTokenRequest,
+    responses(
+        (status = 201, description = "Token created; defaults to the viewer role", body = CreateTokenResponse),
+        (status = 409, description = "Name already in use", body = ApiError)
+    )
+)]
+async fn create(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateTokenRequest>,
+) -> Result<
+    (
+        StatusCode,
+        Extension<HoldsSecret>,
+        Json<CreateTokenResponse>,
+    ),
+    ApiError,
+> {
+    let existing = state.repos().api_tokens().list().await?;
+    if existing.iter().any(|t| t.name == payload.name) {
+        return Err(ApiError::conflict(format!(
+            "Token '{}' already exists",
+            payload.name
+        )));
+    }
+
+    let secret = generate_token();
+    let token = state
+        .repos()
+        .api_tokens()
+        .create(
+            &payload.name,
+            &hash_token(&secret),
+            payload.role.unwrap_or(ApiRole::Viewer),
+        )
+        .await?;
+
+    // The secret is shown once; only its hash is kept
+    Ok((
+        StatusCode::CREATED,
+        Extension(HoldsSecret),
+        Json(CreateTokenResponse { token, secret }),
+    ))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct SetRoleRequest {
+    role: ApiRole,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/tokens/{name}/role",
+    tag = "tokens",
+    params(("name" = String, Path, description = "Token name")),
+    request_body = SetRoleRequest,
+    responses(
+        (status = 204, description = "Role changed"),
+        (status = 404, description = "Token not found", body = ApiError)
+    )
+)]
+async fn set_role(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<SetRoleRequest>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .repos()
+        .api_tokens()
+        .set_role(&name, payload.role)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/tokens/{name}",
+    tag = "tokens",
+    params(("name" = String, Path, description = "Token name")),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 404, description = "Token not found", body = ApiError)
+    )
+)]
+async fn remove(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.repos().api_tokens().delete(&name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
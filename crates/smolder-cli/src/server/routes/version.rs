@@ -0,0 +1,32 @@
+use axum::{routing::get, Json, Router};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/version", get(version))
+}
+
+/// Build of the running server, so the dashboard can tell when it was
+/// served by a different one
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VersionResponse {
+    /// Crate version
+    pub version: &'static str,
+    /// Commit the binary was built from, when built from a git checkout
+    pub git_sha: Option<&'static str>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/version",
+    tag = "health",
+    responses((status = 200, description = "Server version", body = VersionResponse))
+)]
+pub async fn version() -> Json<VersionResponse> {
+    Json(VersionResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: option_env!("SMOLDER_GIT_SHA"),
+    })
+}
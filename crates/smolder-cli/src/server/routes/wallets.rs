@@ -1,15 +1,22 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
-    routing::{delete, get, post},
-    Json, Router,
+    http::{HeaderMap, StatusCode},
+    middleware,
+    response::Response,
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
 };
 use serde::Deserialize;
-use smolder_core::{encrypt_private_key, Error};
-use smolder_db::{NewWallet, Wallet, WalletRepository};
+use smolder_core::{encrypt_private_key, ApiRole, Error, EvmAddress, Secret};
+use smolder_db::{NewWallet, SignatureRecord, Wallet, WalletPolicy};
+use utoipa::ToSchema;
 
+use super::{if_match, versioned};
+use crate::policy;
+use crate::server::auth::{self, Caller};
 use crate::server::error::ApiError;
 use crate::server::AppState;
+use crate::typed_data;
 
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -17,39 +24,79 @@ pub fn router() -> Router<AppState> {
         .route("/wallets", post(create))
         .route("/wallets/{name}", get(get_by_name))
         .route("/wallets/{name}", delete(remove))
+        .route("/wallets/{name}/sign-typed-data", post(sign_typed_data))
+        .route("/wallets/{name}/signatures", get(list_signatures))
+        .route("/wallets/{name}/policy", get(get_policy))
+        .route("/wallets/{name}/policy", put(set_policy))
+        .route("/wallets/{name}/policy", delete(clear_policy))
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/wallets",
+    tag = "wallets",
+    responses((status = 200, description = "All wallets", body = Vec<Wallet>))
+)]
 async fn list(State(state): State<AppState>) -> Result<Json<Vec<Wallet>>, ApiError> {
-    let wallets = WalletRepository::list(state.db()).await?;
+    let wallets = state.repos().wallets().list().await?;
     Ok(Json(wallets))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/wallets/{name}",
+    tag = "wallets",
+    params(("name" = String, Path, description = "Wallet name")),
+    responses(
+        (status = 200, description = "Wallet found, with its row version as ETag", body = Wallet),
+        (status = 404, description = "Wallet not found", body = ApiError)
+    )
+)]
 async fn get_by_name(
     State(state): State<AppState>,
     Path(name): Path<String>,
-) -> Result<Json<Wallet>, ApiError> {
-    let wallet = WalletRepository::get_by_name(state.db(), &name).await?;
-
-    wallet
-        .map(Json)
-        .ok_or_else(|| ApiError::from(Error::WalletNotFound(name)))
+) -> Result<Response, ApiError> {
+    let wallet = state
+        .repos()
+        .wallets()
+        .get_by_name(&name)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::WalletNotFound(name)))?;
+    Ok(versioned(wallet.row_version, wallet))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 struct CreateWalletRequest {
     name: String,
-    private_key: String,
+    #[schema(value_type = String)]
+    private_key: Secret<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/wallets",
+    tag = "wallets",
+    request_body = CreateWalletRequest,
+    responses(
+        (status = 200, description = "Wallet created", body = Wallet),
+        (status = 400, description = "Invalid private key", body = ApiError),
+        (status = 409, description = "Name or address already in use", body = ApiError)
+    )
+)]
 async fn create(
     State(state): State<AppState>,
     Json(payload): Json<CreateWalletRequest>,
 ) -> Result<Json<Wallet>, ApiError> {
     // Normalize private key
-    let private_key = if payload.private_key.starts_with("0x") {
-        payload.private_key.clone()
+    let private_key = payload.private_key.expose();
+    let private_key = if private_key.starts_with("0x") {
+        private_key.clone()
     } else {
-        format!("0x{}", payload.private_key)
+        format!("0x{}", private_key)
     };
 
     // Parse and validate private key, get address
@@ -57,10 +104,13 @@ async fn create(
         .parse()
         .map_err(|e| ApiError::from(Error::invalid_param("private_key", format!("{}", e))))?;
 
-    let address = format!("{:?}", signer.address());
+    let address = EvmAddress::from(signer.address());
 
     // Check if wallet name already exists
-    if WalletRepository::get_by_name(state.db(), &payload.name)
+    if state
+        .repos()
+        .wallets()
+        .get_by_name(&payload.name)
         .await?
         .is_some()
     {
@@ -71,7 +121,10 @@ async fn create(
     }
 
     // Check if address already exists
-    if WalletRepository::get_by_address(state.db(), &address)
+    if state
+        .repos()
+        .wallets()
+        .get_by_address(&address.to_string())
         .await?
         .is_some()
     {
@@ -91,20 +144,166 @@ async fn create(
         encrypted_key,
     };
 
-    let wallet = WalletRepository::create(state.db(), &new_wallet).await?;
+    let wallet = state.repos().wallets().create(&new_wallet).await?;
     Ok(Json(wallet))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/wallets/{name}",
+    tag = "wallets",
+    params(
+        ("name" = String, Path, description = "Wallet name"),
+        ("If-Match" = Option<String>, Header, description = "ETag the wallet was read with")
+    ),
+    responses(
+        (status = 204, description = "Wallet deleted"),
+        (status = 404, description = "Wallet not found", body = ApiError),
+        (status = 412, description = "Wallet changed since it was read", body = ApiError)
+    )
+)]
 async fn remove(
     State(state): State<AppState>,
     Path(name): Path<String>,
+    headers: HeaderMap,
 ) -> Result<StatusCode, ApiError> {
-    // Check if wallet exists
-    WalletRepository::get_by_name(state.db(), &name)
+    state
+        .repos()
+        .wallets()
+        .delete_if(&name, if_match(&headers)?)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/wallets/{name}/sign-typed-data",
+    tag = "wallets",
+    params(("name" = String, Path, description = "Wallet name")),
+    request_body(
+        content = Object,
+        description = "EIP-712 payload as for `eth_signTypedData_v4`: `types`, `primaryType`, `domain` and `message`"
+    ),
+    responses(
+        (status = 200, description = "Payload signed and recorded", body = SignatureRecord),
+        (status = 400, description = "Invalid EIP-712 payload", body = ApiError),
+        (status = 404, description = "Wallet not found", body = ApiError)
+    )
+)]
+async fn sign_typed_data(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    caller: Option<Extension<Caller>>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<SignatureRecord>, ApiError> {
+    let typed = typed_data::parse(&payload)?;
+    let wallet = state
+        .repos()
+        .wallets()
+        .get_with_key(&name)
         .await?
-        .ok_or_else(|| ApiError::from(Error::WalletNotFound(name.clone())))?;
+        .ok_or_else(|| ApiError::from(Error::WalletNotFound(name)))?;
 
-    // Delete from database
-    WalletRepository::delete(state.db(), &name).await?;
+    let signed_by = caller.map(|Extension(caller)| caller.token_id);
+    let record = typed_data::sign(state.repos(), &wallet, &typed, signed_by).await?;
+    Ok(Json(record))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/wallets/{name}/signatures",
+    tag = "wallets",
+    params(("name" = String, Path, description = "Wallet name")),
+    responses(
+        (status = 200, description = "Typed data the wallet signed, newest first", body = Vec<SignatureRecord>),
+        (status = 404, description = "Wallet not found", body = ApiError)
+    )
+)]
+async fn list_signatures(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<SignatureRecord>>, ApiError> {
+    if state.repos().wallets().get_by_name(&name).await?.is_none() {
+        return Err(ApiError::from(Error::WalletNotFound(name)));
+    }
+    let signatures = state.repos().signatures().list(Some(&name)).await?;
+    Ok(Json(signatures))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/wallets/{name}/policy",
+    tag = "wallets",
+    params(("name" = String, Path, description = "Wallet name")),
+    responses(
+        (status = 200, description = "Policy of the wallet, with no rules set when it has none", body = WalletPolicy),
+        (status = 404, description = "Wallet not found", body = ApiError)
+    )
+)]
+async fn get_policy(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<WalletPolicy>, ApiError> {
+    let wallet = get_wallet(&state, name).await?;
+    let policy = state.repos().wallet_policies().get(wallet.id).await?;
+    Ok(Json(policy.unwrap_or_default()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/wallets/{name}/policy",
+    tag = "wallets",
+    params(("name" = String, Path, description = "Wallet name")),
+    request_body(
+        content = WalletPolicy,
+        description = "The whole policy; limits are in wei or with a unit, e.g. `0.5 ether`"
+    ),
+    responses(
+        (status = 200, description = "Policy replaced, with limits in wei", body = WalletPolicy),
+        (status = 400, description = "Invalid limit", body = ApiError),
+        (status = 404, description = "Wallet or network not found", body = ApiError)
+    )
+)]
+async fn set_policy(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<WalletPolicy>,
+) -> Result<Json<WalletPolicy>, ApiError> {
+    let wallet = get_wallet(&state, name).await?;
+    let policy = policy::normalize(state.repos(), payload).await?;
+    let policies = state.repos().wallet_policies();
+    if policy == WalletPolicy::default() {
+        policies.clear(wallet.id).await?;
+    } else {
+        policies.set(wallet.id, &policy).await?;
+    }
+    Ok(Json(policy))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/wallets/{name}/policy",
+    tag = "wallets",
+    params(("name" = String, Path, description = "Wallet name")),
+    responses(
+        (status = 204, description = "Policy removed, or the wallet had none"),
+        (status = 404, description = "Wallet not found", body = ApiError)
+    )
+)]
+async fn clear_policy(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let wallet = get_wallet(&state, name).await?;
+    state.repos().wallet_policies().clear(wallet.id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+async fn get_wallet(state: &AppState, name: String) -> Result<Wallet, ApiError> {
+    state
+        .repos()
+        .wallets()
+        .get_by_name(&name)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::WalletNotFound(name)))
+}
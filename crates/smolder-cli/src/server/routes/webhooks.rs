@@ -0,0 +1,168 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use smolder_core::{ApiRole, Error};
+use smolder_db::{NewWebhook, TransactionStatus, Webhook, WebhookDelivery, WebhookId};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::server::auth;
+use crate::server::error::ApiError;
+use crate::server::webhooks::{generate_secret, validate_url};
+use crate::server::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/webhooks", get(list).post(create))
+        .route("/webhooks/{id}", delete(remove))
+        .route("/webhooks/{id}/deliveries", get(list_deliveries))
+        .route_layer(middleware::from_fn_with_state(
+            ApiRole::Admin,
+            auth::require,
+        ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    responses((status = 200, description = "All webhooks (secrets omitted)", body = Vec<Webhook>))
+)]
+async fn list(State(state): State<AppState>) -> Result<Json<Vec<Webhook>>, ApiError> {
+    let webhooks = state.repos().webhooks().list().await?;
+    Ok(Json(webhooks))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct CreateWebhookRequest {
+    url: String,
+    /// Signing secret; generated when omitted
+    secret: Option<String>,
+    network: Option<String>,
+    contract: Option<String>,
+    /// Only notify for this event
+    event_name: Option<String>,
+    /// Only notify for calls that end with this status
+    tx_status: Option<TransactionStatus>,
+}
+
+/// A newly registered webhook, with the secret its payloads are signed with
+#[derive(Serialize, ToSchema)]
+struct CreateWebhookResponse {
+    #[serde(flatten)]
+    webhook: Webhook,
+    secret: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered", body = CreateWebhookResponse),
+        (status = 400, description = "Invalid URL or filters", body = ApiError)
+    )
+)]
+async fn create(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<CreateWebhookResponse>), ApiError> {
+    validate_url(&payload.url)?;
+    if payload.event_name.is_some() && payload.tx_status.is_some() {
+        return Err(ApiError::bad_request(
+            "event_name and tx_status filter different triggers; set at most one",
+        ));
+    }
+
+    let secret = payload.secret.unwrap_or_else(generate_secret);
+    let webhook = state
+        .repos()
+        .webhooks()
+        .create(&NewWebhook {
+            url: payload.url,
+            secret: secret.clone(),
+            network: payload.network,
+            contract: payload.contract,
+            event_name: payload.event_name,
+            tx_status: payload.tx_status,
+        })
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateWebhookResponse { webhook, secret }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = i64, Path, description = "Webhook ID")),
+    responses(
+        (status = 204, description = "Webhook and its delivery log deleted"),
+        (status = 404, description = "Webhook not found", body = ApiError)
+    )
+)]
+async fn remove(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let id = WebhookId(id);
+    state
+        .repos()
+        .webhooks()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::WebhookNotFound(id)))?;
+
+    state.repos().webhooks().delete(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DeliveriesQuery {
+    #[serde(default = "default_deliveries_limit")]
+    limit: u32,
+}
+
+fn default_deliveries_limit() -> u32 {
+    50
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = i64, Path, description = "Webhook ID"), DeliveriesQuery),
+    responses(
+        (status = 200, description = "Delivery log, newest first", body = Vec<WebhookDelivery>),
+        (status = 404, description = "Webhook not found", body = ApiError)
+    )
+)]
+async fn list_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let id = WebhookId(id);
+    state
+        .repos()
+        .webhooks()
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| ApiError::from(Error::WebhookNotFound(id)))?;
+
+    let deliveries = state
+        .repos()
+        .webhooks()
+        .list_deliveries(id, query.limit)
+        .await?;
+    Ok(Json(deliveries))
+}
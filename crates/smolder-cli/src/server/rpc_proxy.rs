@@ -0,0 +1,200 @@
+//! Read-only JSON-RPC proxy to the configured networks
+//!
+//! `/rpc/{network}` forwards JSON-RPC reads to the network's endpoint, so a
+//! frontend in development can point at smolder instead of shipping the
+//! provider URL and its API key. Writes are refused: transactions go through
+//! the API, where wallets, policies and approvals apply.
+//!
+//! Answers that can't change are cached: the chain ID, reads pinned to a
+//! block hash, and reads pinned to a block number once that block is
+//! finalized, so a reorg can't change them. `null` answers, such as a block
+//! not mined yet, are never cached.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+/// Methods the proxy forwards
+pub const READ_METHODS: &[&str] = &[
+    "eth_blockNumber",
+    "eth_call",
+    "eth_chainId",
+    "eth_estimateGas",
+    "eth_feeHistory",
+    "eth_gasPrice",
+    "eth_getBalance",
+    "eth_getBlockByHash",
+    "eth_getBlockByNumber",
+    "eth_getBlockReceipts",
+    "eth_getCode",
+    "eth_getLogs",
+    "eth_getProof",
+    "eth_getStorageAt",
+    "eth_getTransactionByHash",
+    "eth_getTransactionCount",
+    "eth_getTransactionReceipt",
+    "eth_maxPriorityFeePerGas",
+    "eth_syncing",
+    "net_version",
+    "web3_clientVersion",
+];
+
+/// Answers kept by [`RpcCache`]; the oldest are dropped beyond it
+const CACHE_CAPACITY: usize = 10_000;
+
+/// What the answer to a cacheable request depends on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pin {
+    /// Nothing that changes: the chain ID, or a block hash
+    Fixed,
+    /// A block number, whose block a reorg can replace until it is finalized
+    Block(u64),
+}
+
+/// What the answer to `method` with `params` is pinned to, or `None` when
+/// it follows the chain head
+pub fn cache_pin(method: &str, params: &Value) -> Option<Pin> {
+    let block_param = match method {
+        "eth_chainId" | "net_version" => return Some(Pin::Fixed),
+        "eth_getBlockByNumber" => 0,
+        "eth_call" | "eth_getBalance" | "eth_getCode" | "eth_getTransactionCount" => 1,
+        "eth_getStorageAt" => 2,
+        _ => return None,
+    };
+    params.get(block_param).and_then(block_pin)
+}
+
+/// A block number, or an EIP-1898 object holding a number or hash, rather
+/// than a tag like `latest` that moves
+fn block_pin(block: &Value) -> Option<Pin> {
+    match block {
+        Value::String(tag) => tag
+            .strip_prefix("0x")
+            .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+            .map(Pin::Block),
+        Value::Object(fields) if fields.contains_key("blockHash") => Some(Pin::Fixed),
+        Value::Object(fields) => fields.get("blockNumber").and_then(block_pin),
+        _ => None,
+    }
+}
+
+/// Results of cacheable requests, keyed by RPC URL so a network pointed at
+/// another endpoint starts afresh
+#[derive(Default)]
+pub struct RpcCache {
+    entries: Mutex<CacheEntries>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    results: HashMap<String, Value>,
+    /// Keys in insertion order, oldest first
+    order: VecDeque<String>,
+    /// Latest finalized block seen, by RPC URL
+    finalized: HashMap<String, u64>,
+}
+
+impl RpcCache {
+    pub fn get(&self, rpc_url: &str, method: &str, params: &Value) -> Option<Value> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .results
+            .get(&cache_key(rpc_url, method, params))
+            .cloned()
+    }
+
+    pub fn insert(&self, rpc_url: &str, method: &str, params: &Value, result: Value) {
+        let key = cache_key(rpc_url, method, params);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.results.insert(key.clone(), result).is_none() {
+            entries.order.push_back(key);
+        }
+        while entries.order.len() > CACHE_CAPACITY {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.results.remove(&oldest);
+            }
+        }
+    }
+
+    /// Latest block known to be finalized on `rpc_url`'s chain
+    pub fn finalized(&self, rpc_url: &str) -> Option<u64> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.finalized.get(rpc_url).copied()
+    }
+
+    /// Record that `block` is finalized; finality never goes back
+    pub fn set_finalized(&self, rpc_url: &str, block: u64) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let finalized = entries.finalized.entry(rpc_url.to_string()).or_default();
+        *finalized = (*finalized).max(block);
+    }
+}
+
+fn cache_key(rpc_url: &str, method: &str, params: &Value) -> String {
+    format!("{}\n{}\n{}", rpc_url, method, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cache_pin() {
+        let call = json!({ "to": "0x0000000000000000000000000000000000000001", "data": "0x" });
+        assert_eq!(cache_pin("eth_chainId", &json!([])), Some(Pin::Fixed));
+        assert_eq!(
+            cache_pin("eth_call", &json!([call, "0x10"])),
+            Some(Pin::Block(16))
+        );
+        assert_eq!(
+            cache_pin("eth_call", &json!([call, { "blockHash": "0xabc" }])),
+            Some(Pin::Fixed)
+        );
+        assert_eq!(
+            cache_pin("eth_call", &json!([call, { "blockNumber": "0x10" }])),
+            Some(Pin::Block(16))
+        );
+        assert_eq!(
+            cache_pin("eth_getStorageAt", &json!(["0x01", "0x0", "0x10"])),
+            Some(Pin::Block(16))
+        );
+        assert_eq!(cache_pin("eth_call", &json!([call, "latest"])), None);
+        assert_eq!(cache_pin("eth_call", &json!([call, "0xzz"])), None);
+        assert_eq!(cache_pin("eth_call", &json!([call])), None);
+        assert_eq!(
+            cache_pin("eth_call", &json!([call, { "blockNumber": "safe" }])),
+            None
+        );
+        assert_eq!(cache_pin("eth_blockNumber", &json!([])), None);
+    }
+
+    const MAINNET: &str = "https://mainnet.example/v2/key";
+    const SEPOLIA: &str = "https://sepolia.example/v2/key";
+
+    #[test]
+    fn test_cache() {
+        let cache = RpcCache::default();
+        let params = json!(["0x01", "0x10"]);
+        cache.insert(MAINNET, "eth_getBalance", &params, json!("0x5"));
+        assert_eq!(
+            cache.get(MAINNET, "eth_getBalance", &params),
+            Some(json!("0x5"))
+        );
+        assert!(cache.get(SEPOLIA, "eth_getBalance", &params).is_none());
+        assert!(cache.get(MAINNET, "eth_getCode", &params).is_none());
+
+        for i in 0..CACHE_CAPACITY + 1 {
+            cache.insert(SEPOLIA, "eth_chainId", &json!([i]), json!("0x1"));
+        }
+        assert!(cache.get(MAINNET, "eth_getBalance", &params).is_none());
+        assert!(cache.get(SEPOLIA, "eth_chainId", &json!([0])).is_none());
+        assert!(cache.get(SEPOLIA, "eth_chainId", &json!([1])).is_some());
+
+        cache.set_finalized(MAINNET, 20);
+        cache.set_finalized(MAINNET, 10);
+        assert_eq!(cache.finalized(MAINNET), Some(20));
+        assert_eq!(cache.finalized(SEPOLIA), None);
+    }
+}
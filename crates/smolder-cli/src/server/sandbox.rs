@@ -0,0 +1,241 @@
+//! Anvil forks for exercising writes without spending real gas
+//!
+//! A sandbox is a registry network whose RPC is an anvil fork of another
+//! network. The source network's deployments are cloned into it, so the
+//! interact and deploy routes work against the fork unchanged.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Serialize;
+use smolder_core::Error;
+use smolder_db::{DeploymentFilter, Network, NewDeployment, Repositories};
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+use utoipa::ToSchema;
+
+use crate::rpc;
+
+/// How long anvil gets to fetch the fork and start answering requests
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A fork registered as a network
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Sandbox {
+    /// Registry network the fork is exposed as
+    pub name: String,
+    /// Network it forks
+    pub source: String,
+    pub rpc_url: String,
+    /// Block the fork was taken at
+    pub block: u64,
+    /// Whether the server started the anvil process (and stops it)
+    pub managed: bool,
+}
+
+/// An anvil process started for a sandbox, killed when dropped
+pub struct Anvil {
+    _child: Child,
+    pub rpc_url: String,
+}
+
+impl Anvil {
+    /// Start `anvil` forking `fork_url` at `block` on a local port, and wait
+    /// until it answers requests
+    pub async fn spawn(fork_url: &str, block: u64, port: Option<u16>) -> Result<Self, Error> {
+        let port = match port {
+            Some(port) => port,
+            None => free_port()?,
+        };
+
+        let mut child = Command::new("anvil")
+            .args(["--fork-url", fork_url])
+            .args(["--fork-block-number", &block.to_string()])
+            .args(["--host", "127.0.0.1", "--port", &port.to_string()])
+            .arg("--silent")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    Error::Io("anvil not found; install Foundry to create sandboxes".to_string())
+                }
+                _ => Error::Io(format!("could not start anvil: {}", e)),
+            })?;
+
+        let rpc_url = format!("http://127.0.0.1:{}", port);
+        let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            if let Some(status) = child.try_wait().map_err(|e| Error::Io(e.to_string()))? {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = child.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr).await;
+                }
+                return Err(Error::Rpc(format!(
+                    "anvil exited with {}: {}",
+                    status,
+                    stderr.trim()
+                )));
+            }
+            if rpc::get_chain_id(&rpc_url).await.is_ok() {
+                return Ok(Self {
+                    _child: child,
+                    rpc_url,
+                });
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(Error::Rpc(format!(
+                    "anvil did not start within {}s",
+                    STARTUP_TIMEOUT.as_secs()
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    }
+}
+
+/// Ask the OS for a port nothing is listening on
+fn free_port() -> Result<u16, Error> {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| Error::Io(format!("no free port for anvil: {}", e)))
+}
+
+/// Copy the current deployments of `source` that exist at `block` into
+/// `target`, returning how many were added
+///
+/// Deployments already in `target` are left alone, so attaching to an
+/// existing sandbox again only adds what is missing. Deployments without a
+/// recorded block are assumed to predate the fork.
+pub async fn clone_deployments(
+    repos: &dyn Repositories,
+    source: &Network,
+    target: &Network,
+    block: u64,
+) -> Result<usize, Error> {
+    let deployments = repos.deployments();
+    let mut cloned = 0;
+    for view in deployments
+        .list(DeploymentFilter::for_network(&source.name))
+        .await?
+    {
+        if view.block_number.is_some_and(|b| b as u64 > block)
+            || deployments
+                .exists_by_address(target.id, &view.address.to_string())
+                .await?
+        {
+            continue;
+        }
+        let Some(deployment) = deployments.get_by_id(view.id).await? else {
+            continue;
+        };
+
+        deployments
+            .create(&NewDeployment {
+                contract_id: deployment.contract_id,
+                network_id: target.id,
+                address: deployment.address,
+                deployer: deployment.deployer,
+                tx_hash: deployment.tx_hash,
+                block_number: deployment.block_number,
+                constructor_args: deployment.constructor_args,
+                initcode_size: deployment.initcode_size,
+                runtime_size: deployment.runtime_size,
+                gas_used: deployment.gas_used,
+                gas_price: None,
+            })
+            .await?;
+        cloned += 1;
+    }
+    Ok(cloned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_core::{BytecodeHash, TxHash, B256};
+    use smolder_db::{
+        ChainId, ContractRepository, Database, DeploymentRepository, NetworkRepository,
+        NewContract, NewNetwork,
+    };
+
+    async fn network(db: &Database, name: &str) -> Network {
+        NetworkRepository::upsert(
+            db,
+            &NewNetwork {
+                name: name.to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "http://127.0.0.1:8545".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_clone_deployments() {
+        let db = Database::connect_to(":memory:").await.unwrap();
+        db.init_schema().await.unwrap();
+        let source = network(&db, "mainnet").await;
+        let target = network(&db, "mainnet-sandbox").await;
+        for (name, block) in [("A", Some(100)), ("B", Some(200)), ("C", None)] {
+            let contract = ContractRepository::upsert(
+                &db,
+                &NewContract {
+                    name: name.to_string(),
+                    source_path: format!("src/{}.sol", name),
+                    abi: "[]".to_string(),
+                    bytecode_hash: BytecodeHash::of(name),
+                    storage_layout: None,
+                    compiler: None,
+                },
+            )
+            .await
+            .unwrap();
+            DeploymentRepository::create(
+                &db,
+                &NewDeployment {
+                    contract_id: contract.id,
+                    network_id: source.id,
+                    address: format!("0x{:0>40}", name).parse().unwrap(),
+                    deployer: "0xdeployer".to_string(),
+                    tx_hash: Some(TxHash(B256::repeat_byte(name.as_bytes()[0]))),
+                    block_number: block,
+                    constructor_args: None,
+                    initcode_size: None,
+                    runtime_size: None,
+                    gas_used: None,
+                    gas_price: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        // The deployment after the fork block is left out
+        assert_eq!(
+            clone_deployments(&db, &source, &target, 150).await.unwrap(),
+            2
+        );
+        // Cloning again only adds what is missing
+        assert_eq!(
+            clone_deployments(&db, &source, &target, 250).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            clone_deployments(&db, &source, &target, 250).await.unwrap(),
+            0
+        );
+
+        let cloned =
+            DeploymentRepository::list(&db, DeploymentFilter::for_network("mainnet-sandbox"))
+                .await
+                .unwrap();
+        assert_eq!(cloned.len(), 3);
+        assert!(cloned.iter().all(|d| d.chain_id == ChainId(1)));
+    }
+}
@@ -0,0 +1,68 @@
+//! Graceful shutdown on SIGINT/SIGTERM
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use super::AppState;
+
+/// Default time allowed for in-flight requests and background tasks to finish
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Resolve when the process receives Ctrl+C or, on Unix, SIGTERM
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "could not listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "could not listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Wait for background tasks until `deadline`, then stop sandboxes and close
+/// the database
+///
+/// Called once the listener has stopped and connections have drained (or
+/// were abandoned at the deadline).
+pub async fn finish(state: &AppState, deadline: Instant) {
+    let tasks = state.tasks();
+    tasks.close();
+
+    if !tasks.is_empty() {
+        tracing::info!(pending = tasks.len(), "waiting for background tasks");
+    }
+    if tokio::time::timeout_at(deadline, tasks.wait())
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            abandoned = tasks.len(),
+            "shutdown timeout reached before background tasks finished"
+        );
+    }
+
+    state.stop_sandboxes();
+    state.db().close().await;
+    tracing::info!("shutdown complete");
+}
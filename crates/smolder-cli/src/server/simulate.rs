@@ -0,0 +1,145 @@
+//! Dry runs of `/send` and `/deploy`
+//!
+//! With `?simulate=true` the transaction is executed against the latest block
+//! instead of being broadcast, and the response describes what it would do:
+//! the decoded result, the events it would emit and the gas it would use.
+//! Sends are simulated on Tenderly when `[tenderly]` is configured, which adds
+//! the state diff and a shareable link to the simulation.
+
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use smolder_core::{decode_event_log, Abi};
+use smolder_db::DeploymentFilter;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::rpc::{SimulatedLog, Simulation};
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SimulateQuery {
+    /// Execute without broadcasting and return a [`SimulationResponse`]
+    #[serde(default)]
+    pub simulate: bool,
+}
+
+/// What a transaction would do if it were sent now
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SimulationResponse {
+    pub success: bool,
+    /// Decoded return values of the called function
+    pub result: Option<serde_json::Value>,
+    /// Unknown when the transaction reverts on a node without `debug_traceCall`
+    pub gas_used: Option<u64>,
+    /// Revert reason or error, when the transaction would fail
+    pub error: Option<String>,
+    /// Address a deployment would create the contract at
+    pub contract_address: Option<String>,
+    /// Events that would be emitted, in order. Only known when the node
+    /// supports `debug_traceCall`
+    pub events: Vec<SimulatedEvent>,
+    /// Call tree from the node's call tracer, or Tenderly's
+    #[schema(value_type = Option<Object>)]
+    pub trace: Option<serde_json::Value>,
+    /// Storage and balance changes; only known from Tenderly
+    #[schema(value_type = Option<Object>)]
+    pub state_diff: Option<serde_json::Value>,
+    /// Shareable link to the Tenderly simulation
+    pub simulation_url: Option<String>,
+}
+
+/// An event a simulated transaction would emit
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SimulatedEvent {
+    pub address: String,
+    /// Registry contract at the address
+    pub contract: Option<String>,
+    pub name: Option<String>,
+    pub signature: Option<String>,
+    /// Decoded parameters, when the event is in the contract's ABI
+    #[schema(value_type = Option<Object>)]
+    pub params: Option<serde_json::Value>,
+    pub topics: Vec<String>,
+    pub data: String,
+}
+
+impl SimulationResponse {
+    /// Describe `simulation`, decoding its events with the ABIs of the
+    /// registry's deployments on `network` plus `known`, e.g. the contract a
+    /// deployment would create
+    pub async fn new(
+        state: &AppState,
+        network: &str,
+        simulation: Simulation,
+        result: Option<serde_json::Value>,
+        known: Option<(Address, String, Abi)>,
+    ) -> Result<Self, ApiError> {
+        let events = match simulation.logs.is_empty() {
+            true => Vec::new(),
+            false => decode_events(state, network, &simulation.logs, known).await?,
+        };
+
+        Ok(Self {
+            success: simulation.success,
+            result,
+            gas_used: simulation.gas_used,
+            error: simulation.error,
+            contract_address: simulation.contract_address.map(|a| a.to_string()),
+            events,
+            trace: simulation.trace,
+            state_diff: simulation.state_diff,
+            simulation_url: simulation.url,
+        })
+    }
+}
+
+async fn decode_events(
+    state: &AppState,
+    network: &str,
+    logs: &[SimulatedLog],
+    known: Option<(Address, String, Abi)>,
+) -> Result<Vec<SimulatedEvent>, ApiError> {
+    let mut contracts = registry_abis(state, network).await?;
+    if let Some((address, name, abi)) = known {
+        contracts.insert(address, (name, abi));
+    }
+
+    Ok(logs
+        .iter()
+        .map(|log| {
+            let contract = contracts.get(&log.address);
+            let event = contract.and_then(|(_, abi)| abi.event_by_selector(*log.topics.first()?));
+            SimulatedEvent {
+                address: log.address.to_string(),
+                contract: contract.map(|(name, _)| name.clone()),
+                name: event.map(|e| e.name.clone()),
+                signature: event.map(|e| e.signature()),
+                params: event.and_then(|e| decode_event_log(e, &log.topics, &log.data).ok()),
+                topics: log.topics.iter().map(|t| t.to_string()).collect(),
+                data: log.data.to_string(),
+            }
+        })
+        .collect())
+}
+
+/// Name and ABI of every deployment on `network`, archived ones included,
+/// by address
+pub async fn registry_abis(
+    state: &AppState,
+    network: &str,
+) -> Result<HashMap<Address, (String, Abi)>, ApiError> {
+    let filter = DeploymentFilter {
+        current_only: false,
+        ..DeploymentFilter::for_network(network)
+    };
+    let mut contracts = HashMap::new();
+    for deployment in state.repos().deployments().list(filter).await? {
+        if let Ok(abi) = Abi::parse(&deployment.abi) {
+            contracts.insert(*deployment.address, (deployment.contract_name, abi));
+        }
+    }
+    Ok(contracts)
+}
@@ -1,29 +1,258 @@
 //! Server application state
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::forge::{ArtifactLoader, FileSystemArtifactLoader};
-use smolder_db::Database;
+use crate::forge::ArtifactLoader;
+use crate::prices::PriceOracle;
+use crate::rpc::{HttpRpcClient, RpcClient};
+use crate::server::cors::CorsPolicy;
+use crate::server::history_stream;
+use crate::server::limits::{Limits, RateLimiter};
+use crate::server::rpc_proxy::RpcCache;
+use crate::server::sandbox::{Anvil, Sandbox};
+use crate::tenderly::Tenderly;
+use smolder_db::{CallHistory, Database, Repositories};
+use tokio::sync::{broadcast, Notify, Semaphore};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// Outcome of probing an RPC endpoint with `eth_chainId`
+#[derive(Debug, Clone)]
+pub struct RpcProbe {
+    /// Chain ID reported by the endpoint, or why it could not be fetched
+    pub result: Result<u64, String>,
+    pub latency_ms: u64,
+    pub checked_at: Instant,
+}
+
+/// How long requests on protected networks wait for approval by default
+pub const DEFAULT_APPROVAL_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Sandboxes by network name, with the anvil process when the server started it
+type Sandboxes = HashMap<String, (Sandbox, Option<Anvil>)>;
 
 /// Application state shared across handlers
 ///
-/// Uses the Database which implements all repository traits,
-/// providing type-safe data access through the repository pattern.
+/// Handlers reach data through [`AppState::repos`], artifacts through
+/// [`AppState::artifacts`] and node reads through [`AppState::rpc`], all
+/// trait objects that tests can swap for fakes. The concrete database is only
+/// for what no repository covers: pinging, vacuuming and closing it, and the
+/// registry operations shared with the CLI.
 #[derive(Clone)]
 pub struct AppState {
     db: Arc<Database>,
+    repos: Arc<dyn Repositories>,
     artifact_loader: Arc<dyn ArtifactLoader>,
+    rpc: Arc<dyn RpcClient>,
+    /// Recent RPC probes keyed by RPC URL, so health checks don't hit every
+    /// endpoint on each poll
+    rpc_probes: Arc<Mutex<HashMap<String, RpcProbe>>>,
+    /// Answers of `/rpc/{network}` that can't change
+    rpc_cache: Arc<RpcCache>,
+    /// Call history entries whose status changed, for `/api/history/stream`
+    history_events: broadcast::Sender<CallHistory>,
+    /// Wakes a job worker when a job is queued
+    job_queued: Arc<Notify>,
+    /// Background work that must finish before the server exits
+    tasks: TaskTracker,
+    /// Cancelled when the server starts shutting down
+    shutdown: CancellationToken,
+    /// Network to resolve ENS names on, from `[ens]` in smolder.toml
+    ens_network: Option<Arc<str>>,
+    /// Simulates sends when `[tenderly]` is configured
+    tenderly: Option<Arc<Tenderly>>,
+    /// Fetches USD prices when `[prices]` is configured
+    prices: Option<Arc<PriceOracle>>,
+    /// Forks registered through `/api/sandbox`
+    sandboxes: Arc<Mutex<Sandboxes>>,
+    /// How long requests on protected networks wait for approval
+    approval_ttl: Duration,
+    limits: Limits,
+    rate_limiter: Arc<RateLimiter>,
+    /// Slots for requests that call RPC nodes
+    rpc_permits: Arc<Semaphore>,
+    /// Sub-path a reverse proxy mounts the server under, empty for the root
+    base_path: Arc<str>,
+    /// Whether `X-Forwarded-For` names the client
+    trust_forwarded: bool,
+    /// Browser origins allowed to call the API
+    cors: Arc<CorsPolicy>,
 }
 
 impl AppState {
-    /// Create a new AppState with the given database
-    pub fn new(db: Database) -> Self {
+    /// Create a new AppState with the given database, reading artifacts
+    /// through the given loader
+    pub fn new(db: Database, artifact_loader: Arc<dyn ArtifactLoader>) -> Self {
+        let db = Arc::new(db);
         Self {
-            db: Arc::new(db),
-            artifact_loader: Arc::new(FileSystemArtifactLoader::new()),
+            repos: db.clone(),
+            db,
+            artifact_loader,
+            rpc: Arc::new(HttpRpcClient),
+            rpc_probes: Arc::default(),
+            rpc_cache: Arc::default(),
+            history_events: broadcast::channel(history_stream::CAPACITY).0,
+            job_queued: Arc::default(),
+            tasks: TaskTracker::new(),
+            shutdown: CancellationToken::new(),
+            ens_network: None,
+            tenderly: None,
+            prices: None,
+            sandboxes: Arc::default(),
+            approval_ttl: DEFAULT_APPROVAL_TTL,
+            limits: Limits::default(),
+            rate_limiter: Arc::new(RateLimiter::new(Limits::default().requests_per_minute)),
+            rpc_permits: Arc::new(Semaphore::new(Limits::default().rpc_concurrency)),
+            base_path: Arc::from(""),
+            trust_forwarded: false,
+            cors: Arc::default(),
         }
     }
 
+    /// Serve the API and dashboard under `base_path`, as normalized by
+    /// [`crate::server::proxy::parse_base_path`]
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.base_path = base_path.into();
+        self
+    }
+
+    /// Get the sub-path the server is mounted under, empty for the root
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    /// Take the client from `X-Forwarded-For`, for servers behind a proxy
+    pub fn with_trust_forwarded(mut self, trust_forwarded: bool) -> Self {
+        self.trust_forwarded = trust_forwarded;
+        self
+    }
+
+    /// Whether `X-Forwarded-For` names the client
+    pub fn trust_forwarded(&self) -> bool {
+        self.trust_forwarded
+    }
+
+    /// Let browser pages from the origins in `policy` call the API
+    pub fn with_cors(mut self, policy: CorsPolicy) -> Self {
+        self.cors = Arc::new(policy);
+        self
+    }
+
+    /// Get the browser origins allowed to call the API
+    pub fn cors(&self) -> &CorsPolicy {
+        &self.cors
+    }
+
+    /// Apply `limits` to API requests
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.rate_limiter = Arc::new(RateLimiter::new(limits.requests_per_minute));
+        self.rpc_permits = Arc::new(Semaphore::new(limits.rpc_concurrency));
+        self.limits = limits;
+        self
+    }
+
+    /// Get the limits applied to API requests
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Get the rate limiter shared by all requests
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// Get the slots for requests that call RPC nodes
+    pub fn rpc_permits(&self) -> Arc<Semaphore> {
+        self.rpc_permits.clone()
+    }
+
+    /// Let requests on protected networks wait `ttl` for approval
+    pub fn with_approval_ttl(mut self, ttl: Duration) -> Self {
+        self.approval_ttl = ttl;
+        self
+    }
+
+    /// Get how long requests on protected networks wait for approval
+    pub fn approval_ttl(&self) -> Duration {
+        self.approval_ttl
+    }
+
+    /// Resolve ENS names on `network` instead of picking a network
+    pub fn with_ens_network(mut self, network: Option<String>) -> Self {
+        self.ens_network = network.map(Into::into);
+        self
+    }
+
+    /// Get the configured ENS network, if any
+    pub fn ens_network(&self) -> Option<&str> {
+        self.ens_network.as_deref()
+    }
+
+    /// Simulate sends on Tenderly and keep links to the simulations
+    pub fn with_tenderly(mut self, tenderly: Option<Tenderly>) -> Self {
+        self.tenderly = tenderly.map(Arc::new);
+        self
+    }
+
+    /// Get the Tenderly client, if configured
+    pub fn tenderly(&self) -> Option<&Tenderly> {
+        self.tenderly.as_deref()
+    }
+
+    /// Value fees in USD with prices fetched from `[prices]`
+    pub fn with_prices(mut self, prices: Option<PriceOracle>) -> Self {
+        self.prices = prices.map(Arc::new);
+        self
+    }
+
+    /// Get the price oracle, if configured
+    pub fn prices(&self) -> Option<&PriceOracle> {
+        self.prices.as_deref()
+    }
+
+    /// Read and write data through `repos` instead of the database
+    #[cfg(test)]
+    pub fn with_repositories(mut self, repos: Arc<dyn Repositories>) -> Self {
+        self.repos = repos;
+        self
+    }
+
+    /// Get the repositories handlers read and write data through
+    pub fn repos(&self) -> &dyn Repositories {
+        self.repos.as_ref()
+    }
+
+    /// Make node reads through `rpc`
+    #[cfg(test)]
+    pub fn with_rpc(mut self, rpc: Arc<dyn RpcClient>) -> Self {
+        self.rpc = rpc;
+        self
+    }
+
+    /// Get the client node reads go through
+    pub fn rpc(&self) -> &dyn RpcClient {
+        self.rpc.as_ref()
+    }
+
+    /// Get the answers of the JSON-RPC proxy that can be reused
+    pub fn rpc_cache(&self) -> &RpcCache {
+        &self.rpc_cache
+    }
+
+    /// Get the channel call history entries are published on when their
+    /// status changes
+    pub fn history_events(&self) -> &broadcast::Sender<CallHistory> {
+        &self.history_events
+    }
+
+    /// Get the signal that a job was queued, which idle job workers wait on
+    pub fn job_queued(&self) -> &Notify {
+        &self.job_queued
+    }
+
     /// Get a reference to the database
     pub fn db(&self) -> &Database {
         &self.db
@@ -33,4 +262,65 @@ impl AppState {
     pub fn artifacts(&self) -> &dyn ArtifactLoader {
         self.artifact_loader.as_ref()
     }
+
+    /// Run a task in the background, awaited during graceful shutdown
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.spawn(task);
+    }
+
+    /// Get the tracker of background tasks
+    pub fn tasks(&self) -> &TaskTracker {
+        &self.tasks
+    }
+
+    /// Get the token cancelled on shutdown, which long-running tasks should
+    /// select on to stop cleanly
+    pub fn shutdown_token(&self) -> &CancellationToken {
+        &self.shutdown
+    }
+
+    /// Get the probe of an RPC URL if it was taken within `ttl`
+    pub fn cached_probe(&self, rpc_url: &str, ttl: Duration) -> Option<RpcProbe> {
+        let probes = self.rpc_probes.lock().unwrap_or_else(|e| e.into_inner());
+        probes
+            .get(rpc_url)
+            .filter(|probe| probe.checked_at.elapsed() < ttl)
+            .cloned()
+    }
+
+    /// Remember the latest probe of an RPC URL
+    pub fn store_probe(&self, rpc_url: &str, probe: RpcProbe) {
+        let mut probes = self.rpc_probes.lock().unwrap_or_else(|e| e.into_inner());
+        probes.insert(rpc_url.to_string(), probe);
+    }
+
+    /// List the sandboxes created since the server started
+    pub fn sandboxes(&self) -> Vec<Sandbox> {
+        let sandboxes = self.sandboxes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<Sandbox> = sandboxes.values().map(|(s, _)| s.clone()).collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    /// Register a sandbox, stopping any process previously registered under
+    /// its name
+    pub fn add_sandbox(&self, sandbox: Sandbox, anvil: Option<Anvil>) {
+        let mut sandboxes = self.sandboxes.lock().unwrap_or_else(|e| e.into_inner());
+        sandboxes.insert(sandbox.name.clone(), (sandbox, anvil));
+    }
+
+    /// Forget a sandbox, stopping its anvil process if the server started it
+    pub fn remove_sandbox(&self, name: &str) -> Option<Sandbox> {
+        let mut sandboxes = self.sandboxes.lock().unwrap_or_else(|e| e.into_inner());
+        sandboxes.remove(name).map(|(sandbox, _)| sandbox)
+    }
+
+    /// Stop every anvil process the server started
+    pub fn stop_sandboxes(&self) {
+        let mut sandboxes = self.sandboxes.lock().unwrap_or_else(|e| e.into_inner());
+        sandboxes.clear();
+    }
 }
@@ -1,33 +1,67 @@
+//! The embedded dashboard
+//!
+//! Vite names bundled assets after a hash of their content, so those are
+//! cached for good; everything else, `index.html` above all, is revalidated
+//! with its ETag so a new build shows up on the next load. When the build
+//! ships `.br` or `.gz` siblings of a file, they are sent to clients that
+//! accept them. Paths that aren't files get `index.html`, for the client-side
+//! router.
+
+use std::borrow::Cow;
+
 use axum::{
     body::Body,
-    http::{header, StatusCode, Uri},
+    extract::State,
+    http::{header, HeaderMap, StatusCode, Uri},
     response::{IntoResponse, Response},
 };
-use rust_embed::Embed;
+use rust_embed::{Embed, EmbeddedFile};
+
+use crate::server::AppState;
 
 #[derive(Embed)]
 #[folder = "assets"]
 struct Assets;
 
-pub async fn serve_static(uri: Uri) -> impl IntoResponse {
+/// Cache policy of assets named after their content
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Cache policy of everything else
+const REVALIDATE: &str = "no-cache";
+
+/// Pre-compressed variants, by preference
+const ENCODINGS: [(&str, &str); 2] = [("br", ".br"), ("gzip", ".gz")];
+
+pub async fn serve_static(
+    State(state): State<AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> impl IntoResponse {
     let path = uri.path().trim_start_matches('/');
 
     // Try to serve the exact file first
-    if let Some(content) = Assets::get(path) {
-        let mime = mime_guess::from_path(path).first_or_octet_stream();
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, mime.as_ref())
-            .body(Body::from(content.data))
-            .unwrap();
+    if path != "index.html" {
+        if let Some(content) = Assets::get(path) {
+            return serve_file(path, content, &headers);
+        }
     }
 
-    // For SPA: serve index.html for any non-file route
+    // For SPA: serve index.html for any non-file route. It is rewritten for
+    // the base path, so it is never sent pre-compressed
     if let Some(content) = Assets::get("index.html") {
+        let etag = etag(&content);
+        if is_fresh(&headers, &etag) {
+            return not_modified(&etag, REVALIDATE);
+        }
+        let html = String::from_utf8_lossy(&content.data);
         return Response::builder()
             .status(StatusCode::OK)
             .header(header::CONTENT_TYPE, "text/html")
-            .body(Body::from(content.data))
+            .header(header::CACHE_CONTROL, REVALIDATE)
+            .header(header::ETAG, etag)
+            .body(Body::from(
+                with_base_path(&html, state.base_path()).into_owned(),
+            ))
             .unwrap();
     }
 
@@ -36,3 +70,139 @@ pub async fn serve_static(uri: Uri) -> impl IntoResponse {
         .body(Body::from("Not Found"))
         .unwrap()
 }
+
+/// Serve an embedded file, pre-compressed when possible
+fn serve_file(path: &str, content: EmbeddedFile, headers: &HeaderMap) -> Response {
+    let cache_control = match is_hashed(path) {
+        true => IMMUTABLE,
+        false => REVALIDATE,
+    };
+    let etag = etag(&content);
+    if is_fresh(headers, &etag) {
+        return not_modified(&etag, cache_control);
+    }
+
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, mime.as_ref())
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::ETAG, &etag)
+        .header(header::VARY, "accept-encoding");
+
+    let accepted = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    for (encoding, extension) in ENCODINGS {
+        if !accepts(accepted, encoding) {
+            continue;
+        }
+        if let Some(compressed) = Assets::get(&format!("{}{}", path, extension)) {
+            return response
+                .header(header::CONTENT_ENCODING, encoding)
+                .body(Body::from(compressed.data))
+                .unwrap();
+        }
+    }
+    response.body(Body::from(content.data)).unwrap()
+}
+
+fn not_modified(etag: &str, cache_control: &'static str) -> Response {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control)
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn etag(content: &EmbeddedFile) -> String {
+    format!("\"{}\"", hex::encode(&content.metadata.sha256_hash()[..16]))
+}
+
+/// Whether the client's `If-None-Match` already covers `etag`
+fn is_fresh(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        })
+}
+
+/// Whether an `Accept-Encoding` value allows `encoding`
+fn accepts(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|item| {
+        let mut parts = item.split(';').map(str::trim);
+        let name = parts.next().unwrap_or("");
+        let refused = parts.any(|p| p.replace(' ', "") == "q=0");
+        name.eq_ignore_ascii_case(encoding) && !refused
+    })
+}
+
+/// Whether a file is named after its content, like Vite's
+/// `assets/index-B3xk9a1Q.js`
+fn is_hashed(path: &str) -> bool {
+    let Some(name) = path.strip_prefix("assets/") else {
+        return false;
+    };
+    let stem = name.split('.').next().unwrap_or("");
+    stem.rsplit_once('-').is_some_and(|(_, hash)| {
+        hash.len() == 8 && hash.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Point the dashboard at the sub-path the server is mounted under: root
+/// relative asset URLs get the prefix, and the app reads it from
+/// `window.__SMOLDER_BASE_PATH__` for API calls and routing
+fn with_base_path<'a>(html: &'a str, base_path: &str) -> Cow<'a, str> {
+    if base_path.is_empty() {
+        return Cow::Borrowed(html);
+    }
+    let html = html.replace("=\"/", &format!("=\"{}/", base_path)).replace(
+        "</head>",
+        &format!(
+            "<script>window.__SMOLDER_BASE_PATH__ = \"{}\";</script></head>",
+            base_path
+        ),
+    );
+    Cow::Owned(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_base_path() {
+        let html = r#"<head><link rel="icon" href="/favicon.svg" /><link href="https://fonts.example" /><script src="/assets/index-abc.js"></script></head>"#;
+        assert_eq!(with_base_path(html, ""), html);
+
+        let rewritten = with_base_path(html, "/smolder");
+        assert!(rewritten.contains(r#"href="/smolder/favicon.svg""#));
+        assert!(rewritten.contains(r#"src="/smolder/assets/index-abc.js""#));
+        assert!(rewritten.contains(r#"href="https://fonts.example""#));
+        assert!(rewritten.contains(r#"window.__SMOLDER_BASE_PATH__ = "/smolder";"#));
+    }
+
+    #[test]
+    fn test_is_hashed() {
+        assert!(is_hashed("assets/index-B3xk9a1Q.js"));
+        assert!(is_hashed("assets/index-B3xk9a1Q.css"));
+        assert!(!is_hashed("assets/logo.svg"));
+        assert!(!is_hashed("assets/date-picker.js"));
+        assert!(!is_hashed("favicon.svg"));
+        assert!(!is_hashed("index.html"));
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(accepts("gzip, deflate, br", "br"));
+        assert!(accepts("gzip;q=0.8", "gzip"));
+        assert!(!accepts("gzip;q=0, br", "gzip"));
+        assert!(!accepts("identity", "gzip"));
+    }
+}
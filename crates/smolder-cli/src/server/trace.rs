@@ -0,0 +1,190 @@
+//! Call trees of recorded transactions
+//!
+//! A transaction sent through smolder can be replayed with the node's call
+//! tracer (`debug_traceTransaction`). Every call in the tree is decoded
+//! against the registry: the contract at the called address names it, and
+//! its ABI, or else any registry ABI with a matching selector, decodes the
+//! parameters and return values.
+
+use std::collections::HashMap;
+
+use alloy::primitives::{Address, Bytes, Selector, U256};
+use serde::Serialize;
+use smolder_core::{decode_function_input, decode_function_result, Abi};
+use utoipa::ToSchema;
+
+use super::simulate::registry_abis;
+use crate::rpc;
+use crate::server::error::ApiError;
+use crate::server::AppState;
+
+/// A call in a transaction's call tree
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TraceCall {
+    /// `CALL`, `STATICCALL`, `DELEGATECALL`, `CREATE`, ...
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub from: String,
+    /// Called address, or the address a creation deployed to
+    pub to: Option<String>,
+    /// Registry contract at `to`
+    pub contract: Option<String>,
+    pub function: Option<String>,
+    pub signature: Option<String>,
+    /// Decoded parameters, when the selector is in a registry ABI
+    #[schema(value_type = Option<Object>)]
+    pub params: Option<serde_json::Value>,
+    /// Decoded return values, when the call succeeded
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+    /// Wei sent with the call, when any
+    pub value: Option<String>,
+    pub gas_used: Option<u64>,
+    pub input: String,
+    pub output: Option<String>,
+    /// Revert reason or error, when the call failed
+    pub error: Option<String>,
+    #[schema(no_recursion)]
+    pub calls: Vec<TraceCall>,
+}
+
+/// Decode a `callTracer` frame with the ABIs of the registry's deployments
+/// on `network`
+pub async fn decode(
+    state: &AppState,
+    network: &str,
+    frame: &serde_json::Value,
+) -> Result<TraceCall, ApiError> {
+    let contracts = registry_abis(state, network).await?;
+    Ok(decode_frame(&contracts, frame))
+}
+
+fn decode_frame(
+    contracts: &HashMap<Address, (String, Abi)>,
+    frame: &serde_json::Value,
+) -> TraceCall {
+    let str_field = |name: &str| frame[name].as_str().map(str::to_string);
+    let to: Option<Address> = frame["to"].as_str().and_then(|a| a.parse().ok());
+    let input: Bytes = frame["input"]
+        .as_str()
+        .and_then(|i| i.parse().ok())
+        .unwrap_or_default();
+    let output: Option<Bytes> = frame["output"].as_str().and_then(|o| o.parse().ok());
+    let error = frame["error"]
+        .as_str()
+        .map(|e| match frame["revertReason"].as_str() {
+            Some(reason) => format!("{}: {}", e, reason),
+            None => e.to_string(),
+        });
+    let is_create = matches!(frame["type"].as_str(), Some("CREATE" | "CREATE2"));
+
+    let contract = to.and_then(|to| contracts.get(&to));
+    let function = match (is_create, input.get(..4)) {
+        (false, Some(selector)) => {
+            let selector = Selector::from_slice(selector);
+            contract
+                .and_then(|(_, abi)| abi.function_by_selector(selector))
+                .or_else(|| {
+                    contracts
+                        .values()
+                        .find_map(|(_, abi)| abi.function_by_selector(selector))
+                })
+        }
+        _ => None,
+    };
+
+    TraceCall {
+        call_type: str_field("type").unwrap_or_else(|| "CALL".to_string()),
+        from: str_field("from").unwrap_or_default(),
+        to: to.map(|a| a.to_string()),
+        contract: contract.map(|(name, _)| name.clone()),
+        function: function.map(|f| f.name.clone()),
+        signature: function.map(|f| f.signature()),
+        params: function.and_then(|f| decode_function_input(f, &input).ok()),
+        result: match (function, &output, &error) {
+            (Some(f), Some(output), None) => decode_function_result(f, output).ok(),
+            _ => None,
+        },
+        value: frame["value"]
+            .as_str()
+            .and_then(|v| v.parse::<U256>().ok())
+            .filter(|v| !v.is_zero())
+            .map(|v| v.to_string()),
+        gas_used: frame["gasUsed"].as_str().and_then(rpc::parse_quantity),
+        input: input.to_string(),
+        output: output.map(|o| o.to_string()),
+        error,
+        calls: frame["calls"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+            .iter()
+            .map(|call| decode_frame(contracts, call))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_core::encode_function_call;
+
+    #[test]
+    fn test_decode_frame() {
+        let token = Address::repeat_byte(0x0a);
+        let proxy = Address::repeat_byte(0x0b);
+        let abi = Abi::from_human_readable([
+            "function transfer(address to, uint256 amount) returns (bool)",
+        ])
+        .unwrap();
+        let mut contracts = HashMap::new();
+        contracts.insert(token, ("Token".to_string(), abi.clone()));
+
+        let input = encode_function_call(
+            abi.function("transfer").unwrap(),
+            &[serde_json::json!(proxy.to_string()), serde_json::json!("5")],
+        )
+        .unwrap();
+        let frame = serde_json::json!({
+            "type": "CALL",
+            "from": "0x00000000000000000000000000000000000000ff",
+            "to": proxy.to_string(),
+            "value": "0xde0b6b3a7640000",
+            "gasUsed": "0x5208",
+            "input": input.to_string(),
+            "calls": [
+                {
+                    "type": "DELEGATECALL",
+                    "from": proxy.to_string(),
+                    "to": token.to_string(),
+                    "input": input.to_string(),
+                    "output": format!("0x{:064x}", 1),
+                },
+                {
+                    "type": "STATICCALL",
+                    "from": proxy.to_string(),
+                    "to": token.to_string(),
+                    "input": "0xdeadbeef",
+                    "error": "execution reverted",
+                    "revertReason": "nope",
+                },
+            ],
+        });
+
+        let call = decode_frame(&contracts, &frame);
+        assert_eq!(call.value.as_deref(), Some("1000000000000000000"));
+        assert_eq!(call.gas_used, Some(21_000));
+        // Not in the registry, but the selector is known from Token's ABI
+        assert_eq!(call.contract, None);
+        assert_eq!(call.function.as_deref(), Some("transfer"));
+        assert_eq!(call.params.as_ref().unwrap()["amount"], "5");
+
+        let delegated = &call.calls[0];
+        assert_eq!(delegated.contract.as_deref(), Some("Token"));
+        assert_eq!(delegated.result, Some(serde_json::json!(true)));
+
+        let reverted = &call.calls[1];
+        assert_eq!(reverted.function, None);
+        assert_eq!(reverted.error.as_deref(), Some("execution reverted: nope"));
+    }
+}
@@ -0,0 +1,390 @@
+//! Signed webhook notifications
+//!
+//! [`notify`] queues a delivery for every webhook whose filters match, then
+//! POSTs the JSON payload in the background. Each request is signed with the
+//! webhook's secret (HMAC-SHA256 of the body, in [`SIGNATURE_HEADER`]) and
+//! retried with exponential backoff. Deliveries interrupted by a shutdown stay
+//! pending and are resumed by [`resume_pending`] on the next start.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use smolder_core::{Error, Result};
+use smolder_db::{
+    DeliveryStatus, DeploymentView, NewWebhookDelivery, TransactionStatus, Webhook,
+    WebhookDelivery, WebhookDeliveryUpdate, WebhookTrigger,
+};
+
+use super::AppState;
+
+/// Header carrying `sha256=<hex>`, the HMAC-SHA256 of the body keyed by the
+/// webhook secret
+pub const SIGNATURE_HEADER: &str = "x-smolder-signature";
+
+/// Header carrying the delivery ID, stable across retries
+pub const DELIVERY_HEADER: &str = "x-smolder-delivery";
+
+/// Attempts made before a delivery is marked failed
+pub const MAX_ATTEMPTS: i64 = 5;
+
+/// Wait before the first retry, doubled after each further failure
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+
+/// How long an endpoint gets to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Something that happened to a tracked contract
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub trigger: WebhookTrigger,
+    pub network: String,
+    pub contract: String,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TransactionStatus>,
+    pub data: serde_json::Value,
+}
+
+impl Notification {
+    /// A call history entry on `deployment` reached `status`
+    pub fn call(
+        deployment: &DeploymentView,
+        status: TransactionStatus,
+        data: serde_json::Value,
+    ) -> Self {
+        Self {
+            trigger: WebhookTrigger::Call,
+            network: deployment.network_name.clone(),
+            contract: deployment.contract_name.clone(),
+            address: deployment.address.to_string(),
+            event: None,
+            status: Some(status),
+            data,
+        }
+    }
+
+    /// `deployment` emitted the event `name`
+    pub fn event(deployment: &DeploymentView, name: &str, data: serde_json::Value) -> Self {
+        Self {
+            trigger: WebhookTrigger::Event,
+            network: deployment.network_name.clone(),
+            contract: deployment.contract_name.clone(),
+            address: deployment.address.to_string(),
+            event: Some(name.to_string()),
+            status: None,
+            data,
+        }
+    }
+}
+
+/// Queue and send `notification` to every matching webhook
+///
+/// Failures are logged rather than returned: a broken webhook must never fail
+/// the operation that triggered it.
+pub async fn notify(state: &AppState, notification: &Notification) {
+    if let Err(e) = queue(state, notification).await {
+        tracing::warn!(error = %e, "could not queue webhook deliveries");
+    }
+}
+
+async fn queue(state: &AppState, notification: &Notification) -> Result<()> {
+    let webhooks = state.repos().webhooks().list().await?;
+    let payload = serde_json::to_string(notification)?;
+
+    for webhook in webhooks {
+        if !webhook.matches(
+            notification.trigger,
+            &notification.network,
+            &notification.contract,
+            notification.event.as_deref(),
+            notification.status,
+        ) {
+            continue;
+        }
+
+        let delivery = state
+            .repos()
+            .webhooks()
+            .create_delivery(&NewWebhookDelivery {
+                webhook_id: webhook.id,
+                trigger: notification.trigger,
+                payload: payload.clone(),
+            })
+            .await?;
+
+        let task_state = state.clone();
+        state.spawn(async move { deliver(&task_state, &webhook, delivery).await });
+    }
+
+    Ok(())
+}
+
+/// Restart deliveries left pending by a previous run
+pub async fn resume_pending(state: &AppState) {
+    let pending = match state.repos().webhooks().list_pending_deliveries().await {
+        Ok(pending) => pending,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not load pending webhook deliveries");
+            return;
+        }
+    };
+    if pending.is_empty() {
+        return;
+    }
+
+    tracing::info!(count = pending.len(), "resuming webhook deliveries");
+    let webhooks = state.repos().webhooks().list().await.unwrap_or_default();
+    for delivery in pending {
+        let Some(webhook) = webhooks.iter().find(|w| w.id == delivery.webhook_id) else {
+            continue;
+        };
+        let webhook = webhook.clone();
+        let task_state = state.clone();
+        state.spawn(async move { deliver(&task_state, &webhook, delivery).await });
+    }
+}
+
+/// Generate a random secret for signing a webhook's payloads
+pub fn generate_secret() -> String {
+    format!("whsec_{}", hex::encode(rand::random::<[u8; 32]>()))
+}
+
+/// Reject anything that isn't an absolute http(s) URL
+pub fn validate_url(url: &str) -> Result<()> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        Ok(_) => Err(Error::invalid_param("url", "Must be an http or https URL")),
+        Err(e) => Err(Error::invalid_param("url", e.to_string())),
+    }
+}
+
+/// `sha256=<hex>` signature of `body` under `secret`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// Send `delivery` until it succeeds, runs out of attempts, or the server
+/// shuts down
+async fn deliver(state: &AppState, webhook: &Webhook, mut delivery: WebhookDelivery) {
+    let signature = sign(&webhook.secret, delivery.payload.as_bytes());
+
+    while delivery.attempts < MAX_ATTEMPTS {
+        if delivery.attempts > 0 {
+            let backoff = BASE_BACKOFF * 2u32.pow(delivery.attempts as u32 - 1);
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                // Left pending; resumed on the next start
+                _ = state.shutdown_token().cancelled() => return,
+            }
+        }
+
+        let response = client()
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .header(DELIVERY_HEADER, delivery.id)
+            .body(delivery.payload.clone())
+            .send()
+            .await;
+
+        delivery.attempts += 1;
+        let (response_status, error) = match response {
+            Ok(r) if r.status().is_success() => (Some(r.status().as_u16() as i64), None),
+            Ok(r) => (
+                Some(r.status().as_u16() as i64),
+                Some(format!("Endpoint answered {}", r.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let status = match error {
+            None => DeliveryStatus::Delivered,
+            Some(_) if delivery.attempts >= MAX_ATTEMPTS => DeliveryStatus::Failed,
+            Some(_) => DeliveryStatus::Pending,
+        };
+
+        tracing::debug!(
+            webhook_id = %webhook.id,
+            delivery_id = delivery.id,
+            attempt = delivery.attempts,
+            status = %status,
+            "webhook delivery attempt"
+        );
+
+        let update = WebhookDeliveryUpdate {
+            status,
+            attempts: delivery.attempts,
+            response_status,
+            error_message: error,
+        };
+        if let Err(e) = state
+            .repos()
+            .webhooks()
+            .update_delivery(delivery.id, &update)
+            .await
+        {
+            tracing::warn!(error = %e, delivery_id = delivery.id, "could not record webhook delivery");
+        }
+
+        if status != DeliveryStatus::Pending {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+    use smolder_core::{TxHash, B256};
+    use smolder_db::{ChainId, Database, DeploymentId, ExplorerLinks, NewWebhook};
+
+    use super::*;
+
+    /// Signature header and body of each request an endpoint received
+    type Received = Arc<Mutex<Vec<(String, String)>>>;
+
+    fn deployment() -> DeploymentView {
+        DeploymentView {
+            id: DeploymentId(1),
+            contract_name: "Token".to_string(),
+            network_name: "testnet".to_string(),
+            chain_id: ChainId(12345),
+            address: "0x1234567890abcdef1234567890abcdef12345678"
+                .parse()
+                .unwrap(),
+            deployer: "0xdeployer".to_string(),
+            wallet_name: None,
+            tx_hash: Some(TxHash(B256::repeat_byte(0x11))),
+            block_number: None,
+            version: 1,
+            deployed_at: "2024-01-01 00:00:00".to_string(),
+            is_current: true,
+            archived_at: None,
+            initcode_size: None,
+            runtime_size: None,
+            gas_used: None,
+            gas_price: None,
+            fee: None,
+            created_by_call_id: None,
+            reorged_at: None,
+            finality: None,
+            pinned: false,
+            label: None,
+            description: None,
+            abi: "[]".to_string(),
+            links: ExplorerLinks::default(),
+            token: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_delivers_signed_payload() {
+        let received: Received = Arc::default();
+        let endpoint = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State(received): State<Received>,
+                     headers: HeaderMap,
+                     body: String| async move {
+                        let signature = headers[SIGNATURE_HEADER].to_str().unwrap().to_string();
+                        received.lock().unwrap().push((signature, body));
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, endpoint).await });
+
+        let db = Database::connect_to(":memory:").await.unwrap();
+        db.init_schema().await.unwrap();
+        let state = AppState::new(db, Arc::new(crate::forge::FileSystemArtifactLoader::new()));
+
+        let webhook = state
+            .repos()
+            .webhooks()
+            .create(&NewWebhook {
+                url: format!("http://{}/hook", addr),
+                secret: "secret".to_string(),
+                network: None,
+                contract: Some("Token".to_string()),
+                event_name: None,
+                tx_status: Some(TransactionStatus::Success),
+            })
+            .await
+            .unwrap();
+
+        // Filtered out by tx_status, then delivered
+        notify(
+            &state,
+            &Notification::call(
+                &deployment(),
+                TransactionStatus::Reverted,
+                serde_json::json!({}),
+            ),
+        )
+        .await;
+        notify(
+            &state,
+            &Notification::call(
+                &deployment(),
+                TransactionStatus::Success,
+                serde_json::json!({"history_id": 7}),
+            ),
+        )
+        .await;
+
+        state.tasks().close();
+        state.tasks().wait().await;
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        let (signature, body) = &received[0];
+        assert_eq!(signature, &sign("secret", body.as_bytes()));
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["trigger"], "call");
+        assert_eq!(payload["status"], "success");
+        assert_eq!(payload["data"]["history_id"], 7);
+
+        let deliveries = state
+            .repos()
+            .webhooks()
+            .list_deliveries(webhook.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].attempts, 1);
+        assert_eq!(deliveries[0].response_status, Some(200));
+    }
+
+    #[test]
+    fn test_sign() {
+        // echo -n '{"a":1}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            sign("secret", br#"{"a":1}"#),
+            "sha256=aa9e2e3575f5d7098b6caccd790888c36d5fdb63342a73bada2d6a51747a8494"
+        );
+        assert!(generate_secret().starts_with("whsec_"));
+    }
+}
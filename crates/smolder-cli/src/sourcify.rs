@@ -0,0 +1,56 @@
+//! Source verification status from Sourcify
+//!
+//! Sourcify answers for contracts verified on any chain, so one lookup
+//! covers deployments everywhere. Lookups are best effort: when Sourcify
+//! can't be reached the status is unknown rather than an error.
+
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use futures::future::join_all;
+use serde::Serialize;
+use smolder_db::DeploymentView;
+use utoipa::ToSchema;
+
+const SOURCIFY_URL: &str = "https://sourcify.dev/server/v2/contract";
+
+/// Time allowed for each lookup
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sourcify match (`exact_match` or `match`), or `false` when unverified
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum Verification {
+    Verified(String),
+    Unverified(bool),
+}
+
+/// Look `address` up on Sourcify, `None` when the answer is unknown
+pub async fn lookup(chain_id: i64, address: Address) -> Option<Verification> {
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build().ok()?;
+    let response = client
+        .get(format!("{}/{}/{}", SOURCIFY_URL, chain_id, address))
+        .send()
+        .await
+        .inspect_err(|e| tracing::debug!(error = %e, "Sourcify lookup failed"))
+        .ok()?;
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => Some(Verification::Unverified(false)),
+        status if status.is_success() => {
+            let body: serde_json::Value = response.json().await.ok()?;
+            let level = body["match"].as_str().unwrap_or("match").to_string();
+            Some(Verification::Verified(level))
+        }
+        _ => None,
+    }
+}
+
+/// Look every deployment up at once, in order
+pub async fn lookup_all(deployments: &[DeploymentView]) -> Vec<Option<Verification>> {
+    join_all(
+        deployments
+            .iter()
+            .map(|d| lookup(d.chain_id.0, Address::from(d.address))),
+    )
+    .await
+}
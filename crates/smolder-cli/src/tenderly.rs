@@ -0,0 +1,262 @@
+//! Simulate transactions on Tenderly
+//!
+//! Configured under `[tenderly]` in smolder.toml. Simulations are saved to the
+//! project and shared, so the link attached to a call can be opened by anyone
+//! reviewing it, with the full call trace and state diff.
+
+use std::time::Duration;
+
+use alloy::primitives::{Address, Bytes, U256};
+use color_eyre::eyre::Result;
+use serde_json::json;
+use smolder_core::{ChainId, Error, Secret};
+
+use crate::config::{resolve_env_var, TenderlyConfig};
+use crate::rpc::{SimulatedLog, Simulation};
+
+const API_URL: &str = "https://api.tenderly.co/api/v1";
+const SHARED_URL: &str = "https://www.tdly.co/shared/simulation";
+const DASHBOARD_URL: &str = "https://dashboard.tenderly.co";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Client for a Tenderly project
+#[derive(Debug, Clone)]
+pub struct Tenderly {
+    account: String,
+    project: String,
+    access_key: Secret<String>,
+    client: reqwest::Client,
+}
+
+impl Tenderly {
+    /// Build a client from `[tenderly]`, resolving `${VAR}` references
+    pub fn from_config(config: &TenderlyConfig) -> Result<Self> {
+        Ok(Self {
+            account: resolve_env_var(&config.account)?,
+            project: resolve_env_var(&config.project)?,
+            access_key: Secret::new(resolve_env_var(&config.access_key)?),
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+        })
+    }
+
+    fn project_url(&self) -> String {
+        format!(
+            "{}/account/{}/project/{}",
+            API_URL, self.account, self.project
+        )
+    }
+
+    /// Simulate a call to `to` against the latest block of `chain_id`
+    ///
+    /// The simulation is saved and shared; its link is in
+    /// [`Simulation::url`]. When sharing fails the link points to the
+    /// project's dashboard instead, which needs a Tenderly login.
+    /// `state_overrides` is a geth state override set, keyed by address.
+    pub async fn simulate(
+        &self,
+        chain_id: ChainId,
+        from: Address,
+        to: Address,
+        data: &Bytes,
+        value: Option<U256>,
+        state_overrides: Option<&serde_json::Value>,
+    ) -> Result<Simulation, Error> {
+        let mut body = json!({
+            "network_id": chain_id.to_string(),
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "input": data.to_string(),
+            "value": value.unwrap_or_default().to_string(),
+            "save": true,
+            "save_if_fails": true,
+            "simulation_type": "full",
+        });
+        if let Some(overrides) = state_overrides {
+            body["state_objects"] = state_objects(overrides);
+        }
+
+        let response: serde_json::Value = self
+            .post(&format!("{}/simulate", self.project_url()), &body)
+            .await?;
+        let (mut simulation, id) = simulation_from_response(&response).ok_or_else(|| {
+            Error::Rpc("Tenderly simulation returned an unexpected response".to_string())
+        })?;
+
+        let shared = self
+            .post(
+                &format!("{}/simulations/{}/share", self.project_url(), id),
+                &json!({}),
+            )
+            .await;
+        simulation.url = Some(match shared {
+            Ok(_) => format!("{}/{}", SHARED_URL, id),
+            Err(e) => {
+                tracing::warn!(error = %e, simulation = %id, "could not share Tenderly simulation");
+                format!(
+                    "{}/{}/{}/simulator/{}",
+                    DASHBOARD_URL, self.account, self.project, id
+                )
+            }
+        });
+        Ok(simulation)
+    }
+
+    async fn post(&self, url: &str, body: &serde_json::Value) -> Result<serde_json::Value, Error> {
+        let response = self
+            .client
+            .post(url)
+            .header("X-Access-Key", self.access_key.expose())
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Error::Rpc(format!("Tenderly request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(Error::Rpc(format!(
+                "Tenderly returned {}: {}",
+                status, text
+            )));
+        }
+        // The share endpoint answers with an empty body
+        let text = response
+            .text()
+            .await
+            .map_err(|e| Error::Rpc(format!("Tenderly request failed: {}", e)))?;
+        match text.is_empty() {
+            true => Ok(serde_json::Value::Null),
+            false => serde_json::from_str(&text)
+                .map_err(|e| Error::Rpc(format!("Invalid Tenderly response: {}", e))),
+        }
+    }
+}
+
+/// Translate a geth state override set to Tenderly's `state_objects`
+fn state_objects(overrides: &serde_json::Value) -> serde_json::Value {
+    let Some(overrides) = overrides.as_object() else {
+        return json!({});
+    };
+    overrides
+        .iter()
+        .map(|(address, account)| {
+            let mut object = serde_json::Map::new();
+            for (field, value) in account.as_object().into_iter().flatten() {
+                let field = match field.as_str() {
+                    "state" | "stateDiff" => "storage",
+                    other => other,
+                };
+                object.insert(field.to_string(), value.clone());
+            }
+            (address.clone(), serde_json::Value::Object(object))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Read a simulation and its ID from a `/simulate` response
+fn simulation_from_response(response: &serde_json::Value) -> Option<(Simulation, String)> {
+    let id = response["simulation"]["id"].as_str()?.to_string();
+    let transaction = &response["transaction"];
+    let info = &transaction["transaction_info"];
+    let trace = &info["call_trace"];
+
+    let error = transaction["error_message"]
+        .as_str()
+        .filter(|e| !e.is_empty())
+        .map(str::to_string);
+    let logs = info["logs"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|log| {
+            let raw = &log["raw"];
+            Some(SimulatedLog {
+                address: raw["address"].as_str()?.parse().ok()?,
+                topics: raw["topics"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|t| t.as_str()?.parse().ok())
+                    .collect(),
+                data: raw["data"].as_str()?.parse().ok()?,
+            })
+        })
+        .collect();
+
+    let simulation = Simulation {
+        success: transaction["status"].as_bool().unwrap_or(error.is_none()),
+        output: trace["output"]
+            .as_str()
+            .and_then(|o| o.parse().ok())
+            .unwrap_or_default(),
+        gas_used: transaction["gas_used"]
+            .as_u64()
+            .or_else(|| response["simulation"]["gas_used"].as_u64()),
+        error,
+        logs,
+        trace: (!trace.is_null()).then(|| trace.clone()),
+        contract_address: None,
+        state_diff: info["state_diff"]
+            .as_array()
+            .map(|diff| serde_json::Value::Array(diff.clone())),
+        url: None,
+    };
+    Some((simulation, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulation_from_response() {
+        let response = json!({
+            "simulation": { "id": "sim-1", "gas_used": 1 },
+            "transaction": {
+                "status": false,
+                "gas_used": 30000,
+                "error_message": "execution reverted",
+                "transaction_info": {
+                    "call_trace": { "call_type": "CALL", "output": "0x08c379a0" },
+                    "state_diff": [{ "address": "0x0000000000000000000000000000000000000001" }],
+                    "logs": [{
+                        "name": "Transfer",
+                        "raw": {
+                            "address": "0x0000000000000000000000000000000000000001",
+                            "topics": [format!("0x{:064x}", 1)],
+                            "data": "0x",
+                        },
+                    }],
+                },
+            },
+        });
+
+        let (simulation, id) = simulation_from_response(&response).unwrap();
+        assert_eq!(id, "sim-1");
+        assert!(!simulation.success);
+        assert_eq!(simulation.gas_used, Some(30000));
+        assert_eq!(simulation.error.as_deref(), Some("execution reverted"));
+        assert_eq!(simulation.output.to_string(), "0x08c379a0");
+        assert_eq!(simulation.logs.len(), 1);
+        assert_eq!(simulation.state_diff.unwrap().as_array().unwrap().len(), 1);
+
+        assert!(simulation_from_response(&json!({ "error": "bad key" })).is_none());
+    }
+
+    #[test]
+    fn test_state_objects_use_tenderly_names() {
+        let overrides = json!({
+            "0x0000000000000000000000000000000000000001": {
+                "balance": "0x1",
+                "stateDiff": { "0x00": "0x01" },
+            },
+        });
+        let objects = state_objects(&overrides);
+        let account = &objects["0x0000000000000000000000000000000000000001"];
+        assert_eq!(account["balance"], "0x1");
+        assert_eq!(account["storage"]["0x00"], "0x01");
+    }
+}
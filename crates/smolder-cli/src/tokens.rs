@@ -0,0 +1,170 @@
+//! Token metadata for deployed ERC-20, ERC-721 and ERC-1155 contracts
+//!
+//! The first time a deployment whose ABI looks like a token is seen, its
+//! name, symbol, decimals and total supply are read from the chain and stored
+//! in the registry. Getters a token doesn't implement are left empty, and
+//! deployments on networks that can't be reached are tried again next time.
+//! Reading is best effort and never fails the command or request.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use alloy::sol;
+use futures::stream::{self, StreamExt};
+use smolder_core::{Abi, TokenStandard};
+use smolder_db::{DeploymentId, DeploymentView, NewTokenMetadata, Repositories};
+
+use crate::rpc;
+
+/// How long a network gets to answer before its tokens are skipped
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Contracts read at once
+const CONCURRENCY: usize = 8;
+
+sol! {
+    interface IToken {
+        function name() external view returns (string);
+        function symbol() external view returns (string);
+        function decimals() external view returns (uint8);
+        function totalSupply() external view returns (uint256);
+    }
+}
+
+/// Read and store metadata for the token deployments in `deployments` that
+/// don't have any yet, filling in their `token` field
+pub async fn enrich(db: &dyn Repositories, deployments: &mut [DeploymentView]) {
+    let candidates: Vec<(usize, TokenStandard)> = deployments
+        .iter()
+        .enumerate()
+        .filter_map(|(i, d)| Some((i, missing_standard(d)?)))
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+
+    let networks = match db.networks().list().await {
+        Ok(networks) => networks,
+        Err(e) => {
+            tracing::debug!(error = %e, "could not load networks for token metadata");
+            return;
+        }
+    };
+    let rpc_urls: HashMap<&str, &str> = networks
+        .iter()
+        .map(|n| (n.name.as_str(), n.rpc_url.expose()))
+        .collect();
+
+    // Probe each network once, so a reverting getter can be told apart from
+    // an RPC that is down
+    let mut reachable = HashMap::new();
+    for (index, _) in &candidates {
+        let network = deployments[*index].network_name.as_str();
+        if reachable.contains_key(network) {
+            continue;
+        }
+        let up = match rpc_urls.get(network) {
+            Some(rpc_url) => matches!(
+                tokio::time::timeout(RPC_TIMEOUT, rpc::get_chain_id(rpc_url)).await,
+                Ok(Ok(_))
+            ),
+            None => false,
+        };
+        reachable.insert(network.to_string(), up);
+    }
+
+    let reads: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|(index, standard)| {
+            let deployment = &deployments[index];
+            if !reachable
+                .get(&deployment.network_name)
+                .copied()
+                .unwrap_or(false)
+            {
+                return None;
+            }
+            let rpc_url = rpc_urls.get(deployment.network_name.as_str())?.to_string();
+            Some((index, deployment.id, standard, rpc_url, *deployment.address))
+        })
+        .collect();
+
+    let metadata: Vec<(usize, NewTokenMetadata)> = stream::iter(reads)
+        .map(|(index, id, standard, rpc_url, address)| async move {
+            let metadata = read(&rpc_url, address, id, standard).await;
+            (index, metadata)
+        })
+        .buffer_unordered(CONCURRENCY)
+        .collect()
+        .await;
+
+    for (index, metadata) in metadata {
+        let stored = match db.token_metadata().put(&metadata).await {
+            Ok(()) => db.token_metadata().get(metadata.deployment_id).await,
+            Err(e) => Err(e),
+        };
+        match stored {
+            Ok(token) => deployments[index].token = token,
+            Err(e) => tracing::debug!(error = %e, "could not store token metadata"),
+        }
+    }
+}
+
+/// Whether `deployment` is a token whose metadata hasn't been read yet
+pub fn needs_metadata(deployment: &DeploymentView) -> bool {
+    missing_standard(deployment).is_some()
+}
+
+fn missing_standard(deployment: &DeploymentView) -> Option<TokenStandard> {
+    if deployment.token.is_some() {
+        return None;
+    }
+    Abi::parse(&deployment.abi).ok()?.token_standard()
+}
+
+/// [`enrich`] the deployments with the given IDs, e.g. right after recording them
+pub async fn enrich_ids(db: &dyn Repositories, ids: &[DeploymentId]) {
+    let mut deployments = Vec::new();
+    for id in ids {
+        if let Ok(Some(view)) = db.deployments().get_view_by_id(*id).await {
+            deployments.push(view);
+        }
+    }
+    enrich(db, &mut deployments).await;
+}
+
+/// Call the getters `standard` defines on the token at `address`
+async fn read(
+    rpc_url: &str,
+    address: Address,
+    deployment_id: DeploymentId,
+    standard: TokenStandard,
+) -> NewTokenMetadata {
+    let name = rpc::call(rpc_url, address, IToken::nameCall {});
+    let symbol = rpc::call(rpc_url, address, IToken::symbolCall {});
+    let decimals = async {
+        match standard.is_fungible() {
+            true => rpc::call(rpc_url, address, IToken::decimalsCall {}).await,
+            false => None,
+        }
+    };
+    // Only enumerable ERC-721s have a supply; ERC-1155 supplies are per ID
+    let total_supply = async {
+        match standard {
+            TokenStandard::Erc1155 => None,
+            _ => rpc::call(rpc_url, address, IToken::totalSupplyCall {}).await,
+        }
+    };
+    let (name, symbol, decimals, total_supply) =
+        futures::join!(name, symbol, decimals, total_supply);
+
+    NewTokenMetadata {
+        deployment_id,
+        standard,
+        name: name.filter(|n| !n.is_empty()),
+        symbol: symbol.filter(|s| !s.is_empty()),
+        decimals: decimals.map(i64::from),
+        total_supply: total_supply.map(|s| s.to_string()),
+    }
+}
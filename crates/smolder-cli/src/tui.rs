@@ -0,0 +1,185 @@
+//! Full-screen deployment picker for `smolder list --interactive`
+
+use color_eyre::eyre::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use smolder_db::DeploymentView;
+
+/// Show a fuzzy-searchable table of deployments and return the one selected
+/// with Enter, or `None` if the user quits with Esc.
+pub fn pick_deployment(deployments: &[DeploymentView]) -> Result<Option<&DeploymentView>> {
+    let mut terminal = ratatui::init();
+    let result = Picker::new(deployments).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+struct Picker<'a> {
+    deployments: &'a [DeploymentView],
+    query: String,
+    /// Indices into `deployments` matching the current query
+    matches: Vec<usize>,
+    state: TableState,
+}
+
+impl<'a> Picker<'a> {
+    fn new(deployments: &'a [DeploymentView]) -> Self {
+        let mut picker = Self {
+            deployments,
+            query: String::new(),
+            matches: Vec::new(),
+            state: TableState::default(),
+        };
+        picker.update_matches();
+        picker
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<Option<&'a DeploymentView>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(None)
+                }
+                KeyCode::Enter => return Ok(self.selected()),
+                KeyCode::Up => self.move_selection(-1),
+                KeyCode::Down => self.move_selection(1),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.update_matches();
+                }
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.update_matches();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<&'a DeploymentView> {
+        let deployments = self.deployments;
+        self.state
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|&i| &deployments[i])
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((current + delta).rem_euclid(len) as usize));
+    }
+
+    fn update_matches(&mut self) {
+        self.matches = self
+            .deployments
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| {
+                let haystack = format!("{} {} {}", d.network_name, d.contract_name, d.address);
+                fuzzy_match(&self.query, &haystack)
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        self.state.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search_area, table_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let search = Paragraph::new(self.query.as_str())
+            .block(Block::default().borders(Borders::ALL).title(" Search "));
+        frame.render_widget(search, search_area);
+
+        let rows = self.matches.iter().map(|&i| {
+            let d = &self.deployments[i];
+            Row::new(vec![
+                d.network_name.clone(),
+                d.contract_name.clone(),
+                format!("v{}", d.version),
+                d.address.to_string(),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(15),
+                Constraint::Length(20),
+                Constraint::Length(8),
+                Constraint::Min(42),
+            ],
+        )
+        .header(
+            Row::new(vec!["Network", "Contract", "Version", "Address"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Deployments ({}/{}) ",
+            self.matches.len(),
+            self.deployments.len()
+        )))
+        .row_highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan))
+        .highlight_symbol("> ");
+        frame.render_stateful_widget(table, table_area, &mut self.state);
+
+        let help = Line::from("type to filter  ↑/↓ move  enter select  esc quit")
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(help, help_area);
+    }
+}
+
+/// Case-insensitive subsequence match: every character of `query` appears in
+/// `haystack` in order, though not necessarily contiguously.
+fn fuzzy_match(query: &str, haystack: &str) -> bool {
+    let haystack = haystack.to_lowercase();
+    let mut chars = haystack.chars();
+    query
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|q| chars.any(|h| h == q))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzzy_match;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("", "mainnet Token 0xabc"));
+        assert!(fuzzy_match("tok", "mainnet Token 0xabc"));
+        assert!(fuzzy_match("mn tk", "mainnet Token 0xabc"));
+        assert!(fuzzy_match("MAINTOKEN", "mainnet Token 0xabc"));
+        assert!(!fuzzy_match("tokm", "mainnet Token 0xabc"));
+        assert!(!fuzzy_match("xyz", "mainnet Token 0xabc"));
+    }
+}
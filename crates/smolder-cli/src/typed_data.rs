@@ -0,0 +1,137 @@
+//! EIP-712 typed data signed with stored wallets
+//!
+//! Permits, meta-transactions and governance votes are signed off-chain, so
+//! unlike transactions they leave no trace on chain until someone submits
+//! them. Each signature is recorded with its domain and full payload, so the
+//! registry shows what every wallet has signed.
+
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use alloy_dyn_abi::TypedData;
+use smolder_core::{decrypt_private_key, ApiTokenId, Error};
+use smolder_db::{NewSignature, Repositories, SignatureRecord, WalletWithKey};
+
+/// Parse an `eth_signTypedData_v4` payload: `types`, `primaryType`,
+/// `domain` and `message`
+pub fn parse(payload: &serde_json::Value) -> Result<TypedData, Error> {
+    serde_json::from_value(payload.clone())
+        .map_err(|e| Error::invalid_param("typed_data", format!("Invalid EIP-712 payload: {}", e)))
+}
+
+/// Sign `typed` with `wallet` and record the signature
+///
+/// `signed_by` is the API token that asked for it, `None` from the CLI.
+pub async fn sign(
+    repos: &dyn Repositories,
+    wallet: &WalletWithKey,
+    typed: &TypedData,
+    signed_by: Option<ApiTokenId>,
+) -> Result<SignatureRecord, Error> {
+    let digest = typed
+        .eip712_signing_hash()
+        .map_err(|e| Error::invalid_param("typed_data", format!("Cannot hash payload: {}", e)))?;
+
+    let private_key = decrypt_private_key(&wallet.encrypted_key)?;
+    let signer: PrivateKeySigner = private_key
+        .parse()
+        .map_err(|e| Error::invalid_param("private_key", format!("Invalid: {}", e)))?;
+    let signature = signer
+        .sign_hash_sync(&digest)
+        .map_err(|e| Error::Validation(format!("Failed to sign: {}", e)))?;
+
+    repos
+        .signatures()
+        .create(&NewSignature {
+            wallet_id: wallet.id,
+            signer: wallet.address,
+            primary_type: typed.primary_type.clone(),
+            domain: serde_json::to_string(&typed.domain)?,
+            payload: serde_json::to_string(typed)?,
+            digest: digest.to_string(),
+            signature: signature.to_string(),
+            signed_by,
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, Address};
+    use smolder_core::encrypt_private_key;
+    use smolder_db::{MemoryDatabase, NewWallet};
+
+    /// The `Mail` example of the EIP-712 specification
+    fn mail() -> serde_json::Value {
+        serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" }
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sign_matches_the_specification() {
+        // The specification's signer: keccak256("cow")
+        let private_key = "0xc85ef7d79691fe79573b1a7064c19c1a9819ebdbd1faaab1a8ec92344438aaf4";
+        let cow: Address = address!("0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826");
+        let repos = MemoryDatabase::new();
+        repos
+            .wallets()
+            .create(&NewWallet {
+                name: "cow".to_string(),
+                address: cow.into(),
+                encrypted_key: encrypt_private_key(private_key).unwrap(),
+            })
+            .await
+            .unwrap();
+        let wallet = repos.wallets().get_with_key("cow").await.unwrap().unwrap();
+
+        let typed = parse(&mail()).unwrap();
+        let record = sign(&repos, &wallet, &typed, None).await.unwrap();
+        assert_eq!(
+            record.digest,
+            "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+        assert_eq!(
+            record.signature,
+            "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+        );
+        assert_eq!(record.primary_type, "Mail");
+        assert_eq!(record.wallet_name.as_deref(), Some("cow"));
+        assert!(record.domain.contains("Ether Mail"));
+    }
+
+    #[test]
+    fn test_parse_rejects_payloads_without_types() {
+        let mut payload = mail();
+        payload.as_object_mut().unwrap().remove("types");
+        assert!(parse(&payload).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+//! Upgrades of EIP-1967 proxies
+//!
+//! How a proxy is upgraded depends on its kind: a UUPS proxy takes the call
+//! itself (its implementation checks who is allowed), a transparent proxy
+//! only listens to its admin, which is either a ProxyAdmin contract or the
+//! upgrading wallet. OpenZeppelin 5 removed the upgrade functions that don't
+//! take a call, so the `UPGRADE_INTERFACE_VERSION` constant it added decides
+//! which function to use.
+
+use alloy::primitives::{Address, Bytes};
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use smolder_core::storage::{ADMIN_SLOT, IMPLEMENTATION_SLOT};
+use smolder_core::{check_upgrade, Error, LayoutIssue, NetworkId, StorageLayout};
+use smolder_db::{ContractRepository, Database, DeploymentRepository};
+
+use crate::rpc;
+
+sol! {
+    interface IUpgradeable {
+        function UPGRADE_INTERFACE_VERSION() external view returns (string);
+        function owner() external view returns (address);
+        function upgradeTo(address newImplementation) external;
+        function upgradeToAndCall(address newImplementation, bytes data) external payable;
+    }
+
+    interface IProxyAdmin {
+        function upgrade(address proxy, address implementation) external;
+        function upgradeAndCall(address proxy, address implementation, bytes data) external payable;
+    }
+}
+
+/// Who performs an upgrade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradePath {
+    /// A UUPS proxy, upgraded through its implementation
+    Uups { v5: bool },
+    /// A transparent proxy whose admin is the upgrading wallet
+    AdminWallet,
+    /// A transparent proxy administered by a ProxyAdmin contract the wallet owns
+    ProxyAdmin { admin: Address, v5: bool },
+}
+
+impl std::fmt::Display for UpgradePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpgradePath::Uups { .. } => write!(f, "UUPS"),
+            UpgradePath::AdminWallet => write!(f, "transparent, admin wallet"),
+            UpgradePath::ProxyAdmin { admin, .. } => {
+                write!(f, "transparent, ProxyAdmin {}", admin)
+            }
+        }
+    }
+}
+
+/// A proxy and how the wallet can upgrade it
+#[derive(Debug, Clone, Copy)]
+pub struct Proxy {
+    pub address: Address,
+    /// Implementation it currently delegates to
+    pub implementation: Address,
+    pub path: UpgradePath,
+}
+
+/// The transaction that points a proxy at a new implementation
+#[derive(Debug, Clone)]
+pub struct UpgradeCall {
+    /// The proxy, or its ProxyAdmin
+    pub to: Address,
+    pub function_name: &'static str,
+    pub function_signature: &'static str,
+    /// Arguments, for the call history
+    pub params: serde_json::Value,
+    pub data: Bytes,
+}
+
+/// Read the proxy slots of `address` and work out how `wallet` can upgrade it
+///
+/// Fails when `address` isn't an EIP-1967 proxy, or when it is transparent
+/// and administered by someone else. Whether the wallet may upgrade a UUPS
+/// proxy is only known by trying, see [`simulate`].
+pub async fn inspect(rpc_url: &str, address: Address, wallet: Address) -> Result<Proxy, Error> {
+    let implementation =
+        Address::from_word(rpc::get_storage_at(rpc_url, address, IMPLEMENTATION_SLOT).await?);
+    if implementation.is_zero() {
+        return Err(Error::Validation(format!(
+            "{} is not an EIP-1967 proxy: its implementation slot is empty",
+            address
+        )));
+    }
+    let admin = Address::from_word(rpc::get_storage_at(rpc_url, address, ADMIN_SLOT).await?);
+
+    let path = if admin.is_zero() {
+        UpgradePath::Uups {
+            v5: is_v5(rpc_url, address).await,
+        }
+    } else if admin == wallet {
+        UpgradePath::AdminWallet
+    } else {
+        match rpc::call(rpc_url, admin, IUpgradeable::ownerCall {}).await {
+            Some(owner) if owner == wallet => UpgradePath::ProxyAdmin {
+                admin,
+                v5: is_v5(rpc_url, admin).await,
+            },
+            Some(owner) => {
+                return Err(Error::Validation(format!(
+                "{} is administered by ProxyAdmin {}, which is owned by {} rather than the wallet",
+                address, admin, owner
+            )))
+            }
+            None => {
+                return Err(Error::Validation(format!(
+                    "{} is administered by {}, which is neither the wallet nor a ProxyAdmin",
+                    address, admin
+                )))
+            }
+        }
+    };
+
+    Ok(Proxy {
+        address,
+        implementation,
+        path,
+    })
+}
+
+/// Build the transaction upgrading `proxy` to `implementation`, calling
+/// `init` on it when not empty
+pub fn upgrade_call(proxy: &Proxy, implementation: Address, init: Bytes) -> UpgradeCall {
+    let with_call = !init.is_empty();
+    match proxy.path {
+        UpgradePath::ProxyAdmin { admin, v5 } if with_call || v5 => UpgradeCall {
+            to: admin,
+            function_name: "upgradeAndCall",
+            function_signature: IProxyAdmin::upgradeAndCallCall::SIGNATURE,
+            params: serde_json::json!([
+                proxy.address.to_string(),
+                implementation.to_string(),
+                init.to_string()
+            ]),
+            data: IProxyAdmin::upgradeAndCallCall {
+                proxy: proxy.address,
+                implementation,
+                data: init,
+            }
+            .abi_encode()
+            .into(),
+        },
+        UpgradePath::ProxyAdmin { admin, .. } => UpgradeCall {
+            to: admin,
+            function_name: "upgrade",
+            function_signature: IProxyAdmin::upgradeCall::SIGNATURE,
+            params: serde_json::json!([proxy.address.to_string(), implementation.to_string()]),
+            data: IProxyAdmin::upgradeCall {
+                proxy: proxy.address,
+                implementation,
+            }
+            .abi_encode()
+            .into(),
+        },
+        UpgradePath::Uups { v5: true } => upgrade_to_and_call(proxy, implementation, init),
+        _ if with_call => upgrade_to_and_call(proxy, implementation, init),
+        _ => UpgradeCall {
+            to: proxy.address,
+            function_name: "upgradeTo",
+            function_signature: IUpgradeable::upgradeToCall::SIGNATURE,
+            params: serde_json::json!([implementation.to_string()]),
+            data: IUpgradeable::upgradeToCall {
+                newImplementation: implementation,
+            }
+            .abi_encode()
+            .into(),
+        },
+    }
+}
+
+fn upgrade_to_and_call(proxy: &Proxy, implementation: Address, init: Bytes) -> UpgradeCall {
+    UpgradeCall {
+        to: proxy.address,
+        function_name: "upgradeToAndCall",
+        function_signature: IUpgradeable::upgradeToAndCallCall::SIGNATURE,
+        params: serde_json::json!([implementation.to_string(), init.to_string()]),
+        data: IUpgradeable::upgradeToAndCallCall {
+            newImplementation: implementation,
+            data: init,
+        }
+        .abi_encode()
+        .into(),
+    }
+}
+
+/// Check that `wallet` is allowed to upgrade `proxy` before anything is
+/// deployed, by simulating an upgrade to the implementation it already has
+pub async fn simulate(rpc_url: &str, proxy: &Proxy, wallet: Address) -> Result<(), Error> {
+    let call = upgrade_call(proxy, proxy.implementation, Bytes::new());
+    let simulation = rpc::simulate(rpc_url, wallet, Some(call.to), call.data, None, None).await?;
+    match simulation.success {
+        true => Ok(()),
+        false => Err(Error::Validation(format!(
+            "{} would revert for this wallet: {}",
+            call.function_name,
+            simulation
+                .error
+                .unwrap_or_else(|| "no reason given".to_string())
+        ))),
+    }
+}
+
+/// Outcome of comparing the storage layouts of two implementations
+#[derive(Debug)]
+pub enum LayoutCheck {
+    /// The layouts were compared; the issues found, if any
+    Checked(Vec<LayoutIssue>),
+    /// Why they couldn't be compared
+    Skipped(String),
+}
+
+/// Compare `layout`, the new implementation's, with the layout recorded for
+/// the implementation `current` on the network
+pub async fn check_layout(
+    db: &Database,
+    network_id: NetworkId,
+    current: Address,
+    layout: Option<&str>,
+) -> Result<LayoutCheck, Error> {
+    let Some(layout) = layout else {
+        return Ok(LayoutCheck::Skipped(
+            "the new implementation was built without a storage layout".to_string(),
+        ));
+    };
+    let Some(deployment) =
+        DeploymentRepository::get_by_address(db, network_id, &current.to_string()).await?
+    else {
+        return Ok(LayoutCheck::Skipped(format!(
+            "the current implementation {} is not tracked",
+            current
+        )));
+    };
+    let previous = ContractRepository::get_by_id(db, deployment.contract_id)
+        .await?
+        .and_then(|c| c.storage_layout);
+    let Some(previous) = previous else {
+        return Ok(LayoutCheck::Skipped(format!(
+            "the current implementation {} has no recorded storage layout",
+            current
+        )));
+    };
+
+    Ok(LayoutCheck::Checked(check_upgrade(
+        &StorageLayout::parse(&previous)?,
+        &StorageLayout::parse(layout)?,
+    )))
+}
+
+/// Whether the contract at `address` declares OpenZeppelin 5's upgrade
+/// interface, which only upgrades with a call
+async fn is_v5(rpc_url: &str, address: Address) -> bool {
+    rpc::call(
+        rpc_url,
+        address,
+        IUpgradeable::UPGRADE_INTERFACE_VERSIONCall {},
+    )
+    .await
+    .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy(path: UpgradePath) -> Proxy {
+        Proxy {
+            address: Address::repeat_byte(1),
+            implementation: Address::repeat_byte(2),
+            path,
+        }
+    }
+
+    #[test]
+    fn test_upgrade_call_picks_function() {
+        let admin = Address::repeat_byte(3);
+        let implementation = Address::repeat_byte(4);
+        let init = Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]);
+
+        for (path, init, to, function) in [
+            (
+                UpgradePath::Uups { v5: false },
+                Bytes::new(),
+                1,
+                "upgradeTo",
+            ),
+            (
+                UpgradePath::Uups { v5: false },
+                init.clone(),
+                1,
+                "upgradeToAndCall",
+            ),
+            (
+                UpgradePath::Uups { v5: true },
+                Bytes::new(),
+                1,
+                "upgradeToAndCall",
+            ),
+            (UpgradePath::AdminWallet, Bytes::new(), 1, "upgradeTo"),
+            (
+                UpgradePath::AdminWallet,
+                init.clone(),
+                1,
+                "upgradeToAndCall",
+            ),
+            (
+                UpgradePath::ProxyAdmin { admin, v5: false },
+                Bytes::new(),
+                3,
+                "upgrade",
+            ),
+            (
+                UpgradePath::ProxyAdmin { admin, v5: true },
+                Bytes::new(),
+                3,
+                "upgradeAndCall",
+            ),
+        ] {
+            let call = upgrade_call(&proxy(path), implementation, init);
+            assert_eq!(call.to, Address::repeat_byte(to), "{:?}", path);
+            assert_eq!(call.function_name, function, "{:?}", path);
+            assert!(call.function_signature.starts_with(function));
+        }
+    }
+
+    #[test]
+    fn test_upgrade_call_encodes_arguments() {
+        let implementation = Address::repeat_byte(4);
+        let call = upgrade_call(
+            &proxy(UpgradePath::Uups { v5: true }),
+            implementation,
+            Bytes::from_static(&[1, 2]),
+        );
+        let decoded = IUpgradeable::upgradeToAndCallCall::abi_decode(&call.data).unwrap();
+        assert_eq!(decoded.newImplementation, implementation);
+        assert_eq!(decoded.data.as_ref(), &[1, 2]);
+    }
+}
@@ -0,0 +1,279 @@
+//! Passphrase-encrypted wallet bundles
+//!
+//! `wallet backup` writes the chosen wallets, with their private keys and
+//! policies, to a bundle sealed with a passphrase (Argon2id and AES-256-GCM),
+//! so they can be moved to another machine without entering every key
+//! again. `wallet restore` checks that each key still derives the address
+//! recorded next to it before adding anything.
+
+use alloy::signers::local::PrivateKeySigner;
+use serde::{Deserialize, Serialize};
+use smolder_core::{
+    decrypt_private_key, encrypt_private_key, open_with_passphrase, seal_with_passphrase, Error,
+    EvmAddress,
+};
+use smolder_db::{NewWallet, Repositories, WalletPolicy};
+
+/// Version of the bundle contents, inside the encryption
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    wallets: Vec<BundledWallet>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundledWallet {
+    name: String,
+    address: EvmAddress,
+    private_key: String,
+    policy: Option<WalletPolicy>,
+}
+
+/// What restoring a wallet from a bundle did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Restore {
+    /// Added, along with its policy
+    Added,
+    /// A wallet by that name with the same address already exists
+    Present,
+    /// A wallet by that name has another address
+    NameTaken { existing: EvmAddress },
+    /// The address belongs to a wallet by another name
+    AddressTaken { existing: String },
+}
+
+/// A wallet from a bundle and what restoring it did
+#[derive(Debug, Serialize)]
+pub struct Restored {
+    pub name: String,
+    pub address: EvmAddress,
+    #[serde(flatten)]
+    pub restore: Restore,
+}
+
+/// Seal the wallets named in `names`, or every wallet when it is empty
+///
+/// Returns the bundle and the names of the wallets in it.
+pub async fn backup(
+    repos: &dyn Repositories,
+    names: &[String],
+    passphrase: &str,
+) -> Result<(Vec<u8>, Vec<String>), Error> {
+    let names = match names.is_empty() {
+        true => repos
+            .wallets()
+            .list()
+            .await?
+            .into_iter()
+            .map(|w| w.name)
+            .collect(),
+        false => names.to_vec(),
+    };
+
+    let mut wallets = Vec::with_capacity(names.len());
+    for name in &names {
+        let wallet = repos
+            .wallets()
+            .get_with_key(name)
+            .await?
+            .ok_or_else(|| Error::WalletNotFound(name.clone()))?;
+        wallets.push(BundledWallet {
+            private_key: decrypt_private_key(&wallet.encrypted_key)?,
+            policy: repos.wallet_policies().get(wallet.id).await?,
+            name: wallet.name,
+            address: wallet.address,
+        });
+    }
+
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        wallets,
+    };
+    let json = serde_json::to_vec(&bundle)?;
+    Ok((seal_with_passphrase(&json, passphrase)?, names))
+}
+
+/// Open a bundle and add its wallets, leaving existing ones alone
+///
+/// Fails without adding anything when the passphrase is wrong or a key
+/// doesn't derive the address recorded with it.
+pub async fn restore(
+    repos: &dyn Repositories,
+    sealed: &[u8],
+    passphrase: &str,
+) -> Result<Vec<Restored>, Error> {
+    let json = open_with_passphrase(sealed, passphrase)?;
+    let bundle: Bundle = serde_json::from_slice(&json)
+        .map_err(|e| Error::Validation(format!("Invalid wallet bundle: {}", e)))?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(Error::Validation(format!(
+            "Wallet bundle version {} needs a newer smolder",
+            bundle.version
+        )));
+    }
+
+    for wallet in &bundle.wallets {
+        let signer: PrivateKeySigner = wallet.private_key.parse().map_err(|e| {
+            Error::Validation(format!("Invalid private key for '{}': {}", wallet.name, e))
+        })?;
+        let derived = EvmAddress::from(signer.address());
+        if derived != wallet.address {
+            return Err(Error::Validation(format!(
+                "Key of wallet '{}' is for {}, not {}",
+                wallet.name, derived, wallet.address
+            )));
+        }
+    }
+
+    let mut restored = Vec::with_capacity(bundle.wallets.len());
+    for wallet in bundle.wallets {
+        let by_name = repos.wallets().get_by_name(&wallet.name).await?;
+        let by_address = repos
+            .wallets()
+            .get_by_address(&wallet.address.to_string())
+            .await?;
+        let restore = match (by_name, by_address) {
+            (Some(existing), _) if existing.address == wallet.address => Restore::Present,
+            (Some(existing), _) => Restore::NameTaken {
+                existing: existing.address,
+            },
+            (None, Some(existing)) => Restore::AddressTaken {
+                existing: existing.name,
+            },
+            (None, None) => {
+                let created = repos
+                    .wallets()
+                    .create(&NewWallet {
+                        name: wallet.name.clone(),
+                        address: wallet.address,
+                        encrypted_key: encrypt_private_key(&wallet.private_key)?,
+                    })
+                    .await?;
+                if let Some(policy) = &wallet.policy {
+                    repos.wallet_policies().set(created.id, policy).await?;
+                }
+                Restore::Added
+            }
+        };
+        restored.push(Restored {
+            name: wallet.name,
+            address: wallet.address,
+            restore,
+        });
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smolder_db::MemoryDatabase;
+
+    const KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcac78d7bbdec4e1d7";
+    const OTHER_KEY: &str = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+
+    async fn add(repos: &MemoryDatabase, name: &str, key: &str) {
+        let signer: PrivateKeySigner = key.parse().unwrap();
+        repos
+            .wallets()
+            .create(&NewWallet {
+                name: name.to_string(),
+                address: EvmAddress::from(signer.address()),
+                encrypted_key: encrypt_private_key(key).unwrap(),
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let old = MemoryDatabase::new();
+        add(&old, "deployer", KEY).await;
+        add(&old, "ops", OTHER_KEY).await;
+        let deployer = old
+            .wallets()
+            .get_by_name("deployer")
+            .await
+            .unwrap()
+            .unwrap();
+        let policy = WalletPolicy {
+            allowed_networks: Some(vec!["sepolia".to_string()]),
+            ..Default::default()
+        };
+        old.wallet_policies()
+            .set(deployer.id, &policy)
+            .await
+            .unwrap();
+
+        let (sealed, names) = backup(&old, &[], "hunter2").await.unwrap();
+        assert_eq!(names, ["deployer", "ops"]);
+        assert!(matches!(
+            backup(&old, &["missing".to_string()], "hunter2").await,
+            Err(Error::WalletNotFound(_))
+        ));
+
+        // The new machine already has "ops", with another key
+        let new = MemoryDatabase::new();
+        add(&new, "ops", KEY).await;
+        assert!(matches!(
+            restore(&new, &sealed, "hunter3").await,
+            Err(Error::Decryption(_))
+        ));
+        let restored = restore(&new, &sealed, "hunter2").await.unwrap();
+        assert_eq!(
+            restored[0].restore,
+            Restore::AddressTaken {
+                existing: "ops".to_string()
+            }
+        );
+        assert_eq!(
+            restored[1].restore,
+            Restore::NameTaken {
+                existing: deployer.address
+            }
+        );
+
+        let new = MemoryDatabase::new();
+        let restored = restore(&new, &sealed, "hunter2").await.unwrap();
+        assert!(restored.iter().all(|r| r.restore == Restore::Added));
+        let wallet = new
+            .wallets()
+            .get_with_key("deployer")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(wallet.address, deployer.address);
+        assert_eq!(decrypt_private_key(&wallet.encrypted_key).unwrap(), KEY);
+        assert_eq!(
+            new.wallet_policies().get(wallet.id).await.unwrap(),
+            Some(policy)
+        );
+        let restored = restore(&new, &sealed, "hunter2").await.unwrap();
+        assert!(restored.iter().all(|r| r.restore == Restore::Present));
+    }
+
+    #[tokio::test]
+    async fn test_restore_verifies_addresses() {
+        let bundle = Bundle {
+            version: BUNDLE_VERSION,
+            wallets: vec![BundledWallet {
+                name: "deployer".to_string(),
+                address: EvmAddress(alloy::primitives::Address::ZERO),
+                private_key: KEY.to_string(),
+                policy: None,
+            }],
+        };
+        let sealed =
+            seal_with_passphrase(&serde_json::to_vec(&bundle).unwrap(), "hunter2").unwrap();
+
+        let repos = MemoryDatabase::new();
+        assert!(matches!(
+            restore(&repos, &sealed, "hunter2").await,
+            Err(Error::Validation(_))
+        ));
+        assert!(repos.wallets().list().await.unwrap().is_empty());
+    }
+}
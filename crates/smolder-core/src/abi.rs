@@ -6,14 +6,21 @@
 //! Also provides utilities for converting between JSON and Solidity types:
 //! - [`json_to_sol_value`] - Convert JSON values to Solidity dynamic values
 //! - [`sol_value_to_json`] - Convert Solidity dynamic values to JSON
-
-use alloy::dyn_abi::{DynSolType, DynSolValue};
-use alloy::json_abi::{Function, JsonAbi, Param, StateMutability as AlloyStateMutability};
-use alloy::primitives::{Bytes, I256, U256};
+//! - [`encode_function_call`] / [`decode_function_result`] - Calldata from JSON
+//!   parameters and return data back to JSON
+//! - [`decode_function_input`] - Calldata back to JSON parameters
+//! - [`encode_constructor_args`] - Constructor arguments from JSON
+//! - [`decode_event_log`] - Log topics and data to JSON
+//! - [`parse_function_signature`] / [`parse_event_signature`] - A [`Function`]
+//!   or [`Event`] from a human-readable signature, like `cast` and ethers accept
+
+use alloy::dyn_abi::{DynSolType, DynSolValue, EventExt, FunctionExt, JsonAbiExt};
+use alloy::json_abi::{Event, Function, JsonAbi, Param, StateMutability as AlloyStateMutability};
+use alloy::primitives::{Bytes, Selector, B256, I256, U256};
 use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
-use crate::types::StateMutability;
+use crate::types::{StateMutability, TokenStandard};
 
 // =============================================================================
 // Abi Struct
@@ -26,19 +33,50 @@ pub struct Abi(JsonAbi);
 
 impl Abi {
     /// Parse a JSON ABI string into an Abi struct
+    ///
+    /// Also accepts a JSON array of human-readable signatures, see
+    /// [`Abi::from_human_readable`].
     pub fn parse(json: &str) -> Result<Self, Error> {
-        let abi: JsonAbi = serde_json::from_str(json)
+        let value: serde_json::Value = serde_json::from_str(json)
             .map_err(|e| Error::AbiParse(format!("Failed to parse ABI: {}", e)))?;
-        Ok(Self(abi))
+        Self::from_value(&value)
     }
 
     /// Parse from a serde_json::Value
     pub fn from_value(value: &serde_json::Value) -> Result<Self, Error> {
+        if let Some(signatures) = human_readable_items(value) {
+            return Self::from_human_readable(signatures);
+        }
+
         let abi: JsonAbi = serde_json::from_value(value.clone())
             .map_err(|e| Error::AbiParse(format!("Failed to parse ABI: {}", e)))?;
         Ok(Self(abi))
     }
 
+    /// Build an ABI from human-readable signatures, one item each, e.g.
+    /// `function transfer(address to, uint256 amount) returns (bool)` or
+    /// `event Transfer(address indexed from, address indexed to, uint256 value)`
+    pub fn from_human_readable<I, S>(signatures: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let signatures: Vec<S> = signatures.into_iter().collect();
+        let abi = JsonAbi::parse(signatures.iter().map(|s| s.as_ref().trim()))
+            .map_err(|e| Error::AbiParse(format!("Failed to parse ABI: {}", e)))?;
+        Ok(Self(abi))
+    }
+
+    /// Serialize back to a JSON ABI string, as stored in the registry
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self.0)?)
+    }
+
+    /// Whether `value` is a human-readable ABI (a non-empty array of strings)
+    pub fn is_human_readable(value: &serde_json::Value) -> bool {
+        human_readable_items(value).is_some()
+    }
+
     /// Get the inner JsonAbi for advanced operations
     pub fn inner(&self) -> &JsonAbi {
         &self.0
@@ -93,6 +131,70 @@ impl Abi {
     pub fn function_overloads(&self, name: &str) -> Option<&Vec<Function>> {
         self.0.functions.get(name)
     }
+
+    /// Find the function whose selector is `selector`
+    pub fn function_by_selector(&self, selector: Selector) -> Option<&Function> {
+        self.0.functions().find(|f| f.selector() == selector)
+    }
+
+    // -------------------------------------------------------------------------
+    // Events
+    // -------------------------------------------------------------------------
+
+    /// Find the non-anonymous event whose selector is `topic0`
+    pub fn event_by_selector(&self, topic0: B256) -> Option<&Event> {
+        self.0
+            .events()
+            .find(|event| !event.anonymous && event.selector() == topic0)
+    }
+
+    // -------------------------------------------------------------------------
+    // Tokens
+    // -------------------------------------------------------------------------
+
+    /// The token standard this ABI implements, judged by its functions
+    ///
+    /// ERC-1155 is checked first since its `balanceOf` takes a token ID, then
+    /// ERC-721, whose `balanceOf(address)` and `approve` overlap with ERC-20.
+    pub fn token_standard(&self) -> Option<TokenStandard> {
+        let has_all = |signatures: &[&str]| signatures.iter().all(|s| self.has_function(s));
+
+        if has_all(&[
+            "balanceOf(address,uint256)",
+            "safeTransferFrom(address,address,uint256,uint256,bytes)",
+            "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+        ]) {
+            Some(TokenStandard::Erc1155)
+        } else if has_all(&[
+            "balanceOf(address)",
+            "ownerOf(uint256)",
+            "safeTransferFrom(address,address,uint256)",
+        ]) {
+            Some(TokenStandard::Erc721)
+        } else if has_all(&[
+            "totalSupply()",
+            "balanceOf(address)",
+            "transfer(address,uint256)",
+            "allowance(address,address)",
+        ]) {
+            Some(TokenStandard::Erc20)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a function with this exact signature, e.g. `transfer(address,uint256)`, exists
+    fn has_function(&self, signature: &str) -> bool {
+        let name = signature.split('(').next().unwrap_or_default();
+        self.function_overloads(name)
+            .is_some_and(|overloads| overloads.iter().any(|f| f.signature() == signature))
+    }
+}
+
+/// The signatures of a human-readable ABI, if `value` is one
+fn human_readable_items(value: &serde_json::Value) -> Option<Vec<&str>> {
+    let items = value.as_array().filter(|items| !items.is_empty())?;
+    items.iter().map(|item| item.as_str()).collect()
 }
 
 // =============================================================================
@@ -101,6 +203,7 @@ impl Abi {
 
 /// Constructor information extracted from ABI
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ConstructorInfo {
     pub inputs: Vec<ParamInfo>,
     pub state_mutability: StateMutability,
@@ -119,6 +222,7 @@ impl ConstructorInfo {
 
 /// Parsed contract functions separated by read/write
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ParsedFunctions {
     pub read: Vec<FunctionInfo>,
     pub write: Vec<FunctionInfo>,
@@ -126,6 +230,7 @@ pub struct ParsedFunctions {
 
 /// Information about a single contract function
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FunctionInfo {
     pub name: String,
     pub signature: String,
@@ -163,10 +268,12 @@ impl FunctionInfo {
 
 /// Information about a function or constructor parameter
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ParamInfo {
     pub name: String,
     pub param_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(no_recursion))]
     pub components: Option<Vec<ParamInfo>>,
 }
 
@@ -348,6 +455,181 @@ pub fn sol_value_to_json(value: &DynSolValue) -> serde_json::Value {
     }
 }
 
+// =============================================================================
+// Function Calls
+// =============================================================================
+
+/// Parse a human-readable function signature, as accepted by `cast`.
+///
+/// Both `balanceOf(address)(uint256)` and
+/// `function balanceOf(address owner) view returns (uint256)` are accepted.
+/// Outputs are optional; without them the call result cannot be decoded.
+pub fn parse_function_signature(signature: &str) -> Result<Function, Error> {
+    Function::parse(signature.trim())
+        .map_err(|e| Error::AbiParse(format!("Invalid function signature '{}': {}", signature, e)))
+}
+
+/// Parse a human-readable event signature, e.g.
+/// `event Transfer(address indexed from, address indexed to, uint256 value)`.
+///
+/// The `event` keyword is optional.
+pub fn parse_event_signature(signature: &str) -> Result<Event, Error> {
+    Event::parse(signature.trim())
+        .map_err(|e| Error::AbiParse(format!("Invalid event signature '{}': {}", signature, e)))
+}
+
+/// ABI-encode a call to `function` (selector included) from JSON parameters
+pub fn encode_function_call(
+    function: &Function,
+    params: &[serde_json::Value],
+) -> Result<Bytes, Error> {
+    if params.len() != function.inputs.len() {
+        return Err(Error::AbiEncode(format!(
+            "Expected {} parameters, got {}",
+            function.inputs.len(),
+            params.len()
+        )));
+    }
+
+    let mut sol_values = Vec::new();
+    for (i, (param, value)) in function.inputs.iter().zip(params.iter()).enumerate() {
+        let sol_value = json_to_sol_value(&param.ty.to_string(), value)
+            .map_err(|e| Error::AbiEncode(format!("Parameter {}: {}", i, e)))?;
+        sol_values.push(sol_value);
+    }
+
+    let encoded = function
+        .abi_encode_input(&sol_values)
+        .map_err(|e| Error::AbiEncode(format!("Failed to encode function call: {}", e)))?;
+
+    Ok(Bytes::from(encoded))
+}
+
+/// ABI-encode constructor arguments from JSON, to be appended to creation bytecode
+pub fn encode_constructor_args(
+    inputs: &[ParamInfo],
+    args: &[serde_json::Value],
+) -> Result<Vec<u8>, Error> {
+    if args.len() != inputs.len() {
+        return Err(Error::AbiEncode(format!(
+            "Expected {} constructor arguments, got {}",
+            inputs.len(),
+            args.len()
+        )));
+    }
+
+    let mut sol_values = Vec::new();
+    for (i, (input, value)) in inputs.iter().zip(args.iter()).enumerate() {
+        let sol_value = json_to_sol_value(&input.param_type, value)
+            .map_err(|e| Error::AbiEncode(format!("Argument {}: {}", i, e)))?;
+        sol_values.push(sol_value);
+    }
+
+    Ok(DynSolValue::Tuple(sol_values).abi_encode_params())
+}
+
+/// Decode calldata (selector included) for `function` into a JSON object
+/// keyed by parameter name
+///
+/// Unnamed parameters are keyed by their position.
+pub fn decode_function_input(
+    function: &Function,
+    calldata: &[u8],
+) -> Result<serde_json::Value, Error> {
+    if calldata.get(..4) != Some(function.selector().as_slice()) {
+        return Err(Error::AbiDecode(format!(
+            "Calldata is not a call to {}",
+            function.signature()
+        )));
+    }
+    let decoded = function.abi_decode_input(&calldata[4..]).map_err(|e| {
+        Error::AbiDecode(format!("Failed to decode {} input: {}", function.name, e))
+    })?;
+
+    let params = function
+        .inputs
+        .iter()
+        .zip(&decoded)
+        .enumerate()
+        .map(|(i, (input, value))| {
+            let key = if input.name.is_empty() {
+                i.to_string()
+            } else {
+                input.name.clone()
+            };
+            (key, sol_value_to_json(value))
+        })
+        .collect();
+
+    Ok(serde_json::Value::Object(params))
+}
+
+/// Decode the return data of `function` into JSON.
+///
+/// A single output is returned as-is, multiple outputs as an array, and no
+/// outputs as `null`.
+pub fn decode_function_result(
+    function: &Function,
+    data: &Bytes,
+) -> Result<serde_json::Value, Error> {
+    if function.outputs.is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+
+    let decoded = function
+        .abi_decode_output(data)
+        .map_err(|e| Error::AbiDecode(format!("Failed to decode result: {}", e)))?;
+
+    let mut result: Vec<serde_json::Value> = decoded.iter().map(sol_value_to_json).collect();
+
+    if result.len() == 1 {
+        Ok(result.remove(0))
+    } else {
+        Ok(serde_json::Value::Array(result))
+    }
+}
+
+// =============================================================================
+// Events
+// =============================================================================
+
+/// Decode a log emitted as `event` into a JSON object keyed by parameter name
+///
+/// Unnamed parameters are keyed by their position.
+pub fn decode_event_log(
+    event: &Event,
+    topics: &[B256],
+    data: &[u8],
+) -> Result<serde_json::Value, Error> {
+    let decoded = event
+        .decode_log_parts(topics.iter().copied(), data)
+        .map_err(|e| Error::AbiDecode(format!("Failed to decode {} log: {}", event.name, e)))?;
+
+    let mut indexed = decoded.indexed.iter();
+    let mut body = decoded.body.iter();
+    let mut params = serde_json::Map::new();
+    for (i, input) in event.inputs.iter().enumerate() {
+        let value = if input.indexed {
+            indexed.next()
+        } else {
+            body.next()
+        };
+        let key = if input.name.is_empty() {
+            i.to_string()
+        } else {
+            input.name.clone()
+        };
+        params.insert(
+            key,
+            value
+                .map(sol_value_to_json)
+                .unwrap_or(serde_json::Value::Null),
+        );
+    }
+
+    Ok(serde_json::Value::Object(params))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -397,6 +679,39 @@ mod tests {
         }
     ]"#;
 
+    #[test]
+    fn test_token_standard() {
+        let erc20 = Abi::from_human_readable([
+            "function totalSupply() view returns (uint256)",
+            "function balanceOf(address) view returns (uint256)",
+            "function transfer(address,uint256) returns (bool)",
+            "function allowance(address,address) view returns (uint256)",
+        ])
+        .unwrap();
+        assert_eq!(erc20.token_standard(), Some(TokenStandard::Erc20));
+
+        let erc721 = Abi::from_human_readable([
+            "function balanceOf(address) view returns (uint256)",
+            "function ownerOf(uint256) view returns (address)",
+            "function safeTransferFrom(address,address,uint256)",
+            "function safeTransferFrom(address,address,uint256,bytes)",
+        ])
+        .unwrap();
+        assert_eq!(erc721.token_standard(), Some(TokenStandard::Erc721));
+
+        let erc1155 = Abi::from_human_readable([
+            "function balanceOf(address,uint256) view returns (uint256)",
+            "function safeTransferFrom(address,address,uint256,uint256,bytes)",
+            "function safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+        ])
+        .unwrap();
+        assert_eq!(erc1155.token_standard(), Some(TokenStandard::Erc1155));
+
+        // balanceOf and transfer alone aren't enough
+        let abi = Abi::parse(TEST_ABI).unwrap();
+        assert_eq!(abi.token_standard(), None);
+    }
+
     #[test]
     fn test_parse_abi() {
         let abi = Abi::parse(TEST_ABI).unwrap();
@@ -507,4 +822,162 @@ mod tests {
         assert_eq!(components[1].name, "enabled");
         assert_eq!(components[1].param_type, "bool");
     }
+
+    #[test]
+    fn test_encode_and_decode_function_call() {
+        let abi = Abi::parse(TEST_ABI).unwrap();
+        let function = abi.function("balanceOf").unwrap();
+
+        let owner = "0x1111111111111111111111111111111111111111";
+        let data = encode_function_call(function, &[serde_json::json!(owner)]).unwrap();
+        assert_eq!(&data[..4], function.selector().as_slice());
+        assert_eq!(data.len(), 4 + 32);
+
+        assert!(encode_function_call(function, &[]).is_err());
+
+        let result =
+            decode_function_result(function, &Bytes::from(U256::from(42).to_be_bytes_vec()))
+                .unwrap();
+        assert_eq!(result, serde_json::json!("42"));
+    }
+
+    #[test]
+    fn test_parse_function_signature() {
+        let short = parse_function_signature("balanceOf(address)(uint256)").unwrap();
+        assert_eq!(short.name, "balanceOf");
+        assert_eq!(short.signature(), "balanceOf(address)");
+        assert_eq!(short.outputs.len(), 1);
+
+        let full = parse_function_signature(
+            "function transfer(address to, uint256 amount) external returns (bool)",
+        )
+        .unwrap();
+        assert_eq!(full.signature(), "transfer(address,uint256)");
+        assert_eq!(full.inputs[1].name, "amount");
+
+        let data = encode_function_call(
+            &short,
+            &[serde_json::json!(
+                "0x0000000000000000000000000000000000000001"
+            )],
+        )
+        .unwrap();
+        assert_eq!(&data[..4], &short.selector()[..]);
+
+        assert!(parse_function_signature("not a signature").is_err());
+    }
+
+    #[test]
+    fn test_parse_event_signature() {
+        let event = parse_event_signature(
+            "event Transfer(address indexed from, address indexed to, uint256 value)",
+        )
+        .unwrap();
+        assert_eq!(event.name, "Transfer");
+        assert_eq!(event.signature(), "Transfer(address,address,uint256)");
+        assert!(event.inputs[0].indexed);
+        assert!(!event.inputs[2].indexed);
+
+        assert_eq!(
+            parse_event_signature("Approval(address indexed, address indexed, uint256)")
+                .unwrap()
+                .selector(),
+            alloy::primitives::keccak256("Approval(address,address,uint256)")
+        );
+    }
+
+    #[test]
+    fn test_decode_event_log() {
+        let abi = Abi::from_human_readable([
+            "event Transfer(address indexed from, address indexed to, uint256 value)",
+        ])
+        .unwrap();
+        let event = abi
+            .event_by_selector(alloy::primitives::keccak256(
+                "Transfer(address,address,uint256)",
+            ))
+            .unwrap();
+
+        let from = alloy::primitives::Address::repeat_byte(0x11);
+        let to = alloy::primitives::Address::repeat_byte(0x22);
+        let topics = [event.selector(), from.into_word(), to.into_word()];
+        let data = U256::from(42).to_be_bytes::<32>();
+
+        let decoded = decode_event_log(event, &topics, &data).unwrap();
+        assert_eq!(decoded["from"], serde_json::json!(from.to_string()));
+        assert_eq!(decoded["to"], serde_json::json!(to.to_string()));
+        assert_eq!(decoded["value"], serde_json::json!("42"));
+
+        assert!(decode_event_log(event, &topics[..1], &data).is_err());
+    }
+
+    #[test]
+    fn test_decode_function_input() {
+        let abi =
+            Abi::from_human_readable(["function transfer(address to, uint256) returns (bool)"])
+                .unwrap();
+        let to = alloy::primitives::Address::repeat_byte(0x22);
+        let function = abi.function("transfer").unwrap();
+        let calldata = encode_function_call(
+            function,
+            &[serde_json::json!(to.to_string()), serde_json::json!("42")],
+        )
+        .unwrap();
+
+        let found = abi.function_by_selector(function.selector()).unwrap();
+        let decoded = decode_function_input(found, &calldata).unwrap();
+        assert_eq!(decoded["to"], serde_json::json!(to.to_string()));
+        assert_eq!(decoded["1"], serde_json::json!("42"));
+
+        assert!(decode_function_input(function, &calldata[..4]).is_err());
+        assert!(decode_function_input(function, &[0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_human_readable_abi() {
+        let abi = Abi::from_human_readable([
+            "constructor(string name, string symbol)",
+            "function balanceOf(address owner) view returns (uint256)",
+            "function transfer(address to, uint256 amount) returns (bool)",
+            "event Transfer(address indexed from, address indexed to, uint256 value)",
+        ])
+        .unwrap();
+
+        let functions = abi.functions();
+        assert_eq!(functions.read.len(), 1);
+        assert_eq!(functions.write.len(), 1);
+        assert_eq!(abi.constructor().unwrap().inputs.len(), 2);
+        assert_eq!(abi.inner().events.len(), 1);
+
+        // A JSON array of signatures round-trips into a JSON ABI
+        let json = r#"["function totalSupply() view returns (uint256)"]"#;
+        assert!(Abi::is_human_readable(&serde_json::from_str(json).unwrap()));
+        let parsed = Abi::parse(json).unwrap();
+        let reparsed = Abi::parse(&parsed.to_json().unwrap()).unwrap();
+        assert!(reparsed.function("totalSupply").is_some());
+
+        // An empty array is a (JSON) ABI with no items
+        assert!(!Abi::is_human_readable(&serde_json::json!([])));
+        assert!(Abi::parse("[]").unwrap().functions().read.is_empty());
+
+        assert!(Abi::from_human_readable(["function ("]).is_err());
+    }
+
+    #[test]
+    fn test_encode_constructor_args() {
+        let abi = Abi::parse(TEST_ABI).unwrap();
+        let constructor = abi.constructor().unwrap();
+
+        let encoded = encode_constructor_args(
+            &constructor.inputs,
+            &[serde_json::json!("Token"), serde_json::json!("TKN")],
+        )
+        .unwrap();
+        // Two string offsets, then length + data for each
+        assert_eq!(encoded.len(), 6 * 32);
+
+        assert!(
+            encode_constructor_args(&constructor.inputs, &[serde_json::json!("Token")]).is_err()
+        );
+    }
 }
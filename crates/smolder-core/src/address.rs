@@ -0,0 +1,202 @@
+//! Addresses as stored in the registry
+//!
+//! Addresses reach the registry from forge, hardhat-deploy, users and RPC
+//! nodes, in whatever casing each one prints. [`EvmAddress`] parses any of
+//! them, is written to the database EIP-55 checksummed and renders checksummed
+//! everywhere, so the same address always looks and compares the same.
+//! Mixed-case input must carry a valid checksum, so a mistyped address isn't
+//! taken for another one.
+
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Type};
+
+use crate::error::Error;
+
+/// A 20-byte address, rendered EIP-55 checksummed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::ToSchema),
+    schema(
+        value_type = String,
+        example = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+    )
+)]
+pub struct EvmAddress(pub Address);
+
+impl EvmAddress {
+    /// Checksum `address` if it parses, or return it unchanged; for columns
+    /// that may hold something other than an address
+    pub fn normalize(address: &str) -> String {
+        address
+            .parse::<Self>()
+            .map(|a| a.to_string())
+            .unwrap_or_else(|_| address.to_string())
+    }
+}
+
+impl fmt::Display for EvmAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.to_checksum(None))
+    }
+}
+
+impl FromStr for EvmAddress {
+    type Err = Error;
+
+    /// Parse a `0x`-prefixed hex address, all lowercase, all uppercase or
+    /// EIP-55 checksummed
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) else {
+            return Err(Error::invalid_param(
+                "address",
+                format!("'{}' is not 0x-prefixed", s),
+            ));
+        };
+        let invalid = |e: alloy::primitives::AddressError| {
+            Error::invalid_param("address", format!("'{}': {}", s, e))
+        };
+        let mixed_case = hex.contains(|c: char| c.is_ascii_lowercase())
+            && hex.contains(|c: char| c.is_ascii_uppercase());
+        match mixed_case {
+            true => Address::parse_checksummed(format!("0x{}", hex), None)
+                .map(Self)
+                .map_err(invalid),
+            false => Address::from_str(&hex.to_ascii_lowercase())
+                .map(Self)
+                .map_err(|e| invalid(e.into())),
+        }
+    }
+}
+
+impl Deref for EvmAddress {
+    type Target = Address;
+
+    fn deref(&self) -> &Address {
+        &self.0
+    }
+}
+
+impl From<Address> for EvmAddress {
+    fn from(address: Address) -> Self {
+        Self(address)
+    }
+}
+
+impl From<EvmAddress> for Address {
+    fn from(address: EvmAddress) -> Self {
+        address.0
+    }
+}
+
+impl PartialEq<str> for EvmAddress {
+    /// Compare with an address string, ignoring case
+    fn eq(&self, other: &str) -> bool {
+        other.parse::<Self>().is_ok_and(|other| other == *self)
+    }
+}
+
+impl PartialEq<&str> for EvmAddress {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl Serialize for EvmAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EvmAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Type<Sqlite> for EvmAddress {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <String as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for EvmAddress {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> Result<IsNull, BoxDynError> {
+        <String as Encode<Sqlite>>::encode(self.to_string(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for EvmAddress {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let s = <&str as Decode<Sqlite>>::decode(value)?;
+        Ok(s.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKSUMMED: &str = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+
+    #[test]
+    fn test_parse_any_case() {
+        let lower: EvmAddress = CHECKSUMMED.to_lowercase().parse().unwrap();
+        let upper: EvmAddress = format!("0x{}", CHECKSUMMED[2..].to_uppercase())
+            .parse()
+            .unwrap();
+        assert_eq!(lower, upper);
+        assert_eq!(lower.to_string(), CHECKSUMMED);
+        assert!(lower == *CHECKSUMMED.to_lowercase().as_str());
+
+        assert!("5FbDB2315678afecb367f032d93F642f64180aa3"
+            .parse::<EvmAddress>()
+            .is_err());
+        assert!("0x5FbDB2315678".parse::<EvmAddress>().is_err());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let checksummed: EvmAddress = CHECKSUMMED.parse().unwrap();
+        let lower: EvmAddress = CHECKSUMMED.to_lowercase().parse().unwrap();
+        assert_eq!(checksummed, lower);
+
+        // One letter's case flipped
+        let mistyped = CHECKSUMMED.replacen("FbDB", "fbDB", 1);
+        let err = mistyped.parse::<EvmAddress>().unwrap_err();
+        assert!(err.to_string().contains("checksum"), "{}", err);
+    }
+
+    #[test]
+    fn test_serde() {
+        let address: EvmAddress =
+            serde_json::from_str(&format!("\"{}\"", CHECKSUMMED.to_lowercase())).unwrap();
+        assert_eq!(
+            serde_json::to_string(&address).unwrap(),
+            format!("\"{}\"", CHECKSUMMED)
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            EvmAddress::normalize(&CHECKSUMMED.to_lowercase()),
+            CHECKSUMMED
+        );
+        assert_eq!(EvmAddress::normalize(""), "");
+    }
+}
@@ -60,6 +60,67 @@ impl Bytecode {
     }
 }
 
+/// EIP-170 limit on deployed (runtime) code size, in bytes
+pub const MAX_RUNTIME_SIZE: usize = 24_576;
+
+/// EIP-3860 limit on init code size, in bytes
+pub const MAX_INITCODE_SIZE: usize = 49_152;
+
+/// Share of a limit, in percent, from which a size is reported as close to it
+pub const SIZE_WARNING_PERCENT: usize = 90;
+
+/// Init code and runtime code sizes of a compiled contract, in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CodeSize {
+    pub initcode: usize,
+    pub runtime: usize,
+}
+
+impl CodeSize {
+    /// Measure hex bytecode from an artifact
+    ///
+    /// Sizes are taken from the hex length, so bytecode with unlinked library
+    /// placeholders is measured too.
+    pub fn from_hex(initcode: &str, runtime: &str) -> Self {
+        let size = |hex: &str| hex.trim().trim_start_matches("0x").len() / 2;
+        Self {
+            initcode: size(initcode),
+            runtime: size(runtime),
+        }
+    }
+
+    /// Warnings for sizes over or close to their EIP limits
+    pub fn warnings(&self) -> Vec<String> {
+        [
+            size_warning("Runtime code", self.runtime, MAX_RUNTIME_SIZE, "EIP-170"),
+            size_warning("Init code", self.initcode, MAX_INITCODE_SIZE, "EIP-3860"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+fn size_warning(what: &str, size: usize, limit: usize, eip: &str) -> Option<String> {
+    if size > limit {
+        Some(format!(
+            "{} is {} bytes, over the {}-byte {} limit; the deployment will fail",
+            what, size, limit, eip
+        ))
+    } else if size * 100 >= limit * SIZE_WARNING_PERCENT {
+        Some(format!(
+            "{} is {} bytes, {}% of the {}-byte {} limit",
+            what,
+            size,
+            size * 100 / limit,
+            limit,
+            eip
+        ))
+    } else {
+        None
+    }
+}
+
 /// Check if a hex string represents valid bytecode (non-empty and decodable)
 pub fn is_valid_bytecode(hex: &str) -> bool {
     let clean = hex.trim_start_matches("0x");
@@ -141,6 +202,37 @@ mod tests {
         assert_eq!(parse_hex_block_number("0xff").unwrap(), 255);
     }
 
+    #[test]
+    fn test_code_size() {
+        let size = CodeSize::from_hex("0x6080604052", "6080");
+        assert_eq!(
+            size,
+            CodeSize {
+                initcode: 5,
+                runtime: 2
+            }
+        );
+        assert!(size.warnings().is_empty());
+
+        let near = CodeSize {
+            initcode: 100,
+            runtime: 23_000,
+        };
+        assert_eq!(
+            near.warnings(),
+            vec!["Runtime code is 23000 bytes, 93% of the 24576-byte EIP-170 limit"]
+        );
+
+        let over = CodeSize {
+            initcode: 50_000,
+            runtime: 25_000,
+        };
+        let warnings = over.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("over the 24576-byte EIP-170 limit"));
+        assert!(warnings[1].starts_with("Init code is 50000 bytes, over"));
+    }
+
     #[test]
     fn test_parse_hex_block_number_invalid() {
         assert!(parse_hex_block_number("not_hex").is_err());
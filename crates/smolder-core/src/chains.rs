@@ -0,0 +1,278 @@
+//! Well-known chains, after chainlist.org
+//!
+//! `network add` looks chains up here by ID or name to fill in the explorer
+//! and, with `--public-rpc`, a public RPC endpoint, so common networks don't
+//! need their URLs typed by hand. New networks take their native currency
+//! from here.
+
+use crate::units::Currency;
+
+const ETHER: Currency<'static> = Currency::ETHER;
+
+/// A chain with its public endpoints
+#[derive(Debug, Clone, Copy)]
+pub struct KnownChain {
+    /// Network name used when none is given
+    pub name: &'static str,
+    /// Other names the chain is looked up by
+    pub aliases: &'static [&'static str],
+    pub chain_id: u64,
+    /// Public RPC endpoints, most reliable first
+    pub rpc_urls: &'static [&'static str],
+    pub explorer_url: Option<&'static str>,
+    pub currency: Currency<'static>,
+    /// Test or local chain, whose currency has no market value
+    pub testnet: bool,
+}
+
+pub const CHAINS: &[KnownChain] = &[
+    KnownChain {
+        name: "mainnet",
+        aliases: &["ethereum", "eth"],
+        chain_id: 1,
+        rpc_urls: &[
+            "https://ethereum-rpc.publicnode.com",
+            "https://eth.llamarpc.com",
+            "https://rpc.ankr.com/eth",
+        ],
+        explorer_url: Some("https://etherscan.io"),
+        currency: ETHER,
+        testnet: false,
+    },
+    KnownChain {
+        name: "sepolia",
+        aliases: &[],
+        chain_id: 11155111,
+        rpc_urls: &[
+            "https://ethereum-sepolia-rpc.publicnode.com",
+            "https://rpc.sepolia.org",
+        ],
+        explorer_url: Some("https://sepolia.etherscan.io"),
+        currency: ETHER,
+        testnet: true,
+    },
+    KnownChain {
+        name: "holesky",
+        aliases: &[],
+        chain_id: 17000,
+        rpc_urls: &["https://ethereum-holesky-rpc.publicnode.com"],
+        explorer_url: Some("https://holesky.etherscan.io"),
+        currency: ETHER,
+        testnet: true,
+    },
+    KnownChain {
+        name: "optimism",
+        aliases: &["op", "op-mainnet"],
+        chain_id: 10,
+        rpc_urls: &[
+            "https://mainnet.optimism.io",
+            "https://optimism-rpc.publicnode.com",
+        ],
+        explorer_url: Some("https://optimistic.etherscan.io"),
+        currency: ETHER,
+        testnet: false,
+    },
+    KnownChain {
+        name: "optimism-sepolia",
+        aliases: &["op-sepolia"],
+        chain_id: 11155420,
+        rpc_urls: &["https://sepolia.optimism.io"],
+        explorer_url: Some("https://sepolia-optimism.etherscan.io"),
+        currency: ETHER,
+        testnet: true,
+    },
+    KnownChain {
+        name: "base",
+        aliases: &[],
+        chain_id: 8453,
+        rpc_urls: &[
+            "https://mainnet.base.org",
+            "https://base-rpc.publicnode.com",
+        ],
+        explorer_url: Some("https://basescan.org"),
+        currency: ETHER,
+        testnet: false,
+    },
+    KnownChain {
+        name: "base-sepolia",
+        aliases: &[],
+        chain_id: 84532,
+        rpc_urls: &["https://sepolia.base.org"],
+        explorer_url: Some("https://sepolia.basescan.org"),
+        currency: ETHER,
+        testnet: true,
+    },
+    KnownChain {
+        name: "arbitrum",
+        aliases: &["arbitrum-one", "arb"],
+        chain_id: 42161,
+        rpc_urls: &[
+            "https://arb1.arbitrum.io/rpc",
+            "https://arbitrum-one-rpc.publicnode.com",
+        ],
+        explorer_url: Some("https://arbiscan.io"),
+        currency: ETHER,
+        testnet: false,
+    },
+    KnownChain {
+        name: "arbitrum-sepolia",
+        aliases: &["arb-sepolia"],
+        chain_id: 421614,
+        rpc_urls: &["https://sepolia-rollup.arbitrum.io/rpc"],
+        explorer_url: Some("https://sepolia.arbiscan.io"),
+        currency: ETHER,
+        testnet: true,
+    },
+    KnownChain {
+        name: "polygon",
+        aliases: &["matic"],
+        chain_id: 137,
+        rpc_urls: &[
+            "https://polygon-rpc.com",
+            "https://polygon-bor-rpc.publicnode.com",
+        ],
+        explorer_url: Some("https://polygonscan.com"),
+        currency: Currency {
+            symbol: "POL",
+            decimals: 18,
+        },
+        testnet: false,
+    },
+    KnownChain {
+        name: "polygon-amoy",
+        aliases: &["amoy"],
+        chain_id: 80002,
+        rpc_urls: &["https://rpc-amoy.polygon.technology"],
+        explorer_url: Some("https://amoy.polygonscan.com"),
+        currency: Currency {
+            symbol: "POL",
+            decimals: 18,
+        },
+        testnet: true,
+    },
+    KnownChain {
+        name: "bsc",
+        aliases: &["bnb", "binance"],
+        chain_id: 56,
+        rpc_urls: &[
+            "https://bsc-dataseed.bnbchain.org",
+            "https://bsc-rpc.publicnode.com",
+        ],
+        explorer_url: Some("https://bscscan.com"),
+        currency: Currency {
+            symbol: "BNB",
+            decimals: 18,
+        },
+        testnet: false,
+    },
+    KnownChain {
+        name: "avalanche",
+        aliases: &["avax"],
+        chain_id: 43114,
+        rpc_urls: &["https://api.avax.network/ext/bc/C/rpc"],
+        explorer_url: Some("https://snowtrace.io"),
+        currency: Currency {
+            symbol: "AVAX",
+            decimals: 18,
+        },
+        testnet: false,
+    },
+    KnownChain {
+        name: "gnosis",
+        aliases: &["xdai"],
+        chain_id: 100,
+        rpc_urls: &["https://rpc.gnosischain.com"],
+        explorer_url: Some("https://gnosisscan.io"),
+        currency: Currency {
+            symbol: "XDAI",
+            decimals: 18,
+        },
+        testnet: false,
+    },
+    KnownChain {
+        name: "linea",
+        aliases: &[],
+        chain_id: 59144,
+        rpc_urls: &["https://rpc.linea.build"],
+        explorer_url: Some("https://lineascan.build"),
+        currency: ETHER,
+        testnet: false,
+    },
+    KnownChain {
+        name: "scroll",
+        aliases: &[],
+        chain_id: 534352,
+        rpc_urls: &["https://rpc.scroll.io"],
+        explorer_url: Some("https://scrollscan.com"),
+        currency: ETHER,
+        testnet: false,
+    },
+    KnownChain {
+        name: "zksync",
+        aliases: &["zksync-era"],
+        chain_id: 324,
+        rpc_urls: &["https://mainnet.era.zksync.io"],
+        explorer_url: Some("https://era.zksync.network"),
+        currency: ETHER,
+        testnet: false,
+    },
+    KnownChain {
+        name: "anvil",
+        aliases: &["localhost"],
+        chain_id: 31337,
+        rpc_urls: &["http://127.0.0.1:8545"],
+        explorer_url: None,
+        currency: ETHER,
+        testnet: true,
+    },
+];
+
+/// Native currency of `chain_id`, ETH for chains not listed
+pub fn currency(chain_id: u64) -> Currency<'static> {
+    by_chain_id(chain_id).map_or(ETHER, |c| c.currency)
+}
+
+/// Whether `chain_id` is a known test or local chain
+pub fn is_testnet(chain_id: u64) -> bool {
+    by_chain_id(chain_id).is_some_and(|c| c.testnet)
+}
+
+/// Look up a chain by ID
+pub fn by_chain_id(chain_id: u64) -> Option<&'static KnownChain> {
+    CHAINS.iter().find(|c| c.chain_id == chain_id)
+}
+
+/// Look up a chain by name or alias, ignoring case
+pub fn by_name(name: &str) -> Option<&'static KnownChain> {
+    let name = name.to_lowercase();
+    CHAINS
+        .iter()
+        .find(|c| c.name == name || c.aliases.contains(&name.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(by_chain_id(10).unwrap().name, "optimism");
+        assert_eq!(by_name("OP").unwrap().chain_id, 10);
+        assert_eq!(by_name("polygon").unwrap().currency.symbol, "POL");
+        assert!(by_chain_id(424242).is_none());
+        assert!(is_testnet(11155111) && is_testnet(31337));
+        assert!(!is_testnet(1) && !is_testnet(424242));
+        assert!(by_name("unknown").is_none());
+    }
+
+    #[test]
+    fn test_chains_are_unique() {
+        for (i, chain) in CHAINS.iter().enumerate() {
+            assert!(!chain.rpc_urls.is_empty(), "{} has no RPC", chain.name);
+            for other in &CHAINS[i + 1..] {
+                assert_ne!(chain.chain_id, other.chain_id);
+                assert!(!other.aliases.contains(&chain.name) && other.name != chain.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Compiler version and settings of a contract build
+//!
+//! solc records the compiler version and the settings a contract was built
+//! with in its metadata, which forge keeps under `metadata` in each artifact
+//! and hardhat-deploy as a JSON string in its deployment files. They are what
+//! a block explorer needs to reproduce the bytecode when verifying.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Compiler version and the settings that change the bytecode
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CompilerSettings {
+    /// Full solc version, e.g. `0.8.24+commit.e11b9ed9`
+    pub version: String,
+    pub optimizer_enabled: bool,
+    /// Optimizer runs, when the metadata records them
+    pub optimizer_runs: Option<u64>,
+    /// Target EVM version, e.g. `cancun`
+    pub evm_version: Option<String>,
+    /// Compiled through the IR pipeline (`via_ir`)
+    #[serde(default)]
+    pub via_ir: bool,
+    /// Linked library addresses, by `path:Name`
+    #[serde(default)]
+    pub libraries: BTreeMap<String, String>,
+}
+
+impl CompilerSettings {
+    /// Read the settings from solc metadata, given as an object or as the
+    /// JSON string solc emits; `None` without a compiler version
+    pub fn from_metadata(metadata: &Value) -> Option<Self> {
+        let parsed;
+        let metadata = match metadata {
+            Value::String(raw) => {
+                parsed = serde_json::from_str::<Value>(raw).ok()?;
+                &parsed
+            }
+            value => value,
+        };
+
+        let version = metadata.pointer("/compiler/version")?.as_str()?.to_string();
+        let settings = metadata.get("settings");
+        let setting = |pointer: &str| settings.and_then(|s| s.pointer(pointer));
+
+        // Flat (`path:Name`) in metadata, nested by path in standard JSON input
+        let mut libraries = BTreeMap::new();
+        if let Some(Value::Object(entries)) = setting("/libraries") {
+            for (key, value) in entries {
+                match value {
+                    Value::String(address) => {
+                        libraries.insert(key.clone(), address.clone());
+                    }
+                    Value::Object(names) => {
+                        for (name, address) in names {
+                            if let Some(address) = address.as_str() {
+                                libraries.insert(format!("{}:{}", key, name), address.to_string());
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Some(Self {
+            version,
+            optimizer_enabled: setting("/optimizer/enabled")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            optimizer_runs: setting("/optimizer/runs").and_then(Value::as_u64),
+            evm_version: setting("/evmVersion")
+                .and_then(Value::as_str)
+                .map(String::from),
+            via_ir: setting("/viaIR").and_then(Value::as_bool).unwrap_or(false),
+            libraries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_metadata() {
+        let metadata = json!({
+            "compiler": { "version": "0.8.24+commit.e11b9ed9" },
+            "language": "Solidity",
+            "settings": {
+                "evmVersion": "cancun",
+                "libraries": { "src/Math.sol:Math": "0x1111111111111111111111111111111111111111" },
+                "optimizer": { "enabled": true, "runs": 10000 },
+                "viaIR": true
+            }
+        });
+
+        let settings = CompilerSettings::from_metadata(&metadata).unwrap();
+        assert_eq!(settings.version, "0.8.24+commit.e11b9ed9");
+        assert!(settings.optimizer_enabled);
+        assert_eq!(settings.optimizer_runs, Some(10000));
+        assert_eq!(settings.evm_version.as_deref(), Some("cancun"));
+        assert!(settings.via_ir);
+        assert_eq!(
+            settings.libraries["src/Math.sol:Math"],
+            "0x1111111111111111111111111111111111111111"
+        );
+
+        // hardhat-deploy stores the metadata as a string
+        let raw = Value::String(metadata.to_string());
+        assert_eq!(CompilerSettings::from_metadata(&raw), Some(settings));
+    }
+
+    #[test]
+    fn test_from_metadata_nested_libraries() {
+        let metadata = json!({
+            "compiler": { "version": "0.8.20+commit.a1b79de6" },
+            "settings": {
+                "libraries": { "src/Math.sol": { "Math": "0x2222222222222222222222222222222222222222" } }
+            }
+        });
+
+        let settings = CompilerSettings::from_metadata(&metadata).unwrap();
+        assert!(!settings.optimizer_enabled);
+        assert_eq!(settings.optimizer_runs, None);
+        assert_eq!(settings.libraries.len(), 1);
+        assert!(settings.libraries.contains_key("src/Math.sol:Math"));
+    }
+
+    #[test]
+    fn test_from_metadata_without_version() {
+        assert!(CompilerSettings::from_metadata(&json!({ "settings": {} })).is_none());
+        assert!(CompilerSettings::from_metadata(&json!("not json")).is_none());
+    }
+}
@@ -0,0 +1,84 @@
+//! ENS name hashing
+//!
+//! Names are only lowercased before hashing, which covers the ASCII names
+//! most projects use; full ENSIP-15 normalization is not applied.
+
+use alloy::primitives::{keccak256, Address, B256};
+
+/// The ENS registry, deployed at the same address on mainnet and testnets
+pub const ENS_REGISTRY: Address =
+    alloy::primitives::address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+/// Lowercase and trim a name before hashing or caching it
+pub fn normalize_name(name: &str) -> String {
+    name.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// Whether `value` looks like an ENS name rather than a hex address
+pub fn is_ens_name(value: &str) -> bool {
+    let value = value.trim();
+    !value.starts_with("0x")
+        && value.contains('.')
+        && !value.contains(char::is_whitespace)
+        && value.split('.').all(|label| !label.is_empty())
+}
+
+/// The EIP-137 namehash of `name`
+pub fn namehash(name: &str) -> B256 {
+    let name = normalize_name(name);
+    if name.is_empty() {
+        return B256::ZERO;
+    }
+
+    name.rsplit('.').fold(B256::ZERO, |node, label| {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(node.as_slice());
+        buf[32..].copy_from_slice(keccak256(label.as_bytes()).as_slice());
+        keccak256(buf)
+    })
+}
+
+/// The reverse record name of `address`, `<hex>.addr.reverse`
+pub fn reverse_name(address: Address) -> String {
+    format!("{}.addr.reverse", hex::encode(address.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namehash() {
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(
+            namehash("eth").to_string(),
+            "0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+        );
+        assert_eq!(
+            namehash("foo.eth").to_string(),
+            "0xde9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+        );
+        assert_eq!(namehash("Foo.ETH"), namehash("foo.eth"));
+    }
+
+    #[test]
+    fn test_is_ens_name() {
+        assert!(is_ens_name("vitalik.eth"));
+        assert!(is_ens_name("pay.team.eth"));
+        assert!(!is_ens_name("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"));
+        assert!(!is_ens_name("eth"));
+        assert!(!is_ens_name("foo..eth"));
+        assert!(!is_ens_name("not a name.eth"));
+    }
+
+    #[test]
+    fn test_reverse_name() {
+        let address: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            reverse_name(address),
+            "d8da6bf26964af9d7eed9e03e53415d37aa96045.addr.reverse"
+        );
+    }
+}
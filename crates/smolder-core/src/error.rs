@@ -1,4 +1,5 @@
-use crate::types::DeploymentId;
+use crate::secret::scrub;
+use crate::types::{ApprovalId, DeploymentGroupId, DeploymentId, WebhookId};
 use thiserror::Error;
 
 /// Result type alias using the crate's Error type
@@ -33,15 +34,30 @@ pub enum Error {
     #[error("Deployment not found by ID: {0}")]
     DeploymentNotFoundById(DeploymentId),
 
+    #[error("Deployment group not found: {0}")]
+    DeploymentGroupNotFound(DeploymentGroupId),
+
     #[error("Wallet not found: {0}")]
     WalletNotFound(String),
 
+    #[error("Webhook not found: {0}")]
+    WebhookNotFound(WebhookId),
+
     #[error("Function '{function}' not found in contract '{contract}'")]
     FunctionNotFound { contract: String, function: String },
 
     #[error("Artifact not found: {0}")]
     ArtifactNotFound(String),
 
+    #[error("API token not found: {0}")]
+    ApiTokenNotFound(String),
+
+    #[error("Approval not found: {0}")]
+    ApprovalNotFound(ApprovalId),
+
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+
     // =========================================================================
     // ABI errors
     // =========================================================================
@@ -56,14 +72,17 @@ pub enum Error {
 
     // =========================================================================
     // RPC/Chain errors
+    //
+    // Node errors quote the URL they failed on, so they are scrubbed of
+    // credentials when shown
     // =========================================================================
-    #[error("RPC error: {0}")]
+    #[error("RPC error: {}", scrub(.0))]
     Rpc(String),
 
-    #[error("RPC error on chain {chain_id}: {message}")]
+    #[error("RPC error on chain {chain_id}: {}", scrub(.message))]
     RpcWithChain { chain_id: u64, message: String },
 
-    #[error("Transaction failed: {0}")]
+    #[error("Transaction failed: {}", scrub(.0))]
     TransactionFailed(String),
 
     #[error("Transaction reverted: {reason}")]
@@ -72,6 +91,28 @@ pub enum Error {
         tx_hash: Option<String>,
     },
 
+    #[error("The RPC of '{network}' serves chain {actual}, but the network is registered with chain ID {expected}")]
+    ChainIdMismatch {
+        network: String,
+        expected: u64,
+        actual: u64,
+    },
+
+    #[error("Network '{0}' is locked; confirm it by name to deploy or send on it")]
+    NetworkLocked(String),
+
+    #[error("Policy of wallet '{wallet}' forbids this: {reason}")]
+    PolicyViolation { wallet: String, reason: String },
+
+    #[error(
+        "Insufficient funds: {address} has {balance}, needs about {required} for gas and value"
+    )]
+    InsufficientFunds {
+        address: String,
+        balance: String,
+        required: String,
+    },
+
     // =========================================================================
     // Validation errors
     // =========================================================================
@@ -81,6 +122,14 @@ pub enum Error {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("{entity} '{name}' changed since it was read: version {actual}, not {expected}")]
+    VersionMismatch {
+        entity: &'static str,
+        name: String,
+        expected: i64,
+        actual: i64,
+    },
+
     // =========================================================================
     // Cryptography errors
     // =========================================================================
@@ -121,9 +170,14 @@ impl Error {
                 | Error::ContractNotFound(_)
                 | Error::DeploymentNotFound(_)
                 | Error::DeploymentNotFoundById(_)
+                | Error::DeploymentGroupNotFound(_)
                 | Error::WalletNotFound(_)
+                | Error::WebhookNotFound(_)
                 | Error::FunctionNotFound { .. }
                 | Error::ArtifactNotFound(_)
+                | Error::ApiTokenNotFound(_)
+                | Error::ApprovalNotFound(_)
+                | Error::TemplateNotFound(_)
                 | Error::FileNotFound(_)
         )
     }
@@ -149,17 +203,27 @@ impl Error {
             Error::DeploymentNotFound(_) | Error::DeploymentNotFoundById(_) => {
                 "DEPLOYMENT_NOT_FOUND"
             }
+            Error::DeploymentGroupNotFound(_) => "DEPLOYMENT_GROUP_NOT_FOUND",
             Error::WalletNotFound(_) => "WALLET_NOT_FOUND",
+            Error::WebhookNotFound(_) => "WEBHOOK_NOT_FOUND",
             Error::FunctionNotFound { .. } => "FUNCTION_NOT_FOUND",
             Error::ArtifactNotFound(_) => "ARTIFACT_NOT_FOUND",
+            Error::ApiTokenNotFound(_) => "API_TOKEN_NOT_FOUND",
+            Error::ApprovalNotFound(_) => "APPROVAL_NOT_FOUND",
+            Error::TemplateNotFound(_) => "TEMPLATE_NOT_FOUND",
             Error::AbiParse(_) => "ABI_PARSE_ERROR",
             Error::AbiEncode(_) => "ABI_ENCODE_ERROR",
             Error::AbiDecode(_) => "ABI_DECODE_ERROR",
             Error::Rpc(_) | Error::RpcWithChain { .. } => "RPC_ERROR",
             Error::TransactionFailed(_) => "TRANSACTION_FAILED",
             Error::TransactionReverted { .. } => "TRANSACTION_REVERTED",
+            Error::ChainIdMismatch { .. } => "CHAIN_ID_MISMATCH",
+            Error::NetworkLocked(_) => "NETWORK_LOCKED",
+            Error::PolicyViolation { .. } => "POLICY_VIOLATION",
+            Error::InsufficientFunds { .. } => "INSUFFICIENT_FUNDS",
             Error::InvalidParameter { .. } => "INVALID_PARAMETER",
             Error::Validation(_) => "VALIDATION_ERROR",
+            Error::VersionMismatch { .. } => "VERSION_MISMATCH",
             Error::Keyring(_) => "KEYRING_ERROR",
             Error::Encryption(_) => "ENCRYPTION_ERROR",
             Error::Decryption(_) => "DECRYPTION_ERROR",
@@ -0,0 +1,109 @@
+//! Block explorer links
+//!
+//! A network's explorer URL is either a base URL, which gets Etherscan-style
+//! `/address/<address>` and `/tx/<hash>` paths appended, or a template with
+//! `{kind}` and `{id}` placeholders for explorers laid out differently, e.g.
+//! `https://explorer.example.com/#/{kind}/{id}`. `{kind}` becomes `address`
+//! or `tx`.
+//!
+//! Etherscan API URLs, as written in foundry.toml's `[etherscan]` section, are
+//! mapped to the explorer site: `https://api-sepolia.etherscan.io/api` links
+//! to `https://sepolia.etherscan.io`.
+
+/// What an explorer page shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplorerPage {
+    Address,
+    Tx,
+}
+
+impl ExplorerPage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExplorerPage::Address => "address",
+            ExplorerPage::Tx => "tx",
+        }
+    }
+}
+
+/// Link to `id` on the explorer at `explorer_url`
+pub fn explorer_link(explorer_url: &str, page: ExplorerPage, id: &str) -> String {
+    if explorer_url.contains("{id}") {
+        return explorer_url
+            .replace("{kind}", page.as_str())
+            .replace("{id}", id);
+    }
+    format!("{}/{}/{}", site_url(explorer_url), page.as_str(), id)
+}
+
+/// Link to an address page
+pub fn address_link(explorer_url: &str, address: &str) -> String {
+    explorer_link(explorer_url, ExplorerPage::Address, address)
+}
+
+/// Link to a transaction page
+pub fn tx_link(explorer_url: &str, tx_hash: &str) -> String {
+    explorer_link(explorer_url, ExplorerPage::Tx, tx_hash)
+}
+
+/// Strip trailing slashes and turn Etherscan-style API URLs into the site URL
+fn site_url(explorer_url: &str) -> String {
+    let url = explorer_url.trim_end_matches('/');
+    let url = url.strip_suffix("/api").unwrap_or(url);
+
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let host = rest
+        .strip_prefix("api.")
+        .or_else(|| rest.strip_prefix("api-"))
+        .unwrap_or(rest);
+    format!("{}://{}", scheme, host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+
+    #[test]
+    fn test_base_url() {
+        assert_eq!(
+            address_link("https://etherscan.io", ADDRESS),
+            format!("https://etherscan.io/address/{}", ADDRESS)
+        );
+        assert_eq!(
+            tx_link("https://explorer.test.xyz/", "0xabc"),
+            "https://explorer.test.xyz/tx/0xabc"
+        );
+    }
+
+    #[test]
+    fn test_api_url() {
+        assert_eq!(
+            tx_link("https://api.etherscan.io/api", "0xabc"),
+            "https://etherscan.io/tx/0xabc"
+        );
+        assert_eq!(
+            tx_link("https://api-sepolia.etherscan.io/api/", "0xabc"),
+            "https://sepolia.etherscan.io/tx/0xabc"
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let template = "https://explorer.example.com/#/{kind}/{id}?network=main";
+        assert_eq!(
+            address_link(template, ADDRESS),
+            format!(
+                "https://explorer.example.com/#/address/{}?network=main",
+                ADDRESS
+            )
+        );
+        assert_eq!(
+            tx_link(template, "0xabc"),
+            "https://explorer.example.com/#/tx/0xabc?network=main"
+        );
+    }
+}
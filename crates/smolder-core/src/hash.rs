@@ -0,0 +1,241 @@
+//! Transaction and bytecode hashes as stored in the registry
+//!
+//! Both are 32-byte keccak hashes, written as `0x` and 64 lowercase hex
+//! digits. Parsing rejects anything else, so a malformed hash from a manual
+//! import or a hand-edited deployment file fails where it is read instead of
+//! ending up in the registry.
+
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use alloy::primitives::{keccak256, B256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Type};
+
+use crate::error::{Error, Result};
+
+/// Parse `0x` followed by exactly 64 hex digits, in any case
+fn parse_hash(param: &str, s: &str) -> Result<B256> {
+    let s = s.trim();
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .ok_or_else(|| Error::invalid_param(param, format!("'{}' is not 0x-prefixed", s)))?;
+    if digits.len() != 64 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::invalid_param(
+            param,
+            format!("'{}' is not 32 bytes of hex", s),
+        ));
+    }
+    B256::from_str(digits).map_err(|e| Error::invalid_param(param, format!("'{}': {}", s, e)))
+}
+
+macro_rules! hash_type {
+    ($(#[$meta:meta])* $name:ident, $param:literal, $example:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(
+            feature = "openapi",
+            derive(utoipa::ToSchema),
+            schema(value_type = String, example = $example)
+        )]
+        pub struct $name(pub B256);
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{:#x}", self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self> {
+                parse_hash($param, s).map(Self)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = B256;
+
+            fn deref(&self) -> &B256 {
+                &self.0
+            }
+        }
+
+        impl From<B256> for $name {
+            fn from(hash: B256) -> Self {
+                Self(hash)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl Type<Sqlite> for $name {
+            fn type_info() -> SqliteTypeInfo {
+                <String as Type<Sqlite>>::type_info()
+            }
+
+            fn compatible(ty: &SqliteTypeInfo) -> bool {
+                <String as Type<Sqlite>>::compatible(ty)
+            }
+        }
+
+        impl<'q> Encode<'q, Sqlite> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut Vec<SqliteArgumentValue<'q>>,
+            ) -> std::result::Result<IsNull, BoxDynError> {
+                <String as Encode<Sqlite>>::encode(self.to_string(), buf)
+            }
+        }
+
+        impl<'r> Decode<'r, Sqlite> for $name {
+            fn decode(value: SqliteValueRef<'r>) -> std::result::Result<Self, BoxDynError> {
+                let s = <&str as Decode<Sqlite>>::decode(value)?;
+                Ok(s.parse()?)
+            }
+        }
+    };
+}
+
+hash_type!(
+    /// Hash of a transaction
+    TxHash,
+    "tx_hash",
+    "0x4f1bd8d1c7ab0c9c1f2c5e0d4b8a3f1e2d6c7b8a9f0e1d2c3b4a5968778695a4"
+);
+
+hash_type!(
+    /// keccak256 of a contract's creation bytecode, identifying a build
+    BytecodeHash,
+    "bytecode_hash",
+    "0x9c22ff5f21f0b81b113e63f7db6da94fedef11b2119b4088b89664fb9a3cb658"
+);
+
+impl BytecodeHash {
+    /// Hash `bytecode`
+    pub fn of(bytecode: impl AsRef<[u8]>) -> Self {
+        Self(keccak256(bytecode))
+    }
+}
+
+/// The `tx_hash` column of `deployments`, where a deployment recorded
+/// without its transaction (an import, a hardhat deployment without a
+/// receipt) has an empty string
+///
+/// Read into `Option<TxHash>` with `#[sqlx(try_from = "StoredTxHash")]`, and
+/// bound with `StoredTxHash::from`.
+#[derive(Debug, Clone, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct StoredTxHash(String);
+
+impl TryFrom<StoredTxHash> for Option<TxHash> {
+    type Error = Error;
+
+    fn try_from(stored: StoredTxHash) -> Result<Self> {
+        match stored.0.as_str() {
+            "" => Ok(None),
+            hash => hash.parse().map(Some),
+        }
+    }
+}
+
+impl From<Option<TxHash>> for StoredTxHash {
+    fn from(hash: Option<TxHash>) -> Self {
+        Self(hash.map(|h| h.to_string()).unwrap_or_default())
+    }
+}
+
+/// Serde for an optional [`TxHash`] that also reads the empty string older
+/// sync bundles and deployment files used for an unknown transaction
+pub mod optional_tx_hash {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        hash: &Option<TxHash>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        hash.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Option<TxHash>, D::Error> {
+        match Option::<String>::deserialize(deserializer)?.as_deref() {
+            None | Some("") => Ok(None),
+            Some(hash) => hash.parse().map(Some).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "0x4f1bd8d1c7ab0c9c1f2c5e0d4b8a3f1e2d6c7b8a9f0e1d2c3b4a5968778695a4";
+
+    #[test]
+    fn test_parse_and_display() {
+        let hash: TxHash = HASH.to_uppercase().replacen("0X", "0x", 1).parse().unwrap();
+        assert_eq!(hash.to_string(), HASH);
+        assert_eq!(
+            serde_json::to_string(&hash).unwrap(),
+            format!("\"{}\"", HASH)
+        );
+
+        // Unprefixed, short, long and non-hex hashes are all rejected
+        assert!(HASH[2..].parse::<TxHash>().is_err());
+        assert!("0xabc".parse::<TxHash>().is_err());
+        assert!(format!("{}00", HASH).parse::<BytecodeHash>().is_err());
+        assert!(format!("0x{}", "g".repeat(64))
+            .parse::<BytecodeHash>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_bytecode_hash_of() {
+        let hash = BytecodeHash::of([0x60, 0x80]);
+        assert_eq!(hash.0, keccak256([0x60, 0x80]));
+        assert_eq!(hash.to_string().len(), 66);
+    }
+
+    #[test]
+    fn test_stored_tx_hash() {
+        let unknown: Option<TxHash> = StoredTxHash(String::new()).try_into().unwrap();
+        assert_eq!(unknown, None);
+        let known: Option<TxHash> = StoredTxHash(HASH.to_string()).try_into().unwrap();
+        assert_eq!(StoredTxHash::from(known).0, HASH);
+        assert!(Option::<TxHash>::try_from(StoredTxHash("0xabc".to_string())).is_err());
+
+        #[derive(Deserialize)]
+        struct File {
+            #[serde(with = "optional_tx_hash", default)]
+            tx_hash: Option<TxHash>,
+        }
+        let file: File = serde_json::from_str(r#"{ "tx_hash": "" }"#).unwrap();
+        assert_eq!(file.tx_hash, None);
+        let file: File = serde_json::from_str("{}").unwrap();
+        assert_eq!(file.tx_hash, None);
+    }
+}
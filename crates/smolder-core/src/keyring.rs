@@ -1,14 +1,16 @@
 //! Secure key storage using AES-256-GCM encryption
 //!
-//! Private keys are encrypted with an app-derived key before storage in SQLite.
+//! Private keys, and RPC URLs carrying credentials, are encrypted with an
+//! app-derived key before storage in SQLite.
 //! This provides obfuscation rather than true security - the encryption key
-//! is embedded in the binary. For higher security, consider password-based
-//! key derivation.
+//! is embedded in the binary. Data that leaves the machine, like wallet
+//! backups, is sealed with a key derived from a passphrase instead.
 
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
+use argon2::Argon2;
 use rand::Rng;
 
 use crate::error::Error;
@@ -20,10 +22,29 @@ const APP_KEY: &[u8; 32] = b"smolder-wallet-encrypt-key-0032!";
 /// Nonce size for AES-GCM (96 bits / 12 bytes)
 const NONCE_SIZE: usize = 12;
 
+/// Leading bytes of data sealed with a passphrase, ending in the format
+/// version
+const SEALED_MAGIC: &[u8; 8] = b"SMOLDER1";
+
+/// Argon2 salt size for passphrase-sealed data
+const SALT_SIZE: usize = 16;
+
 /// Encrypt a private key for storage
 ///
 /// Returns the encrypted data with the nonce prepended (nonce || ciphertext)
 pub fn encrypt_private_key(private_key: &str) -> Result<Vec<u8>, Error> {
+    encrypt_secret(private_key)
+}
+
+/// Decrypt a private key from storage
+///
+/// Expects data in format: nonce (12 bytes) || ciphertext
+pub fn decrypt_private_key(encrypted_data: &[u8]) -> Result<String, Error> {
+    decrypt_secret(encrypted_data)
+}
+
+/// Encrypt any secret for storage, in the same format as private keys
+pub fn encrypt_secret(secret: &str) -> Result<Vec<u8>, Error> {
     let cipher = Aes256Gcm::new(APP_KEY.into());
 
     // Generate random nonce
@@ -33,7 +54,7 @@ pub fn encrypt_private_key(private_key: &str) -> Result<Vec<u8>, Error> {
 
     // Encrypt
     let ciphertext = cipher
-        .encrypt(&nonce, private_key.as_bytes())
+        .encrypt(&nonce, secret.as_bytes())
         .map_err(|e| Error::Keyring(format!("Encryption failed: {}", e)))?;
 
     // Prepend nonce to ciphertext
@@ -43,10 +64,8 @@ pub fn encrypt_private_key(private_key: &str) -> Result<Vec<u8>, Error> {
     Ok(result)
 }
 
-/// Decrypt a private key from storage
-///
-/// Expects data in format: nonce (12 bytes) || ciphertext
-pub fn decrypt_private_key(encrypted_data: &[u8]) -> Result<String, Error> {
+/// Decrypt a secret encrypted with [`encrypt_secret`]
+pub fn decrypt_secret(encrypted_data: &[u8]) -> Result<String, Error> {
     if encrypted_data.len() < NONCE_SIZE {
         return Err(Error::Keyring("Invalid encrypted data: too short".into()));
     }
@@ -68,6 +87,69 @@ pub fn decrypt_private_key(encrypted_data: &[u8]) -> Result<String, Error> {
     String::from_utf8(plaintext).map_err(|e| Error::Keyring(format!("Invalid UTF-8: {}", e)))
 }
 
+/// Encrypt data with a key derived from `passphrase` with Argon2id
+///
+/// Returns `SMOLDER1 || salt (16 bytes) || nonce (12 bytes) || ciphertext`,
+/// with the header authenticated along with the ciphertext.
+pub fn seal_with_passphrase(data: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    let mut salt = [0u8; SALT_SIZE];
+    rand::thread_rng().fill(&mut salt);
+    let mut nonce_bytes = [0u8; NONCE_SIZE];
+    rand::thread_rng().fill(&mut nonce_bytes);
+
+    let mut sealed = SEALED_MAGIC.to_vec();
+    sealed.extend(salt);
+    sealed.extend(nonce_bytes);
+
+    let cipher = passphrase_cipher(passphrase, &salt)?;
+    let ciphertext = cipher
+        .encrypt(
+            &Nonce::from(nonce_bytes),
+            Payload {
+                msg: data,
+                aad: &sealed,
+            },
+        )
+        .map_err(|e| Error::Encryption(e.to_string()))?;
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt data sealed with [`seal_with_passphrase`], failing with
+/// `Error::Decryption` when the passphrase is wrong or the data was altered
+pub fn open_with_passphrase(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    let header_size = SEALED_MAGIC.len() + SALT_SIZE + NONCE_SIZE;
+    if sealed.len() < header_size || !sealed.starts_with(SEALED_MAGIC) {
+        return Err(Error::Decryption(
+            "Not data sealed by smolder, or from a newer version".into(),
+        ));
+    }
+    let (header, ciphertext) = sealed.split_at(header_size);
+    let (salt, nonce_bytes) = header[SEALED_MAGIC.len()..].split_at(SALT_SIZE);
+    let nonce_array: [u8; NONCE_SIZE] = nonce_bytes
+        .try_into()
+        .map_err(|_| Error::Decryption("Invalid nonce length".into()))?;
+
+    let cipher = passphrase_cipher(passphrase, salt)?;
+    cipher
+        .decrypt(
+            &Nonce::from(nonce_array),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| Error::Decryption("Wrong passphrase, or the data was altered".into()))
+}
+
+fn passphrase_cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, Error> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| Error::Keyring(format!("Key derivation failed: {}", e)))?;
+    Ok(Aes256Gcm::new(&key.into()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,4 +187,23 @@ mod tests {
         // Invalid ciphertext
         assert!(decrypt_private_key(&[0u8; 20]).is_err());
     }
+
+    #[test]
+    fn test_passphrase_roundtrip() {
+        let sealed = seal_with_passphrase(b"wallets", "correct horse").unwrap();
+        assert!(sealed.starts_with(SEALED_MAGIC));
+        assert_eq!(
+            open_with_passphrase(&sealed, "correct horse").unwrap(),
+            b"wallets"
+        );
+
+        assert!(matches!(
+            open_with_passphrase(&sealed, "wrong horse"),
+            Err(Error::Decryption(_))
+        ));
+        let mut altered = sealed.clone();
+        altered[SEALED_MAGIC.len()] ^= 1;
+        assert!(open_with_passphrase(&altered, "correct horse").is_err());
+        assert!(open_with_passphrase(b"SMOLDER1", "correct horse").is_err());
+    }
 }
@@ -0,0 +1,370 @@
+//! Storage layouts and upgrade compatibility
+//!
+//! solc emits a contract's storage layout when asked for it (forge's
+//! `extra_output = ["storageLayout"]`). Comparing the layout of a new version
+//! against the one already deployed catches the upgrades that would make a
+//! proxy read its state from the wrong slots: removed, reordered or retyped
+//! variables, and storage gaps that no longer end where they did.
+
+use std::collections::{BTreeMap, HashMap};
+
+use alloy::primitives::U256;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Types referencing themselves (a struct holding a mapping of itself) are
+/// only described this deep
+const MAX_TYPE_DEPTH: usize = 8;
+
+/// The `storageLayout` output of solc
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageLayout {
+    #[serde(default)]
+    pub storage: Vec<StorageVariable>,
+    /// Types by solc type ID; `null` in the output of contracts without state
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub types: BTreeMap<String, StorageType>,
+}
+
+/// A state variable, or a member of a struct type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageVariable {
+    pub label: String,
+    #[serde(default)]
+    pub contract: String,
+    pub slot: String,
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// How a storage type is encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageType {
+    pub encoding: String,
+    pub label: String,
+    pub number_of_bytes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<StorageVariable>>,
+}
+
+fn null_as_default<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl StorageLayout {
+    /// Parse a layout from its JSON form
+    pub fn parse(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| Error::invalid_param("storage_layout", format!("{}", e)))
+    }
+
+    /// Parse the `storageLayout` value of an artifact
+    pub fn from_value(value: &serde_json::Value) -> Result<Self> {
+        Self::deserialize(value)
+            .map_err(|e| Error::invalid_param("storage_layout", format!("{}", e)))
+    }
+
+    /// Describe type `id` by how it is stored, leaving out names (of structs,
+    /// contracts, enums) that can change without moving any data
+    fn shape(&self, id: &str, depth: usize) -> String {
+        let Some(ty) = self.types.get(id) else {
+            return id.to_string();
+        };
+        if depth >= MAX_TYPE_DEPTH {
+            return format!("{}:{}", ty.encoding, ty.number_of_bytes);
+        }
+        let inner = |id: &Option<String>| {
+            id.as_deref()
+                .map(|id| self.shape(id, depth + 1))
+                .unwrap_or_default()
+        };
+
+        if let Some(members) = &ty.members {
+            let members: Vec<String> = members
+                .iter()
+                .map(|m| format!("{}:{}:{}", m.slot, m.offset, self.shape(&m.ty, depth + 1)))
+                .collect();
+            return format!("struct{{{}}}", members.join(","));
+        }
+        match ty.encoding.as_str() {
+            "mapping" => format!("mapping({}=>{})", inner(&ty.key), inner(&ty.value)),
+            "dynamic_array" => format!("{}[]", inner(&ty.base)),
+            _ if ty.base.is_some() => format!("{}[{}]", inner(&ty.base), ty.number_of_bytes),
+            _ if ty.label.starts_with("contract ") || ty.label.starts_with("enum ") => {
+                format!("{}:{}", ty.encoding, ty.number_of_bytes)
+            }
+            _ => ty.label.clone(),
+        }
+    }
+
+    /// Number of slots a variable of type `id` takes up
+    fn slots(&self, id: &str) -> U256 {
+        let bytes = self
+            .types
+            .get(id)
+            .and_then(|t| t.number_of_bytes.parse::<U256>().ok())
+            .unwrap_or(U256::from(32));
+        bytes.div_ceil(U256::from(32))
+    }
+
+    /// Solidity type name of type `id`
+    fn type_label<'a>(&'a self, id: &'a str) -> &'a str {
+        self.types.get(id).map(|t| t.label.as_str()).unwrap_or(id)
+    }
+}
+
+/// What is wrong with a variable after an upgrade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutIssueKind {
+    /// Nothing is stored where the variable was; it was removed, or moved by
+    /// a variable inserted before it
+    Removed,
+    /// The variable is stored differently
+    TypeChanged,
+    /// A different name at the same place with the same type
+    Renamed,
+    /// A `__gap` no longer ends at the slot it did
+    GapResized,
+}
+
+impl LayoutIssueKind {
+    /// Whether the new version would read existing state wrongly. A rename
+    /// is usually intended, but worth a look.
+    pub fn is_breaking(self) -> bool {
+        !matches!(self, LayoutIssueKind::Renamed)
+    }
+}
+
+/// A conflict between a deployed storage layout and its upgrade
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LayoutIssue {
+    pub kind: LayoutIssueKind,
+    /// The variable in the deployed layout
+    pub label: String,
+    pub slot: String,
+    pub message: String,
+}
+
+/// Compare `next`, the layout of an upgrade, against `previous`
+///
+/// Variables may only be appended after the existing ones, or take the place
+/// of part of a trailing `__gap` as long as the gap shrinks accordingly.
+pub fn check_upgrade(previous: &StorageLayout, next: &StorageLayout) -> Vec<LayoutIssue> {
+    let by_position: HashMap<(U256, u64), &StorageVariable> = next
+        .storage
+        .iter()
+        .filter_map(|v| Some(((v.slot.parse().ok()?, v.offset), v)))
+        .collect();
+    let gap_ends: Vec<U256> = next
+        .storage
+        .iter()
+        .filter(|v| is_gap(&v.label))
+        .filter_map(|v| Some(v.slot.parse::<U256>().ok()? + next.slots(&v.ty)))
+        .collect();
+
+    let mut issues = Vec::new();
+    for old in &previous.storage {
+        let Ok(slot) = old.slot.parse::<U256>() else {
+            continue;
+        };
+        let issue = |kind, message| LayoutIssue {
+            kind,
+            label: old.label.clone(),
+            slot: old.slot.clone(),
+            message,
+        };
+
+        if is_gap(&old.label) {
+            let end = slot + previous.slots(&old.ty);
+            if !gap_ends.contains(&end) {
+                issues.push(issue(
+                    LayoutIssueKind::GapResized,
+                    format!(
+                        "Storage gap `{}` at slot {} no longer ends at slot {}",
+                        old.label, old.slot, end
+                    ),
+                ));
+            }
+            continue;
+        }
+
+        let Some(new) = by_position.get(&(slot, old.offset)) else {
+            issues.push(issue(
+                LayoutIssueKind::Removed,
+                format!(
+                    "`{}` ({}) at slot {} was removed or moved",
+                    old.label,
+                    previous.type_label(&old.ty),
+                    old.slot
+                ),
+            ));
+            continue;
+        };
+
+        if previous.shape(&old.ty, 0) != next.shape(&new.ty, 0) {
+            issues.push(issue(
+                LayoutIssueKind::TypeChanged,
+                format!(
+                    "`{}` at slot {} changed from {} to {}",
+                    old.label,
+                    old.slot,
+                    previous.type_label(&old.ty),
+                    next.type_label(&new.ty)
+                ),
+            ));
+        } else if old.label != new.label {
+            issues.push(issue(
+                LayoutIssueKind::Renamed,
+                format!(
+                    "`{}` at slot {} was renamed to `{}`",
+                    old.label, old.slot, new.label
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+fn is_gap(label: &str) -> bool {
+    label.starts_with("__gap")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(vars: &[(&str, &str, u64, &str)]) -> StorageLayout {
+        let types = serde_json::json!({
+            "t_uint256": {"encoding": "inplace", "label": "uint256", "numberOfBytes": "32"},
+            "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+            "t_bool": {"encoding": "inplace", "label": "bool", "numberOfBytes": "1"},
+            "t_mapping(t_address,t_uint256)": {
+                "encoding": "mapping", "key": "t_address", "label": "mapping(address => uint256)",
+                "numberOfBytes": "32", "value": "t_uint256"
+            },
+            "t_array(t_uint256)48_storage": {
+                "base": "t_uint256", "encoding": "inplace", "label": "uint256[48]",
+                "numberOfBytes": "1536"
+            },
+            "t_array(t_uint256)49_storage": {
+                "base": "t_uint256", "encoding": "inplace", "label": "uint256[49]",
+                "numberOfBytes": "1568"
+            },
+            "t_array(t_uint256)50_storage": {
+                "base": "t_uint256", "encoding": "inplace", "label": "uint256[50]",
+                "numberOfBytes": "1600"
+            }
+        });
+        let storage: Vec<_> = vars
+            .iter()
+            .map(|(label, slot, offset, ty)| {
+                serde_json::json!({
+                    "astId": 1, "contract": "src/Token.sol:Token", "label": label,
+                    "offset": offset, "slot": slot, "type": ty
+                })
+            })
+            .collect();
+        StorageLayout::from_value(&serde_json::json!({"storage": storage, "types": types})).unwrap()
+    }
+
+    #[test]
+    fn test_parse_empty_layout() {
+        let layout = StorageLayout::parse(r#"{"storage":[],"types":null}"#).unwrap();
+        assert!(layout.storage.is_empty());
+        assert!(layout.types.is_empty());
+    }
+
+    #[test]
+    fn test_appending_is_compatible() {
+        let v1 = layout(&[
+            ("owner", "0", 0, "t_address"),
+            ("paused", "0", 20, "t_bool"),
+            ("balances", "1", 0, "t_mapping(t_address,t_uint256)"),
+        ]);
+        let v2 = layout(&[
+            ("owner", "0", 0, "t_address"),
+            ("paused", "0", 20, "t_bool"),
+            ("balances", "1", 0, "t_mapping(t_address,t_uint256)"),
+            ("cap", "2", 0, "t_uint256"),
+        ]);
+        assert!(check_upgrade(&v1, &v2).is_empty());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let v1 = layout(&[
+            ("owner", "0", 0, "t_address"),
+            ("supply", "1", 0, "t_uint256"),
+            ("balances", "2", 0, "t_mapping(t_address,t_uint256)"),
+        ]);
+        // `cap` inserted before `balances`, `supply` retyped, `owner` renamed
+        let v2 = layout(&[
+            ("admin", "0", 0, "t_address"),
+            ("supply", "1", 0, "t_address"),
+            ("cap", "2", 0, "t_uint256"),
+            ("balances", "3", 0, "t_mapping(t_address,t_uint256)"),
+        ]);
+        let issues = check_upgrade(&v1, &v2);
+        let kinds: Vec<_> = issues.iter().map(|i| (i.label.as_str(), i.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("owner", LayoutIssueKind::Renamed),
+                ("supply", LayoutIssueKind::TypeChanged),
+                ("balances", LayoutIssueKind::TypeChanged),
+            ]
+        );
+        assert!(!LayoutIssueKind::Renamed.is_breaking());
+        assert_eq!(
+            issues[1].message,
+            "`supply` at slot 1 changed from uint256 to address"
+        );
+
+        let v3 = layout(&[("owner", "0", 0, "t_address")]);
+        let issues = check_upgrade(&v1, &v3);
+        assert_eq!(issues.len(), 2);
+        assert!(issues.iter().all(|i| i.kind == LayoutIssueKind::Removed));
+    }
+
+    #[test]
+    fn test_storage_gaps() {
+        let v1 = layout(&[
+            ("owner", "0", 0, "t_address"),
+            ("__gap", "1", 0, "t_array(t_uint256)50_storage"),
+        ]);
+        // A variable takes one slot of the gap
+        let v2 = layout(&[
+            ("owner", "0", 0, "t_address"),
+            ("cap", "1", 0, "t_uint256"),
+            ("__gap", "2", 0, "t_array(t_uint256)49_storage"),
+        ]);
+        assert!(check_upgrade(&v1, &v2).is_empty());
+
+        // ...but the gap wasn't shrunk
+        let v3 = layout(&[
+            ("owner", "0", 0, "t_address"),
+            ("cap", "1", 0, "t_uint256"),
+            ("__gap", "2", 0, "t_array(t_uint256)50_storage"),
+        ]);
+        let issues = check_upgrade(&v1, &v3);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, LayoutIssueKind::GapResized);
+    }
+}
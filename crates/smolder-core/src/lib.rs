@@ -1,16 +1,41 @@
 pub mod abi;
+pub mod address;
 pub mod bytecode;
+pub mod chains;
+pub mod compiler;
 pub mod dir;
+pub mod ens;
 pub mod error;
+pub mod explorer;
+pub mod hash;
 pub mod keyring;
+pub mod layout;
+pub mod linking;
+pub mod secret;
+pub mod storage;
 pub mod types;
+pub mod units;
 
 pub use abi::{
-    json_to_sol_value, parse_int, parse_uint, sol_value_to_json, Abi, ConstructorInfo,
-    FunctionInfo, ParamInfo, ParsedFunctions,
+    decode_event_log, decode_function_input, decode_function_result, encode_constructor_args,
+    encode_function_call, json_to_sol_value, parse_event_signature, parse_function_signature,
+    parse_int, parse_uint, sol_value_to_json, Abi, ConstructorInfo, FunctionInfo, ParamInfo,
+    ParsedFunctions,
 };
-pub use bytecode::Bytecode;
+pub use address::EvmAddress;
+pub use bytecode::{Bytecode, CodeSize};
+pub use compiler::CompilerSettings;
 pub use dir::SmolderDir;
 pub use error::{Error, Result};
-pub use keyring::{decrypt_private_key, encrypt_private_key};
+pub use explorer::{address_link, explorer_link, tx_link, ExplorerPage};
+pub use hash::{BytecodeHash, StoredTxHash, TxHash};
+pub use keyring::{
+    decrypt_private_key, decrypt_secret, encrypt_private_key, encrypt_secret, open_with_passphrase,
+    seal_with_passphrase,
+};
+pub use layout::{check_upgrade, LayoutIssue, LayoutIssueKind, StorageLayout};
+pub use linking::{LinkOffset, LinkReferences};
+pub use secret::{redact_url, scrub, RpcUrl, Secret};
+pub use storage::StorageWord;
 pub use types::*;
+pub use units::{format_gas_price, format_value, parse_ether, parse_units, parse_value, Currency};
@@ -0,0 +1,130 @@
+//! Library linking
+//!
+//! Contracts calling external library functions are compiled with a
+//! `__$<hash>$__` placeholder wherever a library address goes. The artifact's
+//! `linkReferences` tell where each placeholder is; linking writes the
+//! library's deployed address over it before the bytecode can be sent.
+
+use std::collections::BTreeMap;
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Placeholder positions, by source file then library name
+pub type LinkReferences = BTreeMap<String, BTreeMap<String, Vec<LinkOffset>>>;
+
+/// Position of a placeholder in the bytecode, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkOffset {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Fully qualified names (`path:Name`) of the libraries to link, sorted
+pub fn libraries(references: &LinkReferences) -> Vec<String> {
+    references
+        .iter()
+        .flat_map(|(path, names)| names.keys().map(move |name| format!("{}:{}", path, name)))
+        .collect()
+}
+
+/// Whether hex bytecode still holds library placeholders
+pub fn is_unlinked(bytecode: &str) -> bool {
+    bytecode.contains("__$")
+}
+
+/// Write library addresses over the placeholders of hex bytecode
+///
+/// `addresses` is keyed by fully qualified name; every library in
+/// `references` must have one.
+pub fn link(
+    bytecode: &str,
+    references: &LinkReferences,
+    addresses: &BTreeMap<String, Address>,
+) -> Result<String> {
+    let prefixed = bytecode.starts_with("0x");
+    let mut code = bytecode.trim_start_matches("0x").to_string();
+
+    for (path, names) in references {
+        for (name, offsets) in names {
+            let library = format!("{}:{}", path, name);
+            let address = addresses
+                .get(&library)
+                .ok_or_else(|| Error::Validation(format!("Library {} is not linked", library)))?;
+            let address = hex::encode(address);
+            for offset in offsets {
+                let (start, end) = (offset.start * 2, (offset.start + offset.length) * 2);
+                if offset.length != Address::len_bytes() || end > code.len() {
+                    return Err(Error::Validation(format!(
+                        "Invalid link reference for {} at byte {}",
+                        library, offset.start
+                    )));
+                }
+                code.replace_range(start..end, &address);
+            }
+        }
+    }
+
+    if is_unlinked(&code) {
+        return Err(Error::Validation(
+            "Bytecode has library placeholders its link references don't cover".to_string(),
+        ));
+    }
+    Ok(match prefixed {
+        true => format!("0x{}", code),
+        false => code,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLACEHOLDER: &str = "__$1f8d1c6bd1aa4e2f2c0e3bdf5d1c4bba5e$__";
+
+    fn references() -> LinkReferences {
+        let mut names = BTreeMap::new();
+        names.insert(
+            "Math".to_string(),
+            vec![
+                LinkOffset {
+                    start: 2,
+                    length: 20,
+                },
+                LinkOffset {
+                    start: 23,
+                    length: 20,
+                },
+            ],
+        );
+        BTreeMap::from([("src/Math.sol".to_string(), names)])
+    }
+
+    #[test]
+    fn test_link() {
+        let bytecode = format!("0x6080{}00{}fe", PLACEHOLDER, PLACEHOLDER);
+        assert!(is_unlinked(&bytecode));
+        assert_eq!(libraries(&references()), vec!["src/Math.sol:Math"]);
+
+        let address = Address::repeat_byte(0x11);
+        let addresses = BTreeMap::from([("src/Math.sol:Math".to_string(), address)]);
+        let linked = link(&bytecode, &references(), &addresses).unwrap();
+        assert_eq!(
+            linked,
+            format!("0x6080{}00{}fe", "11".repeat(20), "11".repeat(20))
+        );
+    }
+
+    #[test]
+    fn test_link_missing_library() {
+        let bytecode = format!("6080{}00{}fe", PLACEHOLDER, PLACEHOLDER);
+        let err = link(&bytecode, &references(), &BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains("src/Math.sol:Math"));
+
+        // Placeholders without references can't be linked
+        let err = link(&bytecode, &LinkReferences::new(), &BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains("placeholders"));
+    }
+}
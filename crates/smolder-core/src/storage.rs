@@ -0,0 +1,254 @@
+//! Storage slot arithmetic
+//!
+//! Computes where Solidity keeps a value — mapping entries, dynamic array
+//! elements and ERC-7201 namespaces — and knows the fixed slots proxies use,
+//! so raw `eth_getStorageAt` results can be read without a storage layout.
+
+use alloy::primitives::{b256, keccak256, Address, B256, I256, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// A fixed slot looked up by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedSlot {
+    pub name: &'static str,
+    pub slot: B256,
+    pub description: &'static str,
+}
+
+/// EIP-1967 slot holding a proxy's implementation address
+pub const IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// EIP-1967 slot holding a transparent proxy's admin address
+pub const ADMIN_SLOT: B256 =
+    b256!("b53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103");
+
+/// Slots that hold the same thing in every contract using the standard
+pub const NAMED_SLOTS: [NamedSlot; 4] = [
+    NamedSlot {
+        name: "implementation",
+        slot: IMPLEMENTATION_SLOT,
+        description: "EIP-1967 proxy implementation",
+    },
+    NamedSlot {
+        name: "admin",
+        slot: ADMIN_SLOT,
+        description: "EIP-1967 proxy admin",
+    },
+    NamedSlot {
+        name: "beacon",
+        slot: b256!("a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50"),
+        description: "EIP-1967 beacon",
+    },
+    NamedSlot {
+        name: "owner",
+        slot: b256!("9016d09d72d40fdae2fd8ceac6b6234c7706214fd39c1cd1e609a0528c199300"),
+        description: "OpenZeppelin 5 Ownable owner (ERC-7201)",
+    },
+];
+
+/// Look up a [`NamedSlot`] by name, ignoring case
+pub fn named_slot(name: &str) -> Option<&'static NamedSlot> {
+    NAMED_SLOTS
+        .iter()
+        .find(|s| s.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Parse a slot given by name (see [`NAMED_SLOTS`]), as a decimal number or
+/// as hex
+pub fn parse_slot(input: &str) -> Result<B256> {
+    let input = input.trim();
+    if let Some(named) = named_slot(input) {
+        return Ok(named.slot);
+    }
+    let value = match input.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16),
+        None => U256::from_str_radix(input, 10),
+    };
+    value
+        .map(B256::from)
+        .map_err(|_| Error::invalid_param("slot", format!("'{}' is not a slot number", input)))
+}
+
+/// The bytes Solidity hashes for a mapping key
+///
+/// Addresses and numbers are left-padded to a word, 32-byte hex values are
+/// taken as `bytes32`, `true`/`false` as `bool`, and anything else as a
+/// `string` key, which is hashed unpadded.
+pub fn encode_key(key: &str) -> Vec<u8> {
+    let key = key.trim();
+    if let Ok(address) = key.parse::<Address>() {
+        return B256::left_padding_from(address.as_slice()).to_vec();
+    }
+    if let Some(hex) = key.strip_prefix("0x") {
+        if let Ok(value) = U256::from_str_radix(hex, 16) {
+            return B256::from(value).to_vec();
+        }
+    }
+    if let Ok(value) = U256::from_str_radix(key, 10) {
+        return B256::from(value).to_vec();
+    }
+    if key.starts_with('-') {
+        if let Ok(value) = key.parse::<I256>() {
+            return B256::from(value.into_raw()).to_vec();
+        }
+    }
+    match key {
+        "true" => B256::from(U256::from(1)).to_vec(),
+        "false" => B256::ZERO.to_vec(),
+        _ => key.as_bytes().to_vec(),
+    }
+}
+
+/// Slot of `mapping[key]` for a mapping declared at `slot`
+pub fn mapping_slot(key: &[u8], slot: B256) -> B256 {
+    let mut buf = Vec::with_capacity(key.len() + 32);
+    buf.extend_from_slice(key);
+    buf.extend_from_slice(slot.as_slice());
+    keccak256(buf)
+}
+
+/// Slot of `array[index]` for a dynamic array of single-slot elements
+/// declared at `slot`
+pub fn array_slot(slot: B256, index: U256) -> B256 {
+    let start = U256::from_be_bytes(keccak256(slot).0);
+    B256::from(start.wrapping_add(index))
+}
+
+/// Follow mapping `keys` (outermost first) and then an array `index` from a
+/// variable declared at `slot`
+///
+/// `balances[holder]` is `derive_slot(slot, &[holder], None)`, and
+/// `allowances[owner][spender]` takes both keys in source order.
+pub fn derive_slot<K: AsRef<str>>(slot: B256, keys: &[K], index: Option<U256>) -> B256 {
+    let slot = keys.iter().fold(slot, |slot, key| {
+        mapping_slot(&encode_key(key.as_ref()), slot)
+    });
+    match index {
+        Some(index) => array_slot(slot, index),
+        None => slot,
+    }
+}
+
+/// Root slot of an ERC-7201 namespace, e.g. `openzeppelin.storage.Ownable`
+pub fn erc7201_slot(namespace: &str) -> B256 {
+    let id = U256::from_be_bytes(keccak256(namespace.as_bytes()).0) - U256::from(1);
+    let mut slot = keccak256(B256::from(id));
+    slot.0[31] = 0;
+    slot
+}
+
+/// A storage word with the readings that make sense for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StorageWord {
+    pub slot: String,
+    /// The raw 32-byte value
+    pub value: String,
+    /// The value as a decimal `uint256`
+    pub uint: String,
+    /// The value as an address, when it looks like one
+    pub address: Option<String>,
+}
+
+impl StorageWord {
+    pub fn new(slot: B256, value: B256) -> Self {
+        // An address fills the low 20 bytes; small numbers don't reach the
+        // top of that range
+        let looks_like_address =
+            value[..12].iter().all(|b| *b == 0) && value[12..16].iter().any(|b| *b != 0);
+        Self {
+            slot: slot.to_string(),
+            value: value.to_string(),
+            uint: U256::from_be_bytes(value.0).to_string(),
+            address: looks_like_address.then(|| Address::from_word(value).to_checksum(None)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eip1967_slot(label: &str) -> B256 {
+        let hash = U256::from_be_bytes(keccak256(label.as_bytes()).0);
+        B256::from(hash - U256::from(1))
+    }
+
+    #[test]
+    fn test_named_slots() {
+        assert_eq!(
+            named_slot("implementation").unwrap().slot,
+            eip1967_slot("eip1967.proxy.implementation")
+        );
+        assert_eq!(
+            named_slot("Admin").unwrap().slot,
+            eip1967_slot("eip1967.proxy.admin")
+        );
+        assert_eq!(
+            named_slot("beacon").unwrap().slot,
+            eip1967_slot("eip1967.proxy.beacon")
+        );
+        assert_eq!(
+            named_slot("owner").unwrap().slot,
+            erc7201_slot("openzeppelin.storage.Ownable")
+        );
+        assert!(named_slot("unknown").is_none());
+    }
+
+    #[test]
+    fn test_parse_slot() {
+        assert_eq!(parse_slot("3").unwrap(), B256::from(U256::from(3)));
+        assert_eq!(parse_slot("0x10").unwrap(), B256::from(U256::from(16)));
+        assert_eq!(parse_slot("admin").unwrap(), NAMED_SLOTS[1].slot);
+        assert!(parse_slot("nope").is_err());
+    }
+
+    #[test]
+    fn test_mapping_and_array_slots() {
+        // balances[0xd8dA...6045] with `mapping(address => uint256) balances` at slot 0
+        let key = encode_key("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045");
+        assert_eq!(key.len(), 32);
+        assert_eq!(
+            mapping_slot(&key, B256::ZERO),
+            keccak256([key.as_slice(), B256::ZERO.as_slice()].concat())
+        );
+
+        // String keys are hashed unpadded, numbers as words
+        assert_eq!(encode_key("hello"), b"hello".to_vec());
+        assert_eq!(encode_key("1"), B256::from(U256::from(1)).to_vec());
+        assert_eq!(encode_key("-1"), B256::repeat_byte(0xff).to_vec());
+
+        let base = B256::from(U256::from(2));
+        let first = array_slot(base, U256::ZERO);
+        assert_eq!(first, keccak256(base));
+        assert_eq!(
+            U256::from_be_bytes(array_slot(base, U256::from(5)).0),
+            U256::from_be_bytes(first.0) + U256::from(5)
+        );
+
+        // allowances[owner][spender]
+        let nested = derive_slot(base, &["0x01", "0x02"], None);
+        let inner = mapping_slot(&encode_key("0x01"), base);
+        assert_eq!(nested, mapping_slot(&encode_key("0x02"), inner));
+        assert_eq!(derive_slot(base, &[] as &[&str], Some(U256::ZERO)), first);
+    }
+
+    #[test]
+    fn test_storage_word() {
+        let owner: Address = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+            .parse()
+            .unwrap();
+        let word = StorageWord::new(B256::ZERO, owner.into_word());
+        assert_eq!(
+            word.address.as_deref(),
+            Some("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045")
+        );
+
+        let word = StorageWord::new(B256::ZERO, B256::from(U256::from(42)));
+        assert_eq!(word.uint, "42");
+        assert!(word.address.is_none());
+    }
+}
@@ -13,6 +13,7 @@ pub use alloy::primitives::{Address, B256};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum CallType {
     Read,
     Write,
@@ -40,6 +41,7 @@ impl CallType {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum TransactionStatus {
     Pending,
     Success,
@@ -69,9 +71,398 @@ impl TransactionStatus {
     }
 }
 
+/// What caused a webhook notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum WebhookTrigger {
+    /// A contract event was indexed
+    Event,
+    /// A call history entry reached a final status
+    Call,
+}
+
+impl fmt::Display for WebhookTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl WebhookTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookTrigger::Event => "event",
+            WebhookTrigger::Call => "call",
+        }
+    }
+}
+
+/// Status of a webhook delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum DeliveryStatus {
+    /// Not yet delivered; attempts remain
+    Pending,
+    /// The endpoint answered with a 2xx status
+    Delivered,
+    /// Every attempt failed
+    Failed,
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+/// State of a background job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum JobState {
+    /// Waiting for a worker, once `run_after` has passed
+    Queued,
+    /// Taken by a worker
+    Running,
+    Succeeded,
+    /// Every attempt failed, or the job can't be run
+    Failed,
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+        }
+    }
+}
+
+/// Direction of a cached ENS lookup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum EnsLookup {
+    /// Name to address
+    Forward,
+    /// Address to primary name
+    Reverse,
+}
+
+impl fmt::Display for EnsLookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl EnsLookup {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnsLookup::Forward => "forward",
+            EnsLookup::Reverse => "reverse",
+        }
+    }
+}
+
+/// Where a known function signature came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum SelectorSource {
+    /// An ABI in the registry
+    #[serde(rename = "registry")]
+    #[sqlx(rename = "registry")]
+    Registry,
+    /// The 4byte.directory signature database
+    #[serde(rename = "4byte")]
+    #[sqlx(rename = "4byte")]
+    FourByte,
+}
+
+impl fmt::Display for SelectorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl SelectorSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SelectorSource::Registry => "registry",
+            SelectorSource::FourByte => "4byte",
+        }
+    }
+}
+
+/// How settled a mined transaction is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Finality {
+    /// Mined, with fewer confirmations than its network requires, or moved
+    /// by a reorg
+    Pending,
+    /// Buried under the confirmations its network requires
+    Confirmed,
+    /// At or below the node's `finalized` block
+    Finalized,
+}
+
+impl fmt::Display for Finality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Finality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Finality::Pending => "pending",
+            Finality::Confirmed => "confirmed",
+            Finality::Finalized => "finalized",
+        }
+    }
+}
+
+/// Kind of registry row a mined transaction was recorded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum RecordKind {
+    /// A deployment and its creation transaction
+    Deployment,
+    /// A call history entry
+    Call,
+}
+
+impl fmt::Display for RecordKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl RecordKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordKind::Deployment => "deployment",
+            RecordKind::Call => "call",
+        }
+    }
+}
+
+/// A change recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum AuditAction {
+    /// A network was locked against deploys and sends
+    Lock,
+    /// A network was unlocked
+    Unlock,
+    /// A deploy or send went ahead on a locked network, confirmed by name
+    Override,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Lock => "lock",
+            AuditAction::Unlock => "unlock",
+            AuditAction::Override => "override",
+        }
+    }
+}
+
+/// Where an audited change was made from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum AuditSource {
+    /// The command line, by the local user
+    Cli,
+    /// The HTTP API, by an API token
+    Api,
+}
+
+impl fmt::Display for AuditSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl AuditSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditSource::Cli => "cli",
+            AuditSource::Api => "api",
+        }
+    }
+}
+
+/// Token standard a contract's ABI implements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum TokenStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
+impl fmt::Display for TokenStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TokenStandard {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenStandard::Erc20 => "erc20",
+            TokenStandard::Erc721 => "erc721",
+            TokenStandard::Erc1155 => "erc1155",
+        }
+    }
+
+    /// Whether the token has a `decimals()` getter
+    pub fn is_fungible(&self) -> bool {
+        matches!(self, TokenStandard::Erc20)
+    }
+}
+
+/// What an API token may do
+///
+/// Roles are ordered: each one can do everything the previous one can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ApiRole {
+    /// Read the registry
+    Viewer,
+    /// Also call, send and deploy on networks that aren't protected
+    Operator,
+    /// Also manage wallets, networks, webhooks and tokens, and request and
+    /// approve transactions on protected networks
+    Admin,
+}
+
+impl fmt::Display for ApiRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl ApiRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiRole::Viewer => "viewer",
+            ApiRole::Operator => "operator",
+            ApiRole::Admin => "admin",
+        }
+    }
+}
+
+/// What a pending approval will do once approved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ApprovalKind {
+    /// A contract deployment (`POST /api/deploy`)
+    Deploy,
+    /// A transaction to a deployment (`POST /api/deployments/{id}/send`)
+    Send,
+}
+
+impl fmt::Display for ApprovalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl ApprovalKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalKind::Deploy => "deploy",
+            ApprovalKind::Send => "send",
+        }
+    }
+}
+
+/// Status of a request against a protected network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ApprovalStatus {
+    /// Waiting for a second token to approve or reject it
+    Pending,
+    /// Approved and executed
+    Approved,
+    /// Turned down
+    Rejected,
+    /// Not decided on in time
+    Expired,
+    /// Approved, but executing it failed
+    Failed,
+}
+
+impl fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl ApprovalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+            ApprovalStatus::Expired => "expired",
+            ApprovalStatus::Failed => "failed",
+        }
+    }
+}
+
 /// State mutability of a contract function
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum StateMutability {
     Pure,
     View,
@@ -113,6 +504,7 @@ impl fmt::Display for StateMutability {
 /// Network identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 #[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct NetworkId(pub i64);
 
 impl fmt::Display for NetworkId {
@@ -136,6 +528,7 @@ impl From<NetworkId> for i64 {
 /// Contract identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 #[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ContractId(pub i64);
 
 impl fmt::Display for ContractId {
@@ -159,6 +552,7 @@ impl From<ContractId> for i64 {
 /// Deployment identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 #[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DeploymentId(pub i64);
 
 impl fmt::Display for DeploymentId {
@@ -182,6 +576,7 @@ impl From<DeploymentId> for i64 {
 /// Wallet identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 #[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WalletId(pub i64);
 
 impl fmt::Display for WalletId {
@@ -202,9 +597,58 @@ impl From<WalletId> for i64 {
     }
 }
 
+/// Webhook identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookId(pub i64);
+
+impl fmt::Display for WebhookId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<i64> for WebhookId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<WebhookId> for i64 {
+    fn from(value: WebhookId) -> Self {
+        value.0
+    }
+}
+
+/// Deployment group identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeploymentGroupId(pub i64);
+
+impl fmt::Display for DeploymentGroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<i64> for DeploymentGroupId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DeploymentGroupId> for i64 {
+    fn from(value: DeploymentGroupId) -> Self {
+        value.0
+    }
+}
+
 /// Chain ID wrapper
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 #[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ChainId(pub i64);
 
 impl fmt::Display for ChainId {
@@ -236,3 +680,51 @@ impl From<ChainId> for i64 {
         value.0
     }
 }
+
+/// API token identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiTokenId(pub i64);
+
+impl fmt::Display for ApiTokenId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<i64> for ApiTokenId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ApiTokenId> for i64 {
+    fn from(value: ApiTokenId) -> Self {
+        value.0
+    }
+}
+
+/// Approval identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[sqlx(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApprovalId(pub i64);
+
+impl fmt::Display for ApprovalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<i64> for ApprovalId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ApprovalId> for i64 {
+    fn from(value: ApprovalId) -> Self {
+        value.0
+    }
+}
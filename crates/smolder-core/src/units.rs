@@ -0,0 +1,201 @@
+//! Amounts of a network's native currency
+//!
+//! Values are sent and stored in the currency's smallest unit (wei on
+//! Ethereum), but are easier to write and read with a unit: `0.1 ether`,
+//! `0.1 ETH` or `30 gwei`. A bare integer, decimal or `0x` hex, is taken as
+//! the smallest unit, as before units were accepted.
+
+use alloy::primitives::utils;
+use alloy::primitives::U256;
+
+use crate::error::{Error, Result};
+
+/// Symbol and decimals of a network's native currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Currency<'a> {
+    pub symbol: &'a str,
+    pub decimals: u8,
+}
+
+impl Currency<'static> {
+    pub const ETHER: Self = Self {
+        symbol: "ETH",
+        decimals: 18,
+    };
+}
+
+/// Parse a decimal `amount` of a unit with `decimals` into the smallest unit,
+/// e.g. `parse_units("1.5", 9)` for 1.5 gwei in wei
+///
+/// Fails on negative amounts and on fractions finer than the smallest unit,
+/// which would otherwise be silently truncated.
+pub fn parse_units(amount: &str, decimals: u8) -> Result<U256> {
+    let amount = amount.trim();
+    let invalid = |reason: String| Error::invalid_param("value", reason);
+    if amount.starts_with('-') {
+        return Err(invalid(format!("'{}' is negative", amount)));
+    }
+
+    let fraction = amount
+        .split_once('.')
+        .map_or("", |(_, f)| f.trim_end_matches('0'));
+    if fraction.len() > decimals as usize {
+        return Err(invalid(format!(
+            "'{}' has more than {} decimals",
+            amount, decimals
+        )));
+    }
+
+    utils::parse_units(amount, decimals)
+        .map(|parsed| parsed.get_absolute())
+        .map_err(|e| invalid(format!("Invalid amount '{}': {}", amount, e)))
+}
+
+/// Parse a decimal amount of ether into wei, e.g. `parse_ether("0.1")`
+pub fn parse_ether(amount: &str) -> Result<U256> {
+    parse_units(amount, 18)
+}
+
+/// Parse an amount of `currency` into its smallest unit
+///
+/// Accepts a bare integer (`1000000` or `0xf4240`), or a number followed by
+/// `wei`, `gwei`, `ether` or the currency's symbol, in any case (`1.5 ether`,
+/// `0.2 POL`).
+pub fn parse_value(input: &str, currency: Currency<'_>) -> Result<U256> {
+    let input = input.trim();
+    let invalid = |reason: String| Error::invalid_param("value", reason);
+
+    // Hex digits are alphabetic, so don't look for a unit in them
+    if input.starts_with("0x") || input.starts_with("0X") {
+        return input
+            .parse::<U256>()
+            .map_err(|_| invalid(format!("'{}' is not a hex amount of wei", input)));
+    }
+
+    let split = input
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(input.len());
+    let (amount, unit) = (input[..split].trim(), input[split..].trim());
+    let decimals = match unit.to_lowercase().as_str() {
+        "" => {
+            return input.parse::<U256>().map_err(|_| {
+                invalid(format!(
+                    "'{}' is not an integer amount of wei; add a unit, e.g. '{} ether'",
+                    input, input
+                ))
+            })
+        }
+        "wei" => 0,
+        "gwei" => 9,
+        "ether" | "eth" => 18,
+        unit if unit == currency.symbol.to_lowercase() => currency.decimals,
+        _ => {
+            return Err(invalid(format!(
+                "Unknown unit '{}': use wei, gwei, ether or {}",
+                unit, currency.symbol
+            )))
+        }
+    };
+
+    parse_units(amount, decimals)
+}
+
+/// Format an amount in `currency`'s smallest unit, e.g. `1.5 ETH`
+pub fn format_value(value: U256, currency: Currency<'_>) -> String {
+    format!(
+        "{} {}",
+        format_units(value, currency.decimals),
+        currency.symbol
+    )
+}
+
+/// Format a gas price in wei as gwei, e.g. `1.5 gwei`
+pub fn format_gas_price(wei: u128) -> String {
+    format!("{} gwei", format_units(U256::from(wei), 9))
+}
+
+/// `value` in units with `decimals`, without trailing zeros
+fn format_units(value: U256, decimals: u8) -> String {
+    let formatted = utils::format_units(value, decimals).unwrap_or_else(|_| value.to_string());
+    match formatted.contains('.') {
+        true => formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string(),
+        false => formatted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POL: Currency<'static> = Currency {
+        symbol: "POL",
+        decimals: 18,
+    };
+
+    #[test]
+    fn test_parse_value() {
+        let ether = U256::from(10).pow(U256::from(18));
+        assert_eq!(
+            parse_value("1000", Currency::ETHER).unwrap(),
+            U256::from(1000)
+        );
+        assert_eq!(parse_value("1 ether", Currency::ETHER).unwrap(), ether);
+        assert_eq!(
+            parse_value("0.5ETH", Currency::ETHER).unwrap(),
+            ether / U256::from(2)
+        );
+        assert_eq!(
+            parse_value("30 Gwei", Currency::ETHER).unwrap(),
+            U256::from(30_000_000_000u64)
+        );
+        assert_eq!(parse_value("2 pol", POL).unwrap(), ether * U256::from(2));
+        assert_eq!(
+            parse_value("0x10", Currency::ETHER).unwrap(),
+            U256::from(16)
+        );
+        assert_eq!(
+            parse_value("0xDE0B6B3A7640000", Currency::ETHER).unwrap(),
+            ether
+        );
+
+        assert!(parse_value("0.1", Currency::ETHER).is_err());
+        assert!(parse_value("-1 ether", Currency::ETHER).is_err());
+        assert!(parse_value("1 POL", Currency::ETHER).is_err());
+        assert!(parse_value("1.5 wei", Currency::ETHER).is_err());
+        assert!(parse_value("0x10 ether", Currency::ETHER).is_err());
+        assert!(parse_value("0xzz", Currency::ETHER).is_err());
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("1.5", 9).unwrap(), U256::from(1_500_000_000u64));
+        assert_eq!(parse_units("2", 0).unwrap(), U256::from(2));
+        assert_eq!(
+            parse_ether("0.1").unwrap(),
+            U256::from(100_000_000_000_000_000u64)
+        );
+        assert!(parse_units("0.5", 0).is_err());
+        assert!(parse_ether("-1").is_err());
+        assert!(parse_ether("one").is_err());
+    }
+
+    #[test]
+    fn test_format_value() {
+        let ether = U256::from(10).pow(U256::from(18));
+        assert_eq!(
+            format_value(ether * U256::from(3) / U256::from(2), Currency::ETHER),
+            "1.5 ETH"
+        );
+        assert_eq!(format_value(U256::ZERO, POL), "0 POL");
+        assert_eq!(
+            format_value(U256::from(1), Currency::ETHER),
+            "0.000000000000000001 ETH"
+        );
+        assert_eq!(format_gas_price(30_000_000_000), "30 gwei");
+        assert_eq!(format_gas_price(1_500_000_000), "1.5 gwei");
+        assert_eq!(format_gas_price(1), "0.000000001 gwei");
+    }
+}
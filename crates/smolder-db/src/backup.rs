@@ -0,0 +1,142 @@
+//! Online backup and restore through SQLite's backup API
+//!
+//! Unlike copying the database file, the backup API produces a consistent
+//! snapshot even while other connections (e.g. `smolder serve`) are writing.
+
+use std::ffi::{c_int, CStr, CString};
+use std::path::Path;
+use std::ptr::{self, NonNull};
+
+use libsqlite3_sys as ffi;
+use smolder_core::{Error, Result, Secret};
+
+use crate::sql_string;
+
+/// A raw SQLite connection to a file outside the pool, closed on drop
+pub(crate) struct RawConnection(NonNull<ffi::sqlite3>);
+
+impl RawConnection {
+    /// Open the file for reading
+    pub(crate) fn open_read_only(path: &Path) -> Result<Self> {
+        Self::open(path, ffi::SQLITE_OPEN_READONLY)
+    }
+
+    /// Open the file for writing, creating it if missing
+    pub(crate) fn open_read_write(path: &Path) -> Result<Self> {
+        Self::open(path, ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE)
+    }
+
+    fn open(path: &Path, flags: c_int) -> Result<Self> {
+        let c_path = path
+            .to_str()
+            .and_then(|p| CString::new(p).ok())
+            .ok_or_else(|| Error::Io(format!("Invalid path: {}", path.display())))?;
+
+        let mut handle = ptr::null_mut();
+        // SAFETY: c_path is a valid NUL-terminated string and handle is a valid
+        // out-pointer. SQLite allocates a handle even on failure, which is
+        // closed below.
+        let rc = unsafe { ffi::sqlite3_open_v2(c_path.as_ptr(), &mut handle, flags, ptr::null()) };
+
+        let Some(handle) = NonNull::new(handle) else {
+            return Err(Error::Io(
+                "SQLite could not allocate a connection".to_string(),
+            ));
+        };
+        let conn = Self(handle);
+
+        if rc != ffi::SQLITE_OK {
+            return Err(Error::Io(format!(
+                "Could not open {}: {}",
+                path.display(),
+                conn.error_message()
+            )));
+        }
+
+        Ok(conn)
+    }
+
+    /// Run SQL that returns no rows
+    pub(crate) fn execute(&self, sql: &str) -> Result<()> {
+        let c_sql = CString::new(sql).map_err(|e| Error::Io(e.to_string()))?;
+        // SAFETY: the handle is open and c_sql is NUL-terminated; no callback
+        // or error out-pointer is passed
+        let rc = unsafe {
+            ffi::sqlite3_exec(
+                self.as_ptr(),
+                c_sql.as_ptr(),
+                None,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+
+        if rc != ffi::SQLITE_OK {
+            return Err(Error::Io(format!("SQLite error: {}", self.error_message())));
+        }
+        Ok(())
+    }
+
+    /// Give SQLCipher the key of an encrypted database; must come before
+    /// anything else is run on the connection
+    pub(crate) fn set_key(&self, key: Option<&Secret<String>>) -> Result<()> {
+        match key {
+            Some(key) => self.execute(&format!("PRAGMA key = {}", sql_string(key.expose()))),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut ffi::sqlite3 {
+        self.0.as_ptr()
+    }
+
+    fn error_message(&self) -> String {
+        error_message(self.as_ptr())
+    }
+}
+
+impl Drop for RawConnection {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from sqlite3_open_v2 and is closed exactly once
+        unsafe {
+            ffi::sqlite3_close(self.as_ptr());
+        }
+    }
+}
+
+/// Copy the `main` database of `source` over the `main` database of `dest`
+///
+/// # Safety
+///
+/// Both handles must be open connections that are not used concurrently from
+/// another thread for the duration of the call.
+pub(crate) unsafe fn copy_database(
+    source: *mut ffi::sqlite3,
+    dest: *mut ffi::sqlite3,
+) -> Result<()> {
+    let main = c"main";
+
+    let backup = ffi::sqlite3_backup_init(dest, main.as_ptr(), source, main.as_ptr());
+    if backup.is_null() {
+        return Err(Error::Io(format!("Backup failed: {}", error_message(dest))));
+    }
+
+    // Copy every page in one step; the registry is small enough that holding
+    // the source read lock briefly is cheaper than incremental copying
+    let step = ffi::sqlite3_backup_step(backup, -1);
+    let finish = ffi::sqlite3_backup_finish(backup);
+
+    if step != ffi::SQLITE_DONE || finish != ffi::SQLITE_OK {
+        return Err(Error::Io(format!("Backup failed: {}", error_message(dest))));
+    }
+
+    Ok(())
+}
+
+fn error_message(handle: *mut ffi::sqlite3) -> String {
+    // SAFETY: sqlite3_errmsg returns a NUL-terminated string owned by the
+    // connection, copied out before any further call on it
+    unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(handle)) }
+        .to_string_lossy()
+        .into_owned()
+}
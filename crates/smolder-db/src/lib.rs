@@ -1,7 +1,12 @@
 //! SQLite database implementation for Smolder
 //!
 //! This crate provides the [`Database`] struct which implements all repository
-//! traits, backed by SQLite.
+//! traits, backed by SQLite, and [`MemoryDatabase`], an in-memory
+//! implementation of the same traits for tests.
+//!
+//! With the `encrypted-db` feature, SQLite is replaced by SQLCipher and the
+//! whole database file can be encrypted with a passphrase, given through
+//! [`DB_KEY_ENV`] or [`Database::unlock`].
 //!
 //! # Usage
 //!
@@ -14,11 +19,15 @@
 //! let network = NetworkRepository::upsert(&db, &NewNetwork { ... }).await?;
 //! ```
 
+mod backup;
+mod memory;
 pub mod models;
 mod repositories;
 mod schema;
 pub mod traits;
 
+pub use memory::MemoryDatabase;
+
 // Re-export models for convenience
 pub use models::*;
 
@@ -27,20 +36,87 @@ pub use traits::*;
 
 // Re-export types from smolder-core for convenience
 pub use smolder_core::types::{
-    CallType, ChainId, ContractId, DeploymentId, NetworkId, TransactionStatus, WalletId,
+    ApiRole, ApiTokenId, ApprovalId, ApprovalKind, ApprovalStatus, AuditAction, AuditSource,
+    CallType, ChainId, ContractId, DeliveryStatus, DeploymentGroupId, DeploymentId, EnsLookup,
+    Finality, JobState, NetworkId, RecordKind, SelectorSource, TokenStandard, TransactionStatus,
+    WalletId, WebhookId, WebhookTrigger,
 };
 
-use smolder_core::{Result, SmolderDir};
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use smolder_core::{Error, Result, Secret, SmolderDir};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePool, SqlitePoolOptions,
+    SqliteSynchronous,
+};
+use sqlx::Connection;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 /// The database filename within the smolder directory
 pub const DB_FILENAME: &str = "smolder.db";
 
+/// Environment variable holding the passphrase of an encrypted database
+pub const DB_KEY_ENV: &str = "SMOLDER_DB_KEY";
+
+/// First bytes of every plaintext SQLite database; SQLCipher encrypts them
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Passphrase given to [`Database::unlock`]
+static UNLOCKED: OnceLock<Secret<String>> = OnceLock::new();
+
+/// Connection pool and SQLite settings
+///
+/// The defaults suit `smolder serve`, where handlers write concurrently: WAL
+/// lets readers proceed during a write, and the busy timeout makes writers
+/// wait for the lock instead of failing with `database is locked`.
+#[derive(Debug, Clone)]
+pub struct DatabaseOptions {
+    /// Maximum number of pooled read connections; writes always go through a
+    /// single connection, since SQLite allows one writer at a time anyway
+    pub max_connections: u32,
+    /// Prepared statements kept per connection, so repeated repository
+    /// queries skip parsing and planning
+    pub statement_cache_capacity: usize,
+    /// How long a connection waits for a lock held by another before failing
+    pub busy_timeout: Duration,
+    pub journal_mode: SqliteJournalMode,
+    pub synchronous: SqliteSynchronous,
+    /// Passphrase of a database encrypted with SQLCipher, which needs the
+    /// `encrypted-db` feature. Defaults to the one given to
+    /// [`Database::unlock`], else to [`DB_KEY_ENV`]
+    pub key: Option<Secret<String>>,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 8,
+            statement_cache_capacity: 100,
+            busy_timeout: Duration::from_secs(5),
+            journal_mode: SqliteJournalMode::Wal,
+            // Safe with WAL: only the last commits can be lost on power
+            // failure, and the file is never corrupted
+            synchronous: SqliteSynchronous::Normal,
+            key: UNLOCKED.get().cloned().or_else(|| {
+                std::env::var(DB_KEY_ENV)
+                    .ok()
+                    .filter(|key| !key.is_empty())
+                    .map(Secret::new)
+            }),
+        }
+    }
+}
+
 /// SQLite database connection and repository implementation
+///
+/// Holds two pools: a single-connection writer and a read-only pool, so long
+/// reads (dashboard listings, exports) never queue behind writes. Repository
+/// methods that modify data use the writer, everything else the readers.
 pub struct Database {
-    pool: SqlitePool,
+    writer: SqlitePool,
+    reader: SqlitePool,
+    key: Option<Secret<String>>,
 }
 
 impl Database {
@@ -50,6 +126,28 @@ impl Database {
         dir.join(DB_FILENAME).exists()
     }
 
+    /// Whether this build can open databases encrypted with SQLCipher
+    pub const fn supports_encryption() -> bool {
+        cfg!(feature = "encrypted-db")
+    }
+
+    /// Whether the file at `path` is an encrypted database, rather than a
+    /// plaintext one or none at all
+    pub fn is_encrypted<P: AsRef<Path>>(path: P) -> bool {
+        use std::io::Read;
+
+        let mut header = [0u8; 16];
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut header))
+            .is_ok_and(|()| &header != SQLITE_HEADER)
+    }
+
+    /// Open encrypted databases with `passphrase` from now on, for one
+    /// prompted for rather than set in [`DB_KEY_ENV`]
+    pub fn unlock(passphrase: String) {
+        let _ = UNLOCKED.set(Secret::new(passphrase));
+    }
+
     /// Connect to the default database file (`.smolder/smolder.db`)
     pub async fn connect() -> Result<Self> {
         let dir = SmolderDir::new();
@@ -63,40 +161,290 @@ impl Database {
     }
 
     /// Connect to a specific database file
+    ///
+    /// Pending schema migrations are applied to existing databases so that
+    /// callers always see the current schema.
     pub async fn connect_to<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::connect_to_with_options(path, &DatabaseOptions::default()).await
+    }
+
+    /// Connect to a specific database file with custom pool and SQLite settings
+    pub async fn connect_to_with_options<P: AsRef<Path>>(
+        path: P,
+        db_options: &DatabaseOptions,
+    ) -> Result<Self> {
+        if db_options.key.is_some() && !Self::supports_encryption() {
+            return Err(Error::Validation(format!(
+                "A database passphrase was given, but smolder was built without the \
+                 encrypted-db feature; unset {}",
+                DB_KEY_ENV
+            )));
+        }
         let path_str = path.as_ref().to_str().unwrap_or(".smolder/smolder.db");
-        let options = SqliteConnectOptions::from_str(path_str)
+        let mut options = SqliteConnectOptions::from_str(path_str)
             .map_err(smolder_core::Error::Database)?
-            .create_if_missing(true)
-            .foreign_keys(true);
+            .foreign_keys(true)
+            .journal_mode(db_options.journal_mode)
+            .synchronous(db_options.synchronous)
+            .busy_timeout(db_options.busy_timeout)
+            .statement_cache_capacity(db_options.statement_cache_capacity);
+        // SQLCipher needs the key before anything else; sqlx sends it first
+        if let Some(key) = &db_options.key {
+            options = options.pragma("key", sql_string(key.expose()));
+        }
 
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect_with(options)
-            .await?;
+        let encrypted = Self::is_encrypted(path.as_ref());
+        let locked = || {
+            Error::Validation(format!(
+                "{} is encrypted; the passphrase is wrong or missing (set {})",
+                path_str, DB_KEY_ENV
+            ))
+        };
+        let writer = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options.clone().create_if_missing(true))
+            .await
+            .map_err(|e| match encrypted {
+                true => locked(),
+                false => e.into(),
+            })?;
+
+        // Every connection to `:memory:` opens a separate database, so an
+        // in-memory registry has to share its single connection
+        let reader = if path_str == ":memory:" {
+            writer.clone()
+        } else {
+            SqlitePoolOptions::new()
+                .max_connections(db_options.max_connections)
+                .connect_with(options.read_only(true))
+                .await?
+        };
+
+        let db = Self {
+            writer,
+            reader,
+            key: db_options.key.clone(),
+        };
+        let has_schema = db.has_schema().await.map_err(|e| match encrypted {
+            true => locked(),
+            false => e,
+        })?;
+        if has_schema {
+            db.init_schema().await?;
+        }
 
-        Ok(Self { pool })
+        Ok(db)
     }
 
     /// Initialize the database schema
     pub async fn init_schema(&self) -> Result<()> {
-        schema::init_schema(&self.pool).await?;
+        schema::init_schema(&self.writer).await?;
+        Ok(())
+    }
+
+    /// Get the schema version (number of applied migrations)
+    pub async fn schema_version(&self) -> Result<i64> {
+        schema::schema_version(&self.reader).await
+    }
+
+    /// Get the schema version this build of smolder expects
+    pub fn latest_schema_version() -> i64 {
+        schema::MIGRATIONS.len() as i64
+    }
+
+    /// Write a consistent snapshot of the database to `path`
+    ///
+    /// Uses SQLite's backup API rather than a file copy, so it is safe while
+    /// other connections are writing.
+    pub async fn backup_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let dest = backup::RawConnection::open_read_write(path.as_ref())?;
+        // Snapshots of an encrypted database are encrypted with its key
+        dest.set_key(self.key.as_ref())?;
+
+        let mut conn = self.reader.acquire().await?;
+        let mut handle = conn.lock_handle().await?;
+
+        // SAFETY: the pooled connection stays locked for the whole copy and
+        // `dest` is only used here
+        unsafe { backup::copy_database(handle.as_raw_handle().as_ptr(), dest.as_ptr())? };
+
+        // The copy inherits WAL mode from the live database; switch it back so
+        // the snapshot is a single self-contained file
+        dest.execute("PRAGMA journal_mode = DELETE")
+    }
+
+    /// Replace the database contents with the snapshot at `path`
+    ///
+    /// The snapshot must be a smolder database whose schema is not newer than
+    /// this build; older snapshots are migrated once restored.
+    pub async fn restore_from<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(Error::FileNotFound(path.display().to_string()));
+        }
+        self.check_snapshot(path).await?;
+
+        let source = backup::RawConnection::open_read_only(path)?;
+        source.set_key(self.key.as_ref())?;
+        {
+            let mut conn = self.writer.acquire().await?;
+            let mut handle = conn.lock_handle().await?;
+
+            // SAFETY: as in `backup_to`, with the roles swapped
+            unsafe { backup::copy_database(source.as_ptr(), handle.as_raw_handle().as_ptr())? };
+        }
+
+        self.init_schema().await
+    }
+
+    /// Rebuild the database file, returning the space freed by deletes to the
+    /// filesystem
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM").execute(&self.writer).await?;
+        Ok(())
+    }
+
+    /// Write an encrypted copy of the database to `path`, for moving a
+    /// plaintext database to SQLCipher
+    ///
+    /// Needs the `encrypted-db` feature.
+    pub async fn export_encrypted<P: AsRef<Path>>(&self, path: P, key: &str) -> Result<()> {
+        if !Self::supports_encryption() {
+            return Err(Error::Validation(
+                "smolder was built without the encrypted-db feature".to_string(),
+            ));
+        }
+        let path = path.as_ref();
+        let version = self.schema_version().await?;
+
+        let mut conn = self.writer.acquire().await?;
+        sqlx::query("ATTACH DATABASE ? AS encrypted KEY ?")
+            .bind(path.to_string_lossy())
+            .bind(key)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("SELECT sqlcipher_export('encrypted')")
+            .fetch_all(&mut *conn)
+            .await?;
+        // sqlcipher_export copies the schema and rows, not the migrations run
+        sqlx::raw_sql(&format!("PRAGMA encrypted.user_version = {}", version))
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("DETACH DATABASE encrypted")
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Make sure a file is a smolder database this build can restore
+    async fn check_snapshot(&self, path: &Path) -> Result<()> {
+        let mut options = SqliteConnectOptions::new().filename(path).read_only(true);
+        if let Some(key) = &self.key {
+            options = options.pragma("key", sql_string(key.expose()));
+        }
+        let not_smolder =
+            || Error::Validation(format!("{} is not a smolder database", path.display()));
+
+        let mut conn = SqliteConnection::connect_with(&options)
+            .await
+            .map_err(|_| not_smolder())?;
+
+        let has_schema: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'networks')",
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|_| not_smolder())?;
+        if !has_schema {
+            return Err(not_smolder());
+        }
+
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+            .fetch_one(&mut conn)
+            .await?;
+        if version > Self::latest_schema_version() {
+            return Err(Error::Validation(format!(
+                "{} uses schema version {}, newer than this build of smolder supports ({})",
+                path.display(),
+                version,
+                Self::latest_schema_version()
+            )));
+        }
+
+        conn.close().await?;
+        Ok(())
+    }
+
+    /// Check whether the schema has been initialized
+    async fn has_schema(&self) -> Result<bool> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'networks')",
+        )
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(exists)
+    }
+
+    /// Run a trivial query on both pools to check the database is reachable
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.reader).await?;
+        sqlx::query("SELECT 1").execute(&self.writer).await?;
         Ok(())
     }
 
-    /// Get a reference to the underlying connection pool
-    pub fn pool(&self) -> &SqlitePool {
-        &self.pool
+    /// Close both pools, waiting for checked-out connections to be returned
+    pub async fn close(&self) {
+        self.reader.close().await;
+        self.writer.close().await;
+    }
+
+    /// Get the read-only pool, for ad-hoc queries
+    pub fn read_pool(&self) -> &SqlitePool {
+        &self.reader
+    }
+
+    /// Get the single-connection pool used for writes
+    pub fn write_pool(&self) -> &SqlitePool {
+        &self.writer
     }
 }
 
+/// Quote `value` as an SQL string literal
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{NewContract, NewDeployment, NewNetwork};
+    use crate::models::{
+        ActivityKind, Deployment, DeploymentView, ImportStatus, NetworkUpdate, NewApproval,
+        NewAuditEntry, NewBatch, NewCallHistory, NewContract, NewContractDeployment, NewDeployment,
+        NewDeploymentGroup, NewEnsRecord, NewGasSample, NewJob, NewNetwork, NewSelector,
+        NewSignature, NewTemplate, NewWallet, NewWebhook, NewWebhookDelivery, TemplateParam,
+        WebhookDeliveryUpdate,
+    };
     use crate::traits::{
-        ContractRepository, DeploymentFilter, DeploymentRepository, NetworkRepository,
+        AnalyticsRepository, ApiTokenRepository, ApprovalRepository, AuditRepository,
+        BatchRepository, CallHistoryFilter, CallHistoryRepository, ContractFilter,
+        ContractRepository, CostFilter, DeploymentFilter, DeploymentGroupRepository,
+        DeploymentRepository, DeploymentSort, EnsCacheRepository, GasInterval, GasSampleRepository,
+        GasStatsFilter, HistoryRetention, IdempotencyRepository, JobFilter, JobRepository,
+        NetworkRepository, ReorgRepository, SelectorRepository, SignatureRepository, SortOrder,
+        TemplateRepository, TokenMetadataRepository, WalletPolicyRepository, WalletRepository,
+        WebhookRepository,
     };
+    use smolder_core::{BytecodeHash, Currency, EvmAddress, TxHash};
+
+    /// A full-length address from a short one like `0xaaa`, keeping its case
+    fn addr(short: &str) -> String {
+        format!("0x{:0>40}", &short[2..])
+    }
+
+    /// A full-length transaction hash from a short one like `0x111`
+    fn tx(short: &str) -> TxHash {
+        format!("0x{:0>64}", &short[2..]).parse().unwrap()
+    }
 
     async fn setup_test_db() -> Database {
         let db = Database::connect_to(":memory:").await.unwrap();
@@ -139,6 +487,10 @@ mod tests {
         };
 
         let created1 = NetworkRepository::upsert(&db, &network1).await.unwrap();
+        assert!(!created1.impersonation);
+        NetworkRepository::set_impersonation(&db, "tempo", true)
+            .await
+            .unwrap();
 
         let network2 = NewNetwork {
             name: "tempo".to_string(),
@@ -158,6 +510,74 @@ mod tests {
             .unwrap();
         assert_eq!(fetched.chain_id, ChainId(200));
         assert_eq!(fetched.rpc_url, "https://new.rpc");
+        // The capability survives the upsert
+        assert!(fetched.impersonation);
+
+        NetworkRepository::set_protected(&db, "tempo", true)
+            .await
+            .unwrap();
+        assert!(
+            NetworkRepository::get_by_name(&db, "tempo")
+                .await
+                .unwrap()
+                .unwrap()
+                .protected
+        );
+        assert!(NetworkRepository::set_impersonation(&db, "missing", true)
+            .await
+            .is_err());
+
+        NetworkRepository::set_locked(&db, "tempo", true)
+            .await
+            .unwrap();
+        NetworkRepository::upsert(&db, &network2).await.unwrap();
+        assert!(
+            NetworkRepository::get_by_name(&db, "tempo")
+                .await
+                .unwrap()
+                .unwrap()
+                .locked
+        );
+
+        NetworkRepository::set_private_relay(&db, "tempo", Some("https://rpc.flashbots.net"))
+            .await
+            .unwrap();
+        NetworkRepository::upsert(&db, &network2).await.unwrap();
+        let fetched = NetworkRepository::get_by_name(&db, "tempo")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            fetched.private_relay.as_deref(),
+            Some("https://rpc.flashbots.net")
+        );
+
+        let polygon = NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "polygon".to_string(),
+                chain_id: ChainId(137),
+                rpc_url: "https://polygon.rpc".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(polygon.currency_symbol, "POL");
+
+        let custom = Currency {
+            symbol: "TMP",
+            decimals: 6,
+        };
+        NetworkRepository::set_currency(&db, "tempo", custom)
+            .await
+            .unwrap();
+        NetworkRepository::upsert(&db, &network2).await.unwrap();
+        let fetched = NetworkRepository::get_by_name(&db, "tempo")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.currency(), custom);
     }
 
     #[tokio::test]
@@ -194,6 +614,28 @@ mod tests {
         assert_eq!(networks[1].name, "beta");
     }
 
+    #[tokio::test]
+    async fn test_delete_network() {
+        let db = setup_test_db().await;
+
+        NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "doomed".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://doomed".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        NetworkRepository::delete(&db, "doomed").await.unwrap();
+
+        let fetched = NetworkRepository::get_by_name(&db, "doomed").await.unwrap();
+        assert!(fetched.is_none());
+    }
+
     #[tokio::test]
     async fn test_upsert_and_get_contract() {
         let db = setup_test_db().await;
@@ -202,7 +644,9 @@ mod tests {
             name: "MyToken".to_string(),
             source_path: "src/MyToken.sol:MyToken".to_string(),
             abi: r#"[{"type":"function","name":"transfer"}]"#.to_string(),
-            bytecode_hash: "0xabc123".to_string(),
+            bytecode_hash: BytecodeHash::of("0xabc123"),
+            storage_layout: None,
+            compiler: None,
         };
 
         let created = ContractRepository::upsert(&db, &contract).await.unwrap();
@@ -214,6 +658,31 @@ mod tests {
             .unwrap();
         assert_eq!(fetched.name, "MyToken");
         assert_eq!(fetched.source_path, "src/MyToken.sol:MyToken");
+        assert!(fetched.storage_layout.is_none());
+
+        // A layout and compiler settings are recorded once known, and kept
+        // by records without them
+        let layout = r#"{"storage":[],"types":{}}"#.to_string();
+        let compiler = smolder_core::CompilerSettings {
+            version: "0.8.24+commit.e11b9ed9".to_string(),
+            optimizer_enabled: true,
+            optimizer_runs: Some(200),
+            ..Default::default()
+        };
+        ContractRepository::upsert(
+            &db,
+            &NewContract {
+                storage_layout: Some(layout.clone()),
+                compiler: Some(compiler.clone()),
+                ..contract.clone()
+            },
+        )
+        .await
+        .unwrap();
+        let updated = ContractRepository::upsert(&db, &contract).await.unwrap();
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.storage_layout, Some(layout));
+        assert_eq!(updated.compiler, Some(compiler));
     }
 
     #[tokio::test]
@@ -238,7 +707,9 @@ mod tests {
                 name: "Token".to_string(),
                 source_path: "src/Token.sol".to_string(),
                 abi: "[]".to_string(),
-                bytecode_hash: "0x123".to_string(),
+                bytecode_hash: BytecodeHash::of("0x123"),
+                storage_layout: None,
+                compiler: None,
             },
         )
         .await
@@ -250,11 +721,15 @@ mod tests {
             &NewDeployment {
                 contract_id: contract.id,
                 network_id: network.id,
-                address: "0xaaa".to_string(),
+                address: addr("0xaaa").parse().unwrap(),
                 deployer: "0xddd".to_string(),
-                tx_hash: "0x111".to_string(),
+                tx_hash: Some(tx("0x111")),
                 block_number: Some(100),
                 constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
             },
         )
         .await
@@ -266,11 +741,15 @@ mod tests {
             &NewDeployment {
                 contract_id: contract.id,
                 network_id: network.id,
-                address: "0xbbb".to_string(),
+                address: addr("0xbbb").parse().unwrap(),
                 deployer: "0xddd".to_string(),
-                tx_hash: "0x222".to_string(),
+                tx_hash: Some(tx("0x222")),
                 block_number: Some(200),
                 constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
             },
         )
         .await
@@ -281,7 +760,7 @@ mod tests {
             .unwrap()
             .unwrap();
 
-        assert_eq!(current.address, "0xbbb");
+        assert_eq!(current.address, addr("0xbbb").as_str());
         assert_eq!(current.version, 2);
         assert!(current.is_current);
     }
@@ -320,7 +799,9 @@ mod tests {
                 name: "Token".to_string(),
                 source_path: "src/Token.sol".to_string(),
                 abi: "[]".to_string(),
-                bytecode_hash: "0x123".to_string(),
+                bytecode_hash: BytecodeHash::of("0x123"),
+                storage_layout: None,
+                compiler: None,
             },
         )
         .await
@@ -331,11 +812,15 @@ mod tests {
             &NewDeployment {
                 contract_id: contract.id,
                 network_id: net1.id,
-                address: "0x111".to_string(),
+                address: addr("0x111").parse().unwrap(),
                 deployer: "0xddd".to_string(),
-                tx_hash: "0xaaa".to_string(),
+                tx_hash: Some(tx("0xaaa")),
                 block_number: None,
                 constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
             },
         )
         .await
@@ -346,11 +831,15 @@ mod tests {
             &NewDeployment {
                 contract_id: contract.id,
                 network_id: net2.id,
-                address: "0x222".to_string(),
+                address: addr("0x222").parse().unwrap(),
                 deployer: "0xddd".to_string(),
-                tx_hash: "0xbbb".to_string(),
+                tx_hash: Some(tx("0xbbb")),
                 block_number: None,
                 constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
             },
         )
         .await
@@ -377,14 +866,2987 @@ mod tests {
         assert_eq!(net2_only[0].network_name, "net2");
     }
 
+    /// Create a network and contract with two deployments (v1 and v2)
+    async fn setup_versioned_deployments(db: &Database) -> (Deployment, Deployment) {
+        let network = NetworkRepository::upsert(
+            db,
+            &NewNetwork {
+                name: "testnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://rpc".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let contract = ContractRepository::upsert(
+            db,
+            &NewContract {
+                name: "Token".to_string(),
+                source_path: "src/Token.sol".to_string(),
+                abi: "[]".to_string(),
+                bytecode_hash: BytecodeHash::of("0x123"),
+                storage_layout: None,
+                compiler: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut created = Vec::new();
+        for (address, tx_hash) in [("0xaaa", "0x111"), ("0xbbb", "0x222")] {
+            let deployment = DeploymentRepository::create(
+                db,
+                &NewDeployment {
+                    contract_id: contract.id,
+                    network_id: network.id,
+                    address: addr(address).parse().unwrap(),
+                    deployer: "0xddd".to_string(),
+                    tx_hash: Some(tx(tx_hash)),
+                    block_number: None,
+                    constructor_args: None,
+                    initcode_size: None,
+                    runtime_size: None,
+                    gas_used: None,
+                    gas_price: None,
+                },
+            )
+            .await
+            .unwrap();
+            created.push(deployment);
+        }
+
+        let v2 = created.pop().unwrap();
+        let v1 = created.pop().unwrap();
+        (v1, v2)
+    }
+
     #[tokio::test]
-    async fn test_get_current_deployment_not_found() {
+    async fn test_deployment_exists_by_address() {
         let db = setup_test_db().await;
+        let (v1, _) = setup_versioned_deployments(&db).await;
 
-        let result = DeploymentRepository::get_current(&db, "NonExistent", "testnet")
+        assert!(
+            DeploymentRepository::exists_by_address(&db, v1.network_id, &addr("0xAAA"))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !DeploymentRepository::exists_by_address(&db, v1.network_id, &addr("0xccc"))
+                .await
+                .unwrap()
+        );
+        assert!(!DeploymentRepository::exists_by_address(
+            &db,
+            NetworkId(v1.network_id.0 + 1),
+            &addr("0xaaa")
+        )
+        .await
+        .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_hash_migration_keeps_unprefixed_hashes() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
             .await
             .unwrap();
+        sqlx::raw_sql(schema::SCHEMA).execute(&pool).await.unwrap();
+        for migration in &schema::MIGRATIONS[..12] {
+            sqlx::raw_sql(migration).execute(&pool).await.unwrap();
+        }
+        let unprefixed = "AB".repeat(32);
+        sqlx::raw_sql(&format!(
+            r#"
+            INSERT INTO networks (name, chain_id, rpc_url) VALUES ('testnet', 1, 'https://rpc');
+            INSERT INTO contracts (name, source_path, abi, bytecode_hash)
+                VALUES ('Token', 'src/Token.sol', '[]', '0x{1}');
+            INSERT INTO deployments (contract_id, network_id, address, deployer, tx_hash)
+                VALUES (1, 1, '0x01', '0x02', '{0}'), (1, 1, '0x03', '0x02', 'nonsense');
+            INSERT INTO call_history
+                (deployment_id, function_name, function_signature, input_params, call_type, tx_hash)
+                VALUES (1, 'pause', 'pause()', '[]', 'write', '{0}'),
+                    (1, 'pause', 'pause()', '[]', 'write', '0x12');
+            "#,
+            unprefixed,
+            "00".repeat(32)
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
 
-        assert!(result.is_none());
+        sqlx::raw_sql(schema::MIGRATIONS[12])
+            .execute(&pool)
+            .await
+            .unwrap();
+        let expected = format!("0x{}", unprefixed.to_lowercase());
+        let deployments: Vec<String> =
+            sqlx::query_scalar("SELECT tx_hash FROM deployments ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(deployments, vec![expected.clone(), String::new()]);
+        let calls: Vec<Option<String>> =
+            sqlx::query_scalar("SELECT tx_hash FROM call_history ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(calls, vec![Some(expected), None]);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_hashes_are_rejected() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        let err = sqlx::query("UPDATE contracts SET bytecode_hash = 'abc'")
+            .execute(&db.writer)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("malformed bytecode_hash"));
+        let err = sqlx::query("UPDATE deployments SET tx_hash = '0x12' WHERE id = ?")
+            .bind(v1.id)
+            .execute(&db.writer)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("malformed tx_hash"));
+
+        // An empty hash is a deployment recorded without its transaction
+        sqlx::query("UPDATE deployments SET tx_hash = '' WHERE id = ?")
+            .bind(v1.id)
+            .execute(&db.writer)
+            .await
+            .unwrap();
+        let v1 = DeploymentRepository::get_by_id(&db, v1.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(v1.tx_hash, None);
+        assert!(DeploymentRepository::exists_by_tx_hash(&db, &tx("0x222"))
+            .await
+            .unwrap());
+        assert_eq!(v2.tx_hash, Some(tx("0x222")));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_by_address_ignores_case() {
+        let db = setup_test_db().await;
+        let checksummed = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+        WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "deployer".to_string(),
+                address: checksummed.to_lowercase().parse().unwrap(),
+                encrypted_key: vec![1, 2, 3],
+            },
+        )
+        .await
+        .unwrap();
+
+        // Stored checksummed, found in any casing
+        for address in [
+            checksummed.to_string(),
+            checksummed.to_lowercase(),
+            checksummed.to_uppercase().replace("0X", "0x"),
+        ] {
+            let wallet = WalletRepository::get_by_address(&db, &address)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(wallet.address.to_string(), checksummed);
+        }
+        let stored: String = sqlx::query_scalar("SELECT address FROM wallets")
+            .fetch_one(&db.reader)
+            .await
+            .unwrap();
+        assert_eq!(stored, checksummed);
+
+        // The same address in another case is taken
+        let insert = |name: &'static str, address: String| {
+            sqlx::query("INSERT INTO wallets (name, address, encrypted_key) VALUES (?, ?, x'01')")
+                .bind(name)
+                .bind(address)
+                .execute(db.write_pool())
+        };
+        assert!(insert("copy", checksummed.to_lowercase()).await.is_err());
+
+        // Addresses written in lowercase before checksumming are rewritten
+        let legacy = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+        insert("legacy", legacy.to_string()).await.unwrap();
+        db.init_schema().await.unwrap();
+        let wallet = WalletRepository::get_by_name(&db, "legacy")
+            .await
+            .unwrap()
+            .unwrap();
+        let stored: String = sqlx::query_scalar("SELECT address FROM wallets WHERE id = ?")
+            .bind(wallet.id)
+            .fetch_one(&db.reader)
+            .await
+            .unwrap();
+        assert_eq!(stored, "0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+    }
+
+    #[tokio::test]
+    async fn test_record_upgrade() {
+        let db = setup_test_db().await;
+        let (_, proxy) = setup_versioned_deployments(&db).await;
+
+        let upgrade = |name: &str, proxy_id: DeploymentId| NewUpgrade {
+            network_id: proxy.network_id,
+            implementation: NewContractDeployment {
+                contract: NewContract {
+                    name: name.to_string(),
+                    source_path: "src/TokenV2.sol".to_string(),
+                    abi: "[]".to_string(),
+                    bytecode_hash: BytecodeHash::of("0x456"),
+                    storage_layout: None,
+                    compiler: None,
+                },
+                address: addr("0xccc").parse().unwrap(),
+                deployer: "0xddd".to_string(),
+                tx_hash: Some(tx("0x333")),
+                block_number: Some(10),
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: Some(500_000),
+                gas_price: None,
+            },
+            call: NewCallHistory {
+                deployment_id: proxy_id,
+                wallet_id: None,
+                function_name: "upgradeToAndCall".to_string(),
+                function_signature: "upgradeToAndCall(address,bytes)".to_string(),
+                input_params: r#"["0xccc","0x"]"#.to_string(),
+                call_type: CallType::Write,
+            },
+            outcome: CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(tx("0x444")),
+                block_number: Some(11),
+                gas_used: Some(40_000),
+                gas_price: None,
+                status: TransactionStatus::Success,
+                error_message: None,
+            },
+        };
+
+        let (implementation, call) =
+            DeploymentRepository::record_upgrade(&db, &upgrade("TokenV2", proxy.id))
+                .await
+                .unwrap();
+        assert_eq!(implementation.version, 1);
+        assert!(implementation.is_current);
+        assert_eq!(call.deployment_id, proxy.id);
+        assert_eq!(call.tx_hash, Some(tx("0x444")));
+        assert_eq!(call.status, Some(TransactionStatus::Success));
+
+        let found = DeploymentRepository::get_by_address(&db, proxy.network_id, &addr("0xCCC"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, implementation.id);
+
+        // A call against an unknown proxy leaves no implementation behind
+        assert!(
+            DeploymentRepository::record_upgrade(&db, &upgrade("TokenV3", DeploymentId(999)))
+                .await
+                .is_err()
+        );
+        assert!(DeploymentRepository::get_current(&db, "TokenV3", "testnet")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_current_deployment_promotes_previous() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        CallHistoryRepository::create(
+            &db,
+            &NewCallHistory {
+                deployment_id: v2.id,
+                wallet_id: None,
+                function_name: "transfer".to_string(),
+                function_signature: "transfer(address,uint256)".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Write,
+            },
+        )
+        .await
+        .unwrap();
+
+        DeploymentRepository::delete(&db, v2.id).await.unwrap();
+
+        assert!(DeploymentRepository::get_by_id(&db, v2.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let history = CallHistoryRepository::list(&db, CallHistoryFilter::default())
+            .await
+            .unwrap();
+        assert!(history.is_empty());
+
+        let current = DeploymentRepository::get_current(&db, "Token", "testnet")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.id, v1.id);
+    }
+
+    #[tokio::test]
+    async fn test_views_carry_explorer_links() {
+        let db = setup_test_db().await;
+        let (_, v2) = setup_versioned_deployments(&db).await;
+
+        let listed = DeploymentRepository::list(&db, DeploymentFilter::current())
+            .await
+            .unwrap();
+        assert_eq!(listed[0].links, ExplorerLinks::default());
+
+        NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "testnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://rpc".to_string(),
+                explorer_url: Some("https://explorer.xyz".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+
+        let view = DeploymentRepository::get_view_by_id(&db, v2.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            view.links.address_url.as_deref(),
+            Some("https://explorer.xyz/address/0x0000000000000000000000000000000000000bBB")
+        );
+        assert_eq!(
+            view.links.tx_url,
+            Some(format!("https://explorer.xyz/tx/{}", tx("0x222")))
+        );
+
+        let call = CallHistoryRepository::create(
+            &db,
+            &NewCallHistory {
+                deployment_id: v2.id,
+                wallet_id: None,
+                function_name: "transfer".to_string(),
+                function_signature: "transfer(address,uint256)".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Write,
+            },
+        )
+        .await
+        .unwrap();
+        CallHistoryRepository::set_simulation_url(&db, call.id, "https://sim.example/1")
+            .await
+            .unwrap();
+
+        // Not sent yet, so only the contract and the simulation are linked
+        let history = CallHistoryRepository::list_views(&db, CallHistoryFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            history[0].links.address_url.as_deref(),
+            Some("https://explorer.xyz/address/0x0000000000000000000000000000000000000bBB")
+        );
+        assert!(history[0].links.tx_url.is_none());
+        assert_eq!(
+            history[0].simulation_url.as_deref(),
+            Some("https://sim.example/1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_call_history() {
+        let db = setup_test_db().await;
+        let (v1, _) = setup_versioned_deployments(&db).await;
+
+        // Old calls of each status, and one recent success
+        let calls = [
+            (TransactionStatus::Success, "-100 days"),
+            (TransactionStatus::Reverted, "-100 days"),
+            (TransactionStatus::Pending, "-100 days"),
+            (TransactionStatus::Success, "-1 day"),
+        ];
+        for (status, age) in calls {
+            let entry = CallHistoryRepository::create(
+                &db,
+                &NewCallHistory {
+                    deployment_id: v1.id,
+                    wallet_id: None,
+                    function_name: "transfer".to_string(),
+                    function_signature: "transfer(address,uint256)".to_string(),
+                    input_params: "[]".to_string(),
+                    call_type: CallType::Write,
+                },
+            )
+            .await
+            .unwrap();
+            sqlx::query(
+                "UPDATE call_history SET status = ?, created_at = datetime('now', ?) WHERE id = ?",
+            )
+            .bind(status)
+            .bind(age)
+            .bind(entry.id)
+            .execute(&db.writer)
+            .await
+            .unwrap();
+        }
+
+        let mut retention = HistoryRetention {
+            max_age: Duration::from_secs(90 * 24 * 3600),
+            keep_failed: true,
+        };
+        assert_eq!(
+            CallHistoryRepository::count_prunable(&db, &retention)
+                .await
+                .unwrap(),
+            1
+        );
+        retention.keep_failed = false;
+        assert_eq!(
+            CallHistoryRepository::count_prunable(&db, &retention)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            CallHistoryRepository::prune(&db, &retention).await.unwrap(),
+            2
+        );
+        db.vacuum().await.unwrap();
+
+        let left = CallHistoryRepository::list(&db, CallHistoryFilter::default())
+            .await
+            .unwrap();
+        let statuses: Vec<_> = left.iter().filter_map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            [TransactionStatus::Success, TransactionStatus::Pending]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_link_deployment_to_call() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        let call = CallHistoryRepository::create(
+            &db,
+            &NewCallHistory {
+                deployment_id: v1.id,
+                wallet_id: None,
+                function_name: "createPair".to_string(),
+                function_signature: "createPair()".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Write,
+            },
+        )
+        .await
+        .unwrap();
+        DeploymentRepository::link_to_call(&db, v2.id, call.id)
+            .await
+            .unwrap();
+        assert!(
+            DeploymentRepository::link_to_call(&db, DeploymentId(99), call.id)
+                .await
+                .unwrap_err()
+                .is_not_found()
+        );
+
+        let filter = DeploymentFilter {
+            created_by_call_id: Some(call.id),
+            ..Default::default()
+        };
+        let created = DeploymentRepository::list(&db, filter).await.unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].id, v2.id);
+        assert_eq!(created[0].created_by_call_id, Some(call.id));
+
+        // Pruning the call keeps the deployment and drops the link
+        sqlx::query("UPDATE call_history SET created_at = datetime('now', '-100 days')")
+            .execute(&db.writer)
+            .await
+            .unwrap();
+        let retention = HistoryRetention {
+            max_age: Duration::from_secs(90 * 24 * 3600),
+            keep_failed: false,
+        };
+        CallHistoryRepository::prune(&db, &retention).await.unwrap();
+        let deployment = DeploymentRepository::get_by_id(&db, v2.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deployment.created_by_call_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_selectors() {
+        let db = setup_test_db().await;
+        let selector = |signature: &str, source| NewSelector {
+            selector: "0xA9059CBB".to_string(),
+            signature: signature.to_string(),
+            source,
+        };
+
+        let added = SelectorRepository::put_many(
+            &db,
+            &[
+                selector("transfer(address,uint256)", SelectorSource::FourByte),
+                selector("many_msg_babbage(bytes1)", SelectorSource::FourByte),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(added, 2);
+
+        // Seen again from 4byte, nothing changes; from an ABI, it's upgraded
+        let changed = SelectorRepository::put_many(
+            &db,
+            &[
+                selector("many_msg_babbage(bytes1)", SelectorSource::FourByte),
+                selector("transfer(address,uint256)", SelectorSource::Registry),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(changed, 1);
+
+        let known = SelectorRepository::lookup(&db, "0xa9059cbb").await.unwrap();
+        let found: Vec<_> = known
+            .iter()
+            .map(|k| (k.selector.as_str(), k.signature.as_str(), k.source))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "0xa9059cbb",
+                    "transfer(address,uint256)",
+                    SelectorSource::Registry
+                ),
+                (
+                    "0xa9059cbb",
+                    "many_msg_babbage(bytes1)",
+                    SelectorSource::FourByte
+                ),
+            ]
+        );
+        assert!(SelectorRepository::lookup(&db, "0x12345678")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_external_call() {
+        let db = setup_test_db().await;
+        let (_, v2) = setup_versioned_deployments(&db).await;
+        let tx_hash: TxHash = format!("0x{}", "ab".repeat(32)).parse().unwrap();
+        assert!(CallHistoryRepository::get_by_tx_hash(&db, &tx_hash)
+            .await
+            .unwrap()
+            .is_none());
+
+        let sender = "0x1111111111111111111111111111111111111111";
+        let imported = CallHistoryRepository::import_external(
+            &db,
+            &NewCallHistory {
+                deployment_id: v2.id,
+                wallet_id: None,
+                function_name: "transfer".to_string(),
+                function_signature: "transfer(address,uint256)".to_string(),
+                input_params: r#"["0x01","5"]"#.to_string(),
+                call_type: CallType::Write,
+            },
+            sender,
+            &CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(tx_hash),
+                block_number: Some(12),
+                gas_used: Some(21_000),
+                gas_price: Some("2".to_string()),
+                status: TransactionStatus::Success,
+                error_message: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(imported.sender.as_deref(), Some(sender));
+        assert_eq!(imported.status, Some(TransactionStatus::Success));
+        assert_eq!(imported.fee.as_deref(), Some("42000"));
+        assert!(imported.confirmed_at.is_some());
+
+        let found = CallHistoryRepository::get_by_tx_hash(&db, &tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, imported.id);
+        let views = CallHistoryRepository::list_views(&db, CallHistoryFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(views[0].sender.as_deref(), Some(sender));
+    }
+
+    #[tokio::test]
+    async fn test_reorged_calls() {
+        let db = setup_test_db().await;
+        let (_, v2) = setup_versioned_deployments(&db).await;
+        let call = CallHistoryRepository::import_external(
+            &db,
+            &NewCallHistory {
+                deployment_id: v2.id,
+                wallet_id: None,
+                function_name: "pause".to_string(),
+                function_signature: "pause()".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Write,
+            },
+            "0x1111111111111111111111111111111111111111",
+            &CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(tx("0x333")),
+                block_number: Some(12),
+                gas_used: Some(21_000),
+                gas_price: Some("2".to_string()),
+                status: TransactionStatus::Success,
+                error_message: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        // Deployments recorded without a block are never checked
+        let unsettled = ReorgRepository::unsettled(&db, v2.network_id, 5)
+            .await
+            .unwrap();
+        assert_eq!(unsettled.len(), 1);
+        assert_eq!(unsettled[0].kind, RecordKind::Call);
+        assert_eq!(unsettled[0].id, call.id);
+        assert_eq!(unsettled[0].block_hash, None);
+        assert!(ReorgRepository::unsettled(&db, v2.network_id, 12)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let hash = format!("0x{}", "cd".repeat(32));
+        ReorgRepository::set_block(&db, RecordKind::Call, call.id, 13, &hash)
+            .await
+            .unwrap();
+        ReorgRepository::set_reorged(&db, RecordKind::Call, call.id, true)
+            .await
+            .unwrap();
+        // Reorged records stay unsettled however old their block
+        let unsettled = ReorgRepository::unsettled(&db, v2.network_id, 100)
+            .await
+            .unwrap();
+        assert_eq!(unsettled.len(), 1);
+        assert_eq!(unsettled[0].block_number, 13);
+        assert_eq!(unsettled[0].block_hash.as_deref(), Some(hash.as_str()));
+        let views = CallHistoryRepository::list_views(&db, CallHistoryFilter::default())
+            .await
+            .unwrap();
+        assert!(views[0].reorged_at.is_some());
+
+        ReorgRepository::set_reorged(&db, RecordKind::Call, call.id, false)
+            .await
+            .unwrap();
+        assert!(ReorgRepository::unsettled(&db, v2.network_id, 100)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_finality() {
+        let db = setup_test_db().await;
+        let (_, v2) = setup_versioned_deployments(&db).await;
+        let call = CallHistoryRepository::import_external(
+            &db,
+            &NewCallHistory {
+                deployment_id: v2.id,
+                wallet_id: None,
+                function_name: "pause".to_string(),
+                function_signature: "pause()".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Write,
+            },
+            "0x1111111111111111111111111111111111111111",
+            &CallHistoryUpdate {
+                result: None,
+                tx_hash: Some(tx("0x333")),
+                block_number: Some(12),
+                gas_used: Some(21_000),
+                gas_price: Some("2".to_string()),
+                status: TransactionStatus::Success,
+                error_message: None,
+            },
+        )
+        .await
+        .unwrap();
+        async fn finality(db: &Database, id: i64) -> Option<Finality> {
+            CallHistoryRepository::get_by_id(db, id)
+                .await
+                .unwrap()
+                .unwrap()
+                .finality
+        }
+        // Deployments without a block have no finality
+        assert_eq!(v2.finality, None);
+        assert_eq!(finality(&db, call.id).await, Some(Finality::Pending));
+
+        // Reorged records stay pending
+        ReorgRepository::set_reorged(&db, RecordKind::Call, call.id, true)
+            .await
+            .unwrap();
+        let changed = ReorgRepository::advance_finality(&db, v2.network_id, 20, Some(20))
+            .await
+            .unwrap();
+        assert_eq!(changed, 0);
+        ReorgRepository::set_reorged(&db, RecordKind::Call, call.id, false)
+            .await
+            .unwrap();
+
+        ReorgRepository::advance_finality(&db, v2.network_id, 11, None)
+            .await
+            .unwrap();
+        assert_eq!(finality(&db, call.id).await, Some(Finality::Pending));
+        ReorgRepository::advance_finality(&db, v2.network_id, 12, None)
+            .await
+            .unwrap();
+        assert_eq!(finality(&db, call.id).await, Some(Finality::Confirmed));
+        let changed = ReorgRepository::advance_finality(&db, v2.network_id, 15, Some(12))
+            .await
+            .unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(finality(&db, call.id).await, Some(Finality::Finalized));
+
+        // Moving to another block starts over
+        let hash = format!("0x{}", "cd".repeat(32));
+        ReorgRepository::set_block(&db, RecordKind::Call, call.id, 14, &hash)
+            .await
+            .unwrap();
+        assert_eq!(finality(&db, call.id).await, Some(Finality::Pending));
+    }
+
+    #[tokio::test]
+    async fn test_archive_deployment_hides_it_from_listing() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        DeploymentRepository::archive(&db, v2.id).await.unwrap();
+
+        let archived = DeploymentRepository::get_by_id(&db, v2.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(archived.archived_at.is_some());
+        assert!(!archived.is_current);
+
+        let current = DeploymentRepository::list(&db, DeploymentFilter::current())
+            .await
+            .unwrap();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].id, v1.id);
+
+        let all = DeploymentRepository::list(
+            &db,
+            DeploymentFilter {
+                include_archived: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_contract_cascades() {
+        let db = setup_test_db().await;
+        setup_versioned_deployments(&db).await;
+
+        ContractRepository::delete(&db, "Token").await.unwrap();
+
+        assert!(ContractRepository::get_by_name(&db, "Token")
+            .await
+            .unwrap()
+            .is_none());
+        let deployments = DeploymentRepository::list(
+            &db,
+            DeploymentFilter {
+                include_archived: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(deployments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_deployments_sorted_and_paginated() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        let filter = DeploymentFilter {
+            sort: Some(DeploymentSort::Version),
+            order: SortOrder::Desc,
+            limit: Some(1),
+            ..Default::default()
+        };
+
+        let total = DeploymentRepository::count(&db, &filter).await.unwrap();
+        assert_eq!(total, 2);
+
+        let first_page = DeploymentRepository::list(&db, filter.clone())
+            .await
+            .unwrap();
+        assert_eq!(first_page.len(), 1);
+        assert_eq!(first_page[0].id, v2.id);
+
+        let second_page = DeploymentRepository::list(
+            &db,
+            DeploymentFilter {
+                offset: Some(1),
+                ..filter
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].id, v1.id);
+    }
+
+    #[tokio::test]
+    async fn test_list_deployments_filters_by_contract_and_version() {
+        let db = setup_test_db().await;
+        let (v1, _) = setup_versioned_deployments(&db).await;
+
+        let filter = DeploymentFilter {
+            contract: Some("Token".to_string()),
+            version: Some(1),
+            ..Default::default()
+        };
+        let matching = DeploymentRepository::list(&db, filter.clone())
+            .await
+            .unwrap();
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].id, v1.id);
+
+        // v1 is no longer current
+        let current = DeploymentRepository::list(
+            &db,
+            DeploymentFilter {
+                current_only: true,
+                ..filter
+            },
+        )
+        .await
+        .unwrap();
+        assert!(current.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_deployments_filters_by_deployer() {
+        let db = setup_test_db().await;
+        setup_versioned_deployments(&db).await;
+
+        let matching = DeploymentRepository::list(
+            &db,
+            DeploymentFilter {
+                deployer: Some("0xDDD".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(matching.len(), 2);
+
+        let none = DeploymentRepository::list(
+            &db,
+            DeploymentFilter {
+                deployer: Some("0xeee".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deployments_link_to_wallets() {
+        let db = setup_test_db().await;
+        let (v1, _) = setup_versioned_deployments(&db).await;
+        let deploy = |address: &str| NewDeployment {
+            contract_id: v1.contract_id,
+            network_id: v1.network_id,
+            address: addr(address).parse().unwrap(),
+            deployer: addr("0xABC"),
+            tx_hash: None,
+            block_number: None,
+            constructor_args: None,
+            initcode_size: None,
+            runtime_size: None,
+            gas_used: None,
+            gas_price: None,
+        };
+        let by_ops = DeploymentFilter {
+            wallet: Some("ops".to_string()),
+            ..Default::default()
+        };
+
+        // Deployments made before the wallet was added are linked to it
+        let before = DeploymentRepository::create(&db, &deploy("0x1"))
+            .await
+            .unwrap();
+        assert_eq!(before.wallet_id, None);
+        let wallet = WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "ops".to_string(),
+                address: addr("0xabc").parse().unwrap(),
+                encrypted_key: vec![1],
+            },
+        )
+        .await
+        .unwrap();
+        let after = DeploymentRepository::create(&db, &deploy("0x2"))
+            .await
+            .unwrap();
+        assert_eq!(after.wallet_id, Some(wallet.id));
+
+        let listed = DeploymentRepository::list(&db, by_ops.clone())
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed
+            .iter()
+            .all(|d| d.wallet_name.as_deref() == Some("ops")));
+        let untouched = DeploymentRepository::get_by_id(&db, v1.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(untouched.wallet_id, None);
+
+        // Removing the wallet keeps its deployments
+        WalletRepository::delete(&db, "ops").await.unwrap();
+        assert!(DeploymentRepository::list(&db, by_ops)
+            .await
+            .unwrap()
+            .is_empty());
+        let kept = DeploymentRepository::get_by_id(&db, before.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(kept.wallet_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_first() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+        let vault = ContractRepository::upsert(
+            &db,
+            &NewContract {
+                name: "Vault".to_string(),
+                source_path: "src/Vault.sol".to_string(),
+                abi: "[]".to_string(),
+                bytecode_hash: BytecodeHash::of("0x456"),
+                storage_layout: None,
+                compiler: None,
+            },
+        )
+        .await
+        .unwrap();
+        let all = || DeploymentFilter {
+            sort: Some(DeploymentSort::Version),
+            ..Default::default()
+        };
+        let ids = |deployments: Vec<DeploymentView>| -> Vec<DeploymentId> {
+            deployments.iter().map(|d| d.id).collect()
+        };
+
+        DeploymentRepository::set_pinned(&db, v2.id, true)
+            .await
+            .unwrap();
+        let listed = DeploymentRepository::list(&db, all()).await.unwrap();
+        assert_eq!(ids(listed), [v2.id, v1.id]);
+        assert!(
+            DeploymentRepository::get_by_id(&db, v2.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .pinned
+        );
+
+        // Pinning a contract pins every build and deployment of it
+        ContractRepository::set_pinned(&db, "Vault", true)
+            .await
+            .unwrap();
+        let contracts = ContractRepository::list(&db, ContractFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(contracts[0].id, vault.id);
+        assert!(contracts[0].pinned && !contracts[1].pinned);
+        ContractRepository::set_pinned(&db, "Token", true)
+            .await
+            .unwrap();
+        let listed = DeploymentRepository::list(&db, all()).await.unwrap();
+        assert!(listed.iter().all(|d| d.pinned));
+        assert_eq!(ids(listed), [v1.id, v2.id]);
+
+        ContractRepository::set_pinned(&db, "Token", false)
+            .await
+            .unwrap();
+        DeploymentRepository::set_pinned(&db, v2.id, false)
+            .await
+            .unwrap();
+        let listed = DeploymentRepository::list(&db, all()).await.unwrap();
+        assert!(listed.iter().all(|d| !d.pinned));
+        assert!(matches!(
+            ContractRepository::set_pinned(&db, "Missing", true).await,
+            Err(smolder_core::Error::ContractNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_labels() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        ContractRepository::set_label(&db, "Token", Some("Fee token"), Some("Charges **1%**"))
+            .await
+            .unwrap();
+        DeploymentRepository::set_label(&db, v2.id, Some("Main fee vault"), None)
+            .await
+            .unwrap();
+
+        let contract = ContractRepository::get_by_name(&db, "Token")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(contract.label.as_deref(), Some("Fee token"));
+
+        // A deployment's own label wins; missing fields fall back to the
+        // contract's
+        let view = DeploymentRepository::get_view_by_id(&db, v2.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(view.label.as_deref(), Some("Main fee vault"));
+        assert_eq!(view.description.as_deref(), Some("Charges **1%**"));
+        let view = DeploymentRepository::get_view_by_id(&db, v1.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(view.label.as_deref(), Some("Fee token"));
+        let deployment = DeploymentRepository::get_by_id(&db, v1.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(deployment.label.is_none());
+
+        ContractRepository::set_label(&db, "Token", None, None)
+            .await
+            .unwrap();
+        DeploymentRepository::set_label(&db, v2.id, None, None)
+            .await
+            .unwrap();
+        let listed = DeploymentRepository::list(&db, DeploymentFilter::default())
+            .await
+            .unwrap();
+        assert!(listed
+            .iter()
+            .all(|d| d.label.is_none() && d.description.is_none()));
+        assert!(matches!(
+            ContractRepository::set_label(&db, "Missing", Some("x"), None).await,
+            Err(smolder_core::Error::ContractNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_current_deployment_not_found() {
+        let db = setup_test_db().await;
+
+        let result = DeploymentRepository::get_current(&db, "NonExistent", "testnet")
+            .await
+            .unwrap();
+
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_for_export() {
+        use futures::TryStreamExt;
+
+        let db = setup_test_db().await;
+        let (_, v2) = setup_versioned_deployments(&db).await;
+
+        // Only the current version is exported
+        let rows: Vec<DeploymentView> = DeploymentRepository::stream_for_export(&db, None, true)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, v2.id);
+        assert_eq!(rows[0].abi, "[]");
+
+        let rows: Vec<DeploymentView> =
+            DeploymentRepository::stream_for_export(&db, Some("testnet"), false)
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].abi.is_empty());
+
+        let rows: Vec<DeploymentView> =
+            DeploymentRepository::stream_for_export(&db, Some("mainnet"), true)
+                .try_collect()
+                .await
+                .unwrap();
+        assert!(rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_batch() {
+        let db = setup_test_db().await;
+
+        let network = NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "testnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://rpc".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let deployment =
+            |name: &str, address: &str, block_number: Option<i64>| NewContractDeployment {
+                contract: NewContract {
+                    name: name.to_string(),
+                    source_path: format!("src/{}.sol", name),
+                    abi: "[]".to_string(),
+                    bytecode_hash: BytecodeHash::of(name),
+                    storage_layout: None,
+                    compiler: None,
+                },
+                address: addr(address).parse().unwrap(),
+                deployer: "0xddd".to_string(),
+                tx_hash: Some(tx(address)),
+                block_number,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            };
+
+        let batch = vec![
+            deployment("Token", "0xaaa", Some(1)),
+            deployment("Vault", "0xbbb", None),
+            deployment("Token", "0xccc", Some(2)),
+            deployment("Token", "0xAAA", Some(1)),
+        ];
+        let statuses = DeploymentRepository::import_batch(&db, network.id, &batch)
+            .await
+            .unwrap();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportStatus::Added,
+                ImportStatus::Added,
+                ImportStatus::Added,
+                ImportStatus::Skipped
+            ]
+        );
+
+        let versions = DeploymentRepository::list_versions(&db, "Token", "testnet")
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].address, addr("0xccc").as_str());
+        assert_eq!(versions[0].version, 2);
+        assert!(versions[0].is_current);
+        assert!(!versions[1].is_current);
+
+        // Re-importing fills in the missing block number and skips the rest
+        let batch = vec![
+            deployment("Vault", "0xbbb", Some(5)),
+            deployment("Token", "0xccc", Some(2)),
+        ];
+        let statuses = DeploymentRepository::import_batch(&db, network.id, &batch)
+            .await
+            .unwrap();
+        assert_eq!(statuses, vec![ImportStatus::Updated, ImportStatus::Skipped]);
+        let vault = DeploymentRepository::get_current(&db, "Vault", "testnet")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vault.block_number, Some(5));
+
+        // A failing batch leaves nothing behind
+        let batch = vec![deployment("Pool", "0xddd", None)];
+        assert!(
+            DeploymentRepository::import_batch(&db, NetworkId(999), &batch)
+                .await
+                .is_err()
+        );
+        assert!(ContractRepository::get_by_name(&db, "Pool")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = std::env::temp_dir().join(format!("smolder-backup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let snapshot = dir.join("snapshot.db");
+
+        let db = Database::connect_to(dir.join("smolder.db")).await.unwrap();
+        db.init_schema().await.unwrap();
+
+        let network = NewNetwork {
+            name: "mainnet".to_string(),
+            chain_id: ChainId(1),
+            rpc_url: "https://eth.rpc".to_string(),
+            explorer_url: None,
+        };
+        NetworkRepository::upsert(&db, &network).await.unwrap();
+
+        db.backup_to(&snapshot).await.unwrap();
+        assert!(!dir.join("snapshot.db-wal").exists());
+
+        NetworkRepository::delete(&db, "mainnet").await.unwrap();
+        assert!(NetworkRepository::get_by_name(&db, "mainnet")
+            .await
+            .unwrap()
+            .is_none());
+
+        db.restore_from(&snapshot).await.unwrap();
+        assert!(NetworkRepository::get_by_name(&db, "mainnet")
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            db.schema_version().await.unwrap(),
+            Database::latest_schema_version()
+        );
+
+        let not_a_db = dir.join("notes.txt");
+        std::fs::write(&not_a_db, "hello").unwrap();
+        assert!(db.restore_from(&not_a_db).await.is_err());
+        assert!(db.restore_from(dir.join("missing.db")).await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "encrypted-db")]
+    #[tokio::test]
+    async fn test_encrypted_database() {
+        let dir = std::env::temp_dir().join(format!("smolder-encrypted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("smolder.db");
+        let encrypted = dir.join("encrypted.db");
+        let with_key = |key: &str| DatabaseOptions {
+            key: Some(Secret::new(key.to_string())),
+            ..Default::default()
+        };
+        let without_key = DatabaseOptions {
+            key: None,
+            ..Default::default()
+        };
+
+        let db = Database::connect_to_with_options(&plain, &without_key)
+            .await
+            .unwrap();
+        db.init_schema().await.unwrap();
+        let network = NewNetwork {
+            name: "mainnet".to_string(),
+            chain_id: ChainId(1),
+            rpc_url: "https://eth.rpc".to_string(),
+            explorer_url: None,
+        };
+        NetworkRepository::upsert(&db, &network).await.unwrap();
+        db.export_encrypted(&encrypted, "it's secret")
+            .await
+            .unwrap();
+        db.close().await;
+        assert!(!Database::is_encrypted(&plain));
+        assert!(Database::is_encrypted(&encrypted));
+        assert!(!Database::is_encrypted(dir.join("missing.db")));
+
+        let db = Database::connect_to_with_options(&encrypted, &with_key("it's secret"))
+            .await
+            .unwrap();
+        assert!(NetworkRepository::get_by_name(&db, "mainnet")
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            db.schema_version().await.unwrap(),
+            Database::latest_schema_version()
+        );
+
+        // Snapshots stay encrypted, and restore with the same key
+        let snapshot = dir.join("snapshot.db");
+        db.backup_to(&snapshot).await.unwrap();
+        assert!(Database::is_encrypted(&snapshot));
+        db.restore_from(&snapshot).await.unwrap();
+        db.close().await;
+
+        for options in [with_key("wrong"), without_key] {
+            assert!(matches!(
+                Database::connect_to_with_options(&encrypted, &options).await,
+                Err(Error::Validation(_))
+            ));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(not(feature = "encrypted-db"))]
+    #[tokio::test]
+    async fn test_key_needs_encrypted_db() {
+        let options = DatabaseOptions {
+            key: Some(Secret::new("secret".to_string())),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Database::connect_to_with_options(":memory:", &options).await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reads_do_not_wait_for_writes() {
+        let dir = std::env::temp_dir().join(format!("smolder-pools-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let db = Database::connect_to(dir.join("smolder.db")).await.unwrap();
+        db.init_schema().await.unwrap();
+
+        // Hold the write lock with an uncommitted insert
+        let mut tx = db.write_pool().begin().await.unwrap();
+        sqlx::query("INSERT INTO networks (name, chain_id, rpc_url) VALUES ('pending', 1, 'x')")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+
+        let networks = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            NetworkRepository::list(&db),
+        )
+        .await
+        .expect("read blocked behind the open write")
+        .unwrap();
+        assert!(networks.is_empty());
+
+        tx.commit().await.unwrap();
+        assert_eq!(NetworkRepository::list(&db).await.unwrap().len(), 1);
+
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes() {
+        let dir = std::env::temp_dir().join(format!("smolder-stress-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let db = Database::connect_to(dir.join("smolder.db")).await.unwrap();
+        db.init_schema().await.unwrap();
+        let db = std::sync::Arc::new(db);
+
+        // Simulate handlers writing at once, each with its own pooled connection
+        let mut handles = Vec::new();
+        for task in 0..8 {
+            let db = db.clone();
+            handles.push(tokio::spawn(async move {
+                for i in 0..25 {
+                    NetworkRepository::upsert(
+                        db.as_ref(),
+                        &NewNetwork {
+                            name: format!("net-{}-{}", task, i),
+                            chain_id: ChainId(task * 1000 + i),
+                            rpc_url: "https://eth.rpc".to_string(),
+                            explorer_url: None,
+                        },
+                    )
+                    .await?;
+                    ContractRepository::upsert(
+                        db.as_ref(),
+                        &NewContract {
+                            name: format!("Contract{}", task),
+                            source_path: "src/Contract.sol".to_string(),
+                            abi: "[]".to_string(),
+                            bytecode_hash: BytecodeHash::of(i.to_string()),
+                            storage_layout: None,
+                            compiler: None,
+                        },
+                    )
+                    .await?;
+                }
+                Ok::<_, Error>(())
+            }));
+        }
+
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+        assert_eq!(
+            NetworkRepository::list(db.as_ref()).await.unwrap().len(),
+            200
+        );
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(db.write_pool())
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_and_deliveries() {
+        let db = setup_test_db().await;
+
+        let webhook = WebhookRepository::create(
+            &db,
+            &NewWebhook {
+                url: "https://hooks.example.com/smolder".to_string(),
+                secret: "s3cret".to_string(),
+                network: Some("mainnet".to_string()),
+                contract: None,
+                event_name: Some("Transfer".to_string()),
+                tx_status: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(WebhookRepository::list(&db).await.unwrap().len(), 1);
+
+        assert!(webhook.matches(
+            WebhookTrigger::Event,
+            "mainnet",
+            "Token",
+            Some("Transfer"),
+            None
+        ));
+        assert!(!webhook.matches(
+            WebhookTrigger::Event,
+            "mainnet",
+            "Token",
+            Some("Approval"),
+            None
+        ));
+        assert!(!webhook.matches(
+            WebhookTrigger::Event,
+            "sepolia",
+            "Token",
+            Some("Transfer"),
+            None
+        ));
+        assert!(!webhook.matches(
+            WebhookTrigger::Call,
+            "mainnet",
+            "Token",
+            None,
+            Some(TransactionStatus::Success)
+        ));
+
+        let first = WebhookRepository::create_delivery(
+            &db,
+            &NewWebhookDelivery {
+                webhook_id: webhook.id,
+                trigger: WebhookTrigger::Event,
+                payload: "{}".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        let second = WebhookRepository::create_delivery(
+            &db,
+            &NewWebhookDelivery {
+                webhook_id: webhook.id,
+                trigger: WebhookTrigger::Event,
+                payload: "{}".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.status, DeliveryStatus::Pending);
+        assert_eq!(first.attempts, 0);
+
+        WebhookRepository::update_delivery(
+            &db,
+            first.id,
+            &WebhookDeliveryUpdate {
+                status: DeliveryStatus::Delivered,
+                attempts: 2,
+                response_status: Some(200),
+                error_message: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let pending = WebhookRepository::list_pending_deliveries(&db)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, second.id);
+
+        let deliveries = WebhookRepository::list_deliveries(&db, webhook.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(deliveries[0].id, second.id);
+        assert_eq!(deliveries[1].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[1].attempts, 2);
+        assert!(deliveries[1].delivered_at.is_some());
+
+        WebhookRepository::delete(&db, webhook.id).await.unwrap();
+        assert!(WebhookRepository::get_by_id(&db, webhook.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(WebhookRepository::list_pending_deliveries(&db)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_token_metadata() {
+        use futures::TryStreamExt;
+
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        assert!(TokenMetadataRepository::get(&db, v2.id)
+            .await
+            .unwrap()
+            .is_none());
+        let view = DeploymentRepository::get_view_by_id(&db, v2.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(view.token.is_none());
+
+        let mut metadata = NewTokenMetadata {
+            deployment_id: v2.id,
+            standard: TokenStandard::Erc20,
+            name: Some("Test Token".to_string()),
+            symbol: Some("TST".to_string()),
+            decimals: Some(18),
+            total_supply: None,
+        };
+        TokenMetadataRepository::put(&db, &metadata).await.unwrap();
+        // A later read replaces the first
+        metadata.total_supply = Some("1000000000000000000000000000000".to_string());
+        TokenMetadataRepository::put(&db, &metadata).await.unwrap();
+
+        let stored = TokenMetadataRepository::get(&db, v2.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.symbol.as_deref(), Some("TST"));
+        assert_eq!(
+            stored.total_supply.as_deref(),
+            Some("1000000000000000000000000000000")
+        );
+
+        // Views carry it, other versions don't
+        let listed = DeploymentRepository::list(&db, DeploymentFilter::default())
+            .await
+            .unwrap();
+        let token = |id| listed.iter().find(|d| d.id == id).unwrap().token.clone();
+        assert_eq!(token(v2.id), Some(stored));
+        assert_eq!(token(v1.id), None);
+
+        let exported: Vec<DeploymentView> =
+            DeploymentRepository::stream_for_export(&db, None, false)
+                .try_collect()
+                .await
+                .unwrap();
+        let token = exported[0].token.as_ref().unwrap();
+        assert_eq!(token.standard, TokenStandard::Erc20);
+        assert_eq!(token.decimals, Some(18));
+    }
+
+    #[tokio::test]
+    async fn test_gas_stats() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+
+        let calls = [
+            (v1.id, 50_000, TransactionStatus::Success),
+            (v1.id, 60_000, TransactionStatus::Success),
+            (v2.id, 70_000, TransactionStatus::Success),
+            // Reverted calls don't count
+            (v2.id, 90_000, TransactionStatus::Reverted),
+        ];
+        for (deployment_id, gas, status) in calls {
+            let entry = CallHistoryRepository::create(
+                &db,
+                &NewCallHistory {
+                    deployment_id,
+                    wallet_id: None,
+                    function_name: "transfer".to_string(),
+                    function_signature: "transfer(address,uint256)".to_string(),
+                    input_params: "[]".to_string(),
+                    call_type: CallType::Write,
+                },
+            )
+            .await
+            .unwrap();
+            CallHistoryRepository::update(
+                &db,
+                entry.id,
+                &CallHistoryUpdate {
+                    result: None,
+                    tx_hash: Some(tx("0xabc")),
+                    block_number: Some(1),
+                    gas_used: Some(gas),
+                    gas_price: None,
+                    status,
+                    error_message: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let stats = AnalyticsRepository::gas_stats(&db, &GasStatsFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].version, stats[0].calls), (1, 2));
+        assert_eq!(
+            (stats[0].avg_gas, stats[0].min_gas, stats[0].max_gas),
+            (55_000, 50_000, 60_000)
+        );
+        assert_eq!((stats[1].version, stats[1].total_gas), (2, 70_000));
+        assert!(stats[0].period.is_none());
+
+        let filter = GasStatsFilter {
+            interval: Some(GasInterval::Month),
+            ..Default::default()
+        };
+        let stats = AnalyticsRepository::gas_stats(&db, &filter).await.unwrap();
+        assert_eq!(stats[0].period.as_ref().map(String::len), Some(7));
+
+        let filter = GasStatsFilter {
+            contract: Some("Other".to_string()),
+            ..Default::default()
+        };
+        assert!(AnalyticsRepository::gas_stats(&db, &filter)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_costs() {
+        let db = setup_test_db().await;
+        let (v1, _) = setup_versioned_deployments(&db).await;
+        let v3 = DeploymentRepository::create(
+            &db,
+            &NewDeployment {
+                contract_id: v1.contract_id,
+                network_id: v1.network_id,
+                address: addr("0xccc").parse().unwrap(),
+                deployer: "0xddd".to_string(),
+                tx_hash: Some(tx("0x333")),
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: Some(1_000_000),
+                gas_price: Some("10000000000".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(v3.fee.as_deref(), Some("10000000000000000"));
+
+        let calls = [
+            (50_000, Some("2000000000"), TransactionStatus::Success),
+            // Reverted calls still paid for their gas
+            (21_000, Some("1000000000"), TransactionStatus::Reverted),
+            // More than SQLite's integers hold
+            (
+                30_000_000,
+                Some("1000000000000"),
+                TransactionStatus::Success,
+            ),
+            (21_000, None, TransactionStatus::Pending),
+        ];
+        for (gas, gas_price, status) in calls {
+            let entry = CallHistoryRepository::create(
+                &db,
+                &NewCallHistory {
+                    deployment_id: v1.id,
+                    wallet_id: None,
+                    function_name: "transfer".to_string(),
+                    function_signature: "transfer(address,uint256)".to_string(),
+                    input_params: "[]".to_string(),
+                    call_type: CallType::Write,
+                },
+            )
+            .await
+            .unwrap();
+            CallHistoryRepository::update(
+                &db,
+                entry.id,
+                &CallHistoryUpdate {
+                    result: None,
+                    tx_hash: Some(tx("0xabc")),
+                    block_number: Some(1),
+                    gas_used: Some(gas),
+                    gas_price: gas_price.map(str::to_string),
+                    status,
+                    error_message: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let costs = AnalyticsRepository::costs(&db, &CostFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(costs.len(), 2);
+        assert_eq!((costs[0].wallet.as_deref(), costs[0].calls), (None, 3));
+        assert_eq!(costs[0].gas_used, 30_071_000);
+        assert_eq!(costs[0].fee, "30000121000000000000");
+        assert_eq!(costs[1].deployments, 1);
+        assert_eq!(costs[1].fee, "10000000000000000");
+        assert_eq!(costs[1].currency_symbol, "ETH");
+        assert_eq!(costs[1].fee_usd, None);
+
+        let unpriced = AnalyticsRepository::unpriced_fees(&db, &CostFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(unpriced.len(), 1);
+        assert_eq!(unpriced[0].symbol, "ETH");
+        PriceRepository::put(&db, "ETH", &unpriced[0].day, 2000.0)
+            .await
+            .unwrap();
+        assert_eq!(
+            PriceRepository::get(&db, "ETH", &unpriced[0].day)
+                .await
+                .unwrap(),
+            Some(2000.0)
+        );
+        let costs = AnalyticsRepository::costs(&db, &CostFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(costs[1].fee_usd, Some(20.0));
+        assert!(
+            AnalyticsRepository::unpriced_fees(&db, &CostFilter::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let filter = CostFilter {
+            interval: Some(GasInterval::Month),
+            network: Some("other".to_string()),
+            ..Default::default()
+        };
+        assert!(AnalyticsRepository::costs(&db, &filter)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_registry_stats() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+        NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "empty".to_string(),
+                chain_id: ChainId(2),
+                rpc_url: "https://rpc".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        for status in [TransactionStatus::Pending, TransactionStatus::Reverted] {
+            let entry = CallHistoryRepository::create(
+                &db,
+                &NewCallHistory {
+                    deployment_id: v2.id,
+                    wallet_id: None,
+                    function_name: "mint".to_string(),
+                    function_signature: "mint()".to_string(),
+                    input_params: "[]".to_string(),
+                    call_type: CallType::Write,
+                },
+            )
+            .await
+            .unwrap();
+            CallHistoryRepository::update(
+                &db,
+                entry.id,
+                &CallHistoryUpdate {
+                    result: None,
+                    tx_hash: None,
+                    block_number: None,
+                    gas_used: None,
+                    gas_price: None,
+                    status,
+                    error_message: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let stats = AnalyticsRepository::stats(&db, 3).await.unwrap();
+        assert_eq!((stats.contracts, stats.deployments), (1, 1));
+        assert_eq!((stats.calls_24h, stats.calls_7d), (2, 2));
+        assert_eq!((stats.pending_txs, stats.failed_txs), (1, 1));
+
+        assert_eq!(stats.networks.len(), 2);
+        assert_eq!(stats.networks[0].network, "empty");
+        assert_eq!(stats.networks[0].deployments, 0);
+        assert!(stats.networks[0].last_deployed_at.is_none());
+        assert_eq!(stats.networks[1].deployments, 1);
+        assert!(stats.networks[1].last_deployed_at.is_some());
+
+        // Calls come first among entries of the same second, and the oldest
+        // deployment falls off
+        assert_eq!(stats.recent_activity.len(), 3);
+        assert_eq!(stats.recent_activity[0].kind, ActivityKind::Call);
+        assert_eq!(
+            stats.recent_activity[0].function_name.as_deref(),
+            Some("mint")
+        );
+        assert_eq!(stats.recent_activity[2].kind, ActivityKind::Deployment);
+        assert_eq!(stats.recent_activity[2].deployment_id, v2.id);
+        assert_ne!(stats.recent_activity[2].deployment_id, v1.id);
+    }
+
+    #[tokio::test]
+    async fn test_deployment_groups() {
+        let db = setup_test_db().await;
+        let (v1, _) = setup_versioned_deployments(&db).await;
+        let mainnet = NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://eth.example.com".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let group = DeploymentGroupRepository::create(
+            &db,
+            &NewDeploymentGroup {
+                contract_name: "Token".to_string(),
+                bytecode_hash: BytecodeHash::of("0x123"),
+                constructor_args: "[]".to_string(),
+                value: None,
+                wallet_name: "deployer".to_string(),
+                network_ids: vec![mainnet.id, v1.network_id],
+            },
+        )
+        .await
+        .unwrap();
+
+        // Members keep the order the networks were given in
+        let members = DeploymentGroupRepository::list_members(&db, group.id)
+            .await
+            .unwrap();
+        let names: Vec<&str> = members.iter().map(|m| m.network_name.as_str()).collect();
+        assert_eq!(names, ["mainnet", "testnet"]);
+        assert!(members
+            .iter()
+            .all(|m| m.status == TransactionStatus::Pending));
+
+        DeploymentGroupRepository::update_member(
+            &db,
+            group.id,
+            mainnet.id,
+            TransactionStatus::Failed,
+            None,
+            Some("insufficient funds"),
+        )
+        .await
+        .unwrap();
+        DeploymentGroupRepository::update_member(
+            &db,
+            group.id,
+            v1.network_id,
+            TransactionStatus::Success,
+            Some(v1.id),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let members = DeploymentGroupRepository::list_members(&db, group.id)
+            .await
+            .unwrap();
+        assert_eq!(members[0].status, TransactionStatus::Failed);
+        assert_eq!(
+            members[0].error_message.as_deref(),
+            Some("insufficient funds")
+        );
+        assert_eq!(members[1].address, addr("0xaaa").parse().ok());
+        assert_eq!(members[1].tx_hash, Some(tx("0x111")));
+
+        assert_eq!(DeploymentGroupRepository::list(&db).await.unwrap().len(), 1);
+        assert!(DeploymentGroupRepository::get(&db, DeploymentGroupId(99))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_gas_samples() {
+        let db = setup_test_db().await;
+        let network = NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://eth.example.com".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        for block in 1..=3 {
+            GasSampleRepository::record(
+                &db,
+                &NewGasSample {
+                    network_id: network.id,
+                    block_number: block,
+                    base_fee: Some(block * 1_000_000_000),
+                    gas_price: block * 2_000_000_000,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        // The latest samples are kept, oldest first
+        let samples = GasSampleRepository::list(&db, network.id, None, 2)
+            .await
+            .unwrap();
+        let blocks: Vec<i64> = samples.iter().map(|s| s.block_number).collect();
+        assert_eq!(blocks, [2, 3]);
+        assert!(
+            GasSampleRepository::list(&db, network.id, Some("2999-01-01"), 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        sqlx::query(
+            "UPDATE gas_samples SET sampled_at = '2000-01-01 00:00:00' WHERE block_number = 1",
+        )
+        .execute(&db.writer)
+        .await
+        .unwrap();
+        let hour = Duration::from_secs(3600);
+        assert_eq!(GasSampleRepository::prune(&db, hour).await.unwrap(), 1);
+        assert_eq!(
+            GasSampleRepository::list(&db, network.id, None, 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ens_cache() {
+        let db = setup_test_db().await;
+        let hour = Duration::from_secs(3600);
+
+        assert!(
+            EnsCacheRepository::get(&db, ChainId(1), EnsLookup::Forward, "vitalik.eth", hour)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        for (lookup, input, result) in [
+            (EnsLookup::Forward, "vitalik.eth", Some("0xd8da")),
+            (EnsLookup::Reverse, "0xd8da", Some("vitalik.eth")),
+            (EnsLookup::Reverse, "0xbeef", None),
+        ] {
+            EnsCacheRepository::put(
+                &db,
+                &NewEnsRecord {
+                    chain_id: ChainId(1),
+                    lookup,
+                    input: input.to_string(),
+                    result: result.map(str::to_string),
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let forward =
+            EnsCacheRepository::get(&db, ChainId(1), EnsLookup::Forward, "vitalik.eth", hour)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(forward.result.as_deref(), Some("0xd8da"));
+
+        // Misses are cached too, and lookups on other chains are separate
+        let miss = EnsCacheRepository::get(&db, ChainId(1), EnsLookup::Reverse, "0xbeef", hour)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(miss.result.is_none());
+        assert!(
+            EnsCacheRepository::get(&db, ChainId(5), EnsLookup::Forward, "vitalik.eth", hour)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_tokens() {
+        let db = setup_test_db().await;
+        assert_eq!(ApiTokenRepository::count(&db).await.unwrap(), 0);
+
+        let ci = ApiTokenRepository::create(&db, "ci", "0xaaa", ApiRole::Viewer)
+            .await
+            .unwrap();
+        assert!(ci.last_used_at.is_none());
+        assert_eq!(ci.role, ApiRole::Viewer);
+        assert_eq!(ApiTokenRepository::count(&db).await.unwrap(), 1);
+
+        let used = ApiTokenRepository::authenticate(&db, "0xaaa")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(used.id, ci.id);
+        assert!(used.last_used_at.is_some());
+
+        ApiTokenRepository::set_role(&db, "ci", ApiRole::Operator)
+            .await
+            .unwrap();
+        assert_eq!(
+            ApiTokenRepository::list(&db).await.unwrap()[0].role,
+            ApiRole::Operator
+        );
+        assert!(ApiTokenRepository::set_role(&db, "missing", ApiRole::Admin)
+            .await
+            .unwrap_err()
+            .is_not_found());
+        assert!(ApiTokenRepository::authenticate(&db, "0xbbb")
+            .await
+            .unwrap()
+            .is_none());
+
+        ApiTokenRepository::delete(&db, "ci").await.unwrap();
+        assert!(ApiTokenRepository::list(&db).await.unwrap().is_empty());
+        assert!(ApiTokenRepository::delete(&db, "ci")
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_batches() {
+        let db = setup_test_db().await;
+        let (_, deployment) = setup_versioned_deployments(&db).await;
+        let network = NetworkRepository::get_by_name(&db, "testnet")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let batch = BatchRepository::create(
+            &db,
+            &NewBatch {
+                plan: "plan.json".to_string(),
+                network_id: network.id,
+                wallet_id: None,
+                steps: 2,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(batch.status, TransactionStatus::Pending);
+        assert_eq!(batch.network_name, "testnet");
+
+        for (position, function) in ["pause", "unpause"].iter().enumerate().rev() {
+            let call = CallHistoryRepository::create(
+                &db,
+                &NewCallHistory {
+                    deployment_id: deployment.id,
+                    wallet_id: None,
+                    function_name: function.to_string(),
+                    function_signature: format!("{}()", function),
+                    input_params: "[]".to_string(),
+                    call_type: CallType::Write,
+                },
+            )
+            .await
+            .unwrap();
+            BatchRepository::add_call(&db, batch.id, position as i64, call.id)
+                .await
+                .unwrap();
+        }
+        BatchRepository::finish(&db, batch.id, TransactionStatus::Success)
+            .await
+            .unwrap();
+
+        let finished = BatchRepository::get(&db, batch.id).await.unwrap().unwrap();
+        assert_eq!(finished.status, TransactionStatus::Success);
+        assert!(finished.finished_at.is_some());
+        // Calls come back in step order, not insertion order
+        let calls = BatchRepository::list_calls(&db, batch.id).await.unwrap();
+        let functions: Vec<&str> = calls.iter().map(|c| c.function_name.as_str()).collect();
+        assert_eq!(functions, ["pause", "unpause"]);
+    }
+
+    #[tokio::test]
+    async fn test_templates() {
+        let db = setup_test_db().await;
+        let param = |name: &str, ty: &str, default: Option<&str>| TemplateParam {
+            name: name.to_string(),
+            ty: ty.to_string(),
+            default: default.map(str::to_string),
+        };
+        let mut erc20 = NewTemplate {
+            name: "erc20".to_string(),
+            artifact: "MyToken".to_string(),
+            params: vec![
+                param("name", "string", None),
+                param("supply", "uint256", Some("1000000")),
+            ],
+            description: None,
+        };
+        let saved = TemplateRepository::upsert(&db, &erc20).await.unwrap();
+        assert_eq!(saved.params, erc20.params);
+
+        erc20.artifact = "MyTokenV2".to_string();
+        let replaced = TemplateRepository::upsert(&db, &erc20).await.unwrap();
+        assert_eq!(replaced.id, saved.id);
+        let fetched = TemplateRepository::get(&db, "erc20")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.artifact, "MyTokenV2");
+        assert_eq!(fetched.params[1].default.as_deref(), Some("1000000"));
+        assert_eq!(TemplateRepository::list(&db).await.unwrap().len(), 1);
+
+        TemplateRepository::delete(&db, "erc20").await.unwrap();
+        assert!(TemplateRepository::get(&db, "erc20")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(TemplateRepository::delete(&db, "erc20")
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_signatures_outlive_their_wallet() {
+        let db = setup_test_db().await;
+        let wallet = WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "signer".to_string(),
+                address: addr("0xaaa").parse().unwrap(),
+                encrypted_key: vec![1, 2, 3],
+            },
+        )
+        .await
+        .unwrap();
+        let ci = ApiTokenRepository::create(&db, "ci", "0xc1", ApiRole::Admin)
+            .await
+            .unwrap();
+
+        let new = NewSignature {
+            wallet_id: wallet.id,
+            signer: wallet.address,
+            primary_type: "Permit".to_string(),
+            domain: r#"{"name":"Token","chainId":1}"#.to_string(),
+            payload: "{}".to_string(),
+            digest: format!("0x{}", "ab".repeat(32)),
+            signature: format!("0x{}", "cd".repeat(65)),
+            signed_by: Some(ci.id),
+        };
+        let first = SignatureRepository::create(&db, &new).await.unwrap();
+        assert_eq!(first.wallet_name.as_deref(), Some("signer"));
+        assert_eq!(first.signed_by.as_deref(), Some("ci"));
+        let second = SignatureRepository::create(
+            &db,
+            &NewSignature {
+                signed_by: None,
+                ..new
+            },
+        )
+        .await
+        .unwrap();
+
+        let listed = SignatureRepository::list(&db, Some("signer"))
+            .await
+            .unwrap();
+        assert_eq!(
+            listed.iter().map(|s| s.id).collect::<Vec<_>>(),
+            [second.id, first.id]
+        );
+        assert!(SignatureRepository::list(&db, Some("other"))
+            .await
+            .unwrap()
+            .is_empty());
+
+        WalletRepository::delete(&db, "signer").await.unwrap();
+        let kept = SignatureRepository::list(&db, None).await.unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].wallet_name, None);
+        assert_eq!(kept[0].signer, wallet.address);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let db = setup_test_db().await;
+        let entry = |action| NewAuditEntry {
+            action,
+            target: "mainnet".to_string(),
+            source: AuditSource::Cli,
+            actor: Some("alice".to_string()),
+            detail: None,
+        };
+        let lock = AuditRepository::record(&db, &entry(AuditAction::Lock))
+            .await
+            .unwrap();
+        assert_eq!(lock.action, AuditAction::Lock);
+        assert_eq!(lock.actor.as_deref(), Some("alice"));
+        let override_ = AuditRepository::record(
+            &db,
+            &NewAuditEntry {
+                source: AuditSource::Api,
+                actor: None,
+                detail: Some(r#"{"operation":"deploy"}"#.to_string()),
+                ..entry(AuditAction::Override)
+            },
+        )
+        .await
+        .unwrap();
+
+        let newest = AuditRepository::list(&db, 1).await.unwrap();
+        assert_eq!(newest.len(), 1);
+        assert_eq!(newest[0].id, override_.id);
+        assert_eq!(newest[0].source, AuditSource::Api);
+        assert_eq!(
+            newest[0].detail.as_deref(),
+            Some(r#"{"operation":"deploy"}"#)
+        );
+        assert_eq!(AuditRepository::list(&db, 10).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_policies() {
+        let db = setup_test_db().await;
+        let network = NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "http://localhost:8545".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        let wallet = WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "ops".to_string(),
+                address: addr("0xaaa").parse().unwrap(),
+                encrypted_key: vec![1, 2, 3],
+            },
+        )
+        .await
+        .unwrap();
+        assert!(WalletPolicyRepository::get(&db, wallet.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let policy = WalletPolicy {
+            max_value_per_tx: Some("1000".to_string()),
+            allowed_networks: Some(vec!["mainnet".to_string()]),
+            allowed_functions: Some(vec!["pause".to_string()]),
+            ..Default::default()
+        };
+        WalletPolicyRepository::set(&db, wallet.id, &policy)
+            .await
+            .unwrap();
+        assert_eq!(
+            WalletPolicyRepository::get(&db, wallet.id).await.unwrap(),
+            Some(policy.clone())
+        );
+        let replaced = WalletPolicy {
+            max_daily_spend: Some("5000".to_string()),
+            ..Default::default()
+        };
+        WalletPolicyRepository::set(&db, wallet.id, &replaced)
+            .await
+            .unwrap();
+        assert_eq!(
+            WalletPolicyRepository::get(&db, wallet.id).await.unwrap(),
+            Some(replaced)
+        );
+
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        let at_most_two = |earlier: &[String]| earlier.len() < 2;
+        let mut spends = Vec::new();
+        for value in ["300", "200", "100"] {
+            spends.push(
+                WalletPolicyRepository::reserve_spend(
+                    &db,
+                    wallet.id,
+                    network.id,
+                    value,
+                    day,
+                    &at_most_two,
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        assert!(spends[0].is_some() && spends[1].is_some());
+        assert_eq!(spends[2], None);
+        assert_eq!(
+            WalletPolicyRepository::spent_within(&db, wallet.id, network.id, day)
+                .await
+                .unwrap(),
+            ["300", "200"]
+        );
+        WalletPolicyRepository::release_spend(&db, spends[0].unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            WalletPolicyRepository::spent_within(&db, wallet.id, network.id, day)
+                .await
+                .unwrap(),
+            ["200"]
+        );
+
+        assert!(WalletPolicyRepository::clear(&db, wallet.id).await.unwrap());
+        assert!(!WalletPolicyRepository::clear(&db, wallet.id).await.unwrap());
+
+        // Spends go with their wallet
+        WalletRepository::delete(&db, "ops").await.unwrap();
+        assert!(
+            WalletPolicyRepository::spent_within(&db, wallet.id, network.id, day)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wallet_rotation() {
+        let db = setup_test_db().await;
+        let wallet = WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "deployer".to_string(),
+                address: addr("0xaaa").parse().unwrap(),
+                encrypted_key: vec![1, 2, 3],
+            },
+        )
+        .await
+        .unwrap();
+        WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "ops".to_string(),
+                address: addr("0xccc").parse().unwrap(),
+                encrypted_key: vec![7],
+            },
+        )
+        .await
+        .unwrap();
+
+        let new_address: EvmAddress = addr("0xbbb").parse().unwrap();
+        let rotated = WalletRepository::rotate(&db, "deployer", new_address, &[4, 5, 6])
+            .await
+            .unwrap();
+        assert_eq!(rotated.id, wallet.id);
+        assert_eq!(rotated.address, new_address);
+        assert_eq!(rotated.row_version, wallet.row_version + 1);
+        let with_key = WalletRepository::get_with_key(&db, "deployer")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(with_key.encrypted_key, [4, 5, 6]);
+
+        let rotations = WalletRepository::rotations(&db, wallet.id).await.unwrap();
+        assert_eq!(rotations.len(), 1);
+        assert_eq!(rotations[0].old_address, wallet.address);
+        assert_eq!(rotations[0].new_address, new_address);
+
+        // Another wallet's address is taken, and unknown wallets fail
+        let taken: EvmAddress = addr("0xccc").parse().unwrap();
+        assert!(WalletRepository::rotate(&db, "deployer", taken, &[8])
+            .await
+            .is_err());
+        assert!(matches!(
+            WalletRepository::rotate(&db, "missing", new_address, &[8]).await,
+            Err(smolder_core::Error::WalletNotFound(_))
+        ));
+        assert_eq!(
+            WalletRepository::rotations(&db, wallet.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wallet_deployments_and_calls() {
+        let db = setup_test_db().await;
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+        let old_address: EvmAddress = addr("0xAAA").parse().unwrap();
+        let new_address: EvmAddress = addr("0xbbb").parse().unwrap();
+        let wallet = WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "deployer".to_string(),
+                address: old_address,
+                encrypted_key: vec![1],
+            },
+        )
+        .await
+        .unwrap();
+
+        // Deployers are matched whatever their case, across rotations
+        for (address, deployer) in [
+            ("0x1", old_address.to_string().to_lowercase()),
+            ("0x2", "0xddd".to_string()),
+        ] {
+            DeploymentRepository::create(
+                &db,
+                &NewDeployment {
+                    contract_id: v1.contract_id,
+                    network_id: v1.network_id,
+                    address: addr(address).parse().unwrap(),
+                    deployer,
+                    tx_hash: None,
+                    block_number: None,
+                    constructor_args: None,
+                    initcode_size: None,
+                    runtime_size: None,
+                    gas_used: None,
+                    gas_price: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        WalletRepository::rotate(&db, "deployer", new_address, &[2])
+            .await
+            .unwrap();
+        let newest = DeploymentRepository::create(
+            &db,
+            &NewDeployment {
+                contract_id: v1.contract_id,
+                network_id: v1.network_id,
+                address: addr("0x3").parse().unwrap(),
+                deployer: new_address.to_string(),
+                tx_hash: None,
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        // A deployment recorded later from the old key still belongs to it
+        let imported = DeploymentRepository::create(
+            &db,
+            &NewDeployment {
+                contract_id: v1.contract_id,
+                network_id: v1.network_id,
+                address: addr("0x4").parse().unwrap(),
+                deployer: old_address.to_string(),
+                tx_hash: None,
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(imported.wallet_id, Some(wallet.id));
+
+        let deployments = WalletRepository::deployments(&db, wallet.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(deployments.len(), 3);
+        assert_eq!(deployments[0].id, imported.id);
+        assert_eq!(deployments[1].id, newest.id);
+        assert_eq!(
+            deployments[2].address,
+            addr("0x1").parse::<EvmAddress>().unwrap()
+        );
+        assert_eq!(
+            WalletRepository::deployments(&db, wallet.id, 1)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        for wallet_id in [Some(wallet.id), None] {
+            CallHistoryRepository::create(
+                &db,
+                &NewCallHistory {
+                    deployment_id: v2.id,
+                    wallet_id,
+                    function_name: "transfer".to_string(),
+                    function_signature: "transfer(address,uint256)".to_string(),
+                    input_params: "[]".to_string(),
+                    call_type: CallType::Write,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        let calls = WalletRepository::calls(&db, wallet.id, 10).await.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].wallet_name.as_deref(), Some("deployer"));
+    }
+
+    #[tokio::test]
+    async fn test_approvals() {
+        let db = setup_test_db().await;
+        let (v1, _) = setup_versioned_deployments(&db).await;
+        let alice = ApiTokenRepository::create(&db, "alice", "0xa", ApiRole::Admin)
+            .await
+            .unwrap();
+        let bob = ApiTokenRepository::create(&db, "bob", "0xb", ApiRole::Admin)
+            .await
+            .unwrap();
+
+        let new = NewApproval {
+            kind: ApprovalKind::Send,
+            network_id: v1.network_id,
+            deployment_id: Some(v1.id),
+            summary: "Token.pause()".to_string(),
+            request: "{}".to_string(),
+            requested_by: alice.id,
+            ttl: Duration::from_secs(3600),
+        };
+        let approval = ApprovalRepository::create(&db, &new).await.unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Pending);
+        assert_eq!(approval.requested_by.as_deref(), Some("alice"));
+        assert_eq!(approval.requester_token_id, Some(alice.id));
+        assert_eq!(approval.newest_token_id, Some(bob.id));
+        assert!(approval.decided_by.is_none());
+
+        // Only the first decision counts
+        assert!(
+            ApprovalRepository::decide(&db, approval.id, ApprovalStatus::Approved, bob.id)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !ApprovalRepository::decide(&db, approval.id, ApprovalStatus::Rejected, bob.id)
+                .await
+                .unwrap()
+        );
+        ApprovalRepository::record_result(&db, approval.id, None, Some("reverted"))
+            .await
+            .unwrap();
+        let failed = ApprovalRepository::get(&db, approval.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(failed.status, ApprovalStatus::Failed);
+        assert_eq!(failed.decided_by.as_deref(), Some("bob"));
+        assert_eq!(failed.error_message.as_deref(), Some("reverted"));
+
+        // Expired approvals can't be decided
+        let stale = ApprovalRepository::create(
+            &db,
+            &NewApproval {
+                ttl: Duration::ZERO,
+                ..new
+            },
+        )
+        .await
+        .unwrap();
+        assert!(
+            !ApprovalRepository::decide(&db, stale.id, ApprovalStatus::Approved, bob.id)
+                .await
+                .unwrap()
+        );
+        assert_eq!(ApprovalRepository::expire(&db).await.unwrap(), 1);
+
+        let expired = ApprovalRepository::list(&db, Some(ApprovalStatus::Expired))
+            .await
+            .unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, stale.id);
+        assert_eq!(ApprovalRepository::list(&db, None).await.unwrap().len(), 2);
+
+        // Revoking the requesting token keeps its approvals
+        ApiTokenRepository::delete(&db, "alice").await.unwrap();
+        let kept = ApprovalRepository::get(&db, stale.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(kept.requested_by.is_none());
+        assert_eq!(kept.requester_token_id, Some(alice.id));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_credentials_are_encrypted_at_rest() {
+        let db = setup_test_db().await;
+        let url = "https://mainnet.infura.io/v3/9aa3d95b3bc440fa88ea12eaa4456161";
+        NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: url.to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        let stored = || async {
+            sqlx::query_scalar::<_, String>("SELECT rpc_url FROM networks WHERE name = 'mainnet'")
+                .fetch_one(db.write_pool())
+                .await
+                .unwrap()
+        };
+        assert!(!stored().await.contains("9aa3d95b"));
+        let network = NetworkRepository::get_by_name(&db, "mainnet")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(network.rpc_url.expose(), url);
+        assert_eq!(
+            network.rpc_url.to_string(),
+            "https://mainnet.infura.io/v3/***"
+        );
+
+        // URLs stored in plain text by older versions are encrypted on start
+        sqlx::query("UPDATE networks SET rpc_url = ? WHERE name = 'mainnet'")
+            .bind(url)
+            .execute(db.write_pool())
+            .await
+            .unwrap();
+        db.init_schema().await.unwrap();
+        assert!(!stored().await.contains("9aa3d95b"));
+        let network = NetworkRepository::get_by_name(&db, "mainnet")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(network.rpc_url.expose(), url);
+
+        // The masked URL sent back as read keeps the credentials, and other
+        // masked URLs are refused
+        let masked = |rpc_url: &str| NetworkUpdate {
+            rpc_url: Some(rpc_url.to_string()),
+            explorer_url: Some("https://etherscan.io".to_string()),
+        };
+        let updated = NetworkRepository::update(
+            &db,
+            "mainnet",
+            &masked("https://mainnet.infura.io/v3/***"),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.rpc_url.expose(), url);
+        assert_eq!(
+            updated.explorer_url.as_deref(),
+            Some("https://etherscan.io")
+        );
+        assert!(NetworkRepository::update(
+            &db,
+            "mainnet",
+            &masked("https://eth-mainnet.g.alchemy.com/v2/***"),
+            None,
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_row_versions() {
+        let db = setup_test_db().await;
+        let network = NetworkRepository::upsert(
+            &db,
+            &NewNetwork {
+                name: "mainnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://eth.llamarpc.com".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(network.row_version, 1);
+
+        // Every change bumps the version, whichever method made it
+        NetworkRepository::set_protected(&db, "mainnet", true)
+            .await
+            .unwrap();
+        let update = NetworkUpdate {
+            rpc_url: Some("https://rpc.example.com".to_string()),
+            explorer_url: None,
+        };
+        let updated = NetworkRepository::update(&db, "mainnet", &update, Some(2))
+            .await
+            .unwrap();
+        assert_eq!(updated.row_version, 3);
+        assert_eq!(updated.rpc_url, "https://rpc.example.com");
+        assert!(updated.protected);
+
+        let stale = NetworkRepository::update(&db, "mainnet", &update, Some(2)).await;
+        assert!(matches!(
+            stale,
+            Err(smolder_core::Error::VersionMismatch {
+                expected: 2,
+                actual: 3,
+                ..
+            })
+        ));
+        assert!(NetworkRepository::update(&db, "missing", &update, None)
+            .await
+            .unwrap_err()
+            .is_not_found());
+
+        let wallet = WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "deployer".to_string(),
+                address: addr("0xaaa").parse().unwrap(),
+                encrypted_key: vec![1, 2, 3],
+            },
+        )
+        .await
+        .unwrap();
+        assert!(
+            WalletRepository::delete_if(&db, "deployer", Some(wallet.row_version + 1))
+                .await
+                .is_err()
+        );
+        WalletRepository::delete_if(&db, "deployer", Some(wallet.row_version))
+            .await
+            .unwrap();
+        assert!(WalletRepository::delete_if(&db, "deployer", None)
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_keys() {
+        let db = setup_test_db().await;
+
+        assert!(IdempotencyRepository::claim(&db, "ci", "k1", "abc")
+            .await
+            .unwrap()
+            .is_none());
+        let running = IdempotencyRepository::claim(&db, "ci", "k1", "abc")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(running.fingerprint, "abc");
+        assert_eq!(running.status, None);
+
+        // Keys are scoped
+        assert!(IdempotencyRepository::claim(&db, "dashboard", "k1", "def")
+            .await
+            .unwrap()
+            .is_none());
+
+        IdempotencyRepository::complete(&db, "ci", "k1", 201, Some("application/json"), b"{}")
+            .await
+            .unwrap();
+        let done = IdempotencyRepository::claim(&db, "ci", "k1", "abc")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(done.status, Some(201));
+        assert_eq!(done.content_type.as_deref(), Some("application/json"));
+        assert_eq!(done.body.as_deref(), Some(&b"{}"[..]));
+
+        IdempotencyRepository::release(&db, "dashboard", "k1")
+            .await
+            .unwrap();
+        assert!(IdempotencyRepository::claim(&db, "dashboard", "k1", "def")
+            .await
+            .unwrap()
+            .is_none());
+
+        sqlx::query("UPDATE idempotency_keys SET created_at = datetime('now', '-2 days')")
+            .execute(db.write_pool())
+            .await
+            .unwrap();
+        let pruned = IdempotencyRepository::prune(&db, Duration::from_secs(24 * 3600))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 2);
+    }
+
+    fn new_job(kind: &str, max_attempts: i64) -> NewJob {
+        NewJob {
+            kind: kind.to_string(),
+            payload: "{}".to_string(),
+            max_attempts,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jobs() {
+        let db = setup_test_db().await;
+
+        let first = JobRepository::enqueue(&db, &new_job("receipt", 3))
+            .await
+            .unwrap();
+        assert_eq!(first.state, JobState::Queued);
+        assert_eq!(first.attempts, 0);
+        let second = JobRepository::enqueue(&db, &new_job("webhook", 1))
+            .await
+            .unwrap();
+
+        // Oldest first, each job once
+        let claimed = JobRepository::claim(&db).await.unwrap().unwrap();
+        assert_eq!(claimed.id, first.id);
+        assert_eq!(claimed.state, JobState::Running);
+        assert_eq!(claimed.attempts, 1);
+        assert!(claimed.started_at.is_some());
+        assert_eq!(
+            JobRepository::claim(&db).await.unwrap().unwrap().id,
+            second.id
+        );
+        assert!(JobRepository::claim(&db).await.unwrap().is_none());
+
+        // A retried job waits for its delay
+        let state = JobRepository::fail(&db, first.id, "timed out", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert_eq!(state, JobState::Queued);
+        assert!(JobRepository::claim(&db).await.unwrap().is_none());
+
+        // Out of attempts
+        let state = JobRepository::fail(&db, second.id, "timed out", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert_eq!(state, JobState::Failed);
+
+        let jobs = JobRepository::list(&db, &JobFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            jobs.iter().map(|j| j.id).collect::<Vec<_>>(),
+            vec![second.id, first.id]
+        );
+        assert_eq!(jobs[0].last_error.as_deref(), Some("timed out"));
+        assert!(jobs[0].finished_at.is_some());
+        assert!(jobs[1].finished_at.is_none());
+
+        sqlx::query("UPDATE jobs SET run_after = datetime('now', '-1 seconds')")
+            .execute(db.write_pool())
+            .await
+            .unwrap();
+        let retried = JobRepository::claim(&db).await.unwrap().unwrap();
+        assert_eq!((retried.id, retried.attempts), (first.id, 2));
+
+        // An interrupted attempt isn't counted
+        assert_eq!(JobRepository::requeue_running(&db).await.unwrap(), 1);
+        let requeued = JobRepository::claim(&db).await.unwrap().unwrap();
+        assert_eq!((requeued.id, requeued.attempts), (first.id, 2));
+        JobRepository::complete(&db, first.id).await.unwrap();
+
+        let failed = JobRepository::list(
+            &db,
+            &JobFilter {
+                state: Some(JobState::Failed),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, second.id);
+        let receipts = JobRepository::list(
+            &db,
+            &JobFilter {
+                kind: Some("receipt".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(receipts[0].state, JobState::Succeeded);
+
+        // Only finished jobs are pruned
+        let queued = JobRepository::enqueue(&db, &new_job("receipt", 3))
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE jobs SET finished_at = datetime('now', '-2 days'), \
+             created_at = datetime('now', '-2 days')",
+        )
+        .execute(db.write_pool())
+        .await
+        .unwrap();
+        let pruned = JobRepository::prune(&db, Duration::from_secs(24 * 3600))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 2);
+        let left = JobRepository::list(&db, &JobFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, queued.id);
     }
 }
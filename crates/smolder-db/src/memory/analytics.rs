@@ -0,0 +1,293 @@
+//! AnalyticsRepository implementation in memory
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::{chains, Result, TransactionStatus};
+
+use super::{date, period, MemoryDatabase, Tables};
+use crate::models::{
+    Activity, ActivityKind, CostStat, GasStat, NetworkStats, PriceDay, RegistryStats,
+};
+use crate::repositories::{sum_costs, FeePaid};
+use crate::traits::{AnalyticsRepository, CostFilter, GasStatsFilter};
+
+/// Gas spent by one successful call or deployment
+struct GasSpent {
+    network: String,
+    contract: String,
+    version: i64,
+    function: String,
+    spent_at: String,
+    gas: i64,
+}
+
+impl Tables {
+    /// Gas spent by successful calls and by deployments, one row per
+    /// transaction
+    fn gas_spent(&self) -> Vec<GasSpent> {
+        let calls = self.call_history.values().filter_map(|h| {
+            let gas = h
+                .gas_used
+                .filter(|_| h.status == Some(TransactionStatus::Success))?;
+            let view = self.call_view(h)?;
+            Some(GasSpent {
+                network: view.network_name,
+                contract: view.contract_name,
+                version: self.deployments.get(&h.deployment_id.0)?.version,
+                function: h.function_name.clone(),
+                spent_at: h.created_at.clone(),
+                gas,
+            })
+        });
+        let deployments = self.deployments.values().filter_map(|d| {
+            Some(GasSpent {
+                network: self.network_name(d.network_id)?.to_string(),
+                contract: self.contracts.get(&d.contract_id.0)?.name.clone(),
+                version: d.version,
+                function: "constructor".to_string(),
+                spent_at: d.deployed_at.clone(),
+                gas: d.gas_used?,
+            })
+        });
+        calls.chain(deployments).collect()
+    }
+
+    /// Fees paid for deployments and calls matching `filter`, one row per
+    /// transaction, with the cached USD price of the day and `spent_at`
+    fn fees_paid(&self, filter: &CostFilter) -> Vec<(FeePaid, String)> {
+        let deployments = self.deployments.values().filter_map(|d| {
+            let network = self.networks.get(&d.network_id.0)?;
+            let wallet = self
+                .wallet_name(d.wallet_id)
+                .unwrap_or_else(|| d.deployer.clone());
+            Some((
+                network,
+                self.contracts.get(&d.contract_id.0)?.name.clone(),
+                Some(wallet),
+                true,
+                d.deployed_at.clone(),
+                d.gas_used,
+                d.fee.clone()?,
+            ))
+        });
+        let calls = self.call_history.values().filter_map(|h| {
+            let deployment = self.deployments.get(&h.deployment_id.0)?;
+            Some((
+                self.networks.get(&deployment.network_id.0)?,
+                self.contracts.get(&deployment.contract_id.0)?.name.clone(),
+                self.wallet_name(h.wallet_id),
+                false,
+                h.created_at.clone(),
+                h.gas_used,
+                h.fee.clone()?,
+            ))
+        });
+
+        deployments
+            .chain(calls)
+            .filter(|(network, contract, wallet, _, spent_at, _, _)| {
+                filter.network.as_ref().is_none_or(|n| network.name == *n)
+                    && filter.contract.as_ref().is_none_or(|c| contract == c)
+                    && filter.wallet.as_ref().is_none_or(|filter| {
+                        wallet
+                            .as_ref()
+                            .is_some_and(|w| w.eq_ignore_ascii_case(filter))
+                    })
+                    && filter.since.as_ref().is_none_or(|s| spent_at >= s)
+                    && filter.until.as_ref().is_none_or(|u| spent_at <= u)
+            })
+            .map(
+                |(network, contract, wallet, deployment, spent_at, gas, fee)| {
+                    let usd = self
+                        .prices
+                        .get(&(network.currency_symbol.clone(), date(&spent_at)))
+                        .copied();
+                    let paid = FeePaid {
+                        network: network.name.clone(),
+                        chain_id: network.chain_id.0,
+                        currency_symbol: network.currency_symbol.clone(),
+                        currency_decimals: network.currency_decimals,
+                        contract,
+                        wallet,
+                        period: None,
+                        deployment,
+                        gas,
+                        fee,
+                        usd,
+                    };
+                    (paid, spent_at)
+                },
+            )
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AnalyticsRepository for MemoryDatabase {
+    async fn gas_stats(&self, filter: &GasStatsFilter) -> Result<Vec<GasStat>> {
+        let spent = self.read().gas_spent();
+
+        let mut groups: BTreeMap<_, Vec<i64>> = BTreeMap::new();
+        for row in spent {
+            let matches = filter.network.as_ref().is_none_or(|n| row.network == *n)
+                && filter.contract.as_ref().is_none_or(|c| row.contract == *c)
+                && filter.since.as_ref().is_none_or(|s| row.spent_at >= *s)
+                && filter.until.as_ref().is_none_or(|u| row.spent_at <= *u);
+            if !matches {
+                continue;
+            }
+            // Keyed in the order results are sorted by
+            let key = (
+                row.network,
+                row.contract,
+                row.function,
+                row.version,
+                period(filter.interval, &row.spent_at),
+            );
+            groups.entry(key).or_default().push(row.gas);
+        }
+
+        Ok(groups
+            .into_iter()
+            .map(|((network, contract, function, version, period), gas)| {
+                let total_gas: i64 = gas.iter().sum();
+                GasStat {
+                    network,
+                    contract,
+                    version,
+                    function,
+                    period,
+                    calls: gas.len() as i64,
+                    avg_gas: (total_gas as f64 / gas.len() as f64).round() as i64,
+                    min_gas: gas.iter().copied().min().unwrap_or_default(),
+                    max_gas: gas.iter().copied().max().unwrap_or_default(),
+                    total_gas,
+                }
+            })
+            .collect())
+    }
+
+    async fn costs(&self, filter: &CostFilter) -> Result<Vec<CostStat>> {
+        let rows = self
+            .read()
+            .fees_paid(filter)
+            .into_iter()
+            .map(|(paid, spent_at)| FeePaid {
+                period: period(filter.interval, &spent_at),
+                ..paid
+            })
+            .collect();
+        Ok(sum_costs(rows))
+    }
+
+    async fn unpriced_fees(&self, filter: &CostFilter) -> Result<Vec<PriceDay>> {
+        let mut unpriced: Vec<PriceDay> = self
+            .read()
+            .fees_paid(filter)
+            .into_iter()
+            .filter(|(paid, _)| paid.usd.is_none() && !chains::is_testnet(paid.chain_id as u64))
+            .map(|(paid, spent_at)| PriceDay {
+                symbol: paid.currency_symbol,
+                day: date(&spent_at),
+            })
+            .collect();
+        unpriced.sort();
+        unpriced.dedup();
+        Ok(unpriced)
+    }
+
+    async fn stats(&self, recent: u32) -> Result<RegistryStats> {
+        let tables = self.read();
+        let is_live = |d: &&crate::models::Deployment| d.is_current && d.archived_at.is_none();
+        let calls_since = |age: Duration| {
+            let cutoff = tables.ago(age);
+            tables
+                .call_history
+                .values()
+                .filter(|h| h.created_at >= cutoff)
+                .count() as i64
+        };
+        let calls_with = |statuses: &[TransactionStatus]| {
+            tables
+                .call_history
+                .values()
+                .filter(|h| h.status.is_some_and(|s| statuses.contains(&s)))
+                .count() as i64
+        };
+
+        let mut networks: Vec<NetworkStats> = tables
+            .networks
+            .values()
+            .map(|n| {
+                let deployments = tables.deployments.values().filter(|d| d.network_id == n.id);
+                NetworkStats {
+                    network: n.name.clone(),
+                    chain_id: n.chain_id,
+                    deployments: deployments.clone().filter(is_live).count() as i64,
+                    last_deployed_at: deployments.map(|d| d.deployed_at.clone()).max(),
+                }
+            })
+            .collect();
+        networks.sort_by(|a, b| a.network.cmp(&b.network));
+
+        let deployed = tables
+            .deployments
+            .values()
+            .rev()
+            .take(recent as usize)
+            .filter_map(|d| {
+                Some(Activity {
+                    kind: ActivityKind::Deployment,
+                    deployment_id: d.id,
+                    contract_name: tables.contracts.get(&d.contract_id.0)?.name.clone(),
+                    network_name: tables.network_name(d.network_id)?.to_string(),
+                    version: d.version,
+                    function_name: None,
+                    status: None,
+                    at: d.deployed_at.clone(),
+                })
+            });
+        let called = tables
+            .call_history
+            .values()
+            .rev()
+            .take(recent as usize)
+            .filter_map(|h| {
+                let view = tables.call_view(h)?;
+                Some(Activity {
+                    kind: ActivityKind::Call,
+                    deployment_id: h.deployment_id,
+                    contract_name: view.contract_name,
+                    network_name: view.network_name,
+                    version: tables.deployments.get(&h.deployment_id.0)?.version,
+                    function_name: Some(h.function_name.clone()),
+                    status: h.status,
+                    at: h.created_at.clone(),
+                })
+            });
+        let mut recent_activity: Vec<Activity> = deployed.chain(called).collect();
+        // Like SQLite's ORDER BY at DESC, kind, deployment_id DESC, where
+        // 'call' sorts before 'deployment'
+        recent_activity.sort_by(|a, b| {
+            b.at.cmp(&a.at)
+                .then(
+                    (a.kind == ActivityKind::Deployment).cmp(&(b.kind == ActivityKind::Deployment)),
+                )
+                .then(b.deployment_id.0.cmp(&a.deployment_id.0))
+        });
+        recent_activity.truncate(recent as usize);
+
+        Ok(RegistryStats {
+            contracts: tables.contracts.len() as i64,
+            deployments: tables.deployments.values().filter(is_live).count() as i64,
+            calls_24h: calls_since(Duration::from_secs(24 * 60 * 60)),
+            calls_7d: calls_since(Duration::from_secs(7 * 24 * 60 * 60)),
+            pending_txs: calls_with(&[TransactionStatus::Pending]),
+            failed_txs: calls_with(&[TransactionStatus::Failed, TransactionStatus::Reverted]),
+            networks,
+            recent_activity,
+        })
+    }
+}
@@ -0,0 +1,88 @@
+//! ApiTokenRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{ApiRole, ApiTokenId, Error, Result};
+
+use super::{constraint, MemoryDatabase};
+use crate::models::ApiToken;
+use crate::traits::ApiTokenRepository;
+
+#[async_trait]
+impl ApiTokenRepository for MemoryDatabase {
+    async fn list(&self) -> Result<Vec<ApiToken>> {
+        let mut tokens: Vec<ApiToken> = self.read().api_tokens.values().cloned().collect();
+        tokens.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tokens)
+    }
+
+    async fn count(&self) -> Result<i64> {
+        Ok(self.read().api_tokens.len() as i64)
+    }
+
+    async fn create(&self, name: &str, token_hash: &str, role: ApiRole) -> Result<ApiToken> {
+        let mut tables = self.write();
+        if tables
+            .api_tokens
+            .values()
+            .any(|t| t.name == name || t.token_hash == token_hash)
+        {
+            return Err(constraint(format!("Token '{}' already exists", name)));
+        }
+
+        let created = ApiToken {
+            id: ApiTokenId(tables.next_id("api_tokens")),
+            name: name.to_string(),
+            token_hash: token_hash.to_string(),
+            role,
+            created_at: tables.now(),
+            last_used_at: None,
+        };
+        tables.api_tokens.insert(created.id.0, created.clone());
+        Ok(created)
+    }
+
+    async fn set_role(&self, name: &str, role: ApiRole) -> Result<()> {
+        let mut tables = self.write();
+        let token = tables
+            .api_tokens
+            .values_mut()
+            .find(|t| t.name == name)
+            .ok_or_else(|| Error::ApiTokenNotFound(name.to_string()))?;
+        token.role = role;
+        Ok(())
+    }
+
+    async fn authenticate(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let mut tables = self.write();
+        let now = tables.now();
+        Ok(tables
+            .api_tokens
+            .values_mut()
+            .find(|t| t.token_hash == token_hash)
+            .map(|token| {
+                token.last_used_at = Some(now);
+                token.clone()
+            }))
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let mut tables = self.write();
+        let id = tables
+            .api_tokens
+            .values()
+            .find(|t| t.name == name)
+            .map(|t| t.id)
+            .ok_or_else(|| Error::ApiTokenNotFound(name.to_string()))?;
+
+        tables.api_tokens.remove(&id.0);
+        for approval in tables.approvals.values_mut() {
+            if approval.requested_by == Some(id) {
+                approval.requested_by = None;
+            }
+            if approval.decided_by == Some(id) {
+                approval.decided_by = None;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! ApprovalRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{ApiTokenId, ApprovalId, ApprovalStatus, Error, Result};
+
+use super::{constraint, ApprovalRow, MemoryDatabase, Tables};
+use crate::models::{Approval, NewApproval};
+use crate::traits::ApprovalRepository;
+
+impl Tables {
+    /// An approval with its network and tokens resolved to names
+    fn approval_view(&self, approval: &ApprovalRow) -> Option<Approval> {
+        let token_name = |id: Option<ApiTokenId>| {
+            id.and_then(|id| self.api_tokens.get(&id.0))
+                .map(|t| t.name.clone())
+        };
+        Some(Approval {
+            id: approval.id,
+            kind: approval.kind,
+            network_id: approval.network_id,
+            network_name: self.network_name(approval.network_id)?.to_string(),
+            deployment_id: approval.deployment_id,
+            summary: approval.summary.clone(),
+            request: approval.request.clone(),
+            status: approval.status,
+            requested_by: token_name(approval.requested_by),
+            requester_token_id: approval.requester_token_id,
+            newest_token_id: approval.newest_token_id,
+            decided_by: token_name(approval.decided_by),
+            result: approval.result.clone(),
+            error_message: approval.error_message.clone(),
+            created_at: approval.created_at.clone(),
+            expires_at: approval.expires_at.clone(),
+            decided_at: approval.decided_at.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl ApprovalRepository for MemoryDatabase {
+    async fn create(&self, approval: &NewApproval) -> Result<Approval> {
+        let mut tables = self.write();
+        if !tables.networks.contains_key(&approval.network_id.0) {
+            return Err(constraint(format!(
+                "Network {} does not exist",
+                approval.network_id
+            )));
+        }
+
+        let row = ApprovalRow {
+            id: ApprovalId(tables.next_id("approvals")),
+            kind: approval.kind,
+            network_id: approval.network_id,
+            deployment_id: approval.deployment_id,
+            summary: approval.summary.clone(),
+            request: approval.request.clone(),
+            status: ApprovalStatus::Pending,
+            requested_by: Some(approval.requested_by),
+            requester_token_id: Some(approval.requested_by),
+            newest_token_id: tables.api_tokens.keys().max().copied().map(ApiTokenId),
+            decided_by: None,
+            result: None,
+            error_message: None,
+            created_at: tables.now(),
+            expires_at: tables.now_plus(approval.ttl.as_secs() as i64),
+            decided_at: None,
+        };
+        let id = row.id;
+        tables.approvals.insert(id.0, row);
+        tables
+            .approvals
+            .get(&id.0)
+            .and_then(|a| tables.approval_view(a))
+            .ok_or(Error::ApprovalNotFound(id))
+    }
+
+    async fn get(&self, id: ApprovalId) -> Result<Option<Approval>> {
+        let tables = self.read();
+        Ok(tables
+            .approvals
+            .get(&id.0)
+            .and_then(|a| tables.approval_view(a)))
+    }
+
+    async fn list(&self, status: Option<ApprovalStatus>) -> Result<Vec<Approval>> {
+        let tables = self.read();
+        Ok(tables
+            .approvals
+            .values()
+            .rev()
+            .filter(|a| status.is_none_or(|s| a.status == s))
+            .filter_map(|a| tables.approval_view(a))
+            .collect())
+    }
+
+    async fn expire(&self) -> Result<u64> {
+        let mut tables = self.write();
+        let now = tables.now();
+        let mut expired = 0;
+        for approval in tables.approvals.values_mut() {
+            if approval.status == ApprovalStatus::Pending && approval.expires_at <= now {
+                approval.status = ApprovalStatus::Expired;
+                expired += 1;
+            }
+        }
+        Ok(expired)
+    }
+
+    async fn decide(
+        &self,
+        id: ApprovalId,
+        status: ApprovalStatus,
+        decided_by: ApiTokenId,
+    ) -> Result<bool> {
+        let mut tables = self.write();
+        let now = tables.now();
+        match tables.approvals.get_mut(&id.0) {
+            Some(approval)
+                if approval.status == ApprovalStatus::Pending && approval.expires_at > now =>
+            {
+                approval.status = status;
+                approval.decided_by = Some(decided_by);
+                approval.decided_at = Some(now);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn record_result(
+        &self,
+        id: ApprovalId,
+        result: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        if let Some(approval) = self.write().approvals.get_mut(&id.0) {
+            approval.status = match error_message {
+                Some(_) => ApprovalStatus::Failed,
+                None => ApprovalStatus::Approved,
+            };
+            approval.result = result.map(str::to_string);
+            approval.error_message = error_message.map(str::to_string);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+//! AuditRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use super::MemoryDatabase;
+use crate::models::{AuditEntry, NewAuditEntry};
+use crate::traits::AuditRepository;
+
+#[async_trait]
+impl AuditRepository for MemoryDatabase {
+    async fn record(&self, entry: &NewAuditEntry) -> Result<AuditEntry> {
+        let mut tables = self.write();
+        let recorded = AuditEntry {
+            id: tables.next_id("audit_log"),
+            action: entry.action,
+            target: entry.target.clone(),
+            source: entry.source,
+            actor: entry.actor.clone(),
+            detail: entry.detail.clone(),
+            created_at: tables.now(),
+        };
+        tables.audit_log.insert(recorded.id, recorded.clone());
+        Ok(recorded)
+    }
+
+    async fn list(&self, limit: i64) -> Result<Vec<AuditEntry>> {
+        Ok(self
+            .read()
+            .audit_log
+            .values()
+            .rev()
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+}
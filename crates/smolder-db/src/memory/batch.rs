@@ -0,0 +1,111 @@
+//! BatchRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{Error, Result, TransactionStatus};
+
+use super::{constraint, BatchCallRow, BatchRow, MemoryDatabase};
+use crate::models::{Batch, CallHistoryView, NewBatch};
+use crate::traits::BatchRepository;
+
+#[async_trait]
+impl BatchRepository for MemoryDatabase {
+    async fn create(&self, batch: &NewBatch) -> Result<Batch> {
+        let id = {
+            let mut tables = self.write();
+            if !tables.networks.contains_key(&batch.network_id.0) {
+                return Err(constraint(format!(
+                    "Network {} does not exist",
+                    batch.network_id
+                )));
+            }
+
+            let row = BatchRow {
+                id: tables.next_id("batches"),
+                plan: batch.plan.clone(),
+                network_id: batch.network_id,
+                wallet_id: batch.wallet_id,
+                steps: batch.steps,
+                status: TransactionStatus::Pending,
+                created_at: tables.now(),
+                finished_at: None,
+            };
+            let id = row.id;
+            tables.batches.insert(id, row);
+            id
+        };
+
+        BatchRepository::get(self, id)
+            .await?
+            .ok_or_else(|| Error::Validation("Failed to create batch".into()))
+    }
+
+    async fn add_call(&self, batch_id: i64, position: i64, call_history_id: i64) -> Result<()> {
+        let mut tables = self.write();
+        if !tables.batches.contains_key(&batch_id)
+            || !tables.call_history.contains_key(&call_history_id)
+        {
+            return Err(constraint(format!(
+                "Batch {} or call {} does not exist",
+                batch_id, call_history_id
+            )));
+        }
+        if tables
+            .batch_calls
+            .iter()
+            .any(|c| c.batch_id == batch_id && c.position == position)
+        {
+            return Err(constraint(format!(
+                "Step {} of batch {} already has a call",
+                position, batch_id
+            )));
+        }
+
+        tables.batch_calls.push(BatchCallRow {
+            batch_id,
+            position,
+            call_history_id,
+        });
+        Ok(())
+    }
+
+    async fn finish(&self, batch_id: i64, status: TransactionStatus) -> Result<()> {
+        let mut tables = self.write();
+        let now = tables.now();
+        if let Some(batch) = tables.batches.get_mut(&batch_id) {
+            batch.status = status;
+            batch.finished_at = Some(now);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<Batch>> {
+        let tables = self.read();
+        Ok(tables.batches.get(&id).and_then(|b| {
+            Some(Batch {
+                id: b.id,
+                plan: b.plan.clone(),
+                network_name: tables.network_name(b.network_id)?.to_string(),
+                wallet_name: tables.wallet_name(b.wallet_id),
+                steps: b.steps,
+                status: b.status,
+                created_at: b.created_at.clone(),
+                finished_at: b.finished_at.clone(),
+            })
+        }))
+    }
+
+    async fn list_calls(&self, batch_id: i64) -> Result<Vec<CallHistoryView>> {
+        let tables = self.read();
+        let mut steps: Vec<&BatchCallRow> = tables
+            .batch_calls
+            .iter()
+            .filter(|c| c.batch_id == batch_id)
+            .collect();
+        steps.sort_by_key(|c| c.position);
+        Ok(steps
+            .into_iter()
+            .filter_map(|c| tables.call_history.get(&c.call_history_id))
+            .filter_map(|h| tables.call_view(h))
+            .collect())
+    }
+}
@@ -0,0 +1,193 @@
+//! CallHistoryRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{Error, Result, TransactionStatus, TxHash};
+
+use super::{paginate, reblocked, MemoryDatabase, Tables};
+use crate::models::{CallHistory, CallHistoryUpdate, CallHistoryView, NewCallHistory};
+use crate::repositories::fee;
+use crate::traits::{CallHistoryFilter, CallHistoryRepository, HistoryRetention};
+
+impl Tables {
+    /// Record a call before it runs
+    pub(super) fn insert_call(&mut self, entry: &NewCallHistory) -> Result<CallHistory> {
+        if !self.deployments.contains_key(&entry.deployment_id.0) {
+            return Err(Error::DeploymentNotFoundById(entry.deployment_id));
+        }
+
+        let created = CallHistory {
+            id: self.next_id("call_history"),
+            deployment_id: entry.deployment_id,
+            wallet_id: entry.wallet_id,
+            function_name: entry.function_name.clone(),
+            function_signature: entry.function_signature.clone(),
+            input_params: entry.input_params.clone(),
+            call_type: entry.call_type,
+            result: None,
+            tx_hash: None,
+            block_number: None,
+            gas_used: None,
+            gas_price: None,
+            fee: None,
+            status: None,
+            error_message: None,
+            created_at: self.now(),
+            confirmed_at: None,
+            simulation_url: None,
+            sender: None,
+            block_hash: None,
+            reorged_at: None,
+            finality: None,
+        };
+        self.call_history.insert(created.id, created.clone());
+        Ok(created)
+    }
+
+    /// Record the outcome of a call
+    pub(super) fn finish_call(
+        &mut self,
+        id: i64,
+        update: &CallHistoryUpdate,
+    ) -> Result<CallHistory> {
+        let now = self.now();
+        let entry = self
+            .call_history
+            .get_mut(&id)
+            .ok_or_else(|| Error::Validation(format!("Call {} not found", id)))?;
+        entry.result = update.result.clone();
+        entry.tx_hash = update.tx_hash;
+        entry.finality = reblocked(entry.finality, entry.block_number, update.block_number);
+        entry.block_number = update.block_number;
+        entry.gas_used = update.gas_used;
+        entry.gas_price = update.gas_price.clone();
+        entry.fee = fee(update.gas_used, update.gas_price.as_deref());
+        entry.status = Some(update.status);
+        entry.error_message = update.error_message.clone();
+        entry.confirmed_at = Some(now);
+        Ok(entry.clone())
+    }
+
+    /// Views of the calls matching a filter, newest first
+    fn filter_calls(&self, filter: &CallHistoryFilter) -> Vec<CallHistoryView> {
+        let mut calls: Vec<CallHistoryView> = self
+            .call_history
+            .values()
+            .filter_map(|h| self.call_view(h))
+            .filter(|h| {
+                filter.deployment_id.is_none_or(|id| h.deployment_id == id)
+                    && filter
+                        .contract
+                        .as_ref()
+                        .is_none_or(|c| h.contract_name == *c)
+                    && filter.network.as_ref().is_none_or(|n| h.network_name == *n)
+                    && filter
+                        .wallet
+                        .as_ref()
+                        .is_none_or(|w| h.wallet_name.as_ref() == Some(w))
+                    && filter.status.is_none_or(|s| h.status == Some(s))
+                    && filter.since.as_ref().is_none_or(|s| h.created_at >= *s)
+                    && filter.until.as_ref().is_none_or(|u| h.created_at <= *u)
+            })
+            .collect();
+        calls.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        calls
+    }
+
+    /// IDs of the calls `retention` lets go
+    fn prunable(&self, retention: &HistoryRetention) -> Vec<i64> {
+        let cutoff = self.ago(retention.max_age);
+        self.call_history
+            .values()
+            .filter(|h| h.created_at < cutoff)
+            .filter(|h| match h.status {
+                Some(TransactionStatus::Pending) => false,
+                Some(TransactionStatus::Failed | TransactionStatus::Reverted) => {
+                    !retention.keep_failed
+                }
+                _ => true,
+            })
+            .map(|h| h.id)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl CallHistoryRepository for MemoryDatabase {
+    async fn list(&self, filter: CallHistoryFilter) -> Result<Vec<CallHistory>> {
+        let tables = self.read();
+        let calls = paginate(tables.filter_calls(&filter), filter.limit, filter.offset);
+        Ok(calls
+            .iter()
+            .filter_map(|h| tables.call_history.get(&h.id).cloned())
+            .collect())
+    }
+
+    async fn list_views(&self, filter: CallHistoryFilter) -> Result<Vec<CallHistoryView>> {
+        let calls = self.read().filter_calls(&filter);
+        Ok(paginate(calls, filter.limit, filter.offset))
+    }
+
+    async fn count(&self, filter: &CallHistoryFilter) -> Result<i64> {
+        Ok(self.read().filter_calls(filter).len() as i64)
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<CallHistory>> {
+        Ok(self.read().call_history.get(&id).cloned())
+    }
+
+    async fn create(&self, entry: &NewCallHistory) -> Result<CallHistory> {
+        self.write().insert_call(entry)
+    }
+
+    async fn update(&self, id: i64, update: &CallHistoryUpdate) -> Result<()> {
+        let mut tables = self.write();
+        if tables.call_history.contains_key(&id) {
+            tables.finish_call(id, update)?;
+        }
+        Ok(())
+    }
+
+    async fn set_simulation_url(&self, id: i64, url: &str) -> Result<()> {
+        if let Some(entry) = self.write().call_history.get_mut(&id) {
+            entry.simulation_url = Some(url.to_string());
+        }
+        Ok(())
+    }
+
+    async fn get_by_tx_hash(&self, tx_hash: &TxHash) -> Result<Option<CallHistory>> {
+        Ok(self
+            .read()
+            .call_history
+            .values()
+            .find(|h| h.tx_hash.as_ref() == Some(tx_hash))
+            .cloned())
+    }
+
+    async fn import_external(
+        &self,
+        entry: &NewCallHistory,
+        sender: &str,
+        outcome: &CallHistoryUpdate,
+    ) -> Result<CallHistory> {
+        let mut tables = self.write();
+        let id = tables.insert_call(entry)?.id;
+        tables.finish_call(id, outcome)?;
+        let entry = tables
+            .call_history
+            .get_mut(&id)
+            .expect("call was just inserted");
+        entry.sender = Some(sender.to_string());
+        Ok(entry.clone())
+    }
+
+    async fn count_prunable(&self, retention: &HistoryRetention) -> Result<u64> {
+        Ok(self.read().prunable(retention).len() as u64)
+    }
+
+    async fn prune(&self, retention: &HistoryRetention) -> Result<u64> {
+        let mut tables = self.write();
+        let prunable = tables.prunable(retention);
+        tables.remove_calls(&prunable);
+        Ok(prunable.len() as u64)
+    }
+}
@@ -0,0 +1,183 @@
+//! ContractRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{ContractId, Error, Result};
+
+use super::{paginate, MemoryDatabase, Tables};
+use crate::models::{Contract, NewContract};
+use crate::traits::{ContractFilter, ContractRepository, ContractSort, SortOrder};
+
+impl Tables {
+    /// Insert a contract, or refresh the source path and ABI of the one with
+    /// the same name and bytecode hash, keeping a known storage layout and
+    /// compiler when the new record has none
+    pub(super) fn upsert_contract(&mut self, contract: &NewContract) -> Contract {
+        let existing = self
+            .contracts
+            .values_mut()
+            .find(|c| c.name == contract.name && c.bytecode_hash == contract.bytecode_hash);
+        if let Some(existing) = existing {
+            existing.source_path = contract.source_path.clone();
+            existing.abi = contract.abi.clone();
+            if contract.storage_layout.is_some() {
+                existing.storage_layout = contract.storage_layout.clone();
+            }
+            if contract.compiler.is_some() {
+                existing.compiler = contract.compiler.clone();
+            }
+            return existing.clone();
+        }
+
+        let (label, description) = self
+            .contract_labels
+            .get(&contract.name)
+            .cloned()
+            .unwrap_or_default();
+        let created = Contract {
+            id: ContractId(self.next_id("contracts")),
+            name: contract.name.clone(),
+            source_path: contract.source_path.clone(),
+            abi: contract.abi.clone(),
+            bytecode_hash: contract.bytecode_hash,
+            created_at: self.now(),
+            storage_layout: contract.storage_layout.clone(),
+            compiler: contract.compiler.clone(),
+            pinned: self.pinned_contracts.contains(&contract.name),
+            label,
+            description,
+        };
+        self.contracts.insert(created.id.0, created.clone());
+        created
+    }
+
+    /// Contracts matching a filter, unsorted
+    fn filter_contracts(&self, filter: &ContractFilter) -> Vec<&Contract> {
+        let name = filter.name.as_deref().map(str::to_lowercase);
+        self.contracts
+            .values()
+            .filter(|c| {
+                name.as_deref()
+                    .is_none_or(|name| c.name.to_lowercase().contains(name))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ContractRepository for MemoryDatabase {
+    async fn list(&self, filter: ContractFilter) -> Result<Vec<Contract>> {
+        let tables = self.read();
+        let mut contracts: Vec<Contract> = tables
+            .filter_contracts(&filter)
+            .into_iter()
+            .cloned()
+            .collect();
+
+        let sort = filter.sort.unwrap_or(ContractSort::Name);
+        contracts.sort_by(|a, b| {
+            let ordering = match sort {
+                ContractSort::Name => a.name.cmp(&b.name),
+                ContractSort::CreatedAt => a.created_at.cmp(&b.created_at),
+            }
+            .then(a.id.0.cmp(&b.id.0));
+            let ordering = match filter.order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            };
+            b.pinned.cmp(&a.pinned).then(ordering)
+        });
+
+        Ok(paginate(contracts, filter.limit, filter.offset))
+    }
+
+    async fn count(&self, filter: &ContractFilter) -> Result<i64> {
+        Ok(self.read().filter_contracts(filter).len() as i64)
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Contract>> {
+        let tables = self.read();
+        Ok(tables
+            .contracts
+            .values()
+            .filter(|c| c.name == name)
+            .max_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.0.cmp(&b.id.0)))
+            .cloned())
+    }
+
+    async fn get_by_id(&self, id: ContractId) -> Result<Option<Contract>> {
+        Ok(self.read().contracts.get(&id.0).cloned())
+    }
+
+    async fn upsert(&self, contract: &NewContract) -> Result<Contract> {
+        Ok(self.write().upsert_contract(contract))
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let mut tables = self.write();
+        let contracts: Vec<ContractId> = tables
+            .contracts
+            .values()
+            .filter(|c| c.name == name)
+            .map(|c| c.id)
+            .collect();
+        let deployments: Vec<_> = tables
+            .deployments
+            .values()
+            .filter(|d| contracts.contains(&d.contract_id))
+            .map(|d| d.id)
+            .collect();
+
+        for id in deployments {
+            tables.remove_deployment(id);
+        }
+        for id in contracts {
+            tables.contracts.remove(&id.0);
+        }
+        tables.pinned_contracts.remove(name);
+        tables.contract_labels.remove(name);
+        Ok(())
+    }
+
+    async fn set_pinned(&self, name: &str, pinned: bool) -> Result<()> {
+        let mut tables = self.write();
+        let mut found = false;
+        for contract in tables.contracts.values_mut().filter(|c| c.name == name) {
+            contract.pinned = pinned;
+            found = true;
+        }
+        if !found {
+            return Err(Error::ContractNotFound(name.to_string()));
+        }
+        match pinned {
+            true => tables.pinned_contracts.insert(name.to_string()),
+            false => tables.pinned_contracts.remove(name),
+        };
+        Ok(())
+    }
+
+    async fn set_label(
+        &self,
+        name: &str,
+        label: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()> {
+        let mut tables = self.write();
+        let mut found = false;
+        for contract in tables.contracts.values_mut().filter(|c| c.name == name) {
+            contract.label = label.map(str::to_string);
+            contract.description = description.map(str::to_string);
+            found = true;
+        }
+        if !found {
+            return Err(Error::ContractNotFound(name.to_string()));
+        }
+        match (label, description) {
+            (None, None) => tables.contract_labels.remove(name),
+            _ => tables.contract_labels.insert(
+                name.to_string(),
+                (label.map(str::to_string), description.map(str::to_string)),
+            ),
+        };
+        Ok(())
+    }
+}
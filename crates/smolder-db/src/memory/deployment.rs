@@ -0,0 +1,522 @@
+//! DeploymentRepository implementation in memory
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use smolder_core::{
+    ContractId, DeploymentId, Error, EvmAddress, Finality, NetworkId, Result, TxHash,
+};
+
+use super::{constraint, paginate, reblocked, MemoryDatabase, Tables};
+use crate::models::{
+    CallHistory, Deployment, DeploymentView, ImportStatus, NewContractDeployment, NewDeployment,
+    NewUpgrade,
+};
+use crate::repositories::fee;
+use crate::traits::{DeploymentFilter, DeploymentRepository, DeploymentSort, SortOrder};
+
+/// A batch-imported deployment, once its contract is known
+fn with_contract(
+    deployment: &NewContractDeployment,
+    contract_id: ContractId,
+    network_id: NetworkId,
+) -> NewDeployment {
+    NewDeployment {
+        contract_id,
+        network_id,
+        address: deployment.address,
+        deployer: deployment.deployer.clone(),
+        tx_hash: deployment.tx_hash,
+        block_number: deployment.block_number,
+        constructor_args: deployment.constructor_args.clone(),
+        initcode_size: deployment.initcode_size,
+        runtime_size: deployment.runtime_size,
+        gas_used: deployment.gas_used,
+        gas_price: deployment.gas_price.clone(),
+    }
+}
+
+impl Tables {
+    /// Highest version of a contract on a network, 0 for none
+    fn max_version(&self, contract_id: ContractId, network_id: NetworkId) -> i64 {
+        self.deployments
+            .values()
+            .filter(|d| d.contract_id == contract_id && d.network_id == network_id)
+            .map(|d| d.version)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Mark every deployment of a contract on a network as not current
+    fn demote(&mut self, contract_id: ContractId, network_id: NetworkId) {
+        for d in self.deployments.values_mut() {
+            if d.contract_id == contract_id && d.network_id == network_id {
+                d.is_current = false;
+            }
+        }
+    }
+
+    /// Make the latest non-archived version of a contract on a network
+    /// current, after the current one was deleted or archived
+    fn promote_latest(&mut self, contract_id: ContractId, network_id: NetworkId) {
+        let latest = self
+            .deployments
+            .values_mut()
+            .filter(|d| {
+                d.contract_id == contract_id
+                    && d.network_id == network_id
+                    && d.archived_at.is_none()
+            })
+            .max_by_key(|d| d.version);
+        if let Some(latest) = latest {
+            latest.is_current = true;
+        }
+    }
+
+    /// Latest deployment at an address on a network, ignoring case
+    fn deployment_at(&self, network_id: NetworkId, address: &str) -> Option<&Deployment> {
+        self.deployments
+            .values()
+            .rev()
+            .find(|d| d.network_id == network_id && d.address == *address)
+    }
+
+    /// Insert a deployment at `version`, checking what SQLite's constraints
+    /// would
+    fn insert_deployment(
+        &mut self,
+        deployment: &NewDeployment,
+        version: i64,
+        is_current: bool,
+    ) -> Result<Deployment> {
+        if !self.contracts.contains_key(&deployment.contract_id.0) {
+            return Err(constraint(format!(
+                "Contract {} does not exist",
+                deployment.contract_id
+            )));
+        }
+        if !self.networks.contains_key(&deployment.network_id.0) {
+            return Err(constraint(format!(
+                "Network {} does not exist",
+                deployment.network_id
+            )));
+        }
+        if self
+            .deployment_at(deployment.network_id, &deployment.address.to_string())
+            .is_some()
+        {
+            return Err(constraint(format!(
+                "A deployment at {} already exists on network {}",
+                deployment.address, deployment.network_id
+            )));
+        }
+
+        let wallet_id = self
+            .wallets
+            .values()
+            .find(|w| w.wallet.address == *deployment.deployer)
+            .map(|w| w.wallet.id)
+            .or_else(|| {
+                self.wallet_rotations
+                    .iter()
+                    .rev()
+                    .find(|r| r.old_address == *deployment.deployer)
+                    .map(|r| r.wallet_id)
+            });
+        let created = Deployment {
+            id: DeploymentId(self.next_id("deployments")),
+            contract_id: deployment.contract_id,
+            network_id: deployment.network_id,
+            address: deployment.address,
+            deployer: EvmAddress::normalize(&deployment.deployer),
+            wallet_id,
+            tx_hash: deployment.tx_hash,
+            block_number: deployment.block_number,
+            constructor_args: deployment.constructor_args.clone(),
+            version,
+            deployed_at: self.now(),
+            is_current,
+            archived_at: None,
+            initcode_size: deployment.initcode_size,
+            runtime_size: deployment.runtime_size,
+            gas_used: deployment.gas_used,
+            gas_price: deployment.gas_price.clone(),
+            fee: fee(deployment.gas_used, deployment.gas_price.as_deref()),
+            created_by_call_id: None,
+            block_hash: None,
+            reorged_at: None,
+            finality: deployment.block_number.map(|_| Finality::Pending),
+            pinned: false,
+            label: None,
+            description: None,
+        };
+        self.deployments.insert(created.id.0, created.clone());
+        Ok(created)
+    }
+
+    /// Add a deployment as the new current version of its contract
+    fn create_deployment(&mut self, deployment: &NewDeployment) -> Result<Deployment> {
+        let version = self.max_version(deployment.contract_id, deployment.network_id) + 1;
+        let created = self.insert_deployment(deployment, version, true)?;
+        for d in self.deployments.values_mut() {
+            if d.contract_id == created.contract_id
+                && d.network_id == created.network_id
+                && d.id != created.id
+            {
+                d.is_current = false;
+            }
+        }
+        Ok(created)
+    }
+
+    /// Views of the deployments matching a filter, unsorted
+    fn filter_deployments(&self, filter: &DeploymentFilter) -> Vec<DeploymentView> {
+        self.deployments
+            .values()
+            .filter_map(|d| self.deployment_view(d))
+            .filter(|d| {
+                filter.network.as_ref().is_none_or(|n| d.network_name == *n)
+                    && filter
+                        .contract
+                        .as_ref()
+                        .is_none_or(|c| d.contract_name == *c)
+                    && filter.version.is_none_or(|v| d.version == v)
+                    && filter
+                        .wallet
+                        .as_ref()
+                        .is_none_or(|w| d.wallet_name.as_ref() == Some(w))
+                    && filter
+                        .deployer
+                        .as_ref()
+                        .is_none_or(|deployer| d.deployer.eq_ignore_ascii_case(deployer))
+                    && filter
+                        .deployed_after
+                        .as_ref()
+                        .is_none_or(|after| d.deployed_at >= *after)
+                    && filter
+                        .deployed_before
+                        .as_ref()
+                        .is_none_or(|before| d.deployed_at <= *before)
+                    && filter
+                        .created_by_call_id
+                        .is_none_or(|id| d.created_by_call_id == Some(id))
+                    && (!filter.current_only || d.is_current)
+                    && (filter.include_archived || d.archived_at.is_none())
+            })
+            .collect()
+    }
+}
+
+/// Compare two views by a sort field
+fn compare(sort: DeploymentSort, a: &DeploymentView, b: &DeploymentView) -> Ordering {
+    match sort {
+        DeploymentSort::Network => a.network_name.cmp(&b.network_name),
+        DeploymentSort::Contract => a.contract_name.cmp(&b.contract_name),
+        DeploymentSort::Version => a.version.cmp(&b.version),
+        DeploymentSort::BlockNumber => a.block_number.cmp(&b.block_number),
+        DeploymentSort::DeployedAt => a.deployed_at.cmp(&b.deployed_at),
+    }
+}
+
+#[async_trait]
+impl DeploymentRepository for MemoryDatabase {
+    async fn list(&self, filter: DeploymentFilter) -> Result<Vec<DeploymentView>> {
+        let mut deployments = self.read().filter_deployments(&filter);
+
+        match filter.sort {
+            Some(sort) => deployments.sort_by(|a, b| {
+                let ordering = compare(sort, a, b).then(a.id.0.cmp(&b.id.0));
+                match filter.order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            }),
+            None => deployments.sort_by(|a, b| {
+                let ordering = a
+                    .network_name
+                    .cmp(&b.network_name)
+                    .then(a.contract_name.cmp(&b.contract_name));
+                match filter.current_only {
+                    true => ordering,
+                    false => ordering.then(b.version.cmp(&a.version)),
+                }
+                .then(a.id.0.cmp(&b.id.0))
+            }),
+        }
+        // Pinned deployments come first, whatever the sort; `sort_by_key` is
+        // stable
+        deployments.sort_by_key(|d| !d.pinned);
+
+        Ok(paginate(deployments, filter.limit, filter.offset))
+    }
+
+    async fn count(&self, filter: &DeploymentFilter) -> Result<i64> {
+        Ok(self.read().filter_deployments(filter).len() as i64)
+    }
+
+    async fn get_current(&self, contract: &str, network: &str) -> Result<Option<Deployment>> {
+        let tables = self.read();
+        Ok(tables
+            .deployments
+            .values()
+            .find(|d| {
+                d.is_current
+                    && tables
+                        .contracts
+                        .get(&d.contract_id.0)
+                        .is_some_and(|c| c.name == contract)
+                    && tables.network_name(d.network_id) == Some(network)
+            })
+            .cloned())
+    }
+
+    async fn get_by_id(&self, id: DeploymentId) -> Result<Option<Deployment>> {
+        Ok(self.read().deployments.get(&id.0).cloned())
+    }
+
+    async fn get_view_by_id(&self, id: DeploymentId) -> Result<Option<DeploymentView>> {
+        let tables = self.read();
+        Ok(tables
+            .deployments
+            .get(&id.0)
+            .and_then(|d| tables.deployment_view(d)))
+    }
+
+    async fn exists_by_tx_hash(&self, tx_hash: &TxHash) -> Result<bool> {
+        let tables = self.read();
+        Ok(tables
+            .deployments
+            .values()
+            .any(|d| d.tx_hash == Some(*tx_hash)))
+    }
+
+    async fn exists_by_address(&self, network_id: NetworkId, address: &str) -> Result<bool> {
+        Ok(self.read().deployment_at(network_id, address).is_some())
+    }
+
+    async fn get_by_address(
+        &self,
+        network_id: NetworkId,
+        address: &str,
+    ) -> Result<Option<Deployment>> {
+        Ok(self.read().deployment_at(network_id, address).cloned())
+    }
+
+    async fn create(&self, deployment: &NewDeployment) -> Result<Deployment> {
+        self.write().create_deployment(deployment)
+    }
+
+    async fn import_batch(
+        &self,
+        network_id: NetworkId,
+        deployments: &[NewContractDeployment],
+    ) -> Result<Vec<ImportStatus>> {
+        let mut tables = self.write();
+        if !deployments.is_empty() && !tables.networks.contains_key(&network_id.0) {
+            return Err(constraint(format!("Network {} does not exist", network_id)));
+        }
+
+        let mut statuses = Vec::with_capacity(deployments.len());
+        // Last version assigned to each contract in this batch
+        let mut versions: HashMap<ContractId, i64> = HashMap::new();
+        let mut seen_addresses = HashSet::new();
+        let mut rows = Vec::new();
+
+        for deployment in deployments {
+            if !seen_addresses.insert(deployment.address) {
+                statuses.push(ImportStatus::Skipped);
+                continue;
+            }
+
+            let existing = tables
+                .deployment_at(network_id, &deployment.address.to_string())
+                .map(|d| d.id);
+            if let Some(id) = existing {
+                let existing = tables.deployments.get_mut(&id.0).expect("found above");
+                let fills_block =
+                    existing.block_number.is_none() && deployment.block_number.is_some();
+                let fills_args =
+                    existing.constructor_args.is_none() && deployment.constructor_args.is_some();
+                let fills_gas = existing.gas_used.is_none() && deployment.gas_used.is_some();
+
+                if fills_block || fills_args || fills_gas {
+                    let block_number = existing.block_number.or(deployment.block_number);
+                    existing.finality =
+                        reblocked(existing.finality, existing.block_number, block_number);
+                    existing.block_number = block_number;
+                    existing.constructor_args = existing
+                        .constructor_args
+                        .take()
+                        .or_else(|| deployment.constructor_args.clone());
+                    existing.initcode_size = existing.initcode_size.or(deployment.initcode_size);
+                    existing.runtime_size = existing.runtime_size.or(deployment.runtime_size);
+                    existing.gas_used = existing.gas_used.or(deployment.gas_used);
+                    existing.gas_price = existing
+                        .gas_price
+                        .take()
+                        .or_else(|| deployment.gas_price.clone());
+                    existing.fee = existing
+                        .fee
+                        .take()
+                        .or_else(|| fee(deployment.gas_used, deployment.gas_price.as_deref()));
+                    statuses.push(ImportStatus::Updated);
+                } else {
+                    statuses.push(ImportStatus::Skipped);
+                }
+                continue;
+            }
+
+            let contract_id = tables.upsert_contract(&deployment.contract).id;
+            let version = match versions.get(&contract_id) {
+                Some(last) => last + 1,
+                None => tables.max_version(contract_id, network_id) + 1,
+            };
+            versions.insert(contract_id, version);
+
+            rows.push((with_contract(deployment, contract_id, network_id), version));
+            statuses.push(ImportStatus::Added);
+        }
+
+        // The newest imported version of each contract becomes current
+        for contract_id in versions.keys() {
+            tables.demote(*contract_id, network_id);
+        }
+        for (deployment, version) in rows {
+            let is_current = versions[&deployment.contract_id] == version;
+            tables.insert_deployment(&deployment, version, is_current)?;
+        }
+
+        Ok(statuses)
+    }
+
+    async fn record_upgrade(&self, upgrade: &NewUpgrade) -> Result<(Deployment, CallHistory)> {
+        let mut tables = self.write();
+        if !tables
+            .deployments
+            .contains_key(&upgrade.call.deployment_id.0)
+        {
+            return Err(Error::DeploymentNotFoundById(upgrade.call.deployment_id));
+        }
+
+        let contract_id = tables.upsert_contract(&upgrade.implementation.contract).id;
+        let deployment = with_contract(&upgrade.implementation, contract_id, upgrade.network_id);
+        let deployment = tables.create_deployment(&deployment)?;
+
+        let history = tables.insert_call(&upgrade.call)?;
+        let history = tables.finish_call(history.id, &upgrade.outcome)?;
+        Ok((deployment, history))
+    }
+
+    fn stream_for_export<'a>(
+        &'a self,
+        network: Option<&'a str>,
+        with_abi: bool,
+    ) -> BoxStream<'a, Result<DeploymentView>> {
+        let tables = self.read();
+        let mut deployments: Vec<DeploymentView> = tables
+            .deployments
+            .values()
+            .filter(|d| d.is_current && d.archived_at.is_none())
+            .filter_map(|d| tables.deployment_view(d))
+            .filter(|d| network.is_none_or(|n| d.network_name == n))
+            .map(|d| DeploymentView {
+                abi: if with_abi { d.abi } else { String::new() },
+                ..d
+            })
+            .collect();
+        deployments.sort_by(|a, b| {
+            a.network_name
+                .cmp(&b.network_name)
+                .then(a.contract_name.cmp(&b.contract_name))
+                .then(a.id.0.cmp(&b.id.0))
+        });
+
+        stream::iter(deployments.into_iter().map(Ok)).boxed()
+    }
+
+    async fn list_versions(&self, contract: &str, network: &str) -> Result<Vec<DeploymentView>> {
+        let tables = self.read();
+        let mut versions: Vec<DeploymentView> = tables
+            .deployments
+            .values()
+            .filter_map(|d| tables.deployment_view(d))
+            .filter(|d| d.contract_name == contract && d.network_name == network)
+            .collect();
+        versions.sort_by_key(|d| std::cmp::Reverse(d.version));
+        Ok(versions)
+    }
+
+    async fn delete(&self, id: DeploymentId) -> Result<()> {
+        let mut tables = self.write();
+        let deployment = tables
+            .deployments
+            .get(&id.0)
+            .cloned()
+            .ok_or(Error::DeploymentNotFoundById(id))?;
+
+        tables.remove_deployment(id);
+        if deployment.is_current {
+            tables.promote_latest(deployment.contract_id, deployment.network_id);
+        }
+        Ok(())
+    }
+
+    async fn archive(&self, id: DeploymentId) -> Result<()> {
+        let mut tables = self.write();
+        let now = tables.now();
+        let deployment = tables
+            .deployments
+            .get_mut(&id.0)
+            .ok_or(Error::DeploymentNotFoundById(id))?;
+        let was_current = deployment.is_current;
+        deployment.archived_at = Some(now);
+        deployment.is_current = false;
+
+        let (contract_id, network_id) = (deployment.contract_id, deployment.network_id);
+        if was_current {
+            tables.promote_latest(contract_id, network_id);
+        }
+        Ok(())
+    }
+
+    async fn set_pinned(&self, id: DeploymentId, pinned: bool) -> Result<()> {
+        let mut tables = self.write();
+        let deployment = tables
+            .deployments
+            .get_mut(&id.0)
+            .ok_or(Error::DeploymentNotFoundById(id))?;
+        deployment.pinned = pinned;
+        Ok(())
+    }
+
+    async fn set_label(
+        &self,
+        id: DeploymentId,
+        label: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()> {
+        let mut tables = self.write();
+        let deployment = tables
+            .deployments
+            .get_mut(&id.0)
+            .ok_or(Error::DeploymentNotFoundById(id))?;
+        deployment.label = label.map(str::to_string);
+        deployment.description = description.map(str::to_string);
+        Ok(())
+    }
+
+    async fn link_to_call(&self, id: DeploymentId, call_id: i64) -> Result<()> {
+        let mut tables = self.write();
+        if !tables.call_history.contains_key(&call_id) {
+            return Err(constraint(format!("Call {} not found", call_id)));
+        }
+        let deployment = tables
+            .deployments
+            .get_mut(&id.0)
+            .ok_or(Error::DeploymentNotFoundById(id))?;
+        deployment.created_by_call_id = Some(call_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+//! DeploymentGroupRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{DeploymentGroupId, DeploymentId, NetworkId, Result, TransactionStatus};
+
+use super::{constraint, GroupMemberRow, MemoryDatabase};
+use crate::models::{DeploymentGroup, DeploymentGroupMember, NewDeploymentGroup};
+use crate::traits::DeploymentGroupRepository;
+
+#[async_trait]
+impl DeploymentGroupRepository for MemoryDatabase {
+    async fn create(&self, group: &NewDeploymentGroup) -> Result<DeploymentGroup> {
+        let mut tables = self.write();
+        if let Some(missing) = group
+            .network_ids
+            .iter()
+            .find(|id| !tables.networks.contains_key(&id.0))
+        {
+            return Err(constraint(format!("Network {} does not exist", missing)));
+        }
+
+        let created = DeploymentGroup {
+            id: DeploymentGroupId(tables.next_id("deployment_groups")),
+            contract_name: group.contract_name.clone(),
+            bytecode_hash: group.bytecode_hash,
+            constructor_args: group.constructor_args.clone(),
+            value: group.value.clone(),
+            wallet_name: group.wallet_name.clone(),
+            created_at: tables.now(),
+        };
+        for network_id in &group.network_ids {
+            let member = GroupMemberRow {
+                group_id: created.id,
+                network_id: *network_id,
+                status: TransactionStatus::Pending,
+                deployment_id: None,
+                error_message: None,
+                updated_at: created.created_at.clone(),
+            };
+            tables.group_members.push(member);
+        }
+        tables
+            .deployment_groups
+            .insert(created.id.0, created.clone());
+        Ok(created)
+    }
+
+    async fn get(&self, id: DeploymentGroupId) -> Result<Option<DeploymentGroup>> {
+        Ok(self.read().deployment_groups.get(&id.0).cloned())
+    }
+
+    async fn list(&self) -> Result<Vec<DeploymentGroup>> {
+        Ok(self
+            .read()
+            .deployment_groups
+            .values()
+            .rev()
+            .cloned()
+            .collect())
+    }
+
+    async fn list_members(&self, id: DeploymentGroupId) -> Result<Vec<DeploymentGroupMember>> {
+        let tables = self.read();
+        Ok(tables
+            .group_members
+            .iter()
+            .filter(|m| m.group_id == id)
+            .filter_map(|m| {
+                let network = tables.networks.get(&m.network_id.0)?;
+                let deployment = m.deployment_id.and_then(|d| tables.deployments.get(&d.0));
+                Some(DeploymentGroupMember {
+                    group_id: m.group_id,
+                    network_id: m.network_id,
+                    network_name: network.name.clone(),
+                    chain_id: network.chain_id,
+                    status: m.status,
+                    deployment_id: m.deployment_id,
+                    address: deployment.map(|d| d.address),
+                    tx_hash: deployment.and_then(|d| d.tx_hash),
+                    error_message: m.error_message.clone(),
+                    updated_at: m.updated_at.clone(),
+                })
+            })
+            .collect())
+    }
+
+    async fn update_member(
+        &self,
+        id: DeploymentGroupId,
+        network_id: NetworkId,
+        status: TransactionStatus,
+        deployment_id: Option<DeploymentId>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        let mut tables = self.write();
+        let now = tables.now();
+        if let Some(member) = tables
+            .group_members
+            .iter_mut()
+            .find(|m| m.group_id == id && m.network_id == network_id)
+        {
+            member.status = status;
+            member.deployment_id = deployment_id;
+            member.error_message = error_message.map(str::to_string);
+            member.updated_at = now;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+//! EnsCacheRepository implementation in memory
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::{ChainId, EnsLookup, Result};
+
+use super::MemoryDatabase;
+use crate::models::{EnsRecord, NewEnsRecord};
+use crate::traits::EnsCacheRepository;
+
+#[async_trait]
+impl EnsCacheRepository for MemoryDatabase {
+    async fn get(
+        &self,
+        chain_id: ChainId,
+        lookup: EnsLookup,
+        input: &str,
+        max_age: Duration,
+    ) -> Result<Option<EnsRecord>> {
+        let tables = self.read();
+        let cutoff = tables.ago(max_age);
+        Ok(tables
+            .ens_cache
+            .iter()
+            .find(|r| {
+                r.chain_id == chain_id
+                    && r.lookup == lookup
+                    && r.input == input
+                    && r.resolved_at >= cutoff
+            })
+            .cloned())
+    }
+
+    async fn put(&self, record: &NewEnsRecord) -> Result<()> {
+        let mut tables = self.write();
+        let resolved = EnsRecord {
+            chain_id: record.chain_id,
+            lookup: record.lookup,
+            input: record.input.clone(),
+            result: record.result.clone(),
+            resolved_at: tables.now(),
+        };
+        tables.ens_cache.retain(|r| {
+            !(r.chain_id == record.chain_id && r.lookup == record.lookup && r.input == record.input)
+        });
+        tables.ens_cache.push(resolved);
+        Ok(())
+    }
+}
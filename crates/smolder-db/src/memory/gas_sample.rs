@@ -0,0 +1,61 @@
+//! GasSampleRepository implementation in memory
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::{NetworkId, Result};
+
+use super::{constraint, MemoryDatabase};
+use crate::models::{GasSample, NewGasSample};
+use crate::traits::GasSampleRepository;
+
+#[async_trait]
+impl GasSampleRepository for MemoryDatabase {
+    async fn record(&self, sample: &NewGasSample) -> Result<()> {
+        let mut tables = self.write();
+        if !tables.networks.contains_key(&sample.network_id.0) {
+            return Err(constraint(format!(
+                "Network {} does not exist",
+                sample.network_id
+            )));
+        }
+
+        let recorded = GasSample {
+            id: tables.next_id("gas_samples"),
+            network_id: sample.network_id,
+            block_number: sample.block_number,
+            base_fee: sample.base_fee,
+            gas_price: sample.gas_price,
+            sampled_at: tables.now(),
+        };
+        tables.gas_samples.insert(recorded.id, recorded);
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        network_id: NetworkId,
+        since: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GasSample>> {
+        let tables = self.read();
+        let mut samples: Vec<GasSample> = tables
+            .gas_samples
+            .values()
+            .rev()
+            .filter(|s| s.network_id == network_id && since.is_none_or(|t| *s.sampled_at >= *t))
+            .take(limit as usize)
+            .cloned()
+            .collect();
+        samples.reverse();
+        Ok(samples)
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64> {
+        let mut tables = self.write();
+        let cutoff = tables.ago(max_age);
+        let before = tables.gas_samples.len();
+        tables.gas_samples.retain(|_, s| s.sampled_at >= cutoff);
+        Ok((before - tables.gas_samples.len()) as u64)
+    }
+}
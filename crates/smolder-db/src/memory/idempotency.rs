@@ -0,0 +1,75 @@
+//! IdempotencyRepository implementation in memory
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use super::{IdempotencyRow, MemoryDatabase};
+use crate::models::IdempotencyRecord;
+use crate::traits::IdempotencyRepository;
+
+#[async_trait]
+impl IdempotencyRepository for MemoryDatabase {
+    async fn claim(
+        &self,
+        scope: &str,
+        key: &str,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyRecord>> {
+        let mut tables = self.write();
+        let id = (scope.to_string(), key.to_string());
+        if let Some(existing) = tables.idempotency_keys.get(&id) {
+            return Ok(Some(existing.record.clone()));
+        }
+
+        let row = IdempotencyRow {
+            record: IdempotencyRecord {
+                fingerprint: fingerprint.to_string(),
+                status: None,
+                content_type: None,
+                body: None,
+            },
+            created_at: tables.now(),
+        };
+        tables.idempotency_keys.insert(id, row);
+        Ok(None)
+    }
+
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        status: u16,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<()> {
+        let mut tables = self.write();
+        if let Some(row) = tables
+            .idempotency_keys
+            .get_mut(&(scope.to_string(), key.to_string()))
+        {
+            row.record.status = Some(status.into());
+            row.record.content_type = content_type.map(str::to_string);
+            row.record.body = Some(body.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> Result<()> {
+        self.write()
+            .idempotency_keys
+            .remove(&(scope.to_string(), key.to_string()));
+        Ok(())
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64> {
+        let mut tables = self.write();
+        let cutoff = tables.ago(max_age);
+        let before = tables.idempotency_keys.len();
+        tables
+            .idempotency_keys
+            .retain(|_, row| row.created_at >= cutoff);
+        Ok((before - tables.idempotency_keys.len()) as u64)
+    }
+}
@@ -0,0 +1,125 @@
+//! JobRepository implementation in memory
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::{Error, JobState, Result};
+
+use super::MemoryDatabase;
+use crate::models::{Job, NewJob};
+use crate::traits::{JobFilter, JobRepository};
+
+#[async_trait]
+impl JobRepository for MemoryDatabase {
+    async fn enqueue(&self, job: &NewJob) -> Result<Job> {
+        let mut tables = self.write();
+        let now = tables.now();
+        let created = Job {
+            id: tables.next_id("jobs"),
+            kind: job.kind.clone(),
+            payload: job.payload.clone(),
+            state: JobState::Queued,
+            attempts: 0,
+            max_attempts: job.max_attempts,
+            run_after: now.clone(),
+            last_error: None,
+            created_at: now,
+            started_at: None,
+            finished_at: None,
+        };
+        tables.jobs.insert(created.id, created.clone());
+        Ok(created)
+    }
+
+    async fn claim(&self) -> Result<Option<Job>> {
+        let mut tables = self.write();
+        let now = tables.now();
+        let Some(id) = tables
+            .jobs
+            .values()
+            .filter(|j| j.state == JobState::Queued && j.run_after <= now)
+            .min_by(|a, b| (&a.run_after, a.id).cmp(&(&b.run_after, b.id)))
+            .map(|j| j.id)
+        else {
+            return Ok(None);
+        };
+
+        let job = tables.jobs.get_mut(&id).expect("job was just found");
+        job.state = JobState::Running;
+        job.attempts += 1;
+        job.started_at = Some(now);
+        Ok(Some(job.clone()))
+    }
+
+    async fn complete(&self, id: i64) -> Result<()> {
+        let mut tables = self.write();
+        let now = tables.now();
+        if let Some(job) = tables.jobs.get_mut(&id) {
+            job.state = JobState::Succeeded;
+            job.finished_at = Some(now);
+        }
+        Ok(())
+    }
+
+    async fn fail(&self, id: i64, error: &str, retry_in: Option<Duration>) -> Result<JobState> {
+        let mut tables = self.write();
+        let now = tables.now();
+        let run_after = retry_in.map(|delay| tables.now_plus(delay.as_secs() as i64));
+        let job = tables
+            .jobs
+            .get_mut(&id)
+            .ok_or(Error::Database(sqlx::Error::RowNotFound))?;
+
+        match run_after {
+            Some(run_after) if job.attempts < job.max_attempts => {
+                job.state = JobState::Queued;
+                job.run_after = run_after;
+                job.finished_at = None;
+            }
+            _ => {
+                job.state = JobState::Failed;
+                job.finished_at = Some(now);
+            }
+        }
+        job.last_error = Some(error.to_string());
+        Ok(job.state)
+    }
+
+    async fn requeue_running(&self) -> Result<u64> {
+        let mut tables = self.write();
+        let mut requeued = 0;
+        for job in tables.jobs.values_mut() {
+            if job.state == JobState::Running {
+                job.state = JobState::Queued;
+                job.attempts = (job.attempts - 1).max(0);
+                requeued += 1;
+            }
+        }
+        Ok(requeued)
+    }
+
+    async fn list(&self, filter: &JobFilter) -> Result<Vec<Job>> {
+        let tables = self.read();
+        let jobs = tables
+            .jobs
+            .values()
+            .rev()
+            .filter(|j| filter.state.is_none_or(|state| j.state == state))
+            .filter(|j| filter.kind.as_ref().is_none_or(|kind| &j.kind == kind))
+            .take(filter.limit.map_or(usize::MAX, |limit| limit as usize))
+            .cloned()
+            .collect();
+        Ok(jobs)
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64> {
+        let mut tables = self.write();
+        let cutoff = tables.ago(max_age);
+        let before = tables.jobs.len();
+        tables.jobs.retain(|_, j| {
+            !matches!(j.state, JobState::Succeeded | JobState::Failed)
+                || j.finished_at.as_ref().is_none_or(|at| *at >= cutoff)
+        });
+        Ok((before - tables.jobs.len()) as u64)
+    }
+}
@@ -0,0 +1,842 @@
+//! In-memory implementation of the repository traits
+//!
+//! [`MemoryDatabase`] keeps each table in an ordered map behind a lock and
+//! follows the SQLite [`Database`](crate::Database) closely: versions and
+//! current deployments, cascades on delete, row versions, and timestamps in
+//! SQLite's `YYYY-MM-DD HH:MM:SS` format, so string comparisons on them
+//! behave the same. It needs no file or connection, which makes it suited to
+//! tests, and its clock can be moved forward with [`MemoryDatabase::advance`]
+//! to reach expiries and retention cut-offs without waiting.
+
+mod analytics;
+mod api_token;
+mod approval;
+mod audit;
+mod batch;
+mod call_history;
+mod contract;
+mod deployment;
+mod deployment_group;
+mod ens;
+mod gas_sample;
+mod idempotency;
+mod job;
+mod network;
+mod price;
+mod reorg;
+mod selector;
+mod signature;
+mod template;
+mod token;
+mod wallet;
+mod wallet_policy;
+mod webhook;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use smolder_core::types::{
+    ApiTokenId, ApprovalId, ApprovalKind, ApprovalStatus, DeploymentGroupId, DeploymentId,
+    Finality, NetworkId, TransactionStatus, WalletId,
+};
+use smolder_core::{Error, EvmAddress};
+
+use crate::models::{
+    ApiToken, AuditEntry, CallHistory, CallHistoryView, Contract, Deployment, DeploymentGroup,
+    DeploymentView, EnsRecord, ExplorerLinks, GasSample, IdempotencyRecord, Job, KnownSelector,
+    Network, Template, TokenMetadata, Wallet, WalletPolicy, WalletRotation, Webhook,
+    WebhookDelivery,
+};
+use crate::traits::{
+    AnalyticsRepository, ApiTokenRepository, ApprovalRepository, AuditRepository, BatchRepository,
+    CallHistoryRepository, ContractRepository, DeploymentGroupRepository, DeploymentRepository,
+    EnsCacheRepository, GasInterval, GasSampleRepository, IdempotencyRepository, JobRepository,
+    NetworkRepository, PriceRepository, ReorgRepository, Repositories, SelectorRepository,
+    SignatureRepository, TemplateRepository, TokenMetadataRepository, WalletPolicyRepository,
+    WalletRepository, WebhookRepository,
+};
+
+/// Repository implementation holding everything in memory
+///
+/// Every instance is a separate, empty registry. Nothing is persisted.
+#[derive(Default)]
+pub struct MemoryDatabase {
+    tables: RwLock<Tables>,
+}
+
+impl MemoryDatabase {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward, as if `by` had passed since the rows were
+    /// written
+    pub fn advance(&self, by: Duration) {
+        self.write().clock_offset += by.as_secs() as i64;
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Tables> {
+        self.tables.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Tables> {
+        self.tables.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A wallet with its key, which [`Wallet`] leaves out
+struct WalletRow {
+    wallet: Wallet,
+    encrypted_key: Vec<u8>,
+}
+
+struct GroupMemberRow {
+    group_id: DeploymentGroupId,
+    network_id: NetworkId,
+    status: TransactionStatus,
+    deployment_id: Option<DeploymentId>,
+    error_message: Option<String>,
+    updated_at: String,
+}
+
+/// An approval with the IDs its view resolves to names
+struct ApprovalRow {
+    id: ApprovalId,
+    kind: ApprovalKind,
+    network_id: NetworkId,
+    deployment_id: Option<DeploymentId>,
+    summary: String,
+    request: String,
+    status: ApprovalStatus,
+    requested_by: Option<ApiTokenId>,
+    requester_token_id: Option<ApiTokenId>,
+    newest_token_id: Option<ApiTokenId>,
+    decided_by: Option<ApiTokenId>,
+    result: Option<String>,
+    error_message: Option<String>,
+    created_at: String,
+    expires_at: String,
+    decided_at: Option<String>,
+}
+
+struct BatchRow {
+    id: i64,
+    plan: String,
+    network_id: NetworkId,
+    wallet_id: Option<WalletId>,
+    steps: i64,
+    status: TransactionStatus,
+    created_at: String,
+    finished_at: Option<String>,
+}
+
+struct BatchCallRow {
+    batch_id: i64,
+    position: i64,
+    call_history_id: i64,
+}
+
+struct IdempotencyRow {
+    record: IdempotencyRecord,
+    created_at: String,
+}
+
+struct SpendRow {
+    id: i64,
+    wallet_id: WalletId,
+    network_id: NetworkId,
+    value: String,
+    created_at: String,
+}
+
+struct SignatureRow {
+    id: i64,
+    wallet_id: Option<WalletId>,
+    signer: EvmAddress,
+    primary_type: String,
+    domain: String,
+    payload: String,
+    digest: String,
+    signature: String,
+    signed_by: Option<ApiTokenId>,
+    created_at: String,
+}
+
+/// The tables, keyed by ID so iteration follows insertion order
+#[derive(Default)]
+struct Tables {
+    networks: BTreeMap<i64, Network>,
+    contracts: BTreeMap<i64, Contract>,
+    deployments: BTreeMap<i64, Deployment>,
+    wallets: BTreeMap<i64, WalletRow>,
+    call_history: BTreeMap<i64, CallHistory>,
+    webhooks: BTreeMap<i64, Webhook>,
+    webhook_deliveries: BTreeMap<i64, WebhookDelivery>,
+    ens_cache: Vec<EnsRecord>,
+    token_metadata: BTreeMap<i64, TokenMetadata>,
+    prices: BTreeMap<(String, String), f64>,
+    gas_samples: BTreeMap<i64, GasSample>,
+    deployment_groups: BTreeMap<i64, DeploymentGroup>,
+    group_members: Vec<GroupMemberRow>,
+    api_tokens: BTreeMap<i64, ApiToken>,
+    approvals: BTreeMap<i64, ApprovalRow>,
+    templates: BTreeMap<i64, Template>,
+    batches: BTreeMap<i64, BatchRow>,
+    batch_calls: Vec<BatchCallRow>,
+    idempotency_keys: BTreeMap<(String, String), IdempotencyRow>,
+    selectors: BTreeMap<(String, String), KnownSelector>,
+    signatures: BTreeMap<i64, SignatureRow>,
+    audit_log: BTreeMap<i64, AuditEntry>,
+    wallet_policies: BTreeMap<i64, WalletPolicy>,
+    wallet_spends: Vec<SpendRow>,
+    wallet_rotations: Vec<WalletRotation>,
+    jobs: BTreeMap<i64, Job>,
+    pinned_contracts: BTreeSet<String>,
+    /// Label and description by contract name
+    contract_labels: BTreeMap<String, (Option<String>, Option<String>)>,
+    /// Last ID handed out per table; like AUTOINCREMENT, IDs of deleted rows
+    /// are never reused
+    sequences: HashMap<&'static str, i64>,
+    /// Seconds added to the system clock by [`MemoryDatabase::advance`]
+    clock_offset: i64,
+}
+
+impl Tables {
+    fn next_id(&mut self, table: &'static str) -> i64 {
+        let id = self.sequences.entry(table).or_default();
+        *id += 1;
+        *id
+    }
+
+    /// `datetime('now')`
+    fn now(&self) -> String {
+        self.now_plus(0)
+    }
+
+    /// `datetime('now', '<seconds> seconds')`
+    fn now_plus(&self, seconds: i64) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        timestamp(now + self.clock_offset + seconds)
+    }
+
+    /// `datetime('now', '-<age> seconds')`
+    fn ago(&self, age: Duration) -> String {
+        self.now_plus(-(age.as_secs() as i64))
+    }
+
+    fn network_name(&self, id: NetworkId) -> Option<&str> {
+        self.networks.get(&id.0).map(|n| n.name.as_str())
+    }
+
+    fn wallet_name(&self, id: Option<WalletId>) -> Option<String> {
+        id.and_then(|id| self.wallets.get(&id.0))
+            .map(|w| w.wallet.name.clone())
+    }
+
+    /// A deployment joined with its contract, network and token metadata
+    fn deployment_view(&self, deployment: &Deployment) -> Option<DeploymentView> {
+        let contract = self.contracts.get(&deployment.contract_id.0)?;
+        let network = self.networks.get(&deployment.network_id.0)?;
+        let tx_hash = deployment.tx_hash.map(|h| h.to_string());
+        Some(DeploymentView {
+            id: deployment.id,
+            contract_name: contract.name.clone(),
+            network_name: network.name.clone(),
+            chain_id: network.chain_id,
+            address: deployment.address,
+            deployer: deployment.deployer.clone(),
+            wallet_name: self.wallet_name(deployment.wallet_id),
+            tx_hash: deployment.tx_hash,
+            block_number: deployment.block_number,
+            version: deployment.version,
+            deployed_at: deployment.deployed_at.clone(),
+            is_current: deployment.is_current,
+            archived_at: deployment.archived_at.clone(),
+            initcode_size: deployment.initcode_size,
+            runtime_size: deployment.runtime_size,
+            gas_used: deployment.gas_used,
+            gas_price: deployment.gas_price.clone(),
+            fee: deployment.fee.clone(),
+            created_by_call_id: deployment.created_by_call_id,
+            reorged_at: deployment.reorged_at.clone(),
+            finality: deployment.finality,
+            pinned: deployment.pinned || contract.pinned,
+            label: deployment.label.clone().or_else(|| contract.label.clone()),
+            description: deployment
+                .description
+                .clone()
+                .or_else(|| contract.description.clone()),
+            abi: contract.abi.clone(),
+            links: ExplorerLinks::new(
+                network.explorer_url.as_deref(),
+                &deployment.address.to_string(),
+                tx_hash.as_deref(),
+            ),
+            token: self.token_metadata.get(&deployment.id.0).cloned(),
+        })
+    }
+
+    /// A call joined with its deployment, contract, network and wallet
+    fn call_view(&self, call: &CallHistory) -> Option<CallHistoryView> {
+        let deployment = self.deployments.get(&call.deployment_id.0)?;
+        let contract = self.contracts.get(&deployment.contract_id.0)?;
+        let network = self.networks.get(&deployment.network_id.0)?;
+        let tx_hash = call.tx_hash.map(|h| h.to_string());
+        Some(CallHistoryView {
+            id: call.id,
+            deployment_id: call.deployment_id,
+            contract_name: contract.name.clone(),
+            network_name: network.name.clone(),
+            contract_address: deployment.address,
+            wallet_name: self.wallet_name(call.wallet_id),
+            function_name: call.function_name.clone(),
+            function_signature: call.function_signature.clone(),
+            input_params: call.input_params.clone(),
+            call_type: call.call_type,
+            result: call.result.clone(),
+            tx_hash: call.tx_hash,
+            block_number: call.block_number,
+            gas_used: call.gas_used,
+            gas_price: call.gas_price.clone(),
+            fee: call.fee.clone(),
+            status: call.status,
+            error_message: call.error_message.clone(),
+            created_at: call.created_at.clone(),
+            confirmed_at: call.confirmed_at.clone(),
+            simulation_url: call.simulation_url.clone(),
+            sender: call.sender.clone(),
+            reorged_at: call.reorged_at.clone(),
+            finality: call.finality,
+            links: ExplorerLinks::new(
+                network.explorer_url.as_deref(),
+                &deployment.address.to_string(),
+                tx_hash.as_deref(),
+            ),
+        })
+    }
+
+    /// Delete calls and what references them
+    fn remove_calls(&mut self, ids: &[i64]) {
+        for id in ids {
+            self.call_history.remove(id);
+        }
+        for deployment in self.deployments.values_mut() {
+            if deployment
+                .created_by_call_id
+                .is_some_and(|id| ids.contains(&id))
+            {
+                deployment.created_by_call_id = None;
+            }
+        }
+        self.batch_calls
+            .retain(|c| !ids.contains(&c.call_history_id));
+    }
+
+    /// Delete a deployment with its calls, token metadata and approvals
+    fn remove_deployment(&mut self, id: DeploymentId) {
+        let calls: Vec<i64> = self
+            .call_history
+            .values()
+            .filter(|h| h.deployment_id == id)
+            .map(|h| h.id)
+            .collect();
+        self.remove_calls(&calls);
+        self.deployments.remove(&id.0);
+        self.token_metadata.remove(&id.0);
+        self.approvals.retain(|_, a| a.deployment_id != Some(id));
+        for member in &mut self.group_members {
+            if member.deployment_id == Some(id) {
+                member.deployment_id = None;
+            }
+        }
+    }
+}
+
+/// Finality of a row whose block goes from `old` to `new`, as the
+/// `*_finality` triggers set it: pending whenever a block is recorded or
+/// changes
+fn reblocked(finality: Option<Finality>, old: Option<i64>, new: Option<i64>) -> Option<Finality> {
+    match new {
+        _ if old == new => finality,
+        Some(_) => Some(Finality::Pending),
+        None => None,
+    }
+}
+
+/// Error for a write SQLite would reject with a constraint violation
+fn constraint(message: impl Into<String>) -> Error {
+    Error::Validation(message.into())
+}
+
+/// Apply LIMIT/OFFSET to rows already filtered and sorted
+fn paginate<T>(rows: Vec<T>, limit: Option<u32>, offset: Option<u32>) -> Vec<T> {
+    rows.into_iter()
+        .skip(offset.unwrap_or(0) as usize)
+        .take(limit.map_or(usize::MAX, |l| l as usize))
+        .collect()
+}
+
+/// Format seconds since the Unix epoch like SQLite's `datetime()`
+fn timestamp(unix: i64) -> String {
+    let (days, seconds) = (unix.div_euclid(86_400), unix.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Days since the Unix epoch of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a day since the Unix epoch
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `date(at)`: the day of a timestamp
+fn date(at: &str) -> String {
+    at.get(..10).unwrap_or(at).to_string()
+}
+
+/// `strftime(interval.as_strftime(), at)`: the bucket a timestamp falls in
+fn period(interval: Option<GasInterval>, at: &str) -> Option<String> {
+    let field = |range: std::ops::Range<usize>| at.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    Some(match interval? {
+        GasInterval::Day => format!("{:04}-{:02}-{:02}", year, month, day),
+        GasInterval::Month => format!("{:04}-{:02}", year, month),
+        GasInterval::Week => {
+            // %W: weeks start on Monday, and days before the year's first
+            // Monday are in week 0
+            let days = days_from_civil(year, month, day);
+            let day_of_year = days - days_from_civil(year, 1, 1);
+            let days_since_monday = (days + 3).rem_euclid(7);
+            format!(
+                "{:04}-W{:02}",
+                year,
+                (day_of_year + 7 - days_since_monday) / 7
+            )
+        }
+    })
+}
+
+impl Repositories for MemoryDatabase {
+    fn networks(&self) -> &dyn NetworkRepository {
+        self
+    }
+
+    fn contracts(&self) -> &dyn ContractRepository {
+        self
+    }
+
+    fn deployments(&self) -> &dyn DeploymentRepository {
+        self
+    }
+
+    fn wallets(&self) -> &dyn WalletRepository {
+        self
+    }
+
+    fn call_history(&self) -> &dyn CallHistoryRepository {
+        self
+    }
+
+    fn webhooks(&self) -> &dyn WebhookRepository {
+        self
+    }
+
+    fn ens_cache(&self) -> &dyn EnsCacheRepository {
+        self
+    }
+
+    fn token_metadata(&self) -> &dyn TokenMetadataRepository {
+        self
+    }
+
+    fn analytics(&self) -> &dyn AnalyticsRepository {
+        self
+    }
+
+    fn gas_samples(&self) -> &dyn GasSampleRepository {
+        self
+    }
+
+    fn prices(&self) -> &dyn PriceRepository {
+        self
+    }
+
+    fn deployment_groups(&self) -> &dyn DeploymentGroupRepository {
+        self
+    }
+
+    fn api_tokens(&self) -> &dyn ApiTokenRepository {
+        self
+    }
+
+    fn approvals(&self) -> &dyn ApprovalRepository {
+        self
+    }
+
+    fn templates(&self) -> &dyn TemplateRepository {
+        self
+    }
+
+    fn batches(&self) -> &dyn BatchRepository {
+        self
+    }
+
+    fn idempotency_keys(&self) -> &dyn IdempotencyRepository {
+        self
+    }
+
+    fn selectors(&self) -> &dyn SelectorRepository {
+        self
+    }
+
+    fn reorgs(&self) -> &dyn ReorgRepository {
+        self
+    }
+
+    fn signatures(&self) -> &dyn SignatureRepository {
+        self
+    }
+
+    fn audit_log(&self) -> &dyn AuditRepository {
+        self
+    }
+
+    fn wallet_policies(&self) -> &dyn WalletPolicyRepository {
+        self
+    }
+
+    fn jobs(&self) -> &dyn JobRepository {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        CallHistoryUpdate, NetworkUpdate, NewApproval, NewCallHistory, NewContract, NewDeployment,
+        NewJob, NewNetwork, NewWallet,
+    };
+    use crate::traits::{CallHistoryFilter, HistoryRetention};
+    use smolder_core::{ApiRole, BytecodeHash, CallType, ChainId, JobState};
+
+    /// A full-length address from a short one like `0xaaa`
+    fn addr(short: &str) -> String {
+        format!("0x{:0>40}", &short[2..])
+    }
+
+    async fn setup_versioned_deployments(db: &MemoryDatabase) -> (Deployment, Deployment) {
+        let network = NetworkRepository::upsert(
+            db,
+            &NewNetwork {
+                name: "testnet".to_string(),
+                chain_id: ChainId(1),
+                rpc_url: "https://rpc".to_string(),
+                explorer_url: None,
+            },
+        )
+        .await
+        .unwrap();
+        let contract = ContractRepository::upsert(
+            db,
+            &NewContract {
+                name: "Token".to_string(),
+                source_path: "src/Token.sol".to_string(),
+                abi: "[]".to_string(),
+                bytecode_hash: BytecodeHash::of("0x123"),
+                storage_layout: None,
+                compiler: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut created = Vec::new();
+        for address in ["0xaaa", "0xbbb"] {
+            let deployment = DeploymentRepository::create(
+                db,
+                &NewDeployment {
+                    contract_id: contract.id,
+                    network_id: network.id,
+                    address: addr(address).parse().unwrap(),
+                    deployer: "0xddd".to_string(),
+                    tx_hash: None,
+                    block_number: None,
+                    constructor_args: None,
+                    initcode_size: None,
+                    runtime_size: None,
+                    gas_used: None,
+                    gas_price: None,
+                },
+            )
+            .await
+            .unwrap();
+            created.push(deployment);
+        }
+
+        let v2 = created.pop().unwrap();
+        let v1 = created.pop().unwrap();
+        (v1, v2)
+    }
+
+    #[tokio::test]
+    async fn test_versions_and_cascades() {
+        let db = MemoryDatabase::new();
+        let (v1, v2) = setup_versioned_deployments(&db).await;
+        assert_eq!((v1.version, v2.version), (1, 2));
+
+        let current = DeploymentRepository::get_current(&db, "Token", "testnet")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.id, v2.id);
+
+        // Deleting the current version promotes the previous one
+        DeploymentRepository::delete(&db, v2.id).await.unwrap();
+        let current = DeploymentRepository::get_current(&db, "Token", "testnet")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.id, v1.id);
+
+        // Networks with deployments can't be deleted, like with the foreign key
+        assert!(NetworkRepository::delete(&db, "testnet").await.is_err());
+        ContractRepository::delete(&db, "Token").await.unwrap();
+        NetworkRepository::delete(&db, "testnet").await.unwrap();
+        assert!(DeploymentRepository::get_by_id(&db, v1.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deployments_link_rotated_wallets() {
+        let db = MemoryDatabase::new();
+        let (v1, _) = setup_versioned_deployments(&db).await;
+        let old_address: EvmAddress = addr("0xaaa").parse().unwrap();
+        let wallet = WalletRepository::create(
+            &db,
+            &NewWallet {
+                name: "deployer".to_string(),
+                address: old_address,
+                encrypted_key: vec![1],
+            },
+        )
+        .await
+        .unwrap();
+        WalletRepository::rotate(&db, "deployer", addr("0xbbb").parse().unwrap(), &[2])
+            .await
+            .unwrap();
+
+        // Like the trigger, a deployment from the replaced key is linked too
+        let imported = DeploymentRepository::create(
+            &db,
+            &NewDeployment {
+                contract_id: v1.contract_id,
+                network_id: v1.network_id,
+                address: addr("0xccc").parse().unwrap(),
+                deployer: old_address.to_string().to_uppercase().replace("0X", "0x"),
+                tx_hash: None,
+                block_number: None,
+                constructor_args: None,
+                initcode_size: None,
+                runtime_size: None,
+                gas_used: None,
+                gas_price: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(imported.wallet_id, Some(wallet.id));
+    }
+
+    #[tokio::test]
+    async fn test_row_versions() {
+        let db = MemoryDatabase::new();
+        setup_versioned_deployments(&db).await;
+
+        let update = NetworkUpdate {
+            rpc_url: Some("https://rpc.example.com".to_string()),
+            explorer_url: None,
+        };
+        let updated = NetworkRepository::update(&db, "testnet", &update, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(updated.row_version, 2);
+        assert!(matches!(
+            NetworkRepository::update(&db, "testnet", &update, Some(1)).await,
+            Err(Error::VersionMismatch {
+                expected: 1,
+                actual: 2,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_advance_reaches_expiry_and_retention() {
+        let db = MemoryDatabase::new();
+        let (v1, _) = setup_versioned_deployments(&db).await;
+        let token = ApiTokenRepository::create(&db, "alice", "0xa", ApiRole::Admin)
+            .await
+            .unwrap();
+
+        ApprovalRepository::create(
+            &db,
+            &NewApproval {
+                kind: ApprovalKind::Send,
+                network_id: v1.network_id,
+                deployment_id: Some(v1.id),
+                summary: "Token.pause()".to_string(),
+                request: "{}".to_string(),
+                requested_by: token.id,
+                ttl: Duration::from_secs(3600),
+            },
+        )
+        .await
+        .unwrap();
+        let call = CallHistoryRepository::create(
+            &db,
+            &NewCallHistory {
+                deployment_id: v1.id,
+                wallet_id: None,
+                function_name: "pause".to_string(),
+                function_signature: "pause()".to_string(),
+                input_params: "[]".to_string(),
+                call_type: CallType::Write,
+            },
+        )
+        .await
+        .unwrap();
+        CallHistoryRepository::update(
+            &db,
+            call.id,
+            &CallHistoryUpdate {
+                result: None,
+                tx_hash: None,
+                block_number: None,
+                gas_used: Some(21_000),
+                gas_price: Some("1000000000".to_string()),
+                status: TransactionStatus::Success,
+                error_message: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let retention = HistoryRetention {
+            max_age: Duration::from_secs(90 * 24 * 3600),
+            keep_failed: true,
+        };
+        assert_eq!(ApprovalRepository::expire(&db).await.unwrap(), 0);
+        assert_eq!(
+            CallHistoryRepository::count_prunable(&db, &retention)
+                .await
+                .unwrap(),
+            0
+        );
+
+        db.advance(Duration::from_secs(100 * 24 * 3600));
+        assert_eq!(ApprovalRepository::expire(&db).await.unwrap(), 1);
+        assert_eq!(
+            CallHistoryRepository::prune(&db, &retention).await.unwrap(),
+            1
+        );
+        assert!(
+            CallHistoryRepository::list(&db, CallHistoryFilter::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_job_retries_come_due() {
+        let db = MemoryDatabase::new();
+        let job = JobRepository::enqueue(
+            &db,
+            &NewJob {
+                kind: "receipt".to_string(),
+                payload: "{}".to_string(),
+                max_attempts: 2,
+            },
+        )
+        .await
+        .unwrap();
+
+        JobRepository::claim(&db).await.unwrap().unwrap();
+        let state = JobRepository::fail(&db, job.id, "timed out", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert_eq!(state, JobState::Queued);
+        assert!(JobRepository::claim(&db).await.unwrap().is_none());
+
+        db.advance(Duration::from_secs(60));
+        let retried = JobRepository::claim(&db).await.unwrap().unwrap();
+        assert_eq!(retried.attempts, 2);
+        let state = JobRepository::fail(&db, job.id, "timed out", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert_eq!(state, JobState::Failed);
+
+        db.advance(Duration::from_secs(3600));
+        assert_eq!(
+            JobRepository::prune(&db, Duration::from_secs(60))
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dates_match_sqlite() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+        // Every day across a few year boundaries, with a leap year
+        let start = days_from_civil(2023, 12, 20) * 86_400;
+        for day in 0..800 {
+            let unix = start + day * 86_400 + 3_723;
+            let at = timestamp(unix);
+            let (expected_at, expected_week): (String, String) =
+                sqlx::query_as("SELECT datetime(?, 'unixepoch'), strftime('%Y-W%W', ?)")
+                    .bind(unix)
+                    .bind(&at)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(at, expected_at);
+            assert_eq!(period(Some(GasInterval::Week), &at), Some(expected_week));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! NetworkRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::chains;
+use smolder_core::{ChainId, Currency, Error, NetworkId, Result};
+
+use super::{constraint, MemoryDatabase};
+use crate::models::{Network, NetworkUpdate, NewNetwork, DEFAULT_REORG_DEPTH};
+use crate::traits::NetworkRepository;
+
+impl MemoryDatabase {
+    /// Change a network by name, bumping its row version
+    fn update_network(&self, name: &str, change: impl FnOnce(&mut Network)) -> Result<Network> {
+        let mut tables = self.write();
+        let network = tables
+            .networks
+            .values_mut()
+            .find(|n| n.name == name)
+            .ok_or_else(|| Error::NetworkNotFound(name.to_string()))?;
+        change(network);
+        network.row_version += 1;
+        Ok(network.clone())
+    }
+}
+
+#[async_trait]
+impl NetworkRepository for MemoryDatabase {
+    async fn list(&self) -> Result<Vec<Network>> {
+        let mut networks: Vec<Network> = self.read().networks.values().cloned().collect();
+        networks.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(networks)
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Network>> {
+        let tables = self.read();
+        Ok(tables.networks.values().find(|n| n.name == name).cloned())
+    }
+
+    async fn get_by_id(&self, id: NetworkId) -> Result<Option<Network>> {
+        Ok(self.read().networks.get(&id.0).cloned())
+    }
+
+    async fn get_by_chain_id(&self, chain_id: ChainId) -> Result<Option<Network>> {
+        let tables = self.read();
+        Ok(tables
+            .networks
+            .values()
+            .find(|n| n.chain_id == chain_id)
+            .cloned())
+    }
+
+    async fn upsert(&self, network: &NewNetwork) -> Result<Network> {
+        let existing = self.update_network(&network.name, |existing| {
+            existing.chain_id = network.chain_id;
+            existing.rpc_url = network.rpc_url.as_str().into();
+            existing.explorer_url = network.explorer_url.clone();
+        });
+        if let Ok(existing) = existing {
+            return Ok(existing);
+        }
+
+        let currency = chains::currency(network.chain_id.into());
+        let mut tables = self.write();
+        let created = Network {
+            id: NetworkId(tables.next_id("networks")),
+            name: network.name.clone(),
+            chain_id: network.chain_id,
+            rpc_url: network.rpc_url.as_str().into(),
+            explorer_url: network.explorer_url.clone(),
+            created_at: tables.now(),
+            impersonation: false,
+            protected: false,
+            private_relay: None,
+            currency_symbol: currency.symbol.to_string(),
+            currency_decimals: currency.decimals,
+            row_version: 1,
+            reorg_depth: DEFAULT_REORG_DEPTH,
+            confirmations: 1,
+            locked: false,
+        };
+        tables.networks.insert(created.id.0, created.clone());
+        Ok(created)
+    }
+
+    async fn set_impersonation(&self, name: &str, enabled: bool) -> Result<()> {
+        self.update_network(name, |n| n.impersonation = enabled)?;
+        Ok(())
+    }
+
+    async fn set_protected(&self, name: &str, protected: bool) -> Result<()> {
+        self.update_network(name, |n| n.protected = protected)?;
+        Ok(())
+    }
+
+    async fn set_private_relay(&self, name: &str, relay_url: Option<&str>) -> Result<()> {
+        self.update_network(name, |n| n.private_relay = relay_url.map(str::to_string))?;
+        Ok(())
+    }
+
+    async fn set_currency(&self, name: &str, currency: Currency<'_>) -> Result<()> {
+        self.update_network(name, |n| {
+            n.currency_symbol = currency.symbol.to_string();
+            n.currency_decimals = currency.decimals;
+        })?;
+        Ok(())
+    }
+
+    async fn set_reorg_depth(&self, name: &str, depth: u32) -> Result<()> {
+        self.update_network(name, |n| n.reorg_depth = depth)?;
+        Ok(())
+    }
+
+    async fn set_locked(&self, name: &str, locked: bool) -> Result<()> {
+        self.update_network(name, |n| n.locked = locked)?;
+        Ok(())
+    }
+
+    async fn set_confirmations(&self, name: &str, confirmations: u32) -> Result<()> {
+        self.update_network(name, |n| n.confirmations = confirmations)?;
+        Ok(())
+    }
+
+    async fn update(
+        &self,
+        name: &str,
+        update: &NetworkUpdate,
+        if_version: Option<i64>,
+    ) -> Result<Network> {
+        let network = NetworkRepository::get_by_name(self, name)
+            .await?
+            .ok_or_else(|| Error::NetworkNotFound(name.to_string()))?;
+        if let Some(expected) = if_version.filter(|v| *v != network.row_version) {
+            return Err(Error::VersionMismatch {
+                entity: "Network",
+                name: name.to_string(),
+                expected,
+                actual: network.row_version,
+            });
+        }
+
+        let rpc_url = update.rpc_url_over(&network.rpc_url)?;
+
+        self.update_network(name, |n| {
+            if let Some(rpc_url) = rpc_url {
+                n.rpc_url = rpc_url.into();
+            }
+            if let Some(ref explorer_url) = update.explorer_url {
+                n.explorer_url = Some(explorer_url.clone());
+            }
+        })
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let mut tables = self.write();
+        let Some(id) = tables
+            .networks
+            .values()
+            .find(|n| n.name == name)
+            .map(|n| n.id)
+        else {
+            return Ok(());
+        };
+        if tables.deployments.values().any(|d| d.network_id == id) {
+            return Err(constraint(format!(
+                "Network '{}' still has deployments",
+                name
+            )));
+        }
+
+        tables.networks.remove(&id.0);
+        tables.gas_samples.retain(|_, s| s.network_id != id);
+        tables.group_members.retain(|m| m.network_id != id);
+        tables.approvals.retain(|_, a| a.network_id != id);
+        tables.wallet_spends.retain(|s| s.network_id != id);
+        let batches: Vec<i64> = tables
+            .batches
+            .values()
+            .filter(|b| b.network_id == id)
+            .map(|b| b.id)
+            .collect();
+        tables.batches.retain(|_, b| b.network_id != id);
+        tables
+            .batch_calls
+            .retain(|c| !batches.contains(&c.batch_id));
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+//! PriceRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use super::MemoryDatabase;
+use crate::traits::PriceRepository;
+
+#[async_trait]
+impl PriceRepository for MemoryDatabase {
+    async fn get(&self, symbol: &str, day: &str) -> Result<Option<f64>> {
+        let tables = self.read();
+        Ok(tables
+            .prices
+            .get(&(symbol.to_string(), day.to_string()))
+            .copied())
+    }
+
+    async fn put(&self, symbol: &str, day: &str, usd: f64) -> Result<()> {
+        self.write()
+            .prices
+            .insert((symbol.to_string(), day.to_string()), usd);
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! ReorgRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{Finality, NetworkId, RecordKind, Result};
+
+use super::{reblocked, MemoryDatabase};
+use crate::models::MinedRecord;
+use crate::traits::ReorgRepository;
+
+#[async_trait]
+impl ReorgRepository for MemoryDatabase {
+    async fn unsettled(&self, network_id: NetworkId, after: i64) -> Result<Vec<MinedRecord>> {
+        let tables = self.read();
+        let unsettled = |block_number: i64, reorged_at: &Option<String>| {
+            block_number > after || reorged_at.is_some()
+        };
+
+        let deployments = tables
+            .deployments
+            .values()
+            .filter(|d| d.network_id == network_id)
+            .filter_map(|d| {
+                let (tx_hash, block_number) = (d.tx_hash?, d.block_number?);
+                unsettled(block_number, &d.reorged_at).then(|| MinedRecord {
+                    kind: RecordKind::Deployment,
+                    id: d.id.0,
+                    tx_hash,
+                    block_number,
+                    block_hash: d.block_hash.clone(),
+                    reorged_at: d.reorged_at.clone(),
+                })
+            });
+        let calls = tables
+            .call_history
+            .values()
+            .filter(|h| {
+                tables
+                    .deployments
+                    .get(&h.deployment_id.0)
+                    .is_some_and(|d| d.network_id == network_id)
+            })
+            .filter_map(|h| {
+                let (tx_hash, block_number) = (h.tx_hash?, h.block_number?);
+                unsettled(block_number, &h.reorged_at).then(|| MinedRecord {
+                    kind: RecordKind::Call,
+                    id: h.id,
+                    tx_hash,
+                    block_number,
+                    block_hash: h.block_hash.clone(),
+                    reorged_at: h.reorged_at.clone(),
+                })
+            });
+
+        let mut records: Vec<MinedRecord> = deployments.chain(calls).collect();
+        records.sort_by_key(|r| (r.block_number, r.kind == RecordKind::Call, r.id));
+        Ok(records)
+    }
+
+    async fn set_block(
+        &self,
+        kind: RecordKind,
+        id: i64,
+        block_number: i64,
+        block_hash: &str,
+    ) -> Result<()> {
+        let mut tables = self.write();
+        let hash = Some(block_hash.to_string());
+        match kind {
+            RecordKind::Deployment => {
+                if let Some(d) = tables.deployments.get_mut(&id) {
+                    d.finality = reblocked(d.finality, d.block_number, Some(block_number));
+                    (d.block_number, d.block_hash) = (Some(block_number), hash);
+                }
+            }
+            RecordKind::Call => {
+                if let Some(h) = tables.call_history.get_mut(&id) {
+                    h.finality = reblocked(h.finality, h.block_number, Some(block_number));
+                    (h.block_number, h.block_hash) = (Some(block_number), hash);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_reorged(&self, kind: RecordKind, id: i64, reorged: bool) -> Result<()> {
+        let mut tables = self.write();
+        let reorged_at = reorged.then(|| tables.now());
+        let row = match kind {
+            RecordKind::Deployment => tables
+                .deployments
+                .get_mut(&id)
+                .map(|d| (&mut d.finality, &mut d.reorged_at)),
+            RecordKind::Call => tables
+                .call_history
+                .get_mut(&id)
+                .map(|h| (&mut h.finality, &mut h.reorged_at)),
+        };
+        if let Some((finality, reorged_slot)) = row {
+            if reorged {
+                *finality = Some(Finality::Pending);
+            }
+            *reorged_slot = reorged_at;
+        }
+        Ok(())
+    }
+
+    async fn advance_finality(
+        &self,
+        network_id: NetworkId,
+        confirmed: i64,
+        finalized: Option<i64>,
+    ) -> Result<u64> {
+        let mut tables = self.write();
+        let advance = |finality: &mut Option<Finality>, block_number: Option<i64>| {
+            let Some(block_number) = block_number else {
+                return false;
+            };
+            let finalizes = finalized.is_some_and(|f| block_number <= f);
+            let next = match *finality {
+                Some(Finality::Pending | Finality::Confirmed) if finalizes => Finality::Finalized,
+                Some(Finality::Pending) if block_number <= confirmed => Finality::Confirmed,
+                _ => return false,
+            };
+            *finality = Some(next);
+            true
+        };
+
+        let on_network: Vec<i64> = tables
+            .deployments
+            .values()
+            .filter(|d| d.network_id == network_id)
+            .map(|d| d.id.0)
+            .collect();
+        let mut changed = 0;
+        for deployment in tables.deployments.values_mut() {
+            if deployment.network_id == network_id
+                && deployment.reorged_at.is_none()
+                && advance(&mut deployment.finality, deployment.block_number)
+            {
+                changed += 1;
+            }
+        }
+        for call in tables.call_history.values_mut() {
+            if on_network.contains(&call.deployment_id.0)
+                && call.reorged_at.is_none()
+                && advance(&mut call.finality, call.block_number)
+            {
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,60 @@
+//! SelectorRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{Result, SelectorSource};
+
+use super::MemoryDatabase;
+use crate::models::{KnownSelector, NewSelector};
+use crate::traits::SelectorRepository;
+
+#[async_trait]
+impl SelectorRepository for MemoryDatabase {
+    async fn lookup(&self, selector: &str) -> Result<Vec<KnownSelector>> {
+        let selector = selector.to_lowercase();
+        let mut known: Vec<KnownSelector> = self
+            .read()
+            .selectors
+            .values()
+            .filter(|s| s.selector == selector)
+            .cloned()
+            .collect();
+        known.sort_by(|a, b| {
+            (b.source == SelectorSource::Registry)
+                .cmp(&(a.source == SelectorSource::Registry))
+                .then(a.signature.cmp(&b.signature))
+        });
+        Ok(known)
+    }
+
+    async fn put_many(&self, selectors: &[NewSelector]) -> Result<u64> {
+        let mut tables = self.write();
+        let now = tables.now();
+        let mut added = 0;
+        for new in selectors {
+            let key = (new.selector.to_lowercase(), new.signature.clone());
+            match tables.selectors.get_mut(&key) {
+                Some(known) => {
+                    if new.source == SelectorSource::Registry
+                        && known.source != SelectorSource::Registry
+                    {
+                        known.source = SelectorSource::Registry;
+                        added += 1;
+                    }
+                }
+                None => {
+                    tables.selectors.insert(
+                        key.clone(),
+                        KnownSelector {
+                            selector: key.0,
+                            signature: key.1,
+                            source: new.source,
+                            added_at: now.clone(),
+                        },
+                    );
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+}
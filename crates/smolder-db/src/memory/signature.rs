@@ -0,0 +1,69 @@
+//! SignatureRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use super::{constraint, MemoryDatabase, SignatureRow, Tables};
+use crate::models::{NewSignature, SignatureRecord};
+use crate::traits::SignatureRepository;
+
+impl Tables {
+    /// A signature with its wallet and token resolved to names
+    fn signature_view(&self, row: &SignatureRow) -> SignatureRecord {
+        SignatureRecord {
+            id: row.id,
+            wallet_name: self.wallet_name(row.wallet_id),
+            signer: row.signer,
+            primary_type: row.primary_type.clone(),
+            domain: row.domain.clone(),
+            payload: row.payload.clone(),
+            digest: row.digest.clone(),
+            signature: row.signature.clone(),
+            signed_by: row
+                .signed_by
+                .and_then(|id| self.api_tokens.get(&id.0))
+                .map(|t| t.name.clone()),
+            created_at: row.created_at.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl SignatureRepository for MemoryDatabase {
+    async fn create(&self, signature: &NewSignature) -> Result<SignatureRecord> {
+        let mut tables = self.write();
+        if !tables.wallets.contains_key(&signature.wallet_id.0) {
+            return Err(constraint(format!(
+                "Wallet {} does not exist",
+                signature.wallet_id
+            )));
+        }
+
+        let row = SignatureRow {
+            id: tables.next_id("signatures"),
+            wallet_id: Some(signature.wallet_id),
+            signer: signature.signer,
+            primary_type: signature.primary_type.clone(),
+            domain: signature.domain.clone(),
+            payload: signature.payload.clone(),
+            digest: signature.digest.clone(),
+            signature: signature.signature.clone(),
+            signed_by: signature.signed_by,
+            created_at: tables.now(),
+        };
+        let record = tables.signature_view(&row);
+        tables.signatures.insert(row.id, row);
+        Ok(record)
+    }
+
+    async fn list(&self, wallet: Option<&str>) -> Result<Vec<SignatureRecord>> {
+        let tables = self.read();
+        Ok(tables
+            .signatures
+            .values()
+            .rev()
+            .map(|row| tables.signature_view(row))
+            .filter(|record| wallet.is_none() || record.wallet_name.as_deref() == wallet)
+            .collect())
+    }
+}
@@ -0,0 +1,60 @@
+//! TemplateRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{Error, Result};
+
+use super::MemoryDatabase;
+use crate::models::{NewTemplate, Template};
+use crate::traits::TemplateRepository;
+
+#[async_trait]
+impl TemplateRepository for MemoryDatabase {
+    async fn list(&self) -> Result<Vec<Template>> {
+        let mut templates: Vec<Template> = self.read().templates.values().cloned().collect();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Template>> {
+        let tables = self.read();
+        Ok(tables.templates.values().find(|t| t.name == name).cloned())
+    }
+
+    async fn upsert(&self, template: &NewTemplate) -> Result<Template> {
+        let mut tables = self.write();
+        let now = tables.now();
+        if let Some(existing) = tables
+            .templates
+            .values_mut()
+            .find(|t| t.name == template.name)
+        {
+            existing.artifact = template.artifact.clone();
+            existing.params = template.params.clone();
+            existing.description = template.description.clone();
+            existing.updated_at = now;
+            return Ok(existing.clone());
+        }
+
+        let saved = Template {
+            id: tables.next_id("templates"),
+            name: template.name.clone(),
+            artifact: template.artifact.clone(),
+            params: template.params.clone(),
+            description: template.description.clone(),
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        tables.templates.insert(saved.id, saved.clone());
+        Ok(saved)
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let mut tables = self.write();
+        let before = tables.templates.len();
+        tables.templates.retain(|_, t| t.name != name);
+        if tables.templates.len() == before {
+            return Err(Error::TemplateNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+//! TokenMetadataRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{DeploymentId, Error, Result};
+
+use super::MemoryDatabase;
+use crate::models::{NewTokenMetadata, TokenMetadata};
+use crate::traits::TokenMetadataRepository;
+
+#[async_trait]
+impl TokenMetadataRepository for MemoryDatabase {
+    async fn get(&self, deployment_id: DeploymentId) -> Result<Option<TokenMetadata>> {
+        Ok(self.read().token_metadata.get(&deployment_id.0).cloned())
+    }
+
+    async fn put(&self, metadata: &NewTokenMetadata) -> Result<()> {
+        let mut tables = self.write();
+        if !tables.deployments.contains_key(&metadata.deployment_id.0) {
+            return Err(Error::DeploymentNotFoundById(metadata.deployment_id));
+        }
+
+        let stored = TokenMetadata {
+            standard: metadata.standard,
+            name: metadata.name.clone(),
+            symbol: metadata.symbol.clone(),
+            decimals: metadata.decimals,
+            total_supply: metadata.total_supply.clone(),
+            fetched_at: tables.now(),
+        };
+        tables
+            .token_metadata
+            .insert(metadata.deployment_id.0, stored);
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+//! WalletRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{Error, EvmAddress, Result, WalletId};
+
+use super::{constraint, MemoryDatabase, Tables, WalletRow};
+use crate::models::{
+    CallHistoryView, DeploymentView, NewWallet, Wallet, WalletRotation, WalletWithKey,
+};
+use crate::traits::WalletRepository;
+
+impl WalletRow {
+    fn with_key(&self) -> WalletWithKey {
+        WalletWithKey {
+            id: self.wallet.id,
+            name: self.wallet.name.clone(),
+            address: self.wallet.address,
+            encrypted_key: self.encrypted_key.clone(),
+            created_at: self.wallet.created_at.clone(),
+        }
+    }
+}
+
+impl Tables {
+    /// Link the deployments sent from `address` that no wallet claims yet to
+    /// the wallet now holding it
+    fn link_deployments(&mut self, id: WalletId, address: EvmAddress) {
+        for deployment in self.deployments.values_mut() {
+            if deployment.wallet_id.is_none() && address == *deployment.deployer {
+                deployment.wallet_id = Some(id);
+            }
+        }
+    }
+
+    /// Whether `address` is one a wallet has had
+    fn was_wallet_address(&self, id: WalletId, address: &str) -> bool {
+        let matches = |a: &EvmAddress| a.to_string().eq_ignore_ascii_case(address);
+        self.wallets
+            .get(&id.0)
+            .is_some_and(|w| matches(&w.wallet.address))
+            || self
+                .wallet_rotations
+                .iter()
+                .any(|r| r.wallet_id == id && matches(&r.old_address))
+    }
+}
+
+#[async_trait]
+impl WalletRepository for MemoryDatabase {
+    async fn list(&self) -> Result<Vec<Wallet>> {
+        let mut wallets: Vec<Wallet> = self
+            .read()
+            .wallets
+            .values()
+            .map(|w| w.wallet.clone())
+            .collect();
+        wallets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(wallets)
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Wallet>> {
+        let tables = self.read();
+        Ok(tables
+            .wallets
+            .values()
+            .find(|w| w.wallet.name == name)
+            .map(|w| w.wallet.clone()))
+    }
+
+    async fn get_with_key(&self, name: &str) -> Result<Option<WalletWithKey>> {
+        let tables = self.read();
+        Ok(tables
+            .wallets
+            .values()
+            .find(|w| w.wallet.name == name)
+            .map(WalletRow::with_key))
+    }
+
+    async fn get_by_id(&self, id: WalletId) -> Result<Option<Wallet>> {
+        Ok(self.read().wallets.get(&id.0).map(|w| w.wallet.clone()))
+    }
+
+    async fn get_by_address(&self, address: &str) -> Result<Option<Wallet>> {
+        let tables = self.read();
+        Ok(tables
+            .wallets
+            .values()
+            .find(|w| w.wallet.address == *address)
+            .map(|w| w.wallet.clone()))
+    }
+
+    async fn create(&self, wallet: &NewWallet) -> Result<Wallet> {
+        let mut tables = self.write();
+        if let Some(existing) = tables
+            .wallets
+            .values()
+            .find(|w| w.wallet.name == wallet.name || w.wallet.address == wallet.address)
+        {
+            return Err(constraint(format!(
+                "Wallet '{}' already exists at {}",
+                existing.wallet.name, existing.wallet.address
+            )));
+        }
+
+        let created = Wallet {
+            id: WalletId(tables.next_id("wallets")),
+            name: wallet.name.clone(),
+            address: wallet.address,
+            created_at: tables.now(),
+            row_version: 1,
+        };
+        tables.wallets.insert(
+            created.id.0,
+            WalletRow {
+                wallet: created.clone(),
+                encrypted_key: wallet.encrypted_key.clone(),
+            },
+        );
+        tables.link_deployments(created.id, created.address);
+        Ok(created)
+    }
+
+    async fn rotate(
+        &self,
+        name: &str,
+        address: EvmAddress,
+        encrypted_key: &[u8],
+    ) -> Result<Wallet> {
+        let mut tables = self.write();
+        let Some(id) = tables
+            .wallets
+            .values()
+            .find(|w| w.wallet.name == name)
+            .map(|w| w.wallet.id)
+        else {
+            return Err(Error::WalletNotFound(name.to_string()));
+        };
+        if let Some(other) = tables
+            .wallets
+            .values()
+            .find(|w| w.wallet.id != id && w.wallet.address == address)
+        {
+            return Err(constraint(format!(
+                "Wallet '{}' already exists at {}",
+                other.wallet.name, address
+            )));
+        }
+
+        let rotation = WalletRotation {
+            id: tables.next_id("wallet_rotations"),
+            wallet_id: id,
+            old_address: tables.wallets[&id.0].wallet.address,
+            new_address: address,
+            rotated_at: tables.now(),
+        };
+        tables.wallet_rotations.push(rotation);
+        let row = tables.wallets.get_mut(&id.0).expect("found above");
+        row.wallet.address = address;
+        row.wallet.row_version += 1;
+        row.encrypted_key = encrypted_key.to_vec();
+        let rotated = row.wallet.clone();
+        tables.link_deployments(id, address);
+        Ok(rotated)
+    }
+
+    async fn rotations(&self, id: WalletId) -> Result<Vec<WalletRotation>> {
+        Ok(self
+            .read()
+            .wallet_rotations
+            .iter()
+            .filter(|r| r.wallet_id == id)
+            .cloned()
+            .collect())
+    }
+
+    async fn deployments(&self, id: WalletId, limit: u32) -> Result<Vec<DeploymentView>> {
+        let tables = self.read();
+        let mut deployments: Vec<DeploymentView> = tables
+            .deployments
+            .values()
+            .filter(|d| d.wallet_id == Some(id) && d.archived_at.is_none())
+            .filter_map(|d| tables.deployment_view(d))
+            .collect();
+        deployments.sort_by(|a, b| (&b.deployed_at, b.id.0).cmp(&(&a.deployed_at, a.id.0)));
+        deployments.truncate(limit as usize);
+        Ok(deployments)
+    }
+
+    async fn calls(&self, id: WalletId, limit: u32) -> Result<Vec<CallHistoryView>> {
+        let tables = self.read();
+        Ok(tables
+            .call_history
+            .values()
+            .rev()
+            .filter(|h| {
+                h.wallet_id == Some(id)
+                    || h.sender
+                        .as_deref()
+                        .is_some_and(|sender| tables.was_wallet_address(id, sender))
+            })
+            .filter_map(|h| tables.call_view(h))
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let mut tables = self.write();
+        let Some(id) = tables
+            .wallets
+            .values()
+            .find(|w| w.wallet.name == name)
+            .map(|w| w.wallet.id)
+        else {
+            return Ok(());
+        };
+        if tables
+            .call_history
+            .values()
+            .any(|h| h.wallet_id == Some(id))
+        {
+            return Err(constraint(format!("Wallet '{}' has sent calls", name)));
+        }
+
+        tables.wallets.remove(&id.0);
+        tables.wallet_policies.remove(&id.0);
+        tables.wallet_rotations.retain(|r| r.wallet_id != id);
+        tables.wallet_spends.retain(|s| s.wallet_id != id);
+        for deployment in tables.deployments.values_mut() {
+            if deployment.wallet_id == Some(id) {
+                deployment.wallet_id = None;
+            }
+        }
+        for batch in tables.batches.values_mut() {
+            if batch.wallet_id == Some(id) {
+                batch.wallet_id = None;
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_if(&self, name: &str, if_version: Option<i64>) -> Result<()> {
+        let wallet = WalletRepository::get_by_name(self, name)
+            .await?
+            .ok_or_else(|| Error::WalletNotFound(name.to_string()))?;
+        if if_version.is_some_and(|v| v != wallet.row_version) {
+            return Err(Error::VersionMismatch {
+                entity: "Wallet",
+                name: name.to_string(),
+                expected: if_version.unwrap_or_default(),
+                actual: wallet.row_version,
+            });
+        }
+        WalletRepository::delete(self, name).await
+    }
+}
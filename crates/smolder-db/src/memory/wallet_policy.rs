@@ -0,0 +1,83 @@
+//! WalletPolicyRepository implementation in memory
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::types::{NetworkId, WalletId};
+use smolder_core::Result;
+
+use super::{MemoryDatabase, SpendRow};
+use crate::models::WalletPolicy;
+use crate::traits::WalletPolicyRepository;
+
+#[async_trait]
+impl WalletPolicyRepository for MemoryDatabase {
+    async fn get(&self, wallet_id: WalletId) -> Result<Option<WalletPolicy>> {
+        Ok(self.read().wallet_policies.get(&wallet_id.0).cloned())
+    }
+
+    async fn set(&self, wallet_id: WalletId, policy: &WalletPolicy) -> Result<()> {
+        self.write()
+            .wallet_policies
+            .insert(wallet_id.0, policy.clone());
+        Ok(())
+    }
+
+    async fn clear(&self, wallet_id: WalletId) -> Result<bool> {
+        Ok(self.write().wallet_policies.remove(&wallet_id.0).is_some())
+    }
+
+    async fn reserve_spend(
+        &self,
+        wallet_id: WalletId,
+        network_id: NetworkId,
+        value: &str,
+        window: Duration,
+        fits: &(dyn for<'s> Fn(&'s [String]) -> bool + Send + Sync),
+    ) -> Result<Option<i64>> {
+        let mut tables = self.write();
+        let since = tables.now_plus(-(window.as_secs() as i64));
+        let earlier: Vec<String> = tables
+            .wallet_spends
+            .iter()
+            .filter(|s| s.wallet_id == wallet_id && s.network_id == network_id)
+            .filter(|s| s.created_at >= since)
+            .map(|s| s.value.clone())
+            .collect();
+        if !fits(&earlier) {
+            return Ok(None);
+        }
+        let id = tables.next_id("wallet_spends");
+        let created_at = tables.now();
+        tables.wallet_spends.push(SpendRow {
+            id,
+            wallet_id,
+            network_id,
+            value: value.to_string(),
+            created_at,
+        });
+        Ok(Some(id))
+    }
+
+    async fn release_spend(&self, id: i64) -> Result<()> {
+        self.write().wallet_spends.retain(|s| s.id != id);
+        Ok(())
+    }
+
+    async fn spent_within(
+        &self,
+        wallet_id: WalletId,
+        network_id: NetworkId,
+        window: Duration,
+    ) -> Result<Vec<String>> {
+        let tables = self.read();
+        let since = tables.now_plus(-(window.as_secs() as i64));
+        Ok(tables
+            .wallet_spends
+            .iter()
+            .filter(|s| s.wallet_id == wallet_id && s.network_id == network_id)
+            .filter(|s| s.created_at >= since)
+            .map(|s| s.value.clone())
+            .collect())
+    }
+}
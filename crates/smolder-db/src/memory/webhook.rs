@@ -0,0 +1,107 @@
+//! WebhookRepository implementation in memory
+
+use async_trait::async_trait;
+use smolder_core::{DeliveryStatus, Error, Result, WebhookId};
+
+use super::MemoryDatabase;
+use crate::models::{
+    NewWebhook, NewWebhookDelivery, Webhook, WebhookDelivery, WebhookDeliveryUpdate,
+};
+use crate::traits::WebhookRepository;
+
+#[async_trait]
+impl WebhookRepository for MemoryDatabase {
+    async fn list(&self) -> Result<Vec<Webhook>> {
+        Ok(self.read().webhooks.values().cloned().collect())
+    }
+
+    async fn get_by_id(&self, id: WebhookId) -> Result<Option<Webhook>> {
+        Ok(self.read().webhooks.get(&id.0).cloned())
+    }
+
+    async fn create(&self, webhook: &NewWebhook) -> Result<Webhook> {
+        let mut tables = self.write();
+        let created = Webhook {
+            id: WebhookId(tables.next_id("webhooks")),
+            url: webhook.url.clone(),
+            secret: webhook.secret.clone(),
+            network: webhook.network.clone(),
+            contract: webhook.contract.clone(),
+            event_name: webhook.event_name.clone(),
+            tx_status: webhook.tx_status,
+            created_at: tables.now(),
+        };
+        tables.webhooks.insert(created.id.0, created.clone());
+        Ok(created)
+    }
+
+    async fn delete(&self, id: WebhookId) -> Result<()> {
+        let mut tables = self.write();
+        tables.webhooks.remove(&id.0);
+        tables.webhook_deliveries.retain(|_, d| d.webhook_id != id);
+        Ok(())
+    }
+
+    async fn create_delivery(&self, delivery: &NewWebhookDelivery) -> Result<WebhookDelivery> {
+        let mut tables = self.write();
+        if !tables.webhooks.contains_key(&delivery.webhook_id.0) {
+            return Err(Error::WebhookNotFound(delivery.webhook_id));
+        }
+
+        let created = WebhookDelivery {
+            id: tables.next_id("webhook_deliveries"),
+            webhook_id: delivery.webhook_id,
+            trigger: delivery.trigger,
+            payload: delivery.payload.clone(),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            response_status: None,
+            error_message: None,
+            created_at: tables.now(),
+            delivered_at: None,
+        };
+        tables
+            .webhook_deliveries
+            .insert(created.id, created.clone());
+        Ok(created)
+    }
+
+    async fn update_delivery(&self, id: i64, update: &WebhookDeliveryUpdate) -> Result<()> {
+        let mut tables = self.write();
+        let now = tables.now();
+        if let Some(delivery) = tables.webhook_deliveries.get_mut(&id) {
+            delivery.status = update.status;
+            delivery.attempts = update.attempts;
+            delivery.response_status = update.response_status;
+            delivery.error_message = update.error_message.clone();
+            delivery.delivered_at = (update.status == DeliveryStatus::Delivered).then_some(now);
+        }
+        Ok(())
+    }
+
+    async fn list_deliveries(
+        &self,
+        webhook_id: WebhookId,
+        limit: u32,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let tables = self.read();
+        Ok(tables
+            .webhook_deliveries
+            .values()
+            .rev()
+            .filter(|d| d.webhook_id == webhook_id)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_pending_deliveries(&self) -> Result<Vec<WebhookDelivery>> {
+        let tables = self.read();
+        Ok(tables
+            .webhook_deliveries
+            .values()
+            .filter(|d| d.status == DeliveryStatus::Pending)
+            .cloned()
+            .collect())
+    }
+}
@@ -4,64 +4,252 @@
 //! including both read models (with `FromRow`) and write models (New* structs).
 
 use serde::{Deserialize, Serialize};
+use smolder_core::address::EvmAddress;
+use smolder_core::compiler::CompilerSettings;
+use smolder_core::error::{Error, Result};
+use smolder_core::hash::{BytecodeHash, StoredTxHash, TxHash};
+use smolder_core::secret::{RpcUrl, REDACTED};
 use smolder_core::types::{
-    CallType, ChainId, ContractId, DeploymentId, NetworkId, TransactionStatus, WalletId,
+    ApiRole, ApiTokenId, ApprovalId, ApprovalKind, ApprovalStatus, AuditAction, AuditSource,
+    CallType, ChainId, ContractId, DeliveryStatus, DeploymentGroupId, DeploymentId, EnsLookup,
+    Finality, JobState, NetworkId, RecordKind, SelectorSource, TokenStandard, TransactionStatus,
+    WalletId, WebhookId, WebhookTrigger,
 };
-use sqlx::FromRow;
+use smolder_core::units::Currency;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
 
 /// Network configuration stored in database
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Network {
     pub id: NetworkId,
     pub name: String,
     pub chain_id: ChainId,
-    pub rpc_url: String,
+    /// Shown with its credentials masked; stored encrypted when it has any
+    pub rpc_url: RpcUrl,
     pub explorer_url: Option<String>,
     pub created_at: String,
+    /// The node accepts transactions from any address (anvil's
+    /// `anvil_impersonateAccount`), so sends don't need a stored wallet
+    #[serde(default)]
+    pub impersonation: bool,
+    /// Deploys and sends through the API wait for approval by a second token
+    #[serde(default)]
+    pub protected: bool,
+    /// RPC of a private relay (Flashbots Protect, MEV Blocker) that signed
+    /// transactions go to instead of the public mempool
+    #[serde(default)]
+    pub private_relay: Option<String>,
+    /// Symbol of the native currency values are shown in
+    #[serde(default = "default_currency_symbol")]
+    pub currency_symbol: String,
+    /// Decimals of the native currency; values are sent in its smallest unit
+    #[serde(default = "default_currency_decimals")]
+    pub currency_decimals: u8,
+    /// Bumped on every change, and sent as the ETag of the network
+    #[serde(default)]
+    pub row_version: i64,
+    /// Blocks a recorded transaction must be buried under before a reorg is
+    /// no longer expected to drop it
+    #[serde(default = "default_reorg_depth")]
+    pub reorg_depth: u32,
+    /// Blocks, counting its own, a transaction must be buried under before
+    /// it is confirmed
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u32,
+    /// Deploys and sends are refused unless confirmed with the network's
+    /// name
+    #[serde(default)]
+    pub locked: bool,
+}
+
+fn default_confirmations() -> u32 {
+    1
+}
+
+fn default_reorg_depth() -> u32 {
+    DEFAULT_REORG_DEPTH
+}
+
+/// Reorg depth of networks that don't set one
+pub const DEFAULT_REORG_DEPTH: u32 = 12;
+
+fn default_currency_symbol() -> String {
+    Currency::ETHER.symbol.to_string()
+}
+
+fn default_currency_decimals() -> u8 {
+    Currency::ETHER.decimals
+}
+
+impl Network {
+    /// The network's native currency, to parse and format values with
+    pub fn currency(&self) -> Currency<'_> {
+        Currency {
+            symbol: &self.currency_symbol,
+            decimals: self.currency_decimals,
+        }
+    }
 }
 
 /// Contract definition (source-level)
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Contract {
     pub id: ContractId,
     pub name: String,
     pub source_path: String,
     pub abi: String, // JSON string
-    pub bytecode_hash: String,
+    pub bytecode_hash: BytecodeHash,
     pub created_at: String,
+    /// Storage layout JSON, when the compiler emitted one
+    pub storage_layout: Option<String>,
+    /// Compiler version and settings, when the artifact carried metadata
+    #[sqlx(json(nullable))]
+    pub compiler: Option<CompilerSettings>,
+    /// Listed first, along with its deployments; pins are by name, so they
+    /// hold for every build of the contract
+    pub pinned: bool,
+    /// Display name shown alongside the contract's, kept by name like pins
+    pub label: Option<String>,
+    /// Markdown description
+    pub description: Option<String>,
 }
 
 /// Deployment instance on a chain
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Deployment {
     pub id: DeploymentId,
     pub contract_id: ContractId,
     pub network_id: NetworkId,
-    pub address: String,
+    pub address: EvmAddress,
     pub deployer: String,
-    pub tx_hash: String,
+    /// Registered wallet the deployment was sent from, when the deployer is
+    /// one of its addresses
+    pub wallet_id: Option<WalletId>,
+    /// `None` for deployments recorded without their transaction
+    #[sqlx(try_from = "StoredTxHash")]
+    pub tx_hash: Option<TxHash>,
     pub block_number: Option<i64>,
     pub constructor_args: Option<String>, // JSON string
     pub version: i64,
     pub deployed_at: String,
     pub is_current: bool,
+    pub archived_at: Option<String>,
+    /// Init code size in bytes, without constructor arguments
+    pub initcode_size: Option<i64>,
+    /// Deployed (runtime) code size in bytes
+    pub runtime_size: Option<i64>,
+    /// Gas used by the deployment transaction
+    pub gas_used: Option<i64>,
+    /// Effective gas price of the deployment transaction, in wei
+    pub gas_price: Option<String>,
+    /// Fee paid for the deployment transaction, in wei
+    pub fee: Option<String>,
+    /// Call history entry of the factory call that created the contract
+    pub created_by_call_id: Option<i64>,
+    /// Hash of the block the deployment transaction was mined in, once seen
+    pub block_hash: Option<String>,
+    /// When a reorg dropped the deployment transaction from its block;
+    /// cleared once it is mined again and buried deep enough
+    pub reorged_at: Option<String>,
+    /// `None` for deployments recorded without their block
+    pub finality: Option<Finality>,
+    /// Listed first
+    pub pinned: bool,
+    /// Display name shown alongside the contract's
+    pub label: Option<String>,
+    /// Markdown description
+    pub description: Option<String>,
 }
 
 /// Joined view of deployment with contract and network info
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DeploymentView {
     pub id: DeploymentId,
     pub contract_name: String,
     pub network_name: String,
     pub chain_id: ChainId,
-    pub address: String,
+    pub address: EvmAddress,
     pub deployer: String,
-    pub tx_hash: String,
+    /// Name of the registered wallet the deployment was sent from
+    pub wallet_name: Option<String>,
+    /// `None` for deployments recorded without their transaction
+    #[sqlx(try_from = "StoredTxHash")]
+    pub tx_hash: Option<TxHash>,
     pub block_number: Option<i64>,
     pub version: i64,
     pub deployed_at: String,
     pub is_current: bool,
+    pub archived_at: Option<String>,
+    pub initcode_size: Option<i64>,
+    pub runtime_size: Option<i64>,
+    pub gas_used: Option<i64>,
+    pub gas_price: Option<String>,
+    pub fee: Option<String>,
+    /// Call history entry of the factory call that created the contract
+    pub created_by_call_id: Option<i64>,
+    /// When a reorg dropped the deployment transaction, until it is mined
+    /// again
+    pub reorged_at: Option<String>,
+    /// `None` for deployments recorded without their block
+    pub finality: Option<Finality>,
+    /// Pinned itself or through its contract, and listed first
+    pub pinned: bool,
+    /// The deployment's own label, or else its contract's
+    pub label: Option<String>,
+    /// The deployment's own description, or else its contract's
+    pub description: Option<String>,
     pub abi: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub links: ExplorerLinks,
+    /// Token details, once read from a contract whose ABI is a token's
+    #[sqlx(json(nullable))]
+    pub token: Option<TokenMetadata>,
+}
+
+/// Explorer pages for an address and the transaction behind it
+///
+/// Derived from the network's explorer URL when a view is loaded; both are
+/// `None` for networks without an explorer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExplorerLinks {
+    pub address_url: Option<String>,
+    pub tx_url: Option<String>,
+}
+
+impl ExplorerLinks {
+    pub fn new(explorer_url: Option<&str>, address: &str, tx_hash: Option<&str>) -> Self {
+        let Some(explorer_url) = explorer_url.filter(|u| !u.is_empty()) else {
+            return Self::default();
+        };
+        Self {
+            address_url: (!address.is_empty())
+                .then(|| smolder_core::address_link(explorer_url, address)),
+            tx_url: tx_hash
+                .filter(|h| !h.is_empty())
+                .map(|h| smolder_core::tx_link(explorer_url, h)),
+        }
+    }
+}
+
+/// Reads the `explorer_url`, `address` and `tx_hash` columns
+impl<'r> FromRow<'r, SqliteRow> for ExplorerLinks {
+    fn from_row(row: &'r SqliteRow) -> sqlx::Result<Self> {
+        let explorer_url: Option<String> = row.try_get("explorer_url")?;
+        let address: String = row.try_get("address")?;
+        let tx_hash: Option<String> = row.try_get("tx_hash")?;
+        Ok(Self::new(
+            explorer_url.as_deref(),
+            &address,
+            tx_hash.as_deref(),
+        ))
+    }
 }
 
 /// Input for creating a new network
@@ -73,13 +261,41 @@ pub struct NewNetwork {
     pub explorer_url: Option<String>,
 }
 
+/// Changes to an existing network; `None` fields are left as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NetworkUpdate {
+    pub rpc_url: Option<String>,
+    pub explorer_url: Option<String>,
+}
+
+impl NetworkUpdate {
+    /// The RPC URL to store in place of `current`, if any
+    ///
+    /// Networks are read with their URL masked, so the masked form of
+    /// `current` sent back leaves it as it is, and any other masked URL is
+    /// refused rather than stored over the credentials.
+    pub(crate) fn rpc_url_over(&self, current: &RpcUrl) -> Result<Option<&str>> {
+        match self.rpc_url.as_deref() {
+            Some(url) if url == current.redacted() => Ok(None),
+            Some(url) if url.contains(REDACTED) => Err(Error::invalid_param(
+                "rpc_url",
+                format!("has masked credentials ({}); send the full URL", REDACTED),
+            )),
+            url => Ok(url),
+        }
+    }
+}
+
 /// Input for creating a new contract
 #[derive(Debug, Clone)]
 pub struct NewContract {
     pub name: String,
     pub source_path: String,
     pub abi: String,
-    pub bytecode_hash: String,
+    pub bytecode_hash: BytecodeHash,
+    pub storage_layout: Option<String>,
+    pub compiler: Option<CompilerSettings>,
 }
 
 /// Input for creating a new deployment
@@ -87,20 +303,76 @@ pub struct NewContract {
 pub struct NewDeployment {
     pub contract_id: ContractId,
     pub network_id: NetworkId,
-    pub address: String,
+    pub address: EvmAddress,
+    pub deployer: String,
+    pub tx_hash: Option<TxHash>,
+    pub block_number: Option<i64>,
+    pub constructor_args: Option<String>,
+    pub initcode_size: Option<i64>,
+    pub runtime_size: Option<i64>,
+    pub gas_used: Option<i64>,
+    /// Effective gas price from the receipt, in wei
+    pub gas_price: Option<String>,
+}
+
+/// A deployment to record together with its contract, for batch imports
+#[derive(Debug, Clone)]
+pub struct NewContractDeployment {
+    pub contract: NewContract,
+    pub address: EvmAddress,
     pub deployer: String,
-    pub tx_hash: String,
+    pub tx_hash: Option<TxHash>,
     pub block_number: Option<i64>,
     pub constructor_args: Option<String>,
+    pub initcode_size: Option<i64>,
+    pub runtime_size: Option<i64>,
+    pub gas_used: Option<i64>,
+    /// Effective gas price from the receipt, in wei
+    pub gas_price: Option<String>,
+}
+
+/// What a batch import did with one deployment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    /// Recorded as a new deployment
+    Added,
+    /// Already tracked; missing block number, constructor args or gas filled in
+    Updated,
+    /// Already tracked with nothing to change
+    Skipped,
 }
 
 /// Wallet metadata (for listing without key)
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Wallet {
     pub id: WalletId,
     pub name: String,
-    pub address: String,
+    pub address: EvmAddress,
     pub created_at: String,
+    /// Bumped on every change, and sent as the ETag of the wallet
+    #[serde(default)]
+    pub row_version: i64,
+}
+
+/// Limits on what a wallet may sign; `None` fields don't limit anything
+#[derive(Debug, Clone, Default, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WalletPolicy {
+    /// Most wei a single transaction may send
+    pub max_value_per_tx: Option<String>,
+    /// Most wei the wallet may send on each network over 24 hours
+    pub max_daily_spend: Option<String>,
+    /// Networks the wallet may sign on, by name
+    #[sqlx(json(nullable))]
+    pub allowed_networks: Option<Vec<String>>,
+    /// Contracts the wallet may call or deploy, by registry name or address
+    #[sqlx(json(nullable))]
+    pub allowed_contracts: Option<Vec<String>>,
+    /// Functions the wallet may call, by name or full signature
+    #[sqlx(json(nullable))]
+    pub allowed_functions: Option<Vec<String>>,
 }
 
 /// Wallet with encrypted private key (for internal use)
@@ -108,21 +380,35 @@ pub struct Wallet {
 pub struct WalletWithKey {
     pub id: WalletId,
     pub name: String,
-    pub address: String,
+    pub address: EvmAddress,
     pub encrypted_key: Vec<u8>,
     pub created_at: String,
 }
 
+/// A key a wallet had before `wallet rotate` replaced it
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WalletRotation {
+    pub id: i64,
+    pub wallet_id: WalletId,
+    /// Address of the replaced key
+    pub old_address: EvmAddress,
+    /// Address of the key that replaced it
+    pub new_address: EvmAddress,
+    pub rotated_at: String,
+}
+
 /// Input for creating a new wallet
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewWallet {
     pub name: String,
-    pub address: String,
+    pub address: EvmAddress,
     pub encrypted_key: Vec<u8>,
 }
 
 /// Call history entry
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CallHistory {
     pub id: i64,
     pub deployment_id: DeploymentId,
@@ -132,38 +418,242 @@ pub struct CallHistory {
     pub input_params: String,   // JSON
     pub call_type: CallType,    // Read or Write
     pub result: Option<String>, // JSON for read results
-    pub tx_hash: Option<String>,
+    pub tx_hash: Option<TxHash>,
     pub block_number: Option<i64>,
     pub gas_used: Option<i64>,
     pub gas_price: Option<String>,
+    /// Fee paid, in wei
+    pub fee: Option<String>,
     pub status: Option<TransactionStatus>, // Pending, Success, Failed, Reverted
     pub error_message: Option<String>,
     pub created_at: String,
     pub confirmed_at: Option<String>,
+    /// Shareable link to a simulation of the call
+    pub simulation_url: Option<String>,
+    /// Sender of a transaction observed on chain rather than sent by smolder
+    pub sender: Option<String>,
+    /// Hash of the block the transaction was mined in, once seen
+    pub block_hash: Option<String>,
+    /// When a reorg dropped the transaction from its block; cleared once it
+    /// is mined again and buried deep enough
+    pub reorged_at: Option<String>,
+    /// `None` until the transaction is mined
+    pub finality: Option<Finality>,
 }
 
 /// Joined view of call history with deployment and wallet info
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CallHistoryView {
     pub id: i64,
     pub deployment_id: DeploymentId,
     pub contract_name: String,
     pub network_name: String,
-    pub contract_address: String,
+    pub contract_address: EvmAddress,
     pub wallet_name: Option<String>,
     pub function_name: String,
     pub function_signature: String,
     pub input_params: String,
     pub call_type: CallType,
     pub result: Option<String>,
-    pub tx_hash: Option<String>,
+    pub tx_hash: Option<TxHash>,
     pub block_number: Option<i64>,
     pub gas_used: Option<i64>,
     pub gas_price: Option<String>,
+    /// Fee paid, in wei
+    pub fee: Option<String>,
     pub status: Option<TransactionStatus>,
     pub error_message: Option<String>,
     pub created_at: String,
     pub confirmed_at: Option<String>,
+    /// Shareable link to a simulation of the call
+    pub simulation_url: Option<String>,
+    /// Sender of a transaction observed on chain rather than sent by smolder
+    pub sender: Option<String>,
+    /// When a reorg dropped the transaction, until it is mined again
+    pub reorged_at: Option<String>,
+    /// `None` until the transaction is mined
+    pub finality: Option<Finality>,
+    /// Links to the contract and, once sent, the transaction
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub links: ExplorerLinks,
+}
+
+/// Gas used by one function of a contract version, over a period when
+/// statistics are split by interval
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GasStat {
+    pub network: String,
+    pub contract: String,
+    pub version: i64,
+    /// Function name, or `constructor` for the deployment itself
+    pub function: String,
+    /// Day (`2025-01-31`), week (`2025-W04`) or month (`2025-01`)
+    pub period: Option<String>,
+    pub calls: i64,
+    pub avg_gas: i64,
+    pub min_gas: i64,
+    pub max_gas: i64,
+    pub total_gas: i64,
+}
+
+/// Fees paid on one network for one contract from one wallet, over a period
+/// when costs are split by interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CostStat {
+    pub network: String,
+    pub contract: String,
+    /// Wallet name; the deployer's address for deployments not sent from a
+    /// registered wallet, and `None` for impersonated calls
+    pub wallet: Option<String>,
+    /// Day (`2025-01-31`), week (`2025-W04`) or month (`2025-01`)
+    pub period: Option<String>,
+    pub deployments: i64,
+    pub calls: i64,
+    pub gas_used: i64,
+    /// Total fee paid, in wei
+    pub fee: String,
+    /// Native currency the fee was paid in
+    pub currency_symbol: String,
+    pub currency_decimals: u8,
+    /// Approximate value of the fee in USD, at each transaction's daily
+    /// price; zero on test chains, `None` while a price is missing
+    pub fee_usd: Option<f64>,
+}
+
+/// Overview of the registry for the dashboard home
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegistryStats {
+    pub contracts: i64,
+    /// Current deployments, archived ones aside
+    pub deployments: i64,
+    /// Calls recorded in the last 24 hours
+    pub calls_24h: i64,
+    /// Calls recorded in the last 7 days
+    pub calls_7d: i64,
+    /// Transactions still waiting for a receipt
+    pub pending_txs: i64,
+    /// Transactions that failed or reverted
+    pub failed_txs: i64,
+    /// Every network, by name, including those without deployments
+    pub networks: Vec<NetworkStats>,
+    /// Latest deployments and calls, newest first
+    pub recent_activity: Vec<Activity>,
+}
+
+/// Deployments on one network
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NetworkStats {
+    pub network: String,
+    pub chain_id: ChainId,
+    /// Current deployments, archived ones aside
+    pub deployments: i64,
+    /// When the latest deployment on the network was recorded
+    pub last_deployed_at: Option<String>,
+}
+
+/// What an [`Activity`] entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ActivityKind {
+    Deployment,
+    Call,
+}
+
+/// A deployment or call in the recent activity feed
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Activity {
+    pub kind: ActivityKind,
+    pub deployment_id: DeploymentId,
+    pub contract_name: String,
+    pub network_name: String,
+    pub version: i64,
+    /// Called function; `None` for deployments
+    pub function_name: Option<String>,
+    /// Status of a call; `None` for deployments and read calls
+    pub status: Option<TransactionStatus>,
+    pub at: String,
+}
+
+/// A native currency on a day (`YYYY-MM-DD`), the key prices are cached by
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, FromRow)]
+pub struct PriceDay {
+    pub symbol: String,
+    pub day: String,
+}
+
+/// One artifact deployed with the same arguments across several networks
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeploymentGroup {
+    pub id: DeploymentGroupId,
+    pub contract_name: String,
+    /// Hash of the creation bytecode, so a resumed group deploys the same code
+    pub bytecode_hash: BytecodeHash,
+    pub constructor_args: String, // JSON
+    /// Wei sent to the constructor
+    pub value: Option<String>,
+    pub wallet_name: String,
+    pub created_at: String,
+}
+
+/// Progress of a deployment group on one network
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeploymentGroupMember {
+    pub group_id: DeploymentGroupId,
+    pub network_id: NetworkId,
+    pub network_name: String,
+    pub chain_id: ChainId,
+    /// `pending` until the network was tried, then `success` or `failed`
+    pub status: TransactionStatus,
+    pub deployment_id: Option<DeploymentId>,
+    pub address: Option<EvmAddress>,
+    pub tx_hash: Option<TxHash>,
+    pub error_message: Option<String>,
+    pub updated_at: String,
+}
+
+/// Input for creating a deployment group, with one pending member per network
+#[derive(Debug, Clone)]
+pub struct NewDeploymentGroup {
+    pub contract_name: String,
+    pub bytecode_hash: BytecodeHash,
+    pub constructor_args: String,
+    pub value: Option<String>,
+    pub wallet_name: String,
+    pub network_ids: Vec<NetworkId>,
+}
+
+/// Fees of a network at one block, as sampled by the server
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GasSample {
+    pub id: i64,
+    pub network_id: NetworkId,
+    pub block_number: i64,
+    /// Base fee in wei; `None` on chains without EIP-1559
+    pub base_fee: Option<i64>,
+    /// `eth_gasPrice` in wei
+    pub gas_price: i64,
+    pub sampled_at: String,
+}
+
+/// Input for recording a gas sample
+#[derive(Debug, Clone)]
+pub struct NewGasSample {
+    pub network_id: NetworkId,
+    pub block_number: i64,
+    pub base_fee: Option<i64>,
+    pub gas_price: i64,
 }
 
 /// Input for creating a new call history record
@@ -181,10 +671,430 @@ pub struct NewCallHistory {
 #[derive(Debug, Clone)]
 pub struct CallHistoryUpdate {
     pub result: Option<String>,
-    pub tx_hash: Option<String>,
+    pub tx_hash: Option<TxHash>,
     pub block_number: Option<i64>,
     pub gas_used: Option<i64>,
     pub gas_price: Option<String>,
     pub status: TransactionStatus,
     pub error_message: Option<String>,
 }
+
+/// A mined transaction recorded in the registry, as checked for reorgs
+#[derive(Debug, Clone, FromRow)]
+pub struct MinedRecord {
+    pub kind: RecordKind,
+    /// ID of the deployment or call history entry
+    pub id: i64,
+    pub tx_hash: TxHash,
+    pub block_number: i64,
+    /// `None` until the block is first checked
+    pub block_hash: Option<String>,
+    pub reorged_at: Option<String>,
+}
+
+/// A proxy upgrade, recorded in a single transaction: the new implementation
+/// as a deployment, and the upgrade transaction in the proxy's call history
+#[derive(Debug, Clone)]
+pub struct NewUpgrade {
+    pub network_id: NetworkId,
+    pub implementation: NewContractDeployment,
+    pub call: NewCallHistory,
+    pub outcome: CallHistoryUpdate,
+}
+
+/// Webhook subscription
+///
+/// Unset filters match anything. `event_name` only matches event
+/// notifications and `tx_status` only call notifications, so setting one of
+/// them narrows the webhook to that trigger.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Webhook {
+    pub id: WebhookId,
+    pub url: String,
+    /// Key for the payload signature; only shown when the webhook is created
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub network: Option<String>,
+    pub contract: Option<String>,
+    pub event_name: Option<String>,
+    pub tx_status: Option<TransactionStatus>,
+    pub created_at: String,
+}
+
+impl Webhook {
+    /// Whether a notification with these attributes should be sent here
+    pub fn matches(
+        &self,
+        trigger: WebhookTrigger,
+        network: &str,
+        contract: &str,
+        event_name: Option<&str>,
+        tx_status: Option<TransactionStatus>,
+    ) -> bool {
+        let trigger_ok = match trigger {
+            WebhookTrigger::Event => {
+                self.tx_status.is_none()
+                    && self
+                        .event_name
+                        .as_deref()
+                        .is_none_or(|e| Some(e) == event_name)
+            }
+            WebhookTrigger::Call => {
+                self.event_name.is_none() && self.tx_status.is_none_or(|s| Some(s) == tx_status)
+            }
+        };
+
+        trigger_ok
+            && self.network.as_deref().is_none_or(|n| n == network)
+            && self.contract.as_deref().is_none_or(|c| c == contract)
+    }
+}
+
+/// Input for registering a webhook
+#[derive(Debug, Clone)]
+pub struct NewWebhook {
+    pub url: String,
+    pub secret: String,
+    pub network: Option<String>,
+    pub contract: Option<String>,
+    pub event_name: Option<String>,
+    pub tx_status: Option<TransactionStatus>,
+}
+
+/// One notification to one webhook, with the outcome of its attempts
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: WebhookId,
+    pub trigger: WebhookTrigger,
+    pub payload: String, // JSON
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    /// HTTP status of the last attempt, if the endpoint answered
+    pub response_status: Option<i64>,
+    pub error_message: Option<String>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+}
+
+/// Input for queueing a webhook delivery
+#[derive(Debug, Clone)]
+pub struct NewWebhookDelivery {
+    pub webhook_id: WebhookId,
+    pub trigger: WebhookTrigger,
+    pub payload: String,
+}
+
+/// Outcome of a delivery attempt
+#[derive(Debug, Clone)]
+pub struct WebhookDeliveryUpdate {
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    pub response_status: Option<i64>,
+    pub error_message: Option<String>,
+}
+
+/// Background work queued for the server's workers
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Job {
+    pub id: i64,
+    /// What the job does, which decides how `payload` is read
+    pub kind: String,
+    pub payload: String, // JSON
+    pub state: JobState,
+    /// Attempts started so far
+    pub attempts: i64,
+    pub max_attempts: i64,
+    /// When a queued job may run, later than `created_at` after a failure
+    pub run_after: String,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    pub created_at: String,
+    /// When the last attempt started
+    pub started_at: Option<String>,
+    /// When the job succeeded or failed for good
+    pub finished_at: Option<String>,
+}
+
+/// Input for queueing a job
+#[derive(Debug, Clone)]
+pub struct NewJob {
+    pub kind: String,
+    pub payload: String,
+    pub max_attempts: i64,
+}
+
+/// A cached ENS lookup
+///
+/// `input` is the normalized name for forward lookups and the lowercase
+/// address for reverse ones.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EnsRecord {
+    pub chain_id: ChainId,
+    pub lookup: EnsLookup,
+    pub input: String,
+    /// Resolved address or name; `None` when nothing is set
+    pub result: Option<String>,
+    pub resolved_at: String,
+}
+
+/// Input for caching an ENS lookup
+#[derive(Debug, Clone)]
+pub struct NewEnsRecord {
+    pub chain_id: ChainId,
+    pub lookup: EnsLookup,
+    pub input: String,
+    pub result: Option<String>,
+}
+
+/// Name, symbol and supply read from a deployed token contract
+///
+/// Getters the contract doesn't implement, such as `decimals` on ERC-721 or
+/// `name` on most ERC-1155 tokens, are left `None`.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TokenMetadata {
+    pub standard: TokenStandard,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<i64>,
+    /// Decimal string, since supplies overflow SQLite integers
+    pub total_supply: Option<String>,
+    pub fetched_at: String,
+}
+
+/// Input for storing a deployment's token metadata
+#[derive(Debug, Clone)]
+pub struct NewTokenMetadata {
+    pub deployment_id: DeploymentId,
+    pub standard: TokenStandard,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<i64>,
+    pub total_supply: Option<String>,
+}
+
+/// A token accepted by the API server
+///
+/// Only a hash of the secret is stored; the secret itself is shown once,
+/// when the token is created.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiToken {
+    pub id: ApiTokenId,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub role: ApiRole,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// A deploy or send on a protected network, held until a second token
+/// approves it
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Approval {
+    pub id: ApprovalId,
+    pub kind: ApprovalKind,
+    pub network_id: NetworkId,
+    pub network_name: String,
+    /// Deployment a send targets
+    pub deployment_id: Option<DeploymentId>,
+    /// One line describing what will happen, e.g. `Token.mint(address,uint256)`
+    pub summary: String,
+    /// The original request body, replayed on approval
+    pub request: String, // JSON
+    pub status: ApprovalStatus,
+    /// Name of the token that made the request, unless since revoked
+    pub requested_by: Option<String>,
+    /// ID of the token that made the request, kept after it is revoked;
+    /// unknown for requests whose token was revoked before IDs were kept
+    #[serde(skip_serializing)]
+    pub requester_token_id: Option<ApiTokenId>,
+    /// Newest token when the request was made; tokens created since can't
+    /// approve it
+    #[serde(skip_serializing)]
+    pub newest_token_id: Option<ApiTokenId>,
+    /// Name of the token that approved or rejected it
+    pub decided_by: Option<String>,
+    /// Response of the executed request
+    pub result: Option<String>, // JSON
+    pub error_message: Option<String>,
+    pub created_at: String,
+    pub expires_at: String,
+    pub decided_at: Option<String>,
+}
+
+/// Input for holding a request for approval
+#[derive(Debug, Clone)]
+pub struct NewApproval {
+    pub kind: ApprovalKind,
+    pub network_id: NetworkId,
+    pub deployment_id: Option<DeploymentId>,
+    pub summary: String,
+    pub request: String,
+    pub requested_by: ApiTokenId,
+    /// How long the request can wait for a decision
+    pub ttl: std::time::Duration,
+}
+
+/// A reusable deployment recipe: an artifact and the names its constructor
+/// arguments are set by
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Template {
+    pub id: i64,
+    pub name: String,
+    pub artifact: String,
+    /// Constructor parameters, in order
+    #[sqlx(json)]
+    pub params: Vec<TemplateParam>,
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A constructor parameter of a template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TemplateParam {
+    pub name: String,
+    /// Solidity type, e.g. `uint256`
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// Value used when a deployment doesn't set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// Input for saving a template
+#[derive(Debug, Clone)]
+pub struct NewTemplate {
+    pub name: String,
+    pub artifact: String,
+    pub params: Vec<TemplateParam>,
+    pub description: Option<String>,
+}
+
+/// One run of a batch plan: calls sent in order from one wallet
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Batch {
+    pub id: i64,
+    /// Plan file, as given on the command line
+    pub plan: String,
+    pub network_name: String,
+    pub wallet_name: Option<String>,
+    /// Steps in the plan, including any the run didn't reach
+    pub steps: i64,
+    /// `pending` while running, then `success` or `failed`
+    pub status: TransactionStatus,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// Input for recording a batch run
+#[derive(Debug, Clone)]
+pub struct NewBatch {
+    pub plan: String,
+    pub network_id: NetworkId,
+    pub wallet_id: Option<WalletId>,
+    pub steps: i64,
+}
+
+/// A POST sent with an `Idempotency-Key`, and its response once it finished
+#[derive(Debug, Clone, FromRow)]
+pub struct IdempotencyRecord {
+    /// Hash of the method, path and body the key was first sent with
+    pub fingerprint: String,
+    /// HTTP status of the response; `None` while the request is running
+    pub status: Option<i64>,
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+}
+
+/// A function signature known for a 4-byte selector
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KnownSelector {
+    /// `0x` and 8 lowercase hex digits
+    pub selector: String,
+    /// Canonical signature, e.g. `transfer(address,uint256)`
+    pub signature: String,
+    pub source: SelectorSource,
+    pub added_at: String,
+}
+
+/// A change recorded in the audit log
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: AuditAction,
+    /// What changed, e.g. the network's name
+    pub target: String,
+    pub source: AuditSource,
+    /// API token name, or local user for the CLI, when known
+    pub actor: Option<String>,
+    pub detail: Option<String>, // JSON
+    pub created_at: String,
+}
+
+/// Input for recording a change in the audit log
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub action: AuditAction,
+    pub target: String,
+    pub source: AuditSource,
+    pub actor: Option<String>,
+    pub detail: Option<String>,
+}
+
+/// An EIP-712 payload signed with a stored wallet
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SignatureRecord {
+    pub id: i64,
+    /// Name of the wallet that signed, unless since removed
+    pub wallet_name: Option<String>,
+    /// Address of the signing wallet
+    pub signer: EvmAddress,
+    /// Struct type the message is an instance of, e.g. `Permit`
+    pub primary_type: String,
+    pub domain: String, // JSON
+    /// The typed data as signed: types, primary type, domain and message
+    pub payload: String, // JSON
+    /// EIP-712 hash the signature is over
+    pub digest: String,
+    pub signature: String,
+    /// Name of the API token that asked for the signature, unless since
+    /// revoked; `None` from the CLI
+    pub signed_by: Option<String>,
+    pub created_at: String,
+}
+
+/// Input for recording a signature
+#[derive(Debug, Clone)]
+pub struct NewSignature {
+    pub wallet_id: WalletId,
+    pub signer: EvmAddress,
+    pub primary_type: String,
+    pub domain: String,
+    pub payload: String,
+    pub digest: String,
+    pub signature: String,
+    pub signed_by: Option<ApiTokenId>,
+}
+
+/// Input for recording a selector's signature
+#[derive(Debug, Clone)]
+pub struct NewSelector {
+    pub selector: String,
+    pub signature: String,
+    pub source: SelectorSource,
+}
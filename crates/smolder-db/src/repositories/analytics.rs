@@ -0,0 +1,318 @@
+//! AnalyticsRepository implementation for SQLite
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use smolder_core::{chains, Result};
+use sqlx::{FromRow, QueryBuilder};
+
+use crate::models::{Activity, CostStat, GasStat, NetworkStats, PriceDay, RegistryStats};
+use crate::traits::{AnalyticsRepository, CostFilter, GasInterval, GasStatsFilter};
+use crate::Database;
+
+/// Gas spent by successful calls and by deployments, one row per transaction
+const GAS_SPENT: &str = r#"
+    SELECT n.name AS network, c.name AS contract, d.version AS version,
+           h.function_name AS function, h.created_at AS spent_at, h.gas_used AS gas
+    FROM call_history h
+    JOIN deployments d ON h.deployment_id = d.id
+    JOIN contracts c ON d.contract_id = c.id
+    JOIN networks n ON d.network_id = n.id
+    WHERE h.status = 'success' AND h.gas_used IS NOT NULL
+    UNION ALL
+    SELECT n.name, c.name, d.version, 'constructor', d.deployed_at, d.gas_used
+    FROM deployments d
+    JOIN contracts c ON d.contract_id = c.id
+    JOIN networks n ON d.network_id = n.id
+    WHERE d.gas_used IS NOT NULL
+"#;
+
+/// Fees paid for deployments and calls, one row per transaction, with the
+/// USD price of the currency on the day when cached
+const FEES_PAID: &str = r#"
+    SELECT f.*, p.usd FROM (
+        SELECT n.name AS network, n.chain_id, n.currency_symbol, n.currency_decimals,
+               c.name AS contract, COALESCE(w.name, d.deployer) AS wallet,
+               TRUE AS deployment, d.deployed_at AS spent_at, d.gas_used AS gas, d.fee
+        FROM deployments d
+        JOIN contracts c ON d.contract_id = c.id
+        JOIN networks n ON d.network_id = n.id
+        LEFT JOIN wallets w ON d.wallet_id = w.id
+        WHERE d.fee IS NOT NULL
+        UNION ALL
+        SELECT n.name, n.chain_id, n.currency_symbol, n.currency_decimals, c.name, w.name,
+               FALSE, h.created_at, h.gas_used, h.fee
+        FROM call_history h
+        JOIN deployments d ON h.deployment_id = d.id
+        JOIN contracts c ON d.contract_id = c.id
+        JOIN networks n ON d.network_id = n.id
+        LEFT JOIN wallets w ON h.wallet_id = w.id
+        WHERE h.fee IS NOT NULL
+    ) f
+    LEFT JOIN token_prices p ON p.symbol = f.currency_symbol AND p.day = date(f.spent_at)
+"#;
+
+/// A transaction's fee, before [`costs`](AnalyticsRepository::costs) sums
+/// them: SQLite's integers can't hold large totals in wei
+#[derive(FromRow)]
+pub(crate) struct FeePaid {
+    pub network: String,
+    pub chain_id: i64,
+    pub currency_symbol: String,
+    pub currency_decimals: u8,
+    pub contract: String,
+    pub wallet: Option<String>,
+    pub period: Option<String>,
+    pub deployment: bool,
+    pub gas: Option<i64>,
+    pub fee: String,
+    pub usd: Option<f64>,
+}
+
+impl FeePaid {
+    /// Value of the fee in USD; test chains' currencies are worth nothing
+    fn usd(&self, fee: u128) -> Option<f64> {
+        if chains::is_testnet(self.chain_id as u64) {
+            return Some(0.0);
+        }
+        Some(fee as f64 / 10f64.powi(self.currency_decimals.into()) * self.usd?)
+    }
+}
+
+/// `strftime` expression bucketing `spent_at` by `interval`
+fn period(interval: Option<GasInterval>) -> String {
+    match interval {
+        Some(interval) => format!("strftime('{}', spent_at)", interval.as_strftime()),
+        None => "NULL".to_string(),
+    }
+}
+
+/// Select the fees matching `filter`, with `columns` from [`FEES_PAID`]
+fn fees_paid<'a>(columns: &str, filter: &'a CostFilter) -> QueryBuilder<'a, sqlx::Sqlite> {
+    let mut builder = QueryBuilder::new(format!(
+        "SELECT {} FROM ({}) WHERE 1 = 1",
+        columns, FEES_PAID
+    ));
+    if let Some(ref network) = filter.network {
+        builder.push(" AND network = ").push_bind(network);
+    }
+    if let Some(ref contract) = filter.contract {
+        builder.push(" AND contract = ").push_bind(contract);
+    }
+    if let Some(ref wallet) = filter.wallet {
+        builder
+            .push(" AND wallet = ")
+            .push_bind(wallet)
+            .push(" COLLATE NOCASE");
+    }
+    if let Some(ref since) = filter.since {
+        builder.push(" AND spent_at >= ").push_bind(since);
+    }
+    if let Some(ref until) = filter.until {
+        builder.push(" AND spent_at <= ").push_bind(until);
+    }
+    builder
+}
+
+/// Sum fees per period, network, contract and wallet, in wei and USD
+pub(crate) fn sum_costs(rows: Vec<FeePaid>) -> Vec<CostStat> {
+    let mut totals: BTreeMap<_, (CostStat, u128)> = BTreeMap::new();
+    for row in rows {
+        let key = (
+            row.period.clone(),
+            row.network.clone(),
+            row.contract.clone(),
+            row.wallet.clone(),
+        );
+        let fee: u128 = row.fee.parse().unwrap_or_default();
+        let usd = row.usd(fee);
+        let (stat, total) = totals.entry(key).or_insert_with(|| {
+            (
+                CostStat {
+                    network: row.network,
+                    contract: row.contract,
+                    wallet: row.wallet,
+                    period: row.period,
+                    deployments: 0,
+                    calls: 0,
+                    gas_used: 0,
+                    fee: String::new(),
+                    currency_symbol: row.currency_symbol,
+                    currency_decimals: row.currency_decimals,
+                    fee_usd: Some(0.0),
+                },
+                0,
+            )
+        });
+        match row.deployment {
+            true => stat.deployments += 1,
+            false => stat.calls += 1,
+        }
+        stat.gas_used += row.gas.unwrap_or_default();
+        *total = total.saturating_add(fee);
+        stat.fee_usd = stat.fee_usd.zip(usd).map(|(sum, usd)| sum + usd);
+    }
+
+    totals
+        .into_values()
+        .map(|(stat, fee)| CostStat {
+            fee: fee.to_string(),
+            ..stat
+        })
+        .collect()
+}
+
+#[async_trait]
+impl AnalyticsRepository for Database {
+    async fn gas_stats(&self, filter: &GasStatsFilter) -> Result<Vec<GasStat>> {
+        let period = period(filter.interval);
+
+        let mut builder: QueryBuilder<sqlx::Sqlite> = QueryBuilder::new(format!(
+            r#"
+            SELECT network, contract, version, function, {} AS period,
+                   COUNT(*) AS calls,
+                   CAST(ROUND(AVG(gas)) AS INTEGER) AS avg_gas,
+                   MIN(gas) AS min_gas,
+                   MAX(gas) AS max_gas,
+                   SUM(gas) AS total_gas
+            FROM ({}) g
+            WHERE 1 = 1
+            "#,
+            period, GAS_SPENT
+        ));
+        if let Some(ref network) = filter.network {
+            builder.push(" AND network = ").push_bind(network);
+        }
+        if let Some(ref contract) = filter.contract {
+            builder.push(" AND contract = ").push_bind(contract);
+        }
+        if let Some(ref since) = filter.since {
+            builder.push(" AND spent_at >= ").push_bind(since);
+        }
+        if let Some(ref until) = filter.until {
+            builder.push(" AND spent_at <= ").push_bind(until);
+        }
+        builder.push(
+            " GROUP BY network, contract, version, function, period \
+             ORDER BY network, contract, function, version, period",
+        );
+
+        let stats = builder
+            .build_query_as::<GasStat>()
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(stats)
+    }
+
+    async fn costs(&self, filter: &CostFilter) -> Result<Vec<CostStat>> {
+        let columns = format!("*, {} AS period", period(filter.interval));
+        let rows = fees_paid(&columns, filter)
+            .build_query_as::<FeePaid>()
+            .fetch_all(&self.reader)
+            .await?;
+
+        Ok(sum_costs(rows))
+    }
+
+    async fn stats(&self, recent: u32) -> Result<RegistryStats> {
+        let (contracts, deployments, calls_24h, calls_7d, pending_txs, failed_txs): (
+            i64,
+            i64,
+            i64,
+            i64,
+            i64,
+            i64,
+        ) = sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM contracts),
+                (SELECT COUNT(*) FROM deployments
+                 WHERE is_current = TRUE AND archived_at IS NULL),
+                (SELECT COUNT(*) FROM call_history WHERE created_at >= datetime('now', '-1 day')),
+                (SELECT COUNT(*) FROM call_history WHERE created_at >= datetime('now', '-7 days')),
+                (SELECT COUNT(*) FROM call_history WHERE status = 'pending'),
+                (SELECT COUNT(*) FROM call_history WHERE status IN ('failed', 'reverted'))
+            "#,
+        )
+        .fetch_one(&self.reader)
+        .await?;
+
+        let networks = sqlx::query_as::<_, NetworkStats>(
+            r#"
+            SELECT n.name AS network, n.chain_id,
+                   COUNT(d.id) FILTER (WHERE d.is_current = TRUE AND d.archived_at IS NULL)
+                       AS deployments,
+                   MAX(d.deployed_at) AS last_deployed_at
+            FROM networks n
+            LEFT JOIN deployments d ON d.network_id = n.id
+            GROUP BY n.id
+            ORDER BY n.name
+            "#,
+        )
+        .fetch_all(&self.reader)
+        .await?;
+
+        // Each side is cut to `recent` by primary key first, so the feed
+        // never sorts the whole history
+        let recent_activity = sqlx::query_as::<_, Activity>(
+            r#"
+            SELECT * FROM (
+                SELECT 'deployment' AS kind, d.id AS deployment_id, c.name AS contract_name,
+                       n.name AS network_name, d.version, NULL AS function_name,
+                       NULL AS status, d.deployed_at AS at
+                FROM deployments d
+                JOIN contracts c ON d.contract_id = c.id
+                JOIN networks n ON d.network_id = n.id
+                ORDER BY d.id DESC
+                LIMIT ?1
+            )
+            UNION ALL
+            SELECT * FROM (
+                SELECT 'call', d.id, c.name, n.name, d.version, h.function_name, h.status,
+                       h.created_at
+                FROM call_history h
+                JOIN deployments d ON h.deployment_id = d.id
+                JOIN contracts c ON d.contract_id = c.id
+                JOIN networks n ON d.network_id = n.id
+                ORDER BY h.id DESC
+                LIMIT ?1
+            )
+            ORDER BY at DESC, kind, deployment_id DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(recent)
+        .fetch_all(&self.reader)
+        .await?;
+
+        Ok(RegistryStats {
+            contracts,
+            deployments,
+            calls_24h,
+            calls_7d,
+            pending_txs,
+            failed_txs,
+            networks,
+            recent_activity,
+        })
+    }
+
+    async fn unpriced_fees(&self, filter: &CostFilter) -> Result<Vec<PriceDay>> {
+        let mut builder = fees_paid(
+            "DISTINCT chain_id, currency_symbol AS symbol, date(spent_at) AS day",
+            filter,
+        );
+        builder.push(" AND usd IS NULL");
+        let rows: Vec<(i64, String, String)> =
+            builder.build_query_as().fetch_all(&self.reader).await?;
+
+        let mut unpriced: Vec<PriceDay> = rows
+            .into_iter()
+            .filter(|(chain_id, _, _)| !chains::is_testnet(*chain_id as u64))
+            .map(|(_, symbol, day)| PriceDay { symbol, day })
+            .collect();
+        unpriced.sort();
+        unpriced.dedup();
+        Ok(unpriced)
+    }
+}
@@ -0,0 +1,74 @@
+//! ApiTokenRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::{ApiRole, Result};
+
+use crate::models::ApiToken;
+use crate::traits::ApiTokenRepository;
+use crate::Database;
+
+#[async_trait]
+impl ApiTokenRepository for Database {
+    async fn list(&self) -> Result<Vec<ApiToken>> {
+        let tokens = sqlx::query_as::<_, ApiToken>("SELECT * FROM api_tokens ORDER BY name")
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(tokens)
+    }
+
+    async fn count(&self) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM api_tokens")
+            .fetch_one(&self.reader)
+            .await?;
+        Ok(count)
+    }
+
+    async fn create(&self, name: &str, token_hash: &str, role: ApiRole) -> Result<ApiToken> {
+        let token = sqlx::query_as::<_, ApiToken>(
+            "INSERT INTO api_tokens (name, token_hash, role) VALUES (?, ?, ?) RETURNING *",
+        )
+        .bind(name)
+        .bind(token_hash)
+        .bind(role)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(token)
+    }
+
+    async fn set_role(&self, name: &str, role: ApiRole) -> Result<()> {
+        let result = sqlx::query("UPDATE api_tokens SET role = ? WHERE name = ?")
+            .bind(role)
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::ApiTokenNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn authenticate(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let token = sqlx::query_as::<_, ApiToken>(
+            r#"
+            UPDATE api_tokens SET last_used_at = CURRENT_TIMESTAMP
+            WHERE token_hash = ?
+            RETURNING *
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.writer)
+        .await?;
+        Ok(token)
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE name = ?")
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::ApiTokenNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! ApprovalRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::{ApiTokenId, ApprovalId, ApprovalStatus, Result};
+
+use crate::models::{Approval, NewApproval};
+use crate::traits::ApprovalRepository;
+use crate::Database;
+
+const APPROVAL_VIEW: &str = r#"
+    SELECT a.id, a.kind, a.network_id, n.name AS network_name, a.deployment_id, a.summary,
+           a.request, a.status, r.name AS requested_by, a.requester_token_id,
+           a.newest_token_id, d.name AS decided_by, a.result, a.error_message, a.created_at,
+           a.expires_at, a.decided_at
+    FROM approvals a
+    JOIN networks n ON a.network_id = n.id
+    LEFT JOIN api_tokens r ON a.requested_by = r.id
+    LEFT JOIN api_tokens d ON a.decided_by = d.id
+"#;
+
+#[async_trait]
+impl ApprovalRepository for Database {
+    async fn create(&self, approval: &NewApproval) -> Result<Approval> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO approvals
+                (kind, network_id, deployment_id, summary, request, requested_by,
+                 requester_token_id, newest_token_id, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, (SELECT MAX(id) FROM api_tokens), datetime('now', ?))
+            RETURNING id
+            "#,
+        )
+        .bind(approval.kind)
+        .bind(approval.network_id)
+        .bind(approval.deployment_id)
+        .bind(&approval.summary)
+        .bind(&approval.request)
+        .bind(approval.requested_by)
+        .bind(approval.requested_by)
+        .bind(format!("+{} seconds", approval.ttl.as_secs()))
+        .fetch_one(&self.writer)
+        .await?;
+
+        ApprovalRepository::get(self, ApprovalId(id))
+            .await?
+            .ok_or(smolder_core::Error::ApprovalNotFound(ApprovalId(id)))
+    }
+
+    async fn get(&self, id: ApprovalId) -> Result<Option<Approval>> {
+        let approval = sqlx::query_as::<_, Approval>(&format!("{} WHERE a.id = ?", APPROVAL_VIEW))
+            .bind(id)
+            .fetch_optional(&self.reader)
+            .await?;
+        Ok(approval)
+    }
+
+    async fn list(&self, status: Option<ApprovalStatus>) -> Result<Vec<Approval>> {
+        let approvals = sqlx::query_as::<_, Approval>(&format!(
+            "{} WHERE ? IS NULL OR a.status = ? ORDER BY a.id DESC",
+            APPROVAL_VIEW
+        ))
+        .bind(status)
+        .bind(status)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(approvals)
+    }
+
+    async fn expire(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE approvals SET status = 'expired'
+            WHERE status = 'pending' AND expires_at <= datetime('now')
+            "#,
+        )
+        .execute(&self.writer)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn decide(
+        &self,
+        id: ApprovalId,
+        status: ApprovalStatus,
+        decided_by: ApiTokenId,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE approvals SET status = ?, decided_by = ?, decided_at = CURRENT_TIMESTAMP
+            WHERE id = ? AND status = 'pending' AND expires_at > datetime('now')
+            "#,
+        )
+        .bind(status)
+        .bind(decided_by)
+        .bind(id)
+        .execute(&self.writer)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn record_result(
+        &self,
+        id: ApprovalId,
+        result: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        let status = match error_message {
+            Some(_) => ApprovalStatus::Failed,
+            None => ApprovalStatus::Approved,
+        };
+        sqlx::query("UPDATE approvals SET status = ?, result = ?, error_message = ? WHERE id = ?")
+            .bind(status)
+            .bind(result)
+            .bind(error_message)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+}
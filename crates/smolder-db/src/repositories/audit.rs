@@ -0,0 +1,38 @@
+//! AuditRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use crate::models::{AuditEntry, NewAuditEntry};
+use crate::traits::AuditRepository;
+use crate::Database;
+
+#[async_trait]
+impl AuditRepository for Database {
+    async fn record(&self, entry: &NewAuditEntry) -> Result<AuditEntry> {
+        let recorded = sqlx::query_as::<_, AuditEntry>(
+            r#"
+            INSERT INTO audit_log (action, target, source, actor, detail)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(entry.action)
+        .bind(&entry.target)
+        .bind(entry.source)
+        .bind(&entry.actor)
+        .bind(&entry.detail)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(recorded)
+    }
+
+    async fn list(&self, limit: i64) -> Result<Vec<AuditEntry>> {
+        let entries =
+            sqlx::query_as::<_, AuditEntry>("SELECT * FROM audit_log ORDER BY id DESC LIMIT ?")
+                .bind(limit)
+                .fetch_all(&self.reader)
+                .await?;
+        Ok(entries)
+    }
+}
@@ -0,0 +1,80 @@
+//! BatchRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::{Result, TransactionStatus};
+
+use super::call_history::CALL_HISTORY_VIEW_SELECT;
+use crate::models::{Batch, CallHistoryView, NewBatch};
+use crate::traits::BatchRepository;
+use crate::Database;
+
+const BATCH_SELECT: &str = r#"
+    SELECT b.id, b.plan, n.name AS network_name, w.name AS wallet_name, b.steps, b.status,
+        b.created_at, b.finished_at
+    FROM batches b
+    JOIN networks n ON b.network_id = n.id
+    LEFT JOIN wallets w ON b.wallet_id = w.id
+"#;
+
+#[async_trait]
+impl BatchRepository for Database {
+    async fn create(&self, batch: &NewBatch) -> Result<Batch> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO batches (plan, network_id, wallet_id, steps)
+            VALUES (?, ?, ?, ?)
+            RETURNING id
+            "#,
+        )
+        .bind(&batch.plan)
+        .bind(batch.network_id)
+        .bind(batch.wallet_id)
+        .bind(batch.steps)
+        .fetch_one(&self.writer)
+        .await?;
+
+        BatchRepository::get(self, id)
+            .await?
+            .ok_or_else(|| smolder_core::Error::Validation("Failed to create batch".into()))
+    }
+
+    async fn add_call(&self, batch_id: i64, position: i64, call_history_id: i64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO batch_calls (batch_id, position, call_history_id) VALUES (?, ?, ?)",
+        )
+        .bind(batch_id)
+        .bind(position)
+        .bind(call_history_id)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+
+    async fn finish(&self, batch_id: i64, status: TransactionStatus) -> Result<()> {
+        sqlx::query("UPDATE batches SET status = ?, finished_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(status)
+            .bind(batch_id)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<Batch>> {
+        let batch = sqlx::query_as::<_, Batch>(&format!("{} WHERE b.id = ?", BATCH_SELECT))
+            .bind(id)
+            .fetch_optional(&self.reader)
+            .await?;
+        Ok(batch)
+    }
+
+    async fn list_calls(&self, batch_id: i64) -> Result<Vec<CallHistoryView>> {
+        let calls = sqlx::query_as::<_, CallHistoryView>(&format!(
+            "{} JOIN batch_calls bc ON bc.call_history_id = h.id WHERE bc.batch_id = ? ORDER BY bc.position",
+            CALL_HISTORY_VIEW_SELECT
+        ))
+        .bind(batch_id)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(calls)
+    }
+}
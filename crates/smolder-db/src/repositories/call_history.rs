@@ -1,20 +1,23 @@
 //! CallHistoryRepository implementation for SQLite
 
 use async_trait::async_trait;
-use smolder_core::Result;
+use smolder_core::{Result, TxHash};
 use sqlx::QueryBuilder;
 
+use super::{fee, push_pagination};
 use crate::models::{CallHistory, CallHistoryUpdate, CallHistoryView, NewCallHistory};
-use crate::traits::{CallHistoryFilter, CallHistoryRepository};
+use crate::traits::{CallHistoryFilter, CallHistoryRepository, HistoryRetention};
 use crate::Database;
 
-const CALL_HISTORY_VIEW_SELECT: &str = r#"
+pub(super) const CALL_HISTORY_VIEW_SELECT: &str = r#"
     SELECT
         h.id, h.deployment_id, c.name as contract_name, n.name as network_name,
         d.address as contract_address, w.name as wallet_name, h.function_name,
         h.function_signature, h.input_params, h.call_type, h.result, h.tx_hash,
-        h.block_number, h.gas_used, h.gas_price, h.status, h.error_message,
-        h.created_at, h.confirmed_at
+        h.block_number, h.gas_used, h.gas_price, h.fee, h.status, h.error_message,
+        h.created_at, h.confirmed_at, h.simulation_url, h.sender, h.reorged_at, h.finality,
+        d.address,
+        n.explorer_url
     FROM call_history h
     JOIN deployments d ON h.deployment_id = d.id
     JOIN contracts c ON d.contract_id = c.id
@@ -22,57 +25,107 @@ const CALL_HISTORY_VIEW_SELECT: &str = r#"
     LEFT JOIN wallets w ON h.wallet_id = w.id
 "#;
 
-#[async_trait]
-impl CallHistoryRepository for Database {
-    async fn list(&self, filter: CallHistoryFilter) -> Result<Vec<CallHistory>> {
-        let mut builder: QueryBuilder<sqlx::Sqlite> =
-            QueryBuilder::new("SELECT * FROM call_history");
+const CALL_HISTORY_COUNT_SELECT: &str = r#"
+    SELECT COUNT(*)
+    FROM call_history h
+    JOIN deployments d ON h.deployment_id = d.id
+    JOIN contracts c ON d.contract_id = c.id
+    JOIN networks n ON d.network_id = n.id
+    LEFT JOIN wallets w ON h.wallet_id = w.id
+"#;
 
-        if let Some(id) = filter.deployment_id {
-            builder.push(" WHERE deployment_id = ");
-            builder.push_bind(id.0);
-        }
+/// Append the WHERE clause for a call history filter
+///
+/// Expects the joined aliases used by [`CALL_HISTORY_VIEW_SELECT`].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, sqlx::Sqlite>, filter: &'a CallHistoryFilter) {
+    let mut separator = " WHERE ";
+    let mut next = |builder: &mut QueryBuilder<'a, sqlx::Sqlite>| {
+        builder.push(separator);
+        separator = " AND ";
+    };
 
-        builder.push(" ORDER BY created_at DESC");
+    if let Some(id) = filter.deployment_id {
+        next(builder);
+        builder.push("h.deployment_id = ").push_bind(id.0);
+    }
+    if let Some(ref contract) = filter.contract {
+        next(builder);
+        builder.push("c.name = ").push_bind(contract);
+    }
+    if let Some(ref network) = filter.network {
+        next(builder);
+        builder.push("n.name = ").push_bind(network);
+    }
+    if let Some(ref wallet) = filter.wallet {
+        next(builder);
+        builder.push("w.name = ").push_bind(wallet);
+    }
+    if let Some(status) = filter.status {
+        next(builder);
+        builder.push("h.status = ").push_bind(status);
+    }
+    if let Some(ref since) = filter.since {
+        next(builder);
+        builder.push("h.created_at >= ").push_bind(since);
+    }
+    if let Some(ref until) = filter.until {
+        next(builder);
+        builder.push("h.created_at <= ").push_bind(until);
+    }
+}
 
-        if let Some(limit) = filter.limit {
-            builder.push(" LIMIT ");
-            builder.push_bind(limit as i64);
-        }
+#[async_trait]
+impl CallHistoryRepository for Database {
+    async fn list(&self, filter: CallHistoryFilter) -> Result<Vec<CallHistory>> {
+        let mut builder: QueryBuilder<sqlx::Sqlite> = QueryBuilder::new(
+            r#"
+            SELECT h.*
+            FROM call_history h
+            JOIN deployments d ON h.deployment_id = d.id
+            JOIN contracts c ON d.contract_id = c.id
+            JOIN networks n ON d.network_id = n.id
+            LEFT JOIN wallets w ON h.wallet_id = w.id
+            "#,
+        );
+        push_filters(&mut builder, &filter);
+        builder.push(" ORDER BY h.created_at DESC, h.id DESC");
+        push_pagination(&mut builder, filter.limit, filter.offset);
 
         let history = builder
             .build_query_as::<CallHistory>()
-            .fetch_all(&self.pool)
+            .fetch_all(&self.reader)
             .await?;
         Ok(history)
     }
 
     async fn list_views(&self, filter: CallHistoryFilter) -> Result<Vec<CallHistoryView>> {
         let mut builder: QueryBuilder<sqlx::Sqlite> = QueryBuilder::new(CALL_HISTORY_VIEW_SELECT);
-
-        if let Some(id) = filter.deployment_id {
-            builder.push(" WHERE h.deployment_id = ");
-            builder.push_bind(id.0);
-        }
-
-        builder.push(" ORDER BY h.created_at DESC");
-
-        if let Some(limit) = filter.limit {
-            builder.push(" LIMIT ");
-            builder.push_bind(limit as i64);
-        }
+        push_filters(&mut builder, &filter);
+        builder.push(" ORDER BY h.created_at DESC, h.id DESC");
+        push_pagination(&mut builder, filter.limit, filter.offset);
 
         let history = builder
             .build_query_as::<CallHistoryView>()
-            .fetch_all(&self.pool)
+            .fetch_all(&self.reader)
             .await?;
         Ok(history)
     }
 
+    async fn count(&self, filter: &CallHistoryFilter) -> Result<i64> {
+        let mut builder: QueryBuilder<sqlx::Sqlite> = QueryBuilder::new(CALL_HISTORY_COUNT_SELECT);
+        push_filters(&mut builder, filter);
+
+        let count = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.reader)
+            .await?;
+        Ok(count)
+    }
+
     async fn get_by_id(&self, id: i64) -> Result<Option<CallHistory>> {
         let entry = sqlx::query_as::<_, CallHistory>("SELECT * FROM call_history WHERE id = ?")
             .bind(id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.reader)
             .await?;
         Ok(entry)
     }
@@ -91,7 +144,7 @@ impl CallHistoryRepository for Database {
         .bind(&entry.function_signature)
         .bind(&entry.input_params)
         .bind(entry.call_type)
-        .fetch_one(&self.pool)
+        .fetch_one(&self.writer)
         .await?;
 
         CallHistoryRepository::get_by_id(self, id)
@@ -108,6 +161,7 @@ impl CallHistoryRepository for Database {
                 block_number = ?,
                 gas_used = ?,
                 gas_price = ?,
+                fee = ?,
                 status = ?,
                 error_message = ?,
                 confirmed_at = datetime('now')
@@ -115,15 +169,104 @@ impl CallHistoryRepository for Database {
             "#,
         )
         .bind(&update.result)
-        .bind(&update.tx_hash)
+        .bind(update.tx_hash)
         .bind(update.block_number)
         .bind(update.gas_used)
         .bind(&update.gas_price)
+        .bind(fee(update.gas_used, update.gas_price.as_deref()))
         .bind(update.status)
         .bind(&update.error_message)
         .bind(id)
-        .execute(&self.pool)
+        .execute(&self.writer)
         .await?;
         Ok(())
     }
+
+    async fn set_simulation_url(&self, id: i64, url: &str) -> Result<()> {
+        sqlx::query("UPDATE call_history SET simulation_url = ? WHERE id = ?")
+            .bind(url)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_by_tx_hash(&self, tx_hash: &TxHash) -> Result<Option<CallHistory>> {
+        let entry = sqlx::query_as::<_, CallHistory>(
+            "SELECT * FROM call_history WHERE tx_hash = ? ORDER BY id LIMIT 1",
+        )
+        .bind(tx_hash)
+        .fetch_optional(&self.reader)
+        .await?;
+        Ok(entry)
+    }
+
+    async fn import_external(
+        &self,
+        entry: &NewCallHistory,
+        sender: &str,
+        outcome: &CallHistoryUpdate,
+    ) -> Result<CallHistory> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO call_history (
+                deployment_id, wallet_id, function_name, function_signature, input_params,
+                call_type, sender, result, tx_hash, block_number, gas_used, gas_price, fee,
+                status, error_message, confirmed_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
+            RETURNING id
+            "#,
+        )
+        .bind(entry.deployment_id)
+        .bind(entry.wallet_id)
+        .bind(&entry.function_name)
+        .bind(&entry.function_signature)
+        .bind(&entry.input_params)
+        .bind(entry.call_type)
+        .bind(sender)
+        .bind(&outcome.result)
+        .bind(outcome.tx_hash)
+        .bind(outcome.block_number)
+        .bind(outcome.gas_used)
+        .bind(&outcome.gas_price)
+        .bind(fee(outcome.gas_used, outcome.gas_price.as_deref()))
+        .bind(outcome.status)
+        .bind(&outcome.error_message)
+        .fetch_one(&self.writer)
+        .await?;
+
+        CallHistoryRepository::get_by_id(self, id)
+            .await?
+            .ok_or_else(|| smolder_core::Error::Validation("Failed to import call history".into()))
+    }
+
+    async fn count_prunable(&self, retention: &HistoryRetention) -> Result<u64> {
+        let count: i64 = prunable("SELECT COUNT(*) FROM call_history", retention)
+            .build_query_scalar()
+            .fetch_one(&self.reader)
+            .await?;
+        Ok(count as u64)
+    }
+
+    async fn prune(&self, retention: &HistoryRetention) -> Result<u64> {
+        let result = prunable("DELETE FROM call_history", retention)
+            .build()
+            .execute(&self.writer)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// `statement` restricted to the entries `retention` lets go
+fn prunable<'a>(statement: &str, retention: &HistoryRetention) -> QueryBuilder<'a, sqlx::Sqlite> {
+    let mut builder = QueryBuilder::new(statement);
+    builder
+        .push(" WHERE created_at < datetime('now', ")
+        .push_bind(format!("-{} seconds", retention.max_age.as_secs()))
+        .push(") AND (status IS NULL OR status != 'pending')");
+    if retention.keep_failed {
+        builder.push(" AND (status IS NULL OR status NOT IN ('failed', 'reverted'))");
+    }
+    builder
 }
@@ -1,59 +1,208 @@
 //! ContractRepository implementation for SQLite
 
 use async_trait::async_trait;
-use smolder_core::{ContractId, Result};
+use smolder_core::{ContractId, Error, Result};
 
+use sqlx::types::Json;
+use sqlx::QueryBuilder;
+
+use super::push_pagination;
 use crate::models::{Contract, NewContract};
-use crate::traits::ContractRepository;
+use crate::traits::{ContractFilter, ContractRepository, ContractSort};
 use crate::Database;
 
+/// Insert a contract, or refresh the source path and ABI of an existing one
+/// with the same name and bytecode hash. A known storage layout is kept when
+/// the new record has none. Returns the contract ID.
+pub(super) const UPSERT_CONTRACT: &str = r#"
+    INSERT INTO contracts (name, source_path, abi, bytecode_hash, storage_layout, compiler)
+    VALUES (?, ?, ?, ?, ?, ?)
+    ON CONFLICT(name, bytecode_hash) DO UPDATE SET
+        source_path = excluded.source_path,
+        abi = excluded.abi,
+        storage_layout = COALESCE(excluded.storage_layout, contracts.storage_layout),
+        compiler = COALESCE(excluded.compiler, contracts.compiler)
+    RETURNING id
+"#;
+
+const CONTRACT_SELECT: &str = r#"
+    SELECT c.*, p.name IS NOT NULL AS pinned, l.label, l.description
+    FROM contracts c
+    LEFT JOIN pinned_contracts p ON p.name = c.name
+    LEFT JOIN contract_labels l ON l.name = c.name
+"#;
+
+/// Append the WHERE clause for a contract filter
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, sqlx::Sqlite>, filter: &'a ContractFilter) {
+    if let Some(ref name) = filter.name {
+        builder
+            .push(" WHERE c.name LIKE '%' || ")
+            .push_bind(name)
+            .push(" || '%'");
+    }
+}
+
 #[async_trait]
 impl ContractRepository for Database {
-    async fn list(&self) -> Result<Vec<Contract>> {
-        let contracts = sqlx::query_as::<_, Contract>("SELECT * FROM contracts ORDER BY name")
-            .fetch_all(&self.pool)
+    async fn list(&self, filter: ContractFilter) -> Result<Vec<Contract>> {
+        let mut builder: QueryBuilder<sqlx::Sqlite> = QueryBuilder::new(CONTRACT_SELECT);
+        push_filters(&mut builder, &filter);
+
+        let column = match filter.sort.unwrap_or(ContractSort::Name) {
+            ContractSort::Name => "c.name",
+            ContractSort::CreatedAt => "c.created_at",
+        };
+        // Pinned contracts come first, whatever the sort
+        builder.push(format_args!(
+            " ORDER BY p.name IS NOT NULL DESC, {} {}, c.id {}",
+            column,
+            filter.order.as_sql(),
+            filter.order.as_sql()
+        ));
+
+        push_pagination(&mut builder, filter.limit, filter.offset);
+
+        let contracts = builder
+            .build_query_as::<Contract>()
+            .fetch_all(&self.reader)
             .await?;
         Ok(contracts)
     }
 
+    async fn count(&self, filter: &ContractFilter) -> Result<i64> {
+        let mut builder: QueryBuilder<sqlx::Sqlite> =
+            QueryBuilder::new("SELECT COUNT(*) FROM contracts c");
+        push_filters(&mut builder, filter);
+
+        let count = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.reader)
+            .await?;
+        Ok(count)
+    }
+
     async fn get_by_name(&self, name: &str) -> Result<Option<Contract>> {
-        let contract = sqlx::query_as::<_, Contract>(
-            "SELECT * FROM contracts WHERE name = ? ORDER BY created_at DESC LIMIT 1",
-        )
+        let contract = sqlx::query_as::<_, Contract>(&format!(
+            "{} WHERE c.name = ? ORDER BY c.created_at DESC LIMIT 1",
+            CONTRACT_SELECT
+        ))
         .bind(name)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&self.reader)
         .await?;
         Ok(contract)
     }
 
     async fn get_by_id(&self, id: ContractId) -> Result<Option<Contract>> {
-        let contract = sqlx::query_as::<_, Contract>("SELECT * FROM contracts WHERE id = ?")
-            .bind(id.0)
-            .fetch_optional(&self.pool)
-            .await?;
+        let contract =
+            sqlx::query_as::<_, Contract>(&format!("{} WHERE c.id = ?", CONTRACT_SELECT))
+                .bind(id.0)
+                .fetch_optional(&self.reader)
+                .await?;
         Ok(contract)
     }
 
     async fn upsert(&self, contract: &NewContract) -> Result<Contract> {
-        let id = sqlx::query_scalar::<_, i64>(
+        let id = sqlx::query_scalar::<_, i64>(UPSERT_CONTRACT)
+            .bind(&contract.name)
+            .bind(&contract.source_path)
+            .bind(&contract.abi)
+            .bind(contract.bytecode_hash)
+            .bind(&contract.storage_layout)
+            .bind(contract.compiler.as_ref().map(Json))
+            .fetch_one(&self.writer)
+            .await?;
+
+        ContractRepository::get_by_id(self, ContractId(id))
+            .await?
+            .ok_or_else(|| smolder_core::Error::ContractNotFound(contract.name.clone()))
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let mut tx = self.writer.begin().await?;
+
+        sqlx::query(
             r#"
-            INSERT INTO contracts (name, source_path, abi, bytecode_hash)
-            VALUES (?, ?, ?, ?)
-            ON CONFLICT(name, bytecode_hash) DO UPDATE SET
-                source_path = excluded.source_path,
-                abi = excluded.abi
-            RETURNING id
+            DELETE FROM call_history WHERE deployment_id IN (
+                SELECT d.id FROM deployments d
+                JOIN contracts c ON d.contract_id = c.id
+                WHERE c.name = ?
+            )
             "#,
         )
-        .bind(&contract.name)
-        .bind(&contract.source_path)
-        .bind(&contract.abi)
-        .bind(&contract.bytecode_hash)
-        .fetch_one(&self.pool)
+        .bind(name)
+        .execute(&mut *tx)
         .await?;
 
-        ContractRepository::get_by_id(self, ContractId(id))
-            .await?
-            .ok_or_else(|| smolder_core::Error::ContractNotFound(contract.name.clone()))
+        sqlx::query(
+            "DELETE FROM deployments WHERE contract_id IN (SELECT id FROM contracts WHERE name = ?)",
+        )
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM contracts WHERE name = ?")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM pinned_contracts WHERE name = ?")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM contract_labels WHERE name = ?")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn set_pinned(&self, name: &str, pinned: bool) -> Result<()> {
+        if ContractRepository::get_by_name(self, name).await?.is_none() {
+            return Err(Error::ContractNotFound(name.to_string()));
+        }
+        let query = match pinned {
+            true => "INSERT INTO pinned_contracts (name) VALUES (?) ON CONFLICT(name) DO NOTHING",
+            false => "DELETE FROM pinned_contracts WHERE name = ?",
+        };
+        sqlx::query(query).bind(name).execute(&self.writer).await?;
+        Ok(())
+    }
+
+    async fn set_label(
+        &self,
+        name: &str,
+        label: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()> {
+        if ContractRepository::get_by_name(self, name).await?.is_none() {
+            return Err(Error::ContractNotFound(name.to_string()));
+        }
+        match (label, description) {
+            (None, None) => {
+                sqlx::query("DELETE FROM contract_labels WHERE name = ?")
+                    .bind(name)
+                    .execute(&self.writer)
+                    .await?;
+            }
+            _ => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO contract_labels (name, label, description) VALUES (?, ?, ?)
+                    ON CONFLICT(name) DO UPDATE SET
+                        label = excluded.label,
+                        description = excluded.description
+                    "#,
+                )
+                .bind(name)
+                .bind(label)
+                .bind(description)
+                .execute(&self.writer)
+                .await?;
+            }
+        }
+        Ok(())
     }
 }
@@ -1,51 +1,203 @@
 //! DeploymentRepository implementation for SQLite
 
+use std::collections::{HashMap, HashSet};
+
 use async_trait::async_trait;
-use smolder_core::{DeploymentId, Result};
+use futures::stream::{BoxStream, StreamExt};
+use smolder_core::{
+    BytecodeHash, ContractId, DeploymentId, EvmAddress, NetworkId, Result, StoredTxHash, TxHash,
+};
+use sqlx::types::Json;
 use sqlx::QueryBuilder;
 
-use crate::models::{Deployment, DeploymentView, NewDeployment};
-use crate::traits::{DeploymentFilter, DeploymentRepository};
+use super::contract::UPSERT_CONTRACT;
+use super::{fee, push_pagination};
+use crate::models::{
+    CallHistory, Deployment, DeploymentView, ImportStatus, NewContractDeployment, NewDeployment,
+    NewUpgrade,
+};
+use crate::traits::CallHistoryRepository;
+use crate::traits::{DeploymentFilter, DeploymentRepository, DeploymentSort};
 use crate::Database;
 
-const DEPLOYMENT_VIEW_SELECT: &str = r#"
+/// Promote the latest remaining non-archived version of a contract on a network
+/// to current. Used after the current deployment is deleted or archived.
+const PROMOTE_LATEST_VERSION: &str = r#"
+    UPDATE deployments SET is_current = TRUE
+    WHERE id = (
+        SELECT id FROM deployments
+        WHERE contract_id = ? AND network_id = ? AND archived_at IS NULL
+        ORDER BY version DESC
+        LIMIT 1
+    )
+"#;
+
+/// Rows per multi-row INSERT in batch imports, keeping the bound parameters
+/// well under SQLite's limit
+const IMPORT_CHUNK_SIZE: usize = 500;
+
+pub(super) const DEPLOYMENT_VIEW_SELECT: &str = r#"
     SELECT
         d.id, c.name as contract_name, n.name as network_name, n.chain_id,
-        d.address, d.deployer, d.tx_hash, d.block_number, d.version,
-        d.deployed_at, d.is_current, c.abi
+        d.address, d.deployer, w.name as wallet_name, d.tx_hash, d.block_number, d.version,
+        d.deployed_at, d.is_current, d.archived_at, d.initcode_size, d.runtime_size,
+        d.gas_used, d.gas_price, d.fee, d.created_by_call_id, d.reorged_at, d.finality,
+        (d.pinned OR pc.name IS NOT NULL) AS pinned,
+        COALESCE(d.label, cl.label) AS label,
+        COALESCE(d.description, cl.description) AS description, c.abi, n.explorer_url,
+        CASE WHEN m.deployment_id IS NULL THEN NULL ELSE json_object(
+            'standard', m.standard, 'name', m.name, 'symbol', m.symbol,
+            'decimals', m.decimals, 'total_supply', m.total_supply,
+            'fetched_at', m.fetched_at
+        ) END AS token
     FROM deployments d
     JOIN contracts c ON d.contract_id = c.id
     JOIN networks n ON d.network_id = n.id
+    LEFT JOIN wallets w ON d.wallet_id = w.id
+    LEFT JOIN pinned_contracts pc ON pc.name = c.name
+    LEFT JOIN contract_labels cl ON cl.name = c.name
+    LEFT JOIN contract_metadata m ON m.deployment_id = d.id
 "#;
 
+/// Current deployments for export; binds whether to include ABIs, then an
+/// optional network name
+const EXPORT_SELECT: &str = r#"
+    SELECT
+        d.id, c.name as contract_name, n.name as network_name, n.chain_id,
+        d.address, d.deployer, w.name as wallet_name, d.tx_hash, d.block_number, d.version,
+        d.deployed_at, d.is_current, d.archived_at, d.initcode_size, d.runtime_size,
+        d.gas_used, d.gas_price, d.fee, d.created_by_call_id, d.reorged_at, d.finality,
+        (d.pinned OR pc.name IS NOT NULL) AS pinned,
+        COALESCE(d.label, cl.label) AS label,
+        COALESCE(d.description, cl.description) AS description, CASE WHEN ?1 THEN c.abi ELSE '' END AS abi, n.explorer_url,
+        CASE WHEN m.deployment_id IS NULL THEN NULL ELSE json_object(
+            'standard', m.standard, 'name', m.name, 'symbol', m.symbol,
+            'decimals', m.decimals, 'total_supply', m.total_supply,
+            'fetched_at', m.fetched_at
+        ) END AS token
+    FROM deployments d
+    JOIN contracts c ON d.contract_id = c.id
+    JOIN networks n ON d.network_id = n.id
+    LEFT JOIN wallets w ON d.wallet_id = w.id
+    LEFT JOIN pinned_contracts pc ON pc.name = c.name
+    LEFT JOIN contract_labels cl ON cl.name = c.name
+    LEFT JOIN contract_metadata m ON m.deployment_id = d.id
+    WHERE d.is_current = TRUE AND d.archived_at IS NULL
+        AND (?2 IS NULL OR n.name = ?2)
+    ORDER BY n.name, c.name, d.id
+"#;
+
+const DEPLOYMENT_COUNT_SELECT: &str = r#"
+    SELECT COUNT(*)
+    FROM deployments d
+    JOIN contracts c ON d.contract_id = c.id
+    JOIN networks n ON d.network_id = n.id
+    LEFT JOIN wallets w ON d.wallet_id = w.id
+"#;
+
+/// Append the WHERE clause for a deployment filter
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, sqlx::Sqlite>, filter: &'a DeploymentFilter) {
+    let mut separator = " WHERE ";
+    let mut next = |builder: &mut QueryBuilder<'a, sqlx::Sqlite>| {
+        builder.push(separator);
+        separator = " AND ";
+    };
+
+    if let Some(ref network) = filter.network {
+        next(builder);
+        builder.push("n.name = ").push_bind(network);
+    }
+    if let Some(ref contract) = filter.contract {
+        next(builder);
+        builder.push("c.name = ").push_bind(contract);
+    }
+    if let Some(version) = filter.version {
+        next(builder);
+        builder.push("d.version = ").push_bind(version);
+    }
+    if let Some(ref deployer) = filter.deployer {
+        next(builder);
+        builder.push("d.deployer = ").push_bind(deployer);
+        builder.push(" COLLATE NOCASE");
+    }
+    if let Some(ref wallet) = filter.wallet {
+        next(builder);
+        builder.push("w.name = ").push_bind(wallet);
+    }
+    if let Some(ref after) = filter.deployed_after {
+        next(builder);
+        builder.push("d.deployed_at >= ").push_bind(after);
+    }
+    if let Some(ref before) = filter.deployed_before {
+        next(builder);
+        builder.push("d.deployed_at <= ").push_bind(before);
+    }
+    if let Some(call_id) = filter.created_by_call_id {
+        next(builder);
+        builder.push("d.created_by_call_id = ").push_bind(call_id);
+    }
+    if filter.current_only {
+        next(builder);
+        builder.push("d.is_current = TRUE");
+    }
+    if !filter.include_archived {
+        next(builder);
+        builder.push("d.archived_at IS NULL");
+    }
+}
+
 #[async_trait]
 impl DeploymentRepository for Database {
     async fn list(&self, filter: DeploymentFilter) -> Result<Vec<DeploymentView>> {
         let mut builder: QueryBuilder<sqlx::Sqlite> = QueryBuilder::new(DEPLOYMENT_VIEW_SELECT);
+        push_filters(&mut builder, &filter);
 
-        let mut has_where = false;
-        if let Some(ref network) = filter.network {
-            builder.push(" WHERE n.name = ");
-            builder.push_bind(network);
-            has_where = true;
-        }
-        if filter.current_only {
-            builder.push(if has_where { " AND " } else { " WHERE " });
-            builder.push("d.is_current = TRUE");
+        // Pinned deployments come first, whatever the sort
+        builder.push(" ORDER BY (d.pinned OR pc.name IS NOT NULL) DESC, ");
+        match filter.sort {
+            Some(sort) => {
+                let column = match sort {
+                    DeploymentSort::Network => "n.name",
+                    DeploymentSort::Contract => "c.name",
+                    DeploymentSort::Version => "d.version",
+                    DeploymentSort::BlockNumber => "d.block_number",
+                    DeploymentSort::DeployedAt => "d.deployed_at",
+                };
+                builder.push(format_args!(
+                    "{} {}, d.id {}",
+                    column,
+                    filter.order.as_sql(),
+                    filter.order.as_sql()
+                ));
+            }
+            None => {
+                builder.push("n.name, c.name");
+                if !filter.current_only {
+                    builder.push(", d.version DESC");
+                }
+            }
         }
 
-        builder.push(" ORDER BY n.name, c.name");
-        if !filter.current_only {
-            builder.push(", d.version DESC");
-        }
+        push_pagination(&mut builder, filter.limit, filter.offset);
 
         let deployments = builder
             .build_query_as::<DeploymentView>()
-            .fetch_all(&self.pool)
+            .fetch_all(&self.reader)
             .await?;
         Ok(deployments)
     }
 
+    async fn count(&self, filter: &DeploymentFilter) -> Result<i64> {
+        let mut builder: QueryBuilder<sqlx::Sqlite> = QueryBuilder::new(DEPLOYMENT_COUNT_SELECT);
+        push_filters(&mut builder, filter);
+
+        let count = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.reader)
+            .await?;
+        Ok(count)
+    }
+
     async fn get_current(&self, contract: &str, network: &str) -> Result<Option<Deployment>> {
         let deployment = sqlx::query_as::<_, Deployment>(
             r#"
@@ -58,7 +210,7 @@ impl DeploymentRepository for Database {
         )
         .bind(contract)
         .bind(network)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&self.reader)
         .await?;
         Ok(deployment)
     }
@@ -66,7 +218,7 @@ impl DeploymentRepository for Database {
     async fn get_by_id(&self, id: DeploymentId) -> Result<Option<Deployment>> {
         let deployment = sqlx::query_as::<_, Deployment>("SELECT * FROM deployments WHERE id = ?")
             .bind(id.0)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.reader)
             .await?;
         Ok(deployment)
     }
@@ -78,20 +230,46 @@ impl DeploymentRepository for Database {
 
         let deployment = builder
             .build_query_as::<DeploymentView>()
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.reader)
             .await?;
         Ok(deployment)
     }
 
-    async fn exists_by_tx_hash(&self, tx_hash: &str) -> Result<bool> {
+    async fn exists_by_tx_hash(&self, tx_hash: &TxHash) -> Result<bool> {
         let exists: bool =
             sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM deployments WHERE tx_hash = ?)")
                 .bind(tx_hash)
-                .fetch_one(&self.pool)
+                .fetch_one(&self.reader)
                 .await?;
         Ok(exists)
     }
 
+    async fn exists_by_address(&self, network_id: NetworkId, address: &str) -> Result<bool> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM deployments WHERE network_id = ? AND address = ? COLLATE NOCASE)",
+        )
+        .bind(network_id)
+        .bind(address)
+        .fetch_one(&self.reader)
+        .await?;
+        Ok(exists)
+    }
+
+    async fn get_by_address(
+        &self,
+        network_id: NetworkId,
+        address: &str,
+    ) -> Result<Option<Deployment>> {
+        let deployment = sqlx::query_as::<_, Deployment>(
+            "SELECT * FROM deployments WHERE network_id = ? AND address = ? COLLATE NOCASE ORDER BY id DESC LIMIT 1",
+        )
+        .bind(network_id)
+        .bind(address)
+        .fetch_optional(&self.reader)
+        .await?;
+        Ok(deployment)
+    }
+
     async fn create(&self, deployment: &NewDeployment) -> Result<Deployment> {
         // Mark previous deployments as not current
         sqlx::query(
@@ -99,7 +277,7 @@ impl DeploymentRepository for Database {
         )
         .bind(deployment.contract_id)
         .bind(deployment.network_id)
-        .execute(&self.pool)
+        .execute(&self.writer)
         .await?;
 
         // Get next version number
@@ -108,7 +286,7 @@ impl DeploymentRepository for Database {
         )
         .bind(deployment.contract_id)
         .bind(deployment.network_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&self.writer)
         .await?;
 
         let next_version = max_version.unwrap_or(0) + 1;
@@ -116,20 +294,25 @@ impl DeploymentRepository for Database {
         // Insert new deployment
         let id = sqlx::query_scalar::<_, i64>(
             r#"
-            INSERT INTO deployments (contract_id, network_id, address, deployer, tx_hash, block_number, constructor_args, version, is_current)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, TRUE)
+            INSERT INTO deployments (contract_id, network_id, address, deployer, tx_hash, block_number, constructor_args, initcode_size, runtime_size, gas_used, gas_price, fee, version, is_current)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, TRUE)
             RETURNING id
             "#,
         )
         .bind(deployment.contract_id)
         .bind(deployment.network_id)
-        .bind(&deployment.address)
-        .bind(&deployment.deployer)
-        .bind(&deployment.tx_hash)
+        .bind(deployment.address)
+        .bind(EvmAddress::normalize(&deployment.deployer))
+        .bind(StoredTxHash::from(deployment.tx_hash))
         .bind(deployment.block_number)
         .bind(&deployment.constructor_args)
+        .bind(deployment.initcode_size)
+        .bind(deployment.runtime_size)
+        .bind(deployment.gas_used)
+        .bind(&deployment.gas_price)
+        .bind(fee(deployment.gas_used, deployment.gas_price.as_deref()))
         .bind(next_version)
-        .fetch_one(&self.pool)
+        .fetch_one(&self.writer)
         .await?;
 
         DeploymentRepository::get_by_id(self, DeploymentId(id))
@@ -137,12 +320,245 @@ impl DeploymentRepository for Database {
             .ok_or_else(|| smolder_core::Error::DeploymentNotFoundById(DeploymentId(id)))
     }
 
-    async fn list_for_export(&self, network: Option<&str>) -> Result<Vec<DeploymentView>> {
-        let filter = match network {
-            Some(n) => DeploymentFilter::for_network(n),
-            None => DeploymentFilter::current(),
-        };
-        DeploymentRepository::list(self, filter).await
+    async fn import_batch(
+        &self,
+        network_id: NetworkId,
+        deployments: &[NewContractDeployment],
+    ) -> Result<Vec<ImportStatus>> {
+        let mut tx = self.writer.begin().await?;
+
+        let mut statuses = Vec::with_capacity(deployments.len());
+        let mut contract_ids: HashMap<(&str, BytecodeHash), ContractId> = HashMap::new();
+        // Last version assigned to each contract in this batch
+        let mut versions: HashMap<ContractId, i64> = HashMap::new();
+        let mut seen_addresses = HashSet::new();
+        let mut rows = Vec::new();
+
+        for deployment in deployments {
+            if !seen_addresses.insert(deployment.address) {
+                statuses.push(ImportStatus::Skipped);
+                continue;
+            }
+
+            let existing: Option<(i64, Option<i64>, Option<String>, Option<i64>)> = sqlx::query_as(
+                "SELECT id, block_number, constructor_args, gas_used FROM deployments WHERE network_id = ? AND address = ? COLLATE NOCASE",
+            )
+            .bind(network_id)
+            .bind(deployment.address)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some((id, block_number, constructor_args, gas_used)) = existing {
+                let fills_block = block_number.is_none() && deployment.block_number.is_some();
+                let fills_args =
+                    constructor_args.is_none() && deployment.constructor_args.is_some();
+                let fills_gas = gas_used.is_none() && deployment.gas_used.is_some();
+
+                if fills_block || fills_args || fills_gas {
+                    sqlx::query(
+                        "UPDATE deployments SET block_number = COALESCE(block_number, ?), constructor_args = COALESCE(constructor_args, ?), initcode_size = COALESCE(initcode_size, ?), runtime_size = COALESCE(runtime_size, ?), gas_used = COALESCE(gas_used, ?), gas_price = COALESCE(gas_price, ?), fee = COALESCE(fee, ?) WHERE id = ?",
+                    )
+                    .bind(deployment.block_number)
+                    .bind(&deployment.constructor_args)
+                    .bind(deployment.initcode_size)
+                    .bind(deployment.runtime_size)
+                    .bind(deployment.gas_used)
+                    .bind(&deployment.gas_price)
+                    .bind(fee(deployment.gas_used, deployment.gas_price.as_deref()))
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                    statuses.push(ImportStatus::Updated);
+                } else {
+                    statuses.push(ImportStatus::Skipped);
+                }
+                continue;
+            }
+
+            let contract = &deployment.contract;
+            let key = (contract.name.as_str(), contract.bytecode_hash);
+            let contract_id = match contract_ids.get(&key) {
+                Some(id) => *id,
+                None => {
+                    let id = sqlx::query_scalar::<_, i64>(UPSERT_CONTRACT)
+                        .bind(&contract.name)
+                        .bind(&contract.source_path)
+                        .bind(&contract.abi)
+                        .bind(contract.bytecode_hash)
+                        .bind(&contract.storage_layout)
+                        .bind(contract.compiler.as_ref().map(Json))
+                        .fetch_one(&mut *tx)
+                        .await?;
+                    contract_ids.insert(key, ContractId(id));
+                    ContractId(id)
+                }
+            };
+
+            let version = match versions.get(&contract_id) {
+                Some(last) => last + 1,
+                None => {
+                    let max_version: Option<i64> = sqlx::query_scalar(
+                        "SELECT MAX(version) FROM deployments WHERE contract_id = ? AND network_id = ?",
+                    )
+                    .bind(contract_id)
+                    .bind(network_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    max_version.unwrap_or(0) + 1
+                }
+            };
+            versions.insert(contract_id, version);
+
+            rows.push((contract_id, deployment, version));
+            statuses.push(ImportStatus::Added);
+        }
+
+        if !rows.is_empty() {
+            // The newest imported version of each contract becomes current
+            let mut builder =
+                QueryBuilder::new("UPDATE deployments SET is_current = FALSE WHERE network_id = ");
+            builder.push_bind(network_id).push(" AND contract_id IN (");
+            let mut separated = builder.separated(", ");
+            for contract_id in versions.keys() {
+                separated.push_bind(*contract_id);
+            }
+            builder.push(")");
+            builder.build().execute(&mut *tx).await?;
+
+            for chunk in rows.chunks(IMPORT_CHUNK_SIZE) {
+                let mut builder = QueryBuilder::new(
+                    "INSERT INTO deployments (contract_id, network_id, address, deployer, tx_hash, block_number, constructor_args, initcode_size, runtime_size, gas_used, gas_price, fee, version, is_current) ",
+                );
+                builder.push_values(chunk, |mut row, &(contract_id, deployment, version)| {
+                    row.push_bind(contract_id)
+                        .push_bind(network_id)
+                        .push_bind(deployment.address)
+                        .push_bind(EvmAddress::normalize(&deployment.deployer))
+                        .push_bind(StoredTxHash::from(deployment.tx_hash))
+                        .push_bind(deployment.block_number)
+                        .push_bind(&deployment.constructor_args)
+                        .push_bind(deployment.initcode_size)
+                        .push_bind(deployment.runtime_size)
+                        .push_bind(deployment.gas_used)
+                        .push_bind(&deployment.gas_price)
+                        .push_bind(fee(deployment.gas_used, deployment.gas_price.as_deref()))
+                        .push_bind(version)
+                        .push_bind(versions[&contract_id] == version);
+                });
+                builder.build().execute(&mut *tx).await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(statuses)
+    }
+
+    async fn record_upgrade(&self, upgrade: &NewUpgrade) -> Result<(Deployment, CallHistory)> {
+        let implementation = &upgrade.implementation;
+        let contract = &implementation.contract;
+        let mut tx = self.writer.begin().await?;
+
+        let contract_id = sqlx::query_scalar::<_, i64>(UPSERT_CONTRACT)
+            .bind(&contract.name)
+            .bind(&contract.source_path)
+            .bind(&contract.abi)
+            .bind(contract.bytecode_hash)
+            .bind(&contract.storage_layout)
+            .bind(contract.compiler.as_ref().map(Json))
+            .fetch_one(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "UPDATE deployments SET is_current = FALSE WHERE contract_id = ? AND network_id = ?",
+        )
+        .bind(contract_id)
+        .bind(upgrade.network_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let max_version: Option<i64> = sqlx::query_scalar(
+            "SELECT MAX(version) FROM deployments WHERE contract_id = ? AND network_id = ?",
+        )
+        .bind(contract_id)
+        .bind(upgrade.network_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let deployment_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO deployments (contract_id, network_id, address, deployer, tx_hash, block_number, constructor_args, initcode_size, runtime_size, gas_used, gas_price, fee, version, is_current)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, TRUE)
+            RETURNING id
+            "#,
+        )
+        .bind(contract_id)
+        .bind(upgrade.network_id)
+        .bind(implementation.address)
+        .bind(EvmAddress::normalize(&implementation.deployer))
+        .bind(StoredTxHash::from(implementation.tx_hash))
+        .bind(implementation.block_number)
+        .bind(&implementation.constructor_args)
+        .bind(implementation.initcode_size)
+        .bind(implementation.runtime_size)
+        .bind(implementation.gas_used)
+        .bind(&implementation.gas_price)
+        .bind(fee(
+            implementation.gas_used,
+            implementation.gas_price.as_deref(),
+        ))
+        .bind(max_version.unwrap_or(0) + 1)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let (call, outcome) = (&upgrade.call, &upgrade.outcome);
+        let history_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO call_history (deployment_id, wallet_id, function_name, function_signature, input_params, call_type, result, tx_hash, block_number, gas_used, gas_price, fee, status, error_message, confirmed_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
+            RETURNING id
+            "#,
+        )
+        .bind(call.deployment_id)
+        .bind(call.wallet_id)
+        .bind(&call.function_name)
+        .bind(&call.function_signature)
+        .bind(&call.input_params)
+        .bind(call.call_type)
+        .bind(&outcome.result)
+        .bind(outcome.tx_hash)
+        .bind(outcome.block_number)
+        .bind(outcome.gas_used)
+        .bind(&outcome.gas_price)
+        .bind(fee(outcome.gas_used, outcome.gas_price.as_deref()))
+        .bind(outcome.status)
+        .bind(&outcome.error_message)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let deployment = DeploymentRepository::get_by_id(self, DeploymentId(deployment_id))
+            .await?
+            .ok_or(smolder_core::Error::DeploymentNotFoundById(DeploymentId(
+                deployment_id,
+            )))?;
+        let history = CallHistoryRepository::get_by_id(self, history_id)
+            .await?
+            .ok_or_else(|| smolder_core::Error::Validation("Failed to record upgrade".into()))?;
+        Ok((deployment, history))
+    }
+
+    fn stream_for_export<'a>(
+        &'a self,
+        network: Option<&'a str>,
+        with_abi: bool,
+    ) -> BoxStream<'a, Result<DeploymentView>> {
+        sqlx::query_as::<_, DeploymentView>(EXPORT_SELECT)
+            .bind(with_abi)
+            .bind(network)
+            .fetch(&self.reader)
+            .map(|row| row.map_err(Into::into))
+            .boxed()
     }
 
     async fn list_versions(&self, contract: &str, network: &str) -> Result<Vec<DeploymentView>> {
@@ -154,8 +570,105 @@ impl DeploymentRepository for Database {
         let deployments = sqlx::query_as::<_, DeploymentView>(&query)
             .bind(contract)
             .bind(network)
-            .fetch_all(&self.pool)
+            .fetch_all(&self.reader)
             .await?;
         Ok(deployments)
     }
+
+    async fn delete(&self, id: DeploymentId) -> Result<()> {
+        let deployment = DeploymentRepository::get_by_id(self, id)
+            .await?
+            .ok_or(smolder_core::Error::DeploymentNotFoundById(id))?;
+
+        let mut tx = self.writer.begin().await?;
+
+        sqlx::query("DELETE FROM call_history WHERE deployment_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM deployments WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        if deployment.is_current {
+            sqlx::query(PROMOTE_LATEST_VERSION)
+                .bind(deployment.contract_id)
+                .bind(deployment.network_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn archive(&self, id: DeploymentId) -> Result<()> {
+        let deployment = DeploymentRepository::get_by_id(self, id)
+            .await?
+            .ok_or(smolder_core::Error::DeploymentNotFoundById(id))?;
+
+        let mut tx = self.writer.begin().await?;
+
+        sqlx::query(
+            "UPDATE deployments SET archived_at = datetime('now'), is_current = FALSE WHERE id = ?",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        if deployment.is_current {
+            sqlx::query(PROMOTE_LATEST_VERSION)
+                .bind(deployment.contract_id)
+                .bind(deployment.network_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn set_pinned(&self, id: DeploymentId, pinned: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE deployments SET pinned = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::DeploymentNotFoundById(id));
+        }
+        Ok(())
+    }
+
+    async fn set_label(
+        &self,
+        id: DeploymentId,
+        label: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()> {
+        let result = sqlx::query("UPDATE deployments SET label = ?, description = ? WHERE id = ?")
+            .bind(label)
+            .bind(description)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::DeploymentNotFoundById(id));
+        }
+        Ok(())
+    }
+
+    async fn link_to_call(&self, id: DeploymentId, call_id: i64) -> Result<()> {
+        let result = sqlx::query("UPDATE deployments SET created_by_call_id = ? WHERE id = ?")
+            .bind(call_id)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::DeploymentNotFoundById(id));
+        }
+        Ok(())
+    }
 }
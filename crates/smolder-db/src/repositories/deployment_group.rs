@@ -0,0 +1,109 @@
+//! DeploymentGroupRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::{DeploymentGroupId, DeploymentId, NetworkId, Result, TransactionStatus};
+
+use crate::models::{DeploymentGroup, DeploymentGroupMember, NewDeploymentGroup};
+use crate::traits::DeploymentGroupRepository;
+use crate::Database;
+
+#[async_trait]
+impl DeploymentGroupRepository for Database {
+    async fn create(&self, group: &NewDeploymentGroup) -> Result<DeploymentGroup> {
+        let mut tx = self.writer.begin().await?;
+
+        let created = sqlx::query_as::<_, DeploymentGroup>(
+            r#"
+            INSERT INTO deployment_groups
+                (contract_name, bytecode_hash, constructor_args, value, wallet_name)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&group.contract_name)
+        .bind(group.bytecode_hash)
+        .bind(&group.constructor_args)
+        .bind(&group.value)
+        .bind(&group.wallet_name)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for network_id in &group.network_ids {
+            sqlx::query(
+                r#"
+                INSERT INTO deployment_group_members (group_id, network_id, status)
+                VALUES (?, ?, 'pending')
+                "#,
+            )
+            .bind(created.id)
+            .bind(network_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn get(&self, id: DeploymentGroupId) -> Result<Option<DeploymentGroup>> {
+        let group =
+            sqlx::query_as::<_, DeploymentGroup>("SELECT * FROM deployment_groups WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.reader)
+                .await?;
+        Ok(group)
+    }
+
+    async fn list(&self) -> Result<Vec<DeploymentGroup>> {
+        let groups = sqlx::query_as::<_, DeploymentGroup>(
+            "SELECT * FROM deployment_groups ORDER BY id DESC",
+        )
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(groups)
+    }
+
+    async fn list_members(&self, id: DeploymentGroupId) -> Result<Vec<DeploymentGroupMember>> {
+        let members = sqlx::query_as::<_, DeploymentGroupMember>(
+            r#"
+            SELECT m.group_id, m.network_id, n.name AS network_name, n.chain_id, m.status,
+                   m.deployment_id, d.address, NULLIF(d.tx_hash, '') AS tx_hash,
+                   m.error_message, m.updated_at
+            FROM deployment_group_members m
+            JOIN networks n ON m.network_id = n.id
+            LEFT JOIN deployments d ON m.deployment_id = d.id
+            WHERE m.group_id = ?
+            ORDER BY m.rowid
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(members)
+    }
+
+    async fn update_member(
+        &self,
+        id: DeploymentGroupId,
+        network_id: NetworkId,
+        status: TransactionStatus,
+        deployment_id: Option<DeploymentId>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE deployment_group_members
+            SET status = ?, deployment_id = ?, error_message = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE group_id = ? AND network_id = ?
+            "#,
+        )
+        .bind(status)
+        .bind(deployment_id)
+        .bind(error_message)
+        .bind(id)
+        .bind(network_id)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+}
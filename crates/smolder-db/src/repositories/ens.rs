@@ -0,0 +1,55 @@
+//! EnsCacheRepository implementation for SQLite
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::{ChainId, EnsLookup, Result};
+
+use crate::models::{EnsRecord, NewEnsRecord};
+use crate::traits::EnsCacheRepository;
+use crate::Database;
+
+#[async_trait]
+impl EnsCacheRepository for Database {
+    async fn get(
+        &self,
+        chain_id: ChainId,
+        lookup: EnsLookup,
+        input: &str,
+        max_age: Duration,
+    ) -> Result<Option<EnsRecord>> {
+        let record = sqlx::query_as::<_, EnsRecord>(
+            r#"
+            SELECT * FROM ens_cache
+            WHERE chain_id = ? AND lookup = ? AND input = ?
+                AND resolved_at >= datetime('now', ?)
+            "#,
+        )
+        .bind(chain_id)
+        .bind(lookup)
+        .bind(input)
+        .bind(format!("-{} seconds", max_age.as_secs()))
+        .fetch_optional(&self.reader)
+        .await?;
+        Ok(record)
+    }
+
+    async fn put(&self, record: &NewEnsRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO ens_cache (chain_id, lookup, input, result)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (chain_id, lookup, input) DO UPDATE SET
+                result = excluded.result,
+                resolved_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(record.chain_id)
+        .bind(record.lookup)
+        .bind(&record.input)
+        .bind(&record.result)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+}
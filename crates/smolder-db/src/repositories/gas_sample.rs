@@ -0,0 +1,63 @@
+//! GasSampleRepository implementation for SQLite
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::{NetworkId, Result};
+
+use crate::models::{GasSample, NewGasSample};
+use crate::traits::GasSampleRepository;
+use crate::Database;
+
+#[async_trait]
+impl GasSampleRepository for Database {
+    async fn record(&self, sample: &NewGasSample) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO gas_samples (network_id, block_number, base_fee, gas_price)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(sample.network_id)
+        .bind(sample.block_number)
+        .bind(sample.base_fee)
+        .bind(sample.gas_price)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        network_id: NetworkId,
+        since: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GasSample>> {
+        let samples = sqlx::query_as::<_, GasSample>(
+            r#"
+            SELECT * FROM (
+                SELECT * FROM gas_samples
+                WHERE network_id = ? AND (? IS NULL OR sampled_at >= ?)
+                ORDER BY id DESC
+                LIMIT ?
+            )
+            ORDER BY id
+            "#,
+        )
+        .bind(network_id)
+        .bind(since)
+        .bind(since)
+        .bind(limit as i64)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(samples)
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM gas_samples WHERE sampled_at < datetime('now', ?)")
+            .bind(format!("-{} seconds", max_age.as_secs()))
+            .execute(&self.writer)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
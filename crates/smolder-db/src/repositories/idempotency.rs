@@ -0,0 +1,92 @@
+//! IdempotencyRepository implementation for SQLite
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use crate::models::IdempotencyRecord;
+use crate::traits::IdempotencyRepository;
+use crate::Database;
+
+#[async_trait]
+impl IdempotencyRepository for Database {
+    async fn claim(
+        &self,
+        scope: &str,
+        key: &str,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyRecord>> {
+        let claimed = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (scope, key, fingerprint) VALUES (?, ?, ?)
+            ON CONFLICT(scope, key) DO NOTHING
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(fingerprint)
+        .execute(&self.writer)
+        .await?
+        .rows_affected()
+            > 0;
+        if claimed {
+            return Ok(None);
+        }
+
+        // Read from the writer so a key claimed a moment ago is seen
+        let record = sqlx::query_as::<_, IdempotencyRecord>(
+            r#"
+            SELECT fingerprint, status, content_type, body FROM idempotency_keys
+            WHERE scope = ? AND key = ?
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .fetch_optional(&self.writer)
+        .await?;
+        Ok(record)
+    }
+
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        status: u16,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys SET status = ?, content_type = ?, body = ?
+            WHERE scope = ? AND key = ?
+            "#,
+        )
+        .bind(status as i64)
+        .bind(content_type)
+        .bind(body)
+        .bind(scope)
+        .bind(key)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = ? AND key = ?")
+            .bind(scope)
+            .bind(key)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64> {
+        let result =
+            sqlx::query("DELETE FROM idempotency_keys WHERE created_at < datetime('now', ?)")
+                .bind(format!("-{} seconds", max_age.as_secs()))
+                .execute(&self.writer)
+                .await?;
+        Ok(result.rows_affected())
+    }
+}
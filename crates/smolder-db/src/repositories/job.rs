@@ -0,0 +1,128 @@
+//! JobRepository implementation for SQLite
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::{JobState, Result};
+use sqlx::QueryBuilder;
+
+use super::push_pagination;
+use crate::models::{Job, NewJob};
+use crate::traits::{JobFilter, JobRepository};
+use crate::Database;
+
+#[async_trait]
+impl JobRepository for Database {
+    async fn enqueue(&self, job: &NewJob) -> Result<Job> {
+        let created = sqlx::query_as::<_, Job>(
+            "INSERT INTO jobs (kind, payload, max_attempts) VALUES (?, ?, ?) RETURNING *",
+        )
+        .bind(&job.kind)
+        .bind(&job.payload)
+        .bind(job.max_attempts)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(created)
+    }
+
+    async fn claim(&self) -> Result<Option<Job>> {
+        // A single statement, so two workers can't claim the same job
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            UPDATE jobs SET
+                state = 'running',
+                attempts = attempts + 1,
+                started_at = datetime('now')
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE state = 'queued' AND run_after <= datetime('now')
+                ORDER BY run_after, id
+                LIMIT 1
+            )
+            RETURNING *
+            "#,
+        )
+        .fetch_optional(&self.writer)
+        .await?;
+        Ok(job)
+    }
+
+    async fn complete(&self, id: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE jobs SET state = 'succeeded', finished_at = datetime('now') WHERE id = ?",
+        )
+        .bind(id)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+
+    async fn fail(&self, id: i64, error: &str, retry_in: Option<Duration>) -> Result<JobState> {
+        let retry_in = retry_in.map(|delay| format!("+{} seconds", delay.as_secs()));
+        // Columns on the right-hand side hold the values before the update
+        let state = sqlx::query_scalar::<_, JobState>(
+            r#"
+            UPDATE jobs SET
+                state = CASE WHEN ?1 IS NOT NULL AND attempts < max_attempts
+                    THEN 'queued' ELSE 'failed' END,
+                run_after = CASE WHEN ?1 IS NOT NULL AND attempts < max_attempts
+                    THEN datetime('now', ?1) ELSE run_after END,
+                finished_at = CASE WHEN ?1 IS NOT NULL AND attempts < max_attempts
+                    THEN NULL ELSE datetime('now') END,
+                last_error = ?2
+            WHERE id = ?3
+            RETURNING state
+            "#,
+        )
+        .bind(retry_in)
+        .bind(error)
+        .bind(id)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(state)
+    }
+
+    async fn requeue_running(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE jobs SET state = 'queued', attempts = MAX(attempts - 1, 0)
+            WHERE state = 'running'
+            "#,
+        )
+        .execute(&self.writer)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn list(&self, filter: &JobFilter) -> Result<Vec<Job>> {
+        let mut builder: QueryBuilder<sqlx::Sqlite> =
+            QueryBuilder::new("SELECT * FROM jobs WHERE 1 = 1");
+        if let Some(state) = filter.state {
+            builder.push(" AND state = ").push_bind(state);
+        }
+        if let Some(kind) = &filter.kind {
+            builder.push(" AND kind = ").push_bind(kind.clone());
+        }
+        builder.push(" ORDER BY id DESC");
+        push_pagination(&mut builder, filter.limit, None);
+
+        let jobs = builder
+            .build_query_as::<Job>()
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(jobs)
+    }
+
+    async fn prune(&self, max_age: Duration) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM jobs
+            WHERE state IN ('succeeded', 'failed') AND finished_at < datetime('now', ?)
+            "#,
+        )
+        .bind(format!("-{} seconds", max_age.as_secs()))
+        .execute(&self.writer)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
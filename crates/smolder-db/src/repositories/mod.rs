@@ -2,18 +2,73 @@
 //!
 //! Each repository is implemented in its own module for better organization.
 
+mod analytics;
+mod api_token;
+mod approval;
+mod audit;
+mod batch;
 mod call_history;
 mod contract;
 mod deployment;
+mod deployment_group;
+mod ens;
+mod gas_sample;
+mod idempotency;
+mod job;
 mod network;
+mod price;
+mod reorg;
+mod selector;
+mod signature;
+mod template;
+mod token;
 mod wallet;
+mod wallet_policy;
+mod webhook;
+
+pub(crate) use analytics::{sum_costs, FeePaid};
 
 use crate::traits::{
-    CallHistoryRepository, ContractRepository, DeploymentRepository, NetworkRepository,
-    Repositories, WalletRepository,
+    AnalyticsRepository, ApiTokenRepository, ApprovalRepository, AuditRepository, BatchRepository,
+    CallHistoryRepository, ContractRepository, DeploymentGroupRepository, DeploymentRepository,
+    EnsCacheRepository, GasSampleRepository, IdempotencyRepository, JobRepository,
+    NetworkRepository, PriceRepository, ReorgRepository, Repositories, SelectorRepository,
+    SignatureRepository, TemplateRepository, TokenMetadataRepository, WalletPolicyRepository,
+    WalletRepository, WebhookRepository,
 };
 use crate::Database;
 
+/// Append LIMIT/OFFSET clauses to a query
+///
+/// SQLite requires a LIMIT whenever OFFSET is used, so `LIMIT -1` (no limit)
+/// is emitted when only an offset is given.
+pub(crate) fn push_pagination(
+    builder: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) {
+    match (limit, offset) {
+        (Some(limit), _) => {
+            builder.push(" LIMIT ").push_bind(limit as i64);
+        }
+        (None, Some(_)) => {
+            builder.push(" LIMIT -1");
+        }
+        (None, None) => {}
+    }
+    if let Some(offset) = offset {
+        builder.push(" OFFSET ").push_bind(offset as i64);
+    }
+}
+
+/// Fee paid for a transaction in wei, as stored: the gas used times the
+/// effective gas price
+pub(crate) fn fee(gas_used: Option<i64>, gas_price: Option<&str>) -> Option<String> {
+    let gas_used = u128::try_from(gas_used?).ok()?;
+    let gas_price: u128 = gas_price?.parse().ok()?;
+    gas_used.checked_mul(gas_price).map(|fee| fee.to_string())
+}
+
 impl Repositories for Database {
     fn networks(&self) -> &dyn NetworkRepository {
         self
@@ -34,4 +89,76 @@ impl Repositories for Database {
     fn call_history(&self) -> &dyn CallHistoryRepository {
         self
     }
+
+    fn webhooks(&self) -> &dyn WebhookRepository {
+        self
+    }
+
+    fn ens_cache(&self) -> &dyn EnsCacheRepository {
+        self
+    }
+
+    fn token_metadata(&self) -> &dyn TokenMetadataRepository {
+        self
+    }
+
+    fn analytics(&self) -> &dyn AnalyticsRepository {
+        self
+    }
+
+    fn gas_samples(&self) -> &dyn GasSampleRepository {
+        self
+    }
+
+    fn prices(&self) -> &dyn PriceRepository {
+        self
+    }
+
+    fn deployment_groups(&self) -> &dyn DeploymentGroupRepository {
+        self
+    }
+
+    fn api_tokens(&self) -> &dyn ApiTokenRepository {
+        self
+    }
+
+    fn approvals(&self) -> &dyn ApprovalRepository {
+        self
+    }
+
+    fn templates(&self) -> &dyn TemplateRepository {
+        self
+    }
+
+    fn batches(&self) -> &dyn BatchRepository {
+        self
+    }
+
+    fn idempotency_keys(&self) -> &dyn IdempotencyRepository {
+        self
+    }
+
+    fn selectors(&self) -> &dyn SelectorRepository {
+        self
+    }
+
+    fn reorgs(&self) -> &dyn ReorgRepository {
+        self
+    }
+
+    fn signatures(&self) -> &dyn SignatureRepository {
+        self
+    }
+
+    fn audit_log(&self) -> &dyn AuditRepository {
+        self
+    }
+
+    fn wallet_policies(&self) -> &dyn WalletPolicyRepository {
+        self
+    }
+
+    fn jobs(&self) -> &dyn JobRepository {
+        self
+    }
 }
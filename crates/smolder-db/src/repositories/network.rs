@@ -1,9 +1,10 @@
 //! NetworkRepository implementation for SQLite
 
 use async_trait::async_trait;
-use smolder_core::{ChainId, NetworkId, Result};
+use smolder_core::chains;
+use smolder_core::{ChainId, Currency, Error, NetworkId, Result, RpcUrl};
 
-use crate::models::{Network, NewNetwork};
+use crate::models::{Network, NetworkUpdate, NewNetwork};
 use crate::traits::NetworkRepository;
 use crate::Database;
 
@@ -11,7 +12,7 @@ use crate::Database;
 impl NetworkRepository for Database {
     async fn list(&self) -> Result<Vec<Network>> {
         let networks = sqlx::query_as::<_, Network>("SELECT * FROM networks ORDER BY name")
-            .fetch_all(&self.pool)
+            .fetch_all(&self.reader)
             .await?;
         Ok(networks)
     }
@@ -19,7 +20,7 @@ impl NetworkRepository for Database {
     async fn get_by_name(&self, name: &str) -> Result<Option<Network>> {
         let network = sqlx::query_as::<_, Network>("SELECT * FROM networks WHERE name = ?")
             .bind(name)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.reader)
             .await?;
         Ok(network)
     }
@@ -27,7 +28,7 @@ impl NetworkRepository for Database {
     async fn get_by_id(&self, id: NetworkId) -> Result<Option<Network>> {
         let network = sqlx::query_as::<_, Network>("SELECT * FROM networks WHERE id = ?")
             .bind(id.0)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.reader)
             .await?;
         Ok(network)
     }
@@ -35,16 +36,17 @@ impl NetworkRepository for Database {
     async fn get_by_chain_id(&self, chain_id: ChainId) -> Result<Option<Network>> {
         let network = sqlx::query_as::<_, Network>("SELECT * FROM networks WHERE chain_id = ?")
             .bind(chain_id.0)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.reader)
             .await?;
         Ok(network)
     }
 
     async fn upsert(&self, network: &NewNetwork) -> Result<Network> {
+        let currency = chains::currency(network.chain_id.into());
         let id = sqlx::query_scalar::<_, i64>(
             r#"
-            INSERT INTO networks (name, chain_id, rpc_url, explorer_url)
-            VALUES (?, ?, ?, ?)
+            INSERT INTO networks (name, chain_id, rpc_url, explorer_url, currency_symbol, currency_decimals)
+            VALUES (?, ?, ?, ?, ?, ?)
             ON CONFLICT(name) DO UPDATE SET
                 chain_id = excluded.chain_id,
                 rpc_url = excluded.rpc_url,
@@ -54,13 +56,152 @@ impl NetworkRepository for Database {
         )
         .bind(&network.name)
         .bind(network.chain_id)
-        .bind(&network.rpc_url)
+        .bind(RpcUrl::from(network.rpc_url.as_str()))
         .bind(&network.explorer_url)
-        .fetch_one(&self.pool)
+        .bind(currency.symbol)
+        .bind(currency.decimals)
+        .fetch_one(&self.writer)
         .await?;
 
         NetworkRepository::get_by_id(self, NetworkId(id))
             .await?
             .ok_or_else(|| smolder_core::Error::NetworkNotFound(network.name.clone()))
     }
+
+    async fn set_impersonation(&self, name: &str, enabled: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE networks SET impersonation = ? WHERE name = ?")
+            .bind(enabled)
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::NetworkNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn set_protected(&self, name: &str, protected: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE networks SET protected = ? WHERE name = ?")
+            .bind(protected)
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::NetworkNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn set_private_relay(&self, name: &str, relay_url: Option<&str>) -> Result<()> {
+        let result = sqlx::query("UPDATE networks SET private_relay = ? WHERE name = ?")
+            .bind(relay_url)
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::NetworkNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn set_currency(&self, name: &str, currency: Currency<'_>) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE networks SET currency_symbol = ?, currency_decimals = ? WHERE name = ?",
+        )
+        .bind(currency.symbol)
+        .bind(currency.decimals)
+        .bind(name)
+        .execute(&self.writer)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::NetworkNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn set_reorg_depth(&self, name: &str, depth: u32) -> Result<()> {
+        let result = sqlx::query("UPDATE networks SET reorg_depth = ? WHERE name = ?")
+            .bind(depth)
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::NetworkNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn set_locked(&self, name: &str, locked: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE networks SET locked = ? WHERE name = ?")
+            .bind(locked)
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::NetworkNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn set_confirmations(&self, name: &str, confirmations: u32) -> Result<()> {
+        let result = sqlx::query("UPDATE networks SET confirmations = ? WHERE name = ?")
+            .bind(confirmations)
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::NetworkNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn update(
+        &self,
+        name: &str,
+        update: &NetworkUpdate,
+        if_version: Option<i64>,
+    ) -> Result<Network> {
+        let current = NetworkRepository::get_by_name(self, name)
+            .await?
+            .ok_or_else(|| Error::NetworkNotFound(name.to_string()))?;
+        let rpc_url = update.rpc_url_over(&current.rpc_url)?;
+
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            UPDATE networks SET
+                rpc_url = COALESCE(?, rpc_url),
+                explorer_url = COALESCE(?, explorer_url)
+            WHERE name = ? AND (? IS NULL OR row_version = ?)
+            RETURNING id
+            "#,
+        )
+        .bind(rpc_url.map(RpcUrl::from))
+        .bind(&update.explorer_url)
+        .bind(name)
+        .bind(if_version)
+        .bind(if_version)
+        .fetch_optional(&self.writer)
+        .await?;
+
+        let network = NetworkRepository::get_by_name(self, name)
+            .await?
+            .ok_or_else(|| Error::NetworkNotFound(name.to_string()))?;
+        match (id, if_version) {
+            (None, Some(expected)) => Err(Error::VersionMismatch {
+                entity: "Network",
+                name: name.to_string(),
+                expected,
+                actual: network.row_version,
+            }),
+            _ => Ok(network),
+        }
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        sqlx::query("DELETE FROM networks WHERE name = ?")
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,36 @@
+//! PriceRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use crate::traits::PriceRepository;
+use crate::Database;
+
+#[async_trait]
+impl PriceRepository for Database {
+    async fn get(&self, symbol: &str, day: &str) -> Result<Option<f64>> {
+        let usd = sqlx::query_scalar("SELECT usd FROM token_prices WHERE symbol = ? AND day = ?")
+            .bind(symbol)
+            .bind(day)
+            .fetch_optional(&self.reader)
+            .await?;
+        Ok(usd)
+    }
+
+    async fn put(&self, symbol: &str, day: &str, usd: f64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO token_prices (symbol, day, usd) VALUES (?, ?, ?)
+            ON CONFLICT (symbol, day) DO UPDATE SET
+                usd = excluded.usd,
+                fetched_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(symbol)
+        .bind(day)
+        .bind(usd)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+//! ReorgRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::{NetworkId, RecordKind, Result};
+
+use crate::models::MinedRecord;
+use crate::traits::ReorgRepository;
+use crate::Database;
+
+/// Table holding records of `kind`
+fn table(kind: RecordKind) -> &'static str {
+    match kind {
+        RecordKind::Deployment => "deployments",
+        RecordKind::Call => "call_history",
+    }
+}
+
+#[async_trait]
+impl ReorgRepository for Database {
+    async fn unsettled(&self, network_id: NetworkId, after: i64) -> Result<Vec<MinedRecord>> {
+        let records = sqlx::query_as::<_, MinedRecord>(
+            r#"
+            SELECT 'deployment' AS kind, id, tx_hash, block_number, block_hash, reorged_at
+            FROM deployments
+            WHERE network_id = ?1 AND tx_hash != '' AND block_number IS NOT NULL
+                AND (block_number > ?2 OR reorged_at IS NOT NULL)
+            UNION ALL
+            SELECT 'call' AS kind, h.id, h.tx_hash, h.block_number, h.block_hash, h.reorged_at
+            FROM call_history h
+            JOIN deployments d ON h.deployment_id = d.id
+            WHERE d.network_id = ?1 AND h.tx_hash IS NOT NULL AND h.block_number IS NOT NULL
+                AND (h.block_number > ?2 OR h.reorged_at IS NOT NULL)
+            ORDER BY block_number, kind DESC, id
+            "#,
+        )
+        .bind(network_id)
+        .bind(after)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(records)
+    }
+
+    async fn set_block(
+        &self,
+        kind: RecordKind,
+        id: i64,
+        block_number: i64,
+        block_hash: &str,
+    ) -> Result<()> {
+        // The table name comes from a closed set, never from input
+        let query = format!(
+            "UPDATE {} SET block_number = ?, block_hash = ? WHERE id = ?",
+            table(kind)
+        );
+        sqlx::query(&query)
+            .bind(block_number)
+            .bind(block_hash)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_reorged(&self, kind: RecordKind, id: i64, reorged: bool) -> Result<()> {
+        let query = format!(
+            r#"
+            UPDATE {} SET
+                reorged_at = CASE WHEN ?1 THEN datetime('now') END,
+                finality = CASE WHEN ?1 THEN 'pending' ELSE finality END
+            WHERE id = ?2
+            "#,
+            table(kind)
+        );
+        sqlx::query(&query)
+            .bind(reorged)
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+
+    async fn advance_finality(
+        &self,
+        network_id: NetworkId,
+        confirmed: i64,
+        finalized: Option<i64>,
+    ) -> Result<u64> {
+        let mut tx = self.writer.begin().await?;
+        let mut changed = 0;
+        for (table, on_network) in [
+            ("deployments", "network_id = ?1"),
+            (
+                "call_history",
+                "deployment_id IN (SELECT id FROM deployments WHERE network_id = ?1)",
+            ),
+        ] {
+            let query = format!(
+                r#"
+                UPDATE {table} SET finality =
+                    CASE WHEN block_number <= ?3 THEN 'finalized' ELSE 'confirmed' END
+                WHERE {on_network} AND reorged_at IS NULL
+                    AND (
+                        (finality = 'pending' AND block_number <= ?2)
+                        OR (finality IN ('pending', 'confirmed') AND block_number <= ?3)
+                    )
+                "#
+            );
+            changed += sqlx::query(&query)
+                .bind(network_id)
+                .bind(confirmed)
+                .bind(finalized)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(changed)
+    }
+}
@@ -0,0 +1,48 @@
+//! SelectorRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use crate::models::{KnownSelector, NewSelector};
+use crate::traits::SelectorRepository;
+use crate::Database;
+
+#[async_trait]
+impl SelectorRepository for Database {
+    async fn lookup(&self, selector: &str) -> Result<Vec<KnownSelector>> {
+        let selectors = sqlx::query_as::<_, KnownSelector>(
+            r#"
+            SELECT * FROM selectors
+            WHERE selector = lower(?)
+            ORDER BY source = 'registry' DESC, signature
+            "#,
+        )
+        .bind(selector)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(selectors)
+    }
+
+    async fn put_many(&self, selectors: &[NewSelector]) -> Result<u64> {
+        let mut tx = self.writer.begin().await?;
+        let mut added = 0;
+        for selector in selectors {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO selectors (selector, signature, source)
+                VALUES (lower(?), ?, ?)
+                ON CONFLICT (selector, signature) DO UPDATE SET source = excluded.source
+                WHERE excluded.source = 'registry' AND source != 'registry'
+                "#,
+            )
+            .bind(&selector.selector)
+            .bind(&selector.signature)
+            .bind(selector.source)
+            .execute(&mut *tx)
+            .await?;
+            added += result.rows_affected();
+        }
+        tx.commit().await?;
+        Ok(added)
+    }
+}
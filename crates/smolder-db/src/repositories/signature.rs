@@ -0,0 +1,58 @@
+//! SignatureRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::Result;
+
+use crate::models::{NewSignature, SignatureRecord};
+use crate::traits::SignatureRepository;
+use crate::Database;
+
+const SIGNATURE_SELECT: &str = r#"
+    SELECT s.id, w.name AS wallet_name, s.signer, s.primary_type, s.domain, s.payload,
+        s.digest, s.signature, t.name AS signed_by, s.created_at
+    FROM signatures s
+    LEFT JOIN wallets w ON s.wallet_id = w.id
+    LEFT JOIN api_tokens t ON s.signed_by = t.id
+"#;
+
+#[async_trait]
+impl SignatureRepository for Database {
+    async fn create(&self, signature: &NewSignature) -> Result<SignatureRecord> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO signatures
+                (wallet_id, signer, primary_type, domain, payload, digest, signature, signed_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING id
+            "#,
+        )
+        .bind(signature.wallet_id)
+        .bind(signature.signer)
+        .bind(&signature.primary_type)
+        .bind(&signature.domain)
+        .bind(&signature.payload)
+        .bind(&signature.digest)
+        .bind(&signature.signature)
+        .bind(signature.signed_by)
+        .fetch_one(&self.writer)
+        .await?;
+
+        let record =
+            sqlx::query_as::<_, SignatureRecord>(&format!("{} WHERE s.id = ?", SIGNATURE_SELECT))
+                .bind(id)
+                .fetch_one(&self.writer)
+                .await?;
+        Ok(record)
+    }
+
+    async fn list(&self, wallet: Option<&str>) -> Result<Vec<SignatureRecord>> {
+        let records = sqlx::query_as::<_, SignatureRecord>(&format!(
+            "{} WHERE ?1 IS NULL OR w.name = ?1 ORDER BY s.id DESC",
+            SIGNATURE_SELECT
+        ))
+        .bind(wallet)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(records)
+    }
+}
@@ -0,0 +1,60 @@
+//! TemplateRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::Result;
+use sqlx::types::Json;
+
+use crate::models::{NewTemplate, Template};
+use crate::traits::TemplateRepository;
+use crate::Database;
+
+#[async_trait]
+impl TemplateRepository for Database {
+    async fn list(&self) -> Result<Vec<Template>> {
+        let templates = sqlx::query_as::<_, Template>("SELECT * FROM templates ORDER BY name")
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(templates)
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Template>> {
+        let template = sqlx::query_as::<_, Template>("SELECT * FROM templates WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.reader)
+            .await?;
+        Ok(template)
+    }
+
+    async fn upsert(&self, template: &NewTemplate) -> Result<Template> {
+        let saved = sqlx::query_as::<_, Template>(
+            r#"
+            INSERT INTO templates (name, artifact, params, description)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (name) DO UPDATE SET
+                artifact = excluded.artifact,
+                params = excluded.params,
+                description = excluded.description,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING *
+            "#,
+        )
+        .bind(&template.name)
+        .bind(&template.artifact)
+        .bind(Json(&template.params))
+        .bind(&template.description)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(saved)
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM templates WHERE name = ?")
+            .bind(name)
+            .execute(&self.writer)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(smolder_core::Error::TemplateNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
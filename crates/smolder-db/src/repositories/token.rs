@@ -0,0 +1,51 @@
+//! TokenMetadataRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::{DeploymentId, Result};
+
+use crate::models::{NewTokenMetadata, TokenMetadata};
+use crate::traits::TokenMetadataRepository;
+use crate::Database;
+
+#[async_trait]
+impl TokenMetadataRepository for Database {
+    async fn get(&self, deployment_id: DeploymentId) -> Result<Option<TokenMetadata>> {
+        let metadata = sqlx::query_as::<_, TokenMetadata>(
+            r#"
+            SELECT standard, name, symbol, decimals, total_supply, fetched_at
+            FROM contract_metadata
+            WHERE deployment_id = ?
+            "#,
+        )
+        .bind(deployment_id)
+        .fetch_optional(&self.reader)
+        .await?;
+        Ok(metadata)
+    }
+
+    async fn put(&self, metadata: &NewTokenMetadata) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO contract_metadata
+                (deployment_id, standard, name, symbol, decimals, total_supply)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (deployment_id) DO UPDATE SET
+                standard = excluded.standard,
+                name = excluded.name,
+                symbol = excluded.symbol,
+                decimals = excluded.decimals,
+                total_supply = excluded.total_supply,
+                fetched_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(metadata.deployment_id)
+        .bind(metadata.standard)
+        .bind(&metadata.name)
+        .bind(&metadata.symbol)
+        .bind(metadata.decimals)
+        .bind(&metadata.total_supply)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+}
@@ -1,29 +1,36 @@
 //! WalletRepository implementation for SQLite
 
 use async_trait::async_trait;
-use smolder_core::{Result, WalletId};
+use smolder_core::{Error, EvmAddress, Result, WalletId};
 
-use crate::models::{NewWallet, Wallet, WalletWithKey};
+use super::call_history::CALL_HISTORY_VIEW_SELECT;
+use super::deployment::DEPLOYMENT_VIEW_SELECT;
+use crate::models::{
+    CallHistoryView, DeploymentView, NewWallet, Wallet, WalletRotation, WalletWithKey,
+};
 use crate::traits::WalletRepository;
 use crate::Database;
 
+/// Every address a wallet has had; binds the wallet ID as `?1`
+const WALLET_ADDRESSES: &str = "(SELECT address FROM wallets WHERE id = ?1 UNION SELECT old_address FROM wallet_rotations WHERE wallet_id = ?1)";
+
 #[async_trait]
 impl WalletRepository for Database {
     async fn list(&self) -> Result<Vec<Wallet>> {
         let wallets = sqlx::query_as::<_, Wallet>(
-            "SELECT id, name, address, created_at FROM wallets ORDER BY name",
+            "SELECT id, name, address, created_at, row_version FROM wallets ORDER BY name",
         )
-        .fetch_all(&self.pool)
+        .fetch_all(&self.reader)
         .await?;
         Ok(wallets)
     }
 
     async fn get_by_name(&self, name: &str) -> Result<Option<Wallet>> {
         let wallet = sqlx::query_as::<_, Wallet>(
-            "SELECT id, name, address, created_at FROM wallets WHERE name = ?",
+            "SELECT id, name, address, created_at, row_version FROM wallets WHERE name = ?",
         )
         .bind(name)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&self.reader)
         .await?;
         Ok(wallet)
     }
@@ -31,27 +38,27 @@ impl WalletRepository for Database {
     async fn get_with_key(&self, name: &str) -> Result<Option<WalletWithKey>> {
         let wallet = sqlx::query_as::<_, WalletWithKey>("SELECT * FROM wallets WHERE name = ?")
             .bind(name)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&self.reader)
             .await?;
         Ok(wallet)
     }
 
     async fn get_by_id(&self, id: WalletId) -> Result<Option<Wallet>> {
         let wallet = sqlx::query_as::<_, Wallet>(
-            "SELECT id, name, address, created_at FROM wallets WHERE id = ?",
+            "SELECT id, name, address, created_at, row_version FROM wallets WHERE id = ?",
         )
         .bind(id.0)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&self.reader)
         .await?;
         Ok(wallet)
     }
 
     async fn get_by_address(&self, address: &str) -> Result<Option<Wallet>> {
         let wallet = sqlx::query_as::<_, Wallet>(
-            "SELECT id, name, address, created_at FROM wallets WHERE address = ?",
+            "SELECT id, name, address, created_at, row_version FROM wallets WHERE address = ? COLLATE NOCASE",
         )
         .bind(address)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&self.reader)
         .await?;
         Ok(wallet)
     }
@@ -61,9 +68,9 @@ impl WalletRepository for Database {
             "INSERT INTO wallets (name, address, encrypted_key) VALUES (?, ?, ?) RETURNING id",
         )
         .bind(&wallet.name)
-        .bind(&wallet.address)
+        .bind(wallet.address)
         .bind(&wallet.encrypted_key)
-        .fetch_one(&self.pool)
+        .fetch_one(&self.writer)
         .await?;
 
         WalletRepository::get_by_id(self, WalletId(id))
@@ -71,11 +78,104 @@ impl WalletRepository for Database {
             .ok_or_else(|| smolder_core::Error::WalletNotFound(wallet.name.clone()))
     }
 
+    async fn rotate(
+        &self,
+        name: &str,
+        address: EvmAddress,
+        encrypted_key: &[u8],
+    ) -> Result<Wallet> {
+        let mut tx = self.writer.begin().await?;
+        let (id, old_address) = sqlx::query_as::<_, (i64, EvmAddress)>(
+            "SELECT id, address FROM wallets WHERE name = ?",
+        )
+        .bind(name)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| Error::WalletNotFound(name.to_string()))?;
+
+        sqlx::query("UPDATE wallets SET address = ?, encrypted_key = ? WHERE id = ?")
+            .bind(address)
+            .bind(encrypted_key)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO wallet_rotations (wallet_id, old_address, new_address) VALUES (?, ?, ?)",
+        )
+        .bind(id)
+        .bind(old_address)
+        .bind(address)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        WalletRepository::get_by_id(self, WalletId(id))
+            .await?
+            .ok_or_else(|| Error::WalletNotFound(name.to_string()))
+    }
+
+    async fn rotations(&self, id: WalletId) -> Result<Vec<WalletRotation>> {
+        let rotations = sqlx::query_as::<_, WalletRotation>(
+            "SELECT * FROM wallet_rotations WHERE wallet_id = ? ORDER BY id",
+        )
+        .bind(id.0)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(rotations)
+    }
+
+    async fn deployments(&self, id: WalletId, limit: u32) -> Result<Vec<DeploymentView>> {
+        let deployments = sqlx::query_as::<_, DeploymentView>(&format!(
+            "{} WHERE d.wallet_id = ? AND d.archived_at IS NULL ORDER BY d.deployed_at DESC, d.id DESC LIMIT ?",
+            DEPLOYMENT_VIEW_SELECT
+        ))
+        .bind(id.0)
+        .bind(limit)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(deployments)
+    }
+
+    async fn calls(&self, id: WalletId, limit: u32) -> Result<Vec<CallHistoryView>> {
+        let calls = sqlx::query_as::<_, CallHistoryView>(&format!(
+            "{} WHERE h.wallet_id = ?1 OR h.sender COLLATE NOCASE IN {} ORDER BY h.id DESC LIMIT ?2",
+            CALL_HISTORY_VIEW_SELECT, WALLET_ADDRESSES
+        ))
+        .bind(id.0)
+        .bind(limit)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(calls)
+    }
+
     async fn delete(&self, name: &str) -> Result<()> {
         sqlx::query("DELETE FROM wallets WHERE name = ?")
             .bind(name)
-            .execute(&self.pool)
+            .execute(&self.writer)
             .await?;
         Ok(())
     }
+
+    async fn delete_if(&self, name: &str, if_version: Option<i64>) -> Result<()> {
+        let result =
+            sqlx::query("DELETE FROM wallets WHERE name = ? AND (? IS NULL OR row_version = ?)")
+                .bind(name)
+                .bind(if_version)
+                .bind(if_version)
+                .execute(&self.writer)
+                .await?;
+        if result.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        let wallet = WalletRepository::get_by_name(self, name)
+            .await?
+            .ok_or_else(|| Error::WalletNotFound(name.to_string()))?;
+        Err(Error::VersionMismatch {
+            entity: "Wallet",
+            name: name.to_string(),
+            expected: if_version.unwrap_or_default(),
+            actual: wallet.row_version,
+        })
+    }
 }
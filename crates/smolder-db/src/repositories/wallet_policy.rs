@@ -0,0 +1,134 @@
+//! WalletPolicyRepository implementation for SQLite
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use smolder_core::types::{NetworkId, WalletId};
+use smolder_core::Result;
+use sqlx::types::Json;
+
+use crate::models::WalletPolicy;
+use crate::traits::WalletPolicyRepository;
+use crate::Database;
+
+#[async_trait]
+impl WalletPolicyRepository for Database {
+    async fn get(&self, wallet_id: WalletId) -> Result<Option<WalletPolicy>> {
+        let policy = sqlx::query_as::<_, WalletPolicy>(
+            r#"
+            SELECT max_value_per_tx, max_daily_spend, allowed_networks, allowed_contracts,
+                allowed_functions
+            FROM wallet_policies WHERE wallet_id = ?
+            "#,
+        )
+        .bind(wallet_id)
+        .fetch_optional(&self.reader)
+        .await?;
+        Ok(policy)
+    }
+
+    async fn set(&self, wallet_id: WalletId, policy: &WalletPolicy) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO wallet_policies (wallet_id, max_value_per_tx, max_daily_spend,
+                allowed_networks, allowed_contracts, allowed_functions)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (wallet_id) DO UPDATE SET
+                max_value_per_tx = excluded.max_value_per_tx,
+                max_daily_spend = excluded.max_daily_spend,
+                allowed_networks = excluded.allowed_networks,
+                allowed_contracts = excluded.allowed_contracts,
+                allowed_functions = excluded.allowed_functions,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(wallet_id)
+        .bind(&policy.max_value_per_tx)
+        .bind(&policy.max_daily_spend)
+        .bind(policy.allowed_networks.as_ref().map(Json))
+        .bind(policy.allowed_contracts.as_ref().map(Json))
+        .bind(policy.allowed_functions.as_ref().map(Json))
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+
+    async fn clear(&self, wallet_id: WalletId) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM wallet_policies WHERE wallet_id = ?")
+            .bind(wallet_id)
+            .execute(&self.writer)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn reserve_spend(
+        &self,
+        wallet_id: WalletId,
+        network_id: NetworkId,
+        value: &str,
+        window: Duration,
+        fits: &(dyn for<'s> Fn(&'s [String]) -> bool + Send + Sync),
+    ) -> Result<Option<i64>> {
+        let mut tx = self.writer.begin().await?;
+        // Inserting first takes the write lock, so no other spend is recorded
+        // between reading the earlier ones and deciding
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO wallet_spends (wallet_id, network_id, value) VALUES (?, ?, ?) RETURNING id",
+        )
+        .bind(wallet_id)
+        .bind(network_id)
+        .bind(value)
+        .fetch_one(&mut *tx)
+        .await?;
+        let earlier: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT value FROM wallet_spends
+            WHERE wallet_id = ? AND network_id = ? AND created_at >= datetime('now', ?)
+                AND id != ?
+            ORDER BY id
+            "#,
+        )
+        .bind(wallet_id)
+        .bind(network_id)
+        .bind(format!("-{} seconds", window.as_secs()))
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if !fits(&earlier) {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        tx.commit().await?;
+        Ok(Some(id))
+    }
+
+    async fn release_spend(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM wallet_spends WHERE id = ?")
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+
+    async fn spent_within(
+        &self,
+        wallet_id: WalletId,
+        network_id: NetworkId,
+        window: Duration,
+    ) -> Result<Vec<String>> {
+        let values = sqlx::query_scalar(
+            r#"
+            SELECT value FROM wallet_spends
+            WHERE wallet_id = ? AND network_id = ? AND created_at >= datetime('now', ?)
+            ORDER BY id
+            "#,
+        )
+        .bind(wallet_id)
+        .bind(network_id)
+        .bind(format!("-{} seconds", window.as_secs()))
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(values)
+    }
+}
@@ -0,0 +1,119 @@
+//! WebhookRepository implementation for SQLite
+
+use async_trait::async_trait;
+use smolder_core::{Result, WebhookId};
+
+use crate::models::{
+    NewWebhook, NewWebhookDelivery, Webhook, WebhookDelivery, WebhookDeliveryUpdate,
+};
+use crate::traits::WebhookRepository;
+use crate::Database;
+
+#[async_trait]
+impl WebhookRepository for Database {
+    async fn list(&self) -> Result<Vec<Webhook>> {
+        let webhooks = sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks ORDER BY id")
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(webhooks)
+    }
+
+    async fn get_by_id(&self, id: WebhookId) -> Result<Option<Webhook>> {
+        let webhook = sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.reader)
+            .await?;
+        Ok(webhook)
+    }
+
+    async fn create(&self, webhook: &NewWebhook) -> Result<Webhook> {
+        let created = sqlx::query_as::<_, Webhook>(
+            r#"
+            INSERT INTO webhooks (url, secret, network, contract, event_name, tx_status)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(&webhook.network)
+        .bind(&webhook.contract)
+        .bind(&webhook.event_name)
+        .bind(webhook.tx_status)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(created)
+    }
+
+    async fn delete(&self, id: WebhookId) -> Result<()> {
+        // Deliveries go with it through ON DELETE CASCADE
+        sqlx::query("DELETE FROM webhooks WHERE id = ?")
+            .bind(id)
+            .execute(&self.writer)
+            .await?;
+        Ok(())
+    }
+
+    async fn create_delivery(&self, delivery: &NewWebhookDelivery) -> Result<WebhookDelivery> {
+        let created = sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, trigger, payload)
+            VALUES (?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(delivery.webhook_id)
+        .bind(delivery.trigger)
+        .bind(&delivery.payload)
+        .fetch_one(&self.writer)
+        .await?;
+        Ok(created)
+    }
+
+    async fn update_delivery(&self, id: i64, update: &WebhookDeliveryUpdate) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries SET
+                status = ?,
+                attempts = ?,
+                response_status = ?,
+                error_message = ?,
+                delivered_at = CASE WHEN ? = 'delivered' THEN datetime('now') END
+            WHERE id = ?
+            "#,
+        )
+        .bind(update.status)
+        .bind(update.attempts)
+        .bind(update.response_status)
+        .bind(&update.error_message)
+        .bind(update.status)
+        .bind(id)
+        .execute(&self.writer)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_deliveries(
+        &self,
+        webhook_id: WebhookId,
+        limit: u32,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            "SELECT * FROM webhook_deliveries WHERE webhook_id = ? ORDER BY id DESC LIMIT ?",
+        )
+        .bind(webhook_id)
+        .bind(limit as i64)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(deliveries)
+    }
+
+    async fn list_pending_deliveries(&self) -> Result<Vec<WebhookDelivery>> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            "SELECT * FROM webhook_deliveries WHERE status = 'pending' ORDER BY id",
+        )
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(deliveries)
+    }
+}
@@ -1,11 +1,12 @@
 //! SQLite database schema
 //!
-//! This module contains the SQL DDL statements for creating the database schema
-//! and the function to initialize it.
+//! This module contains the SQL DDL statements for creating the database schema,
+//! the incremental migrations applied on top of it, and the function to
+//! initialize both.
 
 use sqlx::SqlitePool;
 
-use smolder_core::Error;
+use smolder_core::{Error, EvmAddress, RpcUrl};
 
 /// SQL schema for initializing the database
 pub const SCHEMA: &str = r#"
@@ -83,8 +84,603 @@ CREATE INDEX IF NOT EXISTS idx_call_history_deployment ON call_history(deploymen
 CREATE INDEX IF NOT EXISTS idx_call_history_wallet ON call_history(wallet_id);
 "#;
 
-/// Initialize the database schema
+/// Incremental migrations applied on top of [`SCHEMA`]
+///
+/// The number of applied migrations is tracked in SQLite's `user_version`
+/// pragma. Entries are applied once, in order: never edit or reorder existing
+/// entries, only append new ones.
+pub const MIGRATIONS: &[&str] = &[
+    // 1: soft-delete support for deployments
+    "ALTER TABLE deployments ADD COLUMN archived_at DATETIME;",
+    // 2: webhook subscriptions and their delivery log
+    r#"
+    CREATE TABLE webhooks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        network TEXT,
+        contract TEXT,
+        event_name TEXT,
+        tx_status TEXT CHECK (tx_status IN ('pending', 'success', 'failed', 'reverted')),
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+
+    CREATE TABLE webhook_deliveries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+        trigger TEXT NOT NULL CHECK (trigger IN ('event', 'call')),
+        payload JSON NOT NULL,
+        status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')),
+        attempts INTEGER NOT NULL DEFAULT 0,
+        response_status INTEGER,
+        error_message TEXT,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        delivered_at DATETIME
+    );
+
+    CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id);
+    CREATE INDEX idx_webhook_deliveries_pending ON webhook_deliveries(status) WHERE status = 'pending';
+    "#,
+    // 3: cached ENS lookups; a NULL result records that nothing was set
+    r#"
+    CREATE TABLE ens_cache (
+        chain_id INTEGER NOT NULL,
+        lookup TEXT NOT NULL CHECK (lookup IN ('forward', 'reverse')),
+        input TEXT NOT NULL,
+        result TEXT,
+        resolved_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (chain_id, lookup, input)
+    );
+    "#,
+    // 4: token metadata read from deployed ERC-20/721/1155 contracts
+    r#"
+    CREATE TABLE contract_metadata (
+        deployment_id INTEGER PRIMARY KEY REFERENCES deployments(id) ON DELETE CASCADE,
+        standard TEXT NOT NULL CHECK (standard IN ('erc20', 'erc721', 'erc1155')),
+        name TEXT,
+        symbol TEXT,
+        decimals INTEGER,
+        total_supply TEXT,
+        fetched_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+    // 5: code sizes and gas used, to track contract growth across versions
+    r#"
+    ALTER TABLE deployments ADD COLUMN initcode_size INTEGER;
+    ALTER TABLE deployments ADD COLUMN runtime_size INTEGER;
+    ALTER TABLE deployments ADD COLUMN gas_used INTEGER;
+    "#,
+    // 6: storage layouts from compiler output, to check upgrades against
+    "ALTER TABLE contracts ADD COLUMN storage_layout JSON;",
+    // 7: networks whose node lets calls be sent from any address
+    "ALTER TABLE networks ADD COLUMN impersonation BOOLEAN NOT NULL DEFAULT FALSE;",
+    // 8: fee samples taken by the server, to chart gas price trends
+    r#"
+    CREATE TABLE gas_samples (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        network_id INTEGER NOT NULL REFERENCES networks(id) ON DELETE CASCADE,
+        block_number INTEGER NOT NULL,
+        base_fee INTEGER,
+        gas_price INTEGER NOT NULL,
+        sampled_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX idx_gas_samples_network ON gas_samples(network_id, sampled_at);
+    "#,
+    // 9: one artifact deployed across several networks, resumable per network
+    r#"
+    CREATE TABLE deployment_groups (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        contract_name TEXT NOT NULL,
+        bytecode_hash TEXT NOT NULL,
+        constructor_args JSON NOT NULL,
+        value TEXT,
+        wallet_name TEXT NOT NULL,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE TABLE deployment_group_members (
+        group_id INTEGER NOT NULL REFERENCES deployment_groups(id) ON DELETE CASCADE,
+        network_id INTEGER NOT NULL REFERENCES networks(id) ON DELETE CASCADE,
+        status TEXT NOT NULL CHECK (status IN ('pending', 'success', 'failed')),
+        deployment_id INTEGER REFERENCES deployments(id) ON DELETE SET NULL,
+        error_message TEXT,
+        updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (group_id, network_id)
+    );
+    "#,
+    // 10: API tokens, and deploys and sends on protected networks held for
+    // approval by a second token
+    r#"
+    ALTER TABLE networks ADD COLUMN protected BOOLEAN NOT NULL DEFAULT FALSE;
+    CREATE TABLE api_tokens (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE,
+        token_hash TEXT NOT NULL UNIQUE,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        last_used_at DATETIME
+    );
+    CREATE TABLE approvals (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL CHECK (kind IN ('deploy', 'send')),
+        network_id INTEGER NOT NULL REFERENCES networks(id) ON DELETE CASCADE,
+        deployment_id INTEGER REFERENCES deployments(id) ON DELETE CASCADE,
+        summary TEXT NOT NULL,
+        request JSON NOT NULL,
+        status TEXT NOT NULL DEFAULT 'pending'
+            CHECK (status IN ('pending', 'approved', 'rejected', 'expired', 'failed')),
+        requested_by INTEGER REFERENCES api_tokens(id) ON DELETE SET NULL,
+        decided_by INTEGER REFERENCES api_tokens(id) ON DELETE SET NULL,
+        result JSON,
+        error_message TEXT,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        expires_at DATETIME NOT NULL,
+        decided_at DATETIME
+    );
+    CREATE INDEX idx_approvals_status ON approvals(status, expires_at);
+    "#,
+    // 11: token roles; tokens created before roles keep full access
+    r#"
+    ALTER TABLE api_tokens ADD COLUMN role TEXT NOT NULL DEFAULT 'admin'
+        CHECK (role IN ('viewer', 'operator', 'admin'));
+    "#,
+    // 12: compiler version and settings from artifact metadata
+    "ALTER TABLE contracts ADD COLUMN compiler JSON;",
+    // 13: hashes as `0x` and 64 lowercase hex digits. Unprefixed or
+    // uppercase hashes are rewritten; transaction hashes that can't be are
+    // dropped, and bytecode hashes get a placeholder derived from the row id,
+    // since a build can't be rehashed here. Triggers then reject malformed
+    // hashes, as SQLite can't add CHECK constraints to existing tables.
+    r#"
+    UPDATE OR IGNORE contracts SET bytecode_hash = '0x' || lower(bytecode_hash)
+        WHERE length(bytecode_hash) = 64 AND bytecode_hash NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE OR IGNORE contracts SET bytecode_hash = '0x' || lower(substr(bytecode_hash, 3))
+        WHERE length(bytecode_hash) = 66 AND lower(substr(bytecode_hash, 1, 2)) = '0x'
+            AND substr(bytecode_hash, 3) NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE contracts SET bytecode_hash = printf('0x%064x', id)
+        WHERE NOT (length(bytecode_hash) = 66 AND substr(bytecode_hash, 1, 2) = '0x'
+            AND substr(bytecode_hash, 3) NOT GLOB '*[^0-9a-f]*');
+
+    UPDATE deployment_groups SET bytecode_hash = '0x' || lower(bytecode_hash)
+        WHERE length(bytecode_hash) = 64 AND bytecode_hash NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE deployment_groups SET bytecode_hash = '0x' || lower(substr(bytecode_hash, 3))
+        WHERE length(bytecode_hash) = 66 AND lower(substr(bytecode_hash, 1, 2)) = '0x'
+            AND substr(bytecode_hash, 3) NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE deployment_groups SET bytecode_hash = printf('0x%064x', id)
+        WHERE NOT (length(bytecode_hash) = 66 AND substr(bytecode_hash, 1, 2) = '0x'
+            AND substr(bytecode_hash, 3) NOT GLOB '*[^0-9a-f]*');
+
+    UPDATE deployments SET tx_hash = '0x' || lower(tx_hash)
+        WHERE length(tx_hash) = 64 AND tx_hash NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE deployments SET tx_hash = '0x' || lower(substr(tx_hash, 3))
+        WHERE length(tx_hash) = 66 AND lower(substr(tx_hash, 1, 2)) = '0x'
+            AND substr(tx_hash, 3) NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE deployments SET tx_hash = ''
+        WHERE tx_hash != '' AND NOT (length(tx_hash) = 66 AND substr(tx_hash, 1, 2) = '0x'
+            AND substr(tx_hash, 3) NOT GLOB '*[^0-9a-f]*');
+
+    UPDATE call_history SET tx_hash = '0x' || lower(tx_hash)
+        WHERE length(tx_hash) = 64 AND tx_hash NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE call_history SET tx_hash = '0x' || lower(substr(tx_hash, 3))
+        WHERE length(tx_hash) = 66 AND lower(substr(tx_hash, 1, 2)) = '0x'
+            AND substr(tx_hash, 3) NOT GLOB '*[^0-9a-fA-F]*';
+    UPDATE call_history SET tx_hash = NULL
+        WHERE NOT (length(tx_hash) = 66 AND substr(tx_hash, 1, 2) = '0x'
+            AND substr(tx_hash, 3) NOT GLOB '*[^0-9a-f]*');
+
+    CREATE TRIGGER contracts_bytecode_hash_insert BEFORE INSERT ON contracts
+    WHEN NOT (length(NEW.bytecode_hash) = 66 AND substr(NEW.bytecode_hash, 1, 2) = '0x'
+        AND substr(NEW.bytecode_hash, 3) NOT GLOB '*[^0-9a-f]*')
+    BEGIN SELECT RAISE(ABORT, 'malformed bytecode_hash'); END;
+    CREATE TRIGGER contracts_bytecode_hash_update BEFORE UPDATE OF bytecode_hash ON contracts
+    WHEN NOT (length(NEW.bytecode_hash) = 66 AND substr(NEW.bytecode_hash, 1, 2) = '0x'
+        AND substr(NEW.bytecode_hash, 3) NOT GLOB '*[^0-9a-f]*')
+    BEGIN SELECT RAISE(ABORT, 'malformed bytecode_hash'); END;
+
+    CREATE TRIGGER deployment_groups_bytecode_hash_insert BEFORE INSERT ON deployment_groups
+    WHEN NOT (length(NEW.bytecode_hash) = 66 AND substr(NEW.bytecode_hash, 1, 2) = '0x'
+        AND substr(NEW.bytecode_hash, 3) NOT GLOB '*[^0-9a-f]*')
+    BEGIN SELECT RAISE(ABORT, 'malformed bytecode_hash'); END;
+
+    CREATE TRIGGER deployments_tx_hash_insert BEFORE INSERT ON deployments
+    WHEN NEW.tx_hash != '' AND NOT (length(NEW.tx_hash) = 66 AND substr(NEW.tx_hash, 1, 2) = '0x'
+        AND substr(NEW.tx_hash, 3) NOT GLOB '*[^0-9a-f]*')
+    BEGIN SELECT RAISE(ABORT, 'malformed tx_hash'); END;
+    CREATE TRIGGER deployments_tx_hash_update BEFORE UPDATE OF tx_hash ON deployments
+    WHEN NEW.tx_hash != '' AND NOT (length(NEW.tx_hash) = 66 AND substr(NEW.tx_hash, 1, 2) = '0x'
+        AND substr(NEW.tx_hash, 3) NOT GLOB '*[^0-9a-f]*')
+    BEGIN SELECT RAISE(ABORT, 'malformed tx_hash'); END;
+
+    CREATE TRIGGER call_history_tx_hash_insert BEFORE INSERT ON call_history
+    WHEN NEW.tx_hash IS NOT NULL AND NOT (length(NEW.tx_hash) = 66
+        AND substr(NEW.tx_hash, 1, 2) = '0x' AND substr(NEW.tx_hash, 3) NOT GLOB '*[^0-9a-f]*')
+    BEGIN SELECT RAISE(ABORT, 'malformed tx_hash'); END;
+    CREATE TRIGGER call_history_tx_hash_update BEFORE UPDATE OF tx_hash ON call_history
+    WHEN NEW.tx_hash IS NOT NULL AND NOT (length(NEW.tx_hash) = 66
+        AND substr(NEW.tx_hash, 1, 2) = '0x' AND substr(NEW.tx_hash, 3) NOT GLOB '*[^0-9a-f]*')
+    BEGIN SELECT RAISE(ABORT, 'malformed tx_hash'); END;
+    "#,
+    // 14: shareable link to a simulation of a call, e.g. on Tenderly
+    "ALTER TABLE call_history ADD COLUMN simulation_url TEXT;",
+    // 15: RPC of a private relay that signed transactions are submitted to
+    "ALTER TABLE networks ADD COLUMN private_relay TEXT;",
+    // 16: native currency, for well-known chains that don't use ether
+    r#"
+    ALTER TABLE networks ADD COLUMN currency_symbol TEXT NOT NULL DEFAULT 'ETH';
+    ALTER TABLE networks ADD COLUMN currency_decimals INTEGER NOT NULL DEFAULT 18;
+    UPDATE networks SET currency_symbol = CASE chain_id
+        WHEN 137 THEN 'POL' WHEN 80002 THEN 'POL' WHEN 56 THEN 'BNB'
+        WHEN 43114 THEN 'AVAX' WHEN 100 THEN 'XDAI' ELSE currency_symbol END;
+    "#,
+    // 17: effective gas price and fee paid, in wei, for cost reports. Fees
+    // of earlier calls are backfilled where they fit SQLite's integers.
+    r#"
+    ALTER TABLE deployments ADD COLUMN gas_price TEXT;
+    ALTER TABLE deployments ADD COLUMN fee TEXT;
+    ALTER TABLE call_history ADD COLUMN fee TEXT;
+    UPDATE call_history SET fee = CAST(gas_used * CAST(gas_price AS INTEGER) AS TEXT)
+        WHERE gas_used > 0 AND gas_price NOT GLOB '*[^0-9]*'
+            AND CAST(gas_price AS INTEGER) <= 9223372036854775807 / gas_used;
+    "#,
+    // 18: daily USD prices of native currencies, to value fees
+    r#"
+    CREATE TABLE token_prices (
+        symbol TEXT NOT NULL,
+        day TEXT NOT NULL,
+        usd REAL NOT NULL,
+        fetched_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (symbol, day)
+    );
+    "#,
+    // 19: reusable deployment recipes: an artifact and its named parameters
+    r#"
+    CREATE TABLE templates (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE,
+        artifact TEXT NOT NULL,
+        params TEXT NOT NULL,
+        description TEXT,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+    // 20: batch runs of a plan file, linked to the calls each step sent
+    r#"
+    CREATE TABLE batches (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        plan TEXT NOT NULL,
+        network_id INTEGER NOT NULL REFERENCES networks(id) ON DELETE CASCADE,
+        wallet_id INTEGER REFERENCES wallets(id) ON DELETE SET NULL,
+        steps INTEGER NOT NULL,
+        status TEXT NOT NULL DEFAULT 'pending',
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        finished_at DATETIME
+    );
+    CREATE TABLE batch_calls (
+        batch_id INTEGER NOT NULL REFERENCES batches(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        call_history_id INTEGER NOT NULL REFERENCES call_history(id) ON DELETE CASCADE,
+        PRIMARY KEY (batch_id, position)
+    );
+    "#,
+    // 21: row versions bumped on every update, for If-Match on the API, and
+    // the responses of POSTs sent with an Idempotency-Key
+    r#"
+    ALTER TABLE networks ADD COLUMN row_version INTEGER NOT NULL DEFAULT 1;
+    ALTER TABLE wallets ADD COLUMN row_version INTEGER NOT NULL DEFAULT 1;
+    CREATE TRIGGER networks_row_version AFTER UPDATE ON networks
+    WHEN NEW.row_version = OLD.row_version
+    BEGIN UPDATE networks SET row_version = OLD.row_version + 1 WHERE id = NEW.id; END;
+    CREATE TRIGGER wallets_row_version AFTER UPDATE ON wallets
+    WHEN NEW.row_version = OLD.row_version
+    BEGIN UPDATE wallets SET row_version = OLD.row_version + 1 WHERE id = NEW.id; END;
+    CREATE TABLE idempotency_keys (
+        scope TEXT NOT NULL,
+        key TEXT NOT NULL,
+        fingerprint TEXT NOT NULL,
+        status INTEGER,
+        content_type TEXT,
+        body BLOB,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (scope, key)
+    );
+    "#,
+    // 22: contracts created by factory calls, linked to the call
+    r#"
+    ALTER TABLE deployments ADD COLUMN created_by_call_id INTEGER REFERENCES call_history(id) ON DELETE SET NULL;
+    CREATE INDEX idx_deployments_created_by_call ON deployments(created_by_call_id);
+    "#,
+    // 23: function signatures by selector, from registry ABIs and
+    // 4byte.directory, for decoding arbitrary calldata
+    r#"
+    CREATE TABLE selectors (
+        selector TEXT NOT NULL,
+        signature TEXT NOT NULL,
+        source TEXT NOT NULL,
+        added_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (selector, signature)
+    );
+    "#,
+    // 24: transactions observed on chain and imported into call history
+    r#"
+    ALTER TABLE call_history ADD COLUMN sender TEXT;
+    CREATE INDEX idx_call_history_tx_hash ON call_history(tx_hash);
+    "#,
+    // 25: block hashes of mined transactions, re-checked against the chain
+    // until buried `reorg_depth` blocks deep, and when a reorg dropped them
+    r#"
+    ALTER TABLE networks ADD COLUMN reorg_depth INTEGER NOT NULL DEFAULT 12;
+    ALTER TABLE deployments ADD COLUMN block_hash TEXT;
+    ALTER TABLE deployments ADD COLUMN reorged_at DATETIME;
+    ALTER TABLE call_history ADD COLUMN block_hash TEXT;
+    ALTER TABLE call_history ADD COLUMN reorged_at DATETIME;
+    CREATE INDEX idx_deployments_network_block ON deployments(network_id, block_number);
+    CREATE INDEX idx_call_history_block ON call_history(block_number);
+    "#,
+    // 26: confirmations required per network, and how settled each mined
+    // transaction is. Recording or moving a block makes it pending again;
+    // rows mined before are taken as confirmed.
+    r#"
+    ALTER TABLE networks ADD COLUMN confirmations INTEGER NOT NULL DEFAULT 1;
+    ALTER TABLE deployments ADD COLUMN finality TEXT
+        CHECK (finality IN ('pending', 'confirmed', 'finalized'));
+    ALTER TABLE call_history ADD COLUMN finality TEXT
+        CHECK (finality IN ('pending', 'confirmed', 'finalized'));
+    UPDATE deployments SET finality = 'confirmed' WHERE block_number IS NOT NULL;
+    UPDATE call_history SET finality = 'confirmed' WHERE block_number IS NOT NULL;
+
+    CREATE TRIGGER deployments_finality_insert AFTER INSERT ON deployments
+    WHEN NEW.block_number IS NOT NULL AND NEW.finality IS NULL
+    BEGIN UPDATE deployments SET finality = 'pending' WHERE id = NEW.id; END;
+    CREATE TRIGGER deployments_finality_block AFTER UPDATE OF block_number ON deployments
+    WHEN NEW.block_number IS NOT OLD.block_number
+    BEGIN
+        UPDATE deployments SET finality = CASE WHEN NEW.block_number IS NULL THEN NULL
+            ELSE 'pending' END WHERE id = NEW.id;
+    END;
+    CREATE TRIGGER call_history_finality_insert AFTER INSERT ON call_history
+    WHEN NEW.block_number IS NOT NULL AND NEW.finality IS NULL
+    BEGIN UPDATE call_history SET finality = 'pending' WHERE id = NEW.id; END;
+    CREATE TRIGGER call_history_finality_block AFTER UPDATE OF block_number ON call_history
+    WHEN NEW.block_number IS NOT OLD.block_number
+    BEGIN
+        UPDATE call_history SET finality = CASE WHEN NEW.block_number IS NULL THEN NULL
+            ELSE 'pending' END WHERE id = NEW.id;
+    END;
+    "#,
+    // 27: EIP-712 payloads signed with stored wallets, kept for audit after
+    // the wallet or token is removed
+    r#"
+    CREATE TABLE signatures (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        wallet_id INTEGER REFERENCES wallets(id) ON DELETE SET NULL,
+        signer TEXT NOT NULL,
+        primary_type TEXT NOT NULL,
+        domain JSON NOT NULL,
+        payload JSON NOT NULL,
+        digest TEXT NOT NULL,
+        signature TEXT NOT NULL,
+        signed_by INTEGER REFERENCES api_tokens(id) ON DELETE SET NULL,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX idx_signatures_wallet ON signatures(wallet_id, id);
+    "#,
+    // 28: networks locked against deploys and sends, and an audit log of
+    // locking and of the operations confirmed through a lock
+    r#"
+    ALTER TABLE networks ADD COLUMN locked BOOLEAN NOT NULL DEFAULT FALSE;
+    CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        action TEXT NOT NULL,
+        target TEXT NOT NULL,
+        source TEXT NOT NULL CHECK (source IN ('cli', 'api')),
+        actor TEXT,
+        detail JSON,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+    // 29: limits on what each wallet may sign, and the native value wallets
+    // sent, to hold them to a daily limit
+    r#"
+    CREATE TABLE wallet_policies (
+        wallet_id INTEGER PRIMARY KEY REFERENCES wallets(id) ON DELETE CASCADE,
+        max_value_per_tx TEXT,
+        max_daily_spend TEXT,
+        allowed_networks JSON,
+        allowed_contracts JSON,
+        allowed_functions JSON,
+        updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE TABLE wallet_spends (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        wallet_id INTEGER NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+        network_id INTEGER NOT NULL REFERENCES networks(id) ON DELETE CASCADE,
+        value TEXT NOT NULL,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX idx_wallet_spends ON wallet_spends(wallet_id, network_id, created_at);
+    "#,
+    // 30: keys replaced by wallet rotation, kept so the history of a wallet
+    // still shows the addresses it signed with
+    r#"
+    CREATE TABLE wallet_rotations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        wallet_id INTEGER NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+        old_address TEXT NOT NULL,
+        new_address TEXT NOT NULL,
+        rotated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX idx_wallet_rotations ON wallet_rotations(wallet_id, id);
+    "#,
+    // 31: the registered wallet each deployment was sent from, matched by
+    // deployer address, including addresses wallets had before a rotation,
+    // and kept up to date as deployments and wallets are added
+    r#"
+    ALTER TABLE deployments ADD COLUMN wallet_id INTEGER REFERENCES wallets(id) ON DELETE SET NULL;
+    UPDATE deployments SET wallet_id = COALESCE(
+        (SELECT id FROM wallets WHERE address = deployments.deployer COLLATE NOCASE),
+        (SELECT wallet_id FROM wallet_rotations
+            WHERE old_address = deployments.deployer COLLATE NOCASE
+            ORDER BY id DESC LIMIT 1)
+    );
+    CREATE INDEX idx_deployments_wallet ON deployments(wallet_id);
+    CREATE TRIGGER deployments_wallet AFTER INSERT ON deployments
+    WHEN NEW.wallet_id IS NULL
+    BEGIN
+        UPDATE deployments SET wallet_id = (
+            SELECT id FROM wallets WHERE address = NEW.deployer COLLATE NOCASE
+        ) WHERE id = NEW.id;
+    END;
+    CREATE TRIGGER wallets_deployments AFTER INSERT ON wallets
+    BEGIN
+        UPDATE deployments SET wallet_id = NEW.id
+        WHERE wallet_id IS NULL AND deployer = NEW.address COLLATE NOCASE;
+    END;
+    CREATE TRIGGER wallets_rotated_deployments AFTER UPDATE OF address ON wallets
+    BEGIN
+        UPDATE deployments SET wallet_id = NEW.id
+        WHERE wallet_id IS NULL AND deployer = NEW.address COLLATE NOCASE;
+    END;
+    "#,
+    // 32: deployments and contracts pinned to the top of listings; contracts
+    // by name, so later builds of a pinned contract stay pinned
+    r#"
+    ALTER TABLE deployments ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE;
+    CREATE TABLE pinned_contracts (
+        name TEXT PRIMARY KEY,
+        pinned_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+    // 33: display labels and markdown descriptions; contracts' by name, like
+    // their pins
+    r#"
+    ALTER TABLE deployments ADD COLUMN label TEXT;
+    ALTER TABLE deployments ADD COLUMN description TEXT;
+    CREATE TABLE contract_labels (
+        name TEXT PRIMARY KEY,
+        label TEXT,
+        description TEXT
+    );
+    "#,
+    // 34: background jobs run by the server's workers; `run_after` delays
+    // the next attempt of a failed one
+    r#"
+    CREATE TABLE jobs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        payload JSON NOT NULL,
+        state TEXT NOT NULL DEFAULT 'queued'
+            CHECK (state IN ('queued', 'running', 'succeeded', 'failed')),
+        attempts INTEGER NOT NULL DEFAULT 0,
+        max_attempts INTEGER NOT NULL,
+        run_after DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        last_error TEXT,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        started_at DATETIME,
+        finished_at DATETIME
+    );
+    CREATE INDEX idx_jobs_due ON jobs(run_after, id) WHERE state = 'queued';
+    CREATE INDEX idx_jobs_state ON jobs(state, id);
+    "#,
+    // 35: deployments added later from an address a wallet had before a
+    // rotation are linked to it too, as the backfill of 31 does
+    r#"
+    DROP TRIGGER deployments_wallet;
+    CREATE TRIGGER deployments_wallet AFTER INSERT ON deployments
+    WHEN NEW.wallet_id IS NULL
+    BEGIN
+        UPDATE deployments SET wallet_id = COALESCE(
+            (SELECT id FROM wallets WHERE address = NEW.deployer COLLATE NOCASE),
+            (SELECT wallet_id FROM wallet_rotations
+                WHERE old_address = NEW.deployer COLLATE NOCASE
+                ORDER BY id DESC LIMIT 1)
+        ) WHERE id = NEW.id;
+    END;
+    "#,
+    // 36: wallet addresses unique whatever their case, as they are looked up;
+    // the column's own UNIQUE only told apart addresses cased differently
+    "CREATE UNIQUE INDEX idx_wallets_address ON wallets(address COLLATE NOCASE);",
+    // 37: the token that requested an approval by ID, kept once the token is
+    // deleted, and the newest token when it was requested, so that tokens
+    // created since can't approve it
+    r#"
+    ALTER TABLE approvals ADD COLUMN requester_token_id INTEGER;
+    ALTER TABLE approvals ADD COLUMN newest_token_id INTEGER;
+    UPDATE approvals SET requester_token_id = requested_by,
+        newest_token_id = (SELECT MAX(id) FROM api_tokens WHERE created_at <= approvals.created_at);
+    "#,
+];
+
+/// Initialize the database schema and apply any pending migrations
 pub async fn init_schema(pool: &SqlitePool) -> Result<(), Error> {
     sqlx::raw_sql(SCHEMA).execute(pool).await?;
+    migrate(pool).await?;
+    seal_rpc_urls(pool).await?;
+    checksum_wallet_addresses(pool).await
+}
+
+/// Get the schema version (number of applied migrations)
+pub async fn schema_version(pool: &SqlitePool) -> Result<i64, Error> {
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(version)
+}
+
+/// Apply migrations that have not been applied yet
+async fn migrate(pool: &SqlitePool) -> Result<(), Error> {
+    let current = schema_version(pool).await?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let mut tx = pool.begin().await?;
+        sqlx::raw_sql(migration).execute(&mut *tx).await?;
+        // PRAGMA does not accept bound parameters
+        sqlx::raw_sql(&format!("PRAGMA user_version = {}", index + 1))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Encrypt RPC URLs with credentials stored in plain text, as they were
+/// before being encrypted at rest
+///
+/// Encryption can't be done in SQL, so this runs after the migrations rather
+/// than as one of them.
+async fn seal_rpc_urls(pool: &SqlitePool) -> Result<(), Error> {
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, rpc_url FROM networks")
+        .fetch_all(pool)
+        .await?;
+    for (id, stored) in rows {
+        if RpcUrl::is_exposed_at_rest(&stored) {
+            sqlx::query("UPDATE networks SET rpc_url = ? WHERE id = ?")
+                .bind(RpcUrl::new(stored))
+                .bind(id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Checksum wallet addresses stored in lowercase, as they were before
+/// addresses were checksummed on write
+///
+/// Checksums can't be computed in SQL, so this runs after the migrations
+/// rather than as one of them.
+async fn checksum_wallet_addresses(pool: &SqlitePool) -> Result<(), Error> {
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, address FROM wallets")
+        .fetch_all(pool)
+        .await?;
+    for (id, stored) in rows {
+        let checksummed = EvmAddress::normalize(&stored);
+        if checksummed != stored {
+            sqlx::query("UPDATE wallets SET address = ? WHERE id = ?")
+                .bind(checksummed)
+                .bind(id)
+                .execute(pool)
+                .await?;
+        }
+    }
     Ok(())
 }
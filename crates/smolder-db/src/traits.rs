@@ -5,20 +5,69 @@
 //! - Easy testing with mock implementations
 //! - Potential for different storage backends (SQLite, Postgres, etc.)
 
+use std::time::Duration;
+
 use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde::Deserialize;
 
+use smolder_core::address::EvmAddress;
 use smolder_core::error::Result;
-use smolder_core::types::{ChainId, ContractId, DeploymentId, NetworkId, WalletId};
+use smolder_core::hash::TxHash;
+use smolder_core::types::{
+    ApiRole, ApiTokenId, ApprovalId, ApprovalStatus, ChainId, ContractId, DeploymentGroupId,
+    DeploymentId, EnsLookup, JobState, NetworkId, RecordKind, TransactionStatus, WalletId,
+    WebhookId,
+};
+use smolder_core::units::Currency;
 
 use crate::models::{
-    CallHistory, CallHistoryUpdate, CallHistoryView, Contract, Deployment, DeploymentView, Network,
-    NewCallHistory, NewContract, NewDeployment, NewNetwork, NewWallet, Wallet, WalletWithKey,
+    ApiToken, Approval, AuditEntry, Batch, CallHistory, CallHistoryUpdate, CallHistoryView,
+    Contract, CostStat, Deployment, DeploymentGroup, DeploymentGroupMember, DeploymentView,
+    EnsRecord, GasSample, GasStat, IdempotencyRecord, ImportStatus, Job, KnownSelector,
+    MinedRecord, Network, NetworkUpdate, NewApproval, NewAuditEntry, NewBatch, NewCallHistory,
+    NewContract, NewContractDeployment, NewDeployment, NewDeploymentGroup, NewEnsRecord,
+    NewGasSample, NewJob, NewNetwork, NewSelector, NewSignature, NewTemplate, NewTokenMetadata,
+    NewUpgrade, NewWallet, NewWebhook, NewWebhookDelivery, PriceDay, RegistryStats,
+    SignatureRecord, Template, TokenMetadata, Wallet, WalletPolicy, WalletRotation, WalletWithKey,
+    Webhook, WebhookDelivery, WebhookDeliveryUpdate,
 };
 
 // =============================================================================
 // Filter Types
 // =============================================================================
 
+/// Direction for sorted list queries
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Field to sort deployments by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum DeploymentSort {
+    Network,
+    Contract,
+    Version,
+    BlockNumber,
+    DeployedAt,
+}
+
 /// Filter for listing deployments
 #[derive(Debug, Default, Clone)]
 pub struct DeploymentFilter {
@@ -26,8 +75,31 @@ pub struct DeploymentFilter {
     pub network: Option<String>,
     /// Filter by contract name
     pub contract: Option<String>,
+    /// Filter by version number; previous versions only match without
+    /// `current_only`
+    pub version: Option<i64>,
+    /// Filter by deployer address (case-insensitive)
+    pub deployer: Option<String>,
+    /// Filter by the name of the wallet the deployment was sent from
+    pub wallet: Option<String>,
+    /// Only include deployments made at or after this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub deployed_after: Option<String>,
+    /// Only include deployments made at or before this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub deployed_before: Option<String>,
+    /// Only include contracts created by this call history entry
+    pub created_by_call_id: Option<i64>,
     /// Only include current (latest) deployments
     pub current_only: bool,
+    /// Include archived (soft-deleted) deployments
+    pub include_archived: bool,
+    /// Sort field (defaults to network, contract, then version)
+    pub sort: Option<DeploymentSort>,
+    /// Sort direction
+    pub order: SortOrder,
+    /// Limit number of results
+    pub limit: Option<u32>,
+    /// Number of results to skip
+    pub offset: Option<u32>,
 }
 
 impl DeploymentFilter {
@@ -49,11 +121,128 @@ impl DeploymentFilter {
     }
 }
 
+/// Field to sort contracts by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ContractSort {
+    Name,
+    CreatedAt,
+}
+
+/// Filter for listing contracts
+#[derive(Debug, Default, Clone)]
+pub struct ContractFilter {
+    /// Filter by contract name (substring match)
+    pub name: Option<String>,
+    /// Sort field (defaults to name)
+    pub sort: Option<ContractSort>,
+    /// Sort direction
+    pub order: SortOrder,
+    /// Limit number of results
+    pub limit: Option<u32>,
+    /// Number of results to skip
+    pub offset: Option<u32>,
+}
+
 /// Filter for listing call history
+///
+/// Results are ordered newest first.
 #[derive(Debug, Default, Clone)]
 pub struct CallHistoryFilter {
     /// Filter by deployment ID
     pub deployment_id: Option<DeploymentId>,
+    /// Filter by contract name
+    pub contract: Option<String>,
+    /// Filter by network name
+    pub network: Option<String>,
+    /// Filter by wallet name
+    pub wallet: Option<String>,
+    /// Filter by transaction status
+    pub status: Option<TransactionStatus>,
+    /// Only include calls made at or after this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub since: Option<String>,
+    /// Only include calls made at or before this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub until: Option<String>,
+    /// Limit number of results
+    pub limit: Option<u32>,
+    /// Number of results to skip
+    pub offset: Option<u32>,
+}
+
+/// Which call history entries pruning removes
+///
+/// Pending calls are always kept, since their receipts are still awaited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryRetention {
+    /// Remove calls recorded longer ago than this
+    pub max_age: Duration,
+    /// Keep failed and reverted calls whatever their age
+    pub keep_failed: bool,
+}
+
+/// Time bucket for gas statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum GasInterval {
+    Day,
+    Week,
+    Month,
+}
+
+impl GasInterval {
+    /// `strftime` format naming the bucket a timestamp falls in
+    pub fn as_strftime(&self) -> &'static str {
+        match self {
+            GasInterval::Day => "%Y-%m-%d",
+            GasInterval::Week => "%Y-W%W",
+            GasInterval::Month => "%Y-%m",
+        }
+    }
+}
+
+/// Filter options for gas statistics
+#[derive(Debug, Default, Clone)]
+pub struct GasStatsFilter {
+    /// Filter by network name
+    pub network: Option<String>,
+    /// Filter by contract name
+    pub contract: Option<String>,
+    /// Only include gas spent at or after this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub since: Option<String>,
+    /// Only include gas spent at or before this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub until: Option<String>,
+    /// Split each function's figures by period
+    pub interval: Option<GasInterval>,
+}
+
+/// Filter options for cost reports
+#[derive(Debug, Default, Clone)]
+pub struct CostFilter {
+    /// Filter by network name
+    pub network: Option<String>,
+    /// Filter by contract name
+    pub contract: Option<String>,
+    /// Filter by wallet name, or deployer address
+    pub wallet: Option<String>,
+    /// Only include fees paid at or after this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub since: Option<String>,
+    /// Only include fees paid at or before this date (`YYYY-MM-DD[ HH:MM:SS]`)
+    pub until: Option<String>,
+    /// Split costs by period
+    pub interval: Option<GasInterval>,
+}
+
+/// Filter for listing background jobs
+///
+/// Results are ordered newest first.
+#[derive(Debug, Default, Clone)]
+pub struct JobFilter {
+    /// Filter by state
+    pub state: Option<JobState>,
+    /// Filter by kind
+    pub kind: Option<String>,
     /// Limit number of results
     pub limit: Option<u32>,
 }
@@ -78,14 +267,60 @@ pub trait NetworkRepository: Send + Sync {
     async fn get_by_chain_id(&self, chain_id: ChainId) -> Result<Option<Network>>;
 
     /// Insert or update a network
+    ///
+    /// New networks get the native currency of their chain when it is well
+    /// known, ETH otherwise.
     async fn upsert(&self, network: &NewNetwork) -> Result<Network>;
+
+    /// Allow or forbid impersonated sends on a network. Kept across upserts
+    async fn set_impersonation(&self, name: &str, enabled: bool) -> Result<()>;
+
+    /// Require approval for deploys and sends through the API on a network.
+    /// Kept across upserts
+    async fn set_protected(&self, name: &str, protected: bool) -> Result<()>;
+
+    /// Submit transactions on a network through a private relay, or through
+    /// its RPC again with `None`. Kept across upserts
+    async fn set_private_relay(&self, name: &str, relay_url: Option<&str>) -> Result<()>;
+
+    /// Set the native currency of a network. Kept across upserts
+    async fn set_currency(&self, name: &str, currency: Currency<'_>) -> Result<()>;
+
+    /// Set how many blocks deep recorded transactions on a network are
+    /// re-checked for reorgs. Kept across upserts
+    async fn set_reorg_depth(&self, name: &str, depth: u32) -> Result<()>;
+
+    /// Set how many confirmations transactions on a network need before
+    /// they count as confirmed. Kept across upserts
+    async fn set_confirmations(&self, name: &str, confirmations: u32) -> Result<()>;
+
+    /// Lock a network against deploys and sends, or unlock it. Kept across
+    /// upserts
+    async fn set_locked(&self, name: &str, locked: bool) -> Result<()>;
+
+    /// Change the RPC or explorer of a network
+    ///
+    /// With `if_version`, fails with `Error::VersionMismatch` unless the
+    /// network is still at that row version.
+    async fn update(
+        &self,
+        name: &str,
+        update: &NetworkUpdate,
+        if_version: Option<i64>,
+    ) -> Result<Network>;
+
+    /// Delete a network by name
+    async fn delete(&self, name: &str) -> Result<()>;
 }
 
 /// Repository for contract operations
 #[async_trait]
 pub trait ContractRepository: Send + Sync {
-    /// List all contracts
-    async fn list(&self) -> Result<Vec<Contract>>;
+    /// List contracts with optional filtering, pinned ones first
+    async fn list(&self, filter: ContractFilter) -> Result<Vec<Contract>>;
+
+    /// Count contracts matching a filter (ignoring limit and offset)
+    async fn count(&self, filter: &ContractFilter) -> Result<i64>;
 
     /// Get a contract by name
     async fn get_by_name(&self, name: &str) -> Result<Option<Contract>>;
@@ -95,14 +330,35 @@ pub trait ContractRepository: Send + Sync {
 
     /// Insert or update a contract
     async fn upsert(&self, contract: &NewContract) -> Result<Contract>;
+
+    /// Delete all versions of a contract by name, along with their
+    /// deployments and call history
+    async fn delete(&self, name: &str) -> Result<()>;
+
+    /// Pin or unpin every build of a contract, failing with
+    /// `Error::ContractNotFound` when there is none by that name
+    async fn set_pinned(&self, name: &str, pinned: bool) -> Result<()>;
+
+    /// Set the label and description of every build of a contract, `None`
+    /// clearing them, failing with `Error::ContractNotFound` when there is
+    /// none by that name
+    async fn set_label(
+        &self,
+        name: &str,
+        label: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()>;
 }
 
 /// Repository for deployment operations
 #[async_trait]
 pub trait DeploymentRepository: Send + Sync {
-    /// List deployments with optional filtering
+    /// List deployments with optional filtering, pinned ones first
     async fn list(&self, filter: DeploymentFilter) -> Result<Vec<DeploymentView>>;
 
+    /// Count deployments matching a filter (ignoring limit and offset)
+    async fn count(&self, filter: &DeploymentFilter) -> Result<i64>;
+
     /// Get the current deployment for a contract on a network
     async fn get_current(&self, contract: &str, network: &str) -> Result<Option<Deployment>>;
 
@@ -113,16 +369,82 @@ pub trait DeploymentRepository: Send + Sync {
     async fn get_view_by_id(&self, id: DeploymentId) -> Result<Option<DeploymentView>>;
 
     /// Check if a deployment exists by transaction hash
-    async fn exists_by_tx_hash(&self, tx_hash: &str) -> Result<bool>;
+    async fn exists_by_tx_hash(&self, tx_hash: &TxHash) -> Result<bool>;
+
+    /// Check if a deployment exists at an address on a network (case-insensitive)
+    async fn exists_by_address(&self, network_id: NetworkId, address: &str) -> Result<bool>;
+
+    /// Get the latest deployment at an address on a network (case-insensitive)
+    async fn get_by_address(
+        &self,
+        network_id: NetworkId,
+        address: &str,
+    ) -> Result<Option<Deployment>>;
 
     /// Create a new deployment (handles versioning automatically)
     async fn create(&self, deployment: &NewDeployment) -> Result<Deployment>;
 
-    /// Get all deployments for export (regardless of current status)
-    async fn list_for_export(&self, network: Option<&str>) -> Result<Vec<DeploymentView>>;
+    /// Link a deployment to the call history entry of the factory call that
+    /// created it
+    async fn link_to_call(&self, id: DeploymentId, call_id: i64) -> Result<()>;
+
+    /// Pin or unpin a deployment
+    async fn set_pinned(&self, id: DeploymentId, pinned: bool) -> Result<()>;
+
+    /// Set a deployment's own label and description, `None` clearing them
+    /// so its contract's show through
+    async fn set_label(
+        &self,
+        id: DeploymentId,
+        label: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()>;
+
+    /// Record a proxy upgrade: upsert the implementation's contract, add its
+    /// deployment as the current version and log the upgrade transaction
+    /// against the proxy, all or nothing
+    async fn record_upgrade(&self, upgrade: &NewUpgrade) -> Result<(Deployment, CallHistory)>;
+
+    /// Record many deployments on a network in a single transaction
+    ///
+    /// Contracts are upserted once per name and bytecode hash, and new
+    /// deployments are inserted in bulk with versions assigned as if created
+    /// one by one, in order. Deployments already tracked at the same address
+    /// are skipped, or updated when the new record fills in a missing block
+    /// number or constructor arguments. Returns what happened to each input;
+    /// if anything fails, nothing is written.
+    async fn import_batch(
+        &self,
+        network_id: NetworkId,
+        deployments: &[NewContractDeployment],
+    ) -> Result<Vec<ImportStatus>>;
+
+    /// Stream the current deployments for export, ordered by network then
+    /// contract, optionally on one network only
+    ///
+    /// Rows are fetched as the stream is polled, so exports of large
+    /// registries never hold every ABI in memory. Without `with_abi`, the
+    /// `abi` field of each view is left empty and ABIs are not read at all.
+    fn stream_for_export<'a>(
+        &'a self,
+        network: Option<&'a str>,
+        with_abi: bool,
+    ) -> BoxStream<'a, Result<DeploymentView>>;
 
     /// Get all versions of a contract deployment on a network (version history)
     async fn list_versions(&self, contract: &str, network: &str) -> Result<Vec<DeploymentView>>;
+
+    /// Permanently delete a deployment and its call history
+    ///
+    /// If the deployment was current, the latest remaining non-archived
+    /// version of the contract on that network becomes current again.
+    async fn delete(&self, id: DeploymentId) -> Result<()>;
+
+    /// Archive (soft-delete) a deployment, keeping it and its call history for auditing
+    ///
+    /// Archived deployments are excluded from listings unless requested, and
+    /// the previous version is promoted to current like with [`Self::delete`].
+    async fn archive(&self, id: DeploymentId) -> Result<()>;
 }
 
 /// Repository for wallet operations
@@ -146,8 +468,31 @@ pub trait WalletRepository: Send + Sync {
     /// Create a new wallet
     async fn create(&self, wallet: &NewWallet) -> Result<Wallet>;
 
+    /// Replace the key of a wallet, keeping its ID, name, policy and
+    /// history, and record the address it had
+    ///
+    /// Fails with `Error::WalletNotFound` when there is no such wallet.
+    async fn rotate(&self, name: &str, address: EvmAddress, encrypted_key: &[u8])
+        -> Result<Wallet>;
+
+    /// Keys a wallet had before, oldest first
+    async fn rotations(&self, id: WalletId) -> Result<Vec<WalletRotation>>;
+
+    /// Deployments sent from the wallet, newest first; archived deployments
+    /// are left out
+    async fn deployments(&self, id: WalletId, limit: u32) -> Result<Vec<DeploymentView>>;
+
+    /// Calls sent with the wallet, or imported from one of its addresses,
+    /// newest first
+    async fn calls(&self, id: WalletId, limit: u32) -> Result<Vec<CallHistoryView>>;
+
     /// Delete a wallet by name
     async fn delete(&self, name: &str) -> Result<()>;
+
+    /// Delete a wallet by name, failing with `Error::WalletNotFound` when
+    /// there is none, and with `Error::VersionMismatch` when `if_version` is
+    /// given and the wallet has changed since
+    async fn delete_if(&self, name: &str, if_version: Option<i64>) -> Result<()>;
 }
 
 /// Repository for call history operations
@@ -159,6 +504,9 @@ pub trait CallHistoryRepository: Send + Sync {
     /// List call history with full view (joined with deployment, contract, network, wallet)
     async fn list_views(&self, filter: CallHistoryFilter) -> Result<Vec<CallHistoryView>>;
 
+    /// Count call history entries matching a filter (ignoring limit and offset)
+    async fn count(&self, filter: &CallHistoryFilter) -> Result<i64>;
+
     /// Get a call history entry by ID
     async fn get_by_id(&self, id: i64) -> Result<Option<CallHistory>>;
 
@@ -167,6 +515,437 @@ pub trait CallHistoryRepository: Send + Sync {
 
     /// Update a call history entry after execution
     async fn update(&self, id: i64, update: &CallHistoryUpdate) -> Result<()>;
+
+    /// Attach a shareable simulation link to a call history entry
+    async fn set_simulation_url(&self, id: i64, url: &str) -> Result<()>;
+
+    /// Get the call history entry of a transaction
+    async fn get_by_tx_hash(&self, tx_hash: &TxHash) -> Result<Option<CallHistory>>;
+
+    /// Record a transaction `sender` sent without smolder, already mined
+    async fn import_external(
+        &self,
+        entry: &NewCallHistory,
+        sender: &str,
+        outcome: &CallHistoryUpdate,
+    ) -> Result<CallHistory>;
+
+    /// Count the entries [`prune`](Self::prune) would remove
+    async fn count_prunable(&self, retention: &HistoryRetention) -> Result<u64>;
+
+    /// Delete the entries `retention` lets go, returning how many were
+    /// removed
+    async fn prune(&self, retention: &HistoryRetention) -> Result<u64>;
+}
+
+/// Repository for webhook subscriptions and their deliveries
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    /// List all webhooks
+    async fn list(&self) -> Result<Vec<Webhook>>;
+
+    /// Get a webhook by ID
+    async fn get_by_id(&self, id: WebhookId) -> Result<Option<Webhook>>;
+
+    /// Register a new webhook
+    async fn create(&self, webhook: &NewWebhook) -> Result<Webhook>;
+
+    /// Delete a webhook and its delivery log
+    async fn delete(&self, id: WebhookId) -> Result<()>;
+
+    /// Queue a delivery to a webhook
+    async fn create_delivery(&self, delivery: &NewWebhookDelivery) -> Result<WebhookDelivery>;
+
+    /// Record the outcome of a delivery attempt
+    async fn update_delivery(&self, id: i64, update: &WebhookDeliveryUpdate) -> Result<()>;
+
+    /// List a webhook's deliveries, newest first
+    async fn list_deliveries(
+        &self,
+        webhook_id: WebhookId,
+        limit: u32,
+    ) -> Result<Vec<WebhookDelivery>>;
+
+    /// List deliveries that still have attempts left, oldest first
+    async fn list_pending_deliveries(&self) -> Result<Vec<WebhookDelivery>>;
+}
+
+/// Repository for cached ENS lookups
+#[async_trait]
+pub trait EnsCacheRepository: Send + Sync {
+    /// Get a cached lookup resolved within `max_age`
+    async fn get(
+        &self,
+        chain_id: ChainId,
+        lookup: EnsLookup,
+        input: &str,
+        max_age: Duration,
+    ) -> Result<Option<EnsRecord>>;
+
+    /// Cache a lookup, replacing any earlier result
+    async fn put(&self, record: &NewEnsRecord) -> Result<()>;
+}
+
+/// Repository for cached USD prices of native currencies
+#[async_trait]
+pub trait PriceRepository: Send + Sync {
+    /// USD price of `symbol` on `day` (`YYYY-MM-DD`), if cached
+    async fn get(&self, symbol: &str, day: &str) -> Result<Option<f64>>;
+
+    /// Cache a price, replacing any earlier one
+    async fn put(&self, symbol: &str, day: &str, usd: f64) -> Result<()>;
+}
+
+/// Aggregate queries across the registry
+#[async_trait]
+pub trait AnalyticsRepository: Send + Sync {
+    /// Gas used per function of each contract version, from successful calls
+    /// and deployment receipts (reported as `constructor`)
+    ///
+    /// Ordered by network, contract, function, version and period, so
+    /// consecutive rows of a function show how its cost moved.
+    async fn gas_stats(&self, filter: &GasStatsFilter) -> Result<Vec<GasStat>>;
+
+    /// Fees paid for deployments and sent calls, per network, contract and
+    /// wallet
+    ///
+    /// Reverted calls count, as their gas was paid. Ordered by period,
+    /// network, contract and wallet.
+    async fn costs(&self, filter: &CostFilter) -> Result<Vec<CostStat>>;
+
+    /// Currencies and days of the fees [`costs`](Self::costs) would report
+    /// without a cached price, test chains aside
+    async fn unpriced_fees(&self, filter: &CostFilter) -> Result<Vec<PriceDay>>;
+
+    /// Counts across the registry, deployments per network and the `recent`
+    /// latest deployments and calls
+    async fn stats(&self, recent: u32) -> Result<RegistryStats>;
+}
+
+/// Repository for multi-network deployment groups
+#[async_trait]
+pub trait DeploymentGroupRepository: Send + Sync {
+    /// Create a group with a pending member for each network
+    async fn create(&self, group: &NewDeploymentGroup) -> Result<DeploymentGroup>;
+
+    /// Get a group by ID
+    async fn get(&self, id: DeploymentGroupId) -> Result<Option<DeploymentGroup>>;
+
+    /// List groups, newest first
+    async fn list(&self) -> Result<Vec<DeploymentGroup>>;
+
+    /// List a group's members in the order their networks were given
+    async fn list_members(&self, id: DeploymentGroupId) -> Result<Vec<DeploymentGroupMember>>;
+
+    /// Record the outcome of deploying a group to one network
+    async fn update_member(
+        &self,
+        id: DeploymentGroupId,
+        network_id: NetworkId,
+        status: TransactionStatus,
+        deployment_id: Option<DeploymentId>,
+        error_message: Option<&str>,
+    ) -> Result<()>;
+}
+
+/// Repository for fee samples taken by the server
+#[async_trait]
+pub trait GasSampleRepository: Send + Sync {
+    /// Record a sample
+    async fn record(&self, sample: &NewGasSample) -> Result<()>;
+
+    /// List the latest `limit` samples of a network taken at or after
+    /// `since`, oldest first
+    async fn list(
+        &self,
+        network_id: NetworkId,
+        since: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GasSample>>;
+
+    /// Delete samples older than `max_age`, returning how many were removed
+    async fn prune(&self, max_age: Duration) -> Result<u64>;
+}
+
+/// Repository for token metadata of deployed contracts
+#[async_trait]
+pub trait TokenMetadataRepository: Send + Sync {
+    /// Get the metadata stored for a deployment
+    async fn get(&self, deployment_id: DeploymentId) -> Result<Option<TokenMetadata>>;
+
+    /// Store a deployment's metadata, replacing any earlier read
+    async fn put(&self, metadata: &NewTokenMetadata) -> Result<()>;
+}
+
+/// Repository for API server tokens
+#[async_trait]
+pub trait ApiTokenRepository: Send + Sync {
+    /// List all tokens
+    async fn list(&self) -> Result<Vec<ApiToken>>;
+
+    /// Count tokens; with none, the server doesn't ask for one
+    async fn count(&self) -> Result<i64>;
+
+    /// Create a token from the hash of its secret
+    async fn create(&self, name: &str, token_hash: &str, role: ApiRole) -> Result<ApiToken>;
+
+    /// Change what a token may do
+    async fn set_role(&self, name: &str, role: ApiRole) -> Result<()>;
+
+    /// Find the token with this hash, recording that it was used
+    async fn authenticate(&self, token_hash: &str) -> Result<Option<ApiToken>>;
+
+    /// Revoke a token by name
+    async fn delete(&self, name: &str) -> Result<()>;
+}
+
+/// Repository for requests waiting on a second token
+#[async_trait]
+pub trait ApprovalRepository: Send + Sync {
+    /// Hold a request for approval
+    async fn create(&self, approval: &NewApproval) -> Result<Approval>;
+
+    /// Get an approval by ID
+    async fn get(&self, id: ApprovalId) -> Result<Option<Approval>>;
+
+    /// List approvals, newest first, optionally with one status only
+    async fn list(&self, status: Option<ApprovalStatus>) -> Result<Vec<Approval>>;
+
+    /// Mark pending approvals past their expiry as expired, returning how
+    /// many were
+    async fn expire(&self) -> Result<u64>;
+
+    /// Approve or reject a pending, unexpired approval on behalf of a token
+    ///
+    /// Returns `false`, changing nothing, when it was already decided or has
+    /// expired, so two tokens deciding at once can't both win.
+    async fn decide(
+        &self,
+        id: ApprovalId,
+        status: ApprovalStatus,
+        decided_by: ApiTokenId,
+    ) -> Result<bool>;
+
+    /// Record the outcome of executing an approved request; an error marks
+    /// it failed
+    async fn record_result(
+        &self,
+        id: ApprovalId,
+        result: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<()>;
+}
+
+/// Repository for batch runs
+#[async_trait]
+pub trait BatchRepository: Send + Sync {
+    /// Record a batch run as pending
+    async fn create(&self, batch: &NewBatch) -> Result<Batch>;
+
+    /// Link the call a step sent to its batch
+    async fn add_call(&self, batch_id: i64, position: i64, call_history_id: i64) -> Result<()>;
+
+    /// Record how a batch run ended
+    async fn finish(&self, batch_id: i64, status: TransactionStatus) -> Result<()>;
+
+    /// Get a batch run by ID
+    async fn get(&self, id: i64) -> Result<Option<Batch>>;
+
+    /// Calls a batch run sent, by step
+    async fn list_calls(&self, batch_id: i64) -> Result<Vec<CallHistoryView>>;
+}
+
+/// Repository for deployment templates
+#[async_trait]
+pub trait TemplateRepository: Send + Sync {
+    /// List all templates by name
+    async fn list(&self) -> Result<Vec<Template>>;
+
+    /// Get a template by name
+    async fn get(&self, name: &str) -> Result<Option<Template>>;
+
+    /// Save a template, replacing any with the same name
+    async fn upsert(&self, template: &NewTemplate) -> Result<Template>;
+
+    /// Delete a template by name
+    async fn delete(&self, name: &str) -> Result<()>;
+}
+
+/// Repository for the responses of POSTs sent with an `Idempotency-Key`
+///
+/// Keys are scoped, e.g. per API token, so clients can't see each other's
+/// responses.
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    /// Claim `key` for a request with `fingerprint`
+    ///
+    /// Returns `None` when the key is new and the request should run, or the
+    /// record of the request that claimed it first.
+    async fn claim(
+        &self,
+        scope: &str,
+        key: &str,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyRecord>>;
+
+    /// Store the response of a claimed request, to replay on retries
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        status: u16,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<()>;
+
+    /// Forget a claimed key, so a retry runs the request again
+    async fn release(&self, scope: &str, key: &str) -> Result<()>;
+
+    /// Delete keys older than `max_age`, returning how many were deleted
+    async fn prune(&self, max_age: Duration) -> Result<u64>;
+}
+
+/// Repository for known function signatures by selector
+#[async_trait]
+pub trait SelectorRepository: Send + Sync {
+    /// Signatures known for `selector` (`0x` and 8 hex digits), those from
+    /// the registry first
+    async fn lookup(&self, selector: &str) -> Result<Vec<KnownSelector>>;
+
+    /// Record signatures, returning how many were new or changed
+    ///
+    /// A signature already known from 4byte.directory that turns up in the
+    /// registry is marked as coming from the registry.
+    async fn put_many(&self, selectors: &[NewSelector]) -> Result<u64>;
+}
+
+/// Repository for the audit log of sensitive changes
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /// Record a change
+    async fn record(&self, entry: &NewAuditEntry) -> Result<AuditEntry>;
+
+    /// Recorded changes, newest first, up to `limit`
+    async fn list(&self, limit: i64) -> Result<Vec<AuditEntry>>;
+}
+
+/// Repository for the limits on what wallets may sign, and the native value
+/// they sent
+#[async_trait]
+pub trait WalletPolicyRepository: Send + Sync {
+    /// Policy of a wallet, `None` when it has none
+    async fn get(&self, wallet_id: WalletId) -> Result<Option<WalletPolicy>>;
+
+    /// Set the policy of a wallet, replacing the one it had
+    async fn set(&self, wallet_id: WalletId, policy: &WalletPolicy) -> Result<()>;
+
+    /// Remove the policy of a wallet; returns whether it had one
+    async fn clear(&self, wallet_id: WalletId) -> Result<bool>;
+
+    /// Record wei a wallet is about to send on a network, if `fits` accepts
+    /// it given the wei the wallet sent there within `window`
+    ///
+    /// The spends are read and the new one recorded in one transaction, so
+    /// concurrent sends count against each other. Returns the ID of the new
+    /// spend, to release it if the send fails, or `None` when `fits` refused.
+    async fn reserve_spend(
+        &self,
+        wallet_id: WalletId,
+        network_id: NetworkId,
+        value: &str,
+        window: Duration,
+        fits: &(dyn for<'s> Fn(&'s [String]) -> bool + Send + Sync),
+    ) -> Result<Option<i64>>;
+
+    /// Remove a spend whose transaction wasn't sent
+    async fn release_spend(&self, id: i64) -> Result<()>;
+
+    /// Wei a wallet sent on a network within the last `window`, one entry per
+    /// transaction
+    async fn spent_within(
+        &self,
+        wallet_id: WalletId,
+        network_id: NetworkId,
+        window: Duration,
+    ) -> Result<Vec<String>>;
+}
+
+/// Repository for the typed data signed with stored wallets
+#[async_trait]
+pub trait SignatureRepository: Send + Sync {
+    /// Record a signature
+    async fn create(&self, signature: &NewSignature) -> Result<SignatureRecord>;
+
+    /// Signatures made by the wallet named `wallet`, or by all wallets,
+    /// newest first
+    async fn list(&self, wallet: Option<&str>) -> Result<Vec<SignatureRecord>>;
+}
+
+/// Repository for the blocks mined deployments and calls were recorded in,
+/// re-checked against the chain to catch reorgs
+#[async_trait]
+pub trait ReorgRepository: Send + Sync {
+    /// Deployments and calls on a network mined after block `after`, or
+    /// marked reorged, oldest block first
+    async fn unsettled(&self, network_id: NetworkId, after: i64) -> Result<Vec<MinedRecord>>;
+
+    /// Record the block a transaction is now mined in
+    async fn set_block(
+        &self,
+        kind: RecordKind,
+        id: i64,
+        block_number: i64,
+        block_hash: &str,
+    ) -> Result<()>;
+
+    /// Mark a record as dropped by a reorg, making it pending again, or
+    /// clear the mark once its transaction is mined again and buried deep
+    /// enough
+    async fn set_reorged(&self, kind: RecordKind, id: i64, reorged: bool) -> Result<()>;
+
+    /// Confirm the pending records on a network mined up to block
+    /// `confirmed`, and finalize those up to block `finalized`, returning
+    /// how many changed
+    ///
+    /// Records marked reorged are left pending.
+    async fn advance_finality(
+        &self,
+        network_id: NetworkId,
+        confirmed: i64,
+        finalized: Option<i64>,
+    ) -> Result<u64>;
+}
+
+/// Repository for the queue of background jobs
+#[async_trait]
+pub trait JobRepository: Send + Sync {
+    /// Queue a job to run as soon as a worker is free
+    async fn enqueue(&self, job: &NewJob) -> Result<Job>;
+
+    /// Take the queued job that has been due the longest, marking it running
+    /// and counting the attempt; `None` when no job is due
+    async fn claim(&self) -> Result<Option<Job>>;
+
+    /// Mark a running job succeeded
+    async fn complete(&self, id: i64) -> Result<()>;
+
+    /// Record a failed attempt of a running job
+    ///
+    /// The job is queued again to run after `retry_in`, unless it is `None`
+    /// or the job has used up its attempts, in which case it fails for good.
+    /// Returns the state the job is left in.
+    async fn fail(&self, id: i64, error: &str, retry_in: Option<Duration>) -> Result<JobState>;
+
+    /// Queue again the jobs a stopped server left running, without counting
+    /// the interrupted attempts; returns how many there were
+    async fn requeue_running(&self) -> Result<u64>;
+
+    /// Jobs matching `filter`
+    async fn list(&self, filter: &JobFilter) -> Result<Vec<Job>>;
+
+    /// Delete jobs that succeeded or failed longer ago than `max_age`,
+    /// returning how many were deleted
+    async fn prune(&self, max_age: Duration) -> Result<u64>;
 }
 
 // =============================================================================
@@ -191,4 +970,58 @@ pub trait Repositories: Send + Sync {
 
     /// Access the call history repository
     fn call_history(&self) -> &dyn CallHistoryRepository;
+
+    /// Access the webhook repository
+    fn webhooks(&self) -> &dyn WebhookRepository;
+
+    /// Access the ENS cache
+    fn ens_cache(&self) -> &dyn EnsCacheRepository;
+
+    /// Access token metadata
+    fn token_metadata(&self) -> &dyn TokenMetadataRepository;
+
+    /// Access aggregate queries
+    fn analytics(&self) -> &dyn AnalyticsRepository;
+
+    /// Access gas samples
+    fn gas_samples(&self) -> &dyn GasSampleRepository;
+
+    /// Access cached prices
+    fn prices(&self) -> &dyn PriceRepository;
+
+    /// Access deployment groups
+    fn deployment_groups(&self) -> &dyn DeploymentGroupRepository;
+
+    /// Access API tokens
+    fn api_tokens(&self) -> &dyn ApiTokenRepository;
+
+    /// Access approvals
+    fn approvals(&self) -> &dyn ApprovalRepository;
+
+    /// Access deployment templates
+    fn templates(&self) -> &dyn TemplateRepository;
+
+    /// Access batch runs
+    fn batches(&self) -> &dyn BatchRepository;
+
+    /// Access idempotency keys
+    fn idempotency_keys(&self) -> &dyn IdempotencyRepository;
+
+    /// Access known function selectors
+    fn selectors(&self) -> &dyn SelectorRepository;
+
+    /// Access the blocks recorded transactions were mined in
+    fn reorgs(&self) -> &dyn ReorgRepository;
+
+    /// Access signed typed data
+    fn signatures(&self) -> &dyn SignatureRepository;
+
+    /// Access the audit log
+    fn audit_log(&self) -> &dyn AuditRepository;
+
+    /// Access wallet policies and spending
+    fn wallet_policies(&self) -> &dyn WalletPolicyRepository;
+
+    /// Access background jobs
+    fn jobs(&self) -> &dyn JobRepository;
 }
@@ -1,25 +1,85 @@
 import type {
+	ApiRole,
+	ApiToken,
+	Approval,
+	ApprovalStatus,
 	ArtifactDetails,
 	ArtifactInfo,
+	AuditEntry,
 	CallHistory,
 	CallRequest,
 	CallResponse,
+	CallStatusEvent,
 	Contract,
+	CostStat,
+	CreateTokenResponse,
+	DecodedCalldata,
 	Deployment,
+	DeploymentGroup,
+	DeploymentGroupResponse,
+	DeployMultiRequest,
 	DeployRequest,
 	DeployResponse,
 	FunctionsResponse,
+	GasHistory,
+	GasStat,
 	HealthResponse,
+	Job,
+	JobState,
 	Network,
+	NetworkDeployment,
+	NetworkUpdate,
+	RegistryStats,
+	RoleReport,
 	SendRequest,
+	Sandbox,
+	SandboxRequest,
+	SandboxResponse,
 	SendResponse,
+	SignatureRecord,
+	SimulationResponse,
+	TraceCall,
+	TypedData,
+	VersionResponse,
 	Wallet,
+	WalletPolicy,
 } from "./types";
+import { BASE_PATH } from "../lib/base-path";
 
-const API_BASE = "/api";
+const API_BASE = `${BASE_PATH}/api`;
+
+const TOKEN_KEY = "smolder-api-token";
+
+/** Fetch with the stored API token, asking for one when the server requires it */
+async function authFetch(url: string, init: RequestInit = {}): Promise<Response> {
+	const send = () => {
+		const token = localStorage.getItem(TOKEN_KEY);
+		const headers = new Headers(init.headers);
+		if (token) {
+			headers.set("Authorization", `Bearer ${token}`);
+		}
+		return fetch(url, { ...init, headers });
+	};
+
+	const response = await send();
+	if (response.status !== 401) {
+		return response;
+	}
+	const token = window.prompt("This server requires an API token");
+	if (!token) {
+		return response;
+	}
+	localStorage.setItem(TOKEN_KEY, token.trim());
+	return send();
+}
+
+/** Whether a deploy or send was held for approval instead of running */
+export function isApproval(response: object): response is Approval {
+	return "status" in response && "summary" in response;
+}
 
 async function fetchJson<T>(url: string): Promise<T> {
-	const response = await fetch(url);
+	const response = await authFetch(url);
 	if (!response.ok) {
 		const text = await response.text();
 		throw new Error(text || `API error: ${response.status}`);
@@ -28,8 +88,46 @@ async function fetchJson<T>(url: string): Promise<T> {
 }
 
 async function postJson<T, R>(url: string, data: T): Promise<R> {
-	const response = await fetch(url, {
+	const headers: Record<string, string> = { "Content-Type": "application/json" };
+	// Lets the server replay the response instead of running a retry twice;
+	// randomUUID is only available in secure contexts
+	const key = globalThis.crypto?.randomUUID?.();
+	if (key) {
+		headers["Idempotency-Key"] = key;
+	}
+	const response = await authFetch(url, {
 		method: "POST",
+		headers,
+		body: JSON.stringify(data),
+	});
+	if (!response.ok) {
+		const text = await response.text();
+		throw new Error(text || `API error: ${response.status}`);
+	}
+	return response.json();
+}
+
+/** `If-Match` for the row version an entity was read at */
+function ifMatch(version?: number): HeadersInit {
+	return version === undefined ? {} : { "If-Match": `"${version}"` };
+}
+
+async function patchJson<T, R>(url: string, data: T, version?: number): Promise<R> {
+	const response = await authFetch(url, {
+		method: "PATCH",
+		headers: { "Content-Type": "application/json", ...ifMatch(version) },
+		body: JSON.stringify(data),
+	});
+	if (!response.ok) {
+		const text = await response.text();
+		throw new Error(text || `API error: ${response.status}`);
+	}
+	return response.json();
+}
+
+async function putJson<T, R>(url: string, data: T): Promise<R> {
+	const response = await authFetch(url, {
+		method: "PUT",
 		headers: { "Content-Type": "application/json" },
 		body: JSON.stringify(data),
 	});
@@ -40,8 +138,11 @@ async function postJson<T, R>(url: string, data: T): Promise<R> {
 	return response.json();
 }
 
-async function deleteRequest(url: string): Promise<void> {
-	const response = await fetch(url, { method: "DELETE" });
+async function deleteRequest(url: string, version?: number): Promise<void> {
+	const response = await authFetch(url, {
+		method: "DELETE",
+		headers: ifMatch(version),
+	});
 	if (!response.ok && response.status !== 204) {
 		const text = await response.text();
 		throw new Error(text || `API error: ${response.status}`);
@@ -51,23 +152,61 @@ async function deleteRequest(url: string): Promise<void> {
 export const api = {
 	health: (): Promise<HealthResponse> => fetchJson(`${API_BASE}/health`),
 
+	version: (): Promise<VersionResponse> => fetchJson(`${API_BASE}/version`),
+
 	networks: {
 		list: (): Promise<Network[]> => fetchJson(`${API_BASE}/networks`),
 		get: (name: string): Promise<Network> =>
 			fetchJson(`${API_BASE}/networks/${name}`),
+		/** Fails with 412 if the network changed since `version` was read */
+		update: (
+			name: string,
+			changes: NetworkUpdate,
+			version?: number,
+		): Promise<Network> =>
+			patchJson(`${API_BASE}/networks/${name}`, changes, version),
+		gasHistory: (
+			name: string,
+			params: { since?: string; limit?: number } = {},
+		): Promise<GasHistory> => {
+			const query = new URLSearchParams();
+			if (params.since) query.set("since", params.since);
+			if (params.limit !== undefined) query.set("limit", String(params.limit));
+			return fetchJson(`${API_BASE}/networks/${name}/gas-history?${query}`);
+		},
+		lock: (name: string): Promise<Network> =>
+			postJson(`${API_BASE}/networks/${name}/lock`, {}),
+		unlock: (name: string): Promise<Network> =>
+			postJson(`${API_BASE}/networks/${name}/unlock`, {}),
 	},
 
+	audit: (limit = 100): Promise<AuditEntry[]> =>
+		fetchJson(`${API_BASE}/audit?limit=${limit}`),
+
 	contracts: {
 		list: (): Promise<Contract[]> => fetchJson(`${API_BASE}/contracts`),
 		get: (name: string): Promise<Contract> =>
 			fetchJson(`${API_BASE}/contracts/${name}`),
+		/** Current deployment on every network, side by side */
+		deployments: (
+			name: string,
+			includeVerification = false,
+		): Promise<NetworkDeployment[]> =>
+			fetchJson(
+				`${API_BASE}/contracts/${name}/deployments${includeVerification ? "?include_verification=true" : ""}`,
+			),
 	},
 
 	deployments: {
-		list: (network?: string): Promise<Deployment[]> => {
-			const params = network ? `?network=${encodeURIComponent(network)}` : "";
-			return fetchJson(`${API_BASE}/deployments${params}`);
+		list: (network?: string, includeStatus = false): Promise<Deployment[]> => {
+			const params = new URLSearchParams();
+			if (network) params.set("network", network);
+			if (includeStatus) params.set("include_status", "true");
+			const query = params.toString();
+			return fetchJson(`${API_BASE}/deployments${query ? `?${query}` : ""}`);
 		},
+		createdBy: (historyId: number): Promise<Deployment[]> =>
+			fetchJson(`${API_BASE}/deployments?created_by_call=${historyId}`),
 		get: (contract: string, network: string): Promise<Deployment> =>
 			fetchJson(`${API_BASE}/deployments/${contract}/${network}`),
 		getVersions: (contract: string, network: string): Promise<Deployment[]> =>
@@ -76,10 +215,19 @@ export const api = {
 			fetchJson(`${API_BASE}/deployments/${id}/functions`),
 		call: (id: number, request: CallRequest): Promise<CallResponse> =>
 			postJson(`${API_BASE}/deployments/${id}/call`, request),
-		send: (id: number, request: SendRequest): Promise<SendResponse> =>
+		send: (
+			id: number,
+			request: SendRequest,
+		): Promise<SendResponse | Approval> =>
 			postJson(`${API_BASE}/deployments/${id}/send`, request),
+		simulate: (id: number, request: SendRequest): Promise<SimulationResponse> =>
+			postJson(`${API_BASE}/deployments/${id}/send?simulate=true`, request),
 		getHistory: (id: number): Promise<CallHistory[]> =>
 			fetchJson(`${API_BASE}/deployments/${id}/history`),
+		getRoles: (id: number): Promise<RoleReport> =>
+			fetchJson(`${API_BASE}/deployments/${id}/roles`),
+		update: (id: number, changes: DeploymentUpdate): Promise<Deployment> =>
+			patchJson(`${API_BASE}/deployments/${id}`, changes),
 	},
 
 	wallets: {
@@ -88,8 +236,21 @@ export const api = {
 			fetchJson(`${API_BASE}/wallets/${name}`),
 		create: (name: string, privateKey: string): Promise<Wallet> =>
 			postJson(`${API_BASE}/wallets`, { name, private_key: privateKey }),
-		remove: (name: string): Promise<void> =>
-			deleteRequest(`${API_BASE}/wallets/${name}`),
+		remove: (name: string, version?: number): Promise<void> =>
+			deleteRequest(`${API_BASE}/wallets/${name}`, version),
+		signTypedData: (
+			name: string,
+			typedData: TypedData,
+		): Promise<SignatureRecord> =>
+			postJson(`${API_BASE}/wallets/${name}/sign-typed-data`, typedData),
+		signatures: (name: string): Promise<SignatureRecord[]> =>
+			fetchJson(`${API_BASE}/wallets/${name}/signatures`),
+		getPolicy: (name: string): Promise<WalletPolicy> =>
+			fetchJson(`${API_BASE}/wallets/${name}/policy`),
+		setPolicy: (name: string, policy: WalletPolicy): Promise<WalletPolicy> =>
+			putJson(`${API_BASE}/wallets/${name}/policy`, policy),
+		clearPolicy: (name: string): Promise<void> =>
+			deleteRequest(`${API_BASE}/wallets/${name}/policy`),
 	},
 
 	artifacts: {
@@ -98,6 +259,174 @@ export const api = {
 			fetchJson(`${API_BASE}/artifacts/${encodeURIComponent(name)}`),
 	},
 
-	deploy: (request: DeployRequest): Promise<DeployResponse> =>
+	history: {
+		/** Download URL of a call history export */
+		exportUrl: (format: "csv" | "json", deploymentId?: number): string => {
+			const params = new URLSearchParams({ format });
+			if (deploymentId !== undefined) {
+				params.set("deployment_id", String(deploymentId));
+			}
+			return `${API_BASE}/history/export?${params}`;
+		},
+		/** Call tree of a sent transaction; needs debug_traceTransaction */
+		trace: (id: number): Promise<TraceCall> =>
+			fetchJson(`${API_BASE}/history/${id}/trace`),
+		/**
+		 * Follow status changes of sent transactions until `signal` aborts.
+		 * `onLagged` runs when events were missed and the history should be reloaded.
+		 */
+		stream: async (
+			onStatus: (entry: CallStatusEvent) => void,
+			options: {
+				deploymentId?: number;
+				onLagged?: () => void;
+				signal?: AbortSignal;
+			} = {},
+		): Promise<void> => {
+			const params = new URLSearchParams();
+			if (options.deploymentId !== undefined) {
+				params.set("deployment_id", String(options.deploymentId));
+			}
+			// EventSource can't send the API token, so read the stream by hand
+			const response = await authFetch(`${API_BASE}/history/stream?${params}`, {
+				headers: { Accept: "text/event-stream" },
+				signal: options.signal,
+			});
+			if (!response.ok || !response.body) {
+				throw new Error(`API error: ${response.status}`);
+			}
+			const reader = response.body
+				.pipeThrough(new TextDecoderStream())
+				.getReader();
+			let buffer = "";
+			for (;;) {
+				const { value, done } = await reader.read();
+				if (done) {
+					return;
+				}
+				buffer += value;
+				let end = buffer.indexOf("\n\n");
+				while (end !== -1) {
+					const lines = buffer.slice(0, end).split("\n");
+					buffer = buffer.slice(end + 2);
+					end = buffer.indexOf("\n\n");
+					const event = lines
+						.find((line) => line.startsWith("event: "))
+						?.slice(7);
+					const data = lines
+						.filter((line) => line.startsWith("data: "))
+						.map((line) => line.slice(6))
+						.join("\n");
+					if (event === "status") {
+						onStatus(JSON.parse(data));
+					} else if (event === "lagged") {
+						options.onLagged?.();
+					}
+				}
+			}
+		},
+	},
+
+	analytics: {
+		gas: (params: {
+			network?: string;
+			contract?: string;
+			interval?: "day" | "week" | "month";
+		}): Promise<GasStat[]> => {
+			const query = new URLSearchParams(
+				Object.entries(params).filter(([, v]) => v !== undefined) as [
+					string,
+					string,
+				][],
+			);
+			return fetchJson(`${API_BASE}/analytics/gas?${query}`);
+		},
+		costs: (params: {
+			network?: string;
+			contract?: string;
+			wallet?: string;
+			since?: string;
+			until?: string;
+			interval?: "day" | "week" | "month";
+		}): Promise<CostStat[]> => {
+			const query = new URLSearchParams(
+				Object.entries(params).filter(([, v]) => v !== undefined) as [
+					string,
+					string,
+				][],
+			);
+			return fetchJson(`${API_BASE}/analytics/costs?${query}`);
+		},
+	},
+
+	stats: (recent?: number): Promise<RegistryStats> =>
+		fetchJson(
+			`${API_BASE}/stats${recent !== undefined ? `?recent=${recent}` : ""}`,
+		),
+
+	deploy: (request: DeployRequest): Promise<DeployResponse | Approval> =>
 		postJson(`${API_BASE}/deploy`, request),
+	simulateDeploy: (request: DeployRequest): Promise<SimulationResponse> =>
+		postJson(`${API_BASE}/deploy?simulate=true`, request),
+	deployMulti: (request: DeployMultiRequest): Promise<DeploymentGroupResponse> =>
+		postJson(`${API_BASE}/deploy/multi`, request),
+
+	deploymentGroups: {
+		list: (): Promise<DeploymentGroup[]> =>
+			fetchJson(`${API_BASE}/deploy/groups`),
+		get: (id: number): Promise<DeploymentGroupResponse> =>
+			fetchJson(`${API_BASE}/deploy/groups/${id}`),
+		resume: (
+			id: number,
+			parallel = false,
+			unlockConfirm: string[] = [],
+		): Promise<DeploymentGroupResponse> => {
+			const query = new URLSearchParams({ parallel: String(parallel) });
+			if (unlockConfirm.length > 0)
+				query.set("unlock_confirm", unlockConfirm.join(","));
+			return postJson(`${API_BASE}/deploy/groups/${id}/resume?${query}`, {});
+		},
+	},
+
+	approvals: {
+		list: (status?: ApprovalStatus): Promise<Approval[]> =>
+			fetchJson(`${API_BASE}/approvals${status ? `?status=${status}` : ""}`),
+		get: (id: number): Promise<Approval> =>
+			fetchJson(`${API_BASE}/approvals/${id}`),
+		approve: (id: number): Promise<Approval> =>
+			postJson(`${API_BASE}/approvals/${id}/approve`, {}),
+		reject: (id: number): Promise<Approval> =>
+			postJson(`${API_BASE}/approvals/${id}/reject`, {}),
+	},
+
+	jobs: {
+		list: (
+			params: { state?: JobState; kind?: string; limit?: number } = {},
+		): Promise<Job[]> => {
+			const query = new URLSearchParams();
+			if (params.state) query.set("state", params.state);
+			if (params.kind) query.set("kind", params.kind);
+			if (params.limit !== undefined) query.set("limit", String(params.limit));
+			return fetchJson(`${API_BASE}/jobs?${query}`);
+		},
+	},
+
+	tokens: {
+		list: (): Promise<ApiToken[]> => fetchJson(`${API_BASE}/tokens`),
+		create: (name: string, role?: ApiRole): Promise<CreateTokenResponse> =>
+			postJson(`${API_BASE}/tokens`, { name, role }),
+		remove: (name: string): Promise<void> =>
+			deleteRequest(`${API_BASE}/tokens/${encodeURIComponent(name)}`),
+	},
+
+	decode: (calldata: string, online = false): Promise<DecodedCalldata> =>
+		postJson(`${API_BASE}/decode`, { calldata, online }),
+
+	sandbox: {
+		list: (): Promise<Sandbox[]> => fetchJson(`${API_BASE}/sandbox`),
+		create: (request: SandboxRequest): Promise<SandboxResponse> =>
+			postJson(`${API_BASE}/sandbox`, request),
+		remove: (name: string): Promise<void> =>
+			deleteRequest(`${API_BASE}/sandbox/${encodeURIComponent(name)}`),
+	},
 };
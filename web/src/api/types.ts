@@ -5,6 +5,25 @@ export interface Network {
 	rpc_url: string;
 	explorer_url: string | null;
 	created_at: string;
+	impersonation: boolean;
+	protected: boolean;
+	private_relay: string | null;
+	currency_symbol: string;
+	currency_decimals: number;
+	/** Bumped on every change; send it back as If-Match */
+	row_version: number;
+	/** Blocks deep recorded transactions are checked for reorgs */
+	reorg_depth: number;
+	confirmations: number;
+	/** Deploys and sends need the network's name as `unlock_confirm` */
+	locked: boolean;
+	/** The RPC URL carries an API key, masked as `***` in `rpc_url` */
+	has_credentials: boolean;
+}
+
+export interface NetworkUpdate {
+	rpc_url?: string;
+	explorer_url?: string;
 }
 
 export interface Contract {
@@ -14,8 +33,26 @@ export interface Contract {
 	abi: string;
 	bytecode_hash: string;
 	created_at: string;
+	storage_layout: string | null;
+	compiler: CompilerSettings | null;
+	/** Pinned by name, so it holds for every build of the contract */
+	pinned: boolean;
+	label: string | null;
+	/** Markdown */
+	description: string | null;
+}
+
+export interface CompilerSettings {
+	version: string;
+	optimizer_enabled: boolean;
+	optimizer_runs: number | null;
+	evm_version: string | null;
+	via_ir: boolean;
+	libraries: Record<string, string>;
 }
 
+export type Finality = "pending" | "confirmed" | "finalized";
+
 export interface Deployment {
 	id: number;
 	contract_name: string;
@@ -23,12 +60,66 @@ export interface Deployment {
 	chain_id: number;
 	address: string;
 	deployer: string;
-	tx_hash: string;
+	/** Registered wallet the deployment was sent from */
+	wallet_name: string | null;
+	/** Null for deployments recorded without their transaction */
+	tx_hash: string | null;
 	block_number: number | null;
 	version: number;
 	deployed_at: string;
 	is_current: boolean;
+	initcode_size: number | null;
+	runtime_size: number | null;
+	gas_used: number | null;
+	/** Effective gas price in wei */
+	gas_price: string | null;
+	/** Fee paid in wei */
+	fee: string | null;
+	/** History entry of the factory call that created the contract */
+	created_by_call_id: number | null;
+	/** Set while a reorg has dropped the deployment transaction */
+	reorged_at: string | null;
+	finality: Finality | null;
+	/** Pinned itself or through its contract; pinned deployments list first */
+	pinned: boolean;
+	/** The deployment's own label, or else its contract's */
+	label: string | null;
+	/** Markdown; the deployment's own, or else its contract's */
+	description: string | null;
 	abi: string;
+	address_url: string | null;
+	tx_url: string | null;
+	token: TokenMetadata | null;
+	status?: ContractStatus;
+}
+
+export interface DeploymentUpdate {
+	pinned?: boolean;
+	/** An empty string removes it */
+	label?: string;
+	/** Markdown; an empty string removes it */
+	description?: string;
+}
+
+/** A contract's current deployment on one network */
+export interface NetworkDeployment extends Deployment {
+	/** Sourcify match level, false when unverified; absent when unknown */
+	verification?: string | false;
+}
+
+export interface ContractStatus {
+	paused: boolean | null;
+	owner: string | null;
+	implementation: string | null;
+}
+
+export interface TokenMetadata {
+	standard: "erc20" | "erc721" | "erc1155";
+	name: string | null;
+	symbol: string | null;
+	decimals: number | null;
+	total_supply: string | null;
+	fetched_at: string;
 }
 
 export interface HealthResponse {
@@ -41,6 +132,45 @@ export interface Wallet {
 	name: string;
 	address: string;
 	created_at: string;
+	/** Bumped on every change; send it back as If-Match */
+	row_version: number;
+}
+
+/** An EIP-712 payload, as for eth_signTypedData_v4 */
+export interface TypedData {
+	types: Record<string, { name: string; type: string }[]>;
+	primaryType: string;
+	domain: Record<string, unknown>;
+	message: Record<string, unknown>;
+}
+
+export interface SignatureRecord {
+	id: number;
+	/** null once the wallet is removed */
+	wallet_name: string | null;
+	signer: string;
+	primary_type: string;
+	domain: string;
+	/** JSON of the typed data as signed */
+	payload: string;
+	digest: string;
+	signature: string;
+	/** Name of the API token that asked for it */
+	signed_by: string | null;
+	created_at: string;
+}
+
+/** Limits on what a wallet may sign; null rules don't apply */
+export interface WalletPolicy {
+	/** Wei, or with a unit such as "0.5 ether" when setting it */
+	max_value_per_tx: string | null;
+	/** Per network over 24 hours, in wei */
+	max_daily_spend: string | null;
+	allowed_networks: string[] | null;
+	/** Contract names or addresses */
+	allowed_contracts: string[] | null;
+	/** Function names or full signatures */
+	allowed_functions: string[] | null;
 }
 
 export interface ParamInfo {
@@ -74,13 +204,37 @@ export interface CallResponse {
 export interface SendRequest {
 	function_name: string;
 	params: unknown[];
-	wallet_name: string;
+	wallet_name?: string;
+	from?: string;
 	value?: string;
+	state_overrides?: Record<string, unknown>;
+	private?: boolean;
+	/** The network's name, when it is locked */
+	unlock_confirm?: string;
 }
 
 export interface SendResponse {
 	tx_hash: string;
 	history_id: number;
+	warnings: PreflightWarning[];
+}
+
+/** A call in a transaction's call tree, decoded with registry ABIs */
+export interface TraceCall {
+	type: string;
+	from: string;
+	to: string | null;
+	contract: string | null;
+	function: string | null;
+	signature: string | null;
+	params: Record<string, unknown> | null;
+	result: unknown;
+	value: string | null;
+	gas_used: number | null;
+	input: string;
+	output: string | null;
+	error: string | null;
+	calls: TraceCall[];
 }
 
 export interface CallHistory {
@@ -99,12 +253,36 @@ export interface CallHistory {
 	block_number: number | null;
 	gas_used: number | null;
 	gas_price: string | null;
+	/** Fee paid in wei */
+	fee: string | null;
 	status: string | null;
 	error_message: string | null;
 	created_at: string;
 	confirmed_at: string | null;
+	simulation_url: string | null;
+	/** Sender of a transaction imported from the chain rather than sent by smolder */
+	sender: string | null;
+	/** Set while a reorg has dropped the transaction */
+	reorged_at: string | null;
+	finality: Finality | null;
+	address_url: string | null;
+	tx_url: string | null;
 }
 
+/** A call history entry as sent by `/api/history/stream` when its status changes */
+export type CallStatusEvent = Omit<
+	CallHistory,
+	| "contract_name"
+	| "network_name"
+	| "contract_address"
+	| "wallet_name"
+	| "address_url"
+	| "tx_url"
+> & {
+	wallet_id: number | null;
+	block_hash: string | null;
+};
+
 export interface ArtifactInfo {
 	name: string;
 	source_path: string;
@@ -131,6 +309,13 @@ export interface ArtifactDetails {
 	constructor: ConstructorInfo | null;
 	has_bytecode: boolean;
 	in_registry: boolean;
+	initcode_size: number;
+	runtime_size: number;
+	size_warnings: string[];
+	storage_layout: unknown | null;
+	compiler: CompilerSettings | null;
+	/** Libraries to link, as `path:Name` */
+	libraries: string[];
 }
 
 export interface DeployRequest {
@@ -139,10 +324,309 @@ export interface DeployRequest {
 	wallet_name: string;
 	constructor_args: unknown[];
 	value?: string;
+	state_overrides?: Record<string, unknown>;
+	/** Library addresses by name or `path:Name` */
+	libraries?: Record<string, string>;
+	/** The network's name, when it is locked */
+	unlock_confirm?: string;
 }
 
 export interface DeployResponse {
 	tx_hash: string;
 	contract_address: string | null;
 	deployment_id: number | null;
+	gas_used: number;
+	size_warnings: string[];
+	layout_issues: LayoutIssue[];
+	warnings: PreflightWarning[];
+}
+
+export interface PreflightWarning {
+	kind: 'pending_transactions' | 'gas_not_estimated';
+	message: string;
+}
+
+export interface DeployMultiRequest {
+	artifact_name: string;
+	network_names: string[];
+	wallet_name: string;
+	constructor_args?: unknown[];
+	value?: string;
+	parallel?: boolean;
+	/** Names of the locked networks among `network_names` */
+	unlock_confirm?: string[];
+}
+
+export interface DeploymentGroup {
+	id: number;
+	contract_name: string;
+	bytecode_hash: string;
+	constructor_args: string;
+	value: string | null;
+	wallet_name: string;
+	created_at: string;
+}
+
+export interface DeploymentGroupMember {
+	group_id: number;
+	network_id: number;
+	network_name: string;
+	chain_id: number;
+	status: "pending" | "success" | "failed";
+	deployment_id: number | null;
+	address: string | null;
+	tx_hash: string | null;
+	error_message: string | null;
+	updated_at: string;
+}
+
+export interface DeploymentGroupResponse extends DeploymentGroup {
+	members: DeploymentGroupMember[];
+}
+
+export interface LayoutIssue {
+	kind: "removed" | "type_changed" | "renamed" | "gap_resized";
+	label: string;
+	slot: string;
+	message: string;
+}
+
+export interface SimulationResponse {
+	success: boolean;
+	result: unknown | null;
+	gas_used: number | null;
+	error: string | null;
+	contract_address: string | null;
+	events: SimulatedEvent[];
+	trace: unknown | null;
+	state_diff: unknown | null;
+	simulation_url: string | null;
+}
+
+export interface SimulatedEvent {
+	address: string;
+	contract: string | null;
+	name: string | null;
+	signature: string | null;
+	params: Record<string, unknown> | null;
+	topics: string[];
+	data: string;
+}
+
+export interface SandboxRequest {
+	network: string;
+	block?: number;
+	name?: string;
+	rpc_url?: string;
+	port?: number;
+}
+
+export interface Sandbox {
+	name: string;
+	source: string;
+	rpc_url: string;
+	block: number;
+	managed: boolean;
+}
+
+export interface SandboxResponse extends Sandbox {
+	cloned: number;
+}
+
+export interface GasStat {
+	network: string;
+	contract: string;
+	version: number;
+	function: string;
+	period: string | null;
+	calls: number;
+	avg_gas: number;
+	min_gas: number;
+	max_gas: number;
+	total_gas: number;
+}
+
+export interface CostStat {
+	network: string;
+	contract: string;
+	/** Wallet name, or the deployer's address */
+	wallet: string | null;
+	period: string | null;
+	deployments: number;
+	calls: number;
+	gas_used: number;
+	/** Total fee in wei of the network's native currency */
+	fee: string;
+	/** Approximate USD value; 0 on test chains, null while a price is missing */
+	fee_usd: number | null;
+	currency_symbol: string;
+	currency_decimals: number;
+}
+
+/** Registry overview for the dashboard home */
+export interface RegistryStats {
+	contracts: number;
+	/** Current deployments, archived ones aside */
+	deployments: number;
+	calls_24h: number;
+	calls_7d: number;
+	pending_txs: number;
+	/** Failed or reverted transactions */
+	failed_txs: number;
+	networks: NetworkStats[];
+	/** Latest deployments and calls, newest first */
+	recent_activity: Activity[];
+}
+
+export interface NetworkStats {
+	network: string;
+	chain_id: number;
+	deployments: number;
+	last_deployed_at: string | null;
+}
+
+export interface Activity {
+	kind: "deployment" | "call";
+	deployment_id: number;
+	contract_name: string;
+	network_name: string;
+	version: number;
+	/** Null for deployments */
+	function_name: string | null;
+	/** Null for deployments and read calls */
+	status: "pending" | "success" | "failed" | "reverted" | null;
+	at: string;
+}
+
+export interface GasSample {
+	id: number;
+	network_id: number;
+	block_number: number;
+	base_fee: number | null;
+	gas_price: number;
+	sampled_at: string;
+}
+
+export interface GasSummary {
+	latest: number;
+	min: number;
+	median: number;
+	max: number;
+	percentile: number;
+}
+
+export interface GasHistory {
+	network: string;
+	samples: GasSample[];
+	summary: GasSummary | null;
+}
+
+export interface RoleMember {
+	address: string;
+	wallet: string | null;
+}
+
+export interface Role {
+	role: string;
+	name: string | null;
+	admin_role: string | null;
+	members: RoleMember[];
+}
+
+export interface RoleReport {
+	owner: RoleMember | null;
+	roles: Role[];
+	events_error: string | null;
+}
+
+export type SelectorSource = "registry" | "4byte";
+
+export interface DecodedMatch {
+	signature: string;
+	source: SelectorSource;
+	contracts: string[];
+	params: Record<string, unknown>;
+}
+
+export interface DecodedCalldata {
+	selector: string;
+	matches: DecodedMatch[];
+}
+
+export type ApprovalStatus =
+	| "pending"
+	| "approved"
+	| "rejected"
+	| "expired"
+	| "failed";
+
+export interface Approval {
+	id: number;
+	kind: "deploy" | "send";
+	network_id: number;
+	network_name: string;
+	deployment_id: number | null;
+	summary: string;
+	request: string;
+	status: ApprovalStatus;
+	requested_by: string | null;
+	decided_by: string | null;
+	result: string | null;
+	error_message: string | null;
+	created_at: string;
+	expires_at: string;
+	decided_at: string | null;
+}
+
+export type JobState = "queued" | "running" | "succeeded" | "failed";
+
+export interface Job {
+	id: number;
+	/** What the job does, e.g. `receipt` */
+	kind: string;
+	/** JSON payload, read according to `kind` */
+	payload: string;
+	state: JobState;
+	attempts: number;
+	max_attempts: number;
+	/** When a queued job may run, later than `created_at` after a failure */
+	run_after: string;
+	last_error: string | null;
+	created_at: string;
+	started_at: string | null;
+	finished_at: string | null;
+}
+
+export type AuditAction = "lock" | "unlock" | "override";
+
+export interface AuditEntry {
+	id: number;
+	action: AuditAction;
+	/** Network the change applies to */
+	target: string;
+	source: "cli" | "api";
+	/** Local user or API token name */
+	actor: string | null;
+	/** JSON details, such as the operation confirmed on a locked network */
+	detail: string | null;
+	created_at: string;
+}
+
+export type ApiRole = "viewer" | "operator" | "admin";
+
+export interface ApiToken {
+	id: number;
+	name: string;
+	role: ApiRole;
+	created_at: string;
+	last_used_at: string | null;
+}
+
+export interface CreateTokenResponse extends ApiToken {
+	secret: string;
+}
+
+export interface VersionResponse {
+	version: string;
+	git_sha: string | null;
 }
@@ -0,0 +1,76 @@
+import { useState } from "react";
+import { api } from "../api/client";
+import type { TraceCall } from "../api/types";
+import { truncateAddress } from "../lib/format";
+
+/** Call tree of a sent transaction, loaded on demand */
+export function CallTrace({ historyId }: { historyId: number }) {
+	const [trace, setTrace] = useState<TraceCall | null>(null);
+	const [loading, setLoading] = useState(false);
+	const [error, setError] = useState<string | null>(null);
+
+	const load = async () => {
+		setLoading(true);
+		setError(null);
+		try {
+			setTrace(await api.history.trace(historyId));
+		} catch (err) {
+			setError(err instanceof Error ? err.message : "Failed to load trace");
+		} finally {
+			setLoading(false);
+		}
+	};
+
+	return (
+		<div>
+			<p className="mb-1 font-medium text-text-muted text-xs">Call Trace</p>
+			{trace ? (
+				<TraceNode call={trace} />
+			) : (
+				<button
+					type="button"
+					onClick={load}
+					disabled={loading}
+					className="text-accent text-xs hover:underline disabled:opacity-50"
+				>
+					{loading ? "Tracing..." : "Load call trace"}
+				</button>
+			)}
+			{error && <p className="mt-1 text-error text-xs">{error}</p>}
+		</div>
+	);
+}
+
+function TraceNode({ call }: { call: TraceCall }) {
+	const target = call.contract ?? (call.to ? truncateAddress(call.to) : "?");
+	const method = call.function ?? call.input.slice(0, 10);
+
+	return (
+		<div className="font-mono text-xs">
+			<div className={call.error ? "text-error" : "text-text-secondary"}>
+				<span className="text-text-faint">{call.type}</span> {target}.
+				{method}
+				{call.params && `(${JSON.stringify(call.params)})`}
+				{call.result !== null &&
+					call.result !== undefined &&
+					` → ${JSON.stringify(call.result)}`}
+				{call.value && (
+					<span className="text-text-faint"> value {call.value}</span>
+				)}
+				{call.gas_used !== null && (
+					<span className="text-text-faint"> gas {call.gas_used}</span>
+				)}
+				{call.error && <span> ✗ {call.error}</span>}
+			</div>
+			{call.calls.length > 0 && (
+				<div className="ml-3 border-border border-l pl-3">
+					{call.calls
+						.map((child, i) => ({ key: `call-${i}`, child }))
+						.map(({ key, child }) => (
+							<TraceNode key={key} call={child} />
+						))}
+				</div>
+			)}
+		</div>
+	);
+}
@@ -1,5 +1,5 @@
 import { useEffect, useState } from "react";
-import { api } from "../api/client";
+import { api, isApproval } from "../api/client";
 import type { ArtifactDetails, Network, Wallet } from "../api/types";
 import { truncateAddress } from "../lib/format";
 import { ParamInput } from "./param-input";
@@ -123,7 +123,11 @@ export function DeployModal({
 				value: value || undefined,
 			});
 
-			if (response.contract_address) {
+			if (isApproval(response)) {
+				setDeployError(
+					`Held for approval #${response.id}: ${selectedNetwork} is protected`,
+				);
+			} else if (response.contract_address) {
 				onSuccess({
 					address: response.contract_address,
 					txHash: response.tx_hash,
@@ -218,6 +222,20 @@ export function DeployModal({
 							onSubmit={handleDeploy}
 							className="space-y-5"
 						>
+							{/* Code size */}
+							{artifactDetails && (
+								<div className="text-sm text-text-muted">
+									Code size: {artifactDetails.runtime_size.toLocaleString()}{" "}
+									bytes runtime,{" "}
+									{artifactDetails.initcode_size.toLocaleString()} bytes init
+									{artifactDetails.size_warnings.map((warning) => (
+										<p key={warning} className="mt-1 text-warning">
+											{warning}
+										</p>
+									))}
+								</div>
+							)}
+
 							{/* Network selection */}
 							<div>
 								<label
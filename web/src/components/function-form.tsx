@@ -1,5 +1,5 @@
 import { useState } from "react";
-import { api } from "../api/client";
+import { api, isApproval } from "../api/client";
 import type { FunctionInfo, Wallet } from "../api/types";
 import { truncateAddress } from "../lib/format";
 import { ParamInput } from "./param-input";
@@ -94,8 +94,15 @@ export function FunctionForm({
 					value: value || undefined,
 				});
 
-				setResult({ success: true, txHash: response.tx_hash });
-				onTxSent?.(response.tx_hash);
+				if (isApproval(response)) {
+					setResult({
+						success: true,
+						data: `Held for approval #${response.id} on a protected network`,
+					});
+				} else {
+					setResult({ success: true, txHash: response.tx_hash });
+					onTxSent?.(response.tx_hash);
+				}
 			} else {
 				const response = await api.deployments.call(deploymentId, {
 					function_name: func.name,
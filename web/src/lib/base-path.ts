@@ -0,0 +1,9 @@
+declare global {
+	interface Window {
+		/** Set by the server when it is mounted under a sub-path */
+		__SMOLDER_BASE_PATH__?: string;
+	}
+}
+
+/** Sub-path the server is mounted under, e.g. "/smolder", or "" at the root */
+export const BASE_PATH = window.__SMOLDER_BASE_PATH__ ?? "";
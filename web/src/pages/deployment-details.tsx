@@ -8,6 +8,7 @@ import type {
 	Network,
 	Wallet,
 } from "../api/types";
+import { CallTrace } from "../components/call-trace";
 import { FunctionForm } from "../components/function-form";
 import { formatDateTime, truncateAddress } from "../lib/format";
 
@@ -154,8 +155,16 @@ export function DeploymentDetail() {
 						</svg>
 					</div>
 					<div>
-						<h1 className="font-semibold text-2xl text-text tracking-tight">
+						<h1
+							className="font-semibold text-2xl text-text tracking-tight"
+							title={deployment.description ?? undefined}
+						>
 							{deployment.contract_name}
+							{deployment.label && (
+								<span className="ml-2 font-normal text-text-secondary">
+									{deployment.label}
+								</span>
+							)}
 						</h1>
 						<div className="flex items-center gap-2 text-text-secondary">
 							<span className="flex items-center gap-1.5">
@@ -168,6 +177,19 @@ export function DeploymentDetail() {
 							<span className="rounded-md bg-bg-muted px-2 py-0.5 font-medium text-text-muted text-xs">
 								v{deployment.version}
 							</span>
+							{deployment.token && (
+								<>
+									<span className="text-text-faint">·</span>
+									<span>
+										{[deployment.token.name, deployment.token.symbol]
+											.filter(Boolean)
+											.join(" · ") || "Token"}{" "}
+										<span className="text-text-faint uppercase">
+											{deployment.token.standard}
+										</span>
+									</span>
+								</>
+							)}
 						</div>
 					</div>
 				</div>
@@ -233,7 +255,6 @@ export function DeploymentDetail() {
 				<VersionsTab
 					versions={versions}
 					currentVersion={deployment.version}
-					network={networkData}
 				/>
 			)}
 		</div>
@@ -273,10 +294,6 @@ function DetailsTab({
 }) {
 	const explorerUrl = network?.explorer_url;
 
-	const getAddressUrl = () =>
-		explorerUrl ? `${explorerUrl}/address/${deployment.address}` : null;
-	const getTxUrl = () =>
-		explorerUrl ? `${explorerUrl}/tx/${deployment.tx_hash}` : null;
 	const getBlockUrl = () =>
 		explorerUrl && deployment.block_number
 			? `${explorerUrl}/block/${deployment.block_number}`
@@ -295,7 +312,7 @@ function DetailsTab({
 						value={deployment.address}
 						mono
 						copyable
-						explorerUrl={getAddressUrl()}
+						explorerUrl={deployment.address_url}
 					/>
 					<DetailRow
 						label="Deployer"
@@ -310,10 +327,10 @@ function DetailsTab({
 					/>
 					<DetailRow
 						label="Transaction Hash"
-						value={deployment.tx_hash}
+						value={deployment.tx_hash ?? "N/A"}
 						mono
 						copyable
-						explorerUrl={getTxUrl()}
+						explorerUrl={deployment.tx_url}
 					/>
 					<DetailRow
 						label="Block Number"
@@ -347,14 +364,10 @@ function DetailsTab({
 function VersionsTab({
 	versions,
 	currentVersion,
-	network,
 }: {
 	versions: Deployment[];
 	currentVersion: number;
-	network: Network | null;
 }) {
-	const explorerUrl = network?.explorer_url;
-
 	if (versions.length === 0) {
 		return (
 			<div className="rounded-xl border border-border bg-bg-elevated p-12 text-center">
@@ -396,13 +409,17 @@ function VersionsTab({
 								</div>
 								<p className="text-text-faint text-xs">
 									{formatDateTime(version.deployed_at)}
+									{version.runtime_size !== null &&
+										` · ${version.runtime_size.toLocaleString()} bytes`}
+									{version.gas_used !== null &&
+										` · ${version.gas_used.toLocaleString()} gas`}
 								</p>
 							</div>
 						</div>
 						<div className="flex items-center gap-2">
-							{explorerUrl && (
+							{version.address_url && (
 								<a
-									href={`${explorerUrl}/address/${version.address}`}
+									href={version.address_url}
 									target="_blank"
 									rel="noopener noreferrer"
 									className="rounded-lg border border-border bg-bg-surface p-2 text-text-muted transition-colors hover:border-accent hover:text-accent"
@@ -634,9 +651,36 @@ function HistoryRow({ item }: { item: CallHistory }) {
 								<p className="mb-1 font-medium text-text-muted text-xs">
 									Transaction Hash
 								</p>
-								<p className="font-mono text-text-secondary text-xs">
-									{item.tx_hash}
+								{item.tx_url ? (
+									<a
+										href={item.tx_url}
+										target="_blank"
+										rel="noopener noreferrer"
+										className="font-mono text-text-secondary text-xs hover:text-accent"
+									>
+										{item.tx_hash}
+									</a>
+								) : (
+									<p className="font-mono text-text-secondary text-xs">
+										{item.tx_hash}
+									</p>
+								)}
+							</div>
+						)}
+						{item.tx_hash && <CallTrace historyId={item.id} />}
+						{item.simulation_url && (
+							<div>
+								<p className="mb-1 font-medium text-text-muted text-xs">
+									Simulation
 								</p>
+								<a
+									href={item.simulation_url}
+									target="_blank"
+									rel="noopener noreferrer"
+									className="text-accent text-xs hover:underline"
+								>
+									View on Tenderly
+								</a>
 							</div>
 						)}
 						{item.error_message && (
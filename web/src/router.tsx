@@ -5,6 +5,7 @@ import {
 	Link,
 	Outlet,
 } from "@tanstack/react-router";
+import { BASE_PATH } from "./lib/base-path";
 import { Contracts } from "./pages/contracts";
 import { DeploymentDetail } from "./pages/deployment-details";
 import { Networks } from "./pages/networks";
@@ -125,7 +126,7 @@ const routeTree = rootRoute.addChildren([
 ]);
 
 // Create router
-export const router = createRouter({ routeTree });
+export const router = createRouter({ routeTree, basepath: BASE_PATH || "/" });
 
 // Register router for type safety
 declare module "@tanstack/react-router" {